@@ -17,6 +17,7 @@ const FEATURES: &[&str] = &[
     "GC",
     "GC_DRC",
     "GC_NULL",
+    "GC_COPYING",
     "CRANELIFT",
     "WINCH",
 ];
@@ -91,6 +91,10 @@ wasmtime_option_group! {
         /// will use. (default: 16)
         pub pooling_max_memory_protection_keys: Option<usize>,
 
+        /// Initialize linear memories in the pooling allocator lazily, on
+        /// first access, with `userfaultfd` on Linux. (default: false)
+        pub pooling_userfaultfd: Option<bool>,
+
         /// Configure attempting to initialize linear memory via a
         /// copy-on-write mapping (default: yes)
         pub memory_init_cow: Option<bool>,
@@ -196,13 +200,17 @@ wasmtime_option_group! {
         /// Currently only `cranelift` and `winch` are supported, but not all
         /// builds of Wasmtime have both built in.
         pub compiler: Option<wasmtime::Strategy>,
-        /// Which garbage collector to use: `drc` or `null`.
+        /// Which garbage collector to use: `drc`, `null`, or `copying`.
         ///
         /// `drc` is the deferred reference-counting collector.
         ///
         /// `null` is the null garbage collector, which does not collect any
         /// garbage.
         ///
+        /// `copying` is the semi-space copying collector, which reclaims all
+        /// unreachable garbage, including cycles, by copying live objects
+        /// into the other half of the GC heap.
+        ///
         /// Note that not all builds of Wasmtime will have support for garbage
         /// collection included.
         pub collector: Option<wasmtime::Collector>,
@@ -244,6 +252,9 @@ wasmtime_option_group! {
         pub log_to_files: Option<bool>,
         /// Enable coredump generation to this file after a WebAssembly trap.
         pub coredump: Option<String>,
+        /// Detect data races between wasi-threads threads accessing shared
+        /// memories, printing each race found to stderr.
+        pub race_detection: Option<bool>,
     }
 
     enum Debug {
@@ -267,6 +278,14 @@ wasmtime_option_group! {
         /// Yield when a global epoch counter changes, allowing for async
         /// operation without blocking the executor.
         pub epoch_interruption: Option<bool>,
+        /// Make execution deterministic, given the same results from host
+        /// calls.
+        ///
+        /// This canonicalizes NaNs, uses the deterministic behavior of relaxed
+        /// SIMD instructions and enables fuel, which is unlimited unless
+        /// `-W fuel` is also passed. It can't be combined with
+        /// `-W epoch-interruption`.
+        pub deterministic: Option<bool>,
         /// Maximum stack size, in bytes, that wasm is allowed to consume before a
         /// stack overflow is reported.
         pub max_wasm_stack: Option<usize>,
@@ -424,6 +443,13 @@ wasmtime_option_group! {
         pub config_var: Vec<KeyValuePair>,
         /// Preset data for the In-Memory provider of WASI key-value API.
         pub keyvalue_in_memory_data: Vec<KeyValuePair>,
+        /// Record the result of every call to the host made by a module or
+        /// component to a trace at the given path.
+        pub record: Option<String>,
+        /// Replay the results of calls to the host made by a module or
+        /// component from a trace at the given path, previously written with
+        /// `-S record`.
+        pub replay: Option<String>,
     }
 
     enum Wasi {
@@ -595,6 +621,11 @@ impl CommonOptions {
             #[cfg(not(feature = "coredump"))]
             anyhow::bail!("support for coredumps disabled at compile time");
         }
+        match_feature! {
+            ["threads" : self.debug.race_detection]
+            enable => config.race_detection(enable),
+            true => err,
+        }
         match_feature! {
             ["cranelift" : self.opts.opt_level]
             level => config.cranelift_opt_level(level),
@@ -717,6 +748,9 @@ impl CommonOptions {
         if let Some(enable) = self.wasm.epoch_interruption {
             config.epoch_interruption(enable);
         }
+        if let Some(enable) = self.wasm.deterministic {
+            config.deterministic(enable);
+        }
         if let Some(enable) = self.debug.address_map {
             config.generate_address_map(enable);
         }
@@ -777,6 +811,9 @@ impl CommonOptions {
                     if let Some(max) = self.opts.pooling_max_unused_warm_slots {
                         cfg.max_unused_warm_slots(max);
                     }
+                    if let Some(enable) = self.opts.pooling_userfaultfd {
+                        cfg.userfaultfd(enable);
+                    }
                     match_feature! {
                         ["async" : self.opts.pooling_async_stack_zeroing]
                         enable => cfg.async_stack_zeroing(enable),
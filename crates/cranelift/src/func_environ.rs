@@ -20,13 +20,15 @@ use smallvec::SmallVec;
 use std::mem;
 use wasmparser::{Operator, WasmFeatures};
 use wasmtime_environ::{
-    BuiltinFunctionIndex, DataIndex, ElemIndex, EngineOrModuleTypeIndex, FuncIndex, GlobalIndex,
-    IndexType, Memory, MemoryIndex, Module, ModuleInternedTypeIndex, ModuleTranslation,
-    ModuleTypesBuilder, PtrSize, Table, TableIndex, TripleExt, Tunables, TypeConvert, TypeIndex,
-    VMOffsets, WasmCompositeInnerType, WasmFuncType, WasmHeapTopType, WasmHeapType, WasmRefType,
-    WasmResult, WasmValType,
+    BuiltinFunctionIndex, DataIndex, DebugLocalKind, ElemIndex, EngineOrModuleTypeIndex, FuncIndex,
+    GlobalIndex, IndexType, Memory, MemoryAccessKind, MemoryIndex, Module, ModuleInternedTypeIndex,
+    ModuleTranslation, ModuleTypesBuilder, PtrSize, Table, TableIndex, TripleExt, Tunables,
+    TypeConvert, TypeIndex, VMOffsets, WasmCompositeInnerType, WasmFuncType, WasmHeapTopType,
+    WasmHeapType, WasmRefType, WasmResult, WasmValType,
+};
+use wasmtime_environ::{
+    DEBUG_LOCAL_SIZE, DEBUG_LOCAL_VALUE_OFFSET, FUNCREF_INIT_BIT, FUNCREF_MASK,
 };
-use wasmtime_environ::{FUNCREF_INIT_BIT, FUNCREF_MASK};
 
 /// A struct with an `Option<ir::FuncRef>` member for every builtin
 /// function, to de-duplicate constructing/getting its function.
@@ -127,6 +129,12 @@ pub struct FuncEnvironment<'module_environment> {
     /// in `*const VMRuntimeLimits`
     fuel_var: cranelift_frontend::Variable,
 
+    /// When fuel profiling is enabled, a function-local variable holding the
+    /// value of `fuel_var` when this function's fuel counter was last
+    /// updated, and the offset of that counter within the `VMContext`.
+    fuel_profile_var: cranelift_frontend::Variable,
+    fuel_profile_offset: Option<u32>,
+
     /// A function-local variable which caches the value of `*const
     /// VMRuntimeLimits` for this function's vmctx argument. This pointer is stored
     /// in the vmctx itself, but never changes for the lifetime of the function,
@@ -148,6 +156,20 @@ pub struct FuncEnvironment<'module_environment> {
 
     fuel_consumed: i64,
 
+    /// The kind of each of this function's locals, recorded when guest
+    /// debugging is enabled so that their values can be passed to the
+    /// `debug_break` builtin.
+    debug_locals: Vec<DebugLocalKind>,
+
+    /// The pointer to the store's guest debugging flags, loaded from
+    /// `VMRuntimeLimits` at the start of the function when guest debugging
+    /// is enabled.
+    debug_flags_ptr: ir::Value,
+
+    /// The source location of the operator currently being translated, which
+    /// is reported along with memory accesses when tracing them.
+    current_srcloc: ir::SourceLoc,
+
     /// A `GlobalValue` in CLIF which represents the stack limit.
     ///
     /// Typically this resides in the `stack_limit` value of `ir::Function` but
@@ -191,6 +213,8 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             offsets: VMOffsets::new(compiler.isa().pointer_bytes(), &translation.module),
             tunables,
             fuel_var: Variable::new(0),
+            fuel_profile_var: Variable::new(0),
+            fuel_profile_offset: None,
             epoch_deadline_var: Variable::new(0),
             epoch_ptr_var: Variable::new(0),
             vmruntime_limits_ptr: ir::Value::reserved_value(),
@@ -199,6 +223,10 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             // functions should consume at least some fuel.
             fuel_consumed: 1,
 
+            debug_locals: Vec::new(),
+            debug_flags_ptr: ir::Value::reserved_value(),
+            current_srcloc: ir::SourceLoc::default(),
+
             #[cfg(feature = "wmemcheck")]
             translation,
 
@@ -320,6 +348,15 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         // is then periodically flushed to the Store-defined location in
         // `VMRuntimeLimits` later.
         builder.declare_var(self.fuel_var, ir::types::I64);
+        if self.tunables.fuel_profiling {
+            let func_index = match &builder.func.name {
+                ir::UserFuncName::User(user) => FuncIndex::from_u32(user.index),
+                _ => panic!("function name not a UserFuncName::User as expected"),
+            };
+            let defined = self.module.defined_func_index(func_index).unwrap();
+            self.fuel_profile_offset = Some(self.offsets.vmctx_fuel_profile_counter(defined));
+            builder.declare_var(self.fuel_profile_var, ir::types::I64);
+        }
         self.fuel_load_into_var(builder);
         self.fuel_check(builder);
     }
@@ -464,6 +501,9 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             .ins()
             .load(ir::types::I64, ir::MemFlags::trusted(), addr, offset);
         builder.def_var(self.fuel_var, fuel);
+        if self.fuel_profile_offset.is_some() {
+            builder.def_var(self.fuel_profile_var, fuel);
+        }
     }
 
     /// Stores the fuel consumption value from `self.fuel_var` into
@@ -474,6 +514,24 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         builder
             .ins()
             .store(ir::MemFlags::trusted(), fuel_consumed, addr, offset);
+        self.fuel_profile_update(builder, fuel_consumed);
+    }
+
+    /// Adds the fuel consumed since the last update to this function's
+    /// counter in the `VMContext`, if fuel profiling is enabled.
+    fn fuel_profile_update(&mut self, builder: &mut FunctionBuilder<'_>, fuel: ir::Value) {
+        let Some(offset) = self.fuel_profile_offset else {
+            return;
+        };
+        let offset = i32::try_from(offset).unwrap();
+        let last = builder.use_var(self.fuel_profile_var);
+        let consumed = builder.ins().isub(fuel, last);
+        let vmctx = self.vmctx_val(&mut builder.cursor());
+        let flags = ir::MemFlags::trusted();
+        let count = builder.ins().load(ir::types::I64, flags, vmctx, offset);
+        let count = builder.ins().iadd(count, consumed);
+        builder.ins().store(flags, count, vmctx, offset);
+        builder.def_var(self.fuel_profile_var, fuel);
     }
 
     /// Returns the `(address, offset)` of the fuel consumption within
@@ -528,6 +586,91 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         builder.switch_to_block(continuation_block);
     }
 
+    fn debug_function_entry(&mut self, builder: &mut FunctionBuilder<'_>) {
+        // The flags themselves may change at any time, but the pointer to them
+        // doesn't, so load it once and reuse it across the entire function.
+        debug_assert!(self.debug_flags_ptr.is_reserved_value());
+        let offset = i32::from(self.offsets.ptr.vmruntime_limits_debug_flags());
+        self.debug_flags_ptr = builder.ins().load(
+            self.pointer_type(),
+            ir::MemFlags::trusted(),
+            self.vmruntime_limits_ptr,
+            offset,
+        );
+    }
+
+    fn debug_check(&mut self, builder: &mut FunctionBuilder, srcloc: ir::SourceLoc) {
+        let check_block = builder.create_block();
+        let break_block = builder.create_block();
+        let continuation_block = builder.create_block();
+        builder.set_cold_block(check_block);
+        builder.set_cold_block(break_block);
+
+        // The store's debug flags are zero unless a breakpoint is set, the
+        // debugger is stepping, or an interruption has been requested, in
+        // which case the host needs to be consulted.
+        let flags = builder
+            .ins()
+            .load(I32, ir::MemFlags::trusted(), self.debug_flags_ptr, 0);
+        builder
+            .ins()
+            .brif(flags, check_block, &[], continuation_block, &[]);
+        builder.seal_block(check_block);
+
+        // Ask the host whether execution should stop at this instruction,
+        // either because there's a breakpoint here or because the debugger is
+        // stepping.
+        builder.switch_to_block(check_block);
+        let vmctx = self.vmctx_val(&mut builder.cursor());
+        let offset = builder.ins().iconst(I32, i64::from(srcloc.bits()));
+        let debug_check = self.builtin_functions.debug_check(builder.func);
+        let call = builder.ins().call(debug_check, &[vmctx, offset]);
+        let should_break = builder.func.dfg.first_result(call);
+        builder
+            .ins()
+            .brif(should_break, break_block, &[], continuation_block, &[]);
+        builder.seal_block(break_block);
+
+        // If so then spill all of the function's locals to the stack so that
+        // the debugger can inspect them. References are omitted since they
+        // can't be meaningfully inspected as raw values.
+        builder.switch_to_block(break_block);
+        let locals = if self.debug_locals.is_empty() {
+            builder.ins().iconst(self.pointer_type(), 0)
+        } else {
+            let size = DEBUG_LOCAL_SIZE * u32::try_from(self.debug_locals.len()).unwrap();
+            let slot = builder.func.create_sized_stack_slot(ir::StackSlotData::new(
+                ir::StackSlotKind::ExplicitSlot,
+                size,
+                4,
+            ));
+            for (i, kind) in self.debug_locals.iter().enumerate() {
+                let record = i32::try_from(i).unwrap() * DEBUG_LOCAL_SIZE as i32;
+                let kind_val = builder.ins().iconst(I32, i64::from(*kind as u32));
+                builder.ins().stack_store(kind_val, slot, record);
+                if *kind != DebugLocalKind::Ref {
+                    let value = builder.use_var(Variable::new(i));
+                    builder.ins().stack_store(
+                        value,
+                        slot,
+                        record + DEBUG_LOCAL_VALUE_OFFSET as i32,
+                    );
+                }
+            }
+            builder.ins().stack_addr(self.pointer_type(), slot, 0)
+        };
+        let len = i64::try_from(self.debug_locals.len()).unwrap();
+        let len = builder.ins().iconst(I32, len);
+        let debug_break = self.builtin_functions.debug_break(builder.func);
+        builder
+            .ins()
+            .call(debug_break, &[vmctx, offset, locals, len]);
+        builder.ins().jump(continuation_block, &[]);
+        builder.seal_block(continuation_block);
+
+        builder.switch_to_block(continuation_block);
+    }
+
     fn epoch_function_entry(&mut self, builder: &mut FunctionBuilder<'_>) {
         builder.declare_var(self.epoch_deadline_var, ir::types::I64);
         // Let epoch_check_full load the current deadline and call def_var
@@ -1748,6 +1891,15 @@ impl FuncEnvironment<'_> {
         self.fuel_var = Variable::new(num_locals);
         self.epoch_deadline_var = Variable::new(num_locals + 1);
         self.epoch_ptr_var = Variable::new(num_locals + 2);
+        self.fuel_profile_var = Variable::new(num_locals + 3);
+    }
+
+    /// Records the types of all of this function's locals, which are used by
+    /// guest debugging instrumentation.
+    pub fn declare_debug_locals(&mut self, tys: impl IntoIterator<Item = wasmparser::ValType>) {
+        if self.tunables.guest_debug {
+            self.debug_locals = tys.into_iter().map(DebugLocalKind::from_val_type).collect();
+        }
     }
 
     pub fn translate_table_grow(
@@ -3051,9 +3203,14 @@ impl FuncEnvironment<'_> {
     pub fn before_translate_operator(
         &mut self,
         op: &Operator,
+        srcloc: ir::SourceLoc,
         builder: &mut FunctionBuilder,
         state: &FuncTranslationState,
     ) -> WasmResult<()> {
+        self.current_srcloc = srcloc;
+        if self.tunables.guest_debug && state.reachable() {
+            self.debug_check(builder, srcloc);
+        }
         if self.tunables.consume_fuel {
             self.fuel_before_op(op, builder, state.reachable());
         }
@@ -3099,7 +3256,10 @@ impl FuncEnvironment<'_> {
 
         // If the `vmruntime_limits_ptr` variable will get used then we initialize
         // it here.
-        if self.tunables.consume_fuel || self.tunables.epoch_interruption {
+        if self.tunables.consume_fuel
+            || self.tunables.epoch_interruption
+            || self.tunables.guest_debug
+        {
             self.declare_vmruntime_limits_ptr(builder);
         }
         // Additionally we initialize `fuel_var` if it will get used.
@@ -3110,6 +3270,10 @@ impl FuncEnvironment<'_> {
         if self.tunables.epoch_interruption {
             self.epoch_function_entry(builder);
         }
+        // Load the pointer to the debug flags checked before each instruction.
+        if self.tunables.guest_debug {
+            self.debug_function_entry(builder);
+        }
 
         #[cfg(feature = "wmemcheck")]
         if self.compiler.wmemcheck {
@@ -3219,6 +3383,50 @@ impl FuncEnvironment<'_> {
         let _ = (builder, val_size, addr, offset);
     }
 
+    /// Reports an access of `size` bytes at `index + offset` in `memory` to the
+    /// `trace_memory_access` builtin, if memory access tracing is enabled.
+    ///
+    /// This is called after any explicit bounds check of the access, but
+    /// bounds checks may be elided in favor of guard pages, so the traced
+    /// access may still trap.
+    pub fn trace_memory_access(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        kind: MemoryAccessKind,
+        memory: MemoryIndex,
+        size: u8,
+        index: ir::Value,
+        offset: u64,
+    ) {
+        let traced = self.tunables.memory_access_tracing
+            || (self.tunables.race_detection && self.module.memories[memory].shared);
+        if !traced {
+            return;
+        }
+        let srcloc = self.current_srcloc;
+        let index = if builder.func.dfg.value_type(index) == I32 {
+            builder.ins().uextend(I64, index)
+        } else {
+            index
+        };
+        let addr = if offset == 0 {
+            index
+        } else {
+            // This wraps around if the address overflows, in which case the
+            // access itself traps.
+            builder.ins().iadd_imm(index, offset as i64)
+        };
+        let vmctx = self.vmctx_val(&mut builder.cursor());
+        let memory = builder.ins().iconst(I32, i64::from(memory.as_u32()));
+        let size = builder.ins().iconst(I32, i64::from(size));
+        let kind = builder.ins().iconst(I32, i64::from(kind as u32));
+        let offset = builder.ins().iconst(I32, i64::from(srcloc.bits()));
+        let trace = self.builtin_functions.trace_memory_access(builder.func);
+        builder
+            .ins()
+            .call(trace, &[vmctx, memory, addr, size, kind, offset]);
+    }
+
     pub fn update_global(
         &mut self,
         builder: &mut FunctionBuilder,
@@ -13,9 +13,11 @@ use smallvec::SmallVec;
 use wasmtime_environ::{
     wasm_unsupported, Collector, GcArrayLayout, GcLayout, GcStructLayout, ModuleInternedTypeIndex,
     PtrSize, TypeIndex, VMGcKind, WasmHeapTopType, WasmHeapType, WasmRefType, WasmResult,
-    WasmStorageType, WasmValType, I31_DISCRIMINANT, NON_NULL_NON_I31_MASK,
+    WasmStorageType, WasmValType, I31_DISCRIMINANT,
 };
 
+#[cfg(feature = "gc-copying")]
+mod copying;
 #[cfg(feature = "gc-drc")]
 mod drc;
 #[cfg(feature = "gc-null")]
@@ -40,20 +42,63 @@ pub fn gc_compiler(func_env: &FuncEnvironment<'_>) -> WasmResult<Box<dyn GcCompi
              was disabled at compile time",
         )),
 
-        #[cfg(any(feature = "gc-drc", feature = "gc-null"))]
+        #[cfg(feature = "gc-copying")]
+        Some(Collector::Copying) => Ok(Box::new(copying::CopyingCompiler::default())),
+        #[cfg(not(feature = "gc-copying"))]
+        Some(Collector::Copying) => Err(wasm_unsupported!(
+            "the copying collector is unavailable because the `gc-copying` \
+             feature was disabled at compile time",
+        )),
+
+        #[cfg(any(feature = "gc-drc", feature = "gc-null", feature = "gc-copying"))]
         None => Err(wasm_unsupported!(
             "support for GC types disabled at configuration time"
         )),
-        #[cfg(not(any(feature = "gc-drc", feature = "gc-null")))]
+        #[cfg(not(any(feature = "gc-drc", feature = "gc-null", feature = "gc-copying")))]
         None => Err(wasm_unsupported!(
             "support for GC types disabled because no collector implementation \
-             was selected at compile time; enable one of the `gc-drc` or \
-             `gc-null` features",
+             was selected at compile time; enable one of the `gc-drc`, \
+             `gc-null`, or `gc-copying` features",
         )),
     }
 }
 
-#[cfg_attr(not(feature = "gc-drc"), allow(dead_code))]
+/// Emit CLIF to call the `gc_raw_alloc` libcall.
+///
+/// It is the caller's responsibility to ensure that `size` fits within the
+/// `VMGcKind`'s unused bits.
+#[cfg(any(feature = "gc-drc", feature = "gc-null", feature = "gc-copying"))]
+fn emit_gc_raw_alloc(
+    func_env: &mut FuncEnvironment<'_>,
+    builder: &mut FunctionBuilder<'_>,
+    kind: VMGcKind,
+    ty: ModuleInternedTypeIndex,
+    size: ir::Value,
+    align: u32,
+) -> ir::Value {
+    let gc_alloc_raw_builtin = func_env.builtin_functions.gc_alloc_raw(builder.func);
+    let vmctx = func_env.vmctx_val(&mut builder.cursor());
+
+    let kind = builder
+        .ins()
+        .iconst(ir::types::I32, i64::from(kind.as_u32()));
+
+    let ty = builder.ins().iconst(ir::types::I32, i64::from(ty.as_u32()));
+
+    assert!(align.is_power_of_two());
+    let align = builder.ins().iconst(ir::types::I32, i64::from(align));
+
+    let call_inst = builder
+        .ins()
+        .call(gc_alloc_raw_builtin, &[vmctx, kind, ty, size, align]);
+
+    let gc_ref = builder.func.dfg.first_result(call_inst);
+    let gc_ref = builder.ins().ireduce(ir::types::I32, gc_ref);
+    builder.declare_value_needs_stack_map(gc_ref);
+    gc_ref
+}
+
+#[cfg_attr(not(any(feature = "gc-drc", feature = "gc-copying")), allow(dead_code))]
 fn unbarriered_load_gc_ref(
     builder: &mut FunctionBuilder,
     ty: WasmHeapType,
@@ -68,7 +113,10 @@ fn unbarriered_load_gc_ref(
     Ok(gc_ref)
 }
 
-#[cfg_attr(not(any(feature = "gc-drc", feature = "gc-null")), allow(dead_code))]
+#[cfg_attr(
+    not(any(feature = "gc-drc", feature = "gc-null", feature = "gc-copying")),
+    allow(dead_code)
+)]
 fn unbarriered_store_gc_ref(
     builder: &mut FunctionBuilder,
     ty: WasmHeapType,
@@ -484,7 +532,10 @@ pub fn translate_array_new_fixed(
 
 impl ArrayInit<'_> {
     /// Get the length (as an `i32`-typed `ir::Value`) of these array elements.
-    #[cfg_attr(not(any(feature = "gc-drc", feature = "gc-null")), allow(dead_code))]
+    #[cfg_attr(
+        not(any(feature = "gc-drc", feature = "gc-null", feature = "gc-copying")),
+        allow(dead_code)
+    )]
     fn len(self, pos: &mut FuncCursor) -> ir::Value {
         match self {
             ArrayInit::Fill { len, .. } => len,
@@ -496,7 +547,10 @@ impl ArrayInit<'_> {
     }
 
     /// Initialize a newly-allocated array's elements.
-    #[cfg_attr(not(any(feature = "gc-drc", feature = "gc-null")), allow(dead_code))]
+    #[cfg_attr(
+        not(any(feature = "gc-drc", feature = "gc-null", feature = "gc-copying")),
+        allow(dead_code)
+    )]
     fn initialize(
         self,
         func_env: &mut FuncEnvironment<'_>,
@@ -1166,7 +1220,10 @@ fn uextend_i32_to_pointer_type(
 /// in its initialization.
 ///
 /// Traps if the size overflows.
-#[cfg_attr(not(any(feature = "gc-drc", feature = "gc-null")), allow(dead_code))]
+#[cfg_attr(
+    not(any(feature = "gc-drc", feature = "gc-null", feature = "gc-copying")),
+    allow(dead_code)
+)]
 fn emit_array_size(
     func_env: &mut FuncEnvironment<'_>,
     builder: &mut FunctionBuilder<'_>,
@@ -1211,7 +1268,10 @@ fn emit_array_size(
 
 /// Common helper for struct-field initialization that can be reused across
 /// collectors.
-#[cfg_attr(not(any(feature = "gc-drc", feature = "gc-null")), allow(dead_code))]
+#[cfg_attr(
+    not(any(feature = "gc-drc", feature = "gc-null", feature = "gc-copying")),
+    allow(dead_code)
+)]
 fn initialize_struct_fields(
     func_env: &mut FuncEnvironment<'_>,
     builder: &mut FunctionBuilder<'_>,
@@ -1444,17 +1504,10 @@ impl FuncEnvironment<'_> {
             // Fully general case: this GC reference could be either null or an
             // i31.
             (true, true) => {
-                // Mask for checking whether any bits are set, other than the
-                // `i31ref` discriminant, which should not be set. This folds
-                // the null and i31ref checks together into a single `band`.
-                let mask = builder.ins().iconst(
-                    ir::types::I32,
-                    (NON_NULL_NON_I31_MASK & u32::MAX as u64) as i64,
-                );
-                let is_non_null_and_non_i31 = builder.ins().band(gc_ref, mask);
-                builder
-                    .ins()
-                    .icmp_imm(ir::condcodes::IntCC::Equal, is_non_null_and_non_i31, 0)
+                let is_i31 = builder.ins().band_imm(gc_ref, I31_DISCRIMINANT as i64);
+                let is_null = builder.ins().icmp_imm(IntCC::Equal, gc_ref, 0);
+                let is_null = builder.ins().uextend(ir::types::I32, is_null);
+                builder.ins().bor(is_i31, is_null)
             }
         }
     }
@@ -10,8 +10,8 @@ use cranelift_codegen::ir::{self, InstBuilder};
 use cranelift_frontend::FunctionBuilder;
 use smallvec::SmallVec;
 use wasmtime_environ::{
-    drc::DrcTypeLayouts, GcTypeLayouts, ModuleInternedTypeIndex, PtrSize, TypeIndex, VMGcKind,
-    WasmHeapTopType, WasmHeapType, WasmRefType, WasmResult, WasmStorageType, WasmValType,
+    drc::DrcTypeLayouts, GcTypeLayouts, PtrSize, TypeIndex, VMGcKind, WasmHeapTopType,
+    WasmHeapType, WasmRefType, WasmResult, WasmStorageType, WasmValType,
 };
 
 #[derive(Default)]
@@ -254,40 +254,6 @@ impl DrcCompiler {
     }
 }
 
-/// Emit CLIF to call the `gc_raw_alloc` libcall.
-///
-/// It is the caller's responsibility to ensure that `size` fits within the
-/// `VMGcKind`'s unused bits.
-fn emit_gc_raw_alloc(
-    func_env: &mut FuncEnvironment<'_>,
-    builder: &mut FunctionBuilder<'_>,
-    kind: VMGcKind,
-    ty: ModuleInternedTypeIndex,
-    size: ir::Value,
-    align: u32,
-) -> ir::Value {
-    let gc_alloc_raw_builtin = func_env.builtin_functions.gc_alloc_raw(builder.func);
-    let vmctx = func_env.vmctx_val(&mut builder.cursor());
-
-    let kind = builder
-        .ins()
-        .iconst(ir::types::I32, i64::from(kind.as_u32()));
-
-    let ty = builder.ins().iconst(ir::types::I32, i64::from(ty.as_u32()));
-
-    assert!(align.is_power_of_two());
-    let align = builder.ins().iconst(ir::types::I32, i64::from(align));
-
-    let call_inst = builder
-        .ins()
-        .call(gc_alloc_raw_builtin, &[vmctx, kind, ty, size, align]);
-
-    let gc_ref = builder.func.dfg.first_result(call_inst);
-    let gc_ref = builder.ins().ireduce(ir::types::I32, gc_ref);
-    builder.declare_value_needs_stack_map(gc_ref);
-    gc_ref
-}
-
 impl GcCompiler for DrcCompiler {
     fn layouts(&self) -> &dyn GcTypeLayouts {
         &self.layouts
@@ -618,15 +584,28 @@ impl GcCompiler for DrcCompiler {
         //
         // dec_ref_block:
         //     let ref_count = load old_val.ref_count
-        //     let new_ref_count = isub_imm ref_count, 1
-        //     let old_val_needs_drop = icmp_imm eq new_ref_count, 0
-        //     brif old_val_needs_drop, drop_old_val_block, store_dec_ref_block
+        //     let old_val_needs_drop = icmp_imm eq ref_count, 1
+        //     brif old_val_needs_drop, drop_old_val_block, defer_dec_ref_block
         //
         // cold drop_old_val_block:
         //     call drop_gc_ref(old_val)
         //     jump continue_block
         //
-        // store_dec_ref_block:
+        // defer_dec_ref_block:
+        //     let (next, end) = load VMGcRefActivationsTable bump region
+        //     let bump_region_is_full = icmp eq next, end
+        //     brif bump_region_is_full, gc_block, push_old_val_block
+        //
+        // push_old_val_block:
+        //     store old_val, next
+        //     let new_next = iadd_imm next, size_of(reference_type)
+        //     store new_next, activations_table.next
+        //     jump continue_block
+        //
+        // cold gc_block:
+        //     call gc(old_val)
+        //     let ref_count = load old_val.ref_count
+        //     let new_ref_count = iadd_imm ref_count, -1
         //     store old_val.ref_count, new_ref_count
         //     jump continue_block
         //
@@ -642,6 +621,15 @@ impl GcCompiler for DrcCompiler {
         // 2. The old value's ref count is decremented, and that it is dropped
         //    if the ref count reaches zero.
         //
+        // When the old value's ref count won't reach zero, the decrement is
+        // deferred by moving `dst`'s reference into the activations table,
+        // which releases it at the next GC. That way the collector sees the
+        // decrement and can record the old value as a candidate for cycle
+        // collection, without calling out of line here. If the bump region is
+        // full, we do a GC to make room, as in the read barrier, and then
+        // decrement the ref count directly, since the GC has already put the
+        // old value in the table.
+        //
         // We must do the increment before the decrement. If we did it in the
         // other order, then when `*dst == new_val`, we could confuse ourselves
         // by observing a zero ref count after the decrement but before it would
@@ -657,18 +645,23 @@ impl GcCompiler for DrcCompiler {
         let check_old_val_block = builder.create_block();
         let dec_ref_block = builder.create_block();
         let drop_old_val_block = builder.create_block();
-        let store_dec_ref_block = builder.create_block();
+        let defer_dec_ref_block = builder.create_block();
+        let push_old_val_block = builder.create_block();
+        let gc_block = builder.create_block();
         let continue_block = builder.create_block();
 
         builder.ensure_inserted_block();
         builder.set_cold_block(drop_old_val_block);
+        builder.set_cold_block(gc_block);
 
         builder.insert_block_after(inc_ref_block, current_block);
         builder.insert_block_after(check_old_val_block, inc_ref_block);
         builder.insert_block_after(dec_ref_block, check_old_val_block);
         builder.insert_block_after(drop_old_val_block, dec_ref_block);
-        builder.insert_block_after(store_dec_ref_block, drop_old_val_block);
-        builder.insert_block_after(continue_block, store_dec_ref_block);
+        builder.insert_block_after(defer_dec_ref_block, drop_old_val_block);
+        builder.insert_block_after(push_old_val_block, defer_dec_ref_block);
+        builder.insert_block_after(gc_block, push_old_val_block);
+        builder.insert_block_after(continue_block, gc_block);
 
         // Load the old value and then check whether the new value is non-null
         // and non-i31.
@@ -709,22 +702,19 @@ impl GcCompiler for DrcCompiler {
         builder.switch_to_block(dec_ref_block);
         builder.seal_block(dec_ref_block);
         let ref_count = self.load_ref_count(func_env, builder, old_val);
-        let new_ref_count = builder.ins().iadd_imm(ref_count, -1);
-        let old_val_needs_drop = builder.ins().icmp_imm(IntCC::Equal, new_ref_count, 0);
+        let old_val_needs_drop = builder.ins().icmp_imm(IntCC::Equal, ref_count, 1);
         builder.ins().brif(
             old_val_needs_drop,
             drop_old_val_block,
             &[],
-            store_dec_ref_block,
+            defer_dec_ref_block,
             &[],
         );
 
         // Block to call out-of-line to drop a GC reference when its ref count
         // reaches zero.
         //
-        // Note that this libcall does its own dec-ref operation, so we only
-        // actually store `new_ref_count` back to the `old_val` object when
-        // `new_ref_count != 0`.
+        // Note that this libcall does its own dec-ref operation.
         builder.switch_to_block(drop_old_val_block);
         builder.seal_block(drop_old_val_block);
         let drop_gc_ref_libcall = func_env.builtin_functions.drop_gc_ref(builder.func);
@@ -732,11 +722,41 @@ impl GcCompiler for DrcCompiler {
         builder.ins().call(drop_gc_ref_libcall, &[vmctx, old_val]);
         builder.ins().jump(continue_block, &[]);
 
-        // Block to store the new ref count back to `old_val` for when
-        // `new_ref_count != 0`, as explained above.
-        builder.switch_to_block(store_dec_ref_block);
-        builder.seal_block(store_dec_ref_block);
-        self.store_ref_count(func_env, builder, old_val, new_ref_count);
+        // Block to defer the decrement when the ref count won't reach zero, by
+        // checking whether the bump region has room for `old_val`.
+        builder.switch_to_block(defer_dec_ref_block);
+        builder.seal_block(defer_dec_ref_block);
+        let (activations_table, next, end) = self.load_bump_region(func_env, builder);
+        let bump_region_is_full = builder.ins().icmp(IntCC::Equal, next, end);
+        builder
+            .ins()
+            .brif(bump_region_is_full, gc_block, &[], push_old_val_block, &[]);
+
+        // Block to move `dst`'s reference to `old_val` into the bump region,
+        // without touching its ref count.
+        builder.switch_to_block(push_old_val_block);
+        builder.seal_block(push_old_val_block);
+        builder
+            .ins()
+            .store(ir::MemFlags::trusted(), old_val, next, 0);
+        let new_next = builder.ins().iadd_imm(next, i64::from(ref_ty.bytes()));
+        builder.ins().store(
+            ir::MemFlags::trusted(),
+            new_next,
+            activations_table,
+            i32::try_from(func_env.offsets.vm_gc_ref_activation_table_next()).unwrap(),
+        );
+        builder.ins().jump(continue_block, &[]);
+
+        // Block for when the bump region is full: do a GC, which also inserts
+        // `old_val` into the activations table, and then drop `dst`'s
+        // reference to it.
+        builder.switch_to_block(gc_block);
+        builder.seal_block(gc_block);
+        let gc_libcall = func_env.builtin_functions.gc(builder.func);
+        let vmctx = func_env.vmctx_val(&mut builder.cursor());
+        builder.ins().call(gc_libcall, &[vmctx, old_val]);
+        self.mutate_ref_count(func_env, builder, old_val, -1);
         builder.ins().jump(continue_block, &[]);
 
         // Join point after we're done with the GC barrier.
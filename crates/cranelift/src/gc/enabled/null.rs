@@ -9,10 +9,9 @@ use crate::gc::gc_compiler;
 use crate::{func_environ::FuncEnvironment, gc::GcCompiler};
 use cranelift_codegen::ir::{self, InstBuilder};
 use cranelift_frontend::FunctionBuilder;
-use wasmtime_environ::VMSharedTypeIndex;
 use wasmtime_environ::{
-    null::NullTypeLayouts, GcTypeLayouts, ModuleInternedTypeIndex, PtrSize, TypeIndex, VMGcKind,
-    WasmRefType, WasmResult,
+    null::{self, NullTypeLayouts},
+    GcTypeLayouts, ModuleInternedTypeIndex, PtrSize, TypeIndex, VMGcKind, WasmRefType, WasmResult,
 };
 
 #[derive(Default)]
@@ -21,9 +20,8 @@ pub struct NullCompiler {
 }
 
 impl NullCompiler {
-    /// Emit code to perform an allocation inline.
-    ///
-    /// `kind` may be `VMGcKind::ExternRef` iff `ty` is `None`.
+    /// Emit code to perform an allocation inline, calling out to the runtime
+    /// when the heap needs to grow.
     ///
     /// `size` must be greater than or equal to `size_of(VMGcHeader)`.
     ///
@@ -41,12 +39,12 @@ impl NullCompiler {
         func_env: &mut FuncEnvironment<'_>,
         builder: &mut FunctionBuilder,
         kind: VMGcKind,
-        ty: Option<ModuleInternedTypeIndex>,
+        ty: ModuleInternedTypeIndex,
         size: ir::Value,
-        align: ir::Value,
+        align: u32,
     ) -> (ir::Value, ir::Value) {
         assert_eq!(builder.func.dfg.value_type(size), ir::types::I32);
-        assert_eq!(builder.func.dfg.value_type(align), ir::types::I32);
+        assert!(align.is_power_of_two());
 
         // Check that the size fits in the unused bits of a `VMGcKind`, since
         // the null collector stores the object's size there.
@@ -66,9 +64,12 @@ impl NullCompiler {
             vmctx,
             i32::from(func_env.offsets.ptr.vmctx_gc_heap_data()),
         );
-        let next = builder
-            .ins()
-            .load(ir::types::I32, ir::MemFlags::trusted(), ptr_to_next, 0);
+        let next = builder.ins().load(
+            ir::types::I32,
+            ir::MemFlags::trusted(),
+            ptr_to_next,
+            i32::try_from(null::HEAP_DATA_NEXT_OFFSET).unwrap(),
+        );
 
         // Increment the bump "pointer" to the requested alignment:
         //
@@ -77,8 +78,7 @@ impl NullCompiler {
         // Overflow means that the alignment is too large to satisfy, so trap
         // accordingly. Note that `align - 1` can't overflow because `align` is
         // a power of two.
-        let minus_one = builder.ins().iconst(ir::types::I32, -1);
-        let align_minus_one = builder.ins().iadd(align, minus_one);
+        let align_minus_one = builder.ins().iconst(ir::types::I32, i64::from(align - 1));
         let next_plus_align_minus_one = func_env.uadd_overflow_trap(
             builder,
             next,
@@ -90,38 +90,47 @@ impl NullCompiler {
             .ins()
             .band(next_plus_align_minus_one, not_align_minus_one);
 
-        // Check whether the allocation fits in the heap space we have left.
+        // Check whether the allocation fits in the portion of the heap that
+        // we may currently allocate within. The runtime keeps that limit
+        // within the heap's bounds.
         let end_of_object =
             func_env.uadd_overflow_trap(builder, aligned, size, crate::TRAP_ALLOCATION_TOO_LARGE);
-        let uext_end_of_object = uextend_i32_to_pointer_type(builder, pointer_type, end_of_object);
-        let (base, bound) = func_env.get_gc_heap_base_bound(builder);
-        let is_in_bounds = builder.ins().icmp(
+        let limit = builder.ins().load(
+            ir::types::I32,
+            ir::MemFlags::trusted(),
+            ptr_to_next,
+            i32::try_from(null::HEAP_DATA_LIMIT_OFFSET).unwrap(),
+        );
+        let fits = builder.ins().icmp(
             ir::condcodes::IntCC::UnsignedLessThanOrEqual,
-            uext_end_of_object,
-            bound,
+            end_of_object,
+            limit,
         );
-        func_env.trapz(builder, is_in_bounds, crate::TRAP_ALLOCATION_TOO_LARGE);
 
-        // Write the header, update the bump "pointer", and return the newly
-        // allocated object.
+        let base = func_env.get_gc_heap_base(builder);
+        let fast_block = builder.create_block();
+        let slow_block = builder.create_block();
+        let continue_block = builder.create_block();
+        let gc_ref = builder.append_block_param(continue_block, ir::types::I32);
+        builder.set_cold_block(slow_block);
+        builder.ins().brif(fits, fast_block, &[], slow_block, &[]);
+
+        // Fast path: write the header, update the bump "pointer", and continue
+        // with the newly allocated object.
         //
         // TODO: Ideally we would use a single `i64` store to write both the
         // header and the type index, but that requires generating different
         // code for big-endian architectures, and I haven't bothered doing that
         // yet.
+        builder.switch_to_block(fast_block);
+        builder.seal_block(fast_block);
         let uext_aligned = uextend_i32_to_pointer_type(builder, pointer_type, aligned);
         let ptr_to_object = builder.ins().iadd(base, uext_aligned);
-        let kind = builder
+        let kind_val = builder
             .ins()
             .iconst(ir::types::I32, i64::from(kind.as_u32()));
-        let kind_and_size = builder.ins().bor(kind, size);
-        let ty = match ty {
-            Some(ty) => func_env.module_interned_to_shared_ty(&mut builder.cursor(), ty),
-            None => builder.ins().iconst(
-                func_env.vmshared_type_index_ty(),
-                i64::from(VMSharedTypeIndex::reserved_value().as_bits()),
-            ),
-        };
+        let kind_and_size = builder.ins().bor(kind_val, size);
+        let shared_ty = func_env.module_interned_to_shared_ty(&mut builder.cursor(), ty);
         builder.ins().store(
             ir::MemFlags::trusted(),
             kind_and_size,
@@ -130,15 +139,31 @@ impl NullCompiler {
         );
         builder.ins().store(
             ir::MemFlags::trusted(),
-            ty,
+            shared_ty,
             ptr_to_object,
             i32::try_from(wasmtime_environ::VM_GC_HEADER_TYPE_INDEX_OFFSET).unwrap(),
         );
-        builder
-            .ins()
-            .store(ir::MemFlags::trusted(), end_of_object, ptr_to_next, 0);
+        builder.ins().store(
+            ir::MemFlags::trusted(),
+            end_of_object,
+            ptr_to_next,
+            i32::try_from(null::HEAP_DATA_NEXT_OFFSET).unwrap(),
+        );
+        builder.ins().jump(continue_block, &[aligned]);
+
+        // Slow path: call out to the runtime, which grows the heap if the
+        // store's resource limiter allows it, and otherwise fails.
+        builder.switch_to_block(slow_block);
+        builder.seal_block(slow_block);
+        let slow_gc_ref = emit_gc_raw_alloc(func_env, builder, kind, ty, size, align);
+        builder.ins().jump(continue_block, &[slow_gc_ref]);
 
-        (aligned, ptr_to_object)
+        builder.switch_to_block(continue_block);
+        builder.seal_block(continue_block);
+        let uext_gc_ref = uextend_i32_to_pointer_type(builder, pointer_type, gc_ref);
+        let ptr_to_object = builder.ins().iadd(base, uext_gc_ref);
+
+        (gc_ref, ptr_to_object)
     }
 }
 
@@ -168,13 +193,11 @@ impl GcCompiler for NullCompiler {
         let size = emit_array_size(func_env, builder, &array_layout, init);
 
         // Next, allocate the array.
-        assert!(align.is_power_of_two());
-        let align = builder.ins().iconst(ir::types::I32, i64::from(align));
         let (gc_ref, ptr_to_object) = self.emit_inline_alloc(
             func_env,
             builder,
             VMGcKind::ArrayRef,
-            Some(interned_type_index),
+            interned_type_index,
             size,
             align,
         );
@@ -226,17 +249,13 @@ impl GcCompiler for NullCompiler {
         assert_eq!(VMGcKind::UNUSED_MASK & struct_size, struct_size);
         let struct_size_val = builder.ins().iconst(ir::types::I32, i64::from(struct_size));
 
-        let align = builder
-            .ins()
-            .iconst(ir::types::I32, i64::from(struct_align));
-
         let (struct_ref, raw_struct_pointer) = self.emit_inline_alloc(
             func_env,
             builder,
             VMGcKind::StructRef,
-            Some(interned_type_index),
+            interned_type_index,
             struct_size_val,
-            align,
+            struct_align,
         );
 
         // Initialize the struct's fields.
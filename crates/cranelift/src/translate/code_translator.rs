@@ -93,8 +93,8 @@ use std::collections::{hash_map, HashMap};
 use std::vec::Vec;
 use wasmparser::{FuncValidator, MemArg, Operator, WasmModuleResources};
 use wasmtime_environ::{
-    wasm_unsupported, DataIndex, ElemIndex, FuncIndex, GlobalIndex, MemoryIndex, Signed,
-    TableIndex, TypeConvert, TypeIndex, Unsigned, WasmRefType, WasmResult,
+    wasm_unsupported, DataIndex, ElemIndex, FuncIndex, GlobalIndex, MemoryAccessKind, MemoryIndex,
+    Signed, TableIndex, TypeConvert, TypeIndex, Unsigned, WasmRefType, WasmResult,
 };
 
 /// Given a `Reachability<T>`, unwrap the inner `T` or, when unreachable, set
@@ -869,50 +869,98 @@ pub fn translate_operator(
         }
         Operator::V128Load8x8S { memarg } => {
             //TODO(#6829): add before_load() and before_store() hooks for SIMD loads and stores.
-            let (flags, _, base) = unwrap_or_return_unreachable_state!(
+            let (flags, index, base) = unwrap_or_return_unreachable_state!(
                 state,
                 prepare_addr(memarg, 8, builder, state, environ)?
             );
+            environ.trace_memory_access(
+                builder,
+                MemoryAccessKind::Load,
+                MemoryIndex::from_u32(memarg.memory),
+                8,
+                index,
+                memarg.offset,
+            );
             let loaded = builder.ins().sload8x8(flags, base, 0);
             state.push1(loaded);
         }
         Operator::V128Load8x8U { memarg } => {
-            let (flags, _, base) = unwrap_or_return_unreachable_state!(
+            let (flags, index, base) = unwrap_or_return_unreachable_state!(
                 state,
                 prepare_addr(memarg, 8, builder, state, environ)?
             );
+            environ.trace_memory_access(
+                builder,
+                MemoryAccessKind::Load,
+                MemoryIndex::from_u32(memarg.memory),
+                8,
+                index,
+                memarg.offset,
+            );
             let loaded = builder.ins().uload8x8(flags, base, 0);
             state.push1(loaded);
         }
         Operator::V128Load16x4S { memarg } => {
-            let (flags, _, base) = unwrap_or_return_unreachable_state!(
+            let (flags, index, base) = unwrap_or_return_unreachable_state!(
                 state,
                 prepare_addr(memarg, 8, builder, state, environ)?
             );
+            environ.trace_memory_access(
+                builder,
+                MemoryAccessKind::Load,
+                MemoryIndex::from_u32(memarg.memory),
+                8,
+                index,
+                memarg.offset,
+            );
             let loaded = builder.ins().sload16x4(flags, base, 0);
             state.push1(loaded);
         }
         Operator::V128Load16x4U { memarg } => {
-            let (flags, _, base) = unwrap_or_return_unreachable_state!(
+            let (flags, index, base) = unwrap_or_return_unreachable_state!(
                 state,
                 prepare_addr(memarg, 8, builder, state, environ)?
             );
+            environ.trace_memory_access(
+                builder,
+                MemoryAccessKind::Load,
+                MemoryIndex::from_u32(memarg.memory),
+                8,
+                index,
+                memarg.offset,
+            );
             let loaded = builder.ins().uload16x4(flags, base, 0);
             state.push1(loaded);
         }
         Operator::V128Load32x2S { memarg } => {
-            let (flags, _, base) = unwrap_or_return_unreachable_state!(
+            let (flags, index, base) = unwrap_or_return_unreachable_state!(
                 state,
                 prepare_addr(memarg, 8, builder, state, environ)?
             );
+            environ.trace_memory_access(
+                builder,
+                MemoryAccessKind::Load,
+                MemoryIndex::from_u32(memarg.memory),
+                8,
+                index,
+                memarg.offset,
+            );
             let loaded = builder.ins().sload32x2(flags, base, 0);
             state.push1(loaded);
         }
         Operator::V128Load32x2U { memarg } => {
-            let (flags, _, base) = unwrap_or_return_unreachable_state!(
+            let (flags, index, base) = unwrap_or_return_unreachable_state!(
                 state,
                 prepare_addr(memarg, 8, builder, state, environ)?
             );
+            environ.trace_memory_access(
+                builder,
+                MemoryAccessKind::Load,
+                MemoryIndex::from_u32(memarg.memory),
+                8,
+                index,
+                memarg.offset,
+            );
             let loaded = builder.ins().uload32x2(flags, base, 0);
             state.push1(loaded);
         }
@@ -3349,6 +3397,14 @@ fn translate_load(
         };
 
     environ.before_load(builder, mem_op_size, wasm_index, memarg.offset);
+    environ.trace_memory_access(
+        builder,
+        MemoryAccessKind::Load,
+        MemoryIndex::from_u32(memarg.memory),
+        mem_op_size,
+        wasm_index,
+        memarg.offset,
+    );
 
     let (load, dfg) = builder
         .ins()
@@ -3375,6 +3431,14 @@ fn translate_store(
     );
 
     environ.before_store(builder, mem_op_size, wasm_index, memarg.offset);
+    environ.trace_memory_access(
+        builder,
+        MemoryAccessKind::Store,
+        MemoryIndex::from_u32(memarg.memory),
+        mem_op_size,
+        wasm_index,
+        memarg.offset,
+    );
 
     builder
         .ins()
@@ -3432,15 +3496,18 @@ fn translate_atomic_rmw(
         arg2 = builder.ins().ireduce(access_ty, arg2);
     }
 
-    let (flags, _, addr) = unwrap_or_return_unreachable_state!(
+    let access_size = u8::try_from(access_ty.bytes()).unwrap();
+    let (flags, index, addr) = unwrap_or_return_unreachable_state!(
         state,
-        prepare_atomic_addr(
-            memarg,
-            u8::try_from(access_ty.bytes()).unwrap(),
-            builder,
-            state,
-            environ,
-        )?
+        prepare_atomic_addr(memarg, access_size, builder, state, environ)?
+    );
+    environ.trace_memory_access(
+        builder,
+        MemoryAccessKind::AtomicRmw,
+        MemoryIndex::from_u32(memarg.memory),
+        access_size,
+        index,
+        memarg.offset,
     );
 
     let mut res = builder.ins().atomic_rmw(access_ty, flags, op, addr, arg2);
@@ -3489,15 +3556,18 @@ fn translate_atomic_cas(
         replacement = builder.ins().ireduce(access_ty, replacement);
     }
 
-    let (flags, _, addr) = unwrap_or_return_unreachable_state!(
+    let access_size = u8::try_from(access_ty.bytes()).unwrap();
+    let (flags, index, addr) = unwrap_or_return_unreachable_state!(
         state,
-        prepare_atomic_addr(
-            memarg,
-            u8::try_from(access_ty.bytes()).unwrap(),
-            builder,
-            state,
-            environ,
-        )?
+        prepare_atomic_addr(memarg, access_size, builder, state, environ)?
+    );
+    environ.trace_memory_access(
+        builder,
+        MemoryAccessKind::AtomicRmw,
+        MemoryIndex::from_u32(memarg.memory),
+        access_size,
+        index,
+        memarg.offset,
     );
     let mut res = builder.ins().atomic_cas(flags, addr, expected, replacement);
     if access_ty != widened_ty {
@@ -3532,15 +3602,18 @@ fn translate_atomic_load(
     };
     assert!(w_ty_ok && widened_ty.bytes() >= access_ty.bytes());
 
-    let (flags, _, addr) = unwrap_or_return_unreachable_state!(
+    let access_size = u8::try_from(access_ty.bytes()).unwrap();
+    let (flags, index, addr) = unwrap_or_return_unreachable_state!(
         state,
-        prepare_atomic_addr(
-            memarg,
-            u8::try_from(access_ty.bytes()).unwrap(),
-            builder,
-            state,
-            environ,
-        )?
+        prepare_atomic_addr(memarg, access_size, builder, state, environ)?
+    );
+    environ.trace_memory_access(
+        builder,
+        MemoryAccessKind::AtomicLoad,
+        MemoryIndex::from_u32(memarg.memory),
+        access_size,
+        index,
+        memarg.offset,
     );
     let mut res = builder.ins().atomic_load(access_ty, flags, addr);
     if access_ty != widened_ty {
@@ -3581,15 +3654,18 @@ fn translate_atomic_store(
         data = builder.ins().ireduce(access_ty, data);
     }
 
-    let (flags, _, addr) = unwrap_or_return_unreachable_state!(
+    let access_size = u8::try_from(access_ty.bytes()).unwrap();
+    let (flags, index, addr) = unwrap_or_return_unreachable_state!(
         state,
-        prepare_atomic_addr(
-            memarg,
-            u8::try_from(access_ty.bytes()).unwrap(),
-            builder,
-            state,
-            environ,
-        )?
+        prepare_atomic_addr(memarg, access_size, builder, state, environ)?
+    );
+    environ.trace_memory_access(
+        builder,
+        MemoryAccessKind::AtomicStore,
+        MemoryIndex::from_u32(memarg.memory),
+        access_size,
+        index,
+        memarg.offset,
     );
     builder.ins().atomic_store(flags, data, addr);
     Ok(())
@@ -156,6 +156,9 @@ fn parse_local_decls(
     }
 
     environ.after_locals(next_local);
+    environ.declare_debug_locals(
+        (0..u32::try_from(next_local).unwrap()).map(|i| validator.get_local_type(i).unwrap()),
+    );
 
     Ok(())
 }
@@ -245,10 +248,11 @@ fn parse_function_body(
     environ.before_translate_function(builder, state)?;
     while !reader.eof() {
         let pos = reader.original_position();
-        builder.set_srcloc(cur_srcloc(&reader));
+        let srcloc = cur_srcloc(&reader);
+        builder.set_srcloc(srcloc);
         let op = reader.read_operator()?;
         validator.op(pos, &op)?;
-        environ.before_translate_operator(&op, builder, state)?;
+        environ.before_translate_operator(&op, srcloc, builder, state)?;
         translate_operator(validator, &op, builder, state, environ)?;
         environ.after_translate_operator(&op, builder, state)?;
     }
@@ -41,6 +41,18 @@ macro_rules! foreach_builtin_function {
             out_of_gas(vmctx: vmctx) -> bool;
             // Invoked when we reach a new epoch.
             new_epoch(vmctx: vmctx) -> i64;
+            // Invoked before each instruction when guest debugging is enabled,
+            // returning whether execution should stop at the instruction at
+            // `offset`.
+            debug_check(vmctx: vmctx, offset: i32) -> i32;
+            // Invoked when execution stops at the instruction at `offset`, with
+            // `len` locals described by `locals`.
+            debug_break(vmctx: vmctx, offset: i32, locals: pointer, len: i32) -> bool;
+            // Invoked before each linear memory access when memory access
+            // tracing is enabled, with the access's effective address, its size
+            // in bytes, its `MemoryAccessKind`, and the offset of the accessing
+            // instruction.
+            trace_memory_access(vmctx: vmctx, memory: i32, addr: i64, size: i32, kind: i32, offset: i32) -> bool;
             // Invoked before malloc returns.
             #[cfg(feature = "wmemcheck")]
             check_malloc(vmctx: vmctx, addr: i32, len: i32) -> bool;
@@ -81,7 +93,7 @@ macro_rules! foreach_builtin_function {
 
             // Allocate a new, uninitialized GC object and return a reference to
             // it.
-            #[cfg(feature = "gc-drc")]
+            #[cfg(any(feature = "gc-drc", feature = "gc-copying"))]
             gc_alloc_raw(
                 vmctx: vmctx,
                 kind: i32,
@@ -371,6 +383,7 @@ impl BuiltinFunctionIndex {
             (@get get_interned_func_ref pointer) => (return None);
             (@get intern_func_ref_for_gc_heap i64) => (return None);
             (@get is_subtype i32) => (return None);
+            (@get debug_check i32) => (return None);
 
             // Bool-returning functions use `false` as an indicator of a trap.
             (@get $name:ident bool) => (TrapSentinel::Falsy);
@@ -10,6 +10,8 @@ use crate::{
 use anyhow::{bail, Result};
 use object::write::{Object, SectionId, StandardSegment, WritableBuffer};
 use object::SectionKind;
+#[cfg(feature = "snapshot")]
+use sha2::{Digest, Sha256};
 use std::ops::Range;
 
 /// Helper structure to create an ELF file as a compilation artifact.
@@ -121,6 +123,8 @@ impl<'a> ObjectBuilder<'a> {
     ) -> Result<CompiledModuleInfo> {
         let ModuleTranslation {
             mut module,
+            #[cfg(feature = "snapshot")]
+            wasm,
             debuginfo,
             has_unparsed_debuginfo,
             data,
@@ -227,6 +231,10 @@ impl<'a> ObjectBuilder<'a> {
                 code_section_offset: debuginfo.wasm_file.code_section_offset,
                 has_wasm_debuginfo: self.tunables.parse_wasm_debuginfo,
                 dwarf,
+                #[cfg(feature = "snapshot")]
+                checksum: Some(Sha256::digest(wasm).into()),
+                #[cfg(not(feature = "snapshot"))]
+                checksum: None,
             },
         })
     }
@@ -234,6 +234,12 @@ impl<'a, 'data> ModuleEnvironment<'a, 'data> {
                     .collect();
                 self.result.exported_signatures.sort_unstable();
                 self.result.exported_signatures.dedup();
+
+                if self.tunables.fuel_profiling {
+                    let module = &mut self.result.module;
+                    module.num_fuel_profile_counters =
+                        module.functions.len() - module.num_imported_funcs;
+                }
             }
 
             Payload::TypeSection(types) => {
@@ -53,12 +53,16 @@ mod compiler;
 #[cfg(feature = "compile")]
 pub mod dfg;
 #[cfg(feature = "compile")]
+mod linked_adapter;
+#[cfg(feature = "compile")]
 mod translate;
 #[cfg(feature = "compile")]
 mod types_builder;
 #[cfg(feature = "compile")]
 pub use self::compiler::*;
 #[cfg(feature = "compile")]
+pub use self::linked_adapter::*;
+#[cfg(feature = "compile")]
 pub use self::translate::*;
 #[cfg(feature = "compile")]
 pub use self::types_builder::*;
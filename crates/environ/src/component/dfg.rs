@@ -361,6 +361,7 @@ impl ComponentDfg {
             runtime_post_return: Default::default(),
             runtime_reallocs: Default::default(),
             runtime_instances: Default::default(),
+            adapters: Default::default(),
             num_lowerings: 0,
             trampolines: Default::default(),
             trampoline_defs: Default::default(),
@@ -401,6 +402,7 @@ impl ComponentDfg {
                 num_runtime_post_returns: linearize.runtime_post_return.len() as u32,
                 num_runtime_reallocs: linearize.runtime_reallocs.len() as u32,
                 num_runtime_instances: linearize.runtime_instances.len() as u32,
+                adapters: linearize.adapters.into_values().collect(),
                 imports: self.imports,
                 import_types: self.import_types,
                 num_runtime_component_instances: self.num_runtime_component_instances,
@@ -433,6 +435,7 @@ struct LinearizeDfg<'a> {
     runtime_reallocs: HashMap<ReallocId, RuntimeReallocIndex>,
     runtime_post_return: HashMap<PostReturnId, RuntimePostReturnIndex>,
     runtime_instances: HashMap<RuntimeInstance, RuntimeInstanceIndex>,
+    adapters: IndexMap<AdapterId, info::AdapterFunc>,
     num_lowerings: u32,
 }
 
@@ -657,6 +660,18 @@ impl LinearizeDfg<'_> {
         // already instantiated at.
         let instance = self.adapter_module(adapter_module);
 
+        if let EntityIndex::Function(func) = entity_index {
+            let info = &self.dfg.adapters[adapter];
+            self.adapters
+                .entry(adapter)
+                .or_insert_with(|| info::AdapterFunc {
+                    instance,
+                    func,
+                    caller: info.lower_options.instance,
+                    callee: info.lift_options.instance,
+                });
+        }
+
         // This adapter is always an export of the instance.
         info::CoreExport {
             instance,
@@ -48,7 +48,7 @@
 
 use crate::component::*;
 use crate::prelude::*;
-use crate::{EntityIndex, ModuleInternedTypeIndex, PrimaryMap, WasmValType};
+use crate::{EntityIndex, FuncIndex, ModuleInternedTypeIndex, PrimaryMap, WasmValType};
 use serde_derive::{Deserialize, Serialize};
 
 /// Metadata as a result of compiling a component.
@@ -178,6 +178,26 @@ pub struct Component {
     /// This is used to determine which set of instance flags are inspected when
     /// testing reentrance.
     pub defined_resource_instances: PrimaryMap<DefinedResourceIndex, RuntimeComponentInstanceIndex>,
+
+    /// The fused adapters used for calls between the component instances
+    /// nested within this component.
+    ///
+    /// This is used to describe such calls in coredumps.
+    pub adapters: Vec<AdapterFunc>,
+}
+
+/// A fused adapter used to call a function lifted by one component instance
+/// from another component instance within the same component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdapterFunc {
+    /// The instance of the adapter module containing this adapter.
+    pub instance: RuntimeInstanceIndex,
+    /// The index of this adapter's function within its adapter module.
+    pub func: FuncIndex,
+    /// The component instance which lowered the function, that is the caller.
+    pub caller: RuntimeComponentInstanceIndex,
+    /// The component instance which lifted the function, that is the callee.
+    pub callee: RuntimeComponentInstanceIndex,
 }
 
 impl Component {
@@ -0,0 +1,244 @@
+//! Compilation of fused adapters between separately-instantiated components.
+//!
+//! Adapters are normally identified and compiled during the translation of a
+//! component, in `translate/adapt.rs`, when both the lifted and the lowered
+//! side of a call are defined within the same component. Components can also
+//! be linked together at runtime though, where the exports of one component
+//! instance are used to satisfy the imports of another. In that situation
+//! neither component knows about the other at compile time, so the adapter is
+//! compiled here instead once the types and canonical options of both sides
+//! are known.
+//!
+//! The adapter module itself is generated by the same `fact` compiler as for
+//! components, the only difference being that its imports are described in
+//! terms of the two sides of the call rather than items within one component.
+
+use crate::component::dfg::{CoreDef, CoreExport, InstanceId};
+use crate::component::{
+    Adapter, AdapterOptions, ComponentTypes, ComponentTypesBuilder, ExportItem,
+    RuntimeComponentInstanceIndex, StringEncoding, Transcode, TypeFuncIndex,
+    TypeResourceTableIndex,
+};
+use crate::fact;
+use crate::prelude::*;
+use crate::{EntityRef, PrimaryMap};
+use wasmparser::Validator;
+
+/// The name of the function exported from a [`LinkedAdapter`]'s module.
+pub const LINKED_ADAPTER_EXPORT: &str = "adapter";
+
+/// One side of a call between two separately-instantiated components.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LinkedSide {
+    /// The component which lowered the function and is making the call.
+    Caller,
+    /// The component which lifted the function and is being called.
+    Callee,
+}
+
+/// The type and canonical options of one side of a [`LinkedAdapter`].
+pub struct LinkedAdapterSide<'a> {
+    /// The types of the component on this side of the call.
+    pub types: &'a ComponentTypes,
+    /// The type of the function as lifted or lowered by this side.
+    pub ty: TypeFuncIndex,
+    /// How strings are encoded by this side.
+    pub string_encoding: StringEncoding,
+    /// Whether this side has a linear memory configured.
+    pub memory: bool,
+    /// If `memory` is set, whether it's a 64-bit memory.
+    pub memory64: bool,
+    /// Whether this side has a `realloc` function configured.
+    pub realloc: bool,
+    /// Whether this side has a `post-return` function configured, which is
+    /// only possible for the callee.
+    pub post_return: bool,
+}
+
+/// An import of a [`LinkedAdapter`]'s module.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LinkedAdapterImport {
+    /// The instance flags of the given side as a mutable `i32` global.
+    Flags(LinkedSide),
+    /// The linear memory of the given side.
+    Memory(LinkedSide),
+    /// The `realloc` function of the given side.
+    Realloc(LinkedSide),
+    /// The core wasm function lifted by the callee.
+    Callee,
+    /// The `post-return` function of the callee.
+    PostReturn,
+    /// A function transcoding strings between the memories of the two sides.
+    ///
+    /// This has the same signature and semantics as the transcoders of
+    /// adapters within a component.
+    Transcode {
+        /// The transcoding operation this performs.
+        op: Transcode,
+        /// The side whose memory is being read.
+        from: LinkedSide,
+        /// Whether or not `from` is a 64-bit memory.
+        from64: bool,
+        /// The side whose memory is being written.
+        to: LinkedSide,
+        /// Whether or not `to` is a 64-bit memory.
+        to64: bool,
+    },
+    /// Transfers an owned resource between the tables of the two sides.
+    ResourceTransferOwn,
+    /// Transfers a borrowed resource between the tables of the two sides.
+    ResourceTransferBorrow,
+    /// Sets up the metadata for borrowed resources when a call starts.
+    ResourceEnterCall,
+    /// Tears down the metadata for borrowed resources when a call finishes.
+    ResourceExitCall,
+}
+
+/// A fused adapter which calls a function lifted by one component from a
+/// function lowered by another.
+pub struct LinkedAdapter {
+    /// The core wasm module containing the adapter, which is exported under
+    /// the name [`LINKED_ADAPTER_EXPORT`].
+    ///
+    /// The adapter's signature is that of the caller's lowered function.
+    pub wasm: Vec<u8>,
+    /// The imports of `wasm`, in order.
+    pub imports: Vec<LinkedAdapterImport>,
+    /// The resource tables referred to by the resource intrinsics imported
+    /// into `wasm`, each of which is a table of either the caller or the
+    /// callee.
+    pub resource_tables: PrimaryMap<TypeResourceTableIndex, (LinkedSide, TypeResourceTableIndex)>,
+}
+
+impl LinkedAdapter {
+    /// Compiles an adapter for calls from `caller` to `callee`.
+    ///
+    /// The function types of both sides must have already been checked to
+    /// match. If `debug` is set then the adapter contains extra debug
+    /// assertions, as with `Tunables::debug_adapter_modules`.
+    pub fn new(
+        caller: &LinkedAdapterSide<'_>,
+        callee: &LinkedAdapterSide<'_>,
+        debug: bool,
+    ) -> LinkedAdapter {
+        assert!(!caller.post_return);
+
+        // Both function types are merged into one set of type information,
+        // with distinct resource tables for each side.
+        let mut types = ComponentTypesBuilder::new(&Validator::new());
+        let mut resource_tables = PrimaryMap::new();
+        let mut import = |side: &LinkedAdapterSide<'_>, which| {
+            types.import_func_type(side.types, side.ty, &mut |types, table| {
+                let index = types.add_resource_table(side.types[table].clone());
+                let index2 = resource_tables.push((which, table));
+                assert_eq!(index, index2);
+                index
+            })
+        };
+        let lower_ty = import(caller, LinkedSide::Caller);
+        let lift_ty = import(callee, LinkedSide::Callee);
+
+        let adapter = Adapter {
+            lift_ty,
+            lift_options: options(callee, LinkedSide::Callee),
+            lower_ty,
+            lower_options: options(caller, LinkedSide::Caller),
+            func: placeholder(LinkedSide::Callee, "callee"),
+        };
+        let mut module = fact::Module::new(&types, debug);
+        module.adapt(LINKED_ADAPTER_EXPORT, &adapter);
+        let wasm = module.encode();
+        let imports = module
+            .imports()
+            .iter()
+            .map(|import| match import {
+                fact::Import::CoreDef(def) => linked_import(def),
+                fact::Import::Transcode {
+                    op,
+                    from,
+                    from64,
+                    to,
+                    to64,
+                } => LinkedAdapterImport::Transcode {
+                    op: *op,
+                    from: memory_side(from),
+                    from64: *from64,
+                    to: memory_side(to),
+                    to64: *to64,
+                },
+                fact::Import::ResourceTransferOwn => LinkedAdapterImport::ResourceTransferOwn,
+                fact::Import::ResourceTransferBorrow => LinkedAdapterImport::ResourceTransferBorrow,
+                fact::Import::ResourceEnterCall => LinkedAdapterImport::ResourceEnterCall,
+                fact::Import::ResourceExitCall => LinkedAdapterImport::ResourceExitCall,
+            })
+            .collect();
+
+        LinkedAdapter {
+            wasm,
+            imports,
+            resource_tables,
+        }
+    }
+}
+
+// The `fact` compiler describes the items imported by an adapter as
+// definitions within a component. Here each side of the call is modeled as a
+// core instance, with the same index for its component instance, which
+// exports its items under fixed names.
+
+fn side_index(side: LinkedSide) -> usize {
+    match side {
+        LinkedSide::Caller => 0,
+        LinkedSide::Callee => 1,
+    }
+}
+
+fn placeholder(side: LinkedSide, name: &str) -> CoreDef {
+    CoreDef::Export(CoreExport {
+        instance: InstanceId::new(side_index(side)),
+        item: ExportItem::Name(name.to_string()),
+    })
+}
+
+fn options(side: &LinkedAdapterSide<'_>, which: LinkedSide) -> AdapterOptions {
+    AdapterOptions {
+        instance: RuntimeComponentInstanceIndex::new(side_index(which)),
+        string_encoding: side.string_encoding,
+        memory: side.memory.then(|| CoreExport {
+            instance: InstanceId::new(side_index(which)),
+            item: ExportItem::Name("memory".to_string()),
+        }),
+        memory64: side.memory64,
+        realloc: side.realloc.then(|| placeholder(which, "realloc")),
+        post_return: side.post_return.then(|| placeholder(which, "post-return")),
+    }
+}
+
+fn linked_import(def: &CoreDef) -> LinkedAdapterImport {
+    let side = |index: usize| match index {
+        0 => LinkedSide::Caller,
+        1 => LinkedSide::Callee,
+        _ => unreachable!(),
+    };
+    match def {
+        CoreDef::InstanceFlags(i) => LinkedAdapterImport::Flags(side(i.index())),
+        CoreDef::Export(CoreExport {
+            instance,
+            item: ExportItem::Name(name),
+        }) => match (side(instance.index()), name.as_str()) {
+            (which, "memory") => LinkedAdapterImport::Memory(which),
+            (which, "realloc") => LinkedAdapterImport::Realloc(which),
+            (LinkedSide::Callee, "callee") => LinkedAdapterImport::Callee,
+            (LinkedSide::Callee, "post-return") => LinkedAdapterImport::PostReturn,
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    }
+}
+
+fn memory_side(def: &CoreDef) -> LinkedSide {
+    match linked_import(def) {
+        LinkedAdapterImport::Memory(side) => side,
+        _ => unreachable!(),
+    }
+}
@@ -570,6 +570,152 @@ impl ComponentTypesBuilder {
         intern_and_fill_flat_types!(self, lists, ty)
     }
 
+    /// Adds a new resource table to the type information.
+    ///
+    /// Unlike other types, resource tables are never interned, so this always
+    /// returns a fresh index.
+    pub fn add_resource_table(&mut self, table: TypeResourceTable) -> TypeResourceTableIndex {
+        self.component_types.resource_tables.push(table)
+    }
+
+    /// Interns the function type `ty` from `types`, along with all of the
+    /// types it refers to, within this type information.
+    ///
+    /// This is used to compile adapters between components that were
+    /// translated separately. Each resource table that the function refers to
+    /// is mapped through `resource` to a table in this type information.
+    pub fn import_func_type(
+        &mut self,
+        types: &ComponentTypes,
+        ty: TypeFuncIndex,
+        resource: &mut dyn FnMut(&mut Self, TypeResourceTableIndex) -> TypeResourceTableIndex,
+    ) -> TypeFuncIndex {
+        let TypeFunc {
+            param_names,
+            params,
+            results,
+        } = &types[ty];
+        let params = self.import_tuple_type(types, *params, resource);
+        let results = self.import_tuple_type(types, *results, resource);
+        self.add_func_type(TypeFunc {
+            param_names: param_names.clone(),
+            params,
+            results,
+        })
+    }
+
+    fn import_tuple_type(
+        &mut self,
+        types: &ComponentTypes,
+        ty: TypeTupleIndex,
+        resource: &mut dyn FnMut(&mut Self, TypeResourceTableIndex) -> TypeResourceTableIndex,
+    ) -> TypeTupleIndex {
+        let ty = &types[ty];
+        let tuple = TypeTuple {
+            types: ty
+                .types
+                .iter()
+                .map(|t| self.import_interface_type(types, t, resource))
+                .collect(),
+            abi: ty.abi.clone(),
+        };
+        self.add_tuple_type(tuple)
+    }
+
+    fn import_interface_type(
+        &mut self,
+        types: &ComponentTypes,
+        ty: &InterfaceType,
+        resource: &mut dyn FnMut(&mut Self, TypeResourceTableIndex) -> TypeResourceTableIndex,
+    ) -> InterfaceType {
+        // Note that the canonical ABI information of each type doesn't depend
+        // on the indices of the types within it, so it's copied as-is.
+        match ty {
+            InterfaceType::Bool
+            | InterfaceType::S8
+            | InterfaceType::U8
+            | InterfaceType::S16
+            | InterfaceType::U16
+            | InterfaceType::S32
+            | InterfaceType::U32
+            | InterfaceType::S64
+            | InterfaceType::U64
+            | InterfaceType::Float32
+            | InterfaceType::Float64
+            | InterfaceType::Char
+            | InterfaceType::String => *ty,
+            InterfaceType::Record(i) => {
+                let ty = &types[*i];
+                let record = TypeRecord {
+                    fields: ty
+                        .fields
+                        .iter()
+                        .map(|f| RecordField {
+                            name: f.name.clone(),
+                            ty: self.import_interface_type(types, &f.ty, resource),
+                        })
+                        .collect(),
+                    abi: ty.abi.clone(),
+                };
+                InterfaceType::Record(self.add_record_type(record))
+            }
+            InterfaceType::Variant(i) => {
+                let ty = &types[*i];
+                let variant = TypeVariant {
+                    cases: ty
+                        .cases
+                        .iter()
+                        .map(|(name, t)| {
+                            let t = t
+                                .as_ref()
+                                .map(|t| self.import_interface_type(types, t, resource));
+                            (name.clone(), t)
+                        })
+                        .collect(),
+                    abi: ty.abi.clone(),
+                    info: ty.info.clone(),
+                };
+                InterfaceType::Variant(self.add_variant_type(variant))
+            }
+            InterfaceType::List(i) => {
+                let element = self.import_interface_type(types, &types[*i].element, resource);
+                InterfaceType::List(self.add_list_type(TypeList { element }))
+            }
+            InterfaceType::Tuple(i) => {
+                InterfaceType::Tuple(self.import_tuple_type(types, *i, resource))
+            }
+            InterfaceType::Flags(i) => InterfaceType::Flags(self.add_flags_type(types[*i].clone())),
+            InterfaceType::Enum(i) => InterfaceType::Enum(self.add_enum_type(types[*i].clone())),
+            InterfaceType::Option(i) => {
+                let ty = &types[*i];
+                let option = TypeOption {
+                    ty: self.import_interface_type(types, &ty.ty, resource),
+                    abi: ty.abi.clone(),
+                    info: ty.info.clone(),
+                };
+                InterfaceType::Option(self.add_option_type(option))
+            }
+            InterfaceType::Result(i) => {
+                let ty = &types[*i];
+                let result = TypeResult {
+                    ok: ty
+                        .ok
+                        .as_ref()
+                        .map(|t| self.import_interface_type(types, t, resource)),
+                    err: ty
+                        .err
+                        .as_ref()
+                        .map(|t| self.import_interface_type(types, t, resource)),
+                    abi: ty.abi.clone(),
+                    info: ty.info.clone(),
+                };
+                InterfaceType::Result(self.add_result_type(result))
+            }
+            InterfaceType::Own(i) => InterfaceType::Own(resource(self, *i)),
+            InterfaceType::Borrow(i) => InterfaceType::Borrow(resource(self, *i)),
+        }
+    }
+
     /// Returns the canonical ABI information about the specified type.
     pub fn canonical_abi(&self, ty: &InterfaceType) -> &CanonicalAbiInfo {
         self.component_types.canonical_abi(ty)
@@ -11,6 +11,13 @@ pub const HEADER_ALIGN: u32 = 8;
 /// The offset of the length field in a `VMNullArrayHeader`.
 pub const ARRAY_LENGTH_OFFSET: u32 = HEADER_SIZE;
 
+/// The offset of the bump-allocation finger within the null collector's heap
+/// data, which compiled code accesses through `VMContext::gc_heap_data`.
+pub const HEAP_DATA_NEXT_OFFSET: u32 = 0;
+
+/// The offset of the allocation limit within the null collector's heap data.
+pub const HEAP_DATA_LIMIT_OFFSET: u32 = 4;
+
 /// The layout of Wasm GC objects in the null collector.
 #[derive(Default)]
 pub struct NullTypeLayouts;
@@ -0,0 +1,59 @@
+//! Layout of the locals that instrumented code passes to the `debug_break`
+//! builtin when guest debugging is enabled.
+
+/// The size, in bytes, of the record describing each local.
+///
+/// Records are laid out contiguously, one per local in order of local index,
+/// and the buffer as a whole is 16-byte aligned.
+pub const DEBUG_LOCAL_SIZE: u32 = 32;
+
+/// The offset, within each record, of the local's value.
+///
+/// The value is stored in its native representation, and the record begins
+/// with a native-endian `u32` holding the local's [`DebugLocalKind`].
+pub const DEBUG_LOCAL_VALUE_OFFSET: u32 = 16;
+
+/// The kind of value held in a local's record.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum DebugLocalKind {
+    /// An `i32`.
+    I32 = 0,
+    /// An `i64`.
+    I64 = 1,
+    /// An `f32`, stored as its bits.
+    F32 = 2,
+    /// An `f64`, stored as its bits.
+    F64 = 3,
+    /// A `v128`.
+    V128 = 4,
+    /// A reference, whose value isn't recorded.
+    Ref = 5,
+}
+
+impl DebugLocalKind {
+    /// Returns the kind of local whose type is `ty`.
+    pub fn from_val_type(ty: wasmparser::ValType) -> DebugLocalKind {
+        match ty {
+            wasmparser::ValType::I32 => DebugLocalKind::I32,
+            wasmparser::ValType::I64 => DebugLocalKind::I64,
+            wasmparser::ValType::F32 => DebugLocalKind::F32,
+            wasmparser::ValType::F64 => DebugLocalKind::F64,
+            wasmparser::ValType::V128 => DebugLocalKind::V128,
+            wasmparser::ValType::Ref(_) => DebugLocalKind::Ref,
+        }
+    }
+
+    /// Returns the kind whose discriminant is `bits`, if any.
+    pub fn from_u32(bits: u32) -> Option<DebugLocalKind> {
+        Some(match bits {
+            0 => DebugLocalKind::I32,
+            1 => DebugLocalKind::I64,
+            2 => DebugLocalKind::F32,
+            3 => DebugLocalKind::F64,
+            4 => DebugLocalKind::V128,
+            5 => DebugLocalKind::Ref,
+            _ => return None,
+        })
+    }
+}
@@ -21,7 +21,9 @@ mod demangling;
 mod error;
 mod ext;
 mod gc;
+mod guest_debug;
 mod hostcall;
+mod memory_access;
 mod module;
 mod module_artifacts;
 mod module_types;
@@ -40,7 +42,9 @@ pub use crate::builtin::*;
 pub use crate::demangling::*;
 pub use crate::error::*;
 pub use crate::gc::*;
+pub use crate::guest_debug::*;
 pub use crate::hostcall::*;
+pub use crate::memory_access::*;
 pub use crate::module::*;
 pub use crate::module_artifacts::*;
 pub use crate::module_types::*;
@@ -0,0 +1,32 @@
+//! Encoding of the linear memory accesses that instrumented code reports to
+//! the `trace_memory_access` builtin when memory access tracing is enabled.
+
+/// The kind of a traced linear memory access.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum MemoryAccessKind {
+    /// A plain load, including the loads of SIMD instructions.
+    Load = 0,
+    /// A plain store, including the stores of SIMD instructions.
+    Store = 1,
+    /// An atomic load.
+    AtomicLoad = 2,
+    /// An atomic store.
+    AtomicStore = 3,
+    /// An atomic read-modify-write or compare-and-exchange.
+    AtomicRmw = 4,
+}
+
+impl MemoryAccessKind {
+    /// Returns the kind whose discriminant is `bits`, if any.
+    pub fn from_u32(bits: u32) -> Option<MemoryAccessKind> {
+        Some(match bits {
+            0 => MemoryAccessKind::Load,
+            1 => MemoryAccessKind::Store,
+            2 => MemoryAccessKind::AtomicLoad,
+            3 => MemoryAccessKind::AtomicStore,
+            4 => MemoryAccessKind::AtomicRmw,
+            _ => return None,
+        })
+    }
+}
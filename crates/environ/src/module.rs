@@ -343,6 +343,10 @@ pub struct Module {
     /// Number of call-indirect caches.
     pub num_call_indirect_caches: usize,
 
+    /// Number of per-function fuel counters in the `VMContext`: one for each
+    /// defined function when fuel profiling is enabled, and zero otherwise.
+    pub num_fuel_profile_counters: usize,
+
     /// Types of functions, imported and local.
     pub functions: PrimaryMap<FuncIndex, FunctionType>,
 
@@ -110,6 +110,12 @@ pub struct Metadata {
     /// Dwarf sections and the offsets at which they're stored in the
     /// ELF_WASMTIME_DWARF
     pub dwarf: Vec<(u8, Range<u64>)>,
+
+    /// The SHA-256 checksum of the original wasm module's bytes, which
+    /// identifies the module in instance snapshots.
+    ///
+    /// This is only computed when the `snapshot` feature is enabled.
+    pub checksum: Option<[u8; 32]>,
 }
 
 /// Value of a configured setting for a [`Compiler`](crate::Compiler)
@@ -82,9 +82,26 @@ define_tunables! {
         /// will be consumed every time a wasm instruction is executed.
         pub consume_fuel: bool,
 
+        /// Whether or not fuel consumed is additionally accumulated into a
+        /// counter for each defined function, stored in the `VMContext`.
+        pub fuel_profiling: bool,
+
         /// Whether or not we use epoch-based interruption.
         pub epoch_interruption: bool,
 
+        /// Whether or not generated code is instrumented to support debugging
+        /// at the level of Wasm instructions, with breakpoints and stepping.
+        pub guest_debug: bool,
+
+        /// Whether or not generated code invokes a builtin before each linear
+        /// memory access, describing the access, so it can be traced.
+        pub memory_access_tracing: bool,
+
+        /// Whether or not generated code invokes the memory access tracing
+        /// builtin before each access to a shared linear memory, for data race
+        /// detection.
+        pub race_detection: bool,
+
         /// Whether or not linear memories are allowed to be reallocated after
         /// initial allocation at runtime.
         pub memory_may_move: bool,
@@ -172,7 +189,11 @@ impl Tunables {
             generate_native_debuginfo: false,
             parse_wasm_debuginfo: true,
             consume_fuel: false,
+            fuel_profiling: false,
             epoch_interruption: false,
+            guest_debug: false,
+            memory_access_tracing: false,
+            race_detection: false,
             memory_may_move: true,
             guard_before_linear_memory: true,
             table_lazy_init: true,
@@ -232,6 +253,8 @@ pub enum Collector {
     DeferredReferenceCounting,
     /// The null collector.
     Null,
+    /// The semi-space copying collector.
+    Copying,
 }
 
 impl fmt::Display for Collector {
@@ -239,6 +262,7 @@ impl fmt::Display for Collector {
         match self {
             Collector::DeferredReferenceCounting => write!(f, "deferred reference-counting"),
             Collector::Null => write!(f, "null"),
+            Collector::Copying => write!(f, "copying"),
         }
     }
 }
@@ -33,11 +33,12 @@
 //      tables: [VMTableDefinition; module.num_defined_tables],
 //      globals: [VMGlobalDefinition; module.num_defined_globals],
 //      func_refs: [VMFuncRef; module.num_escaped_funcs],
+//      fuel_profile: [u64; module.num_fuel_profile_counters],
 // }
 
 use crate::{
-    DefinedGlobalIndex, DefinedMemoryIndex, DefinedTableIndex, FuncIndex, FuncRefIndex,
-    GlobalIndex, MemoryIndex, Module, OwnedMemoryIndex, TableIndex,
+    DefinedFuncIndex, DefinedGlobalIndex, DefinedMemoryIndex, DefinedTableIndex, FuncIndex,
+    FuncRefIndex, GlobalIndex, MemoryIndex, Module, OwnedMemoryIndex, TableIndex,
 };
 use cranelift_entity::packed_option::ReservedValue;
 
@@ -81,6 +82,9 @@ pub struct VMOffsets<P> {
     /// The number of escaped functions in the module, the size of the func_refs
     /// array.
     pub num_escaped_funcs: u32,
+    /// The number of per-function fuel counters, the size of the fuel_profile
+    /// array.
+    pub num_fuel_profile_counters: u32,
 
     // precalculated offsets of various member fields
     imported_functions: u32,
@@ -92,6 +96,7 @@ pub struct VMOffsets<P> {
     owned_memories: u32,
     defined_globals: u32,
     defined_func_refs: u32,
+    fuel_profile: u32,
     size: u32,
 }
 
@@ -186,6 +191,11 @@ pub trait PtrSize {
         self.vmruntime_limits_last_wasm_exit_pc() + self.size()
     }
 
+    /// Return the offset of the `debug_flags` field of `VMRuntimeLimits`.
+    fn vmruntime_limits_debug_flags(&self) -> u8 {
+        self.vmruntime_limits_last_wasm_entry_fp() + self.size()
+    }
+
     // Offsets within `VMMemoryDefinition`
 
     /// The offset of the `base` field.
@@ -341,6 +351,9 @@ pub struct VMOffsetsFields<P> {
     /// The number of escaped functions in the module, the size of the function
     /// references array.
     pub num_escaped_funcs: u32,
+    /// The number of per-function fuel counters, the size of the fuel profile
+    /// array.
+    pub num_fuel_profile_counters: u32,
 }
 
 impl<P: PtrSize> VMOffsets<P> {
@@ -365,6 +378,7 @@ impl<P: PtrSize> VMOffsets<P> {
             num_owned_memories,
             num_defined_globals: cast_to_u32(module.globals.len() - module.num_imported_globals),
             num_escaped_funcs: cast_to_u32(module.num_escaped_funcs),
+            num_fuel_profile_counters: cast_to_u32(module.num_fuel_profile_counters),
         })
     }
 
@@ -394,6 +408,7 @@ impl<P: PtrSize> VMOffsets<P> {
                     num_defined_memories: _,
                     num_owned_memories: _,
                     num_escaped_funcs: _,
+                    num_fuel_profile_counters: _,
 
                     // used as the initial size below
                     size,
@@ -422,6 +437,7 @@ impl<P: PtrSize> VMOffsets<P> {
         }
 
         calculate_sizes! {
+            fuel_profile: "fuel profile counters",
             defined_func_refs: "module functions",
             defined_globals: "defined globals",
             defined_tables: "defined tables",
@@ -448,6 +464,7 @@ impl<P: PtrSize> From<VMOffsetsFields<P>> for VMOffsets<P> {
             num_owned_memories: fields.num_owned_memories,
             num_defined_globals: fields.num_defined_globals,
             num_escaped_funcs: fields.num_escaped_funcs,
+            num_fuel_profile_counters: fields.num_fuel_profile_counters,
             imported_functions: 0,
             imported_tables: 0,
             imported_memories: 0,
@@ -457,6 +474,7 @@ impl<P: PtrSize> From<VMOffsetsFields<P>> for VMOffsets<P> {
             owned_memories: 0,
             defined_globals: 0,
             defined_func_refs: 0,
+            fuel_profile: 0,
             size: 0,
         };
 
@@ -511,6 +529,8 @@ impl<P: PtrSize> From<VMOffsetsFields<P>> for VMOffsets<P> {
                 ret.num_escaped_funcs,
                 ret.ptr.size_of_vm_func_ref(),
             ),
+            align(8),
+            size(fuel_profile) = cmul(ret.num_fuel_profile_counters, 8),
         }
 
         ret.size = next_field_offset;
@@ -701,6 +721,12 @@ impl<P: PtrSize> VMOffsets<P> {
         self.defined_func_refs
     }
 
+    /// The offset of the `fuel_profile` array.
+    #[inline]
+    pub fn vmctx_fuel_profile_begin(&self) -> u32 {
+        self.fuel_profile
+    }
+
     /// Return the size of the `VMContext` allocation.
     #[inline]
     pub fn size_of_vmctx(&self) -> u32 {
@@ -779,6 +805,14 @@ impl<P: PtrSize> VMOffsets<P> {
         self.vmctx_func_refs_begin() + index.as_u32() * u32::from(self.ptr.size_of_vm_func_ref())
     }
 
+    /// Return the offset to the `u64` fuel counter for the defined function
+    /// `index`.
+    #[inline]
+    pub fn vmctx_fuel_profile_counter(&self, index: DefinedFuncIndex) -> u32 {
+        assert!(index.as_u32() < self.num_fuel_profile_counters);
+        self.vmctx_fuel_profile_begin() + index.as_u32() * 8
+    }
+
     /// Return the offset to the `wasm_call` field in `*const VMFunctionBody` index `index`.
     #[inline]
     pub fn vmctx_vmfunction_import_wasm_call(&self, index: FuncIndex) -> u32 {
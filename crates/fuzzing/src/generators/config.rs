@@ -227,6 +227,7 @@ impl Config {
                 Collector::DeferredReferenceCounting => {
                     wasmtime_wast_util::Collector::DeferredReferenceCounting
                 }
+                Collector::Copying => wasmtime_wast_util::Collector::Copying,
             },
             pooling: matches!(
                 self.wasmtime.strategy,
@@ -773,6 +774,7 @@ impl Arbitrary<'_> for CompilerStrategy {
 pub enum Collector {
     DeferredReferenceCounting,
     Null,
+    Copying,
 }
 
 impl Collector {
@@ -780,6 +782,7 @@ impl Collector {
         match self {
             Collector::DeferredReferenceCounting => wasmtime::Collector::DeferredReferenceCounting,
             Collector::Null => wasmtime::Collector::Null,
+            Collector::Copying => wasmtime::Collector::Copying,
         }
     }
 }
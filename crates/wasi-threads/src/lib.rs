@@ -7,7 +7,10 @@ use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Arc;
 use std::thread;
-use wasmtime::{Caller, ExternType, InstancePre, Linker, Module, SharedMemory, Store};
+use wasmtime::{
+    AsContext, Caller, ExternType, InstancePre, Linker, Module, RaceDetector, RaceThread,
+    SharedMemory, Store,
+};
 
 // This name is a function export designated by the wasi-threads specification:
 // https://github.com/WebAssembly/wasi-threads/#detailed-design-discussion
@@ -16,17 +19,73 @@ const WASI_ENTRY_POINT: &str = "wasi_thread_start";
 pub struct WasiThreadsCtx<T> {
     instance_pre: Arc<InstancePre<T>>,
     tid: AtomicI32,
+    race_detector: Option<RaceDetector>,
 }
 
 impl<T: Clone + Send + 'static> WasiThreadsCtx<T> {
     pub fn new(module: Module, linker: Arc<Linker<T>>) -> Result<Self> {
         let instance_pre = Arc::new(linker.instantiate_pre(&module)?);
         let tid = AtomicI32::new(0);
-        Ok(Self { instance_pre, tid })
+        Ok(Self {
+            instance_pre,
+            tid,
+            race_detector: None,
+        })
+    }
+
+    /// Tracks the threads spawned through `thread-spawn` with `detector`.
+    ///
+    /// Each spawned thread is forked from the thread attached to the store
+    /// calling `thread-spawn`, so the store running the main thread should be
+    /// attached to a thread of `detector` as well. The module must be compiled
+    /// with `Config::race_detection` enabled for accesses to be checked.
+    pub fn with_race_detector(mut self, detector: RaceDetector) -> Self {
+        self.race_detector = Some(detector);
+        self
     }
 
     pub fn spawn(&self, host: T, thread_start_arg: i32) -> Result<i32> {
-        let instance_pre = self.instance_pre.clone();
+        let Some(wasi_thread_id) = self.reserve_thread_id() else {
+            return Ok(-1);
+        };
+        let race_thread = self
+            .race_detector
+            .as_ref()
+            .map(|detector| detector.thread());
+        self.start_thread(wasi_thread_id, host, thread_start_arg, race_thread)
+    }
+
+    /// Same as [`WasiThreadsCtx::spawn`], but with `parent` being the store of
+    /// the thread requesting the spawn.
+    ///
+    /// If a race detector is configured, the new thread is forked from the
+    /// thread attached to `parent`, so that everything `parent` did before the
+    /// spawn happens before anything the new thread does. `spawn` instead
+    /// creates a thread which hasn't synchronized with any other, which may
+    /// lead to races being reported for accesses the spawn actually ordered.
+    pub fn spawn_from(
+        &self,
+        parent: impl AsContext,
+        host: T,
+        thread_start_arg: i32,
+    ) -> Result<i32> {
+        let Some(wasi_thread_id) = self.reserve_thread_id() else {
+            return Ok(-1);
+        };
+        let race_thread = self
+            .race_detector
+            .as_ref()
+            .map(|detector| detector.fork(parent));
+        self.start_thread(wasi_thread_id, host, thread_start_arg, race_thread)
+    }
+
+    /// Checks that a thread can be spawned and allocates its thread ID.
+    ///
+    /// As defined in the wasi-threads specification, a spawn that fails here
+    /// should return a negative result to the guest module, so this returns
+    /// `None` after logging why.
+    fn reserve_thread_id(&self) -> Option<i32> {
+        let instance_pre = &self.instance_pre;
 
         // Check that the thread entry point is present. Why here? If we check
         // for this too early, then we cannot accept modules that do not have an
@@ -34,24 +93,31 @@ impl<T: Clone + Send + 'static> WasiThreadsCtx<T> {
         // https://github.com/bytecodealliance/wasmtime/issues/6153, checking
         // the entry point here allows wasi-threads to be compatible with more
         // modules.
-        //
-        // As defined in the wasi-threads specification, returning a negative
-        // result here indicates to the guest module that the spawn failed.
         if !has_entry_point(instance_pre.module()) {
             log::error!("failed to find a wasi-threads entry point function; expected an export with name: {WASI_ENTRY_POINT}");
-            return Ok(-1);
+            return None;
         }
         if !has_correct_signature(instance_pre.module()) {
             log::error!("the exported entry point function has an incorrect signature: expected `(i32, i32) -> ()`");
-            return Ok(-1);
+            return None;
         }
 
         let wasi_thread_id = self.next_thread_id();
         if wasi_thread_id.is_none() {
             log::error!("ran out of valid thread IDs");
-            return Ok(-1);
         }
-        let wasi_thread_id = wasi_thread_id.unwrap();
+        wasi_thread_id
+    }
+
+    /// Starts the thread with the ID returned by `reserve_thread_id`.
+    fn start_thread(
+        &self,
+        wasi_thread_id: i32,
+        host: T,
+        thread_start_arg: i32,
+        race_thread: Option<RaceThread>,
+    ) -> Result<i32> {
+        let instance_pre = self.instance_pre.clone();
 
         // Start a Rust thread running a new instance of the current module.
         let builder = thread::Builder::new().name(format!("wasi-thread-{wasi_thread_id}"));
@@ -61,6 +127,9 @@ impl<T: Clone + Send + 'static> WasiThreadsCtx<T> {
             let result = catch_unwind(AssertUnwindSafe(|| {
                 // Each new instance is created in its own store.
                 let mut store = Store::new(&instance_pre.module().engine(), host);
+                if let Some(race_thread) = race_thread {
+                    race_thread.attach(&mut store);
+                }
 
                 let instance = if instance_pre.module().engine().is_async() {
                     wasmtime_wasi::runtime::in_tokio(instance_pre.instantiate_async(&mut store))
@@ -148,7 +217,18 @@ pub fn add_to_linker<T: Clone + Send + 'static>(
             log::trace!("new thread requested via `wasi::thread_spawn` call");
             let host = caller.data().clone();
             let ctx = get_cx(caller.data_mut());
-            match ctx.spawn(host, start_arg) {
+            let Some(wasi_thread_id) = ctx.reserve_thread_id() else {
+                return -1;
+            };
+            // The context is borrowed from the caller, so fork the race
+            // detector's thread, only once the spawn is known to go ahead,
+            // before borrowing the context again to start the thread.
+            let race_thread = ctx
+                .race_detector
+                .clone()
+                .map(|detector| detector.fork(&caller));
+            let ctx = get_cx(caller.data_mut());
+            match ctx.start_thread(wasi_thread_id, host, start_arg, race_thread) {
                 Ok(thread_id) => {
                     assert!(thread_id >= 0, "thread_id = {thread_id}");
                     thread_id
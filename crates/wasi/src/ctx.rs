@@ -7,7 +7,7 @@ use crate::{
     network::{SocketAddrCheck, SocketAddrUse},
     pipe, random, stdio,
     stdio::{StdinStream, StdoutStream},
-    DirPerms, FilePerms,
+    DirPerms, FilePerms, StreamError,
 };
 use anyhow::Result;
 use cap_rand::{Rng, RngCore, SeedableRng};
@@ -17,6 +17,7 @@ use std::sync::Arc;
 use std::{future::Future, pin::Pin};
 use std::{mem, net::SocketAddr};
 use wasmtime::component::ResourceTable;
+use wasmtime::{ResourceLimiter, SharedResourceLimiter};
 
 /// Builder-style structure used to create a [`WasiCtx`].
 ///
@@ -53,6 +54,7 @@ pub struct WasiCtxBuilder {
     monotonic_clock: Box<dyn HostMonotonicClock + Send>,
     allowed_network_uses: AllowedNetworkUses,
     allow_blocking_current_thread: bool,
+    buffer_limiter: Option<SharedResourceLimiter>,
     built: bool,
 }
 
@@ -102,6 +104,7 @@ impl WasiCtxBuilder {
             monotonic_clock: monotonic_clock(),
             allowed_network_uses: AllowedNetworkUses::default(),
             allow_blocking_current_thread: false,
+            buffer_limiter: None,
             built: false,
         }
     }
@@ -206,6 +209,24 @@ impl WasiCtxBuilder {
         self
     }
 
+    /// Reports the buffers which the host allocates for the streams of this
+    /// context to `limiter`, through
+    /// [`ResourceLimiter::host_buffers_growing`].
+    ///
+    /// The buffer for each read, skip or splice, and each write permitted by
+    /// `check-write`, is as large as WebAssembly requested, and the size of
+    /// the largest buffer so far is reported. If the limiter rejects a larger
+    /// buffer, or returns an error, the stream operation traps with an error
+    /// saying so. The charge is released when the context is dropped.
+    ///
+    /// The contents of a [`MemoryOutputPipe`](crate::pipe::MemoryOutputPipe)
+    /// aren't covered by this, but may be reported to a limiter with
+    /// [`MemoryOutputPipe::with_limiter`](crate::pipe::MemoryOutputPipe::with_limiter).
+    pub fn resource_limiter(&mut self, limiter: SharedResourceLimiter) -> &mut Self {
+        self.buffer_limiter = Some(limiter);
+        self
+    }
+
     /// Appends multiple environment variables at once for this builder.
     ///
     /// All environment variables are appended to the list of environment
@@ -481,6 +502,7 @@ impl WasiCtxBuilder {
             monotonic_clock,
             allowed_network_uses,
             allow_blocking_current_thread,
+            buffer_limiter,
             built: _,
         } = mem::replace(self, Self::new());
         self.built = true;
@@ -500,6 +522,8 @@ impl WasiCtxBuilder {
             monotonic_clock,
             allowed_network_uses,
             allow_blocking_current_thread,
+            buffer_limiter,
+            buffer_peak: 0,
         }
     }
 
@@ -654,6 +678,9 @@ pub struct WasiCtx {
     pub(crate) socket_addr_check: SocketAddrCheck,
     pub(crate) allowed_network_uses: AllowedNetworkUses,
     pub(crate) allow_blocking_current_thread: bool,
+    pub(crate) buffer_limiter: Option<SharedResourceLimiter>,
+    /// The largest stream buffer size `buffer_limiter` has allowed so far.
+    pub(crate) buffer_peak: usize,
 }
 
 impl WasiCtx {
@@ -661,6 +688,37 @@ impl WasiCtx {
     pub fn builder() -> WasiCtxBuilder {
         WasiCtxBuilder::new()
     }
+
+    /// Report a buffer of `len` bytes that a stream is about to allocate to
+    /// this context's resource limiter, failing if it isn't allowed.
+    pub(crate) fn charge_stream_buffer(&mut self, len: usize) -> Result<(), StreamError> {
+        let Some(limiter) = &mut self.buffer_limiter else {
+            return Ok(());
+        };
+        if len <= self.buffer_peak {
+            return Ok(());
+        }
+        match limiter.host_buffers_growing(self.buffer_peak, len, None) {
+            Ok(true) => {
+                self.buffer_peak = len;
+                Ok(())
+            }
+            Ok(false) => Err(StreamError::Trap(anyhow::anyhow!(
+                "stream buffer of {len} bytes rejected by the resource limiter, \
+                 which has allowed buffers of up to {} bytes",
+                self.buffer_peak
+            ))),
+            Err(e) => Err(StreamError::Trap(e)),
+        }
+    }
+}
+
+impl Drop for WasiCtx {
+    fn drop(&mut self) {
+        if let Some(limiter) = &mut self.buffer_limiter {
+            limiter.host_buffers_released(self.buffer_peak, 0);
+        }
+    }
 }
 
 pub struct AllowedNetworkUses {
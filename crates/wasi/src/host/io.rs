@@ -48,6 +48,7 @@ where
 
     fn check_write(&mut self, stream: Resource<OutputStream>) -> StreamResult<u64> {
         let bytes = self.table().get_mut(&stream)?.check_write()?;
+        self.ctx().charge_stream_buffer(bytes)?;
         Ok(bytes as u64)
     }
 
@@ -127,6 +128,7 @@ where
         if len == 0 {
             return Ok(0);
         }
+        self.ctx().charge_stream_buffer(len)?;
 
         let contents = self.table().get_mut(&src)?.read(len)?;
 
@@ -156,6 +158,7 @@ where
         if len == 0 {
             return Ok(0);
         }
+        self.ctx().charge_stream_buffer(len)?;
 
         let contents = self.table().get_mut(&src)?.blocking_read(len).await?;
 
@@ -170,6 +173,19 @@ where
     }
 }
 
+impl<T> WasiImpl<T>
+where
+    T: WasiView,
+{
+    /// Convert the length of a read or skip requested by WebAssembly to the
+    /// size of the buffer to use for it, reporting it to the resource limiter.
+    fn charge_read(&mut self, len: u64) -> StreamResult<usize> {
+        let len = len.try_into().unwrap_or(usize::MAX);
+        self.ctx().charge_stream_buffer(len)?;
+        Ok(len)
+    }
+}
+
 impl<T> streams::HostInputStream for WasiImpl<T>
 where
     T: WasiView,
@@ -180,7 +196,7 @@ where
     }
 
     fn read(&mut self, stream: Resource<InputStream>, len: u64) -> StreamResult<Vec<u8>> {
-        let len = len.try_into().unwrap_or(usize::MAX);
+        let len = self.charge_read(len)?;
         let bytes = self.table().get_mut(&stream)?.read(len)?;
         debug_assert!(bytes.len() <= len);
         Ok(bytes.into())
@@ -191,14 +207,14 @@ where
         stream: Resource<InputStream>,
         len: u64,
     ) -> StreamResult<Vec<u8>> {
-        let len = len.try_into().unwrap_or(usize::MAX);
+        let len = self.charge_read(len)?;
         let bytes = self.table().get_mut(&stream)?.blocking_read(len).await?;
         debug_assert!(bytes.len() <= len);
         Ok(bytes.into())
     }
 
     fn skip(&mut self, stream: Resource<InputStream>, len: u64) -> StreamResult<u64> {
-        let len = len.try_into().unwrap_or(usize::MAX);
+        let len = self.charge_read(len)?;
         let written = self.table().get_mut(&stream)?.skip(len)?;
         Ok(written.try_into().expect("usize always fits in u64"))
     }
@@ -208,7 +224,7 @@ where
         stream: Resource<InputStream>,
         len: u64,
     ) -> StreamResult<u64> {
-        let len = len.try_into().unwrap_or(usize::MAX);
+        let len = self.charge_read(len)?;
         let written = self.table().get_mut(&stream)?.blocking_skip(len).await?;
         Ok(written.try_into().expect("usize always fits in u64"))
     }
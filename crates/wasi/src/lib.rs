@@ -204,6 +204,8 @@ pub mod runtime;
 mod stdio;
 mod stream;
 mod tcp;
+#[cfg(feature = "preview1")]
+pub mod trace;
 mod udp;
 mod write_stream;
 
@@ -13,6 +13,7 @@ use anyhow::anyhow;
 use bytes::Bytes;
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
+use wasmtime::{ResourceLimiter, SharedResourceLimiter};
 
 pub use crate::write_stream::AsyncWriteStream;
 
@@ -56,6 +57,7 @@ impl Subscribe for MemoryInputPipe {
 pub struct MemoryOutputPipe {
     capacity: usize,
     buffer: Arc<Mutex<bytes::BytesMut>>,
+    limiter: Option<SharedResourceLimiter>,
 }
 
 impl MemoryOutputPipe {
@@ -63,9 +65,26 @@ impl MemoryOutputPipe {
         MemoryOutputPipe {
             capacity,
             buffer: std::sync::Arc::new(std::sync::Mutex::new(bytes::BytesMut::new())),
+            limiter: None,
         }
     }
 
+    /// Report the growth of this pipe's contents to `limiter`, through
+    /// [`ResourceLimiter::host_buffers_growing`].
+    ///
+    /// Writes which the limiter rejects trap, in the same way as writes beyond
+    /// the pipe's capacity. The existing contents of the pipe are reported
+    /// right away, as growth from zero, and an error is returned if they're
+    /// rejected. Clones made before calling this don't report their writes.
+    pub fn with_limiter(mut self, mut limiter: SharedResourceLimiter) -> anyhow::Result<Self> {
+        let len = self.buffer.lock().unwrap().len();
+        if len > 0 && !limiter.host_buffers_growing(0, len, Some(self.capacity))? {
+            anyhow::bail!("contents of MemoryOutputPipe rejected by the resource limiter");
+        }
+        self.limiter = Some(limiter);
+        Ok(self)
+    }
+
     pub fn contents(&self) -> bytes::Bytes {
         self.buffer.lock().unwrap().clone().freeze()
     }
@@ -84,6 +103,17 @@ impl HostOutputStream for MemoryOutputPipe {
                 "write beyond capacity of MemoryOutputPipe"
             )));
         }
+        if let Some(limiter) = &mut self.limiter {
+            let len = buf.len();
+            let allowed = limiter
+                .host_buffers_growing(len, len + bytes.len(), Some(self.capacity))
+                .map_err(StreamError::Trap)?;
+            if !allowed {
+                return Err(StreamError::Trap(anyhow!(
+                    "write to MemoryOutputPipe rejected by the resource limiter"
+                )));
+            }
+        }
         buf.extend_from_slice(bytes.as_ref());
         // Always ready for writing
         Ok(())
@@ -320,6 +350,33 @@ mod test {
         (read_half, write_half)
     }
 
+    #[test]
+    fn memory_output_pipe_limiter() {
+        use wasmtime::{MemoryBudget, MemoryCategory, StoreLimitsBuilder};
+
+        let budget = MemoryBudget::new(16);
+        let limiter = SharedResourceLimiter::new(
+            StoreLimitsBuilder::new()
+                .memory_budget(budget.clone())
+                .build(),
+        );
+        let mut pipe = MemoryOutputPipe::new(1024)
+            .with_limiter(limiter.clone())
+            .unwrap();
+        pipe.write(Bytes::from_static(&[0; 10])).unwrap();
+        assert_eq!(budget.used_by(MemoryCategory::HostBuffers), 10);
+        assert!(matches!(
+            pipe.write(Bytes::from_static(&[0; 10])),
+            Err(StreamError::Trap(_))
+        ));
+        assert_eq!(pipe.contents().len(), 10);
+
+        drop(pipe);
+        assert_eq!(budget.used(), 10);
+        drop(limiter);
+        assert_eq!(budget.used(), 0);
+    }
+
     #[test_log::test(tokio::test(flavor = "multi_thread"))]
     async fn empty_read_stream() {
         let mut reader = AsyncReadStream::new(tokio::io::empty());
@@ -0,0 +1,862 @@
+//! Recording and replaying the host calls made by WebAssembly.
+//!
+//! The only sources of nondeterminism left in WebAssembly executing with
+//! [`Config::deterministic`] enabled are the functions it imports from the
+//! host. This module can wrap every function defined in a [`Linker`] so that
+//! each call made to them is logged to a trace: which function was called and
+//! what it returned. For core modules the trace also has the arguments of
+//! each call and the bytes the host wrote into the caller's linear memory,
+//! while for components it has the values the function returned as the
+//! caller received them. For WASI that covers every clock reading, random
+//! number, file read and socket operation.
+//!
+//! A trace can later be fed back with [`replay`] or [`replay_component`] to
+//! reproduce the same run bit-for-bit. While replaying, host functions aren't
+//! called at all: instead their recorded results and memory writes are
+//! applied, so side effects of the original run such as writing to a file or
+//! to stdout don't happen again. If a call is made which doesn't match the
+//! next one in the trace then the replay has diverged, and that call fails
+//! with an error.
+//!
+//! ```
+//! use wasmtime::{Config, Engine, Linker, Module, Store};
+//! use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+//! use wasmtime_wasi::WasiCtxBuilder;
+//!
+//! # fn main() -> wasmtime::Result<()> {
+//! let mut config = Config::new();
+//! config.deterministic(true);
+//! let engine = Engine::new(&config)?;
+//!
+//! let mut linker: Linker<WasiP1Ctx> = Linker::new(&engine);
+//! preview1::add_to_linker_sync(&mut linker, |t| t)?;
+//!
+//! let mut store = Store::new(&engine, WasiCtxBuilder::new().build_p1());
+//! store.set_fuel(u64::MAX)?;
+//! let trace = std::fs::File::create("run.trace")?;
+//! wasmtime_wasi::trace::record(&mut linker, &mut store, trace)?;
+//!
+//! // ... instantiate and run modules with `linker` and `store` ...
+//! # std::fs::remove_file("run.trace")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A few limitations apply:
+//!
+//! * For core modules, only functions whose parameters and results are
+//!   numbers or `v128` values can be recorded.
+//! * For core modules, memory writes are only tracked for the memory the
+//!   calling instance exports as `memory`, which is the one WASI uses, and
+//!   only when they're made through a [`wiggle`]-generated binding such as
+//!   WASI's.
+//! * For components, the arguments of calls aren't recorded, so a replay
+//!   only diverges when functions are called in a different order. Functions
+//!   are identified by their names without the versions of the packages they
+//!   belong to, so that a trace can be replayed with other patch versions of
+//!   WASI.
+//! * A host function exiting with [`I32Exit`] is replayed as the same exit,
+//!   while any other error is replayed as an error with the same message.
+//!
+//! [`Config::deterministic`]: wasmtime::Config::deterministic
+
+use crate::I32Exit;
+use anyhow::{anyhow, bail, Context, Error, Result};
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use wasmtime::component::types::ComponentItem;
+use wasmtime::component::{self, Component, Resource, ResourceAny, ResourceType};
+use wasmtime::{Caller, Engine, Extern, Func, Linker, Memory, Store, StoreContextMut, Val};
+use wiggle::WriteLog;
+
+const MAGIC: &[u8] = b"\0wasmtime-trace";
+const VERSION: u32 = 2;
+
+/// The kinds of trace, recorded in its header.
+const CORE: u8 = 0;
+const COMPONENT: u8 = 1;
+
+type Output = Arc<Mutex<Box<dyn Write + Send>>>;
+type Input = Arc<Mutex<Box<dyn Read + Send>>>;
+
+/// Redefines every function in `linker` so that calls to it are recorded to
+/// `output`.
+///
+/// The functions are instantiated within `store`, so afterwards `linker` can
+/// only be used with `store`. Each call is written to `output` once it
+/// returns, and `output` is flushed, so a trace is complete up to the last
+/// call even if the process is killed.
+pub fn record<T: 'static>(
+    linker: &mut Linker<T>,
+    store: &mut Store<T>,
+    output: impl Write + Send + 'static,
+) -> Result<()> {
+    let output = start_output(output, CORE)?;
+
+    for (module, name, func) in defined_funcs(linker, store) {
+        let ty = func.ty(&*store);
+        let recorder = Recorder {
+            output: output.clone(),
+            module: module.clone(),
+            name: name.clone(),
+        };
+        shadow(linker, |linker| {
+            linker.func_new(&module, &name, ty, move |mut caller, params, results| {
+                let log = caller
+                    .get_export("memory")
+                    .and_then(|e| e.into_memory())
+                    .map(|memory| (memory, WriteLog::new(memory.data_ptr(&caller))));
+                let result = caller.call_func(&func, params, results);
+                let writes = log.map(|(memory, log)| (memory, log.finish()));
+                recorder.write(&caller, writes, params, results, &result)?;
+                result
+            })
+        })?;
+    }
+    Ok(())
+}
+
+/// Calls `define` to redefine a function in `linker`, with shadowing allowed
+/// only for the duration of the call.
+fn shadow<T>(
+    linker: &mut Linker<T>,
+    define: impl FnOnce(&mut Linker<T>) -> Result<&mut Linker<T>>,
+) -> Result<()> {
+    let allowed = linker.shadowing_allowed();
+    linker.allow_shadowing(true);
+    let result = define(linker).map(drop);
+    linker.allow_shadowing(allowed);
+    result
+}
+
+/// Writes calls to one host function to a trace.
+struct Recorder {
+    output: Output,
+    module: String,
+    name: String,
+}
+
+impl Recorder {
+    fn write<T>(
+        &self,
+        caller: &Caller<'_, T>,
+        writes: Option<(Memory, Vec<Range<usize>>)>,
+        params: &[Val],
+        results: &[Val],
+        result: &Result<()>,
+    ) -> Result<()> {
+        let mut event = Vec::new();
+        write_str(&mut event, &self.module);
+        write_str(&mut event, &self.name);
+        write_vals(&mut event, params)?;
+        match result {
+            Ok(()) => {
+                event.push(0);
+                write_vals(&mut event, results)?;
+            }
+            Err(e) => write_error(&mut event, e),
+        }
+        match writes {
+            Some((memory, ranges)) => {
+                event.push(1);
+                write_memory_ranges(&mut event, memory.data(caller), &ranges)?;
+            }
+            None => event.push(0),
+        }
+        write_event(&self.output, &event)
+    }
+}
+
+/// Arranges for every call that components make to functions defined in
+/// `linker` to be recorded to `output`.
+///
+/// This is the equivalent of [`record`] for components. Only the functions
+/// defined in `linker` so far are recorded. Each call is written to `output`
+/// once it returns, and `output` is flushed, so a trace is complete up to the
+/// last call even if the process is killed.
+pub fn record_component<T: 'static>(
+    linker: &mut component::Linker<T>,
+    output: impl Write + Send + 'static,
+) -> Result<()> {
+    let output = start_output(output, COMPONENT)?;
+    linker.observe_host_calls(move |_, name, result| {
+        let mut event = Vec::new();
+        write_str(&mut event, &unversioned(name));
+        match result {
+            Ok(results) => {
+                event.push(0);
+                write_component_vals(&mut event, results)?;
+            }
+            Err(e) => write_error(&mut event, e),
+        }
+        write_event(&output, &event)
+    });
+    Ok(())
+}
+
+/// Redefines every function in `linker` so that calls to it are replayed
+/// from the trace in `input`, which was previously produced by [`record`].
+///
+/// The functions are instantiated within `store`, so afterwards `linker` can
+/// only be used with `store`. None of the original functions are called;
+/// they only need to be defined so that modules importing them can be
+/// instantiated.
+pub fn replay<T: 'static>(
+    linker: &mut Linker<T>,
+    store: &mut Store<T>,
+    input: impl Read + Send + 'static,
+) -> Result<()> {
+    let input = start_input(input, CORE)?;
+
+    for (module, name, func) in defined_funcs(linker, store) {
+        let ty = func.ty(&*store);
+        let input = input.clone();
+        let (module2, name2) = (module.clone(), name.clone());
+        let replay = move |mut caller: Caller<'_, T>, params: &[Val], results: &mut [Val]| {
+            let mut input = input.lock().unwrap();
+            replay_call(&mut **input, &mut caller, &module2, &name2, params, results)
+        };
+        shadow(linker, |linker| linker.func_new(&module, &name, ty, replay))?;
+    }
+    Ok(())
+}
+
+/// Defines every import of `component` in `linker` so that calls to its
+/// functions are replayed from the trace in `input`, which was previously
+/// produced by [`record_component`].
+///
+/// This is the equivalent of [`replay`] for components. None of the
+/// functions already defined in `linker` are called. Every resource type
+/// that `component` imports is defined as a new host resource type, and
+/// resources returned from functions are handles to that type which don't
+/// refer to anything on the host.
+pub fn replay_component<T: 'static>(
+    linker: &mut component::Linker<T>,
+    component: &Component,
+    input: impl Read + Send + 'static,
+) -> Result<()> {
+    let input = start_input(input, COMPONENT)?;
+    let replayer = Arc::new(ComponentReplayer {
+        input,
+        next_rep: AtomicU32::new(0),
+    });
+    let allowed = linker.shadowing_allowed();
+    linker.allow_shadowing(true);
+    let engine = linker.engine().clone();
+    let mut root = linker.root();
+    let result = component
+        .component_type()
+        .imports(&engine)
+        .try_for_each(|(name, item)| {
+            define_replayed(&mut root, &engine, name, name, item, &replayer)
+        });
+    linker.allow_shadowing(allowed);
+    result
+}
+
+/// The host resource type that resources returned from replayed functions
+/// have.
+struct ReplayedResource;
+
+/// Replays the calls made by a component from a trace.
+struct ComponentReplayer {
+    input: Input,
+    /// The representation of the next resource returned from a replayed
+    /// function.
+    next_rep: AtomicU32,
+}
+
+/// Defines the `item` that a component imports as `name`, which is `path`
+/// within its instance, so that calls to functions within it are replayed.
+fn define_replayed<T: 'static>(
+    linker: &mut component::LinkerInstance<'_, T>,
+    engine: &Engine,
+    name: &str,
+    path: &str,
+    item: ComponentItem,
+    replayer: &Arc<ComponentReplayer>,
+) -> Result<()> {
+    match item {
+        ComponentItem::ComponentFunc(_) => {
+            let replayer = replayer.clone();
+            let path = unversioned(path);
+            linker.func_new(name, move |mut store, params, results| {
+                for param in params {
+                    drop_resources(&mut store, param)?;
+                }
+                replayer.replay_call(&mut store, &path, results)
+            })
+        }
+        ComponentItem::ComponentInstance(instance) => {
+            let mut linker = linker.instance(name)?;
+            for (export, item) in instance.exports(engine) {
+                let path = format!("{path}#{export}");
+                define_replayed(&mut linker, engine, export, &path, item, replayer)?;
+            }
+            Ok(())
+        }
+        ComponentItem::Resource(_) => linker.resource(
+            name,
+            ResourceType::host::<ReplayedResource>(),
+            |_, _| Ok(()),
+        ),
+        ComponentItem::Type(_) => Ok(()),
+        ComponentItem::CoreFunc(_) | ComponentItem::Module(_) | ComponentItem::Component(_) => {
+            bail!("cannot replay calls to the import `{path}`")
+        }
+    }
+}
+
+impl ComponentReplayer {
+    fn replay_call<T>(
+        &self,
+        store: &mut StoreContextMut<'_, T>,
+        name: &str,
+        results: &mut [component::Val],
+    ) -> Result<()> {
+        let diverged = || format!("replay diverged at call to `{name}`");
+        let mut input = self.input.lock().unwrap();
+        let input = &mut **input;
+
+        let recorded_name = match read_str_or_eof(input)? {
+            Some(name) => name,
+            None => bail!("{}: the trace has ended", diverged()),
+        };
+        if recorded_name != name {
+            bail!(
+                "{}: the trace expected a call to `{recorded_name}`",
+                diverged()
+            );
+        }
+        match read_u8(input)? {
+            0 => {
+                let recorded = read_component_vals(input)?;
+                if recorded.len() != results.len() {
+                    bail!("{}: mismatched number of results", diverged());
+                }
+                for (slot, val) in results.iter_mut().zip(recorded) {
+                    *slot = self.restore_resources(store, val)?;
+                }
+                Ok(())
+            }
+            1 => Err(I32Exit(read_i32(input)?).into()),
+            2 => Err(anyhow!(read_str(input)?)),
+            n => bail!("invalid outcome {n} in trace"),
+        }
+    }
+
+    /// Replaces the placeholders for resources in `val`, as read from a trace,
+    /// with new handles to resources owned by the caller.
+    fn restore_resources<T>(
+        &self,
+        store: &mut StoreContextMut<'_, T>,
+        val: RecordedVal,
+    ) -> Result<component::Val> {
+        use component::Val as V;
+        let mut restore = |val| self.restore_resources(&mut *store, val);
+        Ok(match val {
+            RecordedVal::Val(val) => val,
+            RecordedVal::Resource => {
+                let rep = self.next_rep.fetch_add(1, Ordering::Relaxed);
+                let resource = Resource::<ReplayedResource>::new_own(rep);
+                V::Resource(ResourceAny::try_from_resource(resource, &mut *store)?)
+            }
+            RecordedVal::List(vals) => {
+                V::List(vals.into_iter().map(restore).collect::<Result<_>>()?)
+            }
+            RecordedVal::Tuple(vals) => {
+                V::Tuple(vals.into_iter().map(restore).collect::<Result<_>>()?)
+            }
+            RecordedVal::Record(fields) => V::Record(
+                fields
+                    .into_iter()
+                    .map(|(name, val)| Ok((name, restore(val)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            RecordedVal::Variant(name, val) => V::Variant(name, restore_box(val, restore)?),
+            RecordedVal::Option(val) => V::Option(restore_box(val, restore)?),
+            RecordedVal::Result(Ok(val)) => V::Result(Ok(restore_box(val, restore)?)),
+            RecordedVal::Result(Err(val)) => V::Result(Err(restore_box(val, restore)?)),
+        })
+    }
+}
+
+fn restore_box(
+    val: Option<Box<RecordedVal>>,
+    mut restore: impl FnMut(RecordedVal) -> Result<component::Val>,
+) -> Result<Option<Box<component::Val>>> {
+    Ok(match val {
+        Some(val) => Some(Box::new(restore(*val)?)),
+        None => None,
+    })
+}
+
+/// Drops all the resources within `val`, which a replayed function is passed
+/// but has no use for.
+fn drop_resources<T>(store: &mut StoreContextMut<'_, T>, val: &component::Val) -> Result<()> {
+    use component::Val as V;
+    match val {
+        V::Resource(resource) => resource.resource_drop(&mut *store),
+        V::List(vals) | V::Tuple(vals) => vals.iter().try_for_each(|v| drop_resources(store, v)),
+        V::Record(fields) => fields
+            .iter()
+            .try_for_each(|(_, v)| drop_resources(store, v)),
+        V::Variant(_, Some(v)) | V::Option(Some(v)) | V::Result(Ok(Some(v)) | Err(Some(v))) => {
+            drop_resources(store, v)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns the name of a component function with the versions of the
+/// packages within it removed, so `wasi:cli/stdin@0.2.0#get-stdin` becomes
+/// `wasi:cli/stdin#get-stdin`.
+fn unversioned(name: &str) -> String {
+    name.split('#')
+        .map(|part| part.split('@').next().unwrap())
+        .collect::<Vec<_>>()
+        .join("#")
+}
+
+/// Collects all the functions defined in `linker`, instantiating them in
+/// `store` where necessary.
+fn defined_funcs<T>(linker: &Linker<T>, store: &mut Store<T>) -> Vec<(String, String, Func)> {
+    linker
+        .iter(store)
+        .filter_map(|(module, name, item)| match item {
+            Extern::Func(func) => Some((module.to_string(), name.to_string(), func)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn replay_call<T>(
+    input: &mut dyn Read,
+    caller: &mut Caller<'_, T>,
+    module: &str,
+    name: &str,
+    params: &[Val],
+    results: &mut [Val],
+) -> Result<()> {
+    let diverged = || format!("replay diverged at call to `{module}::{name}`");
+
+    let recorded_module = match read_str_or_eof(input)? {
+        Some(module) => module,
+        None => bail!("{}: the trace has ended", diverged()),
+    };
+    let recorded_name = read_str(input)?;
+    if recorded_module != module || recorded_name != name {
+        bail!(
+            "{}: the trace expected a call to `{recorded_module}::{recorded_name}`",
+            diverged()
+        );
+    }
+    let recorded_params = read_vals(input)?;
+    let mut actual_params = Vec::new();
+    write_vals(&mut actual_params, params)?;
+    let mut expected_params = Vec::new();
+    write_vals(&mut expected_params, &recorded_params)?;
+    if actual_params != expected_params {
+        bail!(
+            "{}: called with {params:?} but the trace expected {recorded_params:?}",
+            diverged()
+        );
+    }
+
+    let outcome = match read_u8(input)? {
+        0 => {
+            let recorded = read_vals(input)?;
+            if recorded.len() != results.len() {
+                bail!("{}: mismatched number of results", diverged());
+            }
+            results.clone_from_slice(&recorded);
+            Ok(())
+        }
+        1 => Err(I32Exit(read_i32(input)?).into()),
+        2 => Err(anyhow!(read_str(input)?)),
+        n => bail!("invalid outcome {n} in trace"),
+    };
+
+    if read_u8(input)? == 1 {
+        let memory = caller
+            .get_export("memory")
+            .and_then(|e| e.into_memory())
+            .ok_or_else(|| anyhow!("{}: the caller doesn't export a memory", diverged()))?;
+        let size = usize::try_from(read_u64(input)?)?;
+        let current = memory.data_size(&caller);
+        if size > current {
+            let page_size = usize::try_from(memory.page_size(&caller))?;
+            memory.grow(&mut *caller, u64::try_from((size - current) / page_size)?)?;
+        }
+        for _ in 0..read_u32(input)? {
+            let offset = usize::try_from(read_u64(input)?)?;
+            let len = usize::try_from(read_u32(input)?)?;
+            let mut bytes = vec![0; len];
+            input.read_exact(&mut bytes)?;
+            memory.write(&mut *caller, offset, &bytes)?;
+        }
+    }
+
+    outcome
+}
+
+/// Writes the size of `memory` followed by the contents of each of the
+/// `ranges` within it.
+fn write_memory_ranges(event: &mut Vec<u8>, memory: &[u8], ranges: &[Range<usize>]) -> Result<()> {
+    event.extend_from_slice(&u64::try_from(memory.len())?.to_le_bytes());
+    event.extend_from_slice(&u32::try_from(ranges.len())?.to_le_bytes());
+    for range in ranges {
+        let bytes = memory
+            .get(range.clone())
+            .ok_or_else(|| anyhow!("host wrote beyond the end of memory"))?;
+        event.extend_from_slice(&u64::try_from(range.start)?.to_le_bytes());
+        event.extend_from_slice(&u32::try_from(bytes.len())?.to_le_bytes());
+        event.extend_from_slice(bytes);
+    }
+    Ok(())
+}
+
+/// Writes the header of a trace of the given `kind` to `output`.
+fn start_output(output: impl Write + Send + 'static, kind: u8) -> Result<Output> {
+    let mut output: Box<dyn Write + Send> = Box::new(output);
+    output.write_all(MAGIC)?;
+    output.write_all(&VERSION.to_le_bytes())?;
+    output.write_all(&[kind])?;
+    Ok(Arc::new(Mutex::new(output)))
+}
+
+/// Reads the header of `input`, checking that it's a trace of the given
+/// `kind`.
+fn start_input(input: impl Read + Send + 'static, kind: u8) -> Result<Input> {
+    let mut input: Box<dyn Read + Send> = Box::new(input);
+    let mut magic = [0; MAGIC.len()];
+    input
+        .read_exact(&mut magic)
+        .context("failed to read trace header")?;
+    if magic != MAGIC {
+        bail!("input is not a wasmtime trace");
+    }
+    let version = read_u32(&mut input)?;
+    if version != VERSION {
+        bail!("unsupported trace version {version}");
+    }
+    match read_u8(&mut input)? {
+        k if k == kind => {}
+        CORE => bail!("trace was recorded from a core module, not a component"),
+        COMPONENT => bail!("trace was recorded from a component, not a core module"),
+        k => bail!("invalid trace kind {k}"),
+    }
+    Ok(Arc::new(Mutex::new(input)))
+}
+
+fn write_event(output: &Output, event: &[u8]) -> Result<()> {
+    let mut output = output.lock().unwrap();
+    output.write_all(event)?;
+    output.flush()?;
+    Ok(())
+}
+
+fn write_error(event: &mut Vec<u8>, e: &Error) {
+    match e.downcast_ref::<I32Exit>() {
+        Some(exit) => {
+            event.push(1);
+            event.extend_from_slice(&exit.0.to_le_bytes());
+        }
+        None => {
+            event.push(2);
+            write_str(event, &format!("{e:?}"));
+        }
+    }
+}
+
+fn write_str(event: &mut Vec<u8>, s: &str) {
+    event.extend_from_slice(&u32::try_from(s.len()).unwrap().to_le_bytes());
+    event.extend_from_slice(s.as_bytes());
+}
+
+fn write_vals(event: &mut Vec<u8>, vals: &[Val]) -> Result<()> {
+    event.extend_from_slice(&u32::try_from(vals.len())?.to_le_bytes());
+    for val in vals {
+        match val {
+            Val::I32(i) => {
+                event.push(0);
+                event.extend_from_slice(&i.to_le_bytes());
+            }
+            Val::I64(i) => {
+                event.push(1);
+                event.extend_from_slice(&i.to_le_bytes());
+            }
+            Val::F32(bits) => {
+                event.push(2);
+                event.extend_from_slice(&bits.to_le_bytes());
+            }
+            Val::F64(bits) => {
+                event.push(3);
+                event.extend_from_slice(&bits.to_le_bytes());
+            }
+            Val::V128(v) => {
+                event.push(4);
+                event.extend_from_slice(&v.as_u128().to_le_bytes());
+            }
+            _ => bail!("cannot record value of type {:?}", val),
+        }
+    }
+    Ok(())
+}
+
+fn read_vals(input: &mut dyn Read) -> Result<Vec<Val>> {
+    (0..read_u32(input)?)
+        .map(|_| {
+            Ok(match read_u8(input)? {
+                0 => Val::I32(read_i32(input)?),
+                1 => Val::I64(read_u64(input)? as i64),
+                2 => Val::F32(read_u32(input)?),
+                3 => Val::F64(read_u64(input)?),
+                4 => {
+                    let mut bytes = [0; 16];
+                    input.read_exact(&mut bytes)?;
+                    Val::V128(u128::from_le_bytes(bytes).into())
+                }
+                n => bail!("invalid value type {n} in trace"),
+            })
+        })
+        .collect()
+}
+
+/// A component value read from a trace, which may contain placeholders for
+/// resources.
+enum RecordedVal {
+    Val(component::Val),
+    Resource,
+    List(Vec<RecordedVal>),
+    Tuple(Vec<RecordedVal>),
+    Record(Vec<(String, RecordedVal)>),
+    Variant(String, Option<Box<RecordedVal>>),
+    Option(Option<Box<RecordedVal>>),
+    Result(Result<Option<Box<RecordedVal>>, Option<Box<RecordedVal>>>),
+}
+
+fn write_component_vals(event: &mut Vec<u8>, vals: &[component::Val]) -> Result<()> {
+    event.extend_from_slice(&u32::try_from(vals.len())?.to_le_bytes());
+    for val in vals {
+        write_component_val(event, val)?;
+    }
+    Ok(())
+}
+
+fn write_component_val(event: &mut Vec<u8>, val: &component::Val) -> Result<()> {
+    use component::Val as V;
+    let write_opt = |event: &mut Vec<u8>, val: &Option<Box<V>>| match val {
+        Some(val) => {
+            event.push(1);
+            write_component_val(event, val)
+        }
+        None => {
+            event.push(0);
+            Ok(())
+        }
+    };
+    match val {
+        V::Bool(b) => event.extend_from_slice(&[0, u8::from(*b)]),
+        V::S8(i) => {
+            event.push(1);
+            event.extend_from_slice(&i.to_le_bytes());
+        }
+        V::U8(i) => event.extend_from_slice(&[2, *i]),
+        V::S16(i) => {
+            event.push(3);
+            event.extend_from_slice(&i.to_le_bytes());
+        }
+        V::U16(i) => {
+            event.push(4);
+            event.extend_from_slice(&i.to_le_bytes());
+        }
+        V::S32(i) => {
+            event.push(5);
+            event.extend_from_slice(&i.to_le_bytes());
+        }
+        V::U32(i) => {
+            event.push(6);
+            event.extend_from_slice(&i.to_le_bytes());
+        }
+        V::S64(i) => {
+            event.push(7);
+            event.extend_from_slice(&i.to_le_bytes());
+        }
+        V::U64(i) => {
+            event.push(8);
+            event.extend_from_slice(&i.to_le_bytes());
+        }
+        V::Float32(f) => {
+            event.push(9);
+            event.extend_from_slice(&f.to_bits().to_le_bytes());
+        }
+        V::Float64(f) => {
+            event.push(10);
+            event.extend_from_slice(&f.to_bits().to_le_bytes());
+        }
+        V::Char(c) => {
+            event.push(11);
+            event.extend_from_slice(&u32::from(*c).to_le_bytes());
+        }
+        V::String(s) => {
+            event.push(12);
+            write_str(event, s);
+        }
+        V::List(vals) => {
+            event.push(13);
+            write_component_vals(event, vals)?;
+        }
+        V::Record(fields) => {
+            event.push(14);
+            event.extend_from_slice(&u32::try_from(fields.len())?.to_le_bytes());
+            for (name, val) in fields {
+                write_str(event, name);
+                write_component_val(event, val)?;
+            }
+        }
+        V::Tuple(vals) => {
+            event.push(15);
+            write_component_vals(event, vals)?;
+        }
+        V::Variant(name, val) => {
+            event.push(16);
+            write_str(event, name);
+            write_opt(event, val)?;
+        }
+        V::Enum(name) => {
+            event.push(17);
+            write_str(event, name);
+        }
+        V::Option(val) => {
+            event.push(18);
+            write_opt(event, val)?;
+        }
+        V::Result(Ok(val)) => {
+            event.extend_from_slice(&[19, 0]);
+            write_opt(event, val)?;
+        }
+        V::Result(Err(val)) => {
+            event.extend_from_slice(&[19, 1]);
+            write_opt(event, val)?;
+        }
+        V::Flags(names) => {
+            event.push(20);
+            event.extend_from_slice(&u32::try_from(names.len())?.to_le_bytes());
+            for name in names {
+                write_str(event, name);
+            }
+        }
+        // The handle itself isn't recorded as replaying hands out new
+        // resources in the same order, which end up with the same handles.
+        V::Resource(_) => event.push(21),
+    }
+    Ok(())
+}
+
+fn read_component_vals(input: &mut dyn Read) -> Result<Vec<RecordedVal>> {
+    (0..read_u32(input)?)
+        .map(|_| read_component_val(input))
+        .collect()
+}
+
+fn read_component_val(input: &mut dyn Read) -> Result<RecordedVal> {
+    use component::Val as V;
+    fn read_opt(input: &mut dyn Read) -> Result<Option<Box<RecordedVal>>> {
+        Ok(match read_u8(input)? {
+            0 => None,
+            _ => Some(Box::new(read_component_val(input)?)),
+        })
+    }
+    let val = match read_u8(input)? {
+        0 => V::Bool(read_u8(input)? != 0),
+        1 => V::S8(read_u8(input)? as i8),
+        2 => V::U8(read_u8(input)?),
+        3 => V::S16(read_u16(input)? as i16),
+        4 => V::U16(read_u16(input)?),
+        5 => V::S32(read_i32(input)?),
+        6 => V::U32(read_u32(input)?),
+        7 => V::S64(read_u64(input)? as i64),
+        8 => V::U64(read_u64(input)?),
+        9 => V::Float32(f32::from_bits(read_u32(input)?)),
+        10 => V::Float64(f64::from_bits(read_u64(input)?)),
+        11 => V::Char(
+            char::from_u32(read_u32(input)?).ok_or_else(|| anyhow!("invalid char in trace"))?,
+        ),
+        12 => V::String(read_str(input)?),
+        13 => return Ok(RecordedVal::List(read_component_vals(input)?)),
+        14 => {
+            let fields = (0..read_u32(input)?)
+                .map(|_| Ok((read_str(input)?, read_component_val(input)?)))
+                .collect::<Result<_>>()?;
+            return Ok(RecordedVal::Record(fields));
+        }
+        15 => return Ok(RecordedVal::Tuple(read_component_vals(input)?)),
+        16 => {
+            let name = read_str(input)?;
+            return Ok(RecordedVal::Variant(name, read_opt(input)?));
+        }
+        17 => V::Enum(read_str(input)?),
+        18 => return Ok(RecordedVal::Option(read_opt(input)?)),
+        19 => {
+            return Ok(RecordedVal::Result(match read_u8(input)? {
+                0 => Ok(read_opt(input)?),
+                _ => Err(read_opt(input)?),
+            }))
+        }
+        20 => V::Flags(
+            (0..read_u32(input)?)
+                .map(|_| read_str(input))
+                .collect::<Result<_>>()?,
+        ),
+        21 => return Ok(RecordedVal::Resource),
+        n => bail!("invalid value type {n} in trace"),
+    };
+    Ok(RecordedVal::Val(val))
+}
+
+fn read_str_or_eof(input: &mut dyn Read) -> Result<Option<String>> {
+    let mut len = [0; 4];
+    match input.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let mut bytes = vec![0; usize::try_from(u32::from_le_bytes(len))?];
+    input.read_exact(&mut bytes)?;
+    Ok(Some(String::from_utf8(bytes)?))
+}
+
+fn read_str(input: &mut dyn Read) -> Result<String> {
+    read_str_or_eof(input)?.ok_or_else(|| anyhow!("unexpected end of trace"))
+}
+
+fn read_u8(input: &mut dyn Read) -> Result<u8> {
+    let mut bytes = [0; 1];
+    input.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u16(input: &mut dyn Read) -> Result<u16> {
+    let mut bytes = [0; 2];
+    input.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32(input: &mut dyn Read) -> Result<u32> {
+    let mut bytes = [0; 4];
+    input.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_i32(input: &mut dyn Read) -> Result<i32> {
+    Ok(read_u32(input)? as i32)
+}
+
+fn read_u64(input: &mut dyn Read) -> Result<u64> {
+    let mut bytes = [0; 8];
+    input.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
@@ -32,7 +32,7 @@ use wasmtime_fiber::RuntimeFiberStackCreator;
 #[cfg(feature = "runtime")]
 pub use crate::runtime::code_memory::CustomCodeMemory;
 #[cfg(feature = "pooling-allocator")]
-pub use crate::runtime::vm::MpkEnabled;
+pub use crate::runtime::vm::{MpkEnabled, ScrubPolicy};
 #[cfg(all(feature = "incremental-cache", feature = "cranelift"))]
 pub use wasmtime_environ::CacheStore;
 
@@ -164,6 +164,7 @@ pub struct Config {
     pub(crate) coredump_on_trap: bool,
     pub(crate) macos_use_mach_ports: bool,
     pub(crate) detect_host_feature: Option<fn(&str) -> Option<bool>>,
+    pub(crate) deterministic: bool,
 }
 
 /// User-provided configuration for the compiler.
@@ -270,6 +271,7 @@ impl Config {
             detect_host_feature: Some(detect_host_feature),
             #[cfg(not(feature = "std"))]
             detect_host_feature: None,
+            deterministic: false,
         };
         #[cfg(any(feature = "cranelift", feature = "winch"))]
         {
@@ -542,6 +544,96 @@ impl Config {
         self
     }
 
+    /// Configures whether the fuel consumed by each defined function is
+    /// counted separately, for profiling.
+    ///
+    /// When enabled, in addition to consuming fuel from the [`Store`] as
+    /// usual, compiled code accumulates the fuel consumed by each function in
+    /// a counter kept with its instance. The counts can be read with
+    /// [`Instance::function_fuel_profile`](crate::Instance::function_fuel_profile)
+    /// to find which functions execute the most instructions, without the
+    /// overhead or nondeterminism of a sampling profiler. Fuel consumed by a
+    /// function's callees isn't included in its own count.
+    ///
+    /// This requires [`Config::consume_fuel`] to be enabled as well.
+    ///
+    /// By default this option is `false`.
+    ///
+    /// **Note** Enabling this option is not compatible with the Winch compiler.
+    ///
+    /// [`Store`]: crate::Store
+    pub fn fuel_profiling(&mut self, enable: bool) -> &mut Self {
+        self.tunables.fuel_profiling = Some(enable);
+        self
+    }
+
+    /// Configures whether compiled code is instrumented to support debugging
+    /// at the level of WebAssembly instructions.
+    ///
+    /// When enabled, execution can stop before any WebAssembly instruction,
+    /// either at a breakpoint added with [`Store::add_breakpoint`] or while
+    /// stepping, at which point the handler configured with
+    /// [`Store::debug_handler`] is invoked with the values of the current
+    /// function's locals. This instrumentation makes generated code slower,
+    /// and much slower while any breakpoints are set or a store is stepping,
+    /// so it's intended for use only while debugging.
+    ///
+    /// By default this option is `false`.
+    ///
+    /// **Note** Enabling this option is not compatible with the Winch compiler.
+    ///
+    /// [`Store::add_breakpoint`]: crate::Store::add_breakpoint
+    /// [`Store::debug_handler`]: crate::Store::debug_handler
+    pub fn guest_debug(&mut self, enable: bool) -> &mut Self {
+        self.tunables.guest_debug = Some(enable);
+        self
+    }
+
+    /// Configures whether generated code reports each access it makes to
+    /// linear memory.
+    ///
+    /// When enabled, compiled code calls into the runtime before each load
+    /// and store, including atomic and SIMD accesses, which invokes the hook
+    /// configured with [`Store::memory_access_hook`] with the address, size,
+    /// and kind of the access and the offset of the instruction making it.
+    /// This is a building block for watchpoints and for analyses such as data
+    /// race detection in modules using shared memories. Every access becomes a
+    /// call into the host, so generated code is significantly slower, and
+    /// this is intended for use only while debugging.
+    ///
+    /// By default this option is `false`.
+    ///
+    /// **Note** Enabling this option is not compatible with the Winch compiler.
+    ///
+    /// [`Store::memory_access_hook`]: crate::Store::memory_access_hook
+    pub fn memory_access_tracing(&mut self, enable: bool) -> &mut Self {
+        self.tunables.memory_access_tracing = Some(enable);
+        self
+    }
+
+    /// Configures whether generated code reports its accesses to shared
+    /// linear memories, for use by a [`RaceDetector`].
+    ///
+    /// This is a cheaper form of [`Config::memory_access_tracing`] which only
+    /// instruments loads and stores of memories declared `shared`, leaving
+    /// accesses to other memories untouched. The accesses are reported to the
+    /// hook configured with [`Store::memory_access_hook`], which is what
+    /// [`RaceDetector`] installs for each thread it tracks. Like memory access
+    /// tracing this significantly slows down code using shared memories and
+    /// is intended for use only while debugging.
+    ///
+    /// By default this option is `false`.
+    ///
+    /// **Note** Enabling this option is not compatible with the Winch compiler.
+    ///
+    /// [`RaceDetector`]: crate::RaceDetector
+    /// [`Store::memory_access_hook`]: crate::Store::memory_access_hook
+    #[cfg(feature = "threads")]
+    pub fn race_detection(&mut self, enable: bool) -> &mut Self {
+        self.tunables.race_detection = Some(enable);
+        self
+    }
+
     /// Enables epoch-based interruption.
     ///
     /// When executing code in async mode, we sometimes want to
@@ -656,6 +748,39 @@ impl Config {
         self
     }
 
+    /// Configures whether WebAssembly execution is entirely deterministic,
+    /// given the same results from every call to the host.
+    ///
+    /// WebAssembly is deterministic apart from a few well-defined exceptions,
+    /// and this option closes off the ones that Wasmtime is able to:
+    ///
+    /// * NaN values produced by floating-point instructions are canonicalized,
+    ///   as with [`Config::cranelift_nan_canonicalization`].
+    /// * Relaxed SIMD instructions use their deterministic behavior, as with
+    ///   [`Config::relaxed_simd_deterministic`].
+    /// * Execution is interrupted with fuel, as with [`Config::consume_fuel`],
+    ///   which happens after the same amount of execution every time, rather
+    ///   than with epochs, which happens after an amount of time. Enabling
+    ///   [`Config::epoch_interruption`] as well is an error.
+    ///
+    /// These take precedence over any conflicting settings of those options.
+    /// As fuel is enabled, note that a [`Store`](crate::Store) starts with no
+    /// fuel, so some will need to be added to it before executing any code.
+    ///
+    /// What remains is nondeterminism from outside of WebAssembly: the
+    /// results of host functions, along with the exhaustion of resources such
+    /// as memory or stack space, and the interleaving of threads which share
+    /// memory. In combination with recording and replaying the results of
+    /// host functions, this can be used to reproduce an execution exactly.
+    ///
+    /// By default this option is `false`.
+    ///
+    /// **Note** Enabling this option is not compatible with the Winch compiler.
+    pub fn deterministic(&mut self, enable: bool) -> &mut Self {
+        self.deterministic = enable;
+        self
+    }
+
     /// Configures the maximum amount of stack space available for
     /// executing WebAssembly code.
     ///
@@ -2132,6 +2257,33 @@ impl Config {
         {
             tunables.winch_callable = self.compiler_config.strategy == Some(Strategy::Winch);
         }
+        if tunables.guest_debug && tunables.winch_callable {
+            bail!("guest debugging is not supported with the Winch compiler");
+        }
+        if tunables.memory_access_tracing && tunables.winch_callable {
+            bail!("memory access tracing is not supported with the Winch compiler");
+        }
+        if tunables.race_detection && tunables.winch_callable {
+            bail!("race detection is not supported with the Winch compiler");
+        }
+        if self.deterministic {
+            if tunables.winch_callable {
+                bail!("deterministic execution is not supported with the Winch compiler");
+            }
+            if tunables.epoch_interruption {
+                bail!("epoch interruption cannot be enabled with deterministic execution");
+            }
+            tunables.consume_fuel = true;
+            tunables.relaxed_simd_deterministic = true;
+        }
+        if tunables.fuel_profiling {
+            if !tunables.consume_fuel {
+                bail!("fuel profiling requires fuel consumption to be enabled");
+            }
+            if tunables.winch_callable {
+                bail!("fuel profiling is not supported with the Winch compiler");
+            }
+        }
 
         tunables.collector = if features.gc_types() {
             #[cfg(feature = "gc")]
@@ -2140,6 +2292,7 @@ impl Config {
                 Some(match self.collector.try_not_auto()? {
                     Collector::DeferredReferenceCounting => EnvCollector::DeferredReferenceCounting,
                     Collector::Null => EnvCollector::Null,
+                    Collector::Copying => EnvCollector::Copying,
                     Collector::Auto => unreachable!(),
                 })
             }
@@ -2196,6 +2349,17 @@ impl Config {
         }
     }
 
+    /// Returns whether linear memories are initialized lazily with
+    /// `userfaultfd`, see [`PoolingAllocationConfig::userfaultfd`].
+    #[cfg(feature = "runtime")]
+    pub(crate) fn userfaultfd(&self) -> bool {
+        match &self.allocation_strategy {
+            #[cfg(feature = "pooling-allocator")]
+            InstanceAllocationStrategy::Pooling(config) => config.config.userfaultfd,
+            _ => false,
+        }
+    }
+
     #[cfg(feature = "runtime")]
     pub(crate) fn build_gc_runtime(&self) -> Result<Option<Arc<dyn GcRuntime>>> {
         if !self.features().gc_types() {
@@ -2207,7 +2371,7 @@ impl Config {
 
         #[cfg(feature = "gc")]
         #[cfg_attr(
-            not(any(feature = "gc-null", feature = "gc-drc")),
+            not(any(feature = "gc-null", feature = "gc-drc", feature = "gc-copying")),
             allow(unused_variables, unreachable_code)
         )]
         {
@@ -2226,6 +2390,13 @@ impl Config {
                 #[cfg(not(feature = "gc-null"))]
                 Collector::Null => unreachable!(),
 
+                #[cfg(feature = "gc-copying")]
+                Collector::Copying => {
+                    Arc::new(crate::runtime::vm::CopyingCollector::default()) as Arc<dyn GcRuntime>
+                }
+                #[cfg(not(feature = "gc-copying"))]
+                Collector::Copying => unreachable!(),
+
                 Collector::Auto => unreachable!(),
             }))
         }
@@ -2295,6 +2466,12 @@ impl Config {
             .flags
             .insert("enable_probestack".into());
 
+        if self.deterministic {
+            self.compiler_config
+                .settings
+                .insert("enable_nan_canonicalization".into(), "true".into());
+        }
+
         // The current wasm multivalue implementation depends on this.
         // FIXME(#9510) handle this in wasmtime-cranelift instead.
         self.compiler_config
@@ -2594,8 +2771,9 @@ impl Strategy {
 ///
 /// | Collector                   | Collects Garbage[^1] | Latency[^2] | Throughput[^3] | Allocation Speed[^4] | Heap Utilization[^5] |
 /// |-----------------------------|----------------------|-------------|----------------|----------------------|----------------------|
-/// | `DeferredReferenceCounting` | Yes, but not cycles  | 🙂         | 🙁             | 😐                   | 😐                  |
+/// | `DeferredReferenceCounting` | Yes                  | 🙂         | 🙁             | 😐                   | 😐                  |
 /// | `Null`                      | No                   | 🙂         | 🙂             | 🙂                   | 🙂                  |
+/// | `Copying`                   | Yes                  | 🙁         | 😐             | 🙂                   | 🙁                  |
 ///
 /// [^1]: Whether or not the collector is capable of collecting garbage and cyclic garbage.
 ///
@@ -2643,8 +2821,12 @@ pub enum Collector {
     /// refcount-increment and -decrement operations. The cost is the increased
     /// latency associated with tracing the stack.
     ///
-    /// This collector cannot currently collect cycles; they will leak until the
-    /// GC heap's store is dropped.
+    /// Reference counting alone cannot collect cycles, so this collector
+    /// remembers the objects whose reference count was decremented without
+    /// reaching zero, and occasionally runs a backup cycle collector that only
+    /// visits the objects reachable from them.
+    ///
+    /// Individual objects must be smaller than 64 MiB with this collector.
     DeferredReferenceCounting,
 
     /// The null collector.
@@ -2660,6 +2842,20 @@ pub enum Collector {
     /// collectors, as this collector imposes as close to zero throughput and
     /// latency overhead as possible.
     Null,
+
+    /// The semi-space copying collector.
+    ///
+    /// This collector splits its GC heap into two halves and bump allocates
+    /// objects in the active half. When the active half fills up, it traces
+    /// the live objects from the roots and copies them into the other half,
+    /// which then becomes the active half. Everything left behind is garbage,
+    /// including cycles.
+    ///
+    /// Allocation is fast and the collector does not need any GC barriers, but
+    /// only half of the GC heap is usable at any given time and every
+    /// collection pauses the Wasm program for time proportional to the amount
+    /// of live data.
+    Copying,
 }
 
 impl Default for Collector {
@@ -2674,6 +2870,8 @@ impl Collector {
             Collector::Auto => {
                 if cfg!(feature = "gc-drc") {
                     Some(Collector::DeferredReferenceCounting)
+                } else if cfg!(feature = "gc-copying") {
+                    Some(Collector::Copying)
                 } else if cfg!(feature = "gc-null") {
                     Some(Collector::Null)
                 } else {
@@ -2703,12 +2901,20 @@ impl Collector {
                  the `gc-null` feature was not enabled at compile time",
             ),
 
+            #[cfg(feature = "gc-copying")]
+            Some(c @ Collector::Copying) => Ok(c),
+            #[cfg(not(feature = "gc-copying"))]
+            Some(Collector::Copying) => bail!(
+                "cannot create an engine using the copying collector because \
+                 the `gc-copying` feature was not enabled at compile time",
+            ),
+
             Some(Collector::Auto) => unreachable!(),
 
             None => bail!(
                 "cannot create an engine with GC support when none of the \
                  collectors are available; enable one of the following \
-                 features: `gc-drc`, `gc-null`",
+                 features: `gc-drc`, `gc-null`, `gc-copying`",
             ),
         }
     }
@@ -3031,6 +3237,46 @@ impl PoolingAllocationConfig {
         self
     }
 
+    /// Configures how the memory of freed slots is scrubbed before the slots
+    /// are reused, possibly by an instance of an unrelated module.
+    ///
+    /// With the default policy, [`ScrubPolicy::Reset`], linear memories are
+    /// reset to their module's initial image and tables to null, which is all
+    /// that is required for correctness. GC heaps are only logically reset,
+    /// so their unallocated contents may still hold a previous store's
+    /// objects, and async stacks are only zeroed if
+    /// [`PoolingAllocationConfig::async_stack_zeroing`] is enabled.
+    ///
+    /// [`ScrubPolicy::Zero`] additionally zeroes GC heaps and async stacks,
+    /// and [`ScrubPolicy::Decommit`] further keeps nothing resident: the
+    /// `*_keep_resident` options and
+    /// [`PoolingAllocationConfig::decommit_batch_size`] are ignored, and all
+    /// memory written by an instance is released back to the operating
+    /// system when it is deallocated.
+    ///
+    /// This defaults to [`ScrubPolicy::Reset`].
+    pub fn scrub_policy(&mut self, policy: ScrubPolicy) -> &mut Self {
+        self.config.scrub_policy = policy;
+        self
+    }
+
+    /// Configures whether slots are checked to have been scrubbed when they
+    /// are reused.
+    ///
+    /// When enabled, the pooling allocator reads the previously accessible
+    /// contents of each linear memory, table, and, as guaranteed by the
+    /// [`PoolingAllocationConfig::scrub_policy`], async stack and GC heap
+    /// slot before handing it out again, and panics if any byte is not zero.
+    /// The contents of a linear memory's initialization image are not
+    /// checked. This is intended for testing and debugging, as it touches all
+    /// of a slot's memory on every allocation.
+    ///
+    /// This defaults to `false`.
+    pub fn verify_scrubbing(&mut self, enable: bool) -> &mut Self {
+        self.config.verify_scrubbing = enable;
+        self
+    }
+
     /// The maximum number of concurrent component instances supported (default
     /// is `1000`).
     ///
@@ -3323,6 +3569,14 @@ impl PoolingAllocationConfig {
     /// this coloring/striping behavior. For example embeddings might want to
     /// reduce the default 4G allowance to 128M.
     ///
+    /// When memories are striped, the pool's table and GC heap slots are
+    /// colored with the same keys. Each store is assigned one key, and while
+    /// its WebAssembly executes only that store's memories, tables and GC heap
+    /// are accessible, in addition to the guard regions that already protect
+    /// them. Like memories, each stripe then holds an equal share of the
+    /// pool's [`total_tables`](PoolingAllocationConfig::total_tables) and
+    /// `total_gc_heaps`.
+    ///
     /// MPK is only available on Linux (called `pku` there) and recent x86
     /// systems; we check for MPK support at runtime by examining the `CPUID`
     /// register. This configuration setting can be in three states:
@@ -3375,6 +3629,32 @@ impl PoolingAllocationConfig {
         crate::runtime::vm::mpk::is_supported()
     }
 
+    /// Configures whether linear memories are initialized lazily with
+    /// `userfaultfd` (default is `false`).
+    ///
+    /// This option is only supported on Linux, and creating an
+    /// [`Engine`](crate::Engine) with it enabled on other platforms will fail. The kernel must also
+    /// allow this process to create a `userfaultfd`, which for unprivileged
+    /// processes may require the `vm.unprivileged_userfaultfd` sysctl.
+    ///
+    /// When enabled, instantiating a module doesn't copy its data segments
+    /// into linear memory, nor does it map a copy-on-write image of them as
+    /// [`Config::memory_init_cow`] would. Instead a background thread
+    /// populates each page of a linear memory the first time it is accessed,
+    /// copying the page's contents from the module's data segments, so that
+    /// instantiating even modules with very large initialized heaps takes
+    /// constant time. Memories are reset when deallocated by releasing all of
+    /// their pages with `madvise`, which means that
+    /// [`PoolingAllocationConfig::linear_memory_keep_resident`] has no effect.
+    ///
+    /// Only memories whose data segments can be laid out statically, see
+    /// [`Config::memory_init_cow`], are initialized lazily. Others are
+    /// initialized eagerly as usual.
+    pub fn userfaultfd(&mut self, enable: bool) -> &mut Self {
+        self.config.userfaultfd = enable;
+        self
+    }
+
     /// The maximum number of concurrent GC heaps supported (default is `1000`).
     ///
     /// This value has a direct impact on the amount of memory allocated by the
@@ -367,7 +367,11 @@ impl Metadata<'_> {
             generate_native_debuginfo,
             parse_wasm_debuginfo,
             consume_fuel,
+            fuel_profiling,
             epoch_interruption,
+            guest_debug,
+            memory_access_tracing,
+            race_detection,
             memory_may_move,
             guard_before_linear_memory,
             table_lazy_init,
@@ -410,11 +414,19 @@ impl Metadata<'_> {
             "WebAssembly backtrace support",
         )?;
         Self::check_bool(consume_fuel, other.consume_fuel, "fuel support")?;
+        Self::check_bool(fuel_profiling, other.fuel_profiling, "fuel profiling")?;
         Self::check_bool(
             epoch_interruption,
             other.epoch_interruption,
             "epoch interruption",
         )?;
+        Self::check_bool(guest_debug, other.guest_debug, "guest debugging")?;
+        Self::check_bool(
+            memory_access_tracing,
+            other.memory_access_tracing,
+            "memory access tracing",
+        )?;
+        Self::check_bool(race_detection, other.race_detection, "race detection")?;
         Self::check_bool(memory_may_move, other.memory_may_move, "memory may move")?;
         Self::check_bool(
             guard_before_linear_memory,
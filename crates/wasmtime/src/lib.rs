@@ -233,6 +233,11 @@
 //!   a core dump when a trap happens. This can be configured via
 //!   [`Config::coredump_on_trap`].
 //!
+//! * `snapshot` - Enabled by default, this will provide support for taking
+//!   snapshots of instances with [`Instance::snapshot`] and restoring them with
+//!   [`InstanceSnapshot`]. This makes compiling a module also compute a
+//!   checksum of its original Wasm bytes, which identifies it in snapshots.
+//!
 //! * `addr2line` - Enabled by default, this feature configures whether traps
 //!   will attempt to parse DWARF debug information and convert WebAssembly
 //!   addresses to source filenames and line numbers.
@@ -34,13 +34,19 @@ pub(crate) mod code_memory;
 pub(crate) mod debug;
 pub(crate) mod externals;
 pub(crate) mod gc;
+pub(crate) mod guest_debug;
 pub(crate) mod instance;
+#[cfg(feature = "std")]
+pub(crate) mod instance_pre_pool;
 pub(crate) mod instantiate;
 pub(crate) mod limits;
 pub(crate) mod linker;
 pub(crate) mod memory;
+pub(crate) mod memory_trace;
 pub(crate) mod module;
 pub(crate) mod resources;
+#[cfg(feature = "snapshot")]
+pub(crate) mod snapshot;
 pub(crate) mod store;
 pub(crate) mod trampoline;
 pub(crate) mod trap;
@@ -70,18 +76,28 @@ pub use code_memory::CodeMemory;
 pub use externals::*;
 pub use func::*;
 pub use gc::*;
-pub use instance::{Instance, InstancePre};
+pub use guest_debug::{DebugFrame, DebugInterruptHandle, DebugStep};
+pub use instance::{FunctionFuel, Instance, InstancePre};
+#[cfg(feature = "std")]
+pub use instance_pre_pool::{InstancePrePool, PooledInstance};
 pub use instantiate::CompiledModule;
 pub use limits::*;
 pub use linker::*;
 pub use memory::*;
+pub use memory_trace::{MemoryAccess, MemoryAccessKind};
 pub use module::{Module, ModuleExport};
 pub use resources::*;
+#[cfg(feature = "snapshot")]
+pub use snapshot::InstanceSnapshot;
 #[cfg(all(feature = "async", feature = "call-hook"))]
 pub use store::CallHookHandler;
+#[cfg(feature = "std")]
+pub use store::StoreStats;
 pub use store::{
     AsContext, AsContextMut, CallHook, Store, StoreContext, StoreContextMut, UpdateDeadline,
 };
+#[cfg(feature = "async")]
+pub use store::{ResumableCall, Suspended};
 pub use trap::*;
 pub use types::*;
 pub use v128::V128;
@@ -102,6 +118,11 @@ pub(crate) mod stack;
 #[cfg(feature = "async")]
 pub use stack::*;
 
+#[cfg(feature = "threads")]
+mod race_detector;
+#[cfg(feature = "threads")]
+pub use race_detector::{DataRace, RaceDetector, RaceThread, RacingAccess};
+
 #[cfg(feature = "coredump")]
 mod coredump;
 #[cfg(feature = "coredump")]
@@ -122,6 +143,8 @@ fn _assertions_runtime() {
     _assert_send_and_sync::<Instance>();
     _assert_send_and_sync::<InstancePre<()>>();
     _assert_send_and_sync::<InstancePre<*mut u8>>();
+    #[cfg(feature = "std")]
+    _assert_send_and_sync::<InstancePrePool<()>>();
     _assert_send_and_sync::<Linker<()>>();
     _assert_send_and_sync::<Linker<*mut u8>>();
     _assert_send_and_sync::<Module>();
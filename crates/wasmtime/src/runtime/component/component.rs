@@ -448,6 +448,11 @@ impl Component {
         &self.inner.static_modules[idx]
     }
 
+    #[cfg(feature = "profiling")]
+    pub(crate) fn static_modules(&self) -> impl ExactSizeIterator<Item = &Module> {
+        self.inner.static_modules.values()
+    }
+
     #[inline]
     pub(crate) fn types(&self) -> &Arc<ComponentTypes> {
         self.inner.component_types()
@@ -11,11 +11,13 @@ use alloc::sync::Arc;
 use core::mem::{self, MaybeUninit};
 use core::ptr::NonNull;
 use wasmtime_environ::component::{
-    CanonicalOptions, ComponentTypes, CoreDef, InterfaceType, RuntimeComponentInstanceIndex,
-    TypeFuncIndex, TypeTuple, MAX_FLAT_PARAMS, MAX_FLAT_RESULTS,
+    CanonicalOptions, ComponentTypes, CoreDef, ExportIndex, InterfaceType,
+    RuntimeComponentInstanceIndex, TypeFuncIndex, TypeTuple, MAX_FLAT_PARAMS, MAX_FLAT_RESULTS,
 };
 
 mod host;
+#[cfg(any(feature = "cranelift", feature = "winch"))]
+mod linked;
 mod options;
 mod typed;
 pub use self::host::*;
@@ -48,6 +50,8 @@ pub struct FuncData {
     component_instance: RuntimeComponentInstanceIndex,
     post_return: Option<ExportFunction>,
     post_return_arg: Option<ValRaw>,
+    #[cfg_attr(not(feature = "coredump"), allow(dead_code))]
+    export_index: ExportIndex,
 }
 
 impl Func {
@@ -58,6 +62,7 @@ impl Func {
         ty: TypeFuncIndex,
         func: &CoreDef,
         options: &CanonicalOptions,
+        export_index: ExportIndex,
     ) -> Func {
         let export = match data.lookup_def(store, func) {
             Export::Function(f) => f,
@@ -82,6 +87,7 @@ impl Func {
             component_instance,
             post_return,
             post_return_arg: None,
+            export_index,
         }))
     }
 
@@ -313,7 +319,7 @@ impl Func {
             .await?
     }
 
-    fn call_impl(
+    pub(crate) fn call_impl(
         &self,
         mut store: impl AsContextMut,
         params: &[Val],
@@ -409,6 +415,8 @@ impl Func {
             instance,
             component_instance,
             ty,
+            #[cfg(feature = "coredump")]
+            export_index,
             ..
         } = store.0[self.0];
 
@@ -467,14 +475,32 @@ impl Func {
             // wasm function we're calling. Note that this latter point relies
             // on the correctness of this module and `ComponentType`
             // implementations, hence `ComponentType` being an `unsafe` trait.
-            crate::Func::call_unchecked_raw(
+            //
+            // If this call traps then a coredump may be captured which
+            // records which lifted functions were being called at the time.
+            #[cfg(feature = "coredump")]
+            let record_call = store.0.engine().config().coredump_on_trap;
+            #[cfg(feature = "coredump")]
+            if record_call {
+                let instance = store.0[self.0].instance;
+                store
+                    .0
+                    .component_call_stack_mut()
+                    .push((instance, export_index));
+            }
+            let result = crate::Func::call_unchecked_raw(
                 store,
                 export.func_ref,
                 core::ptr::slice_from_raw_parts_mut(
                     space.as_mut_ptr().cast(),
                     mem::size_of_val(space) / mem::size_of::<ValRaw>(),
                 ),
-            )?;
+            );
+            #[cfg(feature = "coredump")]
+            if record_call {
+                store.0.component_call_stack_mut().pop();
+            }
+            result?;
 
             // Note that `.assume_init_ref()` here is unsafe but we're relying
             // on the correctness of the structure of `LowerReturn` and the
@@ -570,7 +596,7 @@ impl Func {
         store.on_fiber(|store| self.post_return_impl(store)).await?
     }
 
-    fn post_return_impl(&self, mut store: impl AsContextMut) -> Result<()> {
+    pub(crate) fn post_return_impl(&self, mut store: impl AsContextMut) -> Result<()> {
         let mut store = store.as_context_mut();
         let data = &mut store.0[self.0];
         let instance = data.instance;
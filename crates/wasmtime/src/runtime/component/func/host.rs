@@ -1,26 +1,51 @@
+#[cfg(any(feature = "cranelift", feature = "winch"))]
+use crate::component::func::linked::{LinkedCaller, LinkedFunc};
 use crate::component::func::{LiftContext, LowerContext, Options};
+use crate::component::linker::release_borrows;
 use crate::component::matching::InstanceType;
 use crate::component::storage::slice_to_storage_mut;
+#[cfg(any(feature = "cranelift", feature = "winch"))]
+use crate::component::Func;
 use crate::component::{ComponentNamedList, ComponentType, Lift, Lower, Val};
 use crate::prelude::*;
 use crate::runtime::vm::component::{
     ComponentInstance, InstanceFlags, VMComponentContext, VMLowering, VMLoweringCallee,
 };
 use crate::runtime::vm::{VMFuncRef, VMMemoryDefinition, VMOpaqueContext};
+use crate::store::StoreId;
+#[cfg(any(feature = "cranelift", feature = "winch"))]
+use crate::store::StoreOpaque;
 use crate::{AsContextMut, CallHook, StoreContextMut, ValRaw};
 use alloc::sync::Arc;
 use core::any::Any;
 use core::mem::{self, MaybeUninit};
 use core::ptr::NonNull;
 use wasmtime_environ::component::{
-    CanonicalAbiInfo, ComponentTypes, InterfaceType, StringEncoding, TypeFuncIndex,
+    CanonicalAbiInfo, ComponentTypes, InterfaceType, StringEncoding, TypeFunc, TypeFuncIndex,
     MAX_FLAT_PARAMS, MAX_FLAT_RESULTS,
 };
 
 pub struct HostFunc {
     entrypoint: VMLoweringCallee,
-    typecheck: Box<dyn (Fn(TypeFuncIndex, &InstanceType<'_>) -> Result<()>) + Send + Sync>,
-    func: Box<dyn Any + Send + Sync>,
+    typecheck: Arc<dyn (Fn(TypeFuncIndex, &InstanceType<'_>) -> Result<()>) + Send + Sync>,
+    func: Arc<dyn Any + Send + Sync>,
+    /// A `HostCallObserver<T>` to call after each call, as configured with
+    /// `Linker::observe_host_calls`.
+    observer: Option<Box<dyn Any + Send + Sync>>,
+    /// The store that this function is bound to, if it can only be called
+    /// from within one store, as set by `HostFunc::bound_to_store`.
+    store: Option<StoreId>,
+}
+
+/// The type of the closure given to `Linker::observe_host_calls`.
+pub(crate) type ObserverFn<T> =
+    dyn Fn(StoreContextMut<'_, T>, &str, Result<&[Val], &Error>) -> Result<()> + Send + Sync;
+
+/// An observer of calls to a particular host function, along with the name
+/// it's reported under.
+struct HostCallObserver<T> {
+    name: String,
+    func: Arc<ObserverFn<T>>,
 }
 
 impl HostFunc {
@@ -33,8 +58,10 @@ impl HostFunc {
         let entrypoint = Self::entrypoint::<T, F, P, R>;
         Arc::new(HostFunc {
             entrypoint,
-            typecheck: Box::new(typecheck::<P, R>),
-            func: Box::new(func),
+            typecheck: Arc::new(typecheck::<P, R>),
+            func: Arc::new(func),
+            observer: None,
+            store: None,
         })
     }
 
@@ -54,8 +81,9 @@ impl HostFunc {
         P: ComponentNamedList + Lift + 'static,
         R: ComponentNamedList + Lower + 'static,
     {
-        let data = data as *const F;
         unsafe {
+            let host = &*(data as *const HostFunc);
+            let func = &*Arc::as_ptr(&host.func).cast::<F>();
             call_host_and_handle_result::<T>(cx, |instance, types, store| {
                 call_host::<_, _, _, _>(
                     instance,
@@ -67,7 +95,8 @@ impl HostFunc {
                     realloc,
                     StringEncoding::from_u8(string_encoding).unwrap(),
                     core::slice::from_raw_parts_mut(storage, storage_len),
-                    |store, args| (*data)(store, args),
+                    host.observer::<T>(),
+                    |store, args| func(store, args),
                 )
             })
         }
@@ -82,17 +111,82 @@ impl HostFunc {
             // This function performs dynamic type checks and subsequently does
             // not need to perform up-front type checks. Instead everything is
             // dynamically managed at runtime.
-            typecheck: Box::new(move |_expected_index, _expected_types| Ok(())),
-            func: Box::new(func),
+            typecheck: Arc::new(move |_expected_index, _expected_types| Ok(())),
+            func: Arc::new(func),
+            observer: None,
+            store: None,
+        })
+    }
+
+    /// Creates a function which calls `func`, a function exported from a
+    /// component instance in `store`, through a fused adapter compiled for
+    /// each component that imports it.
+    #[cfg(any(feature = "cranelift", feature = "winch"))]
+    pub(crate) fn new_linked<T>(store: &StoreOpaque, func: Func) -> Arc<HostFunc> {
+        let func = Arc::new(LinkedFunc::new(store, func));
+        Arc::new(HostFunc {
+            entrypoint: linked_entrypoint::<T>,
+            typecheck: {
+                let func = func.clone();
+                Arc::new(move |expected_index, expected_types| {
+                    func.typecheck(expected_index, expected_types)
+                })
+            },
+            func,
+            observer: None,
+            store: None,
         })
     }
 
+    /// Returns a copy of this function which calls `observer` after each call
+    /// to it, reporting it as `name`.
+    pub(crate) fn observed<T: 'static>(
+        &self,
+        name: String,
+        observer: Arc<ObserverFn<T>>,
+    ) -> Arc<HostFunc> {
+        Arc::new(HostFunc {
+            entrypoint: self.entrypoint,
+            typecheck: self.typecheck.clone(),
+            func: self.func.clone(),
+            observer: Some(Box::new(HostCallObserver {
+                name,
+                func: observer,
+            })),
+            store: self.store,
+        })
+    }
+
+    /// Marks this function as only being callable from components
+    /// instantiated within the store `store`.
+    pub(crate) fn bound_to_store(mut self: Arc<Self>, store: StoreId) -> Arc<HostFunc> {
+        Arc::get_mut(&mut self)
+            .expect("host function bound to a store before being shared")
+            .store = Some(store);
+        self
+    }
+
+    /// Returns the store that this function is bound to, if any.
+    pub(crate) fn store(&self) -> Option<StoreId> {
+        self.store
+    }
+
+    /// Returns this function's observer, if any.
+    ///
+    /// This is unsafe as `T` must be the type of the store that the observer
+    /// was created for, which is asserted by the entrypoints in the same way
+    /// as for the type of `func`.
+    unsafe fn observer<T>(&self) -> Option<&HostCallObserver<T>> {
+        let observer = self.observer.as_deref()?;
+        Some(&*(observer as *const (dyn Any + Send + Sync)).cast::<HostCallObserver<T>>())
+    }
+
     pub fn typecheck(&self, ty: TypeFuncIndex, types: &InstanceType<'_>) -> Result<()> {
         (self.typecheck)(ty, types)
     }
 
     pub fn lowering(&self) -> VMLowering {
-        let data = &*self.func as *const (dyn Any + Send + Sync) as *mut u8;
+        let data = self as *const HostFunc as *mut u8;
         VMLowering {
             callee: self.entrypoint,
             data,
@@ -143,6 +237,7 @@ unsafe fn call_host<T, Params, Return, F>(
     realloc: *mut VMFuncRef,
     string_encoding: StringEncoding,
     storage: &mut [MaybeUninit<ValRaw>],
+    observer: Option<&HostCallObserver<T>>,
     closure: F,
 ) -> Result<()>
 where
@@ -195,6 +290,7 @@ where
     // trivially DCE'd by LLVM. Perhaps one day with enough const programming in
     // Rust we can make monomorphizations of this function codegen only one
     // branch, but today is not that day.
+    let raw_storage = storage as *mut [MaybeUninit<ValRaw>];
     let mut storage: Storage<'_, Params, Return> = if Params::flatten_count() <= MAX_FLAT_PARAMS {
         if Return::flatten_count() <= MAX_FLAT_RESULTS {
             Storage::Direct(slice_to_storage_mut(storage))
@@ -212,12 +308,27 @@ where
     lift.enter_call();
     let params = storage.lift_params(&mut lift, param_tys)?;
 
-    let ret = closure(cx.as_context_mut(), params)?;
+    let ret = match closure(cx.as_context_mut(), params) {
+        Ok(ret) => ret,
+        Err(e) => return Err(observe_error(observer, cx, e)),
+    };
     flags.set_may_leave(false);
     let mut lower = LowerContext::new(cx, &options, types, instance);
     storage.lower_results(&mut lower, result_tys, ret)?;
     flags.set_may_leave(true);
 
+    if let Some(observer) = observer {
+        observe_results(
+            observer,
+            lower.store.as_context_mut(),
+            &options,
+            types,
+            instance,
+            ty,
+            &*raw_storage,
+        )?;
+    }
+
     lower.exit_call()?;
 
     return Ok(());
@@ -321,6 +432,7 @@ unsafe fn call_host_dynamic<T, F>(
     realloc: *mut VMFuncRef,
     string_encoding: StringEncoding,
     storage: &mut [MaybeUninit<ValRaw>],
+    observer: Option<&HostCallObserver<T>>,
     closure: F,
 ) -> Result<()>
 where
@@ -379,7 +491,9 @@ where
     for _ in result_tys.types.iter() {
         result_vals.push(Val::Bool(false));
     }
-    closure(store.as_context_mut(), &args, &mut result_vals)?;
+    if let Err(e) = closure(store.as_context_mut(), &args, &mut result_vals) {
+        return Err(observe_error(observer, store, e));
+    }
     flags.set_may_leave(false);
 
     let mut cx = LowerContext::new(store, &options, types, instance);
@@ -400,11 +514,93 @@ where
 
     flags.set_may_leave(true);
 
+    if let Some(observer) = observer {
+        observe_results(
+            observer,
+            cx.store.as_context_mut(),
+            &options,
+            types,
+            instance,
+            func_ty,
+            storage,
+        )?;
+    }
+
     cx.exit_call()?;
 
     return Ok(());
 }
 
+/// Passes the error `e` that a host function failed with to `observer`, if
+/// any, returning the error to fail the call with.
+fn observe_error<T>(
+    observer: Option<&HostCallObserver<T>>,
+    store: StoreContextMut<'_, T>,
+    e: Error,
+) -> Error {
+    match observer {
+        Some(observer) => match (observer.func)(store, &observer.name, Err(&e)) {
+            Ok(()) => e,
+            Err(observer_error) => observer_error,
+        },
+        None => e,
+    }
+}
+
+/// Passes the results of a call to a host function to `observer` once they've
+/// been lowered into the caller.
+///
+/// The results are lifted back out of the caller to get hold of them as
+/// `Val`s. Any `own` handles within them are lifted as borrows so that the
+/// caller keeps ownership, and these borrows are released before returning.
+unsafe fn observe_results<T>(
+    observer: &HostCallObserver<T>,
+    mut store: StoreContextMut<'_, T>,
+    options: &Options,
+    types: &Arc<ComponentTypes>,
+    instance: *mut ComponentInstance,
+    func_ty: &TypeFunc,
+    storage: &[MaybeUninit<ValRaw>],
+) -> Result<()> {
+    let param_tys = &types[func_ty.params];
+    let result_tys = &types[func_ty.results];
+    let mut cx = LiftContext::new(store.0, options, types, instance);
+    cx.borrow_owned();
+    let results = if let Some(cnt) = result_tys.abi.flat_count(MAX_FLAT_RESULTS) {
+        let mut iter = mem::transmute::<&[MaybeUninit<ValRaw>], &[ValRaw]>(&storage[..cnt]).iter();
+        result_tys
+            .types
+            .iter()
+            .map(|ty| Val::lift(&mut cx, *ty, &mut iter))
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        // The return pointer follows the parameters, or the pointer to them
+        // if they're passed indirectly.
+        let ret_index = param_tys.abi.flat_count(MAX_FLAT_PARAMS).unwrap_or(1);
+        let mut offset = validate_inbounds_dynamic(
+            &result_tys.abi,
+            cx.memory(),
+            storage[ret_index].assume_init_ref(),
+        )?;
+        result_tys
+            .types
+            .iter()
+            .map(|ty| {
+                let abi = types.canonical_abi(ty);
+                let size = usize::try_from(abi.size32).unwrap();
+                let memory = &cx.memory()[abi.next_field32_size(&mut offset)..][..size];
+                Val::load(&mut cx, *ty, memory)
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let result = (observer.func)(store.as_context_mut(), &observer.name, Ok(&results));
+    for val in &results {
+        release_borrows(&mut store, val)?;
+    }
+    result
+}
+
 fn validate_inbounds_dynamic(abi: &CanonicalAbiInfo, memory: &[u8], ptr: &ValRaw) -> Result<usize> {
     // FIXME: needs memory64 support
     let ptr = usize::try_from(ptr.get_u32())?;
@@ -421,6 +617,93 @@ fn validate_inbounds_dynamic(abi: &CanonicalAbiInfo, memory: &[u8], ptr: &ValRaw
     Ok(ptr)
 }
 
+/// Same as `call_host_dynamic`, but for functions created with
+/// `HostFunc::new_linked` which call another component through an adapter
+/// that lifts and lowers the arguments and results itself.
+#[cfg(any(feature = "cranelift", feature = "winch"))]
+unsafe fn call_linked<T>(
+    func: &LinkedFunc,
+    instance: *mut ComponentInstance,
+    types: &Arc<ComponentTypes>,
+    mut store: StoreContextMut<'_, T>,
+    ty: TypeFuncIndex,
+    flags: InstanceFlags,
+    memory: *mut VMMemoryDefinition,
+    realloc: *mut VMFuncRef,
+    string_encoding: StringEncoding,
+    storage: &mut [MaybeUninit<ValRaw>],
+    observer: Option<&HostCallObserver<T>>,
+) -> Result<()> {
+    let options = Options::new(
+        store.0.id(),
+        NonNull::new(memory),
+        NonNull::new(realloc),
+        string_encoding,
+    );
+    let caller = LinkedCaller {
+        instance,
+        types,
+        ty,
+        flags,
+        options,
+    };
+    if let Err(e) = func.call(&mut store, &caller, storage) {
+        return Err(observe_error(observer, store, e));
+    }
+
+    if let Some(observer) = observer {
+        // The adapter has already ended its call by this point, so observing
+        // the results, which borrows any handles within them, happens within a
+        // call of its own.
+        LiftContext::new(store.0, &options, types, instance).enter_call();
+        observe_results(
+            observer,
+            store.as_context_mut(),
+            &options,
+            types,
+            instance,
+            &types[ty],
+            storage,
+        )?;
+        LiftContext::new(store.0, &options, types, instance).exit_call()?;
+    }
+
+    Ok(())
+}
+
+#[cfg(any(feature = "cranelift", feature = "winch"))]
+extern "C" fn linked_entrypoint<T>(
+    cx: *mut VMOpaqueContext,
+    data: *mut u8,
+    ty: u32,
+    flags: *mut u8,
+    memory: *mut VMMemoryDefinition,
+    realloc: *mut VMFuncRef,
+    string_encoding: u8,
+    storage: *mut MaybeUninit<ValRaw>,
+    storage_len: usize,
+) -> bool {
+    unsafe {
+        let host = &*(data as *const HostFunc);
+        let func = &*Arc::as_ptr(&host.func).cast::<LinkedFunc>();
+        call_host_and_handle_result(cx, |instance, types, store| {
+            call_linked::<T>(
+                func,
+                instance,
+                types,
+                store,
+                TypeFuncIndex::from_u32(ty),
+                InstanceFlags::from_raw(flags),
+                memory,
+                realloc,
+                StringEncoding::from_u8(string_encoding).unwrap(),
+                core::slice::from_raw_parts_mut(storage, storage_len),
+                host.observer::<T>(),
+            )
+        })
+    }
+}
+
 extern "C" fn dynamic_entrypoint<T, F>(
     cx: *mut VMOpaqueContext,
     data: *mut u8,
@@ -435,8 +718,9 @@ extern "C" fn dynamic_entrypoint<T, F>(
 where
     F: Fn(StoreContextMut<'_, T>, &[Val], &mut [Val]) -> Result<()> + Send + Sync + 'static,
 {
-    let data = data as *const F;
     unsafe {
+        let host = &*(data as *const HostFunc);
+        let func = &*Arc::as_ptr(&host.func).cast::<F>();
         call_host_and_handle_result(cx, |instance, types, store| {
             call_host_dynamic::<T, _>(
                 instance,
@@ -448,7 +732,8 @@ where
                 realloc,
                 StringEncoding::from_u8(string_encoding).unwrap(),
                 core::slice::from_raw_parts_mut(storage, storage_len),
-                |store, params, results| (*data)(store, params, results),
+                host.observer::<T>(),
+                |store, params, results| func(store, params, results),
             )
         })
     }
@@ -0,0 +1,420 @@
+//! Calls between component instances which were linked together at runtime.
+//!
+//! When the export of one component instance satisfies the import of another
+//! through `LinkerInstance::instance_exports_from` neither component was
+//! compiled with knowledge of the other, so the fused adapter between the two
+//! can't be compiled ahead of time as it is for components composed within
+//! one component. Instead the adapter is compiled the first time the export is
+//! called from a particular caller, see `LinkedAdapter` in
+//! `wasmtime-environ`, and it's then instantiated once per calling component
+//! instance within the store that both instances live in.
+
+use super::{Func, Options};
+use crate::component::matching::InstanceType;
+use crate::component::types::Type;
+use crate::component::ResourceType;
+use crate::hash_map::HashMap;
+use crate::instance::OwnedImports;
+use crate::prelude::*;
+use crate::runtime::vm::component::{self, ComponentInstance, InstanceFlags};
+use crate::runtime::vm::{Export, ExportGlobal, ExportMemory, SendSyncPtr, VMFuncRef};
+use crate::store::StoreOpaque;
+use crate::sync::RwLock;
+use crate::{AsContextMut, Extern, Module, StoreContextMut, ValRaw};
+use alloc::sync::Arc;
+use core::mem::MaybeUninit;
+use core::ptr::{self, NonNull};
+use wasmtime_environ::component::{
+    ComponentTypes, InterfaceType, LinkedAdapter, LinkedAdapterImport, LinkedAdapterSide,
+    LinkedSide, ResourceIndex, StringEncoding, TypeFuncIndex, TypeResourceTableIndex,
+    LINKED_ADAPTER_EXPORT,
+};
+use wasmtime_environ::{Global, IndexType, PrimaryMap, WasmValType};
+
+/// A component function exported from one instance which is called from the
+/// lowered imports of other instances, as defined by
+/// `HostFunc::new_linked`.
+pub(crate) struct LinkedFunc {
+    func: Func,
+    ty: TypeFuncIndex,
+    types: Arc<ComponentTypes>,
+    resources: Arc<PrimaryMap<ResourceIndex, ResourceType>>,
+    /// Adapter modules compiled so far, for each distinct type and set of
+    /// canonical options that this function has been lowered with.
+    modules: RwLock<HashMap<ModuleKey, Arc<AdapterModule>>>,
+    /// The adapter function instantiated for each caller so far.
+    adapters: RwLock<HashMap<CallerKey, SendSyncPtr<VMFuncRef>>>,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct ModuleKey {
+    types: usize,
+    ty: TypeFuncIndex,
+    string_encoding: StringEncoding,
+    memory64: Option<bool>,
+    realloc: bool,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct CallerKey {
+    instance: usize,
+    ty: TypeFuncIndex,
+    flags: usize,
+    memory: usize,
+    realloc: usize,
+    string_encoding: StringEncoding,
+}
+
+struct AdapterModule {
+    module: Module,
+    imports: Vec<LinkedAdapterImport>,
+    resource_tables: PrimaryMap<TypeResourceTableIndex, (LinkedSide, TypeResourceTableIndex)>,
+    // Held to keep `ModuleKey::types` from being reused by other types.
+    _types: Arc<ComponentTypes>,
+}
+
+/// The lowered import that a `LinkedFunc` is called from.
+pub(crate) struct LinkedCaller<'a> {
+    pub instance: *mut ComponentInstance,
+    pub types: &'a Arc<ComponentTypes>,
+    pub ty: TypeFuncIndex,
+    pub flags: InstanceFlags,
+    pub options: Options,
+}
+
+impl LinkedFunc {
+    pub(crate) fn new(store: &StoreOpaque, func: Func) -> LinkedFunc {
+        let data = &store[func.0];
+        let instance = store[data.instance.0].as_ref().unwrap();
+        LinkedFunc {
+            func,
+            ty: data.ty,
+            types: data.types.clone(),
+            resources: instance.ty().resources.clone(),
+            modules: RwLock::new(HashMap::new()),
+            adapters: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Checks that this function has the type `expected` within the component
+    /// described by `ty`.
+    ///
+    /// Unlike host functions this function's type is known, so it must match
+    /// exactly, including which resource types it uses.
+    pub(crate) fn typecheck(&self, expected: TypeFuncIndex, ty: &InstanceType<'_>) -> Result<()> {
+        let actual_ty = InstanceType {
+            types: &self.types,
+            resources: &self.resources,
+        };
+        let expected = &ty.types[expected];
+        let actual = &self.types[self.ty];
+        if Type::from(&InterfaceType::Tuple(expected.params), ty)
+            != Type::from(&InterfaceType::Tuple(actual.params), &actual_ty)
+        {
+            bail!("type mismatch with parameters");
+        }
+        if Type::from(&InterfaceType::Tuple(expected.results), ty)
+            != Type::from(&InterfaceType::Tuple(actual.results), &actual_ty)
+        {
+            bail!("type mismatch with results");
+        }
+        Ok(())
+    }
+
+    /// Calls this function from `caller` through a fused adapter, where
+    /// `storage` is the same as for host functions.
+    ///
+    /// # Unsafety
+    ///
+    /// All of `caller`'s pointers must be valid and belong to `store`, as
+    /// must `storage` be valid for the type of the lowered import.
+    pub(crate) unsafe fn call<T>(
+        &self,
+        store: &mut StoreContextMut<'_, T>,
+        caller: &LinkedCaller<'_>,
+        storage: &mut [MaybeUninit<ValRaw>],
+    ) -> Result<()> {
+        let key = CallerKey {
+            instance: caller.instance as usize,
+            ty: caller.ty,
+            flags: caller.flags.as_raw() as usize,
+            memory: caller
+                .options
+                .raw_memory()
+                .map_or(0, |p| p.as_ptr() as usize),
+            realloc: caller
+                .options
+                .raw_realloc()
+                .map_or(0, |p| p.as_ptr() as usize),
+            string_encoding: caller.options.string_encoding(),
+        };
+        let adapter = self.adapters.read().get(&key).copied();
+        let adapter = match adapter {
+            Some(adapter) => adapter,
+            None => {
+                let adapter = SendSyncPtr::new(self.instantiate(store, caller)?);
+                self.adapters.write().insert(key, adapter);
+                adapter
+            }
+        };
+
+        // As with `Func::call_raw` the callee is recorded in case this call
+        // traps and a coredump is captured.
+        #[cfg(feature = "coredump")]
+        let record_call = store.0.engine().config().coredump_on_trap;
+        #[cfg(feature = "coredump")]
+        if record_call {
+            let data = &store.0[self.func.0];
+            let frame = (data.instance, data.export_index);
+            store.0.component_call_stack_mut().push(frame);
+        }
+        let result = crate::Func::call_unchecked_raw(
+            store,
+            adapter.as_non_null(),
+            storage as *mut [MaybeUninit<ValRaw>] as *mut [ValRaw],
+        );
+        #[cfg(feature = "coredump")]
+        if record_call {
+            store.0.component_call_stack_mut().pop();
+        }
+        result
+    }
+
+    /// Compiles, if necessary, and instantiates the adapter for calls from
+    /// `caller` to this function, returning the adapter function.
+    unsafe fn instantiate<T>(
+        &self,
+        store: &mut StoreContextMut<'_, T>,
+        caller: &LinkedCaller<'_>,
+    ) -> Result<NonNull<VMFuncRef>> {
+        let data = &store.0[self.func.0];
+        let callee_func = data.export.func_ref;
+        let callee_options = data.options;
+        let post_return = data.post_return.map(|f| f.func_ref);
+        let instance = store.0[data.instance.0].as_ref().unwrap();
+        let callee_flags = instance.instance().instance_flags(data.component_instance);
+        let callee = instance.instance_ptr();
+
+        let caller_memory = memory(store.0, &caller.options)?;
+        let callee_memory = memory(store.0, &callee_options)?;
+        let adapter = self.module(
+            store.0,
+            caller,
+            caller_memory.as_ref(),
+            callee_memory.as_ref(),
+            &callee_options,
+        )?;
+
+        let instances = |side| match side {
+            LinkedSide::Caller => caller.instance,
+            LinkedSide::Callee => callee,
+        };
+        let memories = |side| match side {
+            LinkedSide::Caller => caller_memory.clone(),
+            LinkedSide::Callee => callee_memory.clone(),
+        };
+        let options = |side| match side {
+            LinkedSide::Caller => caller.options,
+            LinkedSide::Callee => callee_options,
+        };
+        let mut imports = OwnedImports::empty();
+        imports.reserve(&adapter.module);
+        for (import, ty) in adapter.imports.iter().zip(adapter.module.imports()) {
+            let func = |store: &mut StoreContextMut<'_, T>, func_ref: NonNull<VMFuncRef>| {
+                Extern::Func(crate::Func::from_vm_func_ref(store.0, func_ref))
+            };
+            let item = match *import {
+                LinkedAdapterImport::Flags(side) => {
+                    let flags = match side {
+                        LinkedSide::Caller => caller.flags,
+                        LinkedSide::Callee => callee_flags,
+                    };
+                    imports.push_export(&Export::Global(ExportGlobal {
+                        definition: flags.as_raw(),
+                        vmctx: ptr::null_mut(),
+                        global: Global {
+                            wasm_ty: WasmValType::I32,
+                            mutability: true,
+                        },
+                    }));
+                    continue;
+                }
+                LinkedAdapterImport::Memory(side) => {
+                    imports.push_export(&Export::Memory(memories(side).unwrap()));
+                    continue;
+                }
+                LinkedAdapterImport::Realloc(side) => {
+                    func(store, options(side).raw_realloc().unwrap())
+                }
+                LinkedAdapterImport::Callee => func(store, callee_func),
+                LinkedAdapterImport::PostReturn => func(store, post_return.unwrap()),
+                LinkedAdapterImport::Transcode {
+                    op,
+                    from,
+                    from64,
+                    to,
+                    to64,
+                } => {
+                    let from = options(from);
+                    let to = options(to);
+                    let ty = ty.ty().unwrap_func().clone();
+                    Extern::Func(crate::Func::new_unchecked(
+                        &mut *store,
+                        ty,
+                        move |mut cx, params_and_results| {
+                            let from = from.memory_mut(cx.as_context_mut().0);
+                            let (from_ptr, from_len) = (from.as_mut_ptr(), from.len());
+                            let to = to.memory_mut(cx.as_context_mut().0);
+                            component::transcode(
+                                op,
+                                from_ptr,
+                                from_len,
+                                from64,
+                                to.as_mut_ptr(),
+                                to.len(),
+                                to64,
+                                params_and_results,
+                            )
+                        },
+                    ))
+                }
+                LinkedAdapterImport::ResourceTransferOwn
+                | LinkedAdapterImport::ResourceTransferBorrow => {
+                    let tables = adapter
+                        .resource_tables
+                        .values()
+                        .map(|(side, table)| {
+                            (
+                                SendSyncPtr::new(NonNull::new(instances(*side)).unwrap()),
+                                *table,
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    let transfer: unsafe fn(_, _, _, _, _) -> Result<u32> = match import {
+                        LinkedAdapterImport::ResourceTransferOwn => {
+                            ComponentInstance::resource_transfer_own_between
+                        }
+                        _ => ComponentInstance::resource_transfer_borrow_between,
+                    };
+                    let ty = ty.ty().unwrap_func().clone();
+                    Extern::Func(crate::Func::new_unchecked(
+                        &mut *store,
+                        ty,
+                        move |_, args| {
+                            let table = |i: u32| {
+                                tables
+                                    .get(i as usize)
+                                    .copied()
+                                    .ok_or_else(|| anyhow!("invalid resource table index"))
+                            };
+                            let (src_instance, src) = table(args[1].get_u32())?;
+                            let (dst_instance, dst) = table(args[2].get_u32())?;
+                            let idx = transfer(
+                                src_instance.as_ptr(),
+                                args[0].get_u32(),
+                                src,
+                                dst_instance.as_ptr(),
+                                dst,
+                            )?;
+                            args[0] = ValRaw::u32(idx);
+                            Ok(())
+                        },
+                    ))
+                }
+                // Borrows can only be lent by the caller, so the caller's
+                // tables are used to end the call.
+                LinkedAdapterImport::ResourceEnterCall | LinkedAdapterImport::ResourceExitCall => {
+                    let enter = matches!(import, LinkedAdapterImport::ResourceEnterCall);
+                    let instance = SendSyncPtr::new(NonNull::new(caller.instance).unwrap());
+                    let ty = ty.ty().unwrap_func().clone();
+                    Extern::Func(crate::Func::new_unchecked(&mut *store, ty, move |_, _| {
+                        let instance = &mut *instance.as_ptr();
+                        if enter {
+                            instance.resource_enter_call();
+                            Ok(())
+                        } else {
+                            instance.resource_exit_call()
+                        }
+                    }))
+                }
+            };
+            imports.push(&item, store.0, &adapter.module);
+        }
+
+        let instance = crate::Instance::new_started_impl(store, &adapter.module, imports.as_ref())?;
+        let func = instance
+            .get_func(&mut *store, LINKED_ADAPTER_EXPORT)
+            .unwrap();
+        Ok(func.vm_func_ref(store.0))
+    }
+
+    /// Returns the adapter module for calls from `caller`, compiling it if it
+    /// hasn't been compiled yet.
+    fn module(
+        &self,
+        store: &StoreOpaque,
+        caller: &LinkedCaller<'_>,
+        caller_memory: Option<&ExportMemory>,
+        callee_memory: Option<&ExportMemory>,
+        callee_options: &Options,
+    ) -> Result<Arc<AdapterModule>> {
+        let types = caller.types;
+        let key = ModuleKey {
+            types: Arc::as_ptr(types) as usize,
+            ty: caller.ty,
+            string_encoding: caller.options.string_encoding(),
+            memory64: caller_memory.map(is_memory64),
+            realloc: caller.options.raw_realloc().is_some(),
+        };
+        if let Some(module) = self.modules.read().get(&key) {
+            return Ok(module.clone());
+        }
+
+        let adapter = LinkedAdapter::new(
+            &LinkedAdapterSide {
+                types,
+                ty: caller.ty,
+                string_encoding: key.string_encoding,
+                memory: key.memory64.is_some(),
+                memory64: key.memory64 == Some(true),
+                realloc: key.realloc,
+                post_return: false,
+            },
+            &LinkedAdapterSide {
+                types: &self.types,
+                ty: self.ty,
+                string_encoding: callee_options.string_encoding(),
+                memory: callee_memory.is_some(),
+                memory64: callee_memory.is_some_and(is_memory64),
+                realloc: callee_options.raw_realloc().is_some(),
+                post_return: store[self.func.0].post_return.is_some(),
+            },
+            store.engine().tunables().debug_adapter_modules,
+        );
+        let module = Module::from_binary(store.engine(), &adapter.wasm)
+            .context("failed to compile adapter between linked components")?;
+        let module = Arc::new(AdapterModule {
+            module,
+            imports: adapter.imports,
+            resource_tables: adapter.resource_tables,
+            _types: types.clone(),
+        });
+        self.modules.write().insert(key, module.clone());
+        Ok(module)
+    }
+}
+
+/// Looks up the linear memory of `options` within `store`, if it has one.
+fn memory(store: &mut StoreOpaque, options: &Options) -> Result<Option<ExportMemory>> {
+    let Some(memory) = options.raw_memory() else {
+        return Ok(None);
+    };
+    match store.defined_memory(memory.as_ptr()) {
+        Some(memory) => Ok(Some(memory)),
+        None => bail!("memory of linked component not found in store"),
+    }
+}
+
+fn is_memory64(memory: &ExportMemory) -> bool {
+    memory.memory.idx_type == IndexType::I64
+}
@@ -153,6 +153,17 @@ impl Options {
         }
     }
 
+    /// Returns the raw pointer to the memory of these options, if any.
+    pub(crate) fn raw_memory(&self) -> Option<NonNull<VMMemoryDefinition>> {
+        self.memory
+    }
+
+    /// Returns the raw pointer to the `realloc` function of these options, if
+    /// any.
+    pub(crate) fn raw_realloc(&self) -> Option<NonNull<VMFuncRef>> {
+        self.realloc
+    }
+
     /// Returns the underlying encoding used for strings in this
     /// lifting/lowering.
     pub fn string_encoding(&self) -> StringEncoding {
@@ -408,6 +419,10 @@ pub struct LiftContext<'a> {
     host_resource_data: &'a mut HostResourceData,
 
     calls: &'a mut CallContexts,
+
+    /// Whether `own` handles are lifted as borrows, leaving them with the
+    /// guest, when lifting values which the guest keeps.
+    borrow_owned: bool,
 }
 
 #[doc(hidden)]
@@ -443,9 +458,22 @@ impl<'a> LiftContext<'a> {
             calls,
             host_table,
             host_resource_data,
+            borrow_owned: false,
         }
     }
 
+    /// Configures `own` handles to be lifted as borrows from now on, which is
+    /// used to inspect values that have been lowered into the guest without
+    /// taking ownership of any resources within them.
+    pub(crate) fn borrow_owned(&mut self) {
+        self.borrow_owned = true;
+    }
+
+    /// Returns whether `own` handles are to be lifted as borrows.
+    pub(crate) fn borrows_owned(&self) -> bool {
+        self.borrow_owned
+    }
+
     /// Returns the entire contents of linear memory for this set of lifting
     /// options.
     ///
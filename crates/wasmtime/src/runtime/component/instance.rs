@@ -6,15 +6,15 @@ use crate::component::{
 use crate::instance::OwnedImports;
 use crate::linker::DefinitionType;
 use crate::prelude::*;
-use crate::runtime::vm::component::{ComponentInstance, OwnedComponentInstance};
+use crate::runtime::vm::component::{ComponentInstance, InstanceFlags, OwnedComponentInstance};
 use crate::runtime::vm::{CompiledModuleId, VMFuncRef};
 use crate::store::{StoreOpaque, Stored};
-use crate::{AsContextMut, Engine, Module, StoreContextMut};
+use crate::{AsContext, AsContextMut, Engine, Module, StoreContextMut};
 use alloc::sync::Arc;
 use core::marker;
 use core::ptr::{self, NonNull};
 use wasmtime_environ::{component::*, EngineOrModuleTypeIndex};
-use wasmtime_environ::{EntityIndex, EntityType, Global, PrimaryMap, WasmValType};
+use wasmtime_environ::{EntityIndex, EntityRef, EntityType, Global, PrimaryMap, WasmValType};
 
 /// An instantiated component.
 ///
@@ -171,7 +171,7 @@ impl Instance {
         let ret = name.lookup(&data.component).and_then(|index| {
             match &data.component.env_component().export_items[index] {
                 Export::LiftedFunction { ty, func, options } => Some(Func::from_lifted_func(
-                    store, self, &data, *ty, func, options,
+                    store, self, &data, *ty, func, options, index,
                 )),
                 _ => None,
             }
@@ -242,6 +242,47 @@ impl Instance {
         }
     }
 
+    /// Returns the core wasm instances that were created when instantiating
+    /// this component, in the order they were created.
+    ///
+    /// Each core instance is paired with the index of the module it's an
+    /// instance of, if that module was defined within the component rather
+    /// than imported. Modules are numbered in the order that they appear in
+    /// the component's binary, including those within nested components.
+    /// Wasmtime also generates modules internally to adapt calls between
+    /// components, and instances of those are numbered after all modules in
+    /// the component's binary.
+    ///
+    /// This is a low-level API intended for tools which need to inspect the
+    /// internal state of a component, such as pre-initializers.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this instance.
+    pub fn core_instances(&self, store: impl AsContext) -> Vec<(Option<usize>, crate::Instance)> {
+        self._core_instances(store.as_context().0)
+    }
+
+    pub(crate) fn _core_instances(
+        &self,
+        store: &StoreOpaque,
+    ) -> Vec<(Option<usize>, crate::Instance)> {
+        let data = store[self.0].as_ref().unwrap();
+        let modules = data
+            .component
+            .env_component()
+            .initializers
+            .iter()
+            .filter_map(|init| match init {
+                GlobalInitializer::InstantiateModule(InstantiateModule::Static(idx, _)) => {
+                    Some(Some(idx.index()))
+                }
+                GlobalInitializer::InstantiateModule(InstantiateModule::Import(..)) => Some(None),
+                _ => None,
+            });
+        modules.zip(data.instances.values().copied()).collect()
+    }
+
     /// Looks up an exported resource type by name within this [`Instance`].
     ///
     /// The `store` argument provided must be the store that this instance
@@ -308,6 +349,188 @@ impl Instance {
         })
     }
 
+    /// Returns the names and indices of all items exported from `instance`
+    /// within this instance, or the root exports if `instance` is `None`.
+    ///
+    /// Returns `None` if `instance` doesn't refer to an exported instance.
+    pub(crate) fn export_indices(
+        &self,
+        store: &StoreOpaque,
+        instance: Option<&ComponentExportIndex>,
+    ) -> Option<Vec<(String, ComponentExportIndex)>> {
+        let data = store[self.0].as_ref().unwrap();
+        let info = data.component.env_component();
+        let exports = match instance {
+            Some(idx) => {
+                if idx.id != data.component_id() {
+                    return None;
+                }
+                match &info.export_items[idx.index] {
+                    Export::Instance { exports, .. } => exports,
+                    _ => return None,
+                }
+            }
+            None => &info.exports,
+        };
+        Some(
+            exports
+                .raw_iter()
+                .map(|(name, index)| {
+                    let index = ComponentExportIndex {
+                        id: data.component_id(),
+                        index: *index,
+                    };
+                    (name.clone(), index)
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the type of a resource exported from this instance along with
+    /// the destructor and instance flags of its defining instance, used to
+    /// destroy resources of this type which are owned elsewhere.
+    pub(crate) fn exported_resource(
+        &self,
+        store: &StoreOpaque,
+        index: &ComponentExportIndex,
+    ) -> Option<(
+        ResourceType,
+        Option<NonNull<VMFuncRef>>,
+        Option<InstanceFlags>,
+    )> {
+        let (data, export, _) = self.lookup_export(store, index)?;
+        match export {
+            Export::Type(TypeDef::Resource(id)) => {
+                let (dtor, flags) = data.state.dtor_and_flags(*id);
+                Some((data.ty().resource_type(*id), dtor, flags))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the export at `index`, where exports of nested
+    /// instances are named `instance#export`.
+    #[cfg(feature = "coredump")]
+    pub(crate) fn export_name(&self, store: &StoreOpaque, index: ExportIndex) -> Option<String> {
+        fn find(
+            info: &wasmtime_environ::component::Component,
+            exports: &NameMap<String, ExportIndex>,
+            index: ExportIndex,
+        ) -> Option<String> {
+            for (name, i) in exports.raw_iter() {
+                if *i == index {
+                    return Some(name.clone());
+                }
+                if let Export::Instance { exports, .. } = &info.export_items[*i] {
+                    if let Some(nested) = find(info, exports, index) {
+                        return Some(format!("{name}#{nested}"));
+                    }
+                }
+            }
+            None
+        }
+        let data = store[self.0].as_ref()?;
+        let info = data.component.env_component();
+        find(info, &info.exports, index)
+    }
+
+    /// Returns the module of each core instance of this instance.
+    ///
+    /// Unlike `crate::Instance::module` this doesn't look modules up by the
+    /// address of their code, which can't distinguish between the modules of
+    /// a component as they share one code object.
+    #[cfg(feature = "coredump")]
+    pub(crate) fn core_modules(
+        &self,
+        store: &StoreOpaque,
+    ) -> PrimaryMap<RuntimeInstanceIndex, crate::Module> {
+        let Some(data) = store[self.0].as_ref() else {
+            return PrimaryMap::new();
+        };
+        data.component
+            .env_component()
+            .initializers
+            .iter()
+            .filter_map(|init| match init {
+                GlobalInitializer::InstantiateModule(init) => Some(init),
+                _ => None,
+            })
+            .zip(data.instances.values())
+            .map(|(init, instance)| match init {
+                InstantiateModule::Static(idx, _) => data.component.static_module(*idx).clone(),
+                InstantiateModule::Import(..) => instance._module(store).clone(),
+            })
+            .collect()
+    }
+
+    /// Returns the fused adapters of this instance as `(adapter module,
+    /// function index, caller, callee)`, where the caller and callee are
+    /// component instances nested within this one.
+    #[cfg(feature = "coredump")]
+    pub(crate) fn adapters(&self, store: &StoreOpaque) -> Vec<(crate::Module, u32, u32, u32)> {
+        let Some(data) = store[self.0].as_ref() else {
+            return Vec::new();
+        };
+        let modules = self.core_modules(store);
+        data.component
+            .env_component()
+            .adapters
+            .iter()
+            .map(|adapter| {
+                (
+                    modules[adapter.instance].clone(),
+                    adapter.func.as_u32(),
+                    adapter.caller.as_u32(),
+                    adapter.callee.as_u32(),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the live handles of each non-empty resource table of this
+    /// instance as `(table, [(handle, rep, lend_count)])`.
+    #[cfg(feature = "coredump")]
+    pub(crate) fn resource_handles(
+        &self,
+        store: &StoreOpaque,
+    ) -> Vec<(u32, Vec<(u32, u32, Option<u32>)>)> {
+        let Some(data) = store[self.0].as_ref() else {
+            return Vec::new();
+        };
+        data.state
+            .resource_table_state()
+            .iter()
+            .map(|(index, table)| (index.as_u32(), table.handles().collect::<Vec<_>>()))
+            .filter(|(_, handles)| !handles.is_empty())
+            .collect()
+    }
+
+    /// Returns the name under which this instance imports or exports the
+    /// resource type `resource`, if any.
+    pub(crate) fn resource_type_name(
+        &self,
+        store: &StoreOpaque,
+        resource: ResourceType,
+    ) -> Option<String> {
+        fn find<'a>(
+            ty: &InstanceType<'a>,
+            items: impl IntoIterator<Item = (&'a String, &'a TypeDef)>,
+            resource: ResourceType,
+        ) -> Option<String> {
+            items.into_iter().find_map(|(name, def)| match def {
+                TypeDef::Resource(index) if ty.resource_type(*index) == resource => {
+                    Some(name.clone())
+                }
+                TypeDef::ComponentInstance(index) => find(ty, &ty.types[*index].exports, resource),
+                _ => None,
+            })
+        }
+        let data = store[self.0].as_ref()?;
+        let ty = data.ty();
+        let component = &ty.types[data.component.ty()];
+        find(&ty, &component.imports, resource).or_else(|| find(&ty, &component.exports, resource))
+    }
+
     fn lookup_export<'a>(
         &self,
         store: &'a StoreOpaque,
@@ -826,10 +1049,18 @@ impl<T> InstancePre<T> {
 
     fn instantiate_impl(&self, mut store: impl AsContextMut<Data = T>) -> Result<Instance> {
         let mut store = store.as_context_mut();
+        self.check_store(store.0)?;
         store
             .engine()
             .allocator()
             .increment_component_instance_count()?;
+        if let Err(e) = store.0.code_memory_growing(self.component.code_object()) {
+            store
+                .engine()
+                .allocator()
+                .decrement_component_instance_count();
+            return Err(e);
+        }
         let mut instantiator = Instantiator::new(&self.component, store.0, &self.imports);
         instantiator.run(&mut store).map_err(|e| {
             store
@@ -843,4 +1074,24 @@ impl<T> InstancePre<T> {
         store.0.push_component_instance(instance);
         Ok(instance)
     }
+
+    /// Checks that none of the imports are bound to a store other than
+    /// `store`, such as those defined by `Linker::link_component`.
+    fn check_store(&self, store: &StoreOpaque) -> Result<()> {
+        let bound = self.imports.values().any(|import| {
+            let bound_to = match import {
+                RuntimeImport::Func(func) => func.store(),
+                RuntimeImport::Resource { ty, .. } => ty.store(),
+                RuntimeImport::Module(_) => None,
+            };
+            bound_to.is_some_and(|id| id != store.id())
+        });
+        if bound {
+            bail!(
+                "cannot instantiate component: one of its imports was linked from a \
+                 component instance in a different store"
+            );
+        }
+        Ok(())
+    }
 }
@@ -1,13 +1,15 @@
-use crate::component::func::HostFunc;
+use crate::component::func::{HostFunc, ObserverFn};
 use crate::component::instance::RuntimeImport;
 use crate::component::matching::{InstanceType, TypeChecker};
 use crate::component::types;
 use crate::component::{
-    Component, ComponentNamedList, Instance, InstancePre, Lift, Lower, ResourceType, Val,
+    Component, ComponentExportIndex, ComponentNamedList, Instance, InstancePre, Lift, Lower,
+    ResourceType, Val,
 };
 use crate::hash_map::HashMap;
 use crate::prelude::*;
-use crate::{AsContextMut, Engine, Module, StoreContextMut};
+use crate::runtime::vm::{SendSyncPtr, ValRaw};
+use crate::{AsContextMut, Engine, Module, StoreContextMut, Trap};
 use alloc::sync::Arc;
 use core::future::Future;
 use core::marker;
@@ -135,6 +137,12 @@ impl<T> Linker<T> {
         self
     }
 
+    /// Returns whether name-shadowing is allowed, as configured with
+    /// [`Linker::allow_shadowing`].
+    pub fn shadowing_allowed(&self) -> bool {
+        self.allow_shadowing
+    }
+
     /// Returns the "root instance" of this linker, used to define names into
     /// the root namespace.
     pub fn root(&mut self) -> LinkerInstance<'_, T> {
@@ -158,6 +166,81 @@ impl<T> Linker<T> {
         self.root().into_instance(name)
     }
 
+    /// Defines all exports of the component `instance` under the same names
+    /// in the root of this linker.
+    ///
+    /// This is a convenience method for
+    /// [`LinkerInstance::instance_exports_from`] on [`Linker::root`] and can
+    /// be used to satisfy the imports of one component with the exports of
+    /// another component that was instantiated previously. For example if
+    /// `instance` exports the interface `a:b/c` then components
+    /// subsequently instantiated with this linker can import `a:b/c` and
+    /// their calls will be forwarded to `instance`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the exported names are already defined in
+    /// this linker and shadowing is disallowed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `instance` does not belong to `store`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use wasmtime::{Engine, Store};
+    /// use wasmtime::component::{Component, Linker};
+    ///
+    /// # fn main() -> wasmtime::Result<()> {
+    /// let engine = Engine::default();
+    /// let mut store = Store::new(&engine, ());
+    /// let mut linker = Linker::new(&engine);
+    ///
+    /// let provider = Component::new(&engine, r#"
+    ///     (component
+    ///         (core module $m
+    ///             (func (export "answer") (result i32) i32.const 42)
+    ///         )
+    ///         (core instance $i (instantiate $m))
+    ///         (func $answer (result u32) (canon lift (core func $i "answer")))
+    ///         (instance $api (export "answer" (func $answer)))
+    ///         (export "my:pkg/api" (instance $api))
+    ///     )
+    /// "#)?;
+    /// let provider = linker.instantiate(&mut store, &provider)?;
+    /// linker.link_component(&mut store, &provider)?;
+    ///
+    /// let consumer = Component::new(&engine, r#"
+    ///     (component
+    ///         (import "my:pkg/api" (instance $api
+    ///             (export "answer" (func (result u32)))
+    ///         ))
+    ///         (core func $answer (canon lower (func $api "answer")))
+    ///         (core module $m
+    ///             (import "" "answer" (func $answer (result i32)))
+    ///             (func (export "run") (result i32) call $answer)
+    ///         )
+    ///         (core instance $i (instantiate $m
+    ///             (with "" (instance (export "answer" (func $answer))))
+    ///         ))
+    ///         (func (export "run") (result u32) (canon lift (core func $i "run")))
+    ///     )
+    /// "#)?;
+    /// let consumer = linker.instantiate(&mut store, &consumer)?;
+    /// let run = consumer.get_typed_func::<(), (u32,)>(&mut store, "run")?;
+    /// assert_eq!(run.call(&mut store, ())?, (42,));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn link_component(
+        &mut self,
+        store: impl AsContextMut<Data = T>,
+        instance: &Instance,
+    ) -> Result<()> {
+        self.root().instance_exports_from(store, instance, None)
+    }
+
     fn typecheck<'a>(&'a self, component: &'a Component) -> Result<TypeChecker<'a>> {
         let mut cx = TypeChecker {
             types: component.types(),
@@ -367,6 +450,55 @@ impl<T> Linker<T> {
         }
         Ok(())
     }
+
+    /// Calls `observer` after each call that a component makes to one of the
+    /// host functions currently defined in this linker.
+    ///
+    /// The `observer` is given the name of the function, which is its path
+    /// through the instances that it's defined in separated by `#`, such as
+    /// `wasi:clocks/monotonic-clock@0.2.3#now`. It's also given either the
+    /// results of the call, as lifted back out of the calling component after
+    /// they've been lowered into it, or the error that the call failed with.
+    /// Resources within the results are borrowed from the caller, which keeps
+    /// ownership of them, and the borrows are released once `observer`
+    /// returns. If `observer` returns an error then the call fails with that
+    /// error instead.
+    ///
+    /// Functions defined after this is called aren't observed, and neither
+    /// are resource destructors. Observing a function which is already
+    /// observed replaces its previous observer.
+    pub fn observe_host_calls(
+        &mut self,
+        observer: impl Fn(StoreContextMut<'_, T>, &str, Result<&[Val], &Error>) -> Result<()>
+            + Send
+            + Sync
+            + 'static,
+    ) where
+        T: 'static,
+    {
+        fn observe<T: 'static>(
+            map: &mut NameMap<usize, Definition>,
+            strings: &Strings,
+            path: &mut Vec<Arc<str>>,
+            observer: &Arc<ObserverFn<T>>,
+        ) {
+            let names = map.raw_iter().map(|(name, _)| *name).collect::<Vec<_>>();
+            for name in names {
+                path.push(strings.strings[name].clone());
+                match map.raw_get_mut(&name) {
+                    Some(Definition::Instance(map)) => observe(map, strings, path, observer),
+                    Some(Definition::Func(func)) => {
+                        *func = func.observed(path.join("#"), observer.clone());
+                    }
+                    _ => {}
+                }
+                path.pop();
+            }
+        }
+
+        let observer: Arc<ObserverFn<T>> = Arc::new(observer);
+        observe(&mut self.map, &self.strings, &mut Vec::new(), &observer);
+    }
 }
 
 impl<T> LinkerInstance<'_, T> {
@@ -652,6 +784,127 @@ impl<T> LinkerInstance<'_, T> {
         Ok(())
     }
 
+    /// Defines the exports of a component [`Instance`] within this instance.
+    ///
+    /// Every export of `instance` is defined here under the same name. If
+    /// `export` is `Some` then it must refer to an exported instance of
+    /// `instance`, such as an interface, and only that instance's exports are
+    /// defined. Otherwise all the root exports of `instance` are defined.
+    ///
+    /// Exported functions are defined such that calls to them from another
+    /// component go directly to `instance` through a fused adapter, the same
+    /// as is generated for calls between components composed ahead of time.
+    /// As the two components are compiled separately here, the adapter is
+    /// compiled once the caller's types and canonical options are known, on
+    /// the first call from each component instance. Exported resource types
+    /// are defined as with [`LinkerInstance::resource`], where dropping an
+    /// owned handle runs the destructor of `instance`. Exported modules are
+    /// defined as with [`LinkerInstance::module`] and exported instances are
+    /// defined recursively.
+    ///
+    /// Without the `cranelift` or `winch` features adapters can't be compiled,
+    /// in which case calls are instead forwarded dynamically by converting
+    /// arguments and results through [`Val`].
+    ///
+    /// The definitions here are bound to `store`, and instantiating a
+    /// component which imports any of them within a different store returns
+    /// an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `export` isn't an exported instance or if any of
+    /// the names are already defined and shadowing is disallowed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `instance` does not belong to `store`.
+    pub fn instance_exports_from(
+        &mut self,
+        mut store: impl AsContextMut<Data = T>,
+        instance: &Instance,
+        export: Option<&ComponentExportIndex>,
+    ) -> Result<()> {
+        self._instance_exports_from(store.as_context_mut(), instance, export)
+    }
+
+    fn _instance_exports_from(
+        &mut self,
+        mut store: StoreContextMut<'_, T>,
+        instance: &Instance,
+        export: Option<&ComponentExportIndex>,
+    ) -> Result<()> {
+        let exports = instance
+            .export_indices(store.0, export)
+            .ok_or_else(|| anyhow!("export is not an instance"))?;
+        for (name, index) in exports {
+            if let Some(func) = instance.get_func(&mut store, &index) {
+                // This function is always entered from a wasm import which
+                // means that it's already running on a fiber if async support
+                // is enabled, so the callee can be invoked directly.
+                #[cfg(any(feature = "cranelift", feature = "winch"))]
+                let func = HostFunc::new_linked::<T>(store.0, func);
+                #[cfg(not(any(feature = "cranelift", feature = "winch")))]
+                let func = HostFunc::new_dynamic(
+                    move |mut store: StoreContextMut<'_, T>,
+                          params: &[Val],
+                          results: &mut [Val]| {
+                        func.call_impl(&mut store, params, results)?;
+                        for param in params {
+                            release_borrows(&mut store, param)?;
+                        }
+                        func.post_return_impl(&mut store)
+                    },
+                );
+                self.insert(&name, Definition::Func(func.bound_to_store(store.0.id())))?;
+            } else if let Some(module) = instance.get_module(&mut store, &index) {
+                self.module(&name, &module)?;
+            } else if let Some((ty, dtor, flags)) = instance.exported_resource(store.0, &index) {
+                // Handles to this resource are lowered into other components
+                // with the representation used by `instance`, so dropping an
+                // owned handle elsewhere needs to run `instance`'s destructor
+                // for that representation.
+                let store_id = store.0.id();
+                let dtor = dtor.map(SendSyncPtr::new);
+                let dtor = Arc::new(crate::func::HostFunc::wrap_inner(
+                    &self.engine,
+                    move |mut cx: crate::Caller<'_, T>, (rep,): (u32,)| {
+                        let mut store = cx.as_context_mut();
+                        if store.0.id() != store_id {
+                            bail!("resource linked from a component in a different store");
+                        }
+                        // Same reentrance check as `ResourceAny::resource_drop`.
+                        if let Some(flags) = flags {
+                            if unsafe { !flags.may_enter() } {
+                                bail!(Trap::CannotEnterComponent);
+                            }
+                        }
+                        let Some(dtor) = dtor else { return Ok(()) };
+                        let mut args = [ValRaw::u32(rep)];
+                        // This should be safe because `dtor` belongs to
+                        // `instance` which, as checked above, lives in this
+                        // store, and destructors always have the type
+                        // `(func (param i32))`.
+                        unsafe {
+                            crate::Func::call_unchecked_raw(
+                                &mut store,
+                                dtor.as_non_null(),
+                                &mut args,
+                            )
+                        }
+                    },
+                ));
+                self.insert(&name, Definition::Resource(ty, dtor))?;
+            } else if instance.export_indices(store.0, Some(&index)).is_some() {
+                self.instance(&name)?
+                    ._instance_exports_from(store.as_context_mut(), instance, Some(&index))
+                    .with_context(|| format!("failed to link exported instance `{name}`"))?;
+            }
+            // Other exported types are informational only and don't need a
+            // definition in the linker.
+        }
+        Ok(())
+    }
+
     /// Defines a nested instance within this instance.
     ///
     /// This can be used to describe arbitrarily nested levels of instances
@@ -684,6 +937,24 @@ impl<T> LinkerInstance<'_, T> {
     }
 }
 
+/// Drops the host's borrows of any resources within `val`, which must all be
+/// released before a host function returns.
+pub(crate) fn release_borrows<T>(store: &mut StoreContextMut<'_, T>, val: &Val) -> Result<()> {
+    match val {
+        Val::Resource(resource) if !resource.owned() => resource.resource_drop_impl(store),
+        Val::List(vals) | Val::Tuple(vals) => {
+            vals.iter().try_for_each(|val| release_borrows(store, val))
+        }
+        Val::Record(fields) => fields
+            .iter()
+            .try_for_each(|(_, val)| release_borrows(store, val)),
+        Val::Variant(_, Some(val))
+        | Val::Option(Some(val))
+        | Val::Result(Ok(Some(val)) | Err(Some(val))) => release_borrows(store, val),
+        _ => Ok(()),
+    }
+}
+
 impl NameMapIntern for Strings {
     type Key = usize;
 
@@ -129,6 +129,9 @@ pub(crate) use self::resources::HostResourceData;
 #[cfg(feature = "wave")]
 pub use wasm_wave;
 
+#[cfg(feature = "wave")]
+pub use crate::runtime::wave::WaveHandles;
+
 // These items are used by `#[derive(ComponentType, Lift, Lower)]`, but they are not part of
 // Wasmtime's API stability guarantees
 #[doc(hidden)]
@@ -1,5 +1,6 @@
 use super::Resource;
 use crate::prelude::*;
+use crate::{ResourceLimiter, SharedResourceLimiter};
 use alloc::collections::BTreeSet;
 use core::any::Any;
 use core::fmt;
@@ -7,8 +8,12 @@ use core::fmt;
 #[derive(Debug)]
 /// Errors returned by operations on `ResourceTable`
 pub enum ResourceTableError {
-    /// ResourceTable has no free keys
+    /// ResourceTable has no free keys, or its resource limiter didn't allow
+    /// it to grow
     Full,
+    /// The resource limiter of the ResourceTable returned an error when asked
+    /// whether it may grow
+    Limiter(anyhow::Error),
     /// Resource not present in table
     NotPresent,
     /// Resource present in table, but with a different type
@@ -25,18 +30,30 @@ impl fmt::Display for ResourceTableError {
             Self::NotPresent => write!(f, "resource not present"),
             Self::WrongType => write!(f, "resource is of another type"),
             Self::HasChildren => write!(f, "resource has children"),
+            Self::Limiter(_) => write!(f, "resource table's resource limiter failed"),
         }
     }
 }
 
 #[cfg(feature = "std")]
-impl std::error::Error for ResourceTableError {}
+impl std::error::Error for ResourceTableError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Limiter(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 /// The `ResourceTable` type maps a `Resource<T>` to its `T`.
 #[derive(Debug)]
 pub struct ResourceTable {
     entries: Vec<Entry>,
     free_head: Option<usize>,
+    limiter: Option<SharedResourceLimiter>,
+    /// The number of bytes taken up by occupied entries, which is what has
+    /// been reported to `limiter`.
+    size: usize,
 }
 
 #[derive(Debug)]
@@ -96,6 +113,10 @@ impl TableEntry {
         let was_removed = self.children.remove(&child);
         debug_assert!(was_removed);
     }
+    /// The number of bytes this entry takes up in a table.
+    fn size(&self) -> usize {
+        core::mem::size_of::<Entry>() + core::mem::size_of_val(&*self.entry)
+    }
 }
 
 impl ResourceTable {
@@ -104,6 +125,8 @@ impl ResourceTable {
         ResourceTable {
             entries: Vec::new(),
             free_head: None,
+            limiter: None,
+            size: 0,
         }
     }
 
@@ -112,9 +135,39 @@ impl ResourceTable {
         ResourceTable {
             entries: Vec::with_capacity(capacity),
             free_head: None,
+            limiter: None,
+            size: 0,
         }
     }
 
+    /// Report the growth of this table to `limiter`.
+    ///
+    /// Each resource takes up the size of its value plus the table's own
+    /// per-entry overhead, and the growth of the table's total size is
+    /// reported to [`ResourceLimiter::host_resources_growing`]. Memory owned
+    /// indirectly by a resource, such as the contents of a `Vec`, isn't
+    /// included. Pushing a resource fails with [`ResourceTableError::Full`] if
+    /// the limiter rejects the growth, or with [`ResourceTableError::Limiter`]
+    /// if it returns an error. Deleting a resource, or dropping the table,
+    /// reports the shrinkage to [`ResourceLimiter::host_resources_released`].
+    ///
+    /// The size of the resources already in the table is reported to
+    /// `limiter` right away, as growth from zero, and an error is returned,
+    /// leaving the table unchanged, if it's rejected. Any previous limiter is
+    /// told that all of the table has been released.
+    pub fn set_limiter(&mut self, mut limiter: SharedResourceLimiter) -> Result<()> {
+        if self.size > 0 && !limiter.host_resources_growing(0, self.size, None)? {
+            bail!(
+                "resource table of {} bytes rejected by the resource limiter",
+                self.size
+            );
+        }
+        if let Some(mut old) = self.limiter.replace(limiter) {
+            old.host_resources_released(self.size, 0);
+        }
+        Ok(())
+    }
+
     /// Inserts a new value `T` into this table, returning a corresponding
     /// `Resource<T>` which can be used to refer to it after it was inserted.
     pub fn push<T>(&mut self, entry: T) -> Result<Resource<T>, ResourceTableError>
@@ -152,6 +205,11 @@ impl ResourceTable {
         };
 
         self.free_head = Some(ix);
+        let new_size = self.size - entry.size();
+        if let Some(limiter) = &mut self.limiter {
+            limiter.host_resources_released(self.size, new_size);
+        }
+        self.size = new_size;
 
         entry
     }
@@ -159,18 +217,26 @@ impl ResourceTable {
     /// Push a new entry into the table, returning its handle. This will prefer to use free entries
     /// if they exist, falling back on pushing new entries onto the end of the table.
     fn push_(&mut self, e: TableEntry) -> Result<u32, ResourceTableError> {
+        let new_size = self.size + e.size();
+        let ix = match self.free_head {
+            Some(free) => free,
+            None => self.entries.len(),
+        };
+        let ix = u32::try_from(ix).map_err(|_| ResourceTableError::Full)?;
+        if let Some(limiter) = &mut self.limiter {
+            match limiter.host_resources_growing(self.size, new_size, None) {
+                Ok(true) => {}
+                Ok(false) => return Err(ResourceTableError::Full),
+                Err(e) => return Err(ResourceTableError::Limiter(e)),
+            }
+        }
         if let Some(free) = self.pop_free_list() {
             self.entries[free] = Entry::Occupied { entry: e };
-            Ok(free.try_into().unwrap())
         } else {
-            let ix = self
-                .entries
-                .len()
-                .try_into()
-                .map_err(|_| ResourceTableError::Full)?;
             self.entries.push(Entry::Occupied { entry: e });
-            Ok(ix)
         }
+        self.size = new_size;
+        Ok(ix)
     }
 
     fn occupied(&self, key: u32) -> Result<&TableEntry, ResourceTableError> {
@@ -324,6 +390,14 @@ impl Default for ResourceTable {
     }
 }
 
+impl Drop for ResourceTable {
+    fn drop(&mut self) {
+        if let Some(limiter) = &mut self.limiter {
+            limiter.host_resources_released(self.size, 0);
+        }
+    }
+}
+
 #[test]
 pub fn test_free_list() {
     let mut table = ResourceTable::new();
@@ -353,3 +427,71 @@ pub fn test_free_list() {
     let x = table.push(()).unwrap();
     assert_eq!(x.rep(), 2);
 }
+
+#[test]
+pub fn test_limiter() {
+    use crate::{MemoryBudget, MemoryCategory, StoreLimitsBuilder};
+
+    let budget = MemoryBudget::new(1024);
+    let limiter = SharedResourceLimiter::new(
+        StoreLimitsBuilder::new()
+            .memory_budget(budget.clone())
+            .build(),
+    );
+    let mut table = ResourceTable::new();
+    let x = table.push([0u8; 64]).unwrap();
+    table.set_limiter(limiter.clone()).unwrap();
+    let charged = budget.used_by(MemoryCategory::HostResources);
+    assert!(charged >= 64);
+
+    // Resources can be pushed until the limiter rejects the growth.
+    let mut pushed = Vec::new();
+    loop {
+        match table.push([0u8; 64]) {
+            Ok(r) => pushed.push(r),
+            Err(ResourceTableError::Full) => break,
+            Err(e) => panic!("unexpected error: {e}"),
+        }
+    }
+    assert!(!pushed.is_empty());
+    assert!(budget.used() <= budget.limit());
+
+    // Deleting releases the resource's charge, making room again.
+    let used = budget.used();
+    table.delete(pushed.pop().unwrap()).unwrap();
+    assert!(budget.used() < used);
+    table.push([0u8; 64]).unwrap();
+    assert_eq!(budget.used(), used);
+
+    table.delete(x).unwrap();
+    assert!(budget.used_by(MemoryCategory::HostResources) < used);
+    drop(table);
+    assert_eq!(budget.used_by(MemoryCategory::HostResources), 0);
+    drop(limiter);
+    assert_eq!(budget.used(), 0);
+}
+
+#[test]
+pub fn test_limiter_error() {
+    struct Failing;
+    impl ResourceLimiter for Failing {
+        fn memory_growing(&mut self, _: usize, _: usize, _: Option<usize>) -> Result<bool> {
+            Ok(true)
+        }
+        fn table_growing(&mut self, _: usize, _: usize, _: Option<usize>) -> Result<bool> {
+            Ok(true)
+        }
+        fn host_resources_growing(&mut self, _: usize, _: usize, _: Option<usize>) -> Result<bool> {
+            bail!("no more resources")
+        }
+    }
+
+    let mut table = ResourceTable::new();
+    table
+        .set_limiter(SharedResourceLimiter::new(Failing))
+        .unwrap();
+    match table.push(()) {
+        Err(ResourceTableError::Limiter(e)) => assert_eq!(e.to_string(), "no more resources"),
+        other => panic!("unexpected result: {other:?}"),
+    }
+}
@@ -5,7 +5,7 @@ use crate::prelude::*;
 use crate::runtime::vm::component::{ComponentInstance, InstanceFlags, ResourceTables};
 use crate::runtime::vm::{SendSyncPtr, VMFuncRef, ValRaw};
 use crate::store::{StoreId, StoreOpaque};
-use crate::{AsContextMut, StoreContextMut, Trap};
+use crate::{AsContext, AsContextMut, StoreContextMut, Trap};
 use core::any::TypeId;
 use core::fmt;
 use core::marker;
@@ -56,6 +56,22 @@ impl ResourceType {
         }
     }
 
+    /// Returns the name of this resource type, if known.
+    ///
+    /// Resource types are named after the name they're imported or exported
+    /// under by the components instantiated within `store`, for example
+    /// `descriptor` for a resource exported from the `wasi:filesystem/types`
+    /// interface. The first name seen for a type is the one returned here.
+    /// Returns `None` if no component in `store` has imported or exported
+    /// this resource type.
+    pub fn name(&self, store: impl AsContext) -> Option<String> {
+        let store = store.as_context().0;
+        store
+            .component_instances()
+            .iter()
+            .find_map(|instance| instance.resource_type_name(store, *self))
+    }
+
     pub(crate) fn guest(
         store: StoreId,
         instance: &ComponentInstance,
@@ -70,6 +86,15 @@ impl ResourceType {
         }
     }
 
+    /// Returns the store that this resource type was defined in, if it's a
+    /// guest-defined resource type.
+    pub(crate) fn store(&self) -> Option<StoreId> {
+        match self.kind {
+            ResourceTypeKind::Guest { store, .. } => Some(store),
+            ResourceTypeKind::Host(_) | ResourceTypeKind::Uninstantiated { .. } => None,
+        }
+    }
+
     pub(crate) fn uninstantiated(types: &ComponentTypes, index: ResourceIndex) -> ResourceType {
         ResourceType {
             kind: ResourceTypeKind::Uninstantiated {
@@ -959,6 +984,13 @@ impl ResourceAny {
         self.owned
     }
 
+    /// Returns the name of this resource's type, if known.
+    ///
+    /// See [`ResourceType::name`] for more information.
+    pub fn type_name(&self, store: impl AsContext) -> Option<String> {
+        self.ty.name(store)
+    }
+
     /// Destroy this resource and release any state associated with it.
     ///
     /// This is required to be called (or the async version) for all instances
@@ -992,7 +1024,7 @@ impl ResourceAny {
             .await?
     }
 
-    fn resource_drop_impl<T>(self, store: &mut StoreContextMut<'_, T>) -> Result<()> {
+    pub(crate) fn resource_drop_impl<T>(self, store: &mut StoreContextMut<'_, T>) -> Result<()> {
         // Attempt to remove `self.idx` from the host table in `store`.
         //
         // This could fail if the index is invalid or if this is removing an
@@ -1059,7 +1091,7 @@ impl ResourceAny {
 
     fn lift_from_index(cx: &mut LiftContext<'_>, ty: InterfaceType, index: u32) -> Result<Self> {
         match ty {
-            InterfaceType::Own(t) => {
+            InterfaceType::Own(t) if !cx.borrows_owned() => {
                 let ty = cx.resource_type(t);
                 let (rep, dtor, flags) = cx.guest_resource_lift_own(t, index)?;
                 let idx = cx.host_resource_lower_own(rep, dtor, flags)?;
@@ -1069,7 +1101,7 @@ impl ResourceAny {
                     owned: true,
                 })
             }
-            InterfaceType::Borrow(t) => {
+            InterfaceType::Own(t) | InterfaceType::Borrow(t) => {
                 let ty = cx.resource_type(t);
                 let rep = cx.guest_resource_lift_borrow(t, index)?;
                 let idx = cx.host_resource_lower_borrow(rep)?;
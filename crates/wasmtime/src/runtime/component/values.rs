@@ -602,10 +602,75 @@ impl Val {
     }
 
     /// Serialize a [`Val`] to its [`crate::component::wasm_wave`] encoding.
+    ///
+    /// WAVE cannot represent resources, so this fails for values which contain
+    /// a [`Val::Resource`]; see [`Val::to_wave_with_handles`] for those.
     #[cfg(feature = "wave")]
     pub fn to_wave(&self) -> Result<String> {
+        if self.has_resources() {
+            bail!("cannot encode a resource as WAVE without a `WaveHandles` table");
+        }
         Ok(wasm_wave::to_string(self)?)
     }
+
+    /// Deserialize a [`Val`] from its [`crate::component::wasm_wave`]
+    /// encoding, extended with `@handle(N)` to name the resources in
+    /// `handles`.
+    ///
+    /// Resources passed to an `own` are removed from `handles` as ownership of
+    /// them is transferred to the callee. Resources passed to a `borrow` remain
+    /// in `handles`.
+    #[cfg(feature = "wave")]
+    pub fn from_wave_with_handles(
+        ty: &crate::component::Type,
+        s: &str,
+        handles: &mut crate::component::WaveHandles,
+    ) -> Result<Self> {
+        crate::runtime::wave::from_wave_with_handles(ty, s, handles)
+    }
+
+    /// Deserialize the arguments of a function with parameters of types
+    /// `params` from a [`crate::component::wasm_wave`]-encoded tuple such as
+    /// `(1, "two", @handle(3))`.
+    ///
+    /// Handles are resolved as with [`Val::from_wave_with_handles`]. Trailing
+    /// arguments of `option` types may be omitted, in which case they're
+    /// `none`.
+    #[cfg(feature = "wave")]
+    pub fn from_wave_args_with_handles(
+        params: &[crate::component::Type],
+        s: &str,
+        handles: &mut crate::component::WaveHandles,
+    ) -> Result<Vec<Self>> {
+        crate::runtime::wave::from_wave_args_with_handles(params, s, handles)
+    }
+
+    /// Serialize a [`Val`] to its [`crate::component::wasm_wave`] encoding,
+    /// extended with `@handle(N)` to name resources.
+    ///
+    /// Each resource within this value is added to `handles`, keeping it
+    /// available to later calls to [`Val::from_wave_with_handles`].
+    #[cfg(feature = "wave")]
+    pub fn to_wave_with_handles(
+        &self,
+        handles: &mut crate::component::WaveHandles,
+    ) -> Result<String> {
+        crate::runtime::wave::to_wave_with_handles(self, handles)
+    }
+
+    #[cfg(feature = "wave")]
+    fn has_resources(&self) -> bool {
+        match self {
+            Val::Resource(_) => true,
+            Val::List(vals) | Val::Tuple(vals) => vals.iter().any(|v| v.has_resources()),
+            Val::Record(fields) => fields.iter().any(|(_, v)| v.has_resources()),
+            Val::Variant(_, Some(v))
+            | Val::Option(Some(v))
+            | Val::Result(Ok(Some(v)))
+            | Val::Result(Err(Some(v))) => v.has_resources(),
+            _ => false,
+        }
+    }
 }
 
 impl PartialEq for Val {
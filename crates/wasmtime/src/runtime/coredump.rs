@@ -1,5 +1,7 @@
 use crate::hash_map::HashMap;
 use crate::prelude::*;
+#[cfg(feature = "component-model")]
+use crate::runtime::vm::CompiledModuleId;
 use crate::{
     store::StoreOpaque, AsContextMut, FrameInfo, Global, HeapType, Instance, Memory, Module,
     StoreContextMut, Val, ValType, WasmBacktrace,
@@ -35,6 +37,12 @@ pub struct WasmCoreDump {
     memories: Vec<Memory>,
     globals: Vec<Global>,
     backtrace: WasmBacktrace,
+    #[cfg(feature = "component-model")]
+    components: ComponentCoreDump,
+    /// The module of each core instance created by a component, along with
+    /// the instance's index within `instances`.
+    #[cfg(feature = "component-model")]
+    component_module_instances: Vec<(CompiledModuleId, usize)>,
 }
 
 impl WasmCoreDump {
@@ -46,6 +54,11 @@ impl WasmCoreDump {
         let mut store_globals: Vec<Global> = vec![];
         store.for_each_global(|_store, global| store_globals.push(global));
 
+        #[cfg(feature = "component-model")]
+        let components = ComponentCoreDump::new(store, &instances, backtrace.frames());
+        #[cfg(feature = "component-model")]
+        let component_module_instances = ComponentCoreDump::module_instances(store, &instances);
+
         WasmCoreDump {
             name: String::from("store_name"),
             modules,
@@ -53,6 +66,10 @@ impl WasmCoreDump {
             memories: store_memories,
             globals: store_globals,
             backtrace,
+            #[cfg(feature = "component-model")]
+            components,
+            #[cfg(feature = "component-model")]
+            component_module_instances,
         }
     }
 
@@ -87,17 +104,55 @@ impl WasmCoreDump {
         self.memories.as_ref()
     }
 
+    /// All component instances within the store when the core dump was
+    /// created, in the order they were instantiated.
+    #[cfg(feature = "component-model")]
+    pub fn component_instances(&self) -> &[CoreDumpComponentInstance] {
+        &self.components.instances
+    }
+
+    /// The component-level calls which were in progress when the core dump
+    /// was created: lifted functions called by the host, and calls between
+    /// the component instances nested within a component through fused
+    /// adapters.
+    ///
+    /// Like [`WasmCoreDump::frames`] these appear in callee to caller order.
+    #[cfg(feature = "component-model")]
+    pub fn component_frames(&self) -> &[CoreDumpComponentFrame] {
+        &self.components.frames
+    }
+
+    /// The resource handles owned or borrowed by the host, such as those held
+    /// by a [`ResourceAny`](crate::component::ResourceAny), when the core
+    /// dump was created.
+    #[cfg(feature = "component-model")]
+    pub fn host_resource_handles(&self) -> &[CoreDumpResourceHandle] {
+        &self.components.host_handles
+    }
+
+    /// All of the component-level state of this core dump, as serialized
+    /// into its `component-coredump` custom section.
+    #[cfg(feature = "component-model")]
+    pub fn component_core_dump(&self) -> &ComponentCoreDump {
+        &self.components
+    }
+
     /// Serialize this core dump into [the standard core dump binary
     /// format][spec].
     ///
     /// The `name` parameter may be a file path, URL, or arbitrary name for the
     /// "main" Wasm service or executable that was running in this store.
     ///
+    /// If any components were instantiated in the store then their state is
+    /// additionally described in a [`component-coredump` custom
+    /// section][component].
+    ///
     /// Once serialized, you can write this core dump to disk, send it over the
     /// network, or pass it to other debugging tools that consume Wasm core
     /// dumps.
     ///
     /// [spec]: https://github.com/WebAssembly/tool-conventions/blob/main/Coredump.md
+    /// [component]: https://docs.wasmtime.dev/examples-coredump.html#component-coredumps
     pub fn serialize(&self, mut store: impl AsContextMut, name: &str) -> Vec<u8> {
         let store = store.as_context_mut();
         self._serialize(store, name)
@@ -263,7 +318,11 @@ impl WasmCoreDump {
                     .all_globals(&mut store.0)
                     .collect::<Vec<_>>()
                     .into_iter()
-                    .map(|(_i, global)| global_to_idx[&global.hash_key(&store.0)])
+                    // Globals which aren't part of the store, such as the
+                    // flags of component instances imported by fused
+                    // adapters, aren't included in the core dump.
+                    .filter_map(|(_i, global)| global_to_idx.get(&global.hash_key(&store.0)))
+                    .copied()
                     .collect::<Vec<_>>();
 
                 instances.instance(module_index, memories, globals);
@@ -271,6 +330,14 @@ impl WasmCoreDump {
             core_dump.section(&instances);
         }
 
+        // The modules of a component share one code object, so
+        // `Instance::module` above can't tell them apart. Their instances are
+        // instead looked up through the component that created them.
+        #[cfg(feature = "component-model")]
+        for (module, instance) in self.component_module_instances.iter() {
+            module_to_instance.insert(*module, u32::try_from(*instance).unwrap());
+        }
+
         {
             let thread_name = "main";
             let mut stack = wasm_encoder::CoreDumpStackSection::new(thread_name);
@@ -297,6 +364,11 @@ impl WasmCoreDump {
             core_dump.section(&stack);
         }
 
+        #[cfg(feature = "component-model")]
+        if !self.components.instances.is_empty() {
+            core_dump.section(&self.components.encode());
+        }
+
         core_dump.finish()
     }
 }
@@ -324,6 +396,11 @@ impl fmt::Display for WasmCoreDump {
             writeln!(f, "  {global:?}")?;
         }
 
+        #[cfg(feature = "component-model")]
+        if !self.components.instances.is_empty() {
+            write!(f, "{}", self.components)?;
+        }
+
         writeln!(f, "backtrace:")?;
         write!(f, "{}", self.backtrace)?;
 
@@ -336,3 +413,520 @@ impl fmt::Debug for WasmCoreDump {
         write!(f, "<wasm core dump>")
     }
 }
+
+/// The component-level state of a [`WasmCoreDump`].
+///
+/// This is serialized into the `component-coredump` custom section of a core
+/// dump, which can be read back with [`ComponentCoreDump::parse`].
+#[cfg(feature = "component-model")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentCoreDump {
+    instances: Vec<CoreDumpComponentInstance>,
+    frames: Vec<CoreDumpComponentFrame>,
+    host_handles: Vec<CoreDumpResourceHandle>,
+}
+
+#[cfg(feature = "component-model")]
+impl ComponentCoreDump {
+    /// Name of the custom section that describes the component-level state
+    /// of a serialized core dump.
+    pub const SECTION_NAME: &'static str = "component-coredump";
+
+    /// Version of the custom section's format, bumped on every change.
+    const VERSION: u32 = 2;
+
+    fn new(
+        store: &StoreOpaque,
+        core_instances: &[Instance],
+        core_frames: &[FrameInfo],
+    ) -> ComponentCoreDump {
+        let core_instance_ids = core_instances
+            .iter()
+            .map(|i| i.id(store))
+            .collect::<Vec<_>>();
+        let components = store
+            .component_instances()
+            .iter()
+            .filter(|c| store[c.0].is_some())
+            .collect::<Vec<_>>();
+
+        let instances = components
+            .iter()
+            .map(|c| CoreDumpComponentInstance {
+                core_instances: c
+                    ._core_instances(store)
+                    .into_iter()
+                    .filter_map(|(_, i)| {
+                        let id = i.id(store);
+                        core_instance_ids.iter().position(|other| *other == id)
+                    })
+                    .collect(),
+                resource_tables: c
+                    .resource_handles(store)
+                    .into_iter()
+                    .map(|(index, handles)| CoreDumpResourceTable {
+                        index,
+                        handles: handles
+                            .into_iter()
+                            .map(CoreDumpResourceHandle::new)
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let frames = Self::call_stack(store, &components, core_frames);
+
+        let host_handles = store
+            .component_host_table()
+            .handles()
+            .map(CoreDumpResourceHandle::new)
+            .collect();
+
+        ComponentCoreDump {
+            instances,
+            frames,
+            host_handles,
+        }
+    }
+
+    /// Returns the module of each core instance created by a component, along
+    /// with the instance's index within `core_instances`.
+    fn module_instances(
+        store: &StoreOpaque,
+        core_instances: &[Instance],
+    ) -> Vec<(CompiledModuleId, usize)> {
+        let mut module_instances = Vec::new();
+        for component in store.component_instances() {
+            if store[component.0].is_none() {
+                continue;
+            }
+            let core = component._core_instances(store);
+            for ((_, module), (_, instance)) in component.core_modules(store).into_iter().zip(core)
+            {
+                let id = instance.id(store);
+                if let Some(i) = core_instances.iter().position(|i| i.id(store) == id) {
+                    module_instances.push((module.id(), i));
+                }
+            }
+        }
+        module_instances
+    }
+
+    /// Reconstructs the component-level call stack, youngest first, from the
+    /// calls made by the host and the fused adapters within `core_frames`.
+    ///
+    /// Core frames only identify their module, so as with the core stack in
+    /// `WasmCoreDump::serialize` this is a best effort when several component
+    /// instances share the same modules.
+    fn call_stack(
+        store: &StoreOpaque,
+        components: &[&crate::component::Instance],
+        core_frames: &[FrameInfo],
+    ) -> Vec<CoreDumpComponentFrame> {
+        let mut owners = HashMap::new();
+        let mut adapters = HashMap::new();
+        for (i, component) in components.iter().enumerate() {
+            for (_, module) in component.core_modules(store) {
+                owners.entry(module.id()).or_insert_with(Vec::new).push(i);
+            }
+            for (module, func, caller, callee) in component.adapters(store) {
+                adapters.insert((module.id(), func), (caller, callee));
+            }
+        }
+
+        let mut calls = store
+            .component_call_stack()
+            .iter()
+            .filter_map(|(instance, export)| {
+                Some(CoreDumpComponentFrame {
+                    instance: components.iter().position(|c| c.0 == instance.0)?,
+                    kind: ComponentFrameKind::Export(instance.export_name(store, *export)?),
+                })
+            })
+            .peekable();
+
+        // Walk the core stack from the oldest frame, placing each call made
+        // by the host at the first frame within the called component
+        // instance, and each adapter call at the adapter's own frame.
+        let mut frames = Vec::new();
+        let mut current = None;
+        for frame in core_frames.iter().rev() {
+            let module = frame.module().id();
+            let owners = owners.get(&module).map(|o| &o[..]).unwrap_or(&[]);
+            if let Some(call) = calls.next_if(|call| owners.contains(&call.instance)) {
+                current = Some(call.instance);
+                frames.push(call);
+            }
+            if let Some((caller, callee)) = adapters.get(&(module, frame.func_index())) {
+                let instance = current
+                    .filter(|i| owners.contains(i))
+                    .or_else(|| owners.first().copied());
+                if let Some(instance) = instance {
+                    frames.push(CoreDumpComponentFrame {
+                        instance,
+                        kind: ComponentFrameKind::Adapter {
+                            caller: *caller,
+                            callee: *callee,
+                        },
+                    });
+                }
+            }
+        }
+        frames.extend(calls);
+        frames.reverse();
+        frames
+    }
+
+    /// All component instances within the store, in the order they were
+    /// instantiated.
+    pub fn instances(&self) -> &[CoreDumpComponentInstance] {
+        &self.instances
+    }
+
+    /// The component-level calls in progress, in callee to caller order.
+    pub fn frames(&self) -> &[CoreDumpComponentFrame] {
+        &self.frames
+    }
+
+    /// The resource handles owned or borrowed by the host.
+    pub fn host_handles(&self) -> &[CoreDumpResourceHandle] {
+        &self.host_handles
+    }
+
+    /// Parses the contents of a core dump's `component-coredump` custom
+    /// section, as produced by [`WasmCoreDump::serialize`].
+    ///
+    /// See `docs/examples-coredump.md` for a description of the format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` is malformed or uses a different version
+    /// of the format.
+    pub fn parse(data: &[u8]) -> Result<ComponentCoreDump> {
+        use wasmparser::BinaryReader;
+
+        fn read_vec<'a, T>(
+            reader: &mut BinaryReader<'a>,
+            mut read: impl FnMut(&mut BinaryReader<'a>) -> Result<T>,
+        ) -> Result<Vec<T>> {
+            let len = reader.read_var_u32()?;
+            (0..len).map(|_| read(reader)).collect()
+        }
+
+        fn read_index(reader: &mut BinaryReader<'_>) -> Result<usize> {
+            Ok(usize::try_from(reader.read_var_u32()?)?)
+        }
+
+        fn read_handle(reader: &mut BinaryReader<'_>) -> Result<CoreDumpResourceHandle> {
+            let index = reader.read_var_u32()?;
+            let rep = reader.read_var_u32()?;
+            let lend_count = match reader.read_u8()? {
+                0x00 => Some(reader.read_var_u32()?),
+                0x01 => None,
+                byte => bail!("invalid resource handle kind {byte:#x}"),
+            };
+            Ok(CoreDumpResourceHandle {
+                index,
+                rep,
+                lend_count,
+            })
+        }
+
+        let mut reader = BinaryReader::new(data, 0);
+        let version = reader.read_var_u32()?;
+        if version != Self::VERSION {
+            bail!(
+                "unsupported component coredump version {version}, expected {}",
+                Self::VERSION
+            );
+        }
+
+        let instances = read_vec(&mut reader, |reader| {
+            Ok(CoreDumpComponentInstance {
+                core_instances: read_vec(reader, read_index)?,
+                resource_tables: read_vec(reader, |reader| {
+                    Ok(CoreDumpResourceTable {
+                        index: reader.read_var_u32()?,
+                        handles: read_vec(reader, read_handle)?,
+                    })
+                })?,
+            })
+        })?;
+
+        let frames = read_vec(&mut reader, |reader| {
+            let kind = reader.read_u8()?;
+            let instance = read_index(reader)?;
+            let kind = match kind {
+                0x00 => ComponentFrameKind::Export(reader.read_string()?.to_string()),
+                0x01 => ComponentFrameKind::Adapter {
+                    caller: reader.read_var_u32()?,
+                    callee: reader.read_var_u32()?,
+                },
+                byte => bail!("invalid component frame kind {byte:#x}"),
+            };
+            Ok(CoreDumpComponentFrame { instance, kind })
+        })?;
+
+        let host_handles = read_vec(&mut reader, read_handle)?;
+
+        if !reader.eof() {
+            bail!("trailing bytes at the end of the component coredump");
+        }
+
+        Ok(ComponentCoreDump {
+            instances,
+            frames,
+            host_handles,
+        })
+    }
+
+    /// Encodes this state in the `component-coredump` custom section, see
+    /// `docs/examples-coredump.md` for a description of the format.
+    fn encode(&self) -> wasm_encoder::CustomSection<'static> {
+        use wasm_encoder::Encode;
+
+        fn encode_handles(handles: &[CoreDumpResourceHandle], data: &mut Vec<u8>) {
+            handles.len().encode(data);
+            for handle in handles {
+                handle.index.encode(data);
+                handle.rep.encode(data);
+                match handle.lend_count {
+                    Some(lend_count) => {
+                        data.push(0x00);
+                        lend_count.encode(data);
+                    }
+                    None => data.push(0x01),
+                }
+            }
+        }
+
+        fn encode_index(index: usize, data: &mut Vec<u8>) {
+            u32::try_from(index).unwrap().encode(data);
+        }
+
+        let mut data = Vec::new();
+        Self::VERSION.encode(&mut data);
+
+        self.instances.len().encode(&mut data);
+        for instance in self.instances.iter() {
+            instance.core_instances.len().encode(&mut data);
+            for index in instance.core_instances.iter() {
+                encode_index(*index, &mut data);
+            }
+            instance.resource_tables.len().encode(&mut data);
+            for table in instance.resource_tables.iter() {
+                table.index.encode(&mut data);
+                encode_handles(&table.handles, &mut data);
+            }
+        }
+
+        self.frames.len().encode(&mut data);
+        for frame in self.frames.iter() {
+            match &frame.kind {
+                ComponentFrameKind::Export(export) => {
+                    data.push(0x00);
+                    encode_index(frame.instance, &mut data);
+                    export.encode(&mut data);
+                }
+                ComponentFrameKind::Adapter { caller, callee } => {
+                    data.push(0x01);
+                    encode_index(frame.instance, &mut data);
+                    caller.encode(&mut data);
+                    callee.encode(&mut data);
+                }
+            }
+        }
+
+        encode_handles(&self.host_handles, &mut data);
+
+        wasm_encoder::CustomSection {
+            name: Self::SECTION_NAME.into(),
+            data: data.into(),
+        }
+    }
+}
+
+#[cfg(feature = "component-model")]
+impl fmt::Display for ComponentCoreDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn handles(f: &mut fmt::Formatter<'_>, handles: &[CoreDumpResourceHandle]) -> fmt::Result {
+            for handle in handles {
+                write!(f, "      handle {}: rep {}", handle.index, handle.rep)?;
+                match handle.lend_count {
+                    Some(n) => writeln!(f, ", own (lent {n} times)")?,
+                    None => writeln!(f, ", borrow")?,
+                }
+            }
+            Ok(())
+        }
+
+        writeln!(f, "component instances:")?;
+        for (i, instance) in self.instances.iter().enumerate() {
+            writeln!(f, "  {i}: core instances {:?}", instance.core_instances)?;
+            for table in instance.resource_tables.iter() {
+                writeln!(f, "    resource table {}:", table.index)?;
+                handles(f, &table.handles)?;
+            }
+        }
+
+        writeln!(f, "component frames:")?;
+        for frame in self.frames.iter() {
+            match &frame.kind {
+                ComponentFrameKind::Export(export) => {
+                    writeln!(f, "  {export} (instance {})", frame.instance)?
+                }
+                ComponentFrameKind::Adapter { caller, callee } => writeln!(
+                    f,
+                    "  adapter from {caller} to {callee} (instance {})",
+                    frame.instance
+                )?,
+            }
+        }
+
+        if !self.host_handles.is_empty() {
+            writeln!(f, "host resource handles:")?;
+            handles(f, &self.host_handles)?;
+        }
+        Ok(())
+    }
+}
+
+/// A component instance within a [`WasmCoreDump`].
+#[cfg(feature = "component-model")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoreDumpComponentInstance {
+    core_instances: Vec<usize>,
+    resource_tables: Vec<CoreDumpResourceTable>,
+}
+
+#[cfg(feature = "component-model")]
+impl CoreDumpComponentInstance {
+    /// The core wasm instances created by this component instance, as indices
+    /// into [`WasmCoreDump::instances`].
+    pub fn core_instances(&self) -> &[usize] {
+        &self.core_instances
+    }
+
+    /// The resource tables of this component instance which contained live
+    /// handles.
+    pub fn resource_tables(&self) -> &[CoreDumpResourceTable] {
+        &self.resource_tables
+    }
+}
+
+/// A resource table of a component instance within a [`WasmCoreDump`].
+///
+/// Components have one table of handles per resource type that they use.
+#[cfg(feature = "component-model")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoreDumpResourceTable {
+    index: u32,
+    handles: Vec<CoreDumpResourceHandle>,
+}
+
+#[cfg(feature = "component-model")]
+impl CoreDumpResourceTable {
+    /// The index of this table within its component, which is assigned in
+    /// order of the component's uses of resource types.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The live handles within this table.
+    pub fn handles(&self) -> &[CoreDumpResourceHandle] {
+        &self.handles
+    }
+}
+
+/// A live resource handle within a [`WasmCoreDump`].
+#[cfg(feature = "component-model")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoreDumpResourceHandle {
+    index: u32,
+    rep: u32,
+    lend_count: Option<u32>,
+}
+
+#[cfg(feature = "component-model")]
+impl CoreDumpResourceHandle {
+    fn new((index, rep, lend_count): (u32, u32, Option<u32>)) -> CoreDumpResourceHandle {
+        CoreDumpResourceHandle {
+            index,
+            rep,
+            lend_count,
+        }
+    }
+
+    /// The handle's index within its table, as seen by wasm.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The representation of the resource this handle refers to.
+    pub fn rep(&self) -> u32 {
+        self.rep
+    }
+
+    /// Whether this is an `own` handle, as opposed to a `borrow`.
+    pub fn is_own(&self) -> bool {
+        self.lend_count.is_some()
+    }
+
+    /// The number of outstanding borrows of an `own` handle.
+    pub fn lend_count(&self) -> u32 {
+        self.lend_count.unwrap_or(0)
+    }
+}
+
+/// A component-level call within a [`WasmCoreDump`].
+///
+/// This is either a call from the host to a lifted function, or a call
+/// between two component instances nested within the same component through
+/// a fused adapter.
+#[cfg(feature = "component-model")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoreDumpComponentFrame {
+    instance: usize,
+    kind: ComponentFrameKind,
+}
+
+#[cfg(feature = "component-model")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ComponentFrameKind {
+    Export(String),
+    Adapter { caller: u32, callee: u32 },
+}
+
+#[cfg(feature = "component-model")]
+impl CoreDumpComponentFrame {
+    /// The component instance being called, as an index into
+    /// [`WasmCoreDump::component_instances`].
+    pub fn instance(&self) -> usize {
+        self.instance
+    }
+
+    /// The name of the export called by the host, where exports of an
+    /// exported instance are named like `wasi:cli/run@0.2.0#run`.
+    ///
+    /// Returns `None` for calls through a fused adapter.
+    pub fn export(&self) -> Option<&str> {
+        match &self.kind {
+            ComponentFrameKind::Export(export) => Some(export),
+            ComponentFrameKind::Adapter { .. } => None,
+        }
+    }
+
+    /// For calls through a fused adapter, the `(caller, callee)` component
+    /// instances nested within [`instance`](Self::instance). These are
+    /// numbered in the order they're instantiated, starting with the
+    /// outermost component itself as 0.
+    ///
+    /// Returns `None` for calls made by the host.
+    pub fn adapter(&self) -> Option<(u32, u32)> {
+        match self.kind {
+            ComponentFrameKind::Export(_) => None,
+            ComponentFrameKind::Adapter { caller, callee } => Some((caller, callee)),
+        }
+    }
+}
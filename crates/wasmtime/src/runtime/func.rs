@@ -1014,7 +1014,7 @@ impl Func {
         if need_gc {
             store.0.gc();
         }
-        unsafe { self.call_impl_do_call(&mut store, params, results) }
+        unsafe { self.call_impl_do_call(&mut store, None, params, results) }
     }
 
     /// Invokes this function in an "unchecked" fashion, reading parameters and
@@ -1155,7 +1155,7 @@ impl Func {
             store.0.gc_async().await;
         }
         let result = store
-            .on_fiber(|store| unsafe { self.call_impl_do_call(store, params, results) })
+            .on_fiber(|store| unsafe { self.call_impl_do_call(store, None, params, results) })
             .await??;
         Ok(result)
     }
@@ -1227,9 +1227,13 @@ impl Func {
     /// You must have type checked the arguments by calling
     /// `call_impl_check_args` immediately before calling this function. It is
     /// only safe to call this function if that one did not return an error.
+    ///
+    /// If `caller` is provided then this is a call made from within a host
+    /// function on behalf of that instance, see [`Caller::call_func`].
     unsafe fn call_impl_do_call<T>(
         &self,
         store: &mut StoreContextMut<'_, T>,
+        caller: Option<*mut VMContext>,
         params: &[Val],
         results: &mut [Val],
     ) -> Result<()> {
@@ -1245,11 +1249,23 @@ impl Func {
             }
         }
 
-        unsafe {
-            self.call_unchecked(
-                &mut *store,
-                core::ptr::slice_from_raw_parts_mut(values_vec.as_mut_ptr(), values_vec_size),
-            )?;
+        let params_and_returns =
+            core::ptr::slice_from_raw_parts_mut(values_vec.as_mut_ptr(), values_vec_size);
+        match caller {
+            None => unsafe { self.call_unchecked(&mut *store, params_and_returns)? },
+            // We're already executing within this store, so the stack limit
+            // set up when wasm was first entered still applies. Call hooks
+            // and statistics are still recorded as for any other call.
+            Some(caller) => unsafe {
+                let func_ref = store.0.store_data()[self.0].export().func_ref;
+                call_wasm_and_catch_traps(store, |_, vm| {
+                    func_ref.as_ref().array_call(
+                        vm,
+                        VMOpaqueContext::from_vmcontext(caller),
+                        params_and_returns,
+                    )
+                })?;
+            },
         }
 
         for ((i, slot), val) in results.iter_mut().enumerate().zip(&values_vec) {
@@ -1596,16 +1612,35 @@ pub(crate) fn invoke_wasm_and_catch_traps<T>(
     store: &mut StoreContextMut<'_, T>,
     closure: impl FnMut(*mut VMContext, Option<InterpreterRef<'_>>) -> bool,
 ) -> Result<()> {
-    unsafe {
-        let exit = enter_wasm(store);
+    let exit = enter_wasm(store);
+    call_wasm_and_catch_traps_impl(store, closure, |store| exit_wasm(store, exit))
+}
+
+/// Same as [`invoke_wasm_and_catch_traps`], but for a call made while wasm is
+/// already executing within `store` on the current stack, such as through
+/// [`Caller::call_func`], so the existing stack limit is left as it is.
+fn call_wasm_and_catch_traps<T>(
+    store: &mut StoreContextMut<'_, T>,
+    closure: impl FnMut(*mut VMContext, Option<InterpreterRef<'_>>) -> bool,
+) -> Result<()> {
+    call_wasm_and_catch_traps_impl(store, closure, |_| {})
+}
 
+fn call_wasm_and_catch_traps_impl<T>(
+    store: &mut StoreContextMut<'_, T>,
+    closure: impl FnMut(*mut VMContext, Option<InterpreterRef<'_>>) -> bool,
+    exit: impl FnOnce(&mut StoreContextMut<'_, T>),
+) -> Result<()> {
+    unsafe {
         if let Err(trap) = store.0.call_hook(CallHook::CallingWasm) {
-            exit_wasm(store, exit);
+            exit(store);
             return Err(trap);
         }
         let result = crate::runtime::vm::catch_traps(store, closure);
-        exit_wasm(store, exit);
-        store.0.call_hook(CallHook::ReturningFromWasm)?;
+        exit(store);
+        let hook = store.0.call_hook(CallHook::ReturningFromWasm);
+        store.0.returned_from_wasm(result.is_err() || hook.is_err());
+        hook?;
         result.map_err(|t| crate::trap::from_runtime_box(store.0, t))
     }
 }
@@ -2064,6 +2099,36 @@ impl<T> Caller<'_, T> {
         }
     }
 
+    /// Calls `func` as though it were called directly by the caller's
+    /// instance.
+    ///
+    /// This is the same as [`Func::call`] except that if `func` is a host
+    /// function then it receives a [`Caller`] for the same instance as this
+    /// one, so that, for example, [`Caller::get_export`] finds the same
+    /// exports within it. This makes it possible to wrap host functions
+    /// defined elsewhere with additional behavior.
+    ///
+    /// Unlike [`Func::call`], this may be used within a [`Store`] with async
+    /// support enabled, in which case `func` may be an async host function.
+    ///
+    /// [`Store`]: crate::Store
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in the same situations as [`Func::call`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `func` does not belong to the caller's store.
+    pub fn call_func(&mut self, func: &Func, params: &[Val], results: &mut [Val]) -> Result<()> {
+        let need_gc = func.call_impl_check_args(&mut self.store, params, results)?;
+        if need_gc {
+            self.store.0.gc();
+        }
+        let caller = self.caller.vmctx();
+        unsafe { func.call_impl_do_call(&mut self.store, Some(caller), params, results) }
+    }
+
     /// Looks up an export from the caller's module by the `name` given.
     ///
     /// This is a low-level function that's typically used to implement passing
@@ -319,8 +319,9 @@ impl ArrayRef {
         // Allocate the array and write each field value into the appropriate
         // offset.
         let arrayref = store
-            .gc_store_mut()?
-            .alloc_uninit_array(allocator.type_index(), len, allocator.layout())
+            .retry_after_gc_heap_growth(allocator.layout().layout(len).size(), |gc_store| {
+                gc_store.alloc_uninit_array(allocator.type_index(), len, allocator.layout())
+            })
             .context("unrecoverable error when allocating new `arrayref`")?
             .ok_or_else(|| GcHeapOutOfMemory::new(()))?;
 
@@ -395,6 +396,78 @@ impl ArrayRef {
         return Self::_new_unchecked(store, allocator, elems.iter());
     }
 
+    /// Allocate a new `array` while restoring an
+    /// [`InstanceSnapshot`][crate::InstanceSnapshot].
+    ///
+    /// This is like `_new_fixed`, except that each `None` element, which is
+    /// only allowed if the array's elements are GC references, is initialized
+    /// to null even if the element type is not nullable. Those elements must be
+    /// filled in with `_restore_elem` before the array is made available to
+    /// Wasm or the embedder.
+    pub(crate) fn _new_for_restore(
+        store: &mut StoreOpaque,
+        allocator: &ArrayRefPre,
+        elems: &[Option<Val>],
+    ) -> Result<Rooted<ArrayRef>> {
+        assert_eq!(
+            store.id(),
+            allocator.store_id,
+            "attempted to use a `ArrayRefPre` with the wrong store"
+        );
+
+        let elem_ty = allocator.ty.element_type();
+        for elem in elems {
+            match elem {
+                Some(elem) => elem
+                    .ensure_matches_ty(store, elem_ty.unpack())
+                    .context("element type mismatch")?,
+                None => ensure!(
+                    elem_ty
+                        .unpack()
+                        .as_ref()
+                        .is_some_and(|r| r.heap_type().is_vmgcref_type()),
+                    "element type mismatch: expected GC reference elements, found `{}`",
+                    elem_ty.unpack()
+                ),
+            }
+        }
+
+        let len = u32::try_from(elems.len()).context("too many array elements")?;
+        let arrayref = store
+            .retry_after_gc_heap_growth(allocator.layout().layout(len).size(), |gc_store| {
+                gc_store.alloc_uninit_array(allocator.type_index(), len, allocator.layout())
+            })
+            .context("unrecoverable error when allocating new `arrayref`")?
+            .ok_or_else(|| GcHeapOutOfMemory::new(()))?;
+
+        let mut store = AutoAssertNoGc::new(store);
+        match (|| {
+            for (i, elem) in elems.iter().enumerate() {
+                let i = u32::try_from(i).unwrap();
+                match elem {
+                    Some(elem) => arrayref.initialize_elem(
+                        &mut store,
+                        allocator.layout(),
+                        &elem_ty,
+                        i,
+                        *elem,
+                    )?,
+                    None => store
+                        .gc_store_mut()?
+                        .gc_object_data(arrayref.as_gc_ref())
+                        .write_u32(allocator.layout().elem_offset(i), 0),
+                }
+            }
+            Ok(())
+        })() {
+            Ok(()) => Ok(Rooted::new(&mut store, arrayref.into())),
+            Err(e) => {
+                store.gc_store_mut()?.dealloc_uninit_array(arrayref);
+                Err(e)
+            }
+        }
+    }
+
     #[inline]
     pub(crate) fn comes_from_same_store(&self, store: &StoreOpaque) -> bool {
         self.inner.comes_from_same_store(store)
@@ -652,14 +725,35 @@ impl ArrayRef {
             "attempted to use a value with the wrong store",
         );
 
-        let mut store = AutoAssertNoGc::new(store);
-
-        let field_ty = self.field_ty(&store)?;
+        let field_ty = self.field_ty(store)?;
         ensure!(
             field_ty.mutability().is_var(),
             "cannot set element {index}: array elements are not mutable"
         );
+        self._restore_elem(store, index, value)
+    }
+
+    /// Set this array's `index`th element, regardless of whether the array's
+    /// elements are mutable, while restoring an
+    /// [`InstanceSnapshot`][crate::InstanceSnapshot].
+    pub(crate) fn _restore_elem(
+        &self,
+        store: &mut StoreOpaque,
+        index: u32,
+        value: Val,
+    ) -> Result<()> {
+        assert!(
+            self.comes_from_same_store(store),
+            "attempted to use an array with the wrong store",
+        );
+        assert!(
+            value.comes_from_same_store(store),
+            "attempted to use a value with the wrong store",
+        );
 
+        let mut store = AutoAssertNoGc::new(store);
+
+        let field_ty = self.field_ty(&store)?;
         value
             .ensure_matches_ty(&store, &field_ty.element_type().unpack())
             .with_context(|| format!("cannot set element {index}: type mismatch"))?;
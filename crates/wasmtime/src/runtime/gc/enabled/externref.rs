@@ -2,7 +2,7 @@
 
 use super::{AnyRef, RootedGcRefImpl};
 use crate::prelude::*;
-use crate::runtime::vm::VMGcRef;
+use crate::runtime::vm::{VMExternRef, VMGcRef};
 use crate::{
     store::{AutoAssertNoGc, StoreOpaque},
     AsContextMut, GcHeapOutOfMemory, GcRefImpl, GcRootIndex, HeapType, ManuallyRooted, RefType,
@@ -205,13 +205,7 @@ impl ExternRef {
     {
         let ctx = context.as_context_mut().0;
 
-        let value: Box<dyn Any + Send + Sync> = Box::new(value);
-        let gc_ref = ctx
-            .gc_store_mut()?
-            .alloc_externref(value)
-            .context("unrecoverable error when allocating new `externref`")?
-            .map_err(|x| GcHeapOutOfMemory::<T>::new(*x.downcast().unwrap()))
-            .context("failed to allocate `externref`")?;
+        let gc_ref = Self::alloc(ctx, value)?;
 
         let mut ctx = AutoAssertNoGc::new(ctx);
         Ok(Self::from_cloned_gc_ref(&mut ctx, gc_ref.into()))
@@ -314,18 +308,39 @@ impl ExternRef {
     {
         let ctx = store.as_context_mut().0;
 
-        let value: Box<dyn Any + Send + Sync> = Box::new(value);
-        let gc_ref = ctx
-            .gc_store_mut()?
-            .alloc_externref(value)
-            .context("unrecoverable error when allocating new `externref`")?
-            .map_err(|x| GcHeapOutOfMemory::<T>::new(*x.downcast().unwrap()))
-            .context("failed to allocate `externref`")?;
+        let gc_ref = Self::alloc(ctx, value)?;
 
         let mut ctx = AutoAssertNoGc::new(ctx);
         Ok(ManuallyRooted::new(&mut ctx, gc_ref.into()))
     }
 
+    /// Allocate an `externref` holding `value`, growing the GC heap if there
+    /// isn't room for it.
+    fn alloc<T>(store: &mut StoreOpaque, value: T) -> Result<VMExternRef>
+    where
+        T: 'static + Any + Send + Sync,
+    {
+        let mut value: Option<Box<dyn Any + Send + Sync>> = Some(Box::new(value));
+        let gc_ref = store
+            .retry_after_gc_heap_growth(0, |gc_store| {
+                match gc_store.alloc_externref(value.take().unwrap())? {
+                    Ok(gc_ref) => Ok(Some(gc_ref)),
+                    Err(x) => {
+                        value = Some(x);
+                        Ok(None)
+                    }
+                }
+            })
+            .context("unrecoverable error when allocating new `externref`")?;
+        match gc_ref {
+            Some(gc_ref) => Ok(gc_ref),
+            None => Err(GcHeapOutOfMemory::<T>::new(
+                *value.unwrap().downcast().unwrap(),
+            ))
+            .context("failed to allocate `externref`"),
+        }
+    }
+
     /// Create a new `Rooted<ExternRef>` from the given GC reference.
     ///
     /// Does not invoke the `GcRuntime`'s clone hook; callers should ensure it
@@ -267,8 +267,9 @@ impl StructRef {
         // Allocate the struct and write each field value into the appropriate
         // offset.
         let structref = store
-            .gc_store_mut()?
-            .alloc_uninit_struct(allocator.type_index(), &allocator.layout())
+            .retry_after_gc_heap_growth(allocator.layout().layout().size(), |gc_store| {
+                gc_store.alloc_uninit_struct(allocator.type_index(), &allocator.layout())
+            })
             .context("unrecoverable error when allocating new `structref`")?
             .ok_or_else(|| GcHeapOutOfMemory::new(()))?;
 
@@ -297,6 +298,77 @@ impl StructRef {
         }
     }
 
+    /// Allocate a new `struct` while restoring an
+    /// [`InstanceSnapshot`][crate::InstanceSnapshot].
+    ///
+    /// This is like `_new`, except that each `None` field, which must be a GC
+    /// reference field, is initialized to null even if its type is not
+    /// nullable. Those fields must be filled in with `_restore_field` before
+    /// the struct is made available to Wasm or the embedder.
+    pub(crate) fn _new_for_restore(
+        store: &mut StoreOpaque,
+        allocator: &StructRefPre,
+        fields: &[Option<Val>],
+    ) -> Result<Rooted<StructRef>> {
+        assert_eq!(
+            store.id(),
+            allocator.store_id,
+            "attempted to use a `StructRefPre` with the wrong store"
+        );
+
+        let expected_len = allocator.ty.fields().len();
+        let actual_len = fields.len();
+        ensure!(
+            actual_len == expected_len,
+            "expected {expected_len} fields, got {actual_len}"
+        );
+        for (ty, val) in allocator.ty.fields().zip(fields) {
+            let ty = ty.element_type().unpack();
+            match val {
+                Some(val) => val
+                    .ensure_matches_ty(store, ty)
+                    .context("field type mismatch")?,
+                None => ensure!(
+                    ty.as_ref().is_some_and(|r| r.heap_type().is_vmgcref_type()),
+                    "field type mismatch: expected a GC reference field, found `{ty}`"
+                ),
+            }
+        }
+
+        let structref = store
+            .retry_after_gc_heap_growth(allocator.layout().layout().size(), |gc_store| {
+                gc_store.alloc_uninit_struct(allocator.type_index(), &allocator.layout())
+            })
+            .context("unrecoverable error when allocating new `structref`")?
+            .ok_or_else(|| GcHeapOutOfMemory::new(()))?;
+
+        let mut store = AutoAssertNoGc::new(store);
+        match (|| {
+            for (index, (ty, val)) in allocator.ty.fields().zip(fields).enumerate() {
+                match val {
+                    Some(val) => structref.initialize_field(
+                        &mut store,
+                        allocator.layout(),
+                        ty.element_type(),
+                        index,
+                        *val,
+                    )?,
+                    None => store
+                        .gc_store_mut()?
+                        .gc_object_data(structref.as_gc_ref())
+                        .write_u32(allocator.layout().fields[index], 0),
+                }
+            }
+            Ok(())
+        })() {
+            Ok(()) => Ok(Rooted::new(&mut store, structref.into())),
+            Err(e) => {
+                store.gc_store_mut()?.dealloc_uninit_struct(structref);
+                Err(e)
+            }
+        }
+    }
+
     #[inline]
     pub(crate) fn comes_from_same_store(&self, store: &StoreOpaque) -> bool {
         self.inner.comes_from_same_store(store)
@@ -525,14 +597,26 @@ impl StructRef {
         value: Val,
     ) -> Result<()> {
         assert!(self.comes_from_same_store(store));
-        let mut store = AutoAssertNoGc::new(store);
-
-        let field_ty = self.field_ty(&store, index)?;
+        let field_ty = self.field_ty(store, index)?;
         ensure!(
             field_ty.mutability().is_var(),
             "cannot set field {index}: field is not mutable"
         );
+        self._restore_field(store, index, value)
+    }
 
+    /// Set this struct's `index`th field, regardless of whether it is
+    /// mutable, while restoring an [`InstanceSnapshot`][crate::InstanceSnapshot].
+    pub(crate) fn _restore_field(
+        &self,
+        store: &mut StoreOpaque,
+        index: usize,
+        value: Val,
+    ) -> Result<()> {
+        assert!(self.comes_from_same_store(store));
+        let mut store = AutoAssertNoGc::new(store);
+
+        let field_ty = self.field_ty(&store, index)?;
         value
             .ensure_matches_ty(&store, &field_ty.element_type().unpack())
             .with_context(|| format!("cannot set field {index}: type mismatch"))?;
@@ -0,0 +1,302 @@
+//! Debugging of WebAssembly at the level of individual instructions.
+//!
+//! When [`Config::guest_debug`](crate::Config::guest_debug) is enabled, code
+//! is compiled with a check before each instruction of whether execution
+//! should stop there. That check first loads the store's debug flags through
+//! `VMRuntimeLimits::debug_flags`, which are zero unless a breakpoint is set,
+//! the debugger is stepping, or an interruption has been requested, so that
+//! code runs without calling into the host when the debugger isn't involved.
+//! Otherwise the `debug_check` libcall answers the question using the
+//! `DebugState` in a store, and if the answer is yes then the `debug_break`
+//! libcall invokes the store's debug handler with a [`DebugFrame`] describing
+//! where execution stopped.
+
+use crate::hash_set::HashSet;
+use crate::prelude::*;
+use crate::runtime::vm::CompiledModuleId;
+use crate::{AsContextMut, Global, Instance, Memory, Module, Val, V128};
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicU32, Ordering};
+use wasmtime_environ::{
+    DebugLocalKind, DefinedFuncIndex, EntityRef, DEBUG_LOCAL_SIZE, DEBUG_LOCAL_VALUE_OFFSET,
+};
+
+/// How execution proceeds after the debug handler configured with
+/// [`Store::debug_handler`](crate::Store::debug_handler) returns.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DebugStep {
+    /// Run until a breakpoint is reached or execution is interrupted.
+    Continue,
+    /// Stop at the next instruction to execute, which may be in a function
+    /// called by the current one.
+    Into,
+    /// Stop at the next instruction to execute in the current function or
+    /// one of its callers, running any calls made by the current instruction
+    /// to completion.
+    Over,
+    /// Stop at the next instruction to execute after the current function
+    /// returns.
+    Out,
+}
+
+/// A description of the WebAssembly instruction that execution has stopped
+/// at, passed to the handler configured with
+/// [`Store::debug_handler`](crate::Store::debug_handler).
+///
+/// The rest of the stack can be inspected with
+/// [`WasmBacktrace::capture`](crate::WasmBacktrace::capture), whose first
+/// frame is this one.
+#[derive(Debug)]
+pub struct DebugFrame {
+    instance: Instance,
+    module: Module,
+    func_index: u32,
+    offset: usize,
+    locals: Vec<Option<Val>>,
+}
+
+impl DebugFrame {
+    /// Returns the instance which is executing.
+    pub fn instance(&self) -> Instance {
+        self.instance
+    }
+
+    /// Returns the module of the instance which is executing.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Returns the index, in the module's function index space, of the
+    /// function which is executing.
+    pub fn func_index(&self) -> u32 {
+        self.func_index
+    }
+
+    /// Returns the offset within the original wasm module of the instruction
+    /// which is about to execute.
+    ///
+    /// This is the same offset that's used by
+    /// [`Store::add_breakpoint`](crate::Store::add_breakpoint) and by
+    /// [`FrameInfo::module_offset`](crate::FrameInfo::module_offset).
+    pub fn module_offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the current values of the function's locals, starting with its
+    /// parameters.
+    ///
+    /// Locals of reference types can't be inspected and are `None`.
+    pub fn locals(&self) -> &[Option<Val>] {
+        &self.locals
+    }
+
+    /// Returns all of the instance's globals, whether or not they're
+    /// exported, in order of global index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this frame's instance.
+    pub fn globals(&self, mut store: impl AsContextMut) -> Vec<Global> {
+        self.instance
+            .all_globals(store.as_context_mut().0)
+            .map(|(_, global)| global)
+            .collect()
+    }
+
+    /// Returns all of the instance's linear memories, whether or not they're
+    /// exported, in order of memory index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this frame's instance.
+    pub fn memories(&self, mut store: impl AsContextMut) -> Vec<Memory> {
+        self.instance
+            .all_memories(store.as_context_mut().0)
+            .map(|(_, memory)| memory)
+            .collect()
+    }
+}
+
+/// A handle which can be used to interrupt a [`Store`](crate::Store)'s
+/// execution from another thread, stopping at the next instruction.
+///
+/// Created with
+/// [`Store::debug_interrupt_handle`](crate::Store::debug_interrupt_handle).
+#[derive(Clone, Debug)]
+pub struct DebugInterruptHandle(Arc<AtomicU32>);
+
+impl DebugInterruptHandle {
+    /// Requests that execution stop at the next instruction, invoking the
+    /// store's debug handler.
+    ///
+    /// If the store isn't currently executing WebAssembly then it stops at
+    /// the first instruction that it next executes.
+    pub fn interrupt(&self) {
+        self.0.fetch_or(FLAG_INTERRUPT, Ordering::Relaxed);
+    }
+}
+
+/// Set in a store's debug flags when an interruption has been requested.
+const FLAG_INTERRUPT: u32 = 1 << 0;
+
+/// Set in a store's debug flags when it has a debug handler and either has
+/// breakpoints or is stepping.
+const FLAG_ACTIVE: u32 = 1 << 1;
+
+/// Per-store state which determines where execution stops.
+#[derive(Default)]
+pub(crate) struct DebugState {
+    breakpoints: HashSet<(CompiledModuleId, usize)>,
+    /// How execution is stepping, and the frame pointer of the frame that the
+    /// step started in.
+    step: Option<(DebugStep, usize)>,
+    /// The flags checked by instrumented code before calling `debug_check`,
+    /// made up of `FLAG_INTERRUPT` and `FLAG_ACTIVE`.
+    flags: Arc<AtomicU32>,
+    /// Whether the store has a debug handler, without which execution never
+    /// stops.
+    has_handler: bool,
+}
+
+impl DebugState {
+    pub(crate) fn set_has_handler(&mut self, has_handler: bool) {
+        self.has_handler = has_handler;
+        self.update_active();
+    }
+
+    pub(crate) fn add_breakpoint(&mut self, module: &Module, offset: usize) {
+        self.breakpoints.insert((module.id(), offset));
+        self.update_active();
+    }
+
+    pub(crate) fn remove_breakpoint(&mut self, module: &Module, offset: usize) {
+        self.breakpoints.remove(&(module.id(), offset));
+        self.update_active();
+    }
+
+    pub(crate) fn interrupt_handle(&self) -> DebugInterruptHandle {
+        DebugInterruptHandle(self.flags.clone())
+    }
+
+    /// Returns the pointer to this state's flags which is stored in
+    /// `VMRuntimeLimits::debug_flags`.
+    pub(crate) fn flags_ptr(&self) -> *const u32 {
+        self.flags.as_ptr()
+    }
+
+    /// Sets or clears `FLAG_ACTIVE` depending on whether execution may need
+    /// to stop at a breakpoint or while stepping.
+    fn update_active(&self) {
+        let stepping = matches!(self.step, Some((step, _)) if step != DebugStep::Continue);
+        if self.has_handler && (stepping || !self.breakpoints.is_empty()) {
+            self.flags.fetch_or(FLAG_ACTIVE, Ordering::Relaxed);
+        } else {
+            self.flags.fetch_and(!FLAG_ACTIVE, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns whether execution should stop at `offset` in `module`, where
+    /// `fp` is the frame pointer of the executing function.
+    pub(crate) fn should_break(&self, module: CompiledModuleId, offset: usize, fp: usize) -> bool {
+        if !self.has_handler {
+            return false;
+        }
+        if self.flags.fetch_and(!FLAG_INTERRUPT, Ordering::Relaxed) & FLAG_INTERRUPT != 0 {
+            return true;
+        }
+        if !self.breakpoints.is_empty() && self.breakpoints.contains(&(module, offset)) {
+            return true;
+        }
+        // Stacks grow down, so callers have larger frame pointers than their
+        // callees.
+        match self.step {
+            None | Some((DebugStep::Continue, _)) => false,
+            Some((DebugStep::Into, _)) => true,
+            Some((DebugStep::Over, start)) => fp >= start,
+            Some((DebugStep::Out, start)) => fp > start,
+        }
+    }
+
+    /// Configures how execution proceeds from the frame whose frame pointer is
+    /// `fp`.
+    pub(crate) fn resume(&mut self, step: DebugStep, fp: usize) {
+        self.step = match step {
+            DebugStep::Continue => None,
+            step => Some((step, fp)),
+        };
+        self.update_active();
+    }
+}
+
+impl DebugFrame {
+    /// Creates the frame describing the instruction at `offset` in `instance`.
+    ///
+    /// # Safety
+    ///
+    /// The `locals` pointer must point to `len` records laid out as described
+    /// in `wasmtime_environ::DEBUG_LOCAL_SIZE`.
+    pub(crate) unsafe fn new(
+        instance: &crate::runtime::vm::Instance,
+        offset: usize,
+        locals: *const u8,
+        len: usize,
+    ) -> DebugFrame {
+        let module = instance
+            .runtime_module()
+            .expect("instrumented code always belongs to a module")
+            .clone();
+        let func_index = func_index_at(&module, offset);
+        let locals = (0..len)
+            .map(|i| {
+                let record = locals.add(i * DEBUG_LOCAL_SIZE as usize);
+                let value = record.add(DEBUG_LOCAL_VALUE_OFFSET as usize);
+                let kind = DebugLocalKind::from_u32(record.cast::<u32>().read_unaligned())
+                    .expect("invalid debug local kind");
+                match kind {
+                    DebugLocalKind::I32 => Some(Val::I32(value.cast::<i32>().read_unaligned())),
+                    DebugLocalKind::I64 => Some(Val::I64(value.cast::<i64>().read_unaligned())),
+                    DebugLocalKind::F32 => Some(Val::F32(value.cast::<u32>().read_unaligned())),
+                    DebugLocalKind::F64 => Some(Val::F64(value.cast::<u64>().read_unaligned())),
+                    DebugLocalKind::V128 => {
+                        Some(Val::V128(V128::from(value.cast::<u128>().read_unaligned())))
+                    }
+                    DebugLocalKind::Ref => None,
+                }
+            })
+            .collect();
+        DebugFrame {
+            instance: *instance
+                .host_state()
+                .downcast_ref::<Instance>()
+                .expect("instrumented code always belongs to an instance"),
+            module,
+            func_index,
+            offset,
+            locals,
+        }
+    }
+}
+
+/// Returns the index of the function in `module` containing `offset`.
+fn func_index_at(module: &Module, offset: usize) -> u32 {
+    let compiled = module.compiled_module();
+    let env = compiled.module();
+    let start = |i: usize| {
+        let info = compiled.wasm_func_info(DefinedFuncIndex::new(i));
+        info.start_srcloc.file_offset().map_or(0, |o| o as usize)
+    };
+
+    // Functions are laid out in order in the code section, so find the last
+    // one that starts at or before `offset`.
+    let (mut lo, mut hi) = (0, env.functions.len() - env.num_imported_funcs);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if start(mid) <= offset {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    env.func_index(DefinedFuncIndex::new(lo.saturating_sub(1)))
+        .as_u32()
+}
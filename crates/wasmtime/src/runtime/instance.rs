@@ -1,20 +1,23 @@
 use crate::linker::{Definition, DefinitionType};
 use crate::prelude::*;
 use crate::runtime::vm::{
-    Imports, InstanceAllocationRequest, ModuleRuntimeInfo, StorePtr, VMFuncRef, VMFunctionImport,
-    VMGlobalImport, VMMemoryImport, VMOpaqueContext, VMTableImport,
+    Imports, InstanceAllocationRequest, ModuleRuntimeInfo, RestoredMemories, StorePtr, VMFuncRef,
+    VMFunctionImport, VMGlobalImport, VMMemoryImport, VMOpaqueContext, VMTableImport,
 };
 use crate::store::{InstanceId, StoreOpaque, Stored};
 use crate::types::matching;
 use crate::{
-    AsContextMut, Engine, Export, Extern, Func, Global, Memory, Module, ModuleExport, SharedMemory,
-    StoreContext, StoreContextMut, Table, TypedFunc,
+    AsContext, AsContextMut, Engine, Export, Extern, Func, Global, Memory, Module, ModuleExport,
+    SharedMemory, StoreContext, StoreContextMut, Table, TypedFunc,
 };
+#[cfg(feature = "snapshot")]
+use crate::{ExternRef, InstanceSnapshot, Rooted};
 use alloc::sync::Arc;
 use core::ptr::NonNull;
 use wasmparser::WasmFeatures;
 use wasmtime_environ::{
-    EntityIndex, EntityType, FuncIndex, GlobalIndex, MemoryIndex, PrimaryMap, TableIndex, TypeTrace,
+    DefinedFuncIndex, EntityIndex, EntityRef, EntityType, FuncIndex, GlobalIndex, MemoryIndex,
+    PrimaryMap, TableIndex, TypeTrace,
 };
 
 /// An instantiated WebAssembly module.
@@ -203,7 +206,7 @@ impl Instance {
         module: &Module,
         imports: Imports<'_>,
     ) -> Result<Instance> {
-        let (instance, start) = Instance::new_raw(store.0, module, imports)?;
+        let (instance, start) = Instance::new_raw(store.0, module, imports, None)?;
         if let Some(start) = start {
             instance.start_raw(store, start)?;
         }
@@ -232,6 +235,32 @@ impl Instance {
             .await?
     }
 
+    /// Internal function to create an instance whose memories are restored
+    /// from a snapshot.
+    ///
+    /// The instance's `start` function is not run, since the snapshot was
+    /// taken after it already ran. Restoring the rest of the snapshot's state
+    /// is left to the caller.
+    #[cfg(feature = "snapshot")]
+    pub(crate) fn new_restored(
+        store: &mut StoreOpaque,
+        module: &Module,
+        imports: &[Extern],
+        restore: RestoredMemories<'_>,
+    ) -> Result<Instance> {
+        assert!(
+            !store.async_support(),
+            "cannot restore snapshots in stores with async support enabled",
+        );
+        let imports = Instance::typecheck_externs(store, module, imports)?;
+        // Note that the unsafety here should be satisfied by the call to
+        // `typecheck_externs` above which satisfies the condition that all
+        // the imports are valid for this module.
+        let (instance, _start) =
+            unsafe { Instance::new_raw(store, module, imports.as_ref(), Some(restore))? };
+        Ok(instance)
+    }
+
     /// Internal function to create an instance which doesn't have its `start`
     /// function run yet.
     ///
@@ -251,6 +280,7 @@ impl Instance {
         store: &mut StoreOpaque,
         module: &Module,
         imports: Imports<'_>,
+        restore: Option<RestoredMemories<'_>>,
     ) -> Result<(Instance, Option<FuncIndex>)> {
         if !Engine::same(store.engine(), module.engine()) {
             bail!("cross-`Engine` instantiation is not currently supported");
@@ -266,6 +296,7 @@ impl Instance {
 
         // Register the module just before instantiation to ensure we keep the module
         // properly referenced while in use by the store.
+        store.code_memory_growing(module.code_object())?;
         let module_id = store.modules_mut().register_module(module);
         store.fill_func_refs();
 
@@ -292,6 +323,7 @@ impl Instance {
                     wmemcheck: store.engine().config().wmemcheck,
                     pkey: store.get_pkey(),
                     tunables: store.engine().tunables(),
+                    restore,
                 })?;
 
         // The instance still has lots of setup, for example
@@ -377,7 +409,7 @@ impl Instance {
         self._module(store.into().0)
     }
 
-    fn _module<'a>(&self, store: &'a StoreOpaque) -> &'a Module {
+    pub(crate) fn _module<'a>(&self, store: &'a StoreOpaque) -> &'a Module {
         let InstanceData { id, .. } = store[self.0];
         store.module_for_instance(id).unwrap()
     }
@@ -593,7 +625,6 @@ impl Instance {
         self.get_export(store, name)?.into_global()
     }
 
-    #[cfg(feature = "component-model")]
     pub(crate) fn id(&self, store: &StoreOpaque) -> InstanceId {
         store[self.0].id
     }
@@ -637,6 +668,117 @@ impl Instance {
             .into_iter()
             .map(|(i, m)| (i, unsafe { Memory::from_wasmtime_memory(m, store) }))
     }
+
+    /// Get all tables within this instance.
+    ///
+    /// Returns both import and defined tables.
+    ///
+    /// Returns both exported and non-exported tables.
+    ///
+    /// Gives access to the full tables space.
+    pub(crate) fn all_tables<'a>(
+        &'a self,
+        store: &'a mut StoreOpaque,
+    ) -> impl ExactSizeIterator<Item = (TableIndex, Table)> + 'a {
+        let data = &store[self.0];
+        let instance = store.instance_mut(data.id);
+        instance
+            .all_tables()
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|(i, t)| (i, unsafe { Table::from_wasmtime_table(t, store) }))
+    }
+
+    /// Take a snapshot of this instance's state, which can later be restored
+    /// into a new instance, possibly in another store or process.
+    ///
+    /// See [`InstanceSnapshot`] for details on what is and isn't captured.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if this instance's state can't be represented in a
+    /// snapshot, for example because it refers to a function from another
+    /// instance or to an `externref`, which requires
+    /// [`Instance::snapshot_with_externrefs`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this instance.
+    #[cfg(feature = "snapshot")]
+    pub fn snapshot(&self, mut store: impl AsContextMut) -> Result<InstanceSnapshot> {
+        InstanceSnapshot::new(store.as_context_mut(), self, None)
+    }
+
+    /// Take a snapshot of this instance's state, which may refer to
+    /// `externref`s.
+    ///
+    /// This is like [`Instance::snapshot`], except that `save_externref` is
+    /// called once for each distinct `externref` reachable from the instance's
+    /// state to serialize its host data. The resulting snapshot must be
+    /// restored with [`InstanceSnapshot::instantiate_with_externrefs`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in the same cases as [`Instance::snapshot`], other than
+    /// for `externref`s, and if `save_externref` returns an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this instance.
+    #[cfg(feature = "snapshot")]
+    pub fn snapshot_with_externrefs<T>(
+        &self,
+        mut store: impl AsContextMut<Data = T>,
+        mut save_externref: impl FnMut(StoreContextMut<'_, T>, Rooted<ExternRef>) -> Result<Vec<u8>>,
+    ) -> Result<InstanceSnapshot> {
+        InstanceSnapshot::new(store.as_context_mut(), self, Some(&mut save_externref))
+    }
+
+    /// Returns the fuel consumed so far by each function defined in this
+    /// instance's module, or `None` if this instance's module wasn't compiled
+    /// with [`Config::fuel_profiling`](crate::Config::fuel_profiling).
+    ///
+    /// The returned list has an entry for each defined function, in order of
+    /// function index. Each function's count only includes the fuel it
+    /// consumed itself and not that consumed by the functions it calls.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` does not own this instance.
+    pub fn function_fuel_profile(&self, store: impl AsContext) -> Option<Vec<FunctionFuel>> {
+        let store = store.as_context().0;
+        if !store.engine().tunables().fuel_profiling {
+            return None;
+        }
+        let module = self._module(store).compiled_module();
+        let counters = store.instance(self.id(store)).instance().fuel_profile();
+        Some(
+            counters
+                .iter()
+                .enumerate()
+                .map(|(i, fuel)| {
+                    let index = module.module().func_index(DefinedFuncIndex::new(i));
+                    FunctionFuel {
+                        index: index.as_u32(),
+                        name: module.func_name(index).map(|n| n.to_string()),
+                        fuel: *fuel,
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+/// The fuel consumed by one function, as returned by
+/// [`Instance::function_fuel_profile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionFuel {
+    /// The index of the function within its module's function index space.
+    pub index: u32,
+    /// The function's name from the module's name section, if it has one.
+    pub name: Option<String>,
+    /// The fuel this function has consumed.
+    pub fuel: u64,
 }
 
 pub(crate) struct OwnedImports {
@@ -678,7 +820,7 @@ impl OwnedImports {
         self.globals.clear();
     }
 
-    fn push(&mut self, item: &Extern, store: &mut StoreOpaque, module: &Module) {
+    pub(crate) fn push(&mut self, item: &Extern, store: &mut StoreOpaque, module: &Module) {
         match item {
             Extern::Func(i) => {
                 self.functions.push(i.vmimport(store, module));
@@ -841,6 +983,16 @@ impl<T> InstancePre<T> {
         &self.module
     }
 
+    /// Returns whether none of the imports closed over by this
+    /// [`InstancePre`] are owned by a particular store, meaning it can be
+    /// instantiated in any store.
+    #[cfg(feature = "std")]
+    pub(crate) fn is_store_independent(&self) -> bool {
+        self.items
+            .iter()
+            .all(|item| matches!(item, Definition::HostFunc(_)))
+    }
+
     /// Instantiates this instance, creating a new instance within the provided
     /// `store`.
     ///
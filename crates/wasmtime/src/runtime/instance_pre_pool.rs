@@ -0,0 +1,360 @@
+//! A pool of pre-instantiated instances, see [`InstancePrePool`].
+
+use crate::prelude::*;
+use crate::{Engine, Instance, InstancePre, Store};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::thread::{self, ThreadId};
+
+/// A pool of instances of a module, each fully initialized in its own
+/// [`Store`], ready to be handed out.
+///
+/// Instantiation with an [`InstancePre`] is already cheap, especially with the
+/// [pooling allocator](crate::PoolingAllocationConfig), but each instantiation
+/// still runs the module's data and element segment initialization and its
+/// start function. An `InstancePrePool` moves that work off the critical path:
+/// it keeps up to [`InstancePrePool::capacity`] instances initialized ahead of
+/// time, and [`InstancePrePool::take`] hands one out in O(1).
+///
+/// The pool is refilled with [`InstancePrePool::fill`], or in the background
+/// by a thread started with [`InstancePrePool::spawn_refill`]. Instances which
+/// are no longer needed are dropped along with their [`Store`], and with the
+/// pooling allocator their slots are returned to the pool through its
+/// decommit queue, batched according to
+/// [`PoolingAllocationConfig::decommit_batch_size`](crate::PoolingAllocationConfig::decommit_batch_size).
+/// The background refill is woken up whenever an instance is taken and
+/// whenever slots are returned, so a pool which couldn't be filled because
+/// the pooling allocator ran out of slots is topped back up as soon as slots
+/// become available.
+///
+/// Each instance is created in a fresh store built by the closure passed to
+/// [`InstancePrePool::new`], so the [`InstancePre`] must not close over any
+/// imports owned by a particular store. Imports defined in a
+/// [`Linker`](crate::Linker) with [`Linker::func_wrap`](crate::Linker::func_wrap)
+/// and [`Linker::func_new`](crate::Linker::func_new) are not owned by a store
+/// and can be used.
+///
+/// # Examples
+///
+/// ```
+/// # use wasmtime::*;
+/// # fn main() -> anyhow::Result<()> {
+/// let engine = Engine::default();
+/// let module = Module::new(&engine, r#"(module (func (export "run")))"#)?;
+/// let linker = Linker::new(&engine);
+/// let pre = linker.instantiate_pre(&module)?;
+///
+/// let pool = InstancePrePool::new(pre, 4, |engine| Ok(Store::new(engine, ())))?;
+/// pool.fill()?;
+///
+/// let mut pooled = pool.take()?;
+/// let run = pooled.instance().get_typed_func::<(), ()>(pooled.store(), "run")?;
+/// run.call(pooled.store(), ())?;
+///
+/// // Top the pool back up explicitly...
+/// pool.fill()?;
+///
+/// // ... or keep it topped up from a background thread.
+/// let pool = std::sync::Arc::new(pool);
+/// pool.spawn_refill()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct InstancePrePool<T> {
+    pre: InstancePre<T>,
+    new_store: Box<dyn Fn(&Engine) -> Result<Store<T>> + Send + Sync>,
+    capacity: usize,
+    ready: Mutex<Vec<PooledInstance<T>>>,
+    refill: Arc<Refill>,
+    /// Wakes up `refill`, registered with the engine's instance allocator to
+    /// be called when slots are released. The allocator only holds a weak
+    /// reference, so this unregisters it when the pool is dropped.
+    _on_slots_released: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+/// The state shared between an [`InstancePrePool`] and its background refill
+/// thread.
+#[derive(Default)]
+struct Refill {
+    state: Mutex<RefillState>,
+    wake: Condvar,
+}
+
+#[derive(Default)]
+struct RefillState {
+    /// The refill thread, if one has been spawned.
+    thread: Option<ThreadId>,
+    /// Whether the refill thread should try to fill the pool again.
+    wanted: bool,
+    /// Whether the pool has been dropped and the refill thread should exit.
+    stopped: bool,
+}
+
+impl Refill {
+    /// Wakes up the refill thread, if there is one.
+    fn wake(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.thread.is_some() {
+            state.wanted = true;
+            self.wake.notify_one();
+        }
+    }
+
+    /// Wakes up the refill thread because slots were released, unless they
+    /// were released by the refill thread itself, for example when it rolled
+    /// back a failed instantiation. Otherwise a refill which failed for lack
+    /// of slots would wake itself up again straight away.
+    fn slots_released(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.thread.is_some_and(|t| t != thread::current().id()) {
+            state.wanted = true;
+            self.wake.notify_one();
+        }
+    }
+
+    /// Waits until the pool needs refilling, returning `false` if the pool
+    /// has been dropped instead.
+    fn wait(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        while !state.wanted && !state.stopped {
+            state = self.wake.wait(state).unwrap();
+        }
+        state.wanted = false;
+        !state.stopped
+    }
+}
+
+/// An instance handed out by an [`InstancePrePool`] along with the [`Store`]
+/// that owns it.
+pub struct PooledInstance<T> {
+    store: Store<T>,
+    instance: Instance,
+}
+
+impl<T> InstancePrePool<T> {
+    /// Creates a new, empty, pool which keeps up to `capacity` instances of
+    /// `pre` ready.
+    ///
+    /// The `new_store` closure is used to create the store for each instance
+    /// and is where per-store configuration such as resource limiters, fuel,
+    /// or epoch deadlines should be applied. Stores must not have async
+    /// support enabled if [`InstancePrePool::fill`] is used.
+    ///
+    /// The pool starts out empty; call [`InstancePrePool::fill`] to populate
+    /// it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `pre` closes over imports owned by a particular
+    /// [`Store`].
+    pub fn new(
+        pre: InstancePre<T>,
+        capacity: usize,
+        new_store: impl Fn(&Engine) -> Result<Store<T>> + Send + Sync + 'static,
+    ) -> Result<InstancePrePool<T>> {
+        if !pre.is_store_independent() {
+            bail!(
+                "an `InstancePrePool` cannot be created from an `InstancePre` \
+                 with imports owned by a store"
+            );
+        }
+        let refill = Arc::new(Refill::default());
+        let on_slots_released: Arc<dyn Fn() + Send + Sync> = {
+            let refill = refill.clone();
+            Arc::new(move || refill.slots_released())
+        };
+        let registered = pre
+            .module()
+            .engine()
+            .allocator()
+            .on_slots_released(Arc::downgrade(&on_slots_released));
+        Ok(InstancePrePool {
+            pre,
+            new_store: Box::new(new_store),
+            capacity,
+            ready: Mutex::new(Vec::with_capacity(capacity)),
+            refill,
+            _on_slots_released: registered.then_some(on_slots_released),
+        })
+    }
+
+    /// Spawns a thread which keeps this pool filled in the background, until
+    /// the pool is dropped.
+    ///
+    /// The thread fills the pool straight away, and again whenever an
+    /// instance is taken from it. With the
+    /// [pooling allocator](crate::PoolingAllocationConfig) it also retries
+    /// whenever slots are returned to the allocator, so instances that
+    /// couldn't be created because the allocator was out of slots are created
+    /// once slots are freed up. Errors from filling the pool are logged and
+    /// otherwise ignored, and the pool is filled again the next time the
+    /// thread is woken up.
+    ///
+    /// The thread calls [`InstancePrePool::fill`], so the stores created for
+    /// this pool must not have async support enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a refill thread has already been spawned for this
+    /// pool, or if the thread couldn't be spawned.
+    pub fn spawn_refill(self: &Arc<Self>) -> Result<()>
+    where
+        T: Send + 'static,
+    {
+        let mut state = self.refill.state.lock().unwrap();
+        if state.thread.is_some() {
+            bail!("a refill thread has already been spawned for this `InstancePrePool`");
+        }
+        let pool = Arc::downgrade(self);
+        let refill = self.refill.clone();
+        let handle = thread::Builder::new()
+            .name("wasmtime-instance-pre-pool".into())
+            .spawn(move || Self::run_refill(&pool, &refill))
+            .context("failed to spawn a refill thread for an `InstancePrePool`")?;
+        state.thread = Some(handle.thread().id());
+        state.wanted = true;
+        self.refill.wake.notify_one();
+        Ok(())
+    }
+
+    fn run_refill(pool: &Weak<Self>, refill: &Refill) {
+        while refill.wait() {
+            let Some(pool) = pool.upgrade() else { break };
+            if let Err(e) = pool.fill() {
+                log::debug!("failed to refill an `InstancePrePool`: {e:?}");
+            }
+        }
+    }
+
+    /// Returns the [`InstancePre`] used to create this pool's instances.
+    pub fn instance_pre(&self) -> &InstancePre<T> {
+        &self.pre
+    }
+
+    /// Returns the maximum number of instances this pool keeps ready.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of instances currently ready to be taken.
+    pub fn ready(&self) -> usize {
+        self.ready.lock().unwrap().len()
+    }
+
+    /// Takes a ready instance out of the pool, or returns `None` if the pool
+    /// is empty.
+    pub fn try_take(&self) -> Option<PooledInstance<T>> {
+        let pooled = self.ready.lock().unwrap().pop()?;
+        self.refill.wake();
+        Some(pooled)
+    }
+
+    /// Takes a ready instance out of the pool, instantiating a new one inline
+    /// if the pool is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pool was empty and instantiation failed.
+    pub fn take(&self) -> Result<PooledInstance<T>> {
+        match self.try_take() {
+            Some(pooled) => Ok(pooled),
+            None => self.instantiate(),
+        }
+    }
+
+    /// Instantiates new instances until the pool holds
+    /// [`InstancePrePool::capacity`] of them, returning how many were
+    /// created.
+    ///
+    /// The pool's lock is not held while instantiating, so instances may be
+    /// taken concurrently with a call to `fill`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error encountered while creating a store or
+    /// instantiating; instances created before the error remain in the pool.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stores created for this pool have async support enabled,
+    /// use [`InstancePrePool::fill_async`] for those instead.
+    pub fn fill(&self) -> Result<usize> {
+        let mut created = 0;
+        while self.needs_more() {
+            let pooled = self.instantiate()?;
+            if !self.push(pooled) {
+                break;
+            }
+            created += 1;
+        }
+        Ok(created)
+    }
+
+    /// Same as [`InstancePrePool::fill`], but for stores with async support
+    /// enabled, running start functions asynchronously.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stores created for this pool do not have async support
+    /// enabled.
+    #[cfg(feature = "async")]
+    pub async fn fill_async(&self) -> Result<usize>
+    where
+        T: Send,
+    {
+        let mut created = 0;
+        while self.needs_more() {
+            let mut store = (self.new_store)(self.pre.module().engine())?;
+            let instance = self.pre.instantiate_async(&mut store).await?;
+            if !self.push(PooledInstance { store, instance }) {
+                break;
+            }
+            created += 1;
+        }
+        Ok(created)
+    }
+
+    fn instantiate(&self) -> Result<PooledInstance<T>> {
+        let mut store = (self.new_store)(self.pre.module().engine())?;
+        let instance = self.pre.instantiate(&mut store)?;
+        Ok(PooledInstance { store, instance })
+    }
+
+    fn needs_more(&self) -> bool {
+        self.ready() < self.capacity
+    }
+
+    /// Adds `pooled` to the pool, returning `false` and dropping it if the
+    /// pool was filled concurrently.
+    fn push(&self, pooled: PooledInstance<T>) -> bool {
+        let mut ready = self.ready.lock().unwrap();
+        if ready.len() >= self.capacity {
+            return false;
+        }
+        ready.push(pooled);
+        true
+    }
+}
+
+impl<T> Drop for InstancePrePool<T> {
+    fn drop(&mut self) {
+        self.refill.state.lock().unwrap().stopped = true;
+        self.refill.wake.notify_one();
+    }
+}
+
+impl<T> PooledInstance<T> {
+    /// Returns the pooled instance.
+    pub fn instance(&self) -> Instance {
+        self.instance
+    }
+
+    /// Returns the store which owns the pooled instance.
+    pub fn store(&mut self) -> &mut Store<T> {
+        &mut self.store
+    }
+
+    /// Returns the store and instance, consuming this value.
+    pub fn into_parts(self) -> (Store<T>, Instance) {
+        (self.store, self.instance)
+    }
+}
@@ -279,6 +279,14 @@ impl CompiledModule {
         }))
     }
 
+    /// Returns the SHA-256 checksum of the original wasm module's bytes, if
+    /// it was computed when the module was compiled.
+    ///
+    /// The checksum is only computed when the `snapshot` feature is enabled.
+    pub fn checksum(&self) -> Option<&[u8; 32]> {
+        self.meta.checksum.as_ref()
+    }
+
     /// Returns whether the original wasm module had unparsed debug information
     /// based on the tunables configuration.
     pub fn has_unparsed_debuginfo(&self) -> bool {
@@ -1,4 +1,8 @@
 use crate::prelude::*;
+use crate::sync::RwLock;
+use alloc::sync::Arc;
+use core::fmt;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 /// Value returned by [`ResourceLimiter::instances`] default method
 pub const DEFAULT_INSTANCE_LIMIT: usize = 10000;
@@ -125,6 +129,124 @@ pub trait ResourceLimiter {
         Ok(())
     }
 
+    /// Notifies the resource limiter that the store's GC heap has been
+    /// requested to grow.
+    ///
+    /// * `current` is the current size of the GC heap in bytes.
+    /// * `desired` is the desired size of the GC heap in bytes.
+    /// * `maximum` is the largest size the GC heap may reach, in bytes, or
+    ///   `None` if it is unbounded.
+    ///
+    /// A store's GC heap starts out empty and grows when an allocation
+    /// doesn't fit in it, after collecting garbage hasn't made enough room
+    /// where the allocation is made by WebAssembly. If this returns
+    /// `Ok(false)` then the allocation fails: the WebAssembly instruction
+    /// traps, and host APIs such as [`StructRef::new`](crate::StructRef::new)
+    /// return a [`GcHeapOutOfMemory`](crate::GcHeapOutOfMemory) error. If this
+    /// returns `Err(e)` then the allocation fails with `e`.
+    ///
+    /// By default this allows all growth.
+    fn gc_heap_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        let _ = (current, desired, maximum);
+        Ok(true)
+    }
+
+    /// Notifies the resource limiter that the compiled code used by the
+    /// store has been requested to grow.
+    ///
+    /// * `current` is the size in bytes of the compiled code of the modules
+    ///   and components instantiated within the store so far.
+    /// * `desired` is that size plus the compiled code of a module or
+    ///   component which is being instantiated in the store for the first
+    ///   time.
+    /// * `maximum` is always `None` at this time.
+    ///
+    /// Compiled code is shared by every store which instantiates the same
+    /// module or component, so the same code may be reported to the limiters
+    /// of several stores. If this returns `Ok(false)` then the instantiation
+    /// fails with an error, and if it returns `Err(e)` then it fails with `e`.
+    ///
+    /// By default this allows all growth.
+    fn code_memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        let _ = (current, desired, maximum);
+        Ok(true)
+    }
+
+    /// Notifies the resource limiter that resources held by the host on
+    /// behalf of WebAssembly have been requested to grow.
+    ///
+    /// This isn't called by the store itself, but by host state which has
+    /// been given this limiter through a [`SharedResourceLimiter`]. For
+    /// example a [`ResourceTable`](crate::component::ResourceTable) reports
+    /// the bytes taken up by its entries here. When resources are deleted the
+    /// shrinkage is reported to [`ResourceLimiter::host_resources_released`],
+    /// so `current` is the size which is in use at the time.
+    ///
+    /// If this returns `Ok(false)` or an error then the operation requiring
+    /// the growth fails, as documented by the caller.
+    ///
+    /// By default this allows all growth.
+    fn host_resources_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        let _ = (current, desired, maximum);
+        Ok(true)
+    }
+
+    /// Notifies the resource limiter that data buffered by the host on
+    /// behalf of WebAssembly has been requested to grow.
+    ///
+    /// Like [`ResourceLimiter::host_resources_growing`] this is called by
+    /// host state which has been given this limiter through a
+    /// [`SharedResourceLimiter`], for example by `wasmtime-wasi` for the
+    /// buffers of its streams. Buffers which are freed are reported to
+    /// [`ResourceLimiter::host_buffers_released`].
+    ///
+    /// By default this allows all growth.
+    fn host_buffers_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        let _ = (current, desired, maximum);
+        Ok(true)
+    }
+
+    /// Notifies the resource limiter that resources held by the host on
+    /// behalf of WebAssembly have shrunk from `current` to `desired` bytes,
+    /// for example because they were deleted.
+    ///
+    /// This undoes growth previously allowed by
+    /// [`ResourceLimiter::host_resources_growing`]. By default this does
+    /// nothing.
+    fn host_resources_released(&mut self, current: usize, desired: usize) {
+        let _ = (current, desired);
+    }
+
+    /// Notifies the resource limiter that data buffered by the host on behalf
+    /// of WebAssembly has shrunk from `current` to `desired` bytes.
+    ///
+    /// This undoes growth previously allowed by
+    /// [`ResourceLimiter::host_buffers_growing`]. By default this does
+    /// nothing.
+    fn host_buffers_released(&mut self, current: usize, desired: usize) {
+        let _ = (current, desired);
+    }
+
     /// The maximum number of instances that can be created for a `Store`.
     ///
     /// Module instantiation will fail if this limit is exceeded.
@@ -201,6 +323,60 @@ pub trait ResourceLimiterAsync {
         Ok(())
     }
 
+    /// Identical to [`ResourceLimiter::gc_heap_growing`]
+    fn gc_heap_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        let _ = (current, desired, maximum);
+        Ok(true)
+    }
+
+    /// Identical to [`ResourceLimiter::code_memory_growing`]
+    fn code_memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        let _ = (current, desired, maximum);
+        Ok(true)
+    }
+
+    /// Identical to [`ResourceLimiter::host_resources_growing`]
+    fn host_resources_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        let _ = (current, desired, maximum);
+        Ok(true)
+    }
+
+    /// Identical to [`ResourceLimiter::host_buffers_growing`]
+    fn host_buffers_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        let _ = (current, desired, maximum);
+        Ok(true)
+    }
+
+    /// Identical to [`ResourceLimiter::host_resources_released`]
+    fn host_resources_released(&mut self, current: usize, desired: usize) {
+        let _ = (current, desired);
+    }
+
+    /// Identical to [`ResourceLimiter::host_buffers_released`]
+    fn host_buffers_released(&mut self, current: usize, desired: usize) {
+        let _ = (current, desired);
+    }
+
     /// Identical to [`ResourceLimiter::instances`]`
     fn instances(&self) -> usize {
         DEFAULT_INSTANCE_LIMIT
@@ -298,6 +474,25 @@ impl StoreLimitsBuilder {
         self
     }
 
+    /// A [`MemoryBudget`] which all of the growth reported to these limits is
+    /// charged against, in addition to the other limits here.
+    ///
+    /// This covers linear memories, tables, the GC heap and compiled code,
+    /// as well as any host resources and buffers reported through a
+    /// [`SharedResourceLimiter`] wrapping these limits. Growth which would
+    /// exceed the budget fails in the same way as growth beyond
+    /// [`StoreLimitsBuilder::memory_size`]. Host resources and buffers are
+    /// released as the host reports that they've been freed, and all other
+    /// charges are released when the [`StoreLimits`] are dropped, which
+    /// typically happens along with their store, so one budget may be shared
+    /// between several stores.
+    ///
+    /// By default there is no budget.
+    pub fn memory_budget(mut self, budget: MemoryBudget) -> Self {
+        self.0.budget = Some(BudgetCharges::new(budget));
+        self
+    }
+
     /// Consumes this builder and returns the [`StoreLimits`].
     pub fn build(self) -> StoreLimits {
         self.0
@@ -312,6 +507,9 @@ impl StoreLimitsBuilder {
 /// This is a convenience type included to avoid needing to implement the
 /// [`ResourceLimiter`] trait if your use case fits in the static configuration
 /// that this [`StoreLimits`] provides.
+///
+/// Cloning a [`StoreLimits`] copies its configuration, but not any charges it
+/// has made against its [`MemoryBudget`].
 #[derive(Clone, Debug)]
 pub struct StoreLimits {
     memory_size: Option<usize>,
@@ -320,6 +518,7 @@ pub struct StoreLimits {
     tables: usize,
     memories: usize,
     trap_on_grow_failure: bool,
+    budget: Option<BudgetCharges>,
 }
 
 impl Default for StoreLimits {
@@ -331,6 +530,96 @@ impl Default for StoreLimits {
             tables: DEFAULT_TABLE_LIMIT,
             memories: DEFAULT_MEMORY_LIMIT,
             trap_on_grow_failure: false,
+            budget: None,
+        }
+    }
+}
+
+impl StoreLimits {
+    /// Charges growth from `current` to `desired` bytes against the budget,
+    /// returning whether it fits.
+    fn charge(&mut self, category: MemoryCategory, current: usize, desired: usize) -> bool {
+        match &mut self.budget {
+            Some(budget) => budget.charge(category, current, desired),
+            None => true,
+        }
+    }
+
+    /// Releases the most recent charge, if it was for `category`.
+    fn release_pending(&mut self, category: MemoryCategory) {
+        if let Some(budget) = &mut self.budget {
+            budget.release_pending(category);
+        }
+    }
+
+    /// Releases the charge for shrinking from `current` to `desired` bytes.
+    fn release(&mut self, category: MemoryCategory, current: usize, desired: usize) {
+        if let Some(budget) = &mut self.budget {
+            budget.release(category, current.saturating_sub(desired));
+        }
+    }
+}
+
+/// The charges made against a [`MemoryBudget`] by one [`StoreLimits`], which
+/// are released when it's dropped.
+#[derive(Debug)]
+struct BudgetCharges {
+    budget: MemoryBudget,
+    /// Bytes charged so far, for each category.
+    charged: [usize; MemoryCategory::COUNT],
+    /// The most recent charge, released again if the growth it was for fails.
+    pending: Option<(MemoryCategory, usize)>,
+}
+
+impl BudgetCharges {
+    fn new(budget: MemoryBudget) -> BudgetCharges {
+        BudgetCharges {
+            budget,
+            charged: [0; MemoryCategory::COUNT],
+            pending: None,
+        }
+    }
+
+    fn charge(&mut self, category: MemoryCategory, current: usize, desired: usize) -> bool {
+        self.pending = None;
+        let bytes = desired.saturating_sub(current);
+        if !self.budget.try_charge(category, bytes) {
+            return false;
+        }
+        self.charged[category as usize] += bytes;
+        self.pending = Some((category, bytes));
+        true
+    }
+
+    fn release_pending(&mut self, category: MemoryCategory) {
+        if let Some((pending, bytes)) = self.pending.take() {
+            if pending == category {
+                self.release(category, bytes);
+            }
+        }
+    }
+
+    /// Releases up to `bytes` of what has been charged for `category`.
+    fn release(&mut self, category: MemoryCategory, bytes: usize) {
+        let bytes = bytes.min(self.charged[category as usize]);
+        self.budget.release(category, bytes);
+        self.charged[category as usize] -= bytes;
+    }
+}
+
+/// Clones start out without having charged anything, so that each of them
+/// only releases its own charges.
+impl Clone for BudgetCharges {
+    fn clone(&self) -> Self {
+        BudgetCharges::new(self.budget.clone())
+    }
+}
+
+impl Drop for BudgetCharges {
+    fn drop(&mut self) {
+        for category in MemoryCategory::ALL {
+            self.budget
+                .release(category, self.charged[category as usize]);
         }
     }
 }
@@ -338,7 +627,7 @@ impl Default for StoreLimits {
 impl ResourceLimiter for StoreLimits {
     fn memory_growing(
         &mut self,
-        _current: usize,
+        current: usize,
         desired: usize,
         maximum: Option<usize>,
     ) -> Result<bool> {
@@ -346,7 +635,7 @@ impl ResourceLimiter for StoreLimits {
             Some(limit) if desired > limit => false,
             _ => match maximum {
                 Some(max) if desired > max => false,
-                _ => true,
+                _ => self.charge(MemoryCategory::LinearMemory, current, desired),
             },
         };
         if !allow && self.trap_on_grow_failure {
@@ -357,6 +646,7 @@ impl ResourceLimiter for StoreLimits {
     }
 
     fn memory_grow_failed(&mut self, error: anyhow::Error) -> Result<()> {
+        self.release_pending(MemoryCategory::LinearMemory);
         if self.trap_on_grow_failure {
             Err(error.context("forcing a memory growth failure to be a trap"))
         } else {
@@ -367,7 +657,7 @@ impl ResourceLimiter for StoreLimits {
 
     fn table_growing(
         &mut self,
-        _current: usize,
+        current: usize,
         desired: usize,
         maximum: Option<usize>,
     ) -> Result<bool> {
@@ -375,7 +665,14 @@ impl ResourceLimiter for StoreLimits {
             Some(limit) if desired > limit => false,
             _ => match maximum {
                 Some(max) if desired > max => false,
-                _ => true,
+                _ => {
+                    let size = core::mem::size_of::<usize>();
+                    self.charge(
+                        MemoryCategory::Table,
+                        current.saturating_mul(size),
+                        desired.saturating_mul(size),
+                    )
+                }
             },
         };
         if !allow && self.trap_on_grow_failure {
@@ -386,6 +683,7 @@ impl ResourceLimiter for StoreLimits {
     }
 
     fn table_grow_failed(&mut self, error: anyhow::Error) -> Result<()> {
+        self.release_pending(MemoryCategory::Table);
         if self.trap_on_grow_failure {
             Err(error.context("forcing a table growth failure to be a trap"))
         } else {
@@ -394,6 +692,58 @@ impl ResourceLimiter for StoreLimits {
         }
     }
 
+    fn gc_heap_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        let allow = match maximum {
+            Some(max) if desired > max => false,
+            _ => self.charge(MemoryCategory::GcHeap, current, desired),
+        };
+        if !allow && self.trap_on_grow_failure {
+            bail!("forcing trap when growing the GC heap to {desired} bytes")
+        } else {
+            Ok(allow)
+        }
+    }
+
+    fn code_memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> Result<bool> {
+        Ok(self.charge(MemoryCategory::CompiledCode, current, desired))
+    }
+
+    fn host_resources_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> Result<bool> {
+        Ok(self.charge(MemoryCategory::HostResources, current, desired))
+    }
+
+    fn host_buffers_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> Result<bool> {
+        Ok(self.charge(MemoryCategory::HostBuffers, current, desired))
+    }
+
+    fn host_resources_released(&mut self, current: usize, desired: usize) {
+        self.release(MemoryCategory::HostResources, current, desired);
+    }
+
+    fn host_buffers_released(&mut self, current: usize, desired: usize) {
+        self.release(MemoryCategory::HostBuffers, current, desired);
+    }
+
     fn instances(&self) -> usize {
         self.instances
     }
@@ -406,3 +756,264 @@ impl ResourceLimiter for StoreLimits {
         self.memories
     }
 }
+
+/// The kinds of memory which are charged against a [`MemoryBudget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum MemoryCategory {
+    /// WebAssembly linear memories.
+    LinearMemory,
+    /// WebAssembly tables, at a pointer's worth of space per element.
+    Table,
+    /// The GC heap, holding GC objects such as structs, arrays and
+    /// `externref`s.
+    GcHeap,
+    /// The compiled code of the modules and components instantiated in a
+    /// store.
+    CompiledCode,
+    /// Host resources handed out to WebAssembly, as reported to
+    /// [`ResourceLimiter::host_resources_growing`].
+    HostResources,
+    /// Data buffered by the host on behalf of WebAssembly, as reported to
+    /// [`ResourceLimiter::host_buffers_growing`].
+    HostBuffers,
+}
+
+impl MemoryCategory {
+    const COUNT: usize = 6;
+    const ALL: [MemoryCategory; Self::COUNT] = [
+        MemoryCategory::LinearMemory,
+        MemoryCategory::Table,
+        MemoryCategory::GcHeap,
+        MemoryCategory::CompiledCode,
+        MemoryCategory::HostResources,
+        MemoryCategory::HostBuffers,
+    ];
+}
+
+/// A budget of bytes shared by one or more stores.
+///
+/// Each [`ResourceLimiter`] callback is concerned with a single kind of
+/// resource. A `MemoryBudget` instead provides a single total which all of
+/// the growth reported to a [`StoreLimits`] is charged against, through
+/// [`StoreLimitsBuilder::memory_budget`]. Host state outside of a store, such
+/// as a [`ResourceTable`](crate::component::ResourceTable), reports its growth
+/// to the same limits through a [`SharedResourceLimiter`].
+///
+/// A budget is a cheaply cloneable handle, and all clones share the same
+/// total. Usage is also tracked per [`MemoryCategory`] for reporting.
+#[derive(Clone)]
+pub struct MemoryBudget {
+    inner: Arc<MemoryBudgetInner>,
+}
+
+struct MemoryBudgetInner {
+    limit: usize,
+    used: AtomicUsize,
+    by_category: [AtomicUsize; MemoryCategory::COUNT],
+}
+
+impl MemoryBudget {
+    /// Creates a new budget allowing up to `limit` bytes to be charged.
+    pub fn new(limit: usize) -> MemoryBudget {
+        MemoryBudget {
+            inner: Arc::new(MemoryBudgetInner {
+                limit,
+                used: AtomicUsize::new(0),
+                by_category: Default::default(),
+            }),
+        }
+    }
+
+    /// Returns the number of bytes this budget allows.
+    pub fn limit(&self) -> usize {
+        self.inner.limit
+    }
+
+    /// Returns the number of bytes currently charged against this budget.
+    pub fn used(&self) -> usize {
+        self.inner.used.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes currently charged against this budget for
+    /// `category`.
+    pub fn used_by(&self, category: MemoryCategory) -> usize {
+        self.inner.by_category[category as usize].load(Ordering::Relaxed)
+    }
+
+    /// Charges `bytes` to this budget for `category`.
+    ///
+    /// Returns `false`, charging nothing, if that would take the total over
+    /// the budget's limit.
+    fn try_charge(&self, category: MemoryCategory, bytes: usize) -> bool {
+        let limit = self.inner.limit;
+        let charged = self
+            .inner
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(bytes).filter(|total| *total <= limit)
+            })
+            .is_ok();
+        if charged {
+            self.inner.by_category[category as usize].fetch_add(bytes, Ordering::Relaxed);
+        }
+        charged
+    }
+
+    /// Releases `bytes` previously charged to this budget for `category`.
+    fn release(&self, category: MemoryCategory, bytes: usize) {
+        self.inner.by_category[category as usize].fetch_sub(bytes, Ordering::Relaxed);
+        self.inner.used.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for MemoryBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("MemoryBudget");
+        s.field("limit", &self.limit()).field("used", &self.used());
+        for category in MemoryCategory::ALL {
+            s.field(&format!("{category:?}"), &self.used_by(category));
+        }
+        s.finish()
+    }
+}
+
+/// A [`ResourceLimiter`] which can be shared between a store and host state
+/// outside of it.
+///
+/// A store's limiter is borrowed from the store's data, so host state such as
+/// a [`ResourceTable`](crate::component::ResourceTable) or the streams of
+/// `wasmtime-wasi` can't reach it. Instead the same `SharedResourceLimiter`
+/// can be installed as the store's limiter and cloned into that host state,
+/// so that all of the growth is reported to one [`ResourceLimiter`]:
+///
+/// ```
+/// use wasmtime::*;
+///
+/// struct MyState {
+///     limiter: SharedResourceLimiter,
+/// }
+///
+/// let budget = MemoryBudget::new(1 << 30);
+/// let limiter = SharedResourceLimiter::new(
+///     StoreLimitsBuilder::new().memory_budget(budget).build(),
+/// );
+/// let mut store = Store::new(
+///     &Engine::default(),
+///     MyState {
+///         limiter: limiter.clone(),
+///     },
+/// );
+/// store.limiter(|state| &mut state.limiter);
+/// ```
+///
+/// Each callback locks the wrapped limiter for its duration.
+#[derive(Clone)]
+pub struct SharedResourceLimiter {
+    inner: Arc<RwLock<Box<dyn ResourceLimiter + Send + Sync>>>,
+}
+
+impl SharedResourceLimiter {
+    /// Creates a new shared handle to `limiter`.
+    pub fn new(limiter: impl ResourceLimiter + Send + Sync + 'static) -> SharedResourceLimiter {
+        SharedResourceLimiter {
+            inner: Arc::new(RwLock::new(Box::new(limiter))),
+        }
+    }
+
+    /// Calls `f` with exclusive access to the wrapped limiter.
+    pub fn with<R>(&self, f: impl FnOnce(&mut (dyn ResourceLimiter + Send + Sync)) -> R) -> R {
+        f(&mut **self.inner.write())
+    }
+}
+
+impl fmt::Debug for SharedResourceLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedResourceLimiter")
+            .finish_non_exhaustive()
+    }
+}
+
+impl ResourceLimiter for SharedResourceLimiter {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        self.with(|l| l.memory_growing(current, desired, maximum))
+    }
+
+    fn memory_grow_failed(&mut self, error: anyhow::Error) -> Result<()> {
+        self.with(|l| l.memory_grow_failed(error))
+    }
+
+    fn table_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        self.with(|l| l.table_growing(current, desired, maximum))
+    }
+
+    fn table_grow_failed(&mut self, error: anyhow::Error) -> Result<()> {
+        self.with(|l| l.table_grow_failed(error))
+    }
+
+    fn gc_heap_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        self.with(|l| l.gc_heap_growing(current, desired, maximum))
+    }
+
+    fn code_memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        self.with(|l| l.code_memory_growing(current, desired, maximum))
+    }
+
+    fn host_resources_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        self.with(|l| l.host_resources_growing(current, desired, maximum))
+    }
+
+    fn host_buffers_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool> {
+        self.with(|l| l.host_buffers_growing(current, desired, maximum))
+    }
+
+    fn host_resources_released(&mut self, current: usize, desired: usize) {
+        self.with(|l| l.host_resources_released(current, desired))
+    }
+
+    fn host_buffers_released(&mut self, current: usize, desired: usize) {
+        self.with(|l| l.host_buffers_released(current, desired))
+    }
+
+    fn instances(&self) -> usize {
+        self.with(|l| l.instances())
+    }
+
+    fn tables(&self) -> usize {
+        self.with(|l| l.tables())
+    }
+
+    fn memories(&self) -> usize {
+        self.with(|l| l.memories())
+    }
+}
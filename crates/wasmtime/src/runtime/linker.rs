@@ -187,6 +187,12 @@ impl<T> Linker<T> {
         self
     }
 
+    /// Returns whether this [`Linker`] allows shadowing, as configured with
+    /// [`Linker::allow_shadowing`].
+    pub fn shadowing_allowed(&self) -> bool {
+        self.allow_shadowing
+    }
+
     /// Configures whether this [`Linker`] will allow unknown exports from
     /// command modules.
     ///
@@ -0,0 +1,168 @@
+//! Tracing of the linear memory accesses made by WebAssembly.
+//!
+//! When [`Config::memory_access_tracing`](crate::Config::memory_access_tracing)
+//! is enabled, code is compiled to call the `trace_memory_access` libcall
+//! before each load from or store to linear memory. The libcall invokes the
+//! hook configured with
+//! [`Store::memory_access_hook`](crate::Store::memory_access_hook) with a
+//! [`MemoryAccess`] describing the access.
+
+use crate::Instance;
+
+/// The kind of a [`MemoryAccess`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MemoryAccessKind {
+    /// A non-atomic load, such as `i32.load` or `v128.load8x8_s`.
+    Load,
+    /// A non-atomic store, such as `i32.store` or `v128.store8_lane`.
+    Store,
+    /// An atomic load, such as `i32.atomic.load`.
+    AtomicLoad,
+    /// An atomic store, such as `i32.atomic.store`.
+    AtomicStore,
+    /// An atomic read-modify-write, such as `i32.atomic.rmw.add` or
+    /// `i32.atomic.rmw.cmpxchg`, which both reads and writes memory.
+    AtomicRmw,
+}
+
+impl MemoryAccessKind {
+    /// Returns whether this access reads memory.
+    pub fn is_read(&self) -> bool {
+        !matches!(
+            self,
+            MemoryAccessKind::Store | MemoryAccessKind::AtomicStore
+        )
+    }
+
+    /// Returns whether this access writes memory.
+    pub fn is_write(&self) -> bool {
+        !matches!(self, MemoryAccessKind::Load | MemoryAccessKind::AtomicLoad)
+    }
+
+    /// Returns whether this access is atomic.
+    pub fn is_atomic(&self) -> bool {
+        matches!(
+            self,
+            MemoryAccessKind::AtomicLoad
+                | MemoryAccessKind::AtomicStore
+                | MemoryAccessKind::AtomicRmw
+        )
+    }
+
+    fn from_environ(kind: wasmtime_environ::MemoryAccessKind) -> MemoryAccessKind {
+        match kind {
+            wasmtime_environ::MemoryAccessKind::Load => MemoryAccessKind::Load,
+            wasmtime_environ::MemoryAccessKind::Store => MemoryAccessKind::Store,
+            wasmtime_environ::MemoryAccessKind::AtomicLoad => MemoryAccessKind::AtomicLoad,
+            wasmtime_environ::MemoryAccessKind::AtomicStore => MemoryAccessKind::AtomicStore,
+            wasmtime_environ::MemoryAccessKind::AtomicRmw => MemoryAccessKind::AtomicRmw,
+        }
+    }
+}
+
+/// A description of a WebAssembly access to linear memory, passed to the hook
+/// configured with
+/// [`Store::memory_access_hook`](crate::Store::memory_access_hook).
+///
+/// Accesses are reported before they are performed, so the hook observes the
+/// contents of memory as they were before a store. Accesses which trap as out
+/// of bounds may still be reported: when bounds checks are elided in favor of
+/// guard pages, as they are by default on 64-bit hosts, the hook runs before
+/// the access faults. The reported address of an access whose address
+/// overflows wraps around. Bulk memory instructions such as `memory.copy` and
+/// `memory.fill`, and accesses made by the host, are not reported.
+#[derive(Copy, Clone, Debug)]
+pub struct MemoryAccess {
+    instance: Instance,
+    memory_index: u32,
+    address: u64,
+    size: u8,
+    kind: MemoryAccessKind,
+    module_offset: usize,
+    /// The base address of the accessed memory, if it is a shared memory,
+    /// which identifies it across all the instances and stores using it.
+    shared_memory_base: Option<usize>,
+}
+
+impl MemoryAccess {
+    /// Returns the instance which made the access.
+    pub fn instance(&self) -> Instance {
+        self.instance
+    }
+
+    /// Returns the index, in the instance's module's memory index space, of
+    /// the memory which was accessed.
+    ///
+    /// This includes imported memories, so separate instances sharing one
+    /// memory may report it under different indices.
+    pub fn memory_index(&self) -> u32 {
+        self.memory_index
+    }
+
+    /// Returns the effective address of the access: the address operand of
+    /// the instruction plus its static offset.
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Returns the number of bytes accessed, starting at
+    /// [`MemoryAccess::address`].
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    /// Returns the kind of the access.
+    pub fn kind(&self) -> MemoryAccessKind {
+        self.kind
+    }
+
+    /// Returns the offset, relative to the start of the original wasm module,
+    /// of the instruction which made the access, as with
+    /// [`FrameInfo::module_offset`](crate::FrameInfo::module_offset).
+    pub fn module_offset(&self) -> usize {
+        self.module_offset
+    }
+
+    /// Returns whether the accessed memory is a shared memory.
+    pub fn is_shared(&self) -> bool {
+        self.shared_memory_base.is_some()
+    }
+
+    pub(crate) fn shared_memory_base(&self) -> Option<usize> {
+        self.shared_memory_base
+    }
+}
+
+impl MemoryAccess {
+    /// Creates the description of an access reported by the
+    /// `trace_memory_access` libcall.
+    pub(crate) fn new(
+        instance: &crate::runtime::vm::Instance,
+        memory_index: u32,
+        address: u64,
+        size: u8,
+        kind: wasmtime_environ::MemoryAccessKind,
+        module_offset: usize,
+    ) -> MemoryAccess {
+        let index = wasmtime_environ::MemoryIndex::from_u32(memory_index);
+        // The base of a shared memory never moves, and every instance importing
+        // it sees the same one.
+        let shared_memory_base = if instance.env_module().memories[index].shared {
+            Some(instance.get_memory(index).base as usize)
+        } else {
+            None
+        };
+        MemoryAccess {
+            instance: *instance
+                .host_state()
+                .downcast_ref::<Instance>()
+                .expect("instrumented code always belongs to an instance"),
+            memory_index,
+            address,
+            size,
+            kind: MemoryAccessKind::from_environ(kind),
+            module_offset,
+            shared_memory_base,
+        }
+    }
+}
@@ -1040,6 +1040,66 @@ impl Module {
         })
     }
 
+    /// Returns the offsets of the instructions at which `line` of the source
+    /// file `file` begins, according to the module's DWARF debug information.
+    ///
+    /// Offsets are relative to the start of the original wasm module, as used
+    /// by [`Store::add_breakpoint`](crate::Store::add_breakpoint). Since paths
+    /// in debug information are often relative, a path matches `file` if
+    /// either one ends with the other at a path separator. A line whose code
+    /// is spread across several ranges of instructions has an offset for each
+    /// of them.
+    ///
+    /// The list is empty if the module has no debug information. Debug
+    /// information is only retained if
+    /// [`Config::wasm_backtrace_details`](crate::Config::wasm_backtrace_details)
+    /// is enabled.
+    #[cfg(feature = "addr2line")]
+    pub fn line_offsets(&self, file: &str, line: u32) -> Result<Vec<usize>> {
+        let Some(cx) = self.compiled_module().symbolize_context()? else {
+            return Ok(Vec::new());
+        };
+        let mut rows = cx
+            .addr2line()
+            .find_location_range(0, u64::MAX)?
+            .map(|(addr, len, location)| (addr, len, location.file, location.line))
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|row| row.0);
+
+        let matches = |path: Option<&str>| {
+            let Some(path) = path else { return false };
+            let (long, short) = if path.len() >= file.len() {
+                (path, file)
+            } else {
+                (file, path)
+            };
+            long.ends_with(short)
+                && (long.len() == short.len()
+                    || long[..long.len() - short.len()].ends_with(['/', '\\']))
+        };
+
+        let mut offsets = Vec::new();
+        let mut prev = None;
+        for &(addr, len, row_file, row_line) in rows.iter() {
+            // Only the first of several consecutive rows for the same line
+            // begins that line.
+            let continues_prev = prev.is_some_and(|(end, prev_file, prev_line)| {
+                end == addr && prev_file == row_file && prev_line == row_line
+            });
+            if !continues_prev && row_line == Some(line) && matches(row_file) {
+                offsets.push(usize::try_from(addr + cx.code_section_offset()).unwrap());
+            }
+            prev = Some((addr + len, row_file, row_line));
+        }
+        Ok(offsets)
+    }
+
+    /// Returns whether `a` and `b` refer to the same compiled module.
+    #[inline]
+    pub fn same(a: &Module, b: &Module) -> bool {
+        Arc::ptr_eq(&a.inner, &b.inner)
+    }
+
     pub(crate) fn id(&self) -> CompiledModuleId {
         self.inner.module.unique_id()
     }
@@ -1165,6 +1225,12 @@ fn memory_images(engine: &Engine, module: &CompiledModule) -> Result<Option<Modu
         return Ok(None);
     }
 
+    // Memories are populated lazily from the module's data segments when
+    // `userfaultfd` is in use, so no images are needed.
+    if engine.config().userfaultfd() {
+        return Ok(None);
+    }
+
     // ... otherwise logic is delegated to the `ModuleMemoryImages::new`
     // constructor.
     let mmap = if engine.config().force_memory_init_memfd {
@@ -30,6 +30,9 @@ pub struct ModuleRegistry {
 
     // Preserved for keeping data segments alive or similar
     modules_without_code: Vec<Module>,
+
+    // The size in bytes of the code objects in `loaded_code`.
+    code_size: usize,
 }
 
 struct LoadedCode {
@@ -102,6 +105,23 @@ impl ModuleRegistry {
         self.register(component.code_object(), None);
     }
 
+    /// Returns the size in bytes of the compiled code registered so far, and
+    /// what it would be after also registering `code`.
+    ///
+    /// Code which is already registered isn't counted again.
+    pub fn code_growth(&self, code: &CodeObject) -> (usize, usize) {
+        let text = code.code_memory().text();
+        if text.is_empty() {
+            return (self.code_size, self.code_size);
+        }
+        let end_addr = text.as_ptr() as usize + text.len() - 1;
+        if self.loaded_code.contains_key(&end_addr) {
+            return (self.code_size, self.code_size);
+        }
+        let len = code.code_memory().mmap().len();
+        (self.code_size, self.code_size + len)
+    }
+
     /// Registers a new module with the registry.
     fn register(
         &mut self,
@@ -160,6 +180,7 @@ impl ModuleRegistry {
         }
         let prev = self.loaded_code.insert(end_addr, (start_addr, item));
         assert!(prev.is_none());
+        self.code_size += code.code_memory().mmap().len();
         id
     }
 
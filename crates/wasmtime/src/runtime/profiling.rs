@@ -7,15 +7,18 @@ use fxprof_processed_profile::{
     CategoryHandle, Frame, FrameFlags, FrameInfo, LibraryInfo, MarkerLocation, MarkerSchema,
     MarkerTiming, Profile, ProfilerMarker, ReferenceTimestamp, Symbol, SymbolTable, Timestamp,
 };
+use std::collections::HashMap;
 use std::ops::Range;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use wasmtime_environ::demangle_function_name_or_index;
 
+mod pprof;
+
 // TODO: collect more data
 // - On non-Windows, measure thread-local CPU usage between events with
 //   rustix::time::clock_gettime(ClockId::ThreadCPUTime)
-// - Report which wasm module, and maybe instance, each frame came from
+// - Report which instance each frame came from
 
 /// Collects basic profiling data for a single WebAssembly guest.
 ///
@@ -52,6 +55,25 @@ use wasmtime_environ::demangle_function_name_or_index;
 /// method is not currently async-signal-safe, so doing this correctly is not
 /// easy.
 ///
+/// # Output formats
+///
+/// Once the guest has finished running, the profile can be written out in
+/// one of several formats:
+///
+/// * [`GuestProfiler::finish`] writes the Firefox "processed profile format",
+///   which includes a timeline of samples and host calls.
+/// * [`GuestProfiler::finish_pprof`] writes the [pprof] format used by many
+///   continuous-profiling services.
+/// * [`GuestProfiler::finish_collapsed`] writes "collapsed stacks", with one
+///   line per distinct stack, as consumed by flame graph tools.
+///
+/// The latter two only include samples, aggregated by stack. In all of them,
+/// each function is attributed to the name of the module given to
+/// [`GuestProfiler::new`], or to the component given to
+/// [`GuestProfiler::new_component`].
+///
+/// [pprof]: https://github.com/google/pprof/blob/main/proto/profile.proto
+///
 /// # Security
 ///
 /// Profiles produced using this profiler do not include any configuration
@@ -76,9 +98,34 @@ pub struct GuestProfiler {
     process: fxprof_processed_profile::ProcessHandle,
     thread: fxprof_processed_profile::ThreadHandle,
     start: Instant,
+    start_time: SystemTime,
+    interval: Duration,
+    /// Samples aggregated by stack, for the formats which don't need each
+    /// sample individually.
+    stacks: HashMap<Vec<FuncKey>, StackTotals>,
 }
 
-type Modules = Vec<(Range<usize>, fxprof_processed_profile::LibraryHandle)>;
+type Modules = Vec<ProfiledModule>;
+
+#[derive(Debug)]
+struct ProfiledModule {
+    /// The addresses of the module's compiled code.
+    range: Range<usize>,
+    lib: fxprof_processed_profile::LibraryHandle,
+    name: String,
+    /// The range of offsets in the module's compiled code, and the name, of
+    /// each function, sorted by offset.
+    funcs: Vec<(Range<u32>, String)>,
+}
+
+/// The index of a module in `Modules` and of a function in its `funcs`.
+type FuncKey = (usize, usize);
+
+#[derive(Debug, Default, Clone, Copy)]
+struct StackTotals {
+    samples: u64,
+    cpu: Duration,
+}
 
 impl GuestProfiler {
     /// Begin profiling a new guest. When this function is called, the current
@@ -105,14 +152,29 @@ impl GuestProfiler {
             .filter_map(|(name, module)| {
                 let compiled = module.compiled_module();
                 let text = compiled.text().as_ptr_range();
-                let address_range = text.start as usize..text.end as usize;
-                module_symbols(name, compiled).map(|lib| (address_range, profile.add_lib(lib)))
+                let range = text.start as usize..text.end as usize;
+                let symbols = module_symbols(compiled);
+                if symbols.is_empty() {
+                    return None;
+                }
+                let funcs = symbols
+                    .iter()
+                    .map(|s| (s.address..s.address + s.size.unwrap(), s.name.clone()))
+                    .collect();
+                let lib = profile.add_lib(library_info(name.clone(), symbols));
+                Some(ProfiledModule {
+                    range,
+                    lib,
+                    name,
+                    funcs,
+                })
             })
             .collect();
 
-        modules.sort_unstable_by_key(|(range, _)| range.start);
+        modules.sort_unstable_by_key(|module| module.range.start);
 
-        profile.set_reference_timestamp(std::time::SystemTime::now().into());
+        let start_time = SystemTime::now();
+        profile.set_reference_timestamp(start_time.into());
         let process = profile.add_process(module_name, 0, Timestamp::from_nanos_since_reference(0));
         let thread = profile.add_thread(process, 0, Timestamp::from_nanos_since_reference(0), true);
         let start = Instant::now();
@@ -122,9 +184,44 @@ impl GuestProfiler {
             process,
             thread,
             start,
+            start_time,
+            interval,
+            stacks: HashMap::new(),
         }
     }
 
+    /// Begin profiling a new guest which is an instance of `component`.
+    ///
+    /// This is the same as [`GuestProfiler::new`], except that every core
+    /// module within `component` is profiled, with its functions attributed
+    /// to `component_name`. Modules within the component are distinguished by
+    /// their names, if they have one, or otherwise by their index within the
+    /// component, as in `component_name/module0`.
+    ///
+    /// Modules in `extra_modules` are profiled as well, which is useful for
+    /// profiling other components or modules running in the same store.
+    #[cfg(feature = "component-model")]
+    pub fn new_component(
+        component_name: &str,
+        interval: Duration,
+        component: crate::component::Component,
+        extra_modules: Vec<(String, Module)>,
+    ) -> Self {
+        let modules = component
+            .static_modules()
+            .enumerate()
+            .map(|(i, module)| {
+                let name = match module.name() {
+                    Some(name) => format!("{component_name}/{name}"),
+                    None => format!("{component_name}/module{i}"),
+                };
+                (name, module.clone())
+            })
+            .chain(extra_modules)
+            .collect();
+        Self::new(component_name, interval, modules)
+    }
+
     /// Add a sample to the profile. This function collects a backtrace from
     /// any stack frames for allowed modules on the current stack. It should
     /// typically be called from a callback registered using
@@ -141,6 +238,11 @@ impl GuestProfiler {
         let frames = lookup_frames(&self.modules, &backtrace);
         self.profile
             .add_sample(self.thread, now, frames, delta.into(), 1);
+
+        let stack = lookup_funcs(&self.modules, &backtrace).collect();
+        let totals = self.stacks.entry(stack).or_default();
+        totals.samples += 1;
+        totals.cpu += delta;
     }
 
     /// Add a marker for transitions between guest and host to the profile.
@@ -191,9 +293,71 @@ impl GuestProfiler {
         serde_json::to_writer(output, &self.profile)?;
         Ok(())
     }
+
+    /// When the guest finishes running, call this function to write the
+    /// profile to the given `output` in the [pprof format][fmt], as an
+    /// uncompressed protocol buffer.
+    ///
+    /// Each sample has two values: the number of samples taken with that
+    /// stack, and the total CPU time passed to [`GuestProfiler::sample`] for
+    /// them. Functions are attributed to their module, or component, through
+    /// their file name and mapping.
+    ///
+    /// [fmt]: https://github.com/google/pprof/blob/main/proto/profile.proto
+    pub fn finish_pprof(self, mut output: impl std::io::Write) -> Result<()> {
+        let duration = self.start.elapsed();
+        let start_time = self
+            .start_time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let bytes = pprof::encode(
+            &self.modules,
+            &self.stacks,
+            start_time,
+            duration,
+            self.interval,
+        );
+        output.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// When the guest finishes running, call this function to write the
+    /// profile to the given `output` as "collapsed stacks".
+    ///
+    /// Each line of the output holds a distinct stack, from the outermost
+    /// function to the innermost separated by `;`, followed by a space and
+    /// the number of samples taken with that stack. Each function is written
+    /// as ``module`function``, where `module` is the name of its module or
+    /// component. This is the format used by [`flamegraph.pl`] and
+    /// [`inferno`], among others.
+    ///
+    /// [`flamegraph.pl`]: https://github.com/brendangregg/FlameGraph
+    /// [`inferno`]: https://github.com/jonhoo/inferno
+    pub fn finish_collapsed(self, mut output: impl std::io::Write) -> Result<()> {
+        let mut lines = self
+            .stacks
+            .iter()
+            .filter(|(stack, _)| !stack.is_empty())
+            .map(|(stack, totals)| {
+                let frames = stack
+                    .iter()
+                    .map(|&(module, func)| {
+                        let module = &self.modules[module];
+                        format!("{}`{}", module.name, module.funcs[func].1)
+                    })
+                    .collect::<Vec<_>>();
+                (frames.join(";"), totals.samples)
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+        for (stack, samples) in lines {
+            writeln!(output, "{stack} {samples}")?;
+        }
+        Ok(())
+    }
 }
 
-fn module_symbols(name: String, compiled: &CompiledModule) -> Option<LibraryInfo> {
+fn module_symbols(compiled: &CompiledModule) -> Vec<Symbol> {
     let symbols = Vec::from_iter(compiled.finished_functions().map(|(defined_idx, _)| {
         let loc = compiled.func_loc(defined_idx);
         let func_idx = compiled.module().func_index(defined_idx);
@@ -210,11 +374,11 @@ fn module_symbols(name: String, compiled: &CompiledModule) -> Option<LibraryInfo
             name,
         }
     }));
-    if symbols.is_empty() {
-        return None;
-    }
+    symbols
+}
 
-    Some(LibraryInfo {
+fn library_info(name: String, symbols: Vec<Symbol>) -> LibraryInfo {
+    LibraryInfo {
         name,
         debug_name: String::new(),
         path: String::new(),
@@ -223,7 +387,7 @@ fn module_symbols(name: String, compiled: &CompiledModule) -> Option<LibraryInfo
         code_id: None,
         arch: None,
         symbol_table: Some(Arc::new(SymbolTable::new(symbols))),
-    })
+    }
 }
 
 fn lookup_frames<'a>(
@@ -236,24 +400,52 @@ fn lookup_frames<'a>(
         // first, so iterate in reverse.
         .rev()
         .filter_map(|frame| {
-            // Find the first module whose start address includes this PC.
-            let module_idx = modules.partition_point(|(range, _)| range.start > frame.pc());
-            if let Some((range, lib)) = modules.get(module_idx) {
-                if range.contains(&frame.pc()) {
-                    return Some(FrameInfo {
-                        frame: Frame::RelativeAddressFromReturnAddress(
-                            *lib,
-                            u32::try_from(frame.pc() - range.start).unwrap(),
-                        ),
-                        category_pair: CategoryHandle::OTHER.into(),
-                        flags: FrameFlags::empty(),
-                    });
-                }
-            }
-            None
+            let module = &modules[find_module(modules, frame.pc())?];
+            Some(FrameInfo {
+                frame: Frame::RelativeAddressFromReturnAddress(
+                    module.lib,
+                    u32::try_from(frame.pc() - module.range.start).unwrap(),
+                ),
+                category_pair: CategoryHandle::OTHER.into(),
+                flags: FrameFlags::empty(),
+            })
         })
 }
 
+/// Like `lookup_frames`, but identifies the function of each frame instead.
+fn lookup_funcs<'a>(
+    modules: &'a Modules,
+    backtrace: &'a Backtrace,
+) -> impl Iterator<Item = FuncKey> + 'a {
+    backtrace.frames().rev().filter_map(|frame| {
+        let module_idx = find_module(modules, frame.pc())?;
+        let module = &modules[module_idx];
+        // Frames other than the innermost hold return addresses, which may
+        // be just past the end of the calling function, so look up the
+        // address of the call instead.
+        let offset = u32::try_from(frame.pc() - module.range.start)
+            .unwrap()
+            .saturating_sub(1);
+        let func_idx = module
+            .funcs
+            .partition_point(|(range, _)| range.end <= offset);
+        match module.funcs.get(func_idx) {
+            Some((range, _)) if range.contains(&offset) => Some((module_idx, func_idx)),
+            _ => None,
+        }
+    })
+}
+
+/// Returns the index of the module whose code includes `pc`, if any.
+fn find_module(modules: &Modules, pc: usize) -> Option<usize> {
+    // Modules are sorted by their start address, so find the last module
+    // which starts at or before this PC.
+    let idx = modules
+        .partition_point(|module| module.range.start <= pc)
+        .checked_sub(1)?;
+    modules[idx].range.contains(&pc).then_some(idx)
+}
+
 struct CallMarker;
 
 impl ProfilerMarker for CallMarker {
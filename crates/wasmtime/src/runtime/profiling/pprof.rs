@@ -0,0 +1,187 @@
+//! Encoding of guest profiles in the pprof format.
+//!
+//! The field numbers below are those of the schema at
+//! <https://github.com/google/pprof/blob/main/proto/profile.proto>.
+
+use super::{FuncKey, Modules, StackTotals};
+use crate::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Encodes the samples in `stacks`, whose functions are found in `modules`.
+///
+/// `start_time` is the time since the Unix epoch that profiling started at.
+pub(super) fn encode(
+    modules: &Modules,
+    stacks: &HashMap<Vec<FuncKey>, StackTotals>,
+    start_time: Duration,
+    duration: Duration,
+    interval: Duration,
+) -> Vec<u8> {
+    let mut strings = StringTable::default();
+    let mut profile = Message::default();
+
+    // sample_type
+    for (ty, unit) in [("samples", "count"), ("cpu", "nanoseconds")] {
+        profile.message(1, |m| {
+            m.uint64(1, strings.get(ty));
+            m.uint64(2, strings.get(unit));
+        });
+    }
+
+    // sample, with stacks sorted so that output is deterministic. Each
+    // function has a single location, and they share ids.
+    let mut stacks = stacks.iter().collect::<Vec<_>>();
+    stacks.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    let mut funcs = Vec::new();
+    let mut func_ids = HashMap::new();
+    for (stack, totals) in stacks {
+        if stack.is_empty() {
+            continue;
+        }
+        // Locations are listed from the innermost frame outwards.
+        let locations = stack
+            .iter()
+            .rev()
+            .map(|key| {
+                *func_ids.entry(*key).or_insert_with(|| {
+                    funcs.push(*key);
+                    u64::try_from(funcs.len()).unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+        profile.message(2, |m| {
+            m.packed(1, locations);
+            m.packed(2, [totals.samples, nanos(totals.cpu)]);
+        });
+    }
+
+    // mapping, with one for each module whose addresses are offsets in its
+    // compiled code.
+    for (i, module) in modules.iter().enumerate() {
+        profile.message(3, |m| {
+            m.uint64(1, u64::try_from(i + 1).unwrap());
+            m.uint64(3, u64::try_from(module.range.len()).unwrap());
+            m.uint64(5, strings.get(&module.name));
+            m.uint64(7, 1);
+        });
+    }
+
+    // location and function
+    for (i, &(module_idx, func)) in funcs.iter().enumerate() {
+        let id = u64::try_from(i + 1).unwrap();
+        let module = &modules[module_idx];
+        let (range, name) = &module.funcs[func];
+        profile.message(4, |m| {
+            m.uint64(1, id);
+            m.uint64(2, u64::try_from(module_idx + 1).unwrap());
+            m.uint64(3, u64::from(range.start));
+            m.message(4, |line| line.uint64(1, id));
+        });
+        let name = strings.get(name);
+        let filename = strings.get(&module.name);
+        profile.message(5, |m| {
+            m.uint64(1, id);
+            m.uint64(2, name);
+            m.uint64(3, name);
+            m.uint64(4, filename);
+        });
+    }
+
+    profile.uint64(9, nanos(start_time));
+    profile.uint64(10, nanos(duration));
+    let wall = strings.get("wall");
+    let nanoseconds = strings.get("nanoseconds");
+    profile.message(11, |m| {
+        m.uint64(1, wall);
+        m.uint64(2, nanoseconds);
+    });
+    profile.uint64(12, nanos(interval));
+
+    // string_table
+    for s in strings.strings.iter() {
+        profile.bytes(6, s.as_bytes());
+    }
+    profile.0
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+/// The strings referred to by index throughout a profile, the first of
+/// which must be empty.
+struct StringTable {
+    strings: Vec<String>,
+    indices: HashMap<String, u64>,
+}
+
+impl Default for StringTable {
+    fn default() -> StringTable {
+        let mut table = StringTable {
+            strings: Vec::new(),
+            indices: HashMap::new(),
+        };
+        table.get("");
+        table
+    }
+}
+
+impl StringTable {
+    fn get(&mut self, s: &str) -> u64 {
+        if let Some(index) = self.indices.get(s) {
+            return *index;
+        }
+        let index = u64::try_from(self.strings.len()).unwrap();
+        self.strings.push(s.to_string());
+        self.indices.insert(s.to_string(), index);
+        index
+    }
+}
+
+/// An encoded protobuf message.
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push(u8::try_from(value & 0x7f).unwrap() | 0x80);
+            value >>= 7;
+        }
+        self.0.push(u8::try_from(value).unwrap());
+    }
+
+    fn key(&mut self, field: u32, wire_type: u8) {
+        self.varint((u64::from(field) << 3) | u64::from(wire_type));
+    }
+
+    /// Encodes an integer field, which is omitted if it's zero as that's the
+    /// default.
+    fn uint64(&mut self, field: u32, value: u64) {
+        if value != 0 {
+            self.key(field, 0);
+            self.varint(value);
+        }
+    }
+
+    fn bytes(&mut self, field: u32, bytes: &[u8]) {
+        self.key(field, 2);
+        self.varint(u64::try_from(bytes.len()).unwrap());
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn packed(&mut self, field: u32, values: impl IntoIterator<Item = u64>) {
+        let mut packed = Message::default();
+        for value in values {
+            packed.varint(value);
+        }
+        self.bytes(field, &packed.0);
+    }
+
+    fn message(&mut self, field: u32, f: impl FnOnce(&mut Message)) {
+        let mut message = Message::default();
+        f(&mut message);
+        self.bytes(field, &message.0);
+    }
+}
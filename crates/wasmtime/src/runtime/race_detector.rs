@@ -0,0 +1,523 @@
+//! A happens-before data race detector for shared linear memories, see
+//! [`RaceDetector`].
+
+use crate::prelude::*;
+use crate::store::StoreId;
+use crate::{AsContext, MemoryAccess, MemoryAccessKind, Store, StoreContextMut, WasmBacktrace};
+use core::{fmt, mem};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+/// A detector of data races between threads accessing shared linear memories.
+///
+/// This is modeled after ThreadSanitizer: each thread of execution, typically
+/// a [`Store`] created for a `wasi-threads` thread, carries a vector clock
+/// which tracks what it has synchronized with. Atomic accesses synchronize
+/// threads, with atomic stores releasing the clock of the thread into the
+/// accessed address and atomic loads acquiring it, and spawning a thread
+/// orders everything its parent did before the spawn before the new thread.
+/// Two accesses to the same byte of a shared memory race when they are made
+/// by different threads, at least one of them writes, at least one of them
+/// is not atomic, and neither happens before the other.
+///
+/// Accesses are reported by code compiled with
+/// [`Config::race_detection`](crate::Config::race_detection) enabled, which
+/// instruments every load and store of a shared memory. A thread is tracked
+/// by attaching a [`RaceThread`] to its store with [`RaceThread::attach`].
+/// Each race is reported once per pair of instructions involved, with a
+/// [`WasmBacktrace`] of the access which completed the race, to the handler
+/// given to [`RaceDetector::with_handler`] and in
+/// [`RaceDetector::take_races`].
+///
+/// Only accesses made by WebAssembly loads and stores are tracked: bulk
+/// memory instructions, `memory.atomic.wait` and `memory.atomic.notify`, and
+/// accesses made by the host are not. The detector keeps state for every byte
+/// of shared memory accessed, so it is intended for use only while debugging.
+/// This state is discarded once every attached store has been dropped, and is
+/// flushed whenever it grows past [`RaceDetector::set_shadow_limit`] bytes, in
+/// which case races between accesses on either side of the flush are missed.
+///
+/// # Examples
+///
+/// ```
+/// # use wasmtime::*;
+/// # fn main() -> anyhow::Result<()> {
+/// let mut config = Config::new();
+/// config.wasm_threads(true).race_detection(true);
+/// let engine = Engine::new(&config)?;
+/// let module = Module::new(&engine, r#"
+///     (module
+///         (import "env" "memory" (memory 1 1 shared))
+///         (func (export "write") (i32.store (i32.const 0) (i32.const 1))))
+/// "#)?;
+/// let memory = SharedMemory::new(&engine, MemoryType::shared(1, 1))?;
+///
+/// let detector = RaceDetector::new();
+/// let mut parent = Store::new(&engine, ());
+/// detector.thread().attach(&mut parent);
+/// let instance = Instance::new(&mut parent, &module, &[memory.clone().into()])?;
+/// instance.get_typed_func::<(), ()>(&mut parent, "write")?.call(&mut parent, ())?;
+///
+/// // A thread spawned after the parent's write is ordered after it...
+/// let mut child = Store::new(&engine, ());
+/// detector.fork(&parent).attach(&mut child);
+/// let instance = Instance::new(&mut child, &module, &[memory.clone().into()])?;
+/// instance.get_typed_func::<(), ()>(&mut child, "write")?.call(&mut child, ())?;
+/// assert!(detector.take_races().is_empty());
+///
+/// // ...but the parent's next write isn't ordered with the child's.
+/// let instance = Instance::new(&mut parent, &module, &[memory.into()])?;
+/// instance.get_typed_func::<(), ()>(&mut parent, "write")?.call(&mut parent, ())?;
+/// assert_eq!(detector.take_races().len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RaceDetector {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    state: Mutex<State>,
+    handler: Option<Box<dyn Fn(&DataRace) + Send + Sync>>,
+}
+
+struct State {
+    /// The vector clock of each thread, indexed by thread id.
+    clocks: Vec<VectorClock>,
+    /// The thread attached to each store.
+    stores: HashMap<StoreId, u32>,
+    /// The clocks released into each address by atomic stores, keyed by
+    /// memory base and address.
+    sync: HashMap<(usize, u64), VectorClock>,
+    /// The last accesses to each byte, keyed by memory base and address.
+    shadow: HashMap<(usize, u64), Shadow>,
+    /// The maximum number of entries in `shadow` before it is flushed.
+    shadow_limit: usize,
+    /// The pairs of instruction offsets which have already been reported.
+    reported: HashSet<(usize, usize)>,
+    races: Vec<DataRace>,
+}
+
+/// The default for [`RaceDetector::set_shadow_limit`], 16 million bytes.
+const DEFAULT_SHADOW_LIMIT: usize = 16 << 20;
+
+impl Default for State {
+    fn default() -> State {
+        State {
+            clocks: Vec::new(),
+            stores: HashMap::new(),
+            sync: HashMap::new(),
+            shadow: HashMap::new(),
+            shadow_limit: DEFAULT_SHADOW_LIMIT,
+            reported: HashSet::new(),
+            races: Vec::new(),
+        }
+    }
+}
+
+impl State {
+    /// Forgets all the accesses and releases recorded so far.
+    fn flush(&mut self) {
+        self.shadow = HashMap::new();
+        self.sync = HashMap::new();
+    }
+}
+
+#[derive(Clone, Default)]
+struct VectorClock(Vec<u32>);
+
+impl VectorClock {
+    fn get(&self, thread: u32) -> u32 {
+        self.0.get(thread as usize).copied().unwrap_or(0)
+    }
+
+    fn tick(&mut self, thread: u32) {
+        let thread = thread as usize;
+        if self.0.len() <= thread {
+            self.0.resize(thread + 1, 0);
+        }
+        self.0[thread] += 1;
+    }
+
+    fn join(&mut self, other: &VectorClock) {
+        if self.0.len() < other.0.len() {
+            self.0.resize(other.0.len(), 0);
+        }
+        for (mine, theirs) in self.0.iter_mut().zip(&other.0) {
+            *mine = (*mine).max(*theirs);
+        }
+    }
+
+    /// Returns whether `access` happens before the current point of the
+    /// thread owning this clock.
+    fn has_seen(&self, access: &ShadowAccess) -> bool {
+        access.clock <= self.get(access.info.thread)
+    }
+}
+
+/// The accesses to a byte of shared memory which later accesses may race with.
+#[derive(Default)]
+struct Shadow {
+    write: Option<ShadowAccess>,
+    /// The last read made by each thread since `write`.
+    reads: Vec<ShadowAccess>,
+}
+
+#[derive(Copy, Clone)]
+struct ShadowAccess {
+    clock: u32,
+    info: RacingAccess,
+}
+
+impl ShadowAccess {
+    fn races_with(&self, other: &ShadowAccess, clock: &VectorClock) -> bool {
+        self.info.thread != other.info.thread
+            && !(self.info.kind.is_atomic() && other.info.kind.is_atomic())
+            && !clock.has_seen(self)
+    }
+}
+
+impl RaceDetector {
+    /// Creates a new detector which records the races it finds, to be
+    /// retrieved with [`RaceDetector::take_races`].
+    pub fn new() -> RaceDetector {
+        RaceDetector::new_(None)
+    }
+
+    /// Creates a new detector which additionally invokes `handler` with each
+    /// race as soon as it is found.
+    ///
+    /// The handler is invoked on the thread making the access which completed
+    /// the race, before that access is performed.
+    pub fn with_handler(handler: impl Fn(&DataRace) + Send + Sync + 'static) -> RaceDetector {
+        RaceDetector::new_(Some(Box::new(handler)))
+    }
+
+    fn new_(handler: Option<Box<dyn Fn(&DataRace) + Send + Sync>>) -> RaceDetector {
+        RaceDetector {
+            inner: Arc::new(Inner {
+                state: Mutex::new(State::default()),
+                handler,
+            }),
+        }
+    }
+
+    /// Creates a new thread which hasn't synchronized with any other thread,
+    /// such as the main thread of a program.
+    pub fn thread(&self) -> RaceThread {
+        let mut state = self.inner.state.lock().unwrap();
+        let id = u32::try_from(state.clocks.len()).unwrap();
+        let mut clock = VectorClock::default();
+        clock.tick(id);
+        state.clocks.push(clock);
+        RaceThread {
+            detector: self.clone(),
+            id,
+        }
+    }
+
+    /// Creates a new thread spawned by the thread attached to `parent`, such
+    /// that everything `parent` did so far happens before anything the new
+    /// thread does.
+    ///
+    /// If no thread is attached to `parent` then this is the same as
+    /// [`RaceDetector::thread`].
+    pub fn fork(&self, parent: impl AsContext) -> RaceThread {
+        let thread = self.thread();
+        let mut state = self.inner.state.lock().unwrap();
+        if let Some(&parent) = state.stores.get(&parent.as_context().0.id()) {
+            let mut clock = state.clocks[parent as usize].clone();
+            clock.tick(thread.id);
+            state.clocks[thread.id as usize] = clock;
+            state.clocks[parent as usize].tick(parent);
+        }
+        thread
+    }
+
+    /// Sets the maximum number of bytes of shared memory whose accesses are
+    /// remembered at once.
+    ///
+    /// The detector keeps roughly a hundred bytes of state per byte accessed.
+    /// When the limit is reached all remembered accesses are forgotten, so
+    /// races between an access made before that point and one made after it
+    /// are not reported. Defaults to 16 million bytes.
+    pub fn set_shadow_limit(&self, bytes: usize) {
+        self.inner.state.lock().unwrap().shadow_limit = bytes;
+    }
+
+    /// Returns the races found since the last call to this function.
+    pub fn take_races(&self) -> Vec<DataRace> {
+        mem::take(&mut self.inner.state.lock().unwrap().races)
+    }
+
+    fn access<T>(&self, store: StoreContextMut<'_, T>, thread: u32, access: &MemoryAccess) {
+        let mut races = self.record(thread, access);
+        if races.is_empty() {
+            return;
+        }
+        let backtrace = Arc::new(WasmBacktrace::capture(&store));
+        for race in races.iter_mut() {
+            race.backtrace = Some(backtrace.clone());
+            if let Some(handler) = &self.inner.handler {
+                handler(race);
+            }
+        }
+        self.inner.state.lock().unwrap().races.extend(races);
+    }
+
+    /// Updates the clocks and shadow state for `access` by `thread`,
+    /// returning the new races it completes.
+    fn record(&self, thread: u32, access: &MemoryAccess) -> Vec<DataRace> {
+        let Some(memory) = access.shared_memory_base() else {
+            return Vec::new();
+        };
+        let mut state = self.inner.state.lock().unwrap();
+        if state.shadow.len() + usize::from(access.size()) > state.shadow_limit {
+            state.flush();
+        }
+        let State {
+            clocks,
+            sync,
+            shadow,
+            reported,
+            ..
+        } = &mut *state;
+        let clock = &mut clocks[thread as usize];
+        let kind = access.kind();
+        let address = access.address();
+
+        // All WebAssembly atomics are sequentially consistent, so atomic loads
+        // acquire what was released into their address...
+        if kind.is_atomic() && kind.is_read() {
+            if let Some(released) = sync.get(&(memory, address)) {
+                clock.join(released);
+            }
+        }
+
+        let current = ShadowAccess {
+            clock: clock.get(thread),
+            info: RacingAccess {
+                thread,
+                kind,
+                size: access.size(),
+                module_offset: access.module_offset(),
+            },
+        };
+        let mut races = Vec::new();
+        let mut report = |address: u64, previous: &ShadowAccess| {
+            let key = (previous.info.module_offset, current.info.module_offset);
+            if reported.insert(key) {
+                races.push(DataRace {
+                    address,
+                    memory_index: access.memory_index(),
+                    access: current.info,
+                    previous: previous.info,
+                    backtrace: None,
+                });
+            }
+        };
+        for byte in address..address + u64::from(access.size()) {
+            let shadow = shadow.entry((memory, byte)).or_default();
+            if let Some(write) = &shadow.write {
+                if write.races_with(&current, clock) {
+                    report(byte, write);
+                }
+            }
+            if kind.is_write() {
+                for read in shadow.reads.iter() {
+                    if read.races_with(&current, clock) {
+                        report(byte, read);
+                    }
+                }
+                shadow.write = Some(current);
+                shadow.reads.clear();
+            } else {
+                shadow.reads.retain(|read| read.info.thread != thread);
+                shadow.reads.push(current);
+            }
+        }
+
+        // ...and atomic stores release the thread's clock into it.
+        if kind.is_atomic() && kind.is_write() {
+            sync.entry((memory, address)).or_default().join(clock);
+            clock.tick(thread);
+        }
+        races
+    }
+}
+
+impl Default for RaceDetector {
+    fn default() -> RaceDetector {
+        RaceDetector::new()
+    }
+}
+
+/// A thread of execution tracked by a [`RaceDetector`].
+///
+/// Threads are created with [`RaceDetector::thread`] and
+/// [`RaceDetector::fork`], and then attached to the [`Store`] which runs them.
+pub struct RaceThread {
+    detector: RaceDetector,
+    id: u32,
+}
+
+impl RaceThread {
+    /// Returns the identifier of this thread, as used in [`DataRace`]
+    /// reports.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Attributes all shared memory accesses made in `store` to this thread.
+    ///
+    /// This installs a [`Store::memory_access_hook`], replacing any hook
+    /// previously configured.
+    pub fn attach<T>(self, store: &mut Store<T>) {
+        let RaceThread { detector, id } = self;
+        let store_id = store.as_context().0.id();
+        detector
+            .inner
+            .state
+            .lock()
+            .unwrap()
+            .stores
+            .insert(store_id, id);
+        let attached = Attached { detector, store_id };
+        store.memory_access_hook(move |store, access| {
+            attached.detector.access(store, id, access);
+            Ok(())
+        });
+    }
+}
+
+/// Owned by the hook of a store with an attached thread, to detach the thread
+/// when the store is dropped or the hook replaced.
+struct Attached {
+    detector: RaceDetector,
+    store_id: StoreId,
+}
+
+impl Drop for Attached {
+    fn drop(&mut self) {
+        let mut state = self.detector.inner.state.lock().unwrap();
+        state.stores.remove(&self.store_id);
+        // With no threads left to race with them, the remembered accesses can
+        // be forgotten.
+        if state.stores.is_empty() {
+            state.flush();
+        }
+    }
+}
+
+/// A data race found by a [`RaceDetector`].
+#[derive(Debug)]
+pub struct DataRace {
+    address: u64,
+    memory_index: u32,
+    access: RacingAccess,
+    previous: RacingAccess,
+    backtrace: Option<Arc<WasmBacktrace>>,
+}
+
+impl DataRace {
+    /// Returns the address of the first byte raced on.
+    pub fn address(&self) -> u64 {
+        self.address
+    }
+
+    /// Returns the index of the raced memory in the memory index space of the
+    /// module making [`DataRace::access`].
+    pub fn memory_index(&self) -> u32 {
+        self.memory_index
+    }
+
+    /// Returns the access which completed the race.
+    pub fn access(&self) -> &RacingAccess {
+        &self.access
+    }
+
+    /// Returns the earlier access which [`DataRace::access`] races with.
+    pub fn previous(&self) -> &RacingAccess {
+        &self.previous
+    }
+
+    /// Returns the WebAssembly backtrace of [`DataRace::access`].
+    pub fn backtrace(&self) -> &WasmBacktrace {
+        self.backtrace.as_ref().unwrap()
+    }
+}
+
+impl fmt::Display for DataRace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "data race on address {:#x} of shared memory {}",
+            self.address, self.memory_index
+        )?;
+        writeln!(f, "  {}", self.access)?;
+        writeln!(f, "  races with previous {}", self.previous)?;
+        write!(f, "backtrace of the current access:")?;
+        for (i, frame) in self.backtrace().frames().iter().enumerate() {
+            write!(f, "\n  {i:>3}: ")?;
+            if let Some(offset) = frame.module_offset() {
+                write!(f, "{offset:#6x} - ")?;
+            }
+            write!(f, "{}!", frame.module().name().unwrap_or("<unknown>"))?;
+            match frame.func_name() {
+                Some(name) => write!(f, "{name}")?,
+                None => write!(f, "<wasm function {}>", frame.func_index())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One of the two accesses making up a [`DataRace`].
+#[derive(Copy, Clone, Debug)]
+pub struct RacingAccess {
+    thread: u32,
+    kind: MemoryAccessKind,
+    size: u8,
+    module_offset: usize,
+}
+
+impl RacingAccess {
+    /// Returns the [`RaceThread::id`] of the thread which made the access.
+    pub fn thread(&self) -> u32 {
+        self.thread
+    }
+
+    /// Returns the kind of the access.
+    pub fn kind(&self) -> MemoryAccessKind {
+        self.kind
+    }
+
+    /// Returns the number of bytes accessed.
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    /// Returns the offset, relative to the start of the original wasm module,
+    /// of the instruction which made the access.
+    pub fn module_offset(&self) -> usize {
+        self.module_offset
+    }
+}
+
+impl fmt::Display for RacingAccess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            MemoryAccessKind::Load => "load",
+            MemoryAccessKind::Store => "store",
+            MemoryAccessKind::AtomicLoad => "atomic load",
+            MemoryAccessKind::AtomicStore => "atomic store",
+            MemoryAccessKind::AtomicRmw => "atomic read-modify-write",
+        };
+        write!(
+            f,
+            "{kind} of {} bytes by thread {} at wasm offset {:#x}",
+            self.size, self.thread, self.module_offset
+        )
+    }
+}
@@ -0,0 +1,753 @@
+//! Snapshots of an instance's state, which can be restored into new instances.
+
+use crate::prelude::*;
+use crate::runtime::vm::{ModuleMemoryImages, RestoredMemories, VMFuncRef};
+use crate::sync::OnceLock;
+use crate::{
+    AnyRef, AsContextMut, Extern, ExternRef, Func, HeapType, Instance, Module, Mutability, Ref,
+    Rooted, StoreContextMut, Val, ValType, V128,
+};
+use alloc::collections::VecDeque;
+use core::ptr::NonNull;
+use serde_derive::{Deserialize, Serialize};
+use wasmtime_environ::{
+    DefinedMemoryIndex, EntityRef, FuncIndex, ModuleInternedTypeIndex, PrimaryMap,
+    VMSharedTypeIndex,
+};
+
+#[cfg(feature = "gc")]
+mod gc;
+#[cfg(feature = "gc")]
+use gc::{restore_gc_ref, restore_objects};
+
+/// The magic header at the start of every serialized snapshot.
+const SNAPSHOT_MAGIC: &[u8] = b"\0wasmtime-snapshot-v2\0";
+
+/// A snapshot of the state of an [`Instance`], which can be restored into a
+/// new instance of the same [`Module`].
+///
+/// Snapshots are taken with [`Instance::snapshot`] and restored with
+/// [`InstanceSnapshot::instantiate`]. They may also be serialized to bytes
+/// and restored in another process, similar to pre-initializing a module
+/// with Wizer but at runtime.
+///
+/// A snapshot captures:
+///
+/// * The size and contents of each of the instance's defined linear
+///   memories.
+///
+/// * The value of each of the instance's defined, mutable globals. Immutable
+///   globals are re-initialized from the module when restoring.
+///
+/// * The size and elements of each of the instance's defined tables.
+///
+/// * Every GC object reachable from those globals and tables.
+///
+/// Imported memories, globals, and tables are owned by other instances or the
+/// host, and are not captured. Which data and element segments have been
+/// dropped is not captured either.
+///
+/// References to the instance's own functions are captured by their function
+/// index, and `i31ref`s are captured by value. `struct`s and `array`s are
+/// captured along with their fields, preserving which references point to the
+/// same object, including cycles. The host data behind an `externref` is
+/// opaque to Wasmtime, so it is only captured by
+/// [`Instance::snapshot_with_externrefs`], which asks the embedder to
+/// serialize it, and snapshots containing `externref`s must be restored with
+/// [`InstanceSnapshot::instantiate_with_externrefs`]. References to functions
+/// from other instances cannot be captured, and attempting to snapshot an
+/// instance whose state refers to them is an error.
+///
+/// A snapshot can only be restored into an instance of the module it was
+/// taken from, which is identified by a checksum of the module's original
+/// Wasm bytes.
+///
+/// When restoring, each memory's contents are used to create a copy-on-write
+/// memory image, if supported by the platform and
+/// [`Config::memory_init_cow`](crate::Config::memory_init_cow) is enabled.
+/// This image is created once and shared by all instances restored from the
+/// same `InstanceSnapshot`, making repeated restores cheap.
+pub struct InstanceSnapshot {
+    data: SnapshotData,
+
+    /// The size, in bytes, of each memory in `data.memories`.
+    memory_sizes: PrimaryMap<DefinedMemoryIndex, u64>,
+
+    /// Lazily-created copy-on-write images of `data.memories`.
+    memory_images: OnceLock<Option<ModuleMemoryImages>>,
+}
+
+/// The serialized portion of an `InstanceSnapshot`.
+#[derive(Serialize, Deserialize)]
+struct SnapshotData {
+    /// The checksum of the snapshotted module's original Wasm bytes, used to
+    /// check that a snapshot is restored into an instance of the same module.
+    checksum: [u8; 32],
+
+    /// The contents of each defined memory.
+    memories: Vec<Vec<u8>>,
+
+    /// The elements of each defined table.
+    tables: Vec<Vec<SavedRef>>,
+
+    /// The value of each defined global, or `None` for immutable globals.
+    globals: Vec<Option<SavedVal>>,
+
+    /// The GC objects reachable from `tables` and `globals`.
+    objects: Vec<SavedObject>,
+
+    /// The embedder's serialization of each `externref`'s host data.
+    externrefs: Vec<Vec<u8>>,
+}
+
+/// A saved global value or GC object field.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+enum SavedVal {
+    I32(i32),
+    I64(i64),
+    F32(u32),
+    F64(u64),
+    V128(u128),
+    Ref(SavedRef),
+}
+
+/// A saved reference.
+///
+/// GC references are saved the same way whether they are `anyref`s or
+/// `externref`s, and are converted with `any.convert_extern` or
+/// `extern.convert_any` as needed when restored.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+enum SavedRef {
+    /// A null reference.
+    Null,
+    /// A reference to the function with the given index in the instance.
+    Func(u32),
+    /// An `i31ref` with the given value.
+    I31(u32),
+    /// A reference to the object with the given index in
+    /// `SnapshotData::objects`.
+    Object(u32),
+    /// A reference to the `externref` with the given index in
+    /// `SnapshotData::externrefs`.
+    Extern(u32),
+}
+
+/// A saved `struct` or `array`.
+#[derive(Serialize, Deserialize)]
+struct SavedObject {
+    /// The object's type, as an index into the module's types.
+    ty: u32,
+    /// The struct's fields or the array's elements.
+    fields: Vec<SavedVal>,
+}
+
+/// A function that serializes an `externref`'s host data.
+type SaveExternRef<'a, T> =
+    dyn FnMut(StoreContextMut<'_, T>, Rooted<ExternRef>) -> Result<Vec<u8>> + 'a;
+
+/// A function that recreates an `externref` from its serialized host data.
+type RestoreExternRef<'a, T> =
+    dyn FnMut(StoreContextMut<'_, T>, &[u8]) -> Result<Rooted<ExternRef>> + 'a;
+
+/// The state used while taking a snapshot.
+#[cfg_attr(not(feature = "gc"), allow(dead_code))]
+struct Saver<'a, T> {
+    /// Each of the instance's escaping functions' `VMFuncRef`s, mapped back to
+    /// its index, so that function references can be saved by index.
+    funcs: crate::hash_map::HashMap<NonNull<VMFuncRef>, FuncIndex>,
+
+    /// Each of the module's types, mapped back to its index in the module, so
+    /// that GC objects' types can be saved by index.
+    types: crate::hash_map::HashMap<VMSharedTypeIndex, ModuleInternedTypeIndex>,
+
+    /// The embedder's function to serialize `externref`s, if any.
+    save_host_data: Option<&'a mut SaveExternRef<'a, T>>,
+
+    /// The index of each object and `externref` saved so far, keyed by its raw
+    /// GC reference.
+    object_ids: crate::hash_map::HashMap<u32, u32>,
+    externref_ids: crate::hash_map::HashMap<u32, u32>,
+
+    /// Objects that have been given an index, but whose fields have not been
+    /// saved yet.
+    pending: VecDeque<Rooted<AnyRef>>,
+
+    objects: Vec<SavedObject>,
+    externrefs: Vec<Vec<u8>>,
+}
+
+impl<T> Saver<'_, T> {
+    fn save_ref(&mut self, store: &mut StoreContextMut<'_, T>, r: Ref) -> Result<SavedRef> {
+        match r {
+            Ref::Func(None) | Ref::Extern(None) | Ref::Any(None) => Ok(SavedRef::Null),
+            Ref::Func(Some(f)) => match self.funcs.get(&f.vm_func_ref(store.0)) {
+                Some(index) => Ok(SavedRef::Func(index.as_u32())),
+                None => bail!(
+                    "cannot snapshot a reference to a function that is not \
+                     defined in or imported into the snapshotted instance"
+                ),
+            },
+            Ref::Any(Some(a)) => self.save_anyref(store, a),
+            Ref::Extern(Some(e)) => self.save_externref(store, e),
+        }
+    }
+
+    fn save_val(&mut self, store: &mut StoreContextMut<'_, T>, val: Val) -> Result<SavedVal> {
+        Ok(match val {
+            Val::I32(x) => SavedVal::I32(x),
+            Val::I64(x) => SavedVal::I64(x),
+            Val::F32(x) => SavedVal::F32(x),
+            Val::F64(x) => SavedVal::F64(x),
+            Val::V128(x) => SavedVal::V128(x.as_u128()),
+            Val::FuncRef(f) => SavedVal::Ref(self.save_ref(store, Ref::Func(f))?),
+            Val::ExternRef(e) => SavedVal::Ref(self.save_ref(store, Ref::Extern(e))?),
+            Val::AnyRef(a) => SavedVal::Ref(self.save_ref(store, Ref::Any(a))?),
+        })
+    }
+}
+
+/// The GC references recreated while restoring a snapshot, indexed like
+/// `SnapshotData::objects` and `SnapshotData::externrefs`.
+#[derive(Default)]
+#[cfg_attr(not(feature = "gc"), allow(dead_code))]
+struct RestoredRefs {
+    objects: Vec<Rooted<AnyRef>>,
+    externrefs: Vec<Rooted<ExternRef>>,
+}
+
+impl InstanceSnapshot {
+    pub(crate) fn new<'a, T>(
+        mut store: StoreContextMut<'_, T>,
+        instance: &Instance,
+        save_host_data: Option<&'a mut SaveExternRef<'a, T>>,
+    ) -> Result<InstanceSnapshot> {
+        // Keep every GC reference we encounter rooted until we're done, so
+        // that GC objects can be identified by their raw GC references.
+        let scope = store.0.gc_roots().enter_lifo_scope();
+        let result = Self::save(store.as_context_mut(), instance, save_host_data);
+        store.0.exit_gc_lifo_scope(scope);
+        result
+    }
+
+    fn save<'a, T>(
+        mut store: StoreContextMut<'_, T>,
+        instance: &Instance,
+        save_host_data: Option<&'a mut SaveExternRef<'a, T>>,
+    ) -> Result<InstanceSnapshot> {
+        let module = instance.module(&store).clone();
+        let env_module = module.env_module();
+
+        let funcs = {
+            let id = instance.id(store.0);
+            let handle = store.0.instance_mut(id).instance_mut();
+            env_module
+                .functions
+                .iter()
+                .filter(|(_, func)| func.is_escaping())
+                .filter_map(|(index, _)| Some((handle.get_func_ref(index)?, index)))
+                .collect()
+        };
+        let types = module
+            .signatures()
+            .as_module_map()
+            .iter()
+            .map(|(index, shared)| (*shared, index))
+            .collect();
+        let mut saver = Saver {
+            funcs,
+            types,
+            save_host_data,
+            object_ids: Default::default(),
+            externref_ids: Default::default(),
+            pending: VecDeque::new(),
+            objects: vec![],
+            externrefs: vec![],
+        };
+
+        let mut memories = vec![];
+        for (index, memory) in instance
+            .all_memories(store.0)
+            .skip(env_module.num_imported_memories)
+            .collect::<Vec<_>>()
+        {
+            if env_module.memories[index].shared {
+                bail!("cannot snapshot shared memories");
+            }
+            memories.push(memory.data(&store).to_vec());
+        }
+
+        let mut tables = vec![];
+        for (_index, table) in instance
+            .all_tables(store.0)
+            .skip(env_module.num_imported_tables)
+            .collect::<Vec<_>>()
+        {
+            let size = table.size(&store);
+            let mut elems = Vec::with_capacity(usize::try_from(size).unwrap_or(0));
+            for i in 0..size {
+                let elem = table.get(&mut store, i).expect("index is in bounds");
+                elems.push(saver.save_ref(&mut store, elem)?);
+            }
+            tables.push(elems);
+        }
+
+        let mut globals = vec![];
+        for (_index, global) in instance
+            .all_globals(store.0)
+            .skip(env_module.num_imported_globals)
+            .collect::<Vec<_>>()
+        {
+            if global.ty(&store).mutability() == Mutability::Const {
+                globals.push(None);
+                continue;
+            }
+            let val = global.get(&mut store);
+            globals.push(Some(saver.save_val(&mut store, val)?));
+        }
+
+        saver.save_objects(&mut store)?;
+
+        Ok(InstanceSnapshot::from_data(SnapshotData {
+            checksum: *module_checksum(&module)?,
+            memories,
+            tables,
+            globals,
+            objects: saver.objects,
+            externrefs: saver.externrefs,
+        }))
+    }
+
+    fn from_data(data: SnapshotData) -> InstanceSnapshot {
+        let memory_sizes = data
+            .memories
+            .iter()
+            .map(|m| u64::try_from(m.len()).unwrap())
+            .collect();
+        InstanceSnapshot {
+            data,
+            memory_sizes,
+            memory_images: OnceLock::new(),
+        }
+    }
+
+    /// Serialize this snapshot into bytes, which can be turned back into an
+    /// `InstanceSnapshot` with [`InstanceSnapshot::deserialize`].
+    pub fn serialize(&self) -> Result<Vec<u8>> {
+        let bytes = postcard::to_extend(&self.data, SNAPSHOT_MAGIC.to_vec())
+            .context("failed to serialize instance snapshot")?;
+        Ok(bytes)
+    }
+
+    /// Deserialize a snapshot previously produced by
+    /// [`InstanceSnapshot::serialize`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` is not a valid serialized snapshot.
+    pub fn deserialize(bytes: &[u8]) -> Result<InstanceSnapshot> {
+        let bytes = bytes
+            .strip_prefix(SNAPSHOT_MAGIC)
+            .ok_or_else(|| anyhow!("bytes are not a serialized instance snapshot"))?;
+        let data =
+            postcard::from_bytes(bytes).context("failed to deserialize instance snapshot")?;
+        Ok(InstanceSnapshot::from_data(data))
+    }
+
+    /// Restore this snapshot into a new instance of `module`.
+    ///
+    /// The `module` must be the same module that the snapshotted instance was
+    /// an instance of, and `imports` are provided just like they are for
+    /// [`Instance::new`]. The module's `start` function is not run, since the
+    /// snapshot already reflects its effects.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `module` doesn't match this snapshot, if the
+    /// snapshot contains `externref`s, if the imports are not valid for
+    /// `module`, or if instantiation otherwise fails, for example due to
+    /// resource limits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `store` has async support enabled, or if any [`Extern`]
+    /// supplied is not owned by `store`.
+    pub fn instantiate(
+        &self,
+        mut store: impl AsContextMut,
+        module: &Module,
+        imports: &[Extern],
+    ) -> Result<Instance> {
+        self.instantiate_impl(store.as_context_mut(), module, imports, None)
+    }
+
+    /// Restore this snapshot, which may contain `externref`s, into a new
+    /// instance of `module`.
+    ///
+    /// This is like [`InstanceSnapshot::instantiate`], except that
+    /// `restore_externref` is called with the bytes that the function given to
+    /// [`Instance::snapshot_with_externrefs`] produced for each `externref` in
+    /// the snapshot, and must return an equivalent `externref`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in the same cases as [`InstanceSnapshot::instantiate`],
+    /// and if `restore_externref` returns an error.
+    ///
+    /// # Panics
+    ///
+    /// Panics in the same cases as [`InstanceSnapshot::instantiate`], and if
+    /// `restore_externref` returns an `externref` that is not owned by
+    /// `store`.
+    pub fn instantiate_with_externrefs<T>(
+        &self,
+        mut store: impl AsContextMut<Data = T>,
+        module: &Module,
+        imports: &[Extern],
+        mut restore_externref: impl FnMut(StoreContextMut<'_, T>, &[u8]) -> Result<Rooted<ExternRef>>,
+    ) -> Result<Instance> {
+        self.instantiate_impl(
+            store.as_context_mut(),
+            module,
+            imports,
+            Some(&mut restore_externref),
+        )
+    }
+
+    fn instantiate_impl<T>(
+        &self,
+        mut store: StoreContextMut<'_, T>,
+        module: &Module,
+        imports: &[Extern],
+        restore_externref: Option<&mut RestoreExternRef<'_, T>>,
+    ) -> Result<Instance> {
+        if self.data.checksum != *module_checksum(module)? {
+            bail!("snapshot was taken from an instance of a different module");
+        }
+        self.validate(module)?;
+        if !self.data.externrefs.is_empty() && restore_externref.is_none() {
+            bail!(
+                "cannot restore a snapshot containing `externref`s without a function to \
+                 restore them; see `InstanceSnapshot::instantiate_with_externrefs`"
+            );
+        }
+
+        let images = if store.engine().tunables().memory_init_cow {
+            self.memory_images
+                .get_or_try_init(|| {
+                    ModuleMemoryImages::from_contents(self.data.memories.iter().map(|m| &m[..]))
+                })?
+                .as_ref()
+        } else {
+            None
+        };
+
+        let instance = Instance::new_restored(
+            store.0,
+            module,
+            imports,
+            RestoredMemories {
+                sizes: &self.memory_sizes,
+                images,
+            },
+        )?;
+
+        self.restore_memories(&mut store, &instance, images)?;
+
+        // Keep every restored GC reference rooted until it has been written
+        // into the instance's tables and globals.
+        let scope = store.0.gc_roots().enter_lifo_scope();
+        let result = self.restore_refs(store.as_context_mut(), &instance, restore_externref);
+        store.0.exit_gc_lifo_scope(scope);
+        result?;
+        Ok(instance)
+    }
+
+    /// Check that this snapshot's memories, tables, and globals fit `module`'s
+    /// definitions.
+    ///
+    /// A deserialized snapshot may have come from anywhere, so this is
+    /// checked even when the module's checksum matches.
+    fn validate(&self, module: &Module) -> Result<()> {
+        let env_module = module.env_module();
+
+        let memories = env_module
+            .memories
+            .values()
+            .skip(env_module.num_imported_memories);
+        ensure!(
+            memories.len() == self.data.memories.len(),
+            "snapshot has {} memories but the module defines {}",
+            self.data.memories.len(),
+            memories.len(),
+        );
+        for (i, (ty, size)) in memories.zip(self.memory_sizes.values()).enumerate() {
+            ensure!(
+                size % ty.page_size() == 0,
+                "snapshot's memory {i} is {size} bytes, which isn't a multiple of its page size",
+            );
+            let min = ty.minimum_byte_size().unwrap_or(u64::MAX);
+            let max = ty.maximum_byte_size().unwrap_or(u64::MAX);
+            ensure!(
+                min <= *size && *size <= max,
+                "snapshot's memory {i} is {size} bytes, which is outside of its limits",
+            );
+        }
+
+        let tables = env_module
+            .tables
+            .values()
+            .skip(env_module.num_imported_tables);
+        ensure!(
+            tables.len() == self.data.tables.len(),
+            "snapshot has {} tables but the module defines {}",
+            self.data.tables.len(),
+            tables.len(),
+        );
+        for (i, (ty, saved)) in tables.zip(&self.data.tables).enumerate() {
+            let size = u64::try_from(saved.len()).unwrap();
+            ensure!(
+                ty.limits.min <= size && ty.limits.max.map_or(true, |max| size <= max),
+                "snapshot's table {i} has {size} elements, which is outside of its limits",
+            );
+        }
+
+        let globals = env_module
+            .globals
+            .values()
+            .skip(env_module.num_imported_globals);
+        ensure!(
+            globals.len() == self.data.globals.len(),
+            "snapshot has {} globals but the module defines {}",
+            self.data.globals.len(),
+            globals.len(),
+        );
+        for (i, (ty, saved)) in globals.zip(&self.data.globals).enumerate() {
+            ensure!(
+                ty.mutability == saved.is_some(),
+                "snapshot's global {i} doesn't match the mutability of the module's",
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Restore the GC heap, tables, and globals.
+    fn restore_refs<T>(
+        &self,
+        mut store: StoreContextMut<'_, T>,
+        instance: &Instance,
+        restore_externref: Option<&mut RestoreExternRef<'_, T>>,
+    ) -> Result<()> {
+        let mut refs = RestoredRefs::default();
+        if let Some(restore_externref) = restore_externref {
+            for data in &self.data.externrefs {
+                let externref = restore_externref(store.as_context_mut(), data)
+                    .context("failed to restore an `externref`")?;
+                refs.externrefs.push(externref);
+            }
+        }
+        restore_objects(&mut store, instance, &self.data.objects, &mut refs)?;
+        self.restore_tables(&mut store, instance, &refs)?;
+        self.restore_globals(&mut store, instance, &refs)
+    }
+
+    /// Copy in the contents of each memory that wasn't initialized with a
+    /// copy-on-write image.
+    fn restore_memories<T>(
+        &self,
+        store: &mut StoreContextMut<'_, T>,
+        instance: &Instance,
+        images: Option<&ModuleMemoryImages>,
+    ) -> Result<()> {
+        let num_imported = instance.module(&*store).env_module().num_imported_memories;
+        let memories = instance
+            .all_memories(store.0)
+            .skip(num_imported)
+            .collect::<Vec<_>>();
+        for (i, ((_index, memory), saved)) in
+            memories.into_iter().zip(&self.data.memories).enumerate()
+        {
+            let defined = DefinedMemoryIndex::new(i);
+            if images.is_some_and(|images| images.get_memory_image(defined).is_some()) {
+                continue;
+            }
+
+            // This memory may have been initialized with the module's data
+            // segments, so overwrite the whole thing, zeroes included.
+            let data = memory.data_mut(&mut *store);
+            ensure!(
+                data.len() == saved.len(),
+                "restored memory {i} is {} bytes but its snapshot is {} bytes",
+                data.len(),
+                saved.len(),
+            );
+            data.copy_from_slice(saved);
+        }
+        Ok(())
+    }
+
+    fn restore_tables<T>(
+        &self,
+        store: &mut StoreContextMut<'_, T>,
+        instance: &Instance,
+        refs: &RestoredRefs,
+    ) -> Result<()> {
+        let num_imported = instance.module(&*store).env_module().num_imported_tables;
+        let tables = instance
+            .all_tables(store.0)
+            .skip(num_imported)
+            .collect::<Vec<_>>();
+        for ((_index, table), saved) in tables.into_iter().zip(&self.data.tables) {
+            let heap_type = table.ty(&*store).element().heap_type().clone();
+
+            let saved_size = u64::try_from(saved.len()).unwrap();
+            let size = table.size(&*store);
+            if saved_size > size {
+                table.grow(&mut *store, saved_size - size, Ref::null(&heap_type))?;
+            }
+
+            for (i, elem) in saved.iter().enumerate() {
+                let elem = restore_ref(store, instance, &heap_type, *elem, refs)?;
+                table.set(&mut *store, u64::try_from(i).unwrap(), elem)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn restore_globals<T>(
+        &self,
+        store: &mut StoreContextMut<'_, T>,
+        instance: &Instance,
+        refs: &RestoredRefs,
+    ) -> Result<()> {
+        let num_imported = instance.module(&*store).env_module().num_imported_globals;
+        let globals = instance
+            .all_globals(store.0)
+            .skip(num_imported)
+            .collect::<Vec<_>>();
+        for ((_index, global), saved) in globals.into_iter().zip(&self.data.globals) {
+            let Some(saved) = saved else {
+                continue;
+            };
+            let ty = global.ty(&*store);
+            let val = restore_val(store, instance, ty.content(), *saved, refs)?;
+            global.set(&mut *store, val)?;
+        }
+        Ok(())
+    }
+}
+
+/// Get the checksum that identifies `module` in snapshots.
+fn module_checksum(module: &Module) -> Result<&[u8; 32]> {
+    module.compiled_module().checksum().ok_or_else(|| {
+        anyhow!("module was compiled without the `snapshot` feature, so it cannot be snapshotted")
+    })
+}
+
+/// Turn a saved value back into a `Val` of type `ty` for the given instance.
+fn restore_val<T>(
+    store: &mut StoreContextMut<'_, T>,
+    instance: &Instance,
+    ty: &ValType,
+    saved: SavedVal,
+    refs: &RestoredRefs,
+) -> Result<Val> {
+    Ok(match saved {
+        SavedVal::I32(x) => Val::I32(x),
+        SavedVal::I64(x) => Val::I64(x),
+        SavedVal::F32(x) => Val::F32(x),
+        SavedVal::F64(x) => Val::F64(x),
+        SavedVal::V128(x) => Val::V128(V128::from(x)),
+        SavedVal::Ref(r) => {
+            let ty = ty
+                .as_ref()
+                .ok_or_else(|| anyhow!("snapshot has a reference where `{ty}` was expected"))?;
+            restore_ref(store, instance, ty.heap_type(), r, refs)?.into()
+        }
+    })
+}
+
+/// Turn a saved reference back into a `Ref` for the given instance.
+fn restore_ref<T>(
+    store: &mut StoreContextMut<'_, T>,
+    instance: &Instance,
+    heap_type: &HeapType,
+    saved: SavedRef,
+    refs: &RestoredRefs,
+) -> Result<Ref> {
+    match saved {
+        SavedRef::Null => Ok(Ref::null(heap_type)),
+        SavedRef::Func(index) => {
+            let index = FuncIndex::from_u32(index);
+            let id = instance.id(store.0);
+            let handle = store.0.instance_mut(id).instance_mut();
+            let escapes = handle
+                .env_module()
+                .functions
+                .get(index)
+                .is_some_and(|func| func.is_escaping());
+            let func_ref = escapes
+                .then(|| handle.get_func_ref(index))
+                .flatten()
+                .ok_or_else(|| anyhow!("snapshot references a function that doesn't escape"))?;
+            let func = unsafe { Func::from_vm_func_ref(store.0, func_ref) };
+            Ok(Ref::Func(Some(func)))
+        }
+        SavedRef::I31(_) | SavedRef::Object(_) | SavedRef::Extern(_) => {
+            restore_gc_ref(store, heap_type, saved, refs)
+        }
+    }
+}
+
+#[cfg(not(feature = "gc"))]
+impl<T> Saver<'_, T> {
+    fn save_anyref(
+        &mut self,
+        _store: &mut StoreContextMut<'_, T>,
+        anyref: Rooted<AnyRef>,
+    ) -> Result<SavedRef> {
+        match anyref.inner {}
+    }
+
+    fn save_externref(
+        &mut self,
+        _store: &mut StoreContextMut<'_, T>,
+        externref: Rooted<ExternRef>,
+    ) -> Result<SavedRef> {
+        match externref.inner {}
+    }
+
+    fn save_objects(&mut self, _store: &mut StoreContextMut<'_, T>) -> Result<()> {
+        debug_assert!(self.pending.is_empty());
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "gc"))]
+fn restore_gc_ref<T>(
+    _store: &mut StoreContextMut<'_, T>,
+    _heap_type: &HeapType,
+    _saved: SavedRef,
+    _refs: &RestoredRefs,
+) -> Result<Ref> {
+    bail!("cannot restore a GC reference without the `gc` feature enabled")
+}
+
+#[cfg(not(feature = "gc"))]
+fn restore_objects<T>(
+    _store: &mut StoreContextMut<'_, T>,
+    _instance: &Instance,
+    objects: &[SavedObject],
+    _refs: &mut RestoredRefs,
+) -> Result<()> {
+    ensure!(
+        objects.is_empty(),
+        "cannot restore GC objects without the `gc` feature enabled"
+    );
+    Ok(())
+}
+
+fn _assert_send_sync() {
+    fn _assert<T: Send + Sync>() {}
+    _assert::<InstanceSnapshot>();
+}
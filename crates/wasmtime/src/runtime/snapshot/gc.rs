@@ -0,0 +1,241 @@
+//! Saving and restoring the GC heap as part of an instance snapshot.
+
+use super::{restore_ref, restore_val, RestoredRefs, SavedObject, SavedRef, SavedVal, Saver};
+use crate::prelude::*;
+use crate::type_registry::RegisteredType;
+use crate::{
+    AnyRef, ArrayRef, ArrayRefPre, ArrayType, AsContextMut, ExternRef, HeapType, Instance, Ref,
+    Rooted, RootedGcRefImpl, StoreContextMut, StructRef, StructRefPre, StructType, Val, ValType,
+    I31,
+};
+use wasmtime_environ::ModuleInternedTypeIndex;
+
+impl<T> Saver<'_, T> {
+    /// Save an `anyref`, giving the object it refers to an index and queueing
+    /// it to have its fields saved by `save_objects` if it hasn't been seen
+    /// before.
+    pub(super) fn save_anyref(
+        &mut self,
+        store: &mut StoreContextMut<'_, T>,
+        anyref: Rooted<AnyRef>,
+    ) -> Result<SavedRef> {
+        if let Some(i31) = anyref.as_i31(&*store)? {
+            return Ok(SavedRef::I31(i31.get_u32()));
+        }
+
+        // Every object we've seen stays rooted until the snapshot is done, so
+        // raw GC references are stable and unique identifiers for them.
+        let raw = anyref.try_gc_ref(store.0)?.as_raw_u32();
+
+        if !anyref.is_struct(&*store)? && !anyref.is_array(&*store)? {
+            // This is an `externref` that was converted into an `anyref`.
+            return self.save_host_data(store, raw, anyref);
+        }
+
+        if let Some(index) = self.object_ids.get(&raw) {
+            return Ok(SavedRef::Object(*index));
+        }
+        let index = u32::try_from(self.object_ids.len())?;
+        self.object_ids.insert(raw, index);
+        self.pending.push_back(anyref);
+        Ok(SavedRef::Object(index))
+    }
+
+    /// Save an `externref`, which may be a converted `anyref`.
+    pub(super) fn save_externref(
+        &mut self,
+        store: &mut StoreContextMut<'_, T>,
+        externref: Rooted<ExternRef>,
+    ) -> Result<SavedRef> {
+        let anyref = AnyRef::convert_extern(&mut *store, externref)?;
+        self.save_anyref(store, anyref)
+    }
+
+    /// Save the host data of the `externref` with the given raw GC reference
+    /// with the embedder's function, if it hasn't been saved already.
+    fn save_host_data(
+        &mut self,
+        store: &mut StoreContextMut<'_, T>,
+        raw: u32,
+        anyref: Rooted<AnyRef>,
+    ) -> Result<SavedRef> {
+        if let Some(index) = self.externref_ids.get(&raw) {
+            return Ok(SavedRef::Extern(*index));
+        }
+        let Some(save_host_data) = self.save_host_data.as_mut() else {
+            bail!(
+                "cannot snapshot an `externref` without a function to save its host data; \
+                 see `Instance::snapshot_with_externrefs`"
+            );
+        };
+        let externref = ExternRef::convert_any(&mut *store, anyref)?;
+        let data = save_host_data(store.as_context_mut(), externref)
+            .context("failed to save an `externref`'s host data")?;
+
+        let index = u32::try_from(self.externrefs.len())?;
+        self.externref_ids.insert(raw, index);
+        self.externrefs.push(data);
+        Ok(SavedRef::Extern(index))
+    }
+
+    /// Save the fields of every object queued by `save_anyref`, along with the
+    /// objects that those fields refer to in turn.
+    pub(super) fn save_objects(&mut self, store: &mut StoreContextMut<'_, T>) -> Result<()> {
+        while let Some(anyref) = self.pending.pop_front() {
+            let (ty, vals) = if let Some(structref) = anyref._as_struct(store.0)? {
+                let ty = structref.type_index(store.0)?;
+                let fields = structref._fields(store.0)?.collect::<Vec<_>>();
+                (ty, fields)
+            } else {
+                let arrayref = anyref
+                    ._as_array(store.0)?
+                    .expect("only structs and arrays are queued");
+                let ty = arrayref.type_index(store.0)?;
+                let elems = arrayref._elems(store.0)?.collect::<Vec<_>>();
+                (ty, elems)
+            };
+
+            let ty = *self.types.get(&ty).ok_or_else(|| {
+                anyhow!(
+                    "cannot snapshot a GC object whose type is not defined in the \
+                     snapshotted module"
+                )
+            })?;
+            let fields = vals
+                .into_iter()
+                .map(|val| self.save_val(store, val))
+                .collect::<Result<Vec<_>>>()?;
+
+            // Objects are queued in the order they are given indices, so this
+            // object's index is its position in `objects`.
+            self.objects.push(SavedObject {
+                ty: ty.as_u32(),
+                fields,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Allocate each of the saved GC objects, in order, into `refs.objects`.
+///
+/// Objects may refer to each other in cycles, so this happens in two passes.
+/// The first allocates every object, leaving the fields that refer to other
+/// saved objects null, even if their types are not nullable. The second fills
+/// those fields in. Nothing can observe the objects in between.
+pub(super) fn restore_objects<T>(
+    store: &mut StoreContextMut<'_, T>,
+    instance: &Instance,
+    objects: &[SavedObject],
+    refs: &mut RestoredRefs,
+) -> Result<()> {
+    let module = instance.module(&*store).clone();
+
+    // The fields to fill in during the second pass, as (object index, field
+    // index, field's heap type, referenced object).
+    let mut deferred = vec![];
+
+    for (index, object) in objects.iter().enumerate() {
+        let ty = module
+            .signatures()
+            .shared_type(ModuleInternedTypeIndex::from_u32(object.ty))
+            .and_then(|ty| RegisteredType::root(store.engine(), ty))
+            .ok_or_else(|| anyhow!("snapshot has a GC object of an unknown type"))?;
+
+        let mut restore_field = |store: &mut StoreContextMut<'_, T>,
+                                 field: usize,
+                                 ty: &ValType,
+                                 saved: &SavedVal|
+         -> Result<Option<Val>> {
+            match (saved, ty) {
+                (SavedVal::Ref(r @ SavedRef::Object(_)), ValType::Ref(ty)) => {
+                    deferred.push((index, field, ty.heap_type().clone(), *r));
+                    Ok(None)
+                }
+                _ => restore_val(store, instance, ty, *saved, refs).map(Some),
+            }
+        };
+
+        let anyref = if ty.is_struct() {
+            let ty = StructType::from_registered_type(ty);
+            ensure!(
+                ty.fields().len() == object.fields.len(),
+                "snapshot has a struct with the wrong number of fields"
+            );
+            let fields = ty
+                .fields()
+                .zip(&object.fields)
+                .enumerate()
+                .map(|(i, (field_ty, saved))| {
+                    restore_field(store, i, field_ty.element_type().unpack(), saved)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let allocator = StructRefPre::_new(store.0, ty);
+            StructRef::_new_for_restore(store.0, &allocator, &fields)?.to_anyref()
+        } else if ty.is_array() {
+            let ty = ArrayType::from_registered_type(ty);
+            let elem_ty = ty.element_type().unpack().clone();
+            let elems = object
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(i, saved)| restore_field(store, i, &elem_ty, saved))
+                .collect::<Result<Vec<_>>>()?;
+            let allocator = ArrayRefPre::_new(store.0, ty);
+            ArrayRef::_new_for_restore(store.0, &allocator, &elems)?.to_anyref()
+        } else {
+            bail!("snapshot has a GC object of a function type");
+        };
+        refs.objects.push(anyref);
+    }
+
+    for (index, field, heap_type, saved) in deferred {
+        let val = Val::from(restore_ref(store, instance, &heap_type, saved, refs)?);
+        let anyref = refs.objects[index];
+        if let Some(structref) = anyref._as_struct(store.0)? {
+            structref._restore_field(store.0, field, val)?;
+        } else {
+            let arrayref = anyref._as_array(store.0)?.unwrap();
+            arrayref._restore_elem(store.0, u32::try_from(field)?, val)?;
+        }
+    }
+    Ok(())
+}
+
+/// Turn a saved GC reference back into a `Ref` of the given heap type.
+pub(super) fn restore_gc_ref<T>(
+    store: &mut StoreContextMut<'_, T>,
+    heap_type: &HeapType,
+    saved: SavedRef,
+    refs: &RestoredRefs,
+) -> Result<Ref> {
+    let anyref = match saved {
+        SavedRef::I31(value) => {
+            let i31 = I31::new_u32(value).ok_or_else(|| anyhow!("invalid saved `i31ref`"))?;
+            AnyRef::from_i31(&mut *store, i31)
+        }
+        SavedRef::Object(index) => *refs
+            .objects
+            .get(usize::try_from(index)?)
+            .ok_or_else(|| anyhow!("snapshot references a GC object that doesn't exist"))?,
+        SavedRef::Extern(index) => {
+            let externref = *refs
+                .externrefs
+                .get(usize::try_from(index)?)
+                .ok_or_else(|| anyhow!("snapshot references an `externref` that doesn't exist"))?;
+            if matches!(heap_type.top(), HeapType::Extern) {
+                return Ok(Ref::Extern(Some(externref)));
+            }
+            AnyRef::convert_extern(&mut *store, externref)?
+        }
+        SavedRef::Null | SavedRef::Func(_) => unreachable!(),
+    };
+    if matches!(heap_type.top(), HeapType::Extern) {
+        Ok(Ref::Extern(Some(ExternRef::convert_any(
+            &mut *store,
+            anyref,
+        )?)))
+    } else {
+        Ok(Ref::Any(Some(anyref)))
+    }
+}
@@ -76,6 +76,8 @@
 //! contents of `StoreOpaque`. This is an invariant that we, as the authors of
 //! `wasmtime`, must uphold for the public interface to be safe.
 
+use crate::code::CodeObject;
+use crate::guest_debug::DebugState;
 use crate::hash_set::HashSet;
 use crate::instance::InstanceData;
 use crate::linker::Definition;
@@ -92,6 +94,7 @@ use crate::trampoline::VMHostGlobalContext;
 use crate::type_registry::RegisteredType;
 use crate::RootSet;
 use crate::{module::ModuleRegistry, Engine, Module, Trap, Val, ValRaw};
+use crate::{DebugFrame, DebugInterruptHandle, DebugStep, MemoryAccess};
 use crate::{Global, Instance, Memory, RootScope, Table, Uninhabited};
 use alloc::sync::Arc;
 use core::cell::UnsafeCell;
@@ -112,6 +115,14 @@ mod data;
 pub use self::data::*;
 mod func_refs;
 use func_refs::FuncRefs;
+#[cfg(feature = "async")]
+mod resumable;
+#[cfg(feature = "async")]
+pub use self::resumable::{ResumableCall, Suspended};
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+pub use self::stats::StoreStats;
 
 /// A [`Store`] is a collection of WebAssembly instances and host-defined state.
 ///
@@ -224,6 +235,11 @@ pub struct StoreInner<T> {
     call_hook: Option<CallHookInner<T>>,
     epoch_deadline_behavior:
         Option<Box<dyn FnMut(StoreContextMut<T>) -> Result<UpdateDeadline> + Send + Sync>>,
+    debug_handler: Option<
+        Box<dyn FnMut(StoreContextMut<'_, T>, &DebugFrame) -> Result<DebugStep> + Send + Sync>,
+    >,
+    memory_access_hook:
+        Option<Box<dyn FnMut(StoreContextMut<'_, T>, &MemoryAccess) -> Result<()> + Send + Sync>>,
     // for comments about `ManuallyDrop`, see `Store::into_data`
     data: ManuallyDrop<T>,
 }
@@ -262,7 +278,9 @@ pub enum UpdateDeadline {
     Continue(u64),
     /// Extend the deadline by the specified number of ticks after yielding to
     /// the async executor loop. This can only be used with an async [`Store`]
-    /// configured via [`Config::async_support`](crate::Config::async_support).
+    /// configured via [`Config::async_support`](crate::Config::async_support),
+    /// or within a call made with [`Store::call_resumable`] in which case the
+    /// call is suspended instead.
     #[cfg(feature = "async")]
     Yield(u64),
 }
@@ -315,7 +333,7 @@ pub struct StoreOpaque {
     runtime_limits: VMRuntimeLimits,
     instances: Vec<StoreInstance>,
     #[cfg(feature = "component-model")]
-    num_component_instances: usize,
+    component_instances: Vec<crate::component::Instance>,
     signal_handler: Option<SignalHandler>,
     modules: ModuleRegistry,
     func_refs: FuncRefs,
@@ -344,6 +362,11 @@ pub struct StoreOpaque {
     // until the reserve is empty.
     fuel_reserve: u64,
     fuel_yield_interval: Option<NonZeroU64>,
+    /// Breakpoints and stepping state used when guest debugging.
+    debug: DebugState,
+    /// Time accounting enabled with `Store::enable_stats`.
+    #[cfg(feature = "std")]
+    stats: Option<stats::StatsTracker>,
     /// Indexed data within this `Store`, used to store information about
     /// globals, functions, memories, etc.
     ///
@@ -390,6 +413,14 @@ pub struct StoreOpaque {
     #[cfg(feature = "component-model")]
     host_resource_data: crate::component::HostResourceData,
 
+    /// Lifted component functions currently being called from the host, in
+    /// caller to callee order. Only maintained when coredumps are enabled.
+    #[cfg(all(feature = "component-model", feature = "coredump"))]
+    component_call_stack: Vec<(
+        crate::component::Instance,
+        wasmtime_environ::component::ExportIndex,
+    )>,
+
     /// State related to the Pulley interpreter if that's enabled and configured
     /// for this store's `Engine`. This is `None` if pulley was disabled at
     /// compile time or if it's not being used by the `Engine`.
@@ -547,7 +578,7 @@ impl<T> Store<T> {
                 runtime_limits: Default::default(),
                 instances: Vec::new(),
                 #[cfg(feature = "component-model")]
-                num_component_instances: 0,
+                component_instances: Vec::new(),
                 signal_handler: None,
                 gc_store: None,
                 gc_roots: RootSet::default(),
@@ -570,6 +601,9 @@ impl<T> Store<T> {
                 },
                 fuel_reserve: 0,
                 fuel_yield_interval: None,
+                debug: DebugState::default(),
+                #[cfg(feature = "std")]
+                stats: None,
                 store_data: ManuallyDrop::new(StoreData::new()),
                 default_caller: InstanceHandle::null(),
                 hostcall_val_storage: Vec::new(),
@@ -582,6 +616,8 @@ impl<T> Store<T> {
                 component_calls: Default::default(),
                 #[cfg(feature = "component-model")]
                 host_resource_data: Default::default(),
+                #[cfg(all(feature = "component-model", feature = "coredump"))]
+                component_call_stack: Vec::new(),
                 interpreter: if cfg!(feature = "pulley") && engine.target().is_pulley() {
                     Some(Interpreter::new())
                 } else {
@@ -591,9 +627,14 @@ impl<T> Store<T> {
             limiter: None,
             call_hook: None,
             epoch_deadline_behavior: None,
+            debug_handler: None,
+            memory_access_hook: None,
             data: ManuallyDrop::new(data),
         });
 
+        // Instrumented code reads the debug flags through the runtime limits.
+        *inner.runtime_limits.debug_flags.get_mut() = inner.debug.flags_ptr();
+
         // Wasmtime uses the callee argument to host functions to learn about
         // the original pointer to the `Store` itself, allowing it to
         // reconstruct a `StoreContextMut<T>`. When we initially call a `Func`,
@@ -618,6 +659,7 @@ impl<T> Store<T> {
                         wmemcheck: engine.config().wmemcheck,
                         pkey: None,
                         tunables: engine.tunables(),
+                        restore: None,
                     })
                     .expect("failed to allocate default callee")
             };
@@ -943,6 +985,38 @@ impl<T> Store<T> {
         self.inner.fuel_async_yield_interval(interval)
     }
 
+    /// Configures whether this [`Store`] keeps track of how much time it
+    /// spends executing WebAssembly and host functions.
+    ///
+    /// When enabled, the store measures the time in between each of the
+    /// transitions reported to [`Store::call_hook`], along with counting calls
+    /// to host functions and traps. The totals can be retrieved at any time
+    /// with [`Store::stats`].
+    ///
+    /// Enabling statistics adds the cost of reading the clocks to every call
+    /// between WebAssembly and the host, so it's disabled by default.
+    /// Disabling them discards the totals gathered so far, and re-enabling
+    /// them starts again from zero.
+    ///
+    /// Note that statistics should only be enabled or disabled while no
+    /// WebAssembly is executing in this store, otherwise the time of the calls
+    /// in progress will be misattributed.
+    #[cfg(feature = "std")]
+    pub fn enable_stats(&mut self, enable: bool) {
+        self.inner.enable_stats(enable)
+    }
+
+    /// Returns the time this [`Store`] has spent executing WebAssembly and host
+    /// functions, along with other statistics, since they were enabled with
+    /// [`Store::enable_stats`].
+    ///
+    /// If WebAssembly is executing in this store then the time spent so far
+    /// in the current call is included.
+    #[cfg(feature = "std")]
+    pub fn stats(&self) -> StoreStats {
+        self.inner.stats()
+    }
+
     /// Sets the epoch deadline to a certain number of ticks in the future.
     ///
     /// When the Wasm guest code is compiled with epoch-interruption
@@ -1057,6 +1131,11 @@ impl<T> Store<T> {
     /// signal/interrupt) should call
     /// [`Engine::increment_epoch()`](crate::Engine::increment_epoch).
     ///
+    /// Synchronous stores may also use this with calls made via
+    /// [`Store::call_resumable`], which are suspended when the deadline is
+    /// reached instead of yielding to an executor. Other calls in synchronous
+    /// stores fail with an error when they reach the deadline.
+    ///
     /// See documentation on
     /// [`Config::epoch_interruption()`](crate::Config::epoch_interruption)
     /// for an introduction to epoch-based interruption.
@@ -1064,6 +1143,78 @@ impl<T> Store<T> {
     pub fn epoch_deadline_async_yield_and_update(&mut self, delta: u64) {
         self.inner.epoch_deadline_async_yield_and_update(delta);
     }
+
+    /// Configures the function which is invoked whenever execution stops at
+    /// a WebAssembly instruction while guest debugging.
+    ///
+    /// Execution stops before an instruction at a breakpoint added with
+    /// [`Store::add_breakpoint`], after an interruption requested with
+    /// [`Store::debug_interrupt_handle`], or while stepping. The handler is
+    /// given a [`DebugFrame`] describing the instruction, and returns a
+    /// [`DebugStep`] indicating how execution should proceed. If the handler
+    /// returns an error then execution is terminated with that error.
+    ///
+    /// Execution only ever stops in code compiled with
+    /// [`Config::guest_debug`](crate::Config::guest_debug) enabled, and
+    /// never stops if no handler has been configured.
+    pub fn debug_handler(
+        &mut self,
+        handler: impl FnMut(StoreContextMut<'_, T>, &DebugFrame) -> Result<DebugStep>
+            + Send
+            + Sync
+            + 'static,
+    ) {
+        self.inner.debug_handler = Some(Box::new(handler));
+        self.inner.debug.set_has_handler(true);
+    }
+
+    /// Configures the function which is invoked before each access that
+    /// WebAssembly makes to linear memory.
+    ///
+    /// The hook is given a [`MemoryAccess`] describing the instance, memory,
+    /// address, size and kind of the access, along with the offset of the
+    /// instruction making it. This can be used to implement watchpoints, or to
+    /// record a trace of accesses for analyses such as data race detection
+    /// with shared memories. If the hook returns an error then execution is
+    /// terminated with that error before the access is performed.
+    ///
+    /// Accesses are only reported by code compiled with
+    /// [`Config::memory_access_tracing`](crate::Config::memory_access_tracing)
+    /// enabled, or, for accesses to shared memories, with
+    /// [`Config::race_detection`](crate::Config::race_detection) enabled. The
+    /// hook runs on the thread making the access, so each store sharing a
+    /// memory reports only the accesses of its own instances.
+    pub fn memory_access_hook(
+        &mut self,
+        hook: impl FnMut(StoreContextMut<'_, T>, &MemoryAccess) -> Result<()> + Send + Sync + 'static,
+    ) {
+        self.inner.memory_access_hook = Some(Box::new(hook));
+    }
+
+    /// Adds a breakpoint before the instruction at `offset` within `module`.
+    ///
+    /// The `offset` is relative to the start of the original wasm module, as
+    /// with [`FrameInfo::module_offset`](crate::FrameInfo::module_offset).
+    /// Breakpoints at offsets which aren't the start of an instruction are
+    /// never hit.
+    ///
+    /// See [`Store::debug_handler`] for more information.
+    pub fn add_breakpoint(&mut self, module: &Module, offset: usize) {
+        self.inner.debug.add_breakpoint(module, offset);
+    }
+
+    /// Removes a breakpoint previously added with [`Store::add_breakpoint`].
+    pub fn remove_breakpoint(&mut self, module: &Module, offset: usize) {
+        self.inner.debug.remove_breakpoint(module, offset);
+    }
+
+    /// Returns a handle which can be used to stop execution in this store at
+    /// the next instruction, for example from another thread.
+    ///
+    /// See [`Store::debug_handler`] for more information.
+    pub fn debug_interrupt_handle(&self) -> DebugInterruptHandle {
+        self.inner.debug.interrupt_handle()
+    }
 }
 
 impl<'a, T> StoreContext<'a, T> {
@@ -1155,6 +1306,14 @@ impl<'a, T> StoreContextMut<'a, T> {
         self.0.fuel_async_yield_interval(interval)
     }
 
+    /// Returns the statistics gathered by this `Store`.
+    ///
+    /// For more information see [`Store::stats`].
+    #[cfg(feature = "std")]
+    pub fn stats(&self) -> StoreStats {
+        self.0.stats()
+    }
+
     /// Sets the epoch deadline to a certain number of ticks in the future.
     ///
     /// For more information see [`Store::set_epoch_deadline`].
@@ -1178,6 +1337,20 @@ impl<'a, T> StoreContextMut<'a, T> {
     pub fn epoch_deadline_async_yield_and_update(&mut self, delta: u64) {
         self.0.epoch_deadline_async_yield_and_update(delta);
     }
+
+    /// Adds a breakpoint before the instruction at `offset` within `module`.
+    ///
+    /// For more information see [`Store::add_breakpoint`].
+    pub fn add_breakpoint(&mut self, module: &Module, offset: usize) {
+        self.0.debug.add_breakpoint(module, offset);
+    }
+
+    /// Removes a breakpoint previously added with [`Store::add_breakpoint`].
+    ///
+    /// For more information see [`Store::remove_breakpoint`].
+    pub fn remove_breakpoint(&mut self, module: &Module, offset: usize) {
+        self.0.debug.remove_breakpoint(module, offset);
+    }
 }
 
 impl<T> StoreInner<T> {
@@ -1193,7 +1366,7 @@ impl<T> StoreInner<T> {
 
     #[inline]
     pub fn call_hook(&mut self, s: CallHook) -> Result<()> {
-        if self.inner.pkey.is_none() && self.call_hook.is_none() {
+        if self.inner.pkey.is_none() && self.call_hook.is_none() && !self.inner.stats_enabled() {
             Ok(())
         } else {
             self.call_hook_slow_path(s)
@@ -1201,6 +1374,11 @@ impl<T> StoreInner<T> {
     }
 
     fn call_hook_slow_path(&mut self, s: CallHook) -> Result<()> {
+        #[cfg(feature = "std")]
+        if let Some(stats) = &mut self.inner.stats {
+            stats.transition(s);
+        }
+
         if let Some(pkey) = &self.inner.pkey {
             let allocator = self.engine().allocator();
             match s {
@@ -1435,6 +1613,19 @@ impl StoreOpaque {
             .map(|i| Instance::from_wasmtime(i, self))
     }
 
+    /// Returns the memory defined within this store whose definition is at
+    /// `definition`, if any.
+    #[cfg(feature = "component-model")]
+    pub(crate) fn defined_memory(
+        &mut self,
+        definition: *mut crate::runtime::vm::VMMemoryDefinition,
+    ) -> Option<crate::runtime::vm::ExportMemory> {
+        self.instances
+            .iter_mut()
+            .flat_map(|instance| instance.handle.defined_memories())
+            .find(|memory| memory.definition == definition)
+    }
+
     /// Get all memories (host- or Wasm-defined) within this store.
     pub fn all_memories<'a>(&'a mut self) -> impl Iterator<Item = Memory> + 'a {
         // NB: Host-created memories have dummy instances. Therefore, we can get
@@ -1548,24 +1739,25 @@ impl StoreOpaque {
     #[inline(never)]
     pub(crate) fn allocate_gc_heap(&mut self) -> Result<()> {
         assert!(self.gc_store.is_none());
-        let gc_store = allocate_gc_store(self.engine())?;
+        let gc_store = allocate_gc_store(self.engine(), self.pkey)?;
         self.gc_store = Some(gc_store);
         return Ok(());
 
         #[cfg(feature = "gc")]
-        fn allocate_gc_store(engine: &Engine) -> Result<GcStore> {
+        fn allocate_gc_store(engine: &Engine, pkey: Option<ProtectionKey>) -> Result<GcStore> {
             ensure!(
                 engine.features().gc_types(),
                 "cannot allocate a GC store when GC is disabled at configuration time"
             );
-            let (index, heap) = engine
-                .allocator()
-                .allocate_gc_heap(&**engine.gc_runtime()?)?;
+            let (index, heap) =
+                engine
+                    .allocator()
+                    .allocate_gc_heap(engine, &**engine.gc_runtime()?, pkey)?;
             Ok(GcStore::new(index, heap))
         }
 
         #[cfg(not(feature = "gc"))]
-        fn allocate_gc_store(_engine: &Engine) -> Result<GcStore> {
+        fn allocate_gc_store(_engine: &Engine, _pkey: Option<ProtectionKey>) -> Result<GcStore> {
             bail!("cannot allocate a GC store: the `gc` feature was disabled at compile time")
         }
     }
@@ -1598,6 +1790,65 @@ impl StoreOpaque {
         }
     }
 
+    /// Report the compiled code of a module or component that is about to be
+    /// instantiated to the store's resource limiter, failing if it rejects it.
+    pub(crate) fn code_memory_growing(&mut self, code: &CodeObject) -> Result<()> {
+        let (current, desired) = self.modules().code_growth(code);
+        if desired == current {
+            return Ok(());
+        }
+        // SAFETY: the resource limiter only has access to the store's data.
+        let allowed = unsafe { (*self.traitobj()).code_memory_growing(current, desired, None)? };
+        if !allowed {
+            bail!(
+                "compiled code of {} bytes rejected by the store's resource limiter",
+                desired - current
+            );
+        }
+        Ok(())
+    }
+
+    /// Grow the GC heap to make room for an allocation of `bytes_needed`
+    /// bytes, if the store's resource limiter allows it.
+    ///
+    /// Returns whether the heap grew.
+    #[cfg(feature = "gc")]
+    pub(crate) fn grow_gc_heap(&mut self, bytes_needed: usize) -> Result<bool> {
+        let Some((current, desired, maximum)) = self.unwrap_gc_store().heap_growth(bytes_needed)
+        else {
+            return Ok(false);
+        };
+        // SAFETY: the resource limiter only has access to the store's data,
+        // not to the GC heap which is about to grow.
+        let allowed =
+            unsafe { (*self.traitobj()).gc_heap_growing(current, desired, Some(maximum))? };
+        if allowed {
+            log::trace!("growing GC heap from {current:#x} to {desired:#x} bytes");
+            self.unwrap_gc_store_mut().gc_heap.grow(desired);
+        }
+        Ok(allowed)
+    }
+
+    /// Perform a GC heap allocation with `alloc`, growing the heap and trying
+    /// again if there isn't room for it.
+    ///
+    /// This doesn't collect garbage first, which is left to the embedder for
+    /// allocations made by the host.
+    #[cfg(feature = "gc")]
+    pub(crate) fn retry_after_gc_heap_growth<T>(
+        &mut self,
+        bytes_needed: usize,
+        mut alloc: impl FnMut(&mut GcStore) -> Result<Option<T>>,
+    ) -> Result<Option<T>> {
+        if let Some(x) = alloc(self.gc_store_mut()?)? {
+            return Ok(Some(x));
+        }
+        if !self.grow_gc_heap(bytes_needed)? {
+            return Ok(None);
+        }
+        alloc(self.unwrap_gc_store_mut())
+    }
+
     #[inline]
     #[cfg(feature = "gc")]
     pub(crate) fn unwrap_gc_store(&self) -> &GcStore {
@@ -1872,6 +2123,43 @@ impl StoreOpaque {
         Ok(())
     }
 
+    #[cfg(feature = "std")]
+    pub fn enable_stats(&mut self, enable: bool) {
+        self.stats = if enable {
+            Some(stats::StatsTracker::new())
+        } else {
+            None
+        };
+    }
+
+    #[cfg(feature = "std")]
+    pub fn stats(&self) -> StoreStats {
+        self.stats
+            .as_ref()
+            .map(|s| s.snapshot())
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    fn stats_enabled(&self) -> bool {
+        #[cfg(feature = "std")]
+        return self.stats.is_some();
+        #[cfg(not(feature = "std"))]
+        return false;
+    }
+
+    /// Records the outcome of a call from the host into WebAssembly for
+    /// [`Store::stats`].
+    #[inline]
+    pub(crate) fn returned_from_wasm(&mut self, trapped: bool) {
+        #[cfg(feature = "std")]
+        if let Some(stats) = &mut self.stats {
+            stats.returned_from_wasm(trapped);
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = trapped;
+    }
+
     pub fn fuel_async_yield_interval(&mut self, interval: Option<u64>) -> Result<()> {
         anyhow::ensure!(
             self.engine().tunables().consume_fuel,
@@ -1920,6 +2208,16 @@ impl StoreOpaque {
         Some(handler)
     }
 
+    #[inline]
+    pub(crate) fn debug_state(&self) -> &DebugState {
+        &self.debug
+    }
+
+    #[inline]
+    pub(crate) fn debug_state_mut(&mut self) -> &mut DebugState {
+        &mut self.debug
+    }
+
     #[inline]
     pub fn vmruntime_limits(&self) -> *mut VMRuntimeLimits {
         &self.runtime_limits as *const VMRuntimeLimits as *mut VMRuntimeLimits
@@ -2100,12 +2398,40 @@ at https://bytecodealliance.org/security.
 
     #[cfg(feature = "component-model")]
     pub(crate) fn push_component_instance(&mut self, instance: crate::component::Instance) {
-        // We don't actually need the instance itself right now, but it seems
-        // like something we will almost certainly eventually want to keep
-        // around, so force callers to provide it.
-        let _ = instance;
+        self.component_instances.push(instance);
+    }
+
+    /// Returns all component instances created within this store, in the
+    /// order they were instantiated.
+    #[cfg(feature = "component-model")]
+    pub(crate) fn component_instances(&self) -> &[crate::component::Instance] {
+        &self.component_instances
+    }
+
+    #[cfg(all(feature = "component-model", feature = "coredump"))]
+    pub(crate) fn component_call_stack(
+        &self,
+    ) -> &[(
+        crate::component::Instance,
+        wasmtime_environ::component::ExportIndex,
+    )] {
+        &self.component_call_stack
+    }
+
+    #[cfg(all(feature = "component-model", feature = "coredump"))]
+    pub(crate) fn component_call_stack_mut(
+        &mut self,
+    ) -> &mut Vec<(
+        crate::component::Instance,
+        wasmtime_environ::component::ExportIndex,
+    )> {
+        &mut self.component_call_stack
+    }
 
-        self.num_component_instances += 1;
+    /// Returns the host's table of resource handles, as used by `ResourceAny`.
+    #[cfg(all(feature = "component-model", feature = "coredump"))]
+    pub(crate) fn component_host_table(&self) -> &crate::runtime::vm::component::ResourceTable {
+        &self.component_host_table
     }
 
     pub(crate) fn async_guard_range(&self) -> Range<*mut u8> {
@@ -2648,6 +2974,42 @@ unsafe impl<T> crate::runtime::vm::VMStore for StoreInner<T> {
         }
     }
 
+    fn gc_heap_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool, anyhow::Error> {
+        match self.limiter {
+            Some(ResourceLimiterInner::Sync(ref mut limiter)) => {
+                limiter(&mut self.data).gc_heap_growing(current, desired, maximum)
+            }
+            #[cfg(feature = "async")]
+            Some(ResourceLimiterInner::Async(ref mut limiter)) => {
+                limiter(&mut self.data).gc_heap_growing(current, desired, maximum)
+            }
+            None => Ok(true),
+        }
+    }
+
+    fn code_memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool, anyhow::Error> {
+        match self.limiter {
+            Some(ResourceLimiterInner::Sync(ref mut limiter)) => {
+                limiter(&mut self.data).code_memory_growing(current, desired, maximum)
+            }
+            #[cfg(feature = "async")]
+            Some(ResourceLimiterInner::Async(ref mut limiter)) => {
+                limiter(&mut self.data).code_memory_growing(current, desired, maximum)
+            }
+            None => Ok(true),
+        }
+    }
+
     fn out_of_gas(&mut self) -> Result<()> {
         if !self.refuel() {
             return Err(Trap::OutOfFuel.into());
@@ -2671,13 +3033,27 @@ unsafe impl<T> crate::runtime::vm::VMStore for StoreInner<T> {
 
                     #[cfg(feature = "async")]
                     UpdateDeadline::Yield(delta) => {
-                        assert!(
-                            self.async_support(),
-                            "cannot use `UpdateDeadline::Yield` without enabling async support in the config"
-                        );
-                        // Do the async yield. May return a trap if future was
-                        // canceled while we're yielded.
-                        self.async_yield_impl()?;
+                        if self.async_support() {
+                            // Do the async yield. May return a trap if future
+                            // was canceled while we're yielded.
+                            self.async_yield_impl()?;
+                        } else {
+                            // Otherwise this must be a call made with
+                            // `Store::call_resumable`, so suspend it. May
+                            // return a trap if the call was cancelled while
+                            // suspended. Any other call has nowhere to
+                            // suspend to, so it fails instead.
+                            let suspend = unsafe { *self.async_state.current_suspend.get() };
+                            if suspend.is_null() {
+                                bail!(
+                                    "cannot use `UpdateDeadline::Yield` outside of \
+                                     `Store::call_resumable` without enabling async \
+                                     support in the config"
+                                );
+                            }
+                            let track_pkey_context_switch = self.pkey.is_some();
+                            unsafe { resumable::suspend(suspend, track_pkey_context_switch)? };
+                        }
                         delta
                     }
                 };
@@ -2686,7 +3062,7 @@ unsafe impl<T> crate::runtime::vm::VMStore for StoreInner<T> {
                 // the Wasm code doesn't have to reload it.
                 self.set_epoch_deadline(delta);
                 Ok(self.get_epoch_deadline())
-            })
+            }),
         };
 
         // Put back the original behavior which was replaced by `take`.
@@ -2694,6 +3070,29 @@ unsafe impl<T> crate::runtime::vm::VMStore for StoreInner<T> {
         delta_result
     }
 
+    fn memory_access(&mut self, access: &MemoryAccess) -> Result<()> {
+        // Temporarily take the hook to avoid mutably borrowing multiple times.
+        let mut hook = self.memory_access_hook.take();
+        let result = match &mut hook {
+            Some(hook) => hook((&mut *self).as_context_mut(), access),
+            None => Ok(()),
+        };
+        self.memory_access_hook = hook;
+        result
+    }
+
+    fn debug_break(&mut self, frame: &DebugFrame) -> Result<DebugStep> {
+        // Temporarily take the handler to avoid mutably borrowing multiple
+        // times.
+        let mut handler = self.debug_handler.take();
+        let result = match &mut handler {
+            Some(handler) => handler((&mut *self).as_context_mut(), frame),
+            None => Ok(DebugStep::Continue),
+        };
+        self.debug_handler = handler;
+        result
+    }
+
     #[cfg(feature = "gc")]
     fn maybe_async_gc(&mut self, root: Option<VMGcRef>) -> Result<Option<VMGcRef>> {
         let mut scope = RootScope::new(self);
@@ -2766,10 +3165,6 @@ impl<T> StoreInner<T> {
     }
 
     fn epoch_deadline_async_yield_and_update(&mut self, delta: u64) {
-        assert!(
-            self.async_support(),
-            "cannot use `epoch_deadline_async_yield_and_update` without enabling async support in the config"
-        );
         #[cfg(feature = "async")]
         {
             self.epoch_deadline_behavior =
@@ -2840,7 +3235,7 @@ impl Drop for StoreOpaque {
 
             #[cfg(feature = "component-model")]
             {
-                for _ in 0..self.num_component_instances {
+                for _ in 0..self.component_instances.len() {
                     allocator.decrement_component_instance_count();
                 }
             }
@@ -0,0 +1,234 @@
+//! Support for pausing and resuming synchronous calls into WebAssembly.
+//!
+//! This module implements [`Store::call_resumable`] which runs a call into
+//! WebAssembly on a separate native stack, the same way that async stores do,
+//! but without requiring an async executor. When the epoch deadline of the
+//! store is reached and the deadline callback returns
+//! [`UpdateDeadline::Yield`](crate::UpdateDeadline::Yield) the fiber is
+//! suspended and control is handed back to the caller of
+//! [`Store::call_resumable`] in the form of a [`Suspended`] handle.
+
+use super::Reset;
+use crate::prelude::*;
+use crate::runtime::vm::{AsyncWasmCallState, PreviousAsyncWasmCallState};
+use crate::{Engine, Func, Store, Val};
+use core::marker;
+
+type ResumableFiber<'a> = wasmtime_fiber::Fiber<'a, Result<()>, (), Result<()>>;
+
+/// The result of starting or resuming a call with [`Store::call_resumable`].
+pub enum ResumableCall<'a, T> {
+    /// The call ran to completion and its results have been written to the
+    /// `results` slice originally passed to [`Store::call_resumable`].
+    Finished,
+    /// The call was suspended when its epoch deadline was reached and can be
+    /// continued with [`Suspended::resume`].
+    Suspended(Suspended<'a, T>),
+}
+
+/// A call into WebAssembly, started with [`Store::call_resumable`], which is
+/// currently paused.
+///
+/// The call is continued with [`Suspended::resume`]. Dropping this handle
+/// without resuming it cancels the call: the suspended WebAssembly is unwound
+/// with a trap and its native stack is released.
+///
+/// The [`Store`] that the call was made on stays mutably borrowed while the
+/// call is suspended, and the call can only be resumed on the thread that
+/// started it.
+pub struct Suspended<'a, T> {
+    fiber: Option<ResumableFiber<'a>>,
+    state: Option<AsyncWasmCallState>,
+    engine: Engine,
+    _store: marker::PhantomData<&'a mut Store<T>>,
+}
+
+impl<T> Store<T> {
+    /// Invokes `func` with `params`, allowing the call to be paused when the
+    /// store's epoch deadline is reached and resumed later.
+    ///
+    /// This behaves like [`Func::call`] except that the call runs on a
+    /// separate native stack, sized by
+    /// [`Config::async_stack_size`](crate::Config::async_stack_size). When the
+    /// epoch deadline is reached and the store is configured with
+    /// [`Store::epoch_deadline_async_yield_and_update`], or the
+    /// [`Store::epoch_deadline_callback`] returns
+    /// [`UpdateDeadline::Yield`](crate::UpdateDeadline::Yield), the call is
+    /// suspended and [`ResumableCall::Suspended`] is returned. The deadline is
+    /// extended by the requested number of ticks once the call is resumed.
+    ///
+    /// When the call finishes its results are written to `results` and
+    /// [`ResumableCall::Finished`] is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Func::call`], or an error if a fiber stack
+    /// could not be allocated for the call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the store was configured with
+    /// [`Config::async_support`](crate::Config::async_support); async stores
+    /// should use [`Func::call_async`] instead.
+    pub fn call_resumable<'a>(
+        &'a mut self,
+        func: &Func,
+        params: &[Val],
+        results: &'a mut [Val],
+    ) -> Result<ResumableCall<'a, T>> {
+        assert!(
+            !self.inner.async_support(),
+            "cannot use `call_resumable` when async support is enabled on the config"
+        );
+        let config = self.engine().config();
+        if config.max_wasm_stack > config.async_stack_size {
+            bail!("max_wasm_stack size cannot exceed the async_stack_size");
+        }
+        // Borrowing the whole `Store` guarantees that this is the outermost
+        // call, so the call made on the fiber will configure its own stack
+        // limit.
+        debug_assert_eq!(
+            unsafe { *self.inner.runtime_limits().stack_limit.get() },
+            usize::MAX
+        );
+
+        let current_suspend = self.inner.async_state.current_suspend.get();
+        let stack = self.inner.allocate_fiber_stack()?;
+        let engine = self.engine().clone();
+        let func = *func;
+        let params = params.to_vec();
+        let store: *mut Store<T> = self;
+        let fiber = wasmtime_fiber::Fiber::new(stack, move |keep_going, suspend| {
+            keep_going?;
+
+            // Configure the suspension context which `new_epoch` uses to
+            // pause this call, resetting it once the call is done. This is
+            // the same protocol used by `StoreContextMut::on_fiber`.
+            unsafe {
+                let _reset = Reset(current_suspend, *current_suspend);
+                *current_suspend = suspend;
+                func.call(&mut *store, &params, results)
+            }
+        })?;
+
+        Suspended {
+            fiber: Some(fiber),
+            state: Some(AsyncWasmCallState::new()),
+            engine,
+            _store: marker::PhantomData,
+        }
+        .resume_impl(Ok(()))
+    }
+}
+
+impl<'a, T> Suspended<'a, T> {
+    /// Continues executing this call until it either finishes or is suspended
+    /// again.
+    ///
+    /// For more information see [`Store::call_resumable`].
+    pub fn resume(self) -> Result<ResumableCall<'a, T>> {
+        self.resume_impl(Ok(()))
+    }
+
+    fn resume_impl(mut self, val: Result<()>) -> Result<ResumableCall<'a, T>> {
+        match self.resume_fiber(val) {
+            Ok(result) => {
+                self.state.take().unwrap().assert_null();
+                let stack = self.fiber.take().unwrap().into_stack();
+                unsafe {
+                    self.engine.allocator().deallocate_fiber_stack(stack);
+                }
+                result.map(|()| ResumableCall::Finished)
+            }
+            Err(()) => {
+                // Like async calls, ensure that no thread-local state points
+                // into the suspended stack now that it has been switched away
+                // from.
+                if let Some(range) = self.fiber().stack().range() {
+                    AsyncWasmCallState::assert_current_state_not_in_range(range);
+                }
+                Ok(ResumableCall::Suspended(self))
+            }
+        }
+    }
+
+    fn fiber(&self) -> &ResumableFiber<'a> {
+        self.fiber.as_ref().unwrap()
+    }
+
+    /// Resumes the underlying fiber while managing Wasmtime's thread-local
+    /// list of activations, see `FiberFuture::resume` for more details.
+    fn resume_fiber(&mut self, val: Result<()>) -> Result<Result<()>, ()> {
+        unsafe {
+            let prev = self.state.take().unwrap().push();
+            let restore = Restore {
+                suspended: self,
+                state: Some(prev),
+            };
+            return restore.suspended.fiber().resume(val);
+        }
+
+        struct Restore<'a, 'b, T> {
+            suspended: &'a mut Suspended<'b, T>,
+            state: Option<PreviousAsyncWasmCallState>,
+        }
+
+        impl<T> Drop for Restore<'_, '_, T> {
+            fn drop(&mut self) {
+                unsafe {
+                    self.suspended.state = Some(self.state.take().unwrap().restore());
+                }
+            }
+        }
+    }
+}
+
+impl<T> Drop for Suspended<'_, T> {
+    fn drop(&mut self) {
+        if self.fiber.is_none() {
+            return;
+        }
+
+        if !self.fiber().done() {
+            let result = self.resume_fiber(Err(anyhow!("resumable call cancelled")));
+            debug_assert!(result.is_ok());
+        }
+
+        self.state.take().unwrap().assert_null();
+
+        unsafe {
+            let stack = self.fiber.take().unwrap().into_stack();
+            self.engine.allocator().deallocate_fiber_stack(stack);
+        }
+    }
+}
+
+/// Suspends the currently executing resumable call, returning once it has
+/// been resumed.
+///
+/// Returns an error if the call was cancelled while it was suspended.
+///
+/// # Safety
+///
+/// `suspend` must be the non-null suspension context configured by
+/// [`Store::call_resumable`] for the current fiber.
+pub(super) unsafe fn suspend(
+    suspend: *mut wasmtime_fiber::Suspend<Result<()>, (), Result<()>>,
+    track_pkey_context_switch: bool,
+) -> Result<()> {
+    use crate::runtime::vm::mpk::{self, ProtectionMask};
+
+    debug_assert!(!suspend.is_null());
+    let previous_mask = if track_pkey_context_switch {
+        let previous_mask = mpk::current_mask();
+        mpk::allow(ProtectionMask::all());
+        previous_mask
+    } else {
+        ProtectionMask::all()
+    };
+    let result = (*suspend).suspend(());
+    if track_pkey_context_switch {
+        mpk::allow(previous_mask);
+    }
+    result
+}
@@ -0,0 +1,162 @@
+use crate::runtime::vm;
+use crate::CallHook;
+use core::time::Duration;
+use std::thread::{self, ThreadId};
+use std::time::Instant;
+
+/// A snapshot of the time a [`Store`](crate::Store) has spent executing, as
+/// returned by [`Store::stats`](crate::Store::stats).
+///
+/// Statistics are only gathered once enabled with
+/// [`Store::enable_stats`](crate::Store::enable_stats), and until then every
+/// field is zero.
+///
+/// Times are measured between the transitions reported to
+/// [`Store::call_hook`](crate::Store::call_hook), both in wall-clock time and
+/// in CPU time of the thread making the transitions. Time spent by a host
+/// function blocked on I/O, or by an async host function waiting to be polled
+/// again, is counted as wall-clock time in the host but not as CPU time.
+///
+/// CPU time is only measured on platforms with per-thread CPU clocks, which
+/// are Unix and Windows, and is otherwise zero. An interval in which
+/// execution moved to another thread, which happens when an async call is
+/// polled from different threads, isn't counted as CPU time either, since the
+/// clocks of different threads can't be compared.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct StoreStats {
+    /// Wall-clock time spent executing WebAssembly code.
+    pub wasm_wall_time: Duration,
+    /// Wall-clock time spent in host functions called by WebAssembly code.
+    pub host_wall_time: Duration,
+    /// CPU time spent executing WebAssembly code.
+    pub wasm_cpu_time: Duration,
+    /// CPU time spent in host functions called by WebAssembly code.
+    pub host_cpu_time: Duration,
+    /// The number of calls from WebAssembly code to host functions.
+    pub host_calls: u64,
+    /// The number of calls from the host into WebAssembly which ended with a
+    /// trap, including errors returned by host functions.
+    ///
+    /// Only the outermost call is counted when a trap unwinds through several
+    /// levels of calls between WebAssembly and the host.
+    pub traps: u64,
+}
+
+/// What the store is doing at the moment, for the purpose of attributing
+/// time.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Not executing at all, in between calls made by the embedder.
+    Idle,
+    Wasm,
+    Host,
+}
+
+/// A point in time on both the wall clock and the current thread's CPU clock.
+#[derive(Clone, Copy)]
+struct Timestamp {
+    wall: Instant,
+    cpu: Option<(ThreadId, Duration)>,
+}
+
+impl Timestamp {
+    fn now() -> Timestamp {
+        Timestamp {
+            wall: Instant::now(),
+            cpu: vm::thread_cpu_time().map(|time| (thread::current().id(), time)),
+        }
+    }
+
+    /// Returns the wall-clock and CPU time elapsed from `earlier` to `self`.
+    fn since(&self, earlier: &Timestamp) -> (Duration, Duration) {
+        let wall = self.wall - earlier.wall;
+        let cpu = match (self.cpu, earlier.cpu) {
+            (Some((thread, now)), Some((earlier_thread, earlier))) if thread == earlier_thread => {
+                now.saturating_sub(earlier)
+            }
+            _ => Duration::ZERO,
+        };
+        (wall, cpu)
+    }
+}
+
+/// Accumulates [`StoreStats`] from a store's call hook transitions.
+pub(crate) struct StatsTracker {
+    stats: StoreStats,
+    mode: Mode,
+    since: Timestamp,
+    wasm_depth: usize,
+    host_depth: usize,
+}
+
+impl StatsTracker {
+    pub(crate) fn new() -> StatsTracker {
+        StatsTracker {
+            stats: StoreStats::default(),
+            mode: Mode::Idle,
+            since: Timestamp::now(),
+            wasm_depth: 0,
+            host_depth: 0,
+        }
+    }
+
+    /// Returns the statistics gathered so far, including the time spent in
+    /// the current mode up to now.
+    pub(crate) fn snapshot(&self) -> StoreStats {
+        let mut stats = self.stats;
+        Self::attribute(&mut stats, self.mode, Timestamp::now().since(&self.since));
+        stats
+    }
+
+    pub(crate) fn transition(&mut self, hook: CallHook) {
+        let now = Timestamp::now();
+        Self::attribute(&mut self.stats, self.mode, now.since(&self.since));
+        self.since = now;
+        self.mode = match hook {
+            CallHook::CallingWasm => {
+                self.wasm_depth += 1;
+                Mode::Wasm
+            }
+            CallHook::ReturningFromWasm => {
+                self.wasm_depth = self.wasm_depth.saturating_sub(1);
+                if self.host_depth > 0 {
+                    Mode::Host
+                } else {
+                    Mode::Idle
+                }
+            }
+            CallHook::CallingHost => {
+                self.host_depth += 1;
+                self.stats.host_calls += 1;
+                Mode::Host
+            }
+            CallHook::ReturningFromHost => {
+                self.host_depth = self.host_depth.saturating_sub(1);
+                Mode::Wasm
+            }
+        };
+    }
+
+    /// Records the result of a call from the host into WebAssembly, after its
+    /// `ReturningFromWasm` transition.
+    pub(crate) fn returned_from_wasm(&mut self, trapped: bool) {
+        if trapped && self.wasm_depth == 0 {
+            self.stats.traps += 1;
+        }
+    }
+
+    fn attribute(stats: &mut StoreStats, mode: Mode, (wall, cpu): (Duration, Duration)) {
+        match mode {
+            Mode::Idle => {}
+            Mode::Wasm => {
+                stats.wasm_wall_time += wall;
+                stats.wasm_cpu_time += cpu;
+            }
+            Mode::Host => {
+                stats.host_wall_time += wall;
+                stats.host_cpu_time += cpu;
+            }
+        }
+    }
+}
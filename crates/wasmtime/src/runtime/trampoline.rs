@@ -48,6 +48,7 @@ fn create_handle(
             wmemcheck: false,
             pkey: None,
             tunables: store.engine().tunables(),
+            restore: None,
         })?;
 
         Ok(store.add_dummy_instance(handle))
@@ -9,7 +9,7 @@ use crate::runtime::vm::{
 };
 use crate::store::{InstanceId, StoreOpaque};
 use crate::MemoryType;
-use alloc::sync::Arc;
+use alloc::sync::{Arc, Weak};
 use wasmtime_environ::{
     DefinedMemoryIndex, DefinedTableIndex, EntityIndex, HostPtr, Module, Tunables, VMOffsets,
 };
@@ -59,6 +59,7 @@ pub fn create_memory(
         wmemcheck: false,
         pkey: None,
         tunables: store.engine().tunables(),
+        restore: None,
     };
 
     unsafe {
@@ -242,10 +243,16 @@ unsafe impl InstanceAllocatorImpl for SingleMemoryInstance<'_> {
         unreachable!()
     }
 
+    fn on_slots_released(&self, _listener: Weak<dyn Fn() + Send + Sync>) -> bool {
+        unreachable!()
+    }
+
     #[cfg(feature = "gc")]
     fn allocate_gc_heap(
         &self,
+        _engine: &crate::Engine,
         _gc_runtime: &dyn crate::runtime::vm::GcRuntime,
+        _pkey: Option<ProtectionKey>,
     ) -> Result<(GcHeapAllocationIndex, Box<dyn crate::runtime::vm::GcHeap>)> {
         unreachable!()
     }
@@ -422,6 +422,16 @@ impl Val {
         self.funcref().expect("expected funcref")
     }
 
+    /// Deserialize the arguments of a function of type `params` from the
+    /// WebAssembly Value Encoding (WAVE), such as `(1, 2.5)`.
+    ///
+    /// Only numeric and vector values are supported, with `v128`s encoded as
+    /// a tuple of their low and high `i64` halves.
+    #[cfg(feature = "wave")]
+    pub fn from_wave_args(params: &[ValType], s: &str) -> Result<Vec<Val>> {
+        crate::runtime::wave::from_wave_args(params, s)
+    }
+
     #[inline]
     pub(crate) fn comes_from_same_store(&self, store: &StoreOpaque) -> bool {
         match self {
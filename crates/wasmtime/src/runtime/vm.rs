@@ -64,12 +64,12 @@ pub use crate::runtime::vm::imports::Imports;
 pub use crate::runtime::vm::instance::{
     GcHeapAllocationIndex, Instance, InstanceAllocationRequest, InstanceAllocator,
     InstanceAllocatorImpl, InstanceAndStore, InstanceHandle, MemoryAllocationIndex,
-    OnDemandInstanceAllocator, StorePtr, TableAllocationIndex,
+    OnDemandInstanceAllocator, RestoredMemories, StorePtr, TableAllocationIndex,
 };
 #[cfg(feature = "pooling-allocator")]
 pub use crate::runtime::vm::instance::{
     InstanceLimits, PoolConcurrencyLimitError, PoolingInstanceAllocator,
-    PoolingInstanceAllocatorConfig,
+    PoolingInstanceAllocatorConfig, ScrubPolicy,
 };
 pub use crate::runtime::vm::interpreter::*;
 pub use crate::runtime::vm::memory::{
@@ -80,6 +80,8 @@ pub use crate::runtime::vm::mpk::MpkEnabled;
 pub use crate::runtime::vm::store_box::*;
 #[cfg(feature = "std")]
 pub use crate::runtime::vm::sys::mmap::open_file_for_mmap;
+#[cfg(feature = "std")]
+pub use crate::runtime::vm::sys::thread_cpu_time;
 pub use crate::runtime::vm::sys::unwind::UnwindRegistration;
 pub use crate::runtime::vm::table::{Table, TableElement};
 pub use crate::runtime::vm::traphandlers::*;
@@ -163,6 +165,24 @@ pub unsafe trait VMStore {
     /// Note that this is not invoked if `table_growing` returns an error.
     fn table_grow_failed(&mut self, error: Error) -> Result<()>;
 
+    /// Callback invoked to allow the store's resource limiter to reject the
+    /// growth of the GC heap.
+    fn gc_heap_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool, Error>;
+
+    /// Callback invoked to allow the store's resource limiter to reject the
+    /// growth of the compiled code used by the store.
+    fn code_memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        maximum: Option<usize>,
+    ) -> Result<bool, Error>;
+
     /// Callback invoked whenever fuel runs out by a wasm instance. If an error
     /// is returned that's raised as a trap. Otherwise wasm execution will
     /// continue as normal.
@@ -173,6 +193,14 @@ pub unsafe trait VMStore {
     /// completely semantically transparent. Returns the new deadline.
     fn new_epoch(&mut self) -> Result<u64, Error>;
 
+    /// Callback invoked when execution stops at an instruction while guest
+    /// debugging, returning how execution should proceed.
+    fn debug_break(&mut self, frame: &crate::DebugFrame) -> Result<crate::DebugStep>;
+
+    /// Callback invoked before each linear memory access when memory access
+    /// tracing is enabled.
+    fn memory_access(&mut self, access: &crate::MemoryAccess) -> Result<()>;
+
     /// Callback invoked whenever an instance needs to trigger a GC.
     ///
     /// Optionally given a GC reference that is rooted for the collection, and
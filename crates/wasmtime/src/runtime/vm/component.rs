@@ -30,6 +30,7 @@ const INVALID_PTR: usize = 0xdead_dead_beef_beef_u64 as usize;
 mod libcalls;
 mod resources;
 
+pub use self::libcalls::transcode;
 pub use self::resources::{CallContexts, ResourceTable, ResourceTables};
 
 /// Runtime representation of a component instance and all state necessary for
@@ -573,6 +574,13 @@ impl ComponentInstance {
         &mut self.component_resource_tables
     }
 
+    /// Returns the runtime state of resources associated with this component,
+    /// used to inspect it in coredumps.
+    #[cfg(feature = "coredump")]
+    pub fn resource_table_state(&self) -> &PrimaryMap<TypeResourceTableIndex, ResourceTable> {
+        &self.component_resource_tables
+    }
+
     /// Returns the destructor and instance flags for the specified resource
     /// table type.
     ///
@@ -626,6 +634,56 @@ impl ComponentInstance {
         tables.resource_lower_borrow(Some(dst), rep)
     }
 
+    /// Same as `resource_transfer_own`, except that the handle is moved from
+    /// the `src` table of `src_instance` into the `dst` table of
+    /// `dst_instance`.
+    ///
+    /// This is used by adapters between components which were linked together
+    /// at runtime, where each side of a call is its own component instance.
+    ///
+    /// # Safety
+    ///
+    /// Both instances must be valid and belong to the same store, although
+    /// they're allowed to be the same instance.
+    pub(crate) unsafe fn resource_transfer_own_between(
+        src_instance: *mut ComponentInstance,
+        idx: u32,
+        src: TypeResourceTableIndex,
+        dst_instance: *mut ComponentInstance,
+        dst: TypeResourceTableIndex,
+    ) -> Result<u32> {
+        let rep = (*src_instance)
+            .resource_tables()
+            .resource_lift_own(Some(src), idx)?;
+        (*dst_instance)
+            .resource_tables()
+            .resource_lower_own(Some(dst), rep)
+    }
+
+    /// Same as `resource_transfer_own_between`, but for borrowed handles.
+    ///
+    /// # Safety
+    ///
+    /// Same as `resource_transfer_own_between`.
+    pub(crate) unsafe fn resource_transfer_borrow_between(
+        src_instance: *mut ComponentInstance,
+        idx: u32,
+        src: TypeResourceTableIndex,
+        dst_instance: *mut ComponentInstance,
+        dst: TypeResourceTableIndex,
+    ) -> Result<u32> {
+        let rep = (*src_instance)
+            .resource_tables()
+            .resource_lift_borrow(Some(src), idx)?;
+        // Same special case as in `resource_transfer_borrow`.
+        if (*dst_instance).resource_owned_by_own_instance(dst) {
+            return Ok(rep);
+        }
+        (*dst_instance)
+            .resource_tables()
+            .resource_lower_borrow(Some(dst), rep)
+    }
+
     pub(crate) fn resource_enter_call(&mut self) {
         self.resource_tables().enter_call()
     }
@@ -3,10 +3,11 @@
 use crate::prelude::*;
 use crate::runtime::vm::component::{ComponentInstance, VMComponentContext};
 use crate::runtime::vm::HostResultHasUnwindSentinel;
+use crate::ValRaw;
 use core::cell::Cell;
 use core::convert::Infallible;
 use core::slice;
-use wasmtime_environ::component::TypeResourceTableIndex;
+use wasmtime_environ::component::{FixedEncoding, Transcode, TypeResourceTableIndex};
 
 const UTF16_TAG: usize = 1 << 31;
 
@@ -486,6 +487,134 @@ fn inflate_latin1_bytes(dst: &mut [u16], latin1_bytes_so_far: usize) -> &mut [u1
     return rest;
 }
 
+/// Runs the transcoder `op` on behalf of an adapter between two components
+/// which were linked together at runtime, rather than from a trampoline
+/// compiled into a component.
+///
+/// The memory being read starts at `from` and is `from_len` bytes long, and
+/// likewise for the memory being written at `to`. The `params_and_results`
+/// are the core wasm parameters of the transcoder on entry, where pointers are
+/// offsets into the memories, and its results on return. Unlike with the
+/// trampolines the parameters are validated here, although adapters should
+/// have already done so.
+///
+/// # Safety
+///
+/// The two memories must be valid for their lengths and must not overlap.
+pub unsafe fn transcode(
+    op: Transcode,
+    from: *mut u8,
+    from_len: usize,
+    from64: bool,
+    to: *mut u8,
+    to_len: usize,
+    to64: bool,
+    params_and_results: &mut [ValRaw],
+) -> Result<()> {
+    let param = |i: usize, is64: bool| -> Result<usize> {
+        let val = params_and_results[i];
+        Ok(if is64 {
+            usize::try_from(val.get_u64())?
+        } else {
+            usize::try_from(val.get_u32())?
+        })
+    };
+    // Returns the address of `units` code units of `size` bytes each at
+    // offset `ptr` in a memory.
+    let addr = |base: *mut u8, len: usize, ptr: usize, units: usize, size: usize| {
+        if ptr % size != 0 {
+            bail!("unaligned pointer for a string of {size}-byte code units");
+        }
+        match units.checked_mul(size).and_then(|n| n.checked_add(ptr)) {
+            Some(end) if end <= len => Ok(base.add(ptr)),
+            _ => bail!("string out of bounds of memory"),
+        }
+    };
+    let src = |units, size| addr(from, from_len, param(0, from64)?, units, size);
+    let dst = |units, size| addr(to, to_len, param(2, to64)?, units, size);
+    let ret = |is64: bool, val: usize| -> Result<ValRaw> {
+        Ok(if is64 {
+            ValRaw::u64(u64::try_from(val)?)
+        } else {
+            ValRaw::u32(u32::try_from(val)?)
+        })
+    };
+
+    let len = param(1, from64)?;
+    match op {
+        Transcode::Copy(FixedEncoding::Utf8) => utf8_to_utf8(src(len, 1)?, len, dst(len, 1)?),
+        Transcode::Copy(FixedEncoding::Utf16) => {
+            utf16_to_utf16(src(len, 2)?.cast(), len, dst(len, 2)?.cast())
+        }
+        Transcode::Copy(FixedEncoding::Latin1) => latin1_to_latin1(src(len, 1)?, len, dst(len, 1)?),
+        Transcode::Latin1ToUtf16 => latin1_to_utf16(src(len, 1)?, len, dst(len, 2)?.cast()),
+        Transcode::Utf8ToUtf16 => {
+            let CopySizeReturn(n) = utf8_to_utf16(src(len, 1)?, len, dst(len, 2)?.cast())?;
+            params_and_results[0] = ret(to64, n)?;
+            Ok(())
+        }
+        Transcode::Utf16ToCompactProbablyUtf16 => {
+            let CopySizeReturn(n) =
+                utf16_to_compact_probably_utf16(src(len, 2)?.cast(), len, dst(len, 2)?.cast())?;
+            params_and_results[0] = ret(to64, n)?;
+            Ok(())
+        }
+        Transcode::Utf16ToUtf8 | Transcode::Latin1ToUtf8 => {
+            let dst_len = param(3, to64)?;
+            let SizePair {
+                src_read,
+                dst_written,
+            } = if op == Transcode::Utf16ToUtf8 {
+                utf16_to_utf8(src(len, 2)?.cast(), len, dst(dst_len, 1)?, dst_len)?
+            } else {
+                latin1_to_utf8(src(len, 1)?, len, dst(dst_len, 1)?, dst_len)?
+            };
+            params_and_results[0] = ret(from64, src_read)?;
+            params_and_results[1] = ret(to64, dst_written)?;
+            Ok(())
+        }
+        Transcode::Utf8ToLatin1 | Transcode::Utf16ToLatin1 => {
+            let SizePair {
+                src_read,
+                dst_written,
+            } = if op == Transcode::Utf8ToLatin1 {
+                utf8_to_latin1(src(len, 1)?, len, dst(len, 1)?)?
+            } else {
+                utf16_to_latin1(src(len, 2)?.cast(), len, dst(len, 1)?)?
+            };
+            params_and_results[0] = ret(from64, src_read)?;
+            params_and_results[1] = ret(to64, dst_written)?;
+            Ok(())
+        }
+        Transcode::Utf8ToCompactUtf16 | Transcode::Utf16ToCompactUtf16 => {
+            let dst_len = param(3, to64)?;
+            let bytes_so_far = param(4, to64)?;
+            if bytes_so_far > dst_len {
+                bail!("string out of bounds of memory");
+            }
+            let CopySizeReturn(n) = if op == Transcode::Utf8ToCompactUtf16 {
+                utf8_to_compact_utf16(
+                    src(len, 1)?,
+                    len,
+                    dst(dst_len, 2)?.cast(),
+                    dst_len,
+                    bytes_so_far,
+                )?
+            } else {
+                utf16_to_compact_utf16(
+                    src(len, 2)?.cast(),
+                    len,
+                    dst(dst_len, 2)?.cast(),
+                    dst_len,
+                    bytes_so_far,
+                )?
+            };
+            params_and_results[0] = ret(to64, n)?;
+            Ok(())
+        }
+    }
+}
+
 unsafe fn resource_new32(vmctx: *mut VMComponentContext, resource: u32, rep: u32) -> Result<u32> {
     let resource = TypeResourceTableIndex::from_u32(resource);
     ComponentInstance::from_vmctx(vmctx, |instance| instance.resource_new32(resource, rep))
@@ -315,6 +315,21 @@ impl ResourceTable {
         Ok(ret)
     }
 
+    /// Returns the index, representation, and lend count of each handle in
+    /// this table, where borrows have no lend count.
+    #[cfg(feature = "coredump")]
+    pub fn handles(&self) -> impl Iterator<Item = (u32, u32, Option<u32>)> + '_ {
+        self.slots.iter().enumerate().filter_map(|(i, slot)| {
+            // See `insert` for why handle indices start at 1.
+            let idx = u32::try_from(i + 1).unwrap();
+            match slot {
+                Slot::Free { .. } => None,
+                Slot::Own { rep, lend_count } => Some((idx, *rep, Some(*lend_count))),
+                Slot::Borrow { rep, .. } => Some((idx, *rep, None)),
+            }
+        })
+    }
+
     fn handle_index_to_table_index(&self, idx: u32) -> Option<usize> {
         // NB: `idx` is decremented by one to account for the `+1` above during
         // allocation.
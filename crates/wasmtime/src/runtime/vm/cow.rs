@@ -222,6 +222,53 @@ impl ModuleMemoryImages {
 
         Ok(Some(ModuleMemoryImages { memories }))
     }
+
+    /// Create a new `ModuleMemoryImages` from the saved contents of each of a
+    /// module's defined memories, for restoring instances from a snapshot.
+    ///
+    /// Returns `None` if any memory's contents can't be represented as an
+    /// image, in which case the contents must be copied into each restored
+    /// instance instead.
+    #[cfg(feature = "snapshot")]
+    pub fn from_contents<'a>(
+        contents: impl ExactSizeIterator<Item = &'a [u8]>,
+    ) -> Result<Option<ModuleMemoryImages>> {
+        let mut memories = PrimaryMap::with_capacity(contents.len());
+        let page_size = host_page_size();
+        let page_size_u32 = u32::try_from(page_size).unwrap();
+        for data in contents {
+            // Trim leading and trailing zero pages; there's no need to
+            // include them in the image since memory is zero-initialized
+            // anyways.
+            let is_zero_page = |page: &[u8]| page.iter().all(|b| *b == 0);
+            let num_pages = data.len().div_ceil(page_size);
+            let page = |i: usize| &data[i * page_size..((i + 1) * page_size).min(data.len())];
+            let start = match (0..num_pages).find(|i| !is_zero_page(page(*i))) {
+                Some(start) => start,
+                None => {
+                    memories.push(None);
+                    continue;
+                }
+            };
+            let end = (0..num_pages).rfind(|i| !is_zero_page(page(*i))).unwrap() + 1;
+
+            // The image must be made of whole host pages and fit inside the
+            // memory, which isn't the case for a partial trailing page.
+            let start = start * page_size;
+            let end = end * page_size;
+            if end > data.len() {
+                return Ok(None);
+            }
+
+            let offset = HostAlignedByteCount::new(start).unwrap();
+            match MemoryImage::new(page_size_u32, offset, &data[start..end], None)? {
+                Some(image) => memories.push(Some(Arc::new(image))),
+                None => return Ok(None),
+            };
+        }
+
+        Ok(Some(ModuleMemoryImages { memories }))
+    }
 }
 
 /// Slot management of a copy-on-write image which can be reused for the pooling
@@ -318,6 +365,11 @@ pub struct MemoryImageSlot {
     /// `self.accessible` and `self.static_size` is inaccessible.
     dirty: bool,
 
+    /// Whether the initial contents of this slot are populated lazily on
+    /// first access, by `userfaultfd`, instead of being initialized by
+    /// copying data segments in at instantiation time.
+    lazy_init: bool,
+
     /// Whether this MemoryImageSlot is responsible for mapping anonymous
     /// memory (to hold the reservation while overwriting mappings
     /// specific to this slot) in place when it is dropped. Default
@@ -345,6 +397,7 @@ impl MemoryImageSlot {
             accessible,
             image: None,
             dirty: false,
+            lazy_init: false,
             clear_on_drop: true,
         }
     }
@@ -713,15 +766,50 @@ impl MemoryImageSlot {
         Ok(())
     }
 
+    #[cfg(test)]
     pub(crate) fn has_image(&self) -> bool {
         self.image.is_some()
     }
 
+    /// Configures whether this slot's initial contents are populated lazily,
+    /// meaning data segments don't need to be copied in at instantiation.
+    #[allow(dead_code)] // ignore warnings as this is only used in some cfgs
+    pub(crate) fn set_lazy_init(&mut self, lazy: bool) {
+        self.lazy_init = lazy;
+    }
+
+    /// Returns whether data segments need to be copied into this slot at
+    /// instantiation time.
+    pub(crate) fn needs_init(&self) -> bool {
+        self.image.is_none() && !self.lazy_init
+    }
+
     #[allow(dead_code)] // ignore warnings as this is only used in some cfgs
     pub(crate) fn is_dirty(&self) -> bool {
         self.dirty
     }
 
+    /// Returns the accessible contents of this clean slot which lie outside of
+    /// its image, all of which must be zero.
+    #[allow(dead_code)] // ignore warnings as this is only used in some cfgs
+    pub(crate) fn contents_outside_image(&self) -> [&[u8]; 2] {
+        assert!(!self.dirty);
+        let accessible = self.accessible.byte_count();
+        let (image_start, image_end) = match &self.image {
+            Some(image) => {
+                let start = image.linear_memory_offset.byte_count();
+                let end = start + image.len.byte_count();
+                (start.min(accessible), end.min(accessible))
+            }
+            None => (accessible, accessible),
+        };
+        // SAFETY: the first `accessible` bytes of the slot are readable, and
+        // nothing else accesses a slot which isn't in use.
+        let contents =
+            unsafe { core::slice::from_raw_parts(self.base.as_mut_ptr().cast_const(), accessible) };
+        [&contents[..image_start], &contents[image_end..]]
+    }
+
     /// Map anonymous zeroed memory across the whole slot,
     /// inaccessible. Used both during instantiate and during drop.
     fn reset_with_anon_memory(&mut self) -> Result<()> {
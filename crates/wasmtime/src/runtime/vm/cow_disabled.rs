@@ -30,6 +30,13 @@ impl ModuleMemoryImages {
     ) -> Result<Option<ModuleMemoryImages>> {
         Ok(None)
     }
+
+    #[cfg(feature = "snapshot")]
+    pub fn from_contents<'a>(
+        _contents: impl ExactSizeIterator<Item = &'a [u8]>,
+    ) -> Result<Option<ModuleMemoryImages>> {
+        Ok(None)
+    }
 }
 
 #[derive(Debug)]
@@ -40,7 +47,7 @@ impl MemoryImageSlot {
         match *self {}
     }
 
-    pub(crate) fn has_image(&self) -> bool {
+    pub(crate) fn needs_init(&self) -> bool {
         match *self {}
     }
 }
@@ -62,6 +62,32 @@ impl GcStore {
         }
     }
 
+    /// Get the current size of the GC heap, the size it should grow to in
+    /// order to make room for an allocation of `bytes_needed` bytes, and the
+    /// largest size it may grow to.
+    ///
+    /// Returns `None` if the heap can't grow any larger.
+    pub fn heap_growth(&self, bytes_needed: usize) -> Option<(usize, usize, usize)> {
+        /// The smallest amount to grow the heap by at once.
+        const MIN_GROWTH: usize = 64 << 10;
+
+        let current = self.gc_heap.size();
+        let maximum = self.gc_heap.heap_slice().len();
+        if current >= maximum {
+            return None;
+        }
+
+        // Grow geometrically, so that the cost of growth is amortized, and by
+        // at least twice the allocation's size, so that it fits even when the
+        // collector splits its heap into two semi-spaces.
+        let desired = current
+            .saturating_mul(2)
+            .max(current.saturating_add(bytes_needed.saturating_mul(2)))
+            .max(MIN_GROWTH)
+            .min(maximum);
+        Some((current, desired, maximum))
+    }
+
     /// Perform garbage collection within this heap.
     pub fn gc(&mut self, roots: GcRootsIter<'_>) {
         let mut collection = self.gc_heap.gc(roots, &mut self.host_data_table);
@@ -21,6 +21,14 @@ mod null;
 #[cfg(feature = "gc-null")]
 pub use null::*;
 
+#[cfg(feature = "gc-copying")]
+mod copying;
+#[cfg(feature = "gc-copying")]
+pub use copying::*;
+
+#[cfg(any(feature = "gc-drc", feature = "gc-copying"))]
+mod trace_info;
+
 use crate::runtime::vm::GcRuntime;
 
 /// The default GC heap capacity: 512KiB.
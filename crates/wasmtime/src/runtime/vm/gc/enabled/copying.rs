@@ -0,0 +1,666 @@
+//! The semi-space copying collector.
+//!
+//! The copying collector splits its GC heap into two equally-sized
+//! semi-spaces. Objects are bump allocated in the active semi-space. When it
+//! fills up, we do a Cheney-style collection: every object reachable from the
+//! roots is copied into the other semi-space, the roots and the copied objects'
+//! GC references are updated to point to the copies, and then the semi-spaces'
+//! roles are swapped. Anything left behind in the old semi-space is garbage,
+//! including cycles.
+//!
+//! All objects are sized and aligned to a multiple of `ALIGN`, so objects
+//! within a semi-space are contiguous, with no padding between them. This
+//! means that the copies of the live objects always fit in the other
+//! semi-space, and that we can walk a semi-space object-by-object.
+//!
+//! When an object is copied, its old header is overwritten with a forwarding
+//! header: its `VMGcKind` bits are cleared (which never happens for a valid
+//! object), its size is preserved so that we can still walk the old
+//! semi-space, and its type index is replaced with the new copy's heap index.
+//!
+//! The semi-spaces start out empty and grow together, towards their maximum
+//! size of half of the heap, as the store's resource limiter allows.
+//!
+//! This collector does not require any GC barriers.
+
+use super::trace_info::{TraceInfo, TraceInfos};
+use super::*;
+use crate::{
+    prelude::*,
+    vm::{
+        mmap::AlignedLength, ExternRefHostDataId, ExternRefHostDataTable, GarbageCollection,
+        GcHeap, GcHeapObject, GcProgress, GcRootsIter, Mmap, TypedGcRef, VMGcHeader, VMGcRef,
+    },
+    Engine, GcHeapOutOfMemory,
+};
+use core::{
+    alloc::Layout,
+    any::Any,
+    cell::UnsafeCell,
+    num::{NonZeroU32, NonZeroUsize},
+    ops::Range,
+    ptr,
+};
+use wasmtime_environ::{
+    copying::CopyingTypeLayouts, GcArrayLayout, GcStructLayout, GcTypeLayouts, VMGcKind,
+    VMSharedTypeIndex, VM_GC_HEADER_KIND_OFFSET, VM_GC_HEADER_TYPE_INDEX_OFFSET,
+};
+
+/// The size and alignment granularity of every object in the copying
+/// collector's heap.
+///
+/// This is the maximum alignment that any GC object may require.
+const ALIGN: u32 = 16;
+
+/// The offset of the first element in an array of GC references.
+const ARRAY_GC_REF_ELEMS_OFFSET: u32 = wasmtime_environ::copying::ARRAY_LENGTH_OFFSET + 4;
+
+/// The semi-space copying collector.
+#[derive(Default)]
+pub struct CopyingCollector {
+    layouts: CopyingTypeLayouts,
+}
+
+unsafe impl GcRuntime for CopyingCollector {
+    fn layouts(&self) -> &dyn GcTypeLayouts {
+        &self.layouts
+    }
+
+    fn new_gc_heap(&self, engine: &Engine) -> Result<Box<dyn GcHeap>> {
+        let heap = CopyingHeap::new(engine)?;
+        Ok(Box::new(heap) as _)
+    }
+}
+
+/// A GC heap for the copying collector.
+struct CopyingHeap {
+    /// Bump-allocation finger within the active semi-space.
+    next: u32,
+
+    /// The range of heap indices making up the active semi-space.
+    active: Range<u32>,
+
+    /// The range of heap indices making up the inactive semi-space.
+    inactive: Range<u32>,
+
+    /// The length that each semi-space may grow to.
+    max_space_len: u32,
+
+    /// The number of active no-gc scopes at the current moment.
+    no_gc_count: usize,
+
+    /// Where the GC references are inside the objects we've allocated.
+    trace_infos: TraceInfos,
+
+    /// The actual GC heap.
+    heap: Mmap<AlignedLength>,
+}
+
+/// The common header for all arrays in the copying collector.
+#[repr(C)]
+struct VMCopyingArrayHeader {
+    header: VMGcHeader,
+    length: u32,
+}
+
+unsafe impl GcHeapObject for VMCopyingArrayHeader {
+    #[inline]
+    fn is(header: &VMGcHeader) -> bool {
+        header.kind() == VMGcKind::ArrayRef
+    }
+}
+
+/// The representation of an `externref` in the copying collector.
+#[repr(C)]
+struct VMCopyingExternRef {
+    header: VMGcHeader,
+    host_data: ExternRefHostDataId,
+}
+
+unsafe impl GcHeapObject for VMCopyingExternRef {
+    #[inline]
+    fn is(header: &VMGcHeader) -> bool {
+        header.kind() == VMGcKind::ExternRef
+    }
+}
+
+fn oom() -> Error {
+    GcHeapOutOfMemory::new(()).into()
+}
+
+/// Round `size` up to the next multiple of `ALIGN`, if possible.
+fn round_up_to_align(size: u32) -> Option<u32> {
+    size.checked_next_multiple_of(ALIGN)
+}
+
+impl CopyingHeap {
+    /// Construct a new, default heap for the copying collector.
+    fn new(engine: &Engine) -> Result<Self> {
+        Self::with_capacity(engine, super::DEFAULT_GC_HEAP_CAPACITY)
+    }
+
+    /// Create a new copying heap with the given capacity.
+    ///
+    /// Only half of the capacity is usable at any given time.
+    fn with_capacity(engine: &Engine, capacity: usize) -> Result<Self> {
+        let heap = Mmap::with_at_least(capacity)?;
+
+        // Split the heap into two equally-sized halves for the semi-spaces.
+        // Neither starts at index zero, since that is the null reference, and
+        // both start out empty until the heap is grown.
+        let len = u32::try_from(heap.len()).unwrap_or(u32::MAX);
+        let half = (len / 2) & !(ALIGN - 1);
+        let active = ALIGN..ALIGN;
+        let inactive = half + ALIGN..half + ALIGN;
+
+        Ok(Self {
+            next: active.start,
+            active,
+            inactive,
+            max_space_len: half - ALIGN,
+            no_gc_count: 0,
+            trace_infos: TraceInfos::new(engine),
+            heap,
+        })
+    }
+
+    fn alloc(&mut self, mut header: VMGcHeader, layout: Layout) -> Result<Option<VMGcRef>> {
+        debug_assert!(layout.size() >= core::mem::size_of::<VMGcHeader>());
+        debug_assert!(layout.align() >= core::mem::align_of::<VMGcHeader>());
+        debug_assert!(layout.align() <= usize::try_from(ALIGN).unwrap());
+
+        // Make sure that the requested allocation's size, rounded up to our
+        // alignment, fits in the GC header's unused bits.
+        let size = match u32::try_from(layout.size())
+            .ok()
+            .and_then(round_up_to_align)
+            .filter(|size| VMGcKind::value_fits_in_unused_bits(*size))
+        {
+            Some(size) => size,
+            None => return Err(crate::Trap::AllocationTooLarge.into()),
+        };
+
+        // If the object can't fit even in an empty semi-space of the largest
+        // size, then collecting garbage or growing the heap won't help.
+        if size > self.max_space_len {
+            return Err(oom());
+        }
+
+        // Check whether the allocation fits in the active semi-space's
+        // remaining space, and if not, ask our caller to collect garbage or
+        // grow the heap.
+        debug_assert_eq!(self.next % ALIGN, 0);
+        let end_of_object = match self.next.checked_add(size) {
+            Some(end) if end <= self.active.end => end,
+            _ => return Ok(None),
+        };
+
+        let gc_ref = VMGcRef::from_heap_index(NonZeroU32::new(self.next).unwrap()).unwrap();
+        self.next = end_of_object;
+
+        if let Some(ty) = header.ty() {
+            debug_assert!(matches!(
+                header.kind(),
+                VMGcKind::StructRef | VMGcKind::ArrayRef
+            ));
+            self.trace_infos.ensure(ty);
+        }
+
+        debug_assert_eq!(header.reserved_u27(), 0);
+        header.set_reserved_u27(size);
+        *self.header_mut(&gc_ref) = header;
+
+        Ok(Some(gc_ref))
+    }
+
+    /// Get a `VMGcRef` for the object at the given heap index.
+    fn gc_ref_at(index: u32) -> VMGcRef {
+        VMGcRef::from_heap_index(NonZeroU32::new(index).unwrap()).unwrap()
+    }
+
+    /// Copy all live objects from the active semi-space into the inactive
+    /// semi-space, update the roots, and then swap the semi-spaces.
+    fn collect(
+        &mut self,
+        roots: &mut GcRootsIter<'_>,
+        host_data_table: &mut ExternRefHostDataTable,
+    ) {
+        let from_space = self.active.start..self.next;
+        log::trace!(
+            "Begin copying collection; {} bytes allocated",
+            from_space.end - from_space.start
+        );
+
+        let mut copier = Copier {
+            heap: {
+                let ptr = self.heap.as_mut_ptr();
+                let len = self.heap.len();
+                unsafe { core::slice::from_raw_parts_mut(ptr, len) }
+            },
+            trace_infos: &self.trace_infos,
+            next: self.inactive.start,
+            end: self.inactive.end,
+        };
+
+        // First, copy every object directly referenced by a root, and update
+        // the root to point to the copy.
+        for mut root in roots {
+            let gc_ref = root.get();
+            if gc_ref.is_i31() {
+                continue;
+            }
+            let index = gc_ref.as_heap_index().unwrap().get();
+            let new_index = copier.forward(index);
+            log::trace!("Updating root {gc_ref:#p} -> {new_index:#x}");
+            root.set(Self::gc_ref_at(new_index));
+        }
+
+        // Next, scan the copied objects, copying the objects they reference in
+        // turn, until we've caught up with the end of the copied objects.
+        let mut scan = self.inactive.start;
+        while scan < copier.next {
+            scan += copier.scan_object(scan);
+        }
+        let to_space_next = copier.next;
+
+        // Then, walk the old semi-space and deallocate the host data for any
+        // `externref`s that weren't copied.
+        let mut index = from_space.start;
+        while index < from_space.end {
+            let kind_and_size =
+                read_ne_u32(self.heap_slice_mut(), index + VM_GC_HEADER_KIND_OFFSET);
+            let size = kind_and_size & VMGcKind::UNUSED_MASK;
+            let is_forwarded = kind_and_size & VMGcKind::MASK == 0;
+            if !is_forwarded
+                && VMGcKind::from_high_bits_of_u32(kind_and_size) == VMGcKind::ExternRef
+            {
+                let externref = Self::gc_ref_at(index);
+                let host_data = self
+                    .index::<VMCopyingExternRef>(externref.as_typed_unchecked())
+                    .host_data;
+                log::trace!("Deallocating host data for unreachable externref {externref:#p}");
+                host_data_table.dealloc(host_data);
+            }
+            assert!(size > 0, "corrupt GC heap: zero-sized object");
+            index += size;
+        }
+
+        // Finally, swap the semi-spaces.
+        core::mem::swap(&mut self.active, &mut self.inactive);
+        self.next = to_space_next;
+        log::trace!(
+            "End copying collection; {} bytes live",
+            self.next - self.active.start
+        );
+    }
+}
+
+/// Read a native-endian `u32` out of the heap, such as a header word or an
+/// array's length.
+///
+/// NB: Forwarding headers intentionally have invalid `VMGcKind`s, so we read
+/// header words directly rather than via `VMGcHeader::kind`, which would panic.
+fn read_ne_u32(heap: &[u8], index: u32) -> u32 {
+    let index = usize::try_from(index).unwrap();
+    u32::from_ne_bytes(heap[index..][..4].try_into().unwrap())
+}
+
+/// The state of copying objects from one semi-space into the other.
+struct Copier<'a> {
+    heap: &'a mut [u8],
+    trace_infos: &'a TraceInfos,
+    /// The bump finger within the to-space.
+    next: u32,
+    /// The end of the to-space.
+    end: u32,
+}
+
+impl Copier<'_> {
+    fn read_ne_u32(&self, index: u32) -> u32 {
+        read_ne_u32(self.heap, index)
+    }
+
+    fn write_ne_u32(&mut self, index: u32, val: u32) {
+        let index = usize::try_from(index).unwrap();
+        self.heap[index..][..4].copy_from_slice(&val.to_ne_bytes());
+    }
+
+    fn read_le_u32(&self, index: u32) -> u32 {
+        let index = usize::try_from(index).unwrap();
+        u32::from_le_bytes(self.heap[index..][..4].try_into().unwrap())
+    }
+
+    fn write_le_u32(&mut self, index: u32, val: u32) {
+        let index = usize::try_from(index).unwrap();
+        self.heap[index..][..4].copy_from_slice(&val.to_le_bytes());
+    }
+
+    /// Copy the object at the given from-space index into the to-space, if it
+    /// hasn't been already, and return the index of its copy.
+    fn forward(&mut self, index: u32) -> u32 {
+        let kind_and_size = self.read_ne_u32(index + VM_GC_HEADER_KIND_OFFSET);
+        if kind_and_size & VMGcKind::MASK == 0 {
+            // Already copied: the forwarding header holds the new index.
+            return self.read_ne_u32(index + VM_GC_HEADER_TYPE_INDEX_OFFSET);
+        }
+
+        let size = kind_and_size & VMGcKind::UNUSED_MASK;
+        debug_assert_eq!(size % ALIGN, 0);
+        let new_index = self.next;
+        let new_next = new_index.checked_add(size).unwrap();
+        assert!(
+            new_next <= self.end,
+            "live objects should always fit in the to-space"
+        );
+
+        let from = usize::try_from(index).unwrap();
+        let size_usize = usize::try_from(size).unwrap();
+        let to = usize::try_from(new_index).unwrap();
+        self.heap.copy_within(from..from + size_usize, to);
+        self.next = new_next;
+
+        // Replace the old object's header with a forwarding header.
+        self.write_ne_u32(index + VM_GC_HEADER_KIND_OFFSET, size);
+        self.write_ne_u32(index + VM_GC_HEADER_TYPE_INDEX_OFFSET, new_index);
+
+        log::trace!("Copied object {index:#x} -> {new_index:#x} ({size} bytes)");
+        new_index
+    }
+
+    /// Forward the GC reference stored in the field at the given index, if
+    /// any, and update the field to point to the copy.
+    fn forward_field(&mut self, field: u32) {
+        let raw = self.read_le_u32(field);
+        let Some(gc_ref) = VMGcRef::from_raw_u32(raw) else {
+            return;
+        };
+        if gc_ref.is_i31() {
+            return;
+        }
+        let index = gc_ref.as_heap_index().unwrap().get();
+        let new_index = self.forward(index);
+        self.write_le_u32(field, new_index);
+    }
+
+    /// Forward all the GC references inside the to-space object at the given
+    /// index, returning the object's size.
+    fn scan_object(&mut self, index: u32) -> u32 {
+        let kind_and_size = self.read_ne_u32(index + VM_GC_HEADER_KIND_OFFSET);
+        let kind = VMGcKind::from_high_bits_of_u32(kind_and_size);
+        let size = kind_and_size & VMGcKind::UNUSED_MASK;
+        assert!(size > 0, "corrupt GC heap: zero-sized object");
+
+        match kind {
+            VMGcKind::ExternRef => {}
+            VMGcKind::StructRef | VMGcKind::ArrayRef => {
+                let ty = self.read_ne_u32(index + VM_GC_HEADER_TYPE_INDEX_OFFSET);
+                let ty = VMSharedTypeIndex::new(ty);
+                let trace_infos = self.trace_infos;
+                match trace_infos.get(ty) {
+                    TraceInfo::Struct { gc_ref_offsets } => {
+                        for offset in gc_ref_offsets.iter() {
+                            self.forward_field(index + offset);
+                        }
+                    }
+                    TraceInfo::Array { gc_ref_elems: true } => {
+                        let len = self
+                            .read_ne_u32(index + wasmtime_environ::copying::ARRAY_LENGTH_OFFSET);
+                        for i in 0..len {
+                            self.forward_field(index + ARRAY_GC_REF_ELEMS_OFFSET + i * 4);
+                        }
+                    }
+                    TraceInfo::Array {
+                        gc_ref_elems: false,
+                    } => {}
+                }
+            }
+            VMGcKind::AnyRef | VMGcKind::EqRef => {
+                unreachable!("objects are never allocated with abstract kinds")
+            }
+        }
+
+        size
+    }
+}
+
+unsafe impl GcHeap for CopyingHeap {
+    fn as_any(&self) -> &dyn Any {
+        self as _
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self as _
+    }
+
+    fn enter_no_gc_scope(&mut self) {
+        self.no_gc_count += 1;
+    }
+
+    fn exit_no_gc_scope(&mut self) {
+        self.no_gc_count -= 1;
+    }
+
+    fn size(&self) -> usize {
+        let space_len = self.active.end - self.active.start;
+        usize::try_from(2 * space_len).unwrap()
+    }
+
+    fn grow(&mut self, new_size: usize) {
+        // Both semi-spaces grow in place, so that objects in the active
+        // semi-space don't need to move.
+        let space_len = u32::try_from(new_size / 2)
+            .unwrap_or(u32::MAX)
+            .min(self.max_space_len)
+            & !(ALIGN - 1);
+        debug_assert!(space_len >= self.active.end - self.active.start);
+        self.active.end = self.active.start + space_len;
+        self.inactive.end = self.inactive.start + space_len;
+    }
+
+    fn heap_slice(&self) -> &[UnsafeCell<u8>] {
+        let ptr = self.heap.as_ptr().cast();
+        let len = self.heap.len();
+        unsafe { core::slice::from_raw_parts(ptr, len) }
+    }
+
+    fn heap_slice_mut(&mut self) -> &mut [u8] {
+        let ptr = self.heap.as_mut_ptr();
+        let len = self.heap.len();
+        unsafe { core::slice::from_raw_parts_mut(ptr, len) }
+    }
+
+    fn clone_gc_ref(&mut self, gc_ref: &VMGcRef) -> VMGcRef {
+        gc_ref.unchecked_copy()
+    }
+
+    fn write_gc_ref(
+        &mut self,
+        _host_data_table: &mut ExternRefHostDataTable,
+        destination: &mut Option<VMGcRef>,
+        source: Option<&VMGcRef>,
+    ) {
+        *destination = source.map(|s| s.unchecked_copy());
+    }
+
+    fn expose_gc_ref_to_wasm(&mut self, _gc_ref: VMGcRef) {
+        // Don't need to do anything special here.
+    }
+
+    fn need_gc_before_entering_wasm(&self, _num_gc_refs: NonZeroUsize) -> bool {
+        // Never need to GC before entering Wasm.
+        false
+    }
+
+    fn alloc_externref(&mut self, host_data: ExternRefHostDataId) -> Result<Option<VMExternRef>> {
+        let gc_ref =
+            match self.alloc(VMGcHeader::externref(), Layout::new::<VMCopyingExternRef>())? {
+                None => return Ok(None),
+                Some(gc_ref) => gc_ref,
+            };
+        self.index_mut::<VMCopyingExternRef>(gc_ref.as_typed_unchecked())
+            .host_data = host_data;
+        Ok(Some(gc_ref.into_externref_unchecked()))
+    }
+
+    fn externref_host_data(&self, externref: &VMExternRef) -> ExternRefHostDataId {
+        let gc_ref = externref.as_gc_ref();
+        debug_assert!(gc_ref.is_typed::<VMCopyingExternRef>(self));
+        let typed_ref: &TypedGcRef<VMCopyingExternRef> = gc_ref.as_typed_unchecked();
+        self.index(typed_ref).host_data
+    }
+
+    fn object_size(&self, gc_ref: &VMGcRef) -> usize {
+        let size = self.header(gc_ref).reserved_u27();
+        usize::try_from(size).unwrap()
+    }
+
+    fn header(&self, gc_ref: &VMGcRef) -> &VMGcHeader {
+        self.index(gc_ref.as_typed_unchecked())
+    }
+
+    fn header_mut(&mut self, gc_ref: &VMGcRef) -> &mut VMGcHeader {
+        self.index_mut(gc_ref.as_typed_unchecked())
+    }
+
+    fn alloc_raw(&mut self, header: VMGcHeader, layout: Layout) -> Result<Option<VMGcRef>> {
+        self.alloc(header, layout)
+    }
+
+    fn alloc_uninit_struct(
+        &mut self,
+        ty: VMSharedTypeIndex,
+        layout: &GcStructLayout,
+    ) -> Result<Option<VMStructRef>> {
+        let gc_ref = self.alloc(
+            VMGcHeader::from_kind_and_index(VMGcKind::StructRef, ty),
+            layout.layout(),
+        )?;
+        Ok(gc_ref.map(|r| r.into_structref_unchecked()))
+    }
+
+    fn dealloc_uninit_struct(&mut self, _struct_ref: VMStructRef) {}
+
+    fn alloc_uninit_array(
+        &mut self,
+        ty: VMSharedTypeIndex,
+        length: u32,
+        layout: &GcArrayLayout,
+    ) -> Result<Option<VMArrayRef>> {
+        let gc_ref = match self.alloc(
+            VMGcHeader::from_kind_and_index(VMGcKind::ArrayRef, ty),
+            layout.layout(length),
+        )? {
+            None => return Ok(None),
+            Some(gc_ref) => gc_ref,
+        };
+        self.index_mut::<VMCopyingArrayHeader>(gc_ref.as_typed_unchecked())
+            .length = length;
+        Ok(Some(gc_ref.into_arrayref_unchecked()))
+    }
+
+    fn dealloc_uninit_array(&mut self, _array_ref: VMArrayRef) {}
+
+    fn array_len(&self, arrayref: &VMArrayRef) -> u32 {
+        debug_assert!(arrayref.as_gc_ref().is_typed::<VMCopyingArrayHeader>(self));
+        self.index::<VMCopyingArrayHeader>(arrayref.as_gc_ref().as_typed_unchecked())
+            .length
+    }
+
+    fn gc<'a>(
+        &'a mut self,
+        roots: GcRootsIter<'a>,
+        host_data_table: &'a mut ExternRefHostDataTable,
+    ) -> Box<dyn GarbageCollection<'a> + 'a> {
+        assert_eq!(self.no_gc_count, 0, "Cannot GC inside a no-GC scope!");
+        Box::new(CopyingCollection {
+            roots,
+            host_data_table,
+            heap: self,
+            done: false,
+        })
+    }
+
+    unsafe fn vmctx_gc_heap_data(&self) -> *mut u8 {
+        // Compiled Wasm code always calls out to the runtime to allocate, so it
+        // doesn't need any collector data.
+        ptr::null_mut()
+    }
+
+    #[cfg(feature = "pooling-allocator")]
+    fn reset(&mut self) {
+        let CopyingHeap {
+            next,
+            active,
+            inactive,
+            max_space_len: _,
+            no_gc_count,
+            trace_infos,
+            heap: _,
+        } = self;
+
+        active.end = active.start;
+        inactive.end = inactive.start;
+        *next = active.start;
+        *no_gc_count = 0;
+        trace_infos.clear();
+    }
+}
+
+struct CopyingCollection<'a> {
+    roots: GcRootsIter<'a>,
+    host_data_table: &'a mut ExternRefHostDataTable,
+    heap: &'a mut CopyingHeap,
+    done: bool,
+}
+
+impl<'a> GarbageCollection<'a> for CopyingCollection<'a> {
+    fn collect_increment(&mut self) -> GcProgress {
+        if !self.done {
+            self.heap.collect(&mut self.roots, self.host_data_table);
+            self.done = true;
+        }
+        GcProgress::Complete
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmtime_environ::{
+        WasmArrayType, WasmFieldType, WasmHeapType, WasmRefType, WasmStorageType, WasmValType,
+    };
+
+    #[test]
+    fn vm_gc_copying_header_size_align() {
+        assert_eq!(
+            (wasmtime_environ::copying::HEADER_SIZE as usize),
+            core::mem::size_of::<VMGcHeader>()
+        );
+        assert_eq!(
+            (wasmtime_environ::copying::HEADER_ALIGN as usize),
+            core::mem::align_of::<VMGcHeader>()
+        );
+    }
+
+    #[test]
+    fn vm_copying_array_header_length_offset() {
+        assert_eq!(
+            wasmtime_environ::copying::ARRAY_LENGTH_OFFSET,
+            u32::try_from(core::mem::offset_of!(VMCopyingArrayHeader, length)).unwrap(),
+        );
+    }
+
+    #[test]
+    fn vm_copying_array_gc_ref_elems_offset() {
+        let ty = WasmArrayType(WasmFieldType {
+            element_type: WasmStorageType::Val(WasmValType::Ref(WasmRefType {
+                nullable: true,
+                heap_type: WasmHeapType::Any,
+            })),
+            mutable: true,
+        });
+        let layout = CopyingTypeLayouts::default().array_layout(&ty);
+        assert_eq!(layout.base_size, ARRAY_GC_REF_ELEMS_OFFSET);
+        assert_eq!(layout.elem_size, 4);
+    }
+}
@@ -1,7 +1,11 @@
 //! The deferred reference-counting (DRC) collector.
 //!
-//! Warning: this ref-counting collector does not have a tracing cycle
-//! collector, and therefore cannot collect cycles between GC objects!
+//! Reference counting alone cannot reclaim cycles between GC objects, so this
+//! collector is backed up by a trial-deletion cycle collector (see
+//! `DrcHeap::collect_cycles`) that runs occasionally, rather than on every GC.
+//! It only visits the objects reachable from *cycle candidates*: structs and
+//! arrays whose ref count was decremented without reaching zero, since every
+//! garbage cycle must contain such an object.
 //!
 //! For host VM code, we use plain reference counting, where cloning increments
 //! the reference count, and dropping decrements it. We can avoid many of the
@@ -42,6 +46,7 @@
 //! <https://openresearch-repository.anu.edu.au/bitstream/1885/42030/2/hon-thesis.pdf>
 
 use super::free_list::FreeList;
+use super::trace_info::{TraceInfo, TraceInfos};
 use super::{VMArrayRef, VMGcObjectDataMut, VMStructRef};
 use crate::hash_set::HashSet;
 use crate::prelude::*;
@@ -64,8 +69,13 @@ use wasmtime_environ::{GcArrayLayout, GcStructLayout, GcTypeLayouts, VMGcKind, V
 
 /// The deferred reference-counting (DRC) collector.
 ///
-/// This reference-counting collector does not have a cycle collector, and so it
-/// will not be able to reclaim garbage cycles.
+/// Garbage cycles are reclaimed by a backup trial-deletion cycle collector,
+/// which is only run once enough cycle candidates have accumulated or after
+/// an allocation fails, since it must visit every object reachable from the
+/// candidates.
+///
+/// Objects must be smaller than 64 MiB, since the DRC collector uses one of the
+/// bits in the object header that would otherwise hold its size.
 ///
 /// This is not a moving collector; it doesn't have a nursery or do any
 /// compaction.
@@ -79,8 +89,8 @@ unsafe impl GcRuntime for DrcCollector {
         &self.layouts
     }
 
-    fn new_gc_heap(&self) -> Result<Box<dyn GcHeap>> {
-        let heap = DrcHeap::new()?;
+    fn new_gc_heap(&self, engine: &crate::Engine) -> Result<Box<dyn GcHeap>> {
+        let heap = DrcHeap::new(engine)?;
         Ok(Box::new(heap) as _)
     }
 }
@@ -93,27 +103,72 @@ struct DrcHeap {
     activations_table: Box<VMGcRefActivationsTable>,
     heap: Mmap<AlignedLength>,
     free_list: FreeList,
+
+    /// Where the GC references are inside each type of object allocated in
+    /// this heap.
+    trace_infos: TraceInfos,
+
+    /// Structs and arrays which may be part of a garbage cycle, because their
+    /// ref count was decremented without reaching zero since the last cycle
+    /// collection.
+    ///
+    /// Each of these has `BUFFERED_BIT` set in its header, which keeps it
+    /// from being deallocated, even if its ref count reaches zero, until the
+    /// next cycle collection removes it from here.
+    cycle_candidates: Vec<VMGcRef>,
+
+    /// Run the cycle collector once there are more cycle candidates than
+    /// this.
+    cycle_collection_threshold: usize,
+
+    /// Whether an allocation has failed since the last collection.
+    alloc_failed: bool,
 }
 
+/// The minimum number of cycle candidates that will trigger a cycle
+/// collection.
+const MIN_CYCLE_COLLECTION_THRESHOLD: usize = 1024;
+
+/// The bit in an object's `VMGcHeader::reserved_u27` which is set while the
+/// object is in `DrcHeap::cycle_candidates`.
+///
+/// This is the top reserved bit, so it leaves the lower 26 bits for the
+/// object's size. Therefore, the largest object that the DRC collector can
+/// allocate is half the size of the largest object that the other collectors
+/// can allocate, and `alloc_raw` rejects anything larger with
+/// `Trap::AllocationTooLarge`.
+const BUFFERED_BIT: u32 = 1 << 26;
+
+/// The bits in an object's `VMGcHeader::reserved_u27` which hold its size.
+const SIZE_MASK: u32 = BUFFERED_BIT - 1;
+
+/// The offset of the first element in an array of GC references.
+const ARRAY_GC_REF_ELEMS_OFFSET: u32 = wasmtime_environ::drc::ARRAY_LENGTH_OFFSET + 4;
+
 impl DrcHeap {
     /// Construct a new, default DRC heap.
-    fn new() -> Result<Self> {
-        Self::with_capacity(super::DEFAULT_GC_HEAP_CAPACITY)
+    fn new(engine: &crate::Engine) -> Result<Self> {
+        Self::with_capacity(engine, super::DEFAULT_GC_HEAP_CAPACITY)
     }
 
     /// Create a new DRC heap with the given capacity.
-    fn with_capacity(capacity: usize) -> Result<Self> {
+    fn with_capacity(engine: &crate::Engine, capacity: usize) -> Result<Self> {
         let heap = Mmap::with_at_least(capacity)?;
-        let free_list = FreeList::new(heap.len());
+        let free_list = FreeList::new_growable(heap.len());
         Ok(Self {
             no_gc_count: 0,
             activations_table: Box::new(VMGcRefActivationsTable::default()),
             heap,
             free_list,
+            trace_infos: TraceInfos::new(engine),
+            cycle_candidates: Vec::new(),
+            cycle_collection_threshold: MIN_CYCLE_COLLECTION_THRESHOLD,
+            alloc_failed: false,
         })
     }
 
     fn dealloc(&mut self, gc_ref: VMGcRef) {
+        debug_assert!(!self.is_buffered(&gc_ref));
         let drc_ref = drc_ref(&gc_ref);
         let size = self.index(drc_ref).object_size();
         let layout = FreeList::layout(size);
@@ -188,7 +243,16 @@ impl DrcHeap {
         host_data_table: &mut ExternRefHostDataTable,
         gc_ref: &VMGcRef,
     ) {
+        // Use an explicit work list, rather than recursion, so that dropping
+        // a long linked list doesn't overflow the native stack.
+        let mut worklist = vec![];
         if self.dec_ref(gc_ref) {
+            worklist.push(gc_ref.unchecked_copy());
+        } else if !gc_ref.is_i31() {
+            self.maybe_buffer_cycle_candidate(gc_ref);
+        }
+
+        while let Some(gc_ref) = worklist.pop() {
             // If this was an `externref`, remove its associated entry from
             // the host data table.
             if let Some(externref) = gc_ref.as_typed::<VMDrcExternRef>(self) {
@@ -196,14 +260,246 @@ impl DrcHeap {
                 host_data_table.dealloc(host_data_id);
             }
 
-            // TODO: `dec_ref_and_maybe_dealloc` each `VMGcRef` inside this
-            // object.
+            // Decrement the ref count of each `VMGcRef` inside this object,
+            // and deallocate those that become unreachable as well.
+            let mut children = vec![];
+            self.trace_children(&gc_ref, &mut children);
+            for child in children {
+                if self.dec_ref(&child) {
+                    worklist.push(child);
+                } else {
+                    self.maybe_buffer_cycle_candidate(&child);
+                }
+            }
+
+            // Deallocate this GC object, unless it's a cycle candidate, in
+            // which case the next cycle collection will deallocate it instead
+            // so that the candidates never refer to freed memory.
+            if !self.is_buffered(&gc_ref) {
+                self.dealloc(gc_ref);
+            }
+        }
+    }
+
+    /// Is the given object in `cycle_candidates`?
+    fn is_buffered(&self, gc_ref: &VMGcRef) -> bool {
+        self.header(gc_ref).reserved_u27() & BUFFERED_BIT != 0
+    }
+
+    fn set_buffered(&mut self, gc_ref: &VMGcRef, buffered: bool) {
+        let header = self.header_mut(gc_ref);
+        let bits = header.reserved_u27() & SIZE_MASK;
+        header.set_reserved_u27(if buffered { bits | BUFFERED_BIT } else { bits });
+    }
+
+    /// Add the given object, whose ref count was just decremented without
+    /// reaching zero, to `cycle_candidates` if it could be part of a cycle.
+    fn maybe_buffer_cycle_candidate(&mut self, gc_ref: &VMGcRef) {
+        if self.is_buffered(gc_ref) || !self.may_have_children(gc_ref) {
+            return;
+        }
+        self.set_buffered(gc_ref, true);
+        self.cycle_candidates.push(gc_ref.unchecked_copy());
+    }
 
-            // Deallocate this GC object.
-            self.dealloc(gc_ref.unchecked_copy());
+    /// Can the given object hold references to other objects?
+    ///
+    /// Only such objects can be part of a cycle.
+    fn may_have_children(&self, gc_ref: &VMGcRef) -> bool {
+        let Some(ty) = self.header(gc_ref).ty() else {
+            return false;
+        };
+        match self.trace_infos.get(ty) {
+            TraceInfo::Struct { gc_ref_offsets } => !gc_ref_offsets.is_empty(),
+            TraceInfo::Array { gc_ref_elems } => *gc_ref_elems,
         }
     }
 
+    /// Push each non-null, non-`i31` GC reference inside the given object onto
+    /// `children`.
+    fn trace_children(&self, gc_ref: &VMGcRef, children: &mut Vec<VMGcRef>) {
+        // Only structs and arrays have types, and only they can contain other
+        // GC references.
+        let Some(ty) = self.header(gc_ref).ty() else {
+            return;
+        };
+        let index = gc_ref.as_heap_index().unwrap().get();
+
+        let mut push_field = |offset: u32| {
+            let raw = self.read_field_u32(index + offset);
+            if let Some(child) = VMGcRef::from_raw_u32(raw) {
+                if !child.is_i31() {
+                    children.push(child);
+                }
+            }
+        };
+
+        match self.trace_infos.get(ty) {
+            TraceInfo::Struct { gc_ref_offsets } => {
+                for offset in gc_ref_offsets.iter() {
+                    push_field(*offset);
+                }
+            }
+            TraceInfo::Array { gc_ref_elems: true } => {
+                let len = self
+                    .index::<VMDrcArrayHeader>(gc_ref.as_typed_unchecked())
+                    .length;
+                for i in 0..len {
+                    push_field(ARRAY_GC_REF_ELEMS_OFFSET + i * 4);
+                }
+            }
+            TraceInfo::Array {
+                gc_ref_elems: false,
+            } => {}
+        }
+    }
+
+    /// Read the little-endian `u32` object field at the given heap index.
+    fn read_field_u32(&self, index: u32) -> u32 {
+        let index = usize::try_from(index).unwrap();
+        let bytes = &self.heap_slice()[index..][..4];
+        // Safety: we are not racing with Wasm, which is the only thing that
+        // writes to the heap through the `UnsafeCell`s.
+        u32::from_le_bytes(core::array::from_fn(|i| unsafe { *bytes[i].get() }))
+    }
+
+    /// Get the given object's current ref count.
+    fn ref_count(&self, gc_ref: &VMGcRef) -> u64 {
+        // Safety: same as in `read_field_u32`.
+        unsafe { *self.index(drc_ref(gc_ref)).ref_count.get() }
+    }
+
+    /// Should this collection also run the cycle collector?
+    fn should_collect_cycles(&self) -> bool {
+        (self.alloc_failed && !self.cycle_candidates.is_empty())
+            || self.cycle_candidates.len() > self.cycle_collection_threshold
+    }
+
+    /// Find and deallocate garbage cycles via trial deletion.
+    ///
+    /// Every garbage cycle contains an object whose ref count was decremented
+    /// without reaching zero, when the last reference to the cycle from
+    /// outside of it was removed, so we only consider the objects reachable
+    /// from the cycle candidates. An object's ref count is the number of
+    /// references to it from other objects in the heap plus the number of
+    /// references to it from outside the heap: globals, tables, the
+    /// activations table, and host roots. We subtract every reference from
+    /// inside the considered objects, and whatever objects still have a
+    /// nonzero count are definitely held from elsewhere. Everything
+    /// transitively reachable from those objects is live, and everything else
+    /// is unreachable garbage whose ref counts are being kept nonzero only by
+    /// cycles.
+    ///
+    /// This must be run after `sweep`, so that the activations table holds
+    /// exactly the precise set of on-stack roots.
+    fn collect_cycles(&mut self, host_data_table: &mut ExternRefHostDataTable) {
+        log::trace!(
+            "Begin DRC cycle collection from {} candidates",
+            self.cycle_candidates.len()
+        );
+
+        // Candidates whose ref count has since reached zero have already
+        // released their references, and were only kept allocated because
+        // they were candidates, so deallocate them now.
+        let mut worklist = vec![];
+        for gc_ref in mem::take(&mut self.cycle_candidates) {
+            self.set_buffered(&gc_ref, false);
+            if self.ref_count(&gc_ref) == 0 {
+                self.dealloc(gc_ref);
+            } else {
+                worklist.push(gc_ref);
+            }
+        }
+
+        // Find the structs and arrays reachable from the remaining candidates,
+        // along with their ref counts. `externref`s can't be part of a cycle,
+        // so they're left out.
+        let mut external_counts: crate::hash_map::HashMap<VMGcRef, u64> = Default::default();
+        let mut children = vec![];
+        while let Some(gc_ref) = worklist.pop() {
+            if external_counts.contains_key(&gc_ref) {
+                continue;
+            }
+            external_counts.insert(gc_ref.unchecked_copy(), self.ref_count(&gc_ref));
+            self.trace_children(&gc_ref, &mut children);
+            worklist.extend(
+                children
+                    .drain(..)
+                    .filter(|c| self.header(c).ty().is_some() && !external_counts.contains_key(c)),
+            );
+        }
+
+        // Subtract the references from inside those objects from each of
+        // their ref counts.
+        let objects: Vec<VMGcRef> = external_counts
+            .keys()
+            .map(|gc_ref| gc_ref.unchecked_copy())
+            .collect();
+        for gc_ref in objects.iter() {
+            self.trace_children(gc_ref, &mut children);
+            for child in children.drain(..) {
+                if let Some(count) = external_counts.get_mut(&child) {
+                    *count = count.saturating_sub(1);
+                }
+            }
+        }
+
+        // Mark everything reachable from objects that are referenced from
+        // elsewhere.
+        let mut live: HashSet<VMGcRef> = HashSet::default();
+        let mut worklist: Vec<VMGcRef> = external_counts
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(gc_ref, _)| gc_ref.unchecked_copy())
+            .collect();
+        while let Some(gc_ref) = worklist.pop() {
+            if !live.insert(gc_ref.unchecked_copy()) {
+                continue;
+            }
+            self.trace_children(&gc_ref, &mut children);
+            worklist.extend(
+                children
+                    .drain(..)
+                    .filter(|c| external_counts.contains_key(c) && !live.contains(c)),
+            );
+        }
+
+        // Everything else is garbage.
+        let garbage: HashSet<VMGcRef> = objects
+            .into_iter()
+            .filter(|gc_ref| !live.contains(gc_ref))
+            .collect();
+        log::trace!("Found {} objects in garbage cycles", garbage.len());
+
+        // Release the garbage's references to things that aren't themselves
+        // garbage (i.e. `externref`s and live objects) and then deallocate the
+        // garbage, without bothering to maintain ref counts within the garbage
+        // set.
+        for gc_ref in garbage.iter() {
+            self.trace_children(gc_ref, &mut children);
+            for child in mem::take(&mut children) {
+                if !garbage.contains(&child) {
+                    self.dec_ref_and_maybe_dealloc(host_data_table, &child);
+                }
+            }
+        }
+
+        // Deallocate in address order, so that adjacent blocks coalesce as we
+        // go and the free list stays small. Releasing references above only
+        // ever decrements the ref counts of objects outside the garbage, so
+        // none of it has been deallocated or become a candidate again.
+        let mut garbage: Vec<VMGcRef> = garbage.into_iter().collect();
+        garbage.sort_unstable_by_key(|gc_ref| gc_ref.as_raw_u32());
+        for gc_ref in garbage {
+            self.dealloc(gc_ref);
+        }
+
+        self.alloc_failed = false;
+        self.cycle_collection_threshold =
+            core::cmp::max(MIN_CYCLE_COLLECTION_THRESHOLD, 2 * live.len());
+        log::trace!("End DRC cycle collection");
+    }
+
     fn trace(&mut self, roots: &mut GcRootsIter<'_>) {
         debug_assert!({
             // This set is only non-empty during collection. It is built up when
@@ -431,7 +727,7 @@ impl VMDrcHeader {
     ///
     /// This is stored in the inner `VMGcHeader`'s reserved bits.
     fn object_size(&self) -> usize {
-        let size = self.header.reserved_u27();
+        let size = self.header.reserved_u27() & SIZE_MASK;
         usize::try_from(size).unwrap()
     }
 }
@@ -540,7 +836,7 @@ unsafe impl GcHeap for DrcHeap {
     }
 
     fn object_size(&self, gc_ref: &VMGcRef) -> usize {
-        let size = self.header(gc_ref).reserved_u27();
+        let size = self.header(gc_ref).reserved_u27() & SIZE_MASK;
         usize::try_from(size).unwrap()
     }
 
@@ -548,16 +844,32 @@ unsafe impl GcHeap for DrcHeap {
         debug_assert!(layout.size() >= core::mem::size_of::<VMDrcHeader>());
         debug_assert!(layout.align() >= core::mem::align_of::<VMDrcHeader>());
 
-        let size = u32::try_from(layout.size()).unwrap();
-        if !VMGcKind::value_fits_in_unused_bits(size) {
-            return Err(crate::Trap::AllocationTooLarge.into());
-        }
+        // Make sure that the requested allocation's size fits in the GC
+        // header's unused bits, minus the one we use for `BUFFERED_BIT`.
+        let size = match u32::try_from(layout.size())
+            .ok()
+            .filter(|size| size & SIZE_MASK == *size)
+        {
+            Some(size) => size,
+            None => return Err(crate::Trap::AllocationTooLarge.into()),
+        };
 
         let gc_ref = match self.free_list.alloc(layout)? {
-            None => return Ok(None),
+            None => {
+                self.alloc_failed = true;
+                return Ok(None);
+            }
             Some(index) => VMGcRef::from_heap_index(index).unwrap(),
         };
 
+        if let Some(ty) = header.ty() {
+            debug_assert!(matches!(
+                header.kind(),
+                VMGcKind::StructRef | VMGcKind::ArrayRef
+            ));
+            self.trace_infos.ensure(ty);
+        }
+
         debug_assert_eq!(header.reserved_u27(), 0);
         header.set_reserved_u27(size);
 
@@ -678,12 +990,28 @@ unsafe impl GcHeap for DrcHeap {
             no_gc_count,
             activations_table,
             free_list,
-            heap: _,
+            heap,
+            trace_infos,
+            cycle_candidates,
+            cycle_collection_threshold,
+            alloc_failed,
         } = self;
 
         *no_gc_count = 0;
-        free_list.reset();
+        *free_list = FreeList::new_growable(heap.len());
         activations_table.reset();
+        trace_infos.clear();
+        cycle_candidates.clear();
+        *cycle_collection_threshold = MIN_CYCLE_COLLECTION_THRESHOLD;
+        *alloc_failed = false;
+    }
+
+    fn size(&self) -> usize {
+        self.free_list.capacity()
+    }
+
+    fn grow(&mut self, new_size: usize) {
+        self.free_list.grow(new_size);
     }
 
     fn heap_slice(&self) -> &[UnsafeCell<u8>] {
@@ -709,6 +1037,7 @@ struct DrcCollection<'a> {
 enum DrcCollectionPhase {
     Trace,
     Sweep,
+    CollectCycles,
     Done,
 }
 
@@ -726,6 +1055,16 @@ impl<'a> GarbageCollection<'a> for DrcCollection<'a> {
                 log::trace!("Begin DRC sweep");
                 self.heap.sweep(self.host_data_table);
                 log::trace!("End DRC sweep");
+                if self.heap.should_collect_cycles() {
+                    self.phase = DrcCollectionPhase::CollectCycles;
+                    GcProgress::Continue
+                } else {
+                    self.phase = DrcCollectionPhase::Done;
+                    GcProgress::Complete
+                }
+            }
+            DrcCollectionPhase::CollectCycles => {
+                self.heap.collect_cycles(self.host_data_table);
                 self.phase = DrcCollectionPhase::Done;
                 GcProgress::Complete
             }
@@ -868,6 +1207,11 @@ impl VMGcRefActivationsTable {
             precise_stack_roots,
         } = self;
 
+        // Clear out any roots left behind by the previous store, since only
+        // the slots before the bump pointer are swept.
+        for slot in alloc.chunk.iter_mut() {
+            *slot.get_mut() = 0;
+        }
         alloc.reset();
         over_approximated_stack_roots.clear();
         precise_stack_roots.clear();
@@ -985,7 +1329,10 @@ impl<T> DerefMut for DebugOnly<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use wasmtime_environ::HostPtr;
+    use wasmtime_environ::{
+        HostPtr, WasmArrayType, WasmFieldType, WasmHeapType, WasmRefType, WasmStorageType,
+        WasmValType,
+    };
 
     #[test]
     fn vm_drc_header_size_align() {
@@ -1007,6 +1354,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn vm_drc_array_gc_ref_elems_offset() {
+        let ty = WasmArrayType(WasmFieldType {
+            element_type: WasmStorageType::Val(WasmValType::Ref(WasmRefType {
+                nullable: true,
+                heap_type: WasmHeapType::Any,
+            })),
+            mutable: true,
+        });
+        let layout = DrcTypeLayouts::default().array_layout(&ty);
+        assert_eq!(layout.base_size, ARRAY_GC_REF_ELEMS_OFFSET);
+        assert_eq!(layout.elem_size, 4);
+    }
+
     #[test]
     fn ref_count_is_at_correct_offset() {
         let extern_data = VMDrcHeader {
@@ -1030,6 +1391,7 @@ mod tests {
             num_owned_memories: 0,
             num_defined_globals: 0,
             num_escaped_funcs: 0,
+            num_fuel_profile_counters: 0,
         });
 
         assert_eq!(
@@ -1058,6 +1420,7 @@ mod tests {
             num_owned_memories: 0,
             num_defined_globals: 0,
             num_escaped_funcs: 0,
+            num_fuel_profile_counters: 0,
         });
         assert_eq!(
             offsets.vm_gc_ref_activation_table_next() as usize,
@@ -1085,6 +1448,7 @@ mod tests {
             num_owned_memories: 0,
             num_defined_globals: 0,
             num_escaped_funcs: 0,
+            num_fuel_profile_counters: 0,
         });
         assert_eq!(
             offsets.vm_gc_ref_activation_table_end() as usize,
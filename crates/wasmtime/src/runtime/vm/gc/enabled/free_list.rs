@@ -7,6 +7,8 @@ use core::{alloc::Layout, num::NonZeroU32, ops::Bound};
 pub(crate) struct FreeList {
     /// The total capacity of the contiguous range of memory we are managing.
     capacity: usize,
+    /// The capacity that this free list may grow to.
+    max_capacity: usize,
     /// Our free blocks, as a map from index to length of the free block at that
     /// index.
     free_block_index_to_len: BTreeMap<u32, u32>,
@@ -29,17 +31,47 @@ impl FreeList {
 
     /// Create a new `FreeList` for a contiguous region of memory of the given
     /// size.
+    #[cfg(test)]
     pub fn new(capacity: usize) -> Self {
-        let mut free_list = FreeList {
-            capacity,
-            free_block_index_to_len: BTreeMap::new(),
-        };
-        free_list.reset();
+        let mut free_list = FreeList::new_growable(capacity);
+        free_list.grow(capacity);
         free_list
     }
 
+    /// Create a new, empty `FreeList` which may be grown to manage a
+    /// contiguous region of memory of up to the given size.
+    pub fn new_growable(max_capacity: usize) -> Self {
+        FreeList {
+            capacity: 0,
+            max_capacity,
+            free_block_index_to_len: BTreeMap::new(),
+        }
+    }
+
+    /// Grow the range of memory we are managing to the given size, making the
+    /// new space at its end available for allocation.
+    pub fn grow(&mut self, new_capacity: usize) {
+        assert!(self.capacity <= new_capacity && new_capacity <= self.max_capacity);
+
+        // Don't start at `0`, so that a valid allocation is never null.
+        let start = round_u32_down_to_pow2(clamp_to_u32(self.capacity), ALIGN_U32).max(ALIGN_U32);
+        let end = round_u32_down_to_pow2(clamp_to_u32(new_capacity), ALIGN_U32);
+        self.capacity = new_capacity;
+        if end.saturating_sub(start) >= MIN_BLOCK_SIZE {
+            self.insert_free_block(start, end - start);
+        }
+
+        #[cfg(debug_assertions)]
+        self.check_integrity();
+    }
+
+    /// The size of the range of memory we are currently managing.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     fn max_size(&self) -> usize {
-        let cap = cmp::min(self.capacity, usize::try_from(u32::MAX).unwrap());
+        let cap = cmp::min(self.max_capacity, usize::try_from(u32::MAX).unwrap());
         round_usize_down_to_pow2(cap.saturating_sub(ALIGN_USIZE), ALIGN_USIZE)
     }
 
@@ -161,6 +193,17 @@ impl FreeList {
         let alloc_size = self.check_layout(layout).unwrap();
         debug_assert_eq!(alloc_size % ALIGN_U32, 0);
 
+        self.insert_free_block(index, alloc_size);
+
+        // After we've added to/mutated the free list, double check its
+        // integrity.
+        #[cfg(debug_assertions)]
+        self.check_integrity();
+    }
+
+    /// Add the block of `alloc_size` bytes at `index` to the free list,
+    /// merging it with its neighbors if they are contiguous.
+    fn insert_free_block(&mut self, index: u32, alloc_size: u32) {
         let prev_block = self
             .free_block_index_to_len
             .range((Bound::Unbounded, Bound::Excluded(index)))
@@ -213,11 +256,6 @@ impl FreeList {
                 self.free_block_index_to_len.insert(index, alloc_size);
             }
         }
-
-        // After we've added to/mutated the free list, double check its
-        // integrity.
-        #[cfg(debug_assertions)]
-        self.check_integrity();
     }
 
     /// Assert that the free list is valid:
@@ -256,30 +294,14 @@ impl FreeList {
             prev_end = Some(end);
         }
     }
+}
 
-    /// Reset this free list, making the whole range available for allocation.
-    pub fn reset(&mut self) {
-        let end = u32::try_from(self.capacity).unwrap_or_else(|_| {
-            assert!(self.capacity > usize::try_from(u32::MAX).unwrap());
-            u32::MAX
-        });
-
-        // Don't start at `0`. Reserve that for "null pointers" and this way we
-        // can use `NonZeroU32` as out pointer type, giving us some more
-        // bitpacking opportunities.
-        let start = ALIGN_U32;
-
-        let len = round_u32_down_to_pow2(end.saturating_sub(start), ALIGN_U32);
-
-        let entire_range = if len >= MIN_BLOCK_SIZE {
-            Some((start, len))
-        } else {
-            None
-        };
-
-        self.free_block_index_to_len.clear();
-        self.free_block_index_to_len.extend(entire_range);
-    }
+#[inline]
+fn clamp_to_u32(capacity: usize) -> u32 {
+    u32::try_from(capacity).unwrap_or_else(|_| {
+        assert!(capacity > usize::try_from(u32::MAX).unwrap());
+        u32::MAX
+    })
 }
 
 #[inline]
@@ -773,4 +795,40 @@ mod tests {
             )
             .is_err());
     }
+
+    #[test]
+    fn grow_merges_with_trailing_free_block() {
+        let min = usize::try_from(MIN_BLOCK_SIZE).unwrap();
+        let layout = Layout::from_size_align(min, ALIGN_USIZE).unwrap();
+
+        // A growable free list has nothing to allocate until it is grown, but
+        // still accepts layouts up to its maximum capacity.
+        let mut free_list = FreeList::new_growable(ALIGN_USIZE + min * 4);
+        assert_eq!(free_list.max_size(), min * 4);
+        assert!(free_list.alloc(layout).unwrap().is_none());
+
+        // Grow it to fit two blocks and allocate one of them.
+        free_list.grow(ALIGN_USIZE + min * 2);
+        let a = free_list.alloc(layout).unwrap().unwrap();
+        assert_eq!(free_list.free_block_index_to_len.len(), 1);
+
+        // Growing it again extends the remaining free block rather than adding
+        // a new one.
+        free_list.grow(ALIGN_USIZE + min * 4);
+        assert_eq!(free_list.free_block_index_to_len.len(), 1);
+        assert_eq!(
+            free_list
+                .free_block_index_to_len
+                .get(&(a.get() + MIN_BLOCK_SIZE)),
+            Some(&(MIN_BLOCK_SIZE * 3))
+        );
+
+        // And the whole range merges back together once `a` is freed.
+        free_list.dealloc(a, layout);
+        assert_eq!(free_list.free_block_index_to_len.len(), 1);
+        assert_eq!(
+            free_list.free_block_index_to_len.get(&ALIGN_U32),
+            Some(&(MIN_BLOCK_SIZE * 4))
+        );
+    }
 }
@@ -1,8 +1,9 @@
 //! The null collector.
 //!
 //! The null collector bump allocates objects until it runs out of space, at
-//! which point it returns an out-of-memory error. It never collects garbage.
-//! It does not require any GC barriers.
+//! which point it grows the heap if the store's resource limiter allows, and
+//! otherwise returns an out-of-memory error. It never collects garbage. It
+//! does not require any GC barriers.
 
 use super::*;
 use crate::{
@@ -36,7 +37,7 @@ unsafe impl GcRuntime for NullCollector {
         &self.layouts
     }
 
-    fn new_gc_heap(&self) -> Result<Box<dyn GcHeap>> {
+    fn new_gc_heap(&self, _engine: &crate::Engine) -> Result<Box<dyn GcHeap>> {
         let heap = NullHeap::new()?;
         Ok(Box::new(heap) as _)
     }
@@ -45,11 +46,11 @@ unsafe impl GcRuntime for NullCollector {
 /// A GC heap for the null collector.
 #[repr(C)]
 struct NullHeap {
-    /// Bump-allocation finger indexing within `1..self.heap.len()`.
+    /// The bump-allocation state, which is shared with compiled Wasm code.
     ///
     /// NB: this is an `UnsafeCell` because it is written to by compiled Wasm
     /// code.
-    next: SendSyncUnsafeCell<NonZeroU32>,
+    data: SendSyncUnsafeCell<VMNullHeapData>,
 
     /// The number of active no-gc scopes at the current moment.
     no_gc_count: usize,
@@ -58,6 +59,18 @@ struct NullHeap {
     heap: Mmap<AlignedLength>,
 }
 
+/// The data that compiled Wasm code accesses through
+/// `VMContext::gc_heap_data` to allocate inline.
+#[repr(C)]
+struct VMNullHeapData {
+    /// Bump-allocation finger indexing within `1..self.limit`.
+    next: NonZeroU32,
+
+    /// The end of the portion of the heap that objects may currently be
+    /// allocated within, which is at most the heap's length.
+    limit: u32,
+}
+
 /// The common header for all arrays in the null collector.
 #[repr(C)]
 struct VMNullArrayHeader {
@@ -125,12 +138,15 @@ impl NullHeap {
         let heap = Mmap::with_at_least(capacity)?;
         Ok(Self {
             no_gc_count: 0,
-            next: SendSyncUnsafeCell::new(NonZeroU32::new(1).unwrap()),
+            data: SendSyncUnsafeCell::new(VMNullHeapData {
+                next: NonZeroU32::new(1).unwrap(),
+                limit: 0,
+            }),
             heap,
         })
     }
 
-    fn alloc(&mut self, mut header: VMGcHeader, layout: Layout) -> Result<VMGcRef> {
+    fn alloc(&mut self, mut header: VMGcHeader, layout: Layout) -> Result<Option<VMGcRef>> {
         debug_assert!(layout.size() >= core::mem::size_of::<VMGcHeader>());
         debug_assert!(layout.align() >= core::mem::align_of::<VMGcHeader>());
 
@@ -147,7 +163,7 @@ impl NullHeap {
             None => return Err(crate::Trap::AllocationTooLarge.into()),
         };
 
-        let next = *self.next.get_mut();
+        let VMNullHeapData { next, limit } = *self.data.get_mut();
 
         // Increment the bump pointer to the layout's requested alignment.
         let aligned = match u32::try_from(layout.align())
@@ -158,20 +174,22 @@ impl NullHeap {
             None => return Err(oom()),
         };
 
-        // Check whether the allocation fits in the heap space we have left.
+        // Check whether the allocation fits in the heap space we have left, and
+        // if it doesn't but would fit after growing the heap, ask our caller to
+        // do that.
         let end_of_object = match aligned.checked_add(size) {
             Some(end) => end,
             None => return Err(oom()),
         };
-        if u32::try_from(self.heap.len())
-            .ok()
-            .map_or(true, |heap_len| end_of_object > heap_len)
-        {
-            return Err(oom());
+        if end_of_object > limit {
+            if end_of_object > Self::max_limit(&self.heap) {
+                return Err(oom());
+            }
+            return Ok(None);
         }
 
         // Update the bump pointer, write the header, and return the GC ref.
-        *self.next.get_mut() = NonZeroU32::new(end_of_object).unwrap();
+        self.data.get_mut().next = NonZeroU32::new(end_of_object).unwrap();
 
         let aligned = NonZeroU32::new(aligned).unwrap();
         let gc_ref = VMGcRef::from_heap_index(aligned).unwrap();
@@ -180,7 +198,13 @@ impl NullHeap {
         header.set_reserved_u27(size);
         *self.header_mut(&gc_ref) = header;
 
-        Ok(gc_ref)
+        Ok(Some(gc_ref))
+    }
+
+    /// The largest that `VMNullHeapData::limit` may grow to for the given
+    /// heap.
+    fn max_limit(heap: &Mmap<AlignedLength>) -> u32 {
+        u32::try_from(heap.len()).unwrap_or(u32::MAX)
     }
 }
 
@@ -201,6 +225,22 @@ unsafe impl GcHeap for NullHeap {
         self.no_gc_count -= 1;
     }
 
+    fn size(&self) -> usize {
+        // SAFETY: compiled Wasm code only writes to `next`, and can't be
+        // running while we are borrowed.
+        let limit = unsafe { (*self.data.get()).limit };
+        usize::try_from(limit).unwrap()
+    }
+
+    fn grow(&mut self, new_size: usize) {
+        let new_limit = u32::try_from(new_size)
+            .unwrap_or(u32::MAX)
+            .min(Self::max_limit(&self.heap));
+        let data = self.data.get_mut();
+        debug_assert!(new_limit >= data.limit);
+        data.limit = new_limit;
+    }
+
     fn heap_slice(&self) -> &[UnsafeCell<u8>] {
         let ptr = self.heap.as_ptr().cast();
         let len = self.heap.len();
@@ -236,7 +276,10 @@ unsafe impl GcHeap for NullHeap {
     }
 
     fn alloc_externref(&mut self, host_data: ExternRefHostDataId) -> Result<Option<VMExternRef>> {
-        let gc_ref = self.alloc(VMGcHeader::externref(), Layout::new::<VMNullExternRef>())?;
+        let gc_ref = match self.alloc(VMGcHeader::externref(), Layout::new::<VMNullExternRef>())? {
+            Some(r) => r,
+            None => return Ok(None),
+        };
         self.index_mut::<VMNullExternRef>(gc_ref.as_typed_unchecked())
             .host_data = host_data;
         Ok(Some(gc_ref.into_externref_unchecked()))
@@ -261,7 +304,7 @@ unsafe impl GcHeap for NullHeap {
     }
 
     fn alloc_raw(&mut self, header: VMGcHeader, layout: Layout) -> Result<Option<VMGcRef>> {
-        self.alloc(header, layout).map(Some)
+        self.alloc(header, layout)
     }
 
     fn alloc_uninit_struct(
@@ -273,7 +316,7 @@ unsafe impl GcHeap for NullHeap {
             VMGcHeader::from_kind_and_index(VMGcKind::StructRef, ty),
             layout.layout(),
         )?;
-        Ok(Some(gc_ref.into_structref_unchecked()))
+        Ok(gc_ref.map(|r| r.into_structref_unchecked()))
     }
 
     fn dealloc_uninit_struct(&mut self, _struct_ref: VMStructRef) {}
@@ -284,10 +327,13 @@ unsafe impl GcHeap for NullHeap {
         length: u32,
         layout: &GcArrayLayout,
     ) -> Result<Option<VMArrayRef>> {
-        let gc_ref = self.alloc(
+        let gc_ref = match self.alloc(
             VMGcHeader::from_kind_and_index(VMGcKind::ArrayRef, ty),
             layout.layout(length),
-        )?;
+        )? {
+            Some(r) => r,
+            None => return Ok(None),
+        };
         self.index_mut::<VMNullArrayHeader>(gc_ref.as_typed_unchecked())
             .length = length;
         Ok(Some(gc_ref.into_arrayref_unchecked()))
@@ -310,18 +356,21 @@ unsafe impl GcHeap for NullHeap {
     }
 
     unsafe fn vmctx_gc_heap_data(&self) -> *mut u8 {
-        self.next.get().cast()
+        self.data.get().cast()
     }
 
     #[cfg(feature = "pooling-allocator")]
     fn reset(&mut self) {
         let NullHeap {
-            next,
+            data,
             no_gc_count,
             heap: _,
         } = self;
 
-        *next.get_mut() = NonZeroU32::new(1).unwrap();
+        *data.get_mut() = VMNullHeapData {
+            next: NonZeroU32::new(1).unwrap(),
+            limit: 0,
+        };
         *no_gc_count = 0;
     }
 }
@@ -350,6 +399,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn vm_null_heap_data_offsets() {
+        assert_eq!(
+            wasmtime_environ::null::HEAP_DATA_NEXT_OFFSET,
+            u32::try_from(core::mem::offset_of!(VMNullHeapData, next)).unwrap(),
+        );
+        assert_eq!(
+            wasmtime_environ::null::HEAP_DATA_LIMIT_OFFSET,
+            u32::try_from(core::mem::offset_of!(VMNullHeapData, limit)).unwrap(),
+        );
+    }
+
     #[test]
     fn vm_null_array_header_length_offset() {
         assert_eq!(
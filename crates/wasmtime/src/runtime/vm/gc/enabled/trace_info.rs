@@ -0,0 +1,113 @@
+//! Tracing information for GC objects.
+//!
+//! Tracing collectors need to know where the GC references inside each object
+//! are. That information is derived from the object's type, which lives in the
+//! engine's type registry, so we compute it once per type and cache it inside
+//! the GC heap.
+
+use crate::hash_map::HashMap;
+use crate::prelude::*;
+use crate::{Engine, EngineWeak};
+use wasmtime_environ::{GcLayout, VMSharedTypeIndex, WasmCompositeInnerType, WasmStorageType};
+
+/// Where the GC references inside an object of a particular type are.
+#[derive(Debug)]
+pub(super) enum TraceInfo {
+    /// A struct type.
+    Struct {
+        /// The offsets of each field that holds a GC reference.
+        gc_ref_offsets: Box<[u32]>,
+    },
+
+    /// An array type.
+    Array {
+        /// Whether this array's elements are GC references.
+        gc_ref_elems: bool,
+    },
+}
+
+/// A cache of `TraceInfo`s for the types of objects allocated in a GC heap.
+pub(super) struct TraceInfos {
+    engine: EngineWeak,
+    infos: HashMap<VMSharedTypeIndex, TraceInfo>,
+}
+
+impl TraceInfos {
+    /// Create a new, empty cache for types registered in the given engine.
+    pub fn new(engine: &Engine) -> Self {
+        Self {
+            engine: engine.weak(),
+            infos: HashMap::default(),
+        }
+    }
+
+    /// Ensure that the trace info for the given struct or array type is in
+    /// this cache.
+    ///
+    /// This must be called when allocating an object of the given type, while
+    /// the type is still known to be registered.
+    pub fn ensure(&mut self, ty: VMSharedTypeIndex) {
+        if self.infos.contains_key(&ty) {
+            return;
+        }
+
+        let engine = self
+            .engine
+            .upgrade()
+            .expect("engine should outlive its GC heaps");
+        let sub_ty = engine
+            .signatures()
+            .borrow(ty)
+            .expect("should have a registered type for allocated object");
+        let layout = engine
+            .signatures()
+            .layout(ty)
+            .expect("should have a GC layout for allocated object");
+
+        let info = match (&sub_ty.composite_type.inner, layout) {
+            (WasmCompositeInnerType::Struct(s), GcLayout::Struct(layout)) => TraceInfo::Struct {
+                gc_ref_offsets: s
+                    .fields
+                    .iter()
+                    .zip(layout.fields.iter().copied())
+                    .filter(|(f, _)| is_gc_ref(&f.element_type))
+                    .map(|(_, offset)| offset)
+                    .collect(),
+            },
+            (WasmCompositeInnerType::Array(a), GcLayout::Array(_)) => TraceInfo::Array {
+                gc_ref_elems: is_gc_ref(&a.0.element_type),
+            },
+            (ty, layout) => unreachable!("bad GC type and layout: {ty:?} / {layout:?}"),
+        };
+
+        let old = self.infos.insert(ty, info);
+        debug_assert!(old.is_none());
+    }
+
+    /// Get the trace info for the given type.
+    ///
+    /// Panics if `ensure` was not called for this type first.
+    pub fn get(&self, ty: VMSharedTypeIndex) -> &TraceInfo {
+        &self.infos[&ty]
+    }
+
+    /// Forget all cached trace infos.
+    ///
+    /// Used when the heap is reset and reused with a new store, whose types may
+    /// have been registered at different indices.
+    #[cfg_attr(not(feature = "pooling-allocator"), allow(dead_code))]
+    pub fn clear(&mut self) {
+        self.infos.clear();
+    }
+}
+
+/// Does the given storage type hold a reference into the GC heap?
+///
+/// Note that `funcref`s are stored as `FuncRefTableId`s, not as GC references,
+/// so they aren't traced.
+fn is_gc_ref(ty: &WasmStorageType) -> bool {
+    match ty {
+        WasmStorageType::I8 | WasmStorageType::I16 => false,
+        WasmStorageType::Val(v) => v.is_vmgcref_type(),
+    }
+}
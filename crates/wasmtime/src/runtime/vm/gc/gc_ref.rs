@@ -90,7 +90,7 @@ impl VMGcHeader {
             VMGcKind::value_fits_in_unused_bits(value),
             "VMGcHeader::set_reserved_u26 with value using more than 26 bits"
         );
-        self.kind |= value;
+        self.kind = (self.kind & VMGcKind::MASK) | value;
     }
 
     /// Set the 27-bit reserved value.
@@ -35,8 +35,8 @@ pub unsafe trait GcRuntime: 'static + Send + Sync {
     /// Get this collector's GC type layouts.
     fn layouts(&self) -> &dyn GcTypeLayouts;
 
-    /// Construct a new GC heap.
-    fn new_gc_heap(&self) -> Result<Box<dyn GcHeap>>;
+    /// Construct a new GC heap for use with stores of the given engine.
+    fn new_gc_heap(&self, engine: &crate::Engine) -> Result<Box<dyn GcHeap>>;
 }
 
 /// A heap that manages garbage-collected objects.
@@ -181,7 +181,7 @@ pub unsafe trait GcHeap: 'static + Send + Sync {
     /// * `Ok(None)`: There is currently no available space for this
     ///   allocation. The caller should call `self.gc()`, run the GC to
     ///   completion so the collector can reclaim space, and then try allocating
-    ///   again.
+    ///   again, growing the heap with `self.grow()` if that also fails.
     ///
     /// * `Err(_)`: The collector cannot satisfy this allocation request, and
     ///   would not be able to even after the caller were to trigger a
@@ -236,7 +236,7 @@ pub unsafe trait GcHeap: 'static + Send + Sync {
     /// * `Ok(None)`: There is currently no available space for this
     ///   allocation. The caller should call `self.gc()`, run the GC to
     ///   completion so the collector can reclaim space, and then try allocating
-    ///   again.
+    ///   again, growing the heap with `self.grow()` if that also fails.
     ///
     /// * `Err(_)`: The collector cannot satisfy this allocation request, and
     ///   would not be able to even after the caller were to trigger a
@@ -262,7 +262,7 @@ pub unsafe trait GcHeap: 'static + Send + Sync {
     /// * `Ok(None)`: There is currently no available space for this
     ///   allocation. The caller should call `self.gc()`, run the GC to
     ///   completion so the collector can reclaim space, and then try allocating
-    ///   again.
+    ///   again, growing the heap with `self.grow()` if that also fails.
     ///
     /// * `Err(_)`: The collector cannot satisfy this allocation request, and
     ///   would not be able to even after the caller were to trigger a
@@ -288,7 +288,7 @@ pub unsafe trait GcHeap: 'static + Send + Sync {
     /// * `Ok(None)`: There is currently no available space for this
     ///   allocation. The caller should call `self.gc()`, run the GC to
     ///   completion so the collector can reclaim space, and then try allocating
-    ///   again.
+    ///   again, growing the heap with `self.grow()` if that also fails.
     ///
     /// * `Err(_)`: The collector cannot satisfy this allocation request, and
     ///   would not be able to even after the caller were to trigger a
@@ -375,6 +375,28 @@ pub unsafe trait GcHeap: 'static + Send + Sync {
     #[cfg(feature = "pooling-allocator")]
     fn reset(&mut self);
 
+    ////////////////////////////////////////////////////////////////////////////
+    // Growing the GC Heap
+
+    /// Get the number of bytes of this heap that objects may currently be
+    /// allocated within.
+    ///
+    /// This starts out at zero, is only increased by `self.grow()`, and is
+    /// never larger than `self.heap_slice().len()`.
+    fn size(&self) -> usize;
+
+    /// Grow the portion of this heap that objects may be allocated within to
+    /// `new_size` bytes.
+    ///
+    /// Callers must ensure that `new_size` is greater than `self.size()`, and
+    /// implementations may round it down to their own granularity and clamp
+    /// it to the largest size that they support. Objects that are already
+    /// allocated don't move.
+    ///
+    /// When an allocation method returns `Ok(None)` and collecting garbage
+    /// doesn't make room, callers may grow the heap and try again.
+    fn grow(&mut self, new_size: usize);
+
     ////////////////////////////////////////////////////////////////////////////
     // Accessors for the raw bytes of the GC heap
 
@@ -23,7 +23,7 @@ use core::any::Any;
 use core::ops::Range;
 use core::ptr::NonNull;
 use core::sync::atomic::AtomicU64;
-use core::{mem, ptr};
+use core::{mem, ptr, slice};
 use sptr::Strict;
 use wasmtime_environ::{
     packed_option::ReservedValue, DataIndex, DefinedGlobalIndex, DefinedMemoryIndex,
@@ -1418,6 +1418,25 @@ impl Instance {
         for (index, _init) in module.global_initializers.iter() {
             ptr::write(self.global_ptr(index), VMGlobalDefinition::new());
         }
+
+        // Fuel profile counters all start at zero.
+        ptr::write_bytes(
+            self.vmctx_plus_offset_mut::<u64>(offsets.vmctx_fuel_profile_begin()),
+            0,
+            module.num_fuel_profile_counters,
+        );
+    }
+
+    /// Returns the fuel consumed by each defined function, as counted when
+    /// fuel profiling is enabled.
+    pub(crate) fn fuel_profile(&self) -> &[u64] {
+        let offsets = self.offsets();
+        unsafe {
+            slice::from_raw_parts(
+                self.vmctx_plus_offset::<u64>(offsets.vmctx_fuel_profile_begin()),
+                self.env_module().num_fuel_profile_counters,
+            )
+        }
     }
 
     fn wasm_fault(&self, addr: usize) -> Option<WasmFault> {
@@ -5,9 +5,13 @@ use crate::runtime::vm::instance::{Instance, InstanceHandle};
 use crate::runtime::vm::memory::Memory;
 use crate::runtime::vm::mpk::ProtectionKey;
 use crate::runtime::vm::table::Table;
-use crate::runtime::vm::{CompiledModuleId, ModuleRuntimeInfo, VMFuncRef, VMGcRef, VMStore};
+use crate::runtime::vm::{
+    CompiledModuleId, MemoryImage, ModuleMemoryImages, ModuleRuntimeInfo, VMFuncRef, VMGcRef,
+    VMStore,
+};
 use crate::store::{AutoAssertNoGc, StoreOpaque};
 use crate::vm::VMGlobalDefinition;
+use alloc::sync::{Arc, Weak};
 use core::ptr::NonNull;
 use core::{any::Any, mem, ptr};
 use wasmtime_environ::{
@@ -33,7 +37,7 @@ mod pooling;
 #[cfg(feature = "pooling-allocator")]
 pub use self::pooling::{
     InstanceLimits, PoolConcurrencyLimitError, PoolingInstanceAllocator,
-    PoolingInstanceAllocatorConfig,
+    PoolingInstanceAllocatorConfig, ScrubPolicy,
 };
 
 /// Represents a request for a new runtime instance.
@@ -78,6 +82,42 @@ pub struct InstanceAllocationRequest<'a> {
 
     /// Tunable configuration options the engine is using.
     pub tunables: &'a Tunables,
+
+    /// Saved memory state to restore into the instance's defined memories,
+    /// instead of initializing them from the module, when restoring an
+    /// instance from a snapshot.
+    pub restore: Option<RestoredMemories<'a>>,
+}
+
+impl<'a> InstanceAllocationRequest<'a> {
+    /// Get the copy-on-write image to map into the given defined memory, if
+    /// any.
+    pub(crate) fn memory_image(
+        &self,
+        memory: DefinedMemoryIndex,
+    ) -> Result<Option<&'a Arc<MemoryImage>>> {
+        match self.restore {
+            Some(restore) => Ok(restore
+                .images
+                .and_then(|images| images.get_memory_image(memory))),
+            None => self.runtime_info.memory_image(memory),
+        }
+    }
+}
+
+/// The saved state of an instance's defined memories, used to restore a new
+/// instance from a snapshot.
+#[derive(Clone, Copy)]
+pub struct RestoredMemories<'a> {
+    /// The size, in bytes, of each defined memory.
+    pub sizes: &'a PrimaryMap<DefinedMemoryIndex, u64>,
+
+    /// Copy-on-write images of each defined memory's contents, if they could
+    /// be created.
+    ///
+    /// When this is `None`, or a memory has no image, the memory is allocated
+    /// at its saved size and its contents must be copied in afterwards.
+    pub images: Option<&'a ModuleMemoryImages>,
 }
 
 /// A pointer to a Store. This Option<*mut dyn Store> is wrapped in a struct
@@ -301,11 +341,14 @@ pub unsafe trait InstanceAllocatorImpl {
     #[cfg(feature = "async")]
     unsafe fn deallocate_fiber_stack(&self, stack: wasmtime_fiber::FiberStack);
 
-    /// Allocate a GC heap for allocating Wasm GC objects within.
+    /// Allocate a GC heap for allocating Wasm GC objects within, for a store
+    /// using the protection key `pkey`.
     #[cfg(feature = "gc")]
     fn allocate_gc_heap(
         &self,
+        engine: &crate::Engine,
         gc_runtime: &dyn GcRuntime,
+        pkey: Option<ProtectionKey>,
     ) -> Result<(GcHeapAllocationIndex, Box<dyn GcHeap>)>;
 
     /// Deallocate a GC heap that was previously allocated with
@@ -337,6 +380,15 @@ pub unsafe trait InstanceAllocatorImpl {
 
     /// Allow access to memory regions protected by any protection key.
     fn allow_all_pkeys(&self);
+
+    /// Register `listener` to be called whenever slots are returned to this
+    /// allocator and may be allocated again.
+    ///
+    /// The listener is called from whichever thread returned the slots, and
+    /// is dropped from the allocator once it can no longer be upgraded.
+    /// Returns `false`, without registering anything, for allocators which
+    /// don't run out of slots.
+    fn on_slots_released(&self, listener: Weak<dyn Fn() + Send + Sync>) -> bool;
 }
 
 /// A thing that can allocate instances.
@@ -452,6 +504,32 @@ pub trait InstanceAllocator: InstanceAllocatorImpl {
                 .defined_memory_index(memory_index)
                 .expect("should be a defined memory since we skipped imported ones");
 
+            // When restoring from a snapshot, allocate the memory at its saved
+            // size rather than the module's minimum, so that the saved image
+            // fits inside it.
+            let restored_ty;
+            let ty = match request.restore {
+                Some(restore) => {
+                    let size = *restore
+                        .sizes
+                        .get(memory_index)
+                        .ok_or_else(|| anyhow!("no saved size for memory {memory_index:?}"))?;
+                    ensure!(
+                        size % ty.page_size() == 0,
+                        "saved size of memory {memory_index:?} isn't a multiple of its page size"
+                    );
+                    restored_ty = wasmtime_environ::Memory {
+                        limits: wasmtime_environ::Limits {
+                            min: size >> ty.page_size_log2,
+                            max: ty.limits.max,
+                        },
+                        ..*ty
+                    };
+                    &restored_ty
+                }
+                None => ty,
+            };
+
             memories.push(self.allocate_memory(request, ty, request.tunables, memory_index)?);
         }
 
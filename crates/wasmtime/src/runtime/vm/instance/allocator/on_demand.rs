@@ -7,7 +7,7 @@ use crate::runtime::vm::memory::{DefaultMemoryCreator, Memory};
 use crate::runtime::vm::mpk::ProtectionKey;
 use crate::runtime::vm::table::Table;
 use crate::runtime::vm::CompiledModuleId;
-use alloc::sync::Arc;
+use alloc::sync::{Arc, Weak};
 use wasmtime_environ::{
     DefinedMemoryIndex, DefinedTableIndex, HostPtr, Module, Tunables, VMOffsets,
 };
@@ -104,7 +104,7 @@ unsafe impl InstanceAllocatorImpl for OnDemandInstanceAllocator {
             .mem_creator
             .as_deref()
             .unwrap_or_else(|| &DefaultMemoryCreator);
-        let image = request.runtime_info.memory_image(memory_index)?;
+        let image = request.memory_image(memory_index)?;
         let allocation_index = MemoryAllocationIndex::default();
         let memory = Memory::new_dynamic(
             ty,
@@ -203,12 +203,23 @@ unsafe impl InstanceAllocatorImpl for OnDemandInstanceAllocator {
         unreachable!()
     }
 
+    fn on_slots_released(&self, _listener: Weak<dyn Fn() + Send + Sync>) -> bool {
+        // Memories and tables are allocated on demand, so there are no slots
+        // to wait for.
+        false
+    }
+
     #[cfg(feature = "gc")]
     fn allocate_gc_heap(
         &self,
+        engine: &crate::Engine,
         gc_runtime: &dyn GcRuntime,
+        _pkey: Option<ProtectionKey>,
     ) -> Result<(GcHeapAllocationIndex, Box<dyn GcHeap>)> {
-        Ok((GcHeapAllocationIndex::default(), gc_runtime.new_gc_heap()?))
+        Ok((
+            GcHeapAllocationIndex::default(),
+            gc_runtime.new_gc_heap(engine)?,
+        ))
     }
 
     #[cfg(feature = "gc")]
@@ -40,7 +40,7 @@ cfg_if::cfg_if! {
     }
 }
 
-use self::decommit_queue::DecommitQueue;
+use self::decommit_queue::{DecommitQueue, SlotReleaseListeners};
 use self::memory_pool::MemoryPool;
 use self::table_pool::TablePool;
 use super::{
@@ -54,7 +54,7 @@ use crate::runtime::vm::{
 };
 use std::borrow::Cow;
 use std::fmt::Display;
-use std::sync::{Mutex, MutexGuard};
+use std::sync::{Mutex, MutexGuard, Weak};
 use std::{
     mem,
     sync::atomic::{AtomicU64, Ordering},
@@ -213,6 +213,12 @@ pub struct PoolingInstanceAllocatorConfig {
     pub memory_protection_keys: MpkEnabled,
     /// How many memory protection keys to allocate.
     pub max_memory_protection_keys: usize,
+    /// Whether linear memories are initialized lazily with `userfaultfd`.
+    pub userfaultfd: bool,
+    /// How the memory of freed slots is scrubbed before reuse.
+    pub scrub_policy: ScrubPolicy,
+    /// Whether to check that slots were scrubbed when they are reused.
+    pub verify_scrubbing: bool,
 }
 
 impl Default for PoolingInstanceAllocatorConfig {
@@ -228,10 +234,57 @@ impl Default for PoolingInstanceAllocatorConfig {
             table_keep_resident: 0,
             memory_protection_keys: MpkEnabled::Disable,
             max_memory_protection_keys: 16,
+            userfaultfd: false,
+            scrub_policy: ScrubPolicy::Reset,
+            verify_scrubbing: false,
         }
     }
 }
 
+/// How the pooling allocator scrubs the memory of freed slots before they are
+/// reused.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ScrubPolicy {
+    /// Linear memories are reset to their initial image and tables to null,
+    /// with `memset` up to the configured keep-resident sizes and by
+    /// decommitting the rest. GC heaps are only logically reset and async
+    /// stacks are only zeroed when async stack zeroing is enabled.
+    Reset,
+    /// Like [`ScrubPolicy::Reset`], but additionally always zeroes async
+    /// stacks and GC heaps before reuse.
+    Zero,
+    /// Like [`ScrubPolicy::Zero`], but keeps nothing resident: all scrubbed
+    /// memory is decommitted, and decommits are not batched, so nothing
+    /// written to a slot remains resident once it is deallocated.
+    Decommit,
+}
+
+impl ScrubPolicy {
+    /// Whether async stacks and GC heaps are zeroed under this policy.
+    fn zeroes_everything(&self) -> bool {
+        !matches!(self, ScrubPolicy::Reset)
+    }
+
+    /// Returns the number of bytes of a slot to reset with `memset`, rather
+    /// than by decommitting, given the configured `keep_resident` size.
+    fn keep_resident(&self, keep_resident: usize) -> usize {
+        match self {
+            ScrubPolicy::Decommit => 0,
+            _ => keep_resident,
+        }
+    }
+}
+
+/// Panics if any of `bytes`, the contents of a freshly reused slot, is not
+/// zero.
+fn verify_scrubbed(what: &str, index: usize, bytes: &[u8]) {
+    if let Some(offset) = bytes.iter().position(|b| *b != 0) {
+        panic!(
+            "{what} slot {index} was reused without being scrubbed: byte {offset:#x} is not zero"
+        );
+    }
+}
+
 /// An error returned when the pooling allocator cannot allocate a table,
 /// memory, etc... because the maximum number of concurrent allocations for that
 /// entity has been reached.
@@ -283,6 +336,7 @@ pub struct PoolingInstanceAllocator {
     live_component_instances: AtomicU64,
 
     decommit_queue: Mutex<DecommitQueue>,
+    slot_release_listeners: SlotReleaseListeners,
     memories: MemoryPool,
     tables: TablePool,
 
@@ -323,16 +377,24 @@ impl Drop for PoolingInstanceAllocator {
 impl PoolingInstanceAllocator {
     /// Creates a new pooling instance allocator with the given strategy and limits.
     pub fn new(config: &PoolingInstanceAllocatorConfig, tunables: &Tunables) -> Result<Self> {
+        // Tables and GC heaps are striped with the same protection keys as
+        // linear memories, if any, since a store is assigned a single key.
+        let memories = MemoryPool::new(config, tunables)?;
+        let pkeys = memories.stripe_pkeys();
         Ok(Self {
-            decommit_batch_size: config.decommit_batch_size,
+            decommit_batch_size: match config.scrub_policy {
+                ScrubPolicy::Decommit => 1,
+                _ => config.decommit_batch_size,
+            },
             limits: config.limits,
             live_component_instances: AtomicU64::new(0),
             live_core_instances: AtomicU64::new(0),
             decommit_queue: Mutex::new(DecommitQueue::default()),
-            memories: MemoryPool::new(config, tunables)?,
-            tables: TablePool::new(config)?,
+            slot_release_listeners: SlotReleaseListeners::default(),
+            tables: TablePool::new(config, &pkeys)?,
             #[cfg(feature = "gc")]
-            gc_heaps: GcHeapPool::new(config)?,
+            gc_heaps: GcHeapPool::new(config, &pkeys)?,
+            memories,
             #[cfg(feature = "async")]
             stacks: StackPool::new(config)?,
         })
@@ -563,7 +625,8 @@ unsafe impl InstanceAllocatorImpl for PoolingInstanceAllocator {
     fn increment_component_instance_count(&self) -> Result<()> {
         let old_count = self.live_component_instances.fetch_add(1, Ordering::AcqRel);
         if old_count >= u64::from(self.limits.total_component_instances) {
-            self.decrement_component_instance_count();
+            // Nothing was released, so there's no need to notify listeners.
+            self.live_component_instances.fetch_sub(1, Ordering::AcqRel);
             return Err(PoolConcurrencyLimitError::new(
                 usize::try_from(self.limits.total_component_instances).unwrap(),
                 "component instances",
@@ -575,12 +638,14 @@ unsafe impl InstanceAllocatorImpl for PoolingInstanceAllocator {
 
     fn decrement_component_instance_count(&self) {
         self.live_component_instances.fetch_sub(1, Ordering::AcqRel);
+        self.slot_release_listeners.notify();
     }
 
     fn increment_core_instance_count(&self) -> Result<()> {
         let old_count = self.live_core_instances.fetch_add(1, Ordering::AcqRel);
         if old_count >= u64::from(self.limits.total_core_instances) {
-            self.decrement_core_instance_count();
+            // Nothing was released, so there's no need to notify listeners.
+            self.live_core_instances.fetch_sub(1, Ordering::AcqRel);
             return Err(PoolConcurrencyLimitError::new(
                 usize::try_from(self.limits.total_core_instances).unwrap(),
                 "core instances",
@@ -592,6 +657,7 @@ unsafe impl InstanceAllocatorImpl for PoolingInstanceAllocator {
 
     fn decrement_core_instance_count(&self) {
         self.live_core_instances.fetch_sub(1, Ordering::AcqRel);
+        self.slot_release_listeners.notify();
     }
 
     unsafe fn allocate_memory(
@@ -680,12 +746,19 @@ unsafe impl InstanceAllocatorImpl for PoolingInstanceAllocator {
         mpk::allow(ProtectionMask::all());
     }
 
+    fn on_slots_released(&self, listener: Weak<dyn Fn() + Send + Sync>) -> bool {
+        self.slot_release_listeners.register(listener);
+        true
+    }
+
     #[cfg(feature = "gc")]
     fn allocate_gc_heap(
         &self,
+        engine: &crate::Engine,
         gc_runtime: &dyn GcRuntime,
+        pkey: Option<ProtectionKey>,
     ) -> Result<(GcHeapAllocationIndex, Box<dyn GcHeap>)> {
-        self.gc_heaps.allocate(gc_runtime)
+        self.gc_heaps.allocate(engine, gc_runtime, pkey)
     }
 
     #[cfg(feature = "gc")]
@@ -795,4 +868,13 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    #[should_panic(
+        expected = "table slot 3 was reused without being scrubbed: byte 0x2 is not zero"
+    )]
+    fn verify_scrubbed_detects_dirty_slots() {
+        verify_scrubbed("table", 0, &[0; 8]);
+        verify_scrubbed("table", 3, &[0, 0, 1, 0]);
+    }
 }
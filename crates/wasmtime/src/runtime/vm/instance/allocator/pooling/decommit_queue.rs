@@ -7,10 +7,17 @@
 //! Even when batching is "disabled" we still use this queue. Batching is
 //! disabled by specifying a batch size of one, in which case, this queue will
 //! immediately get flushed every time we push onto it.
+//!
+//! Flushing the queue is also when slots become available again, so that is
+//! where listeners waiting for free slots, such as the background refill of an
+//! `InstancePrePool`, are notified.
 
 use super::PoolingInstanceAllocator;
+use crate::prelude::*;
 use crate::vm::{MemoryAllocationIndex, MemoryImageSlot, Table, TableAllocationIndex};
 use smallvec::SmallVec;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Weak};
 
 #[cfg(feature = "async")]
 use wasmtime_fiber::FiberStack;
@@ -194,6 +201,58 @@ impl DecommitQueue {
             }
         }
 
+        if deallocated_any {
+            pool.slot_release_listeners.notify();
+        }
         deallocated_any
     }
 }
+
+/// The listeners to notify whenever slots are returned to a pool, either by
+/// flushing a `DecommitQueue` or by an instance being deallocated.
+#[derive(Default)]
+pub struct SlotReleaseListeners {
+    /// Whether `listeners` may be non-empty, so that releasing slots doesn't
+    /// need to take the lock when nothing is listening.
+    any: AtomicBool,
+    listeners: Mutex<Vec<Weak<dyn Fn() + Send + Sync>>>,
+}
+
+impl SlotReleaseListeners {
+    /// Register `listener` to be notified until it can no longer be upgraded.
+    pub fn register(&self, listener: Weak<dyn Fn() + Send + Sync>) {
+        let mut listeners = self.listeners.lock().unwrap();
+        listeners.retain(|l| l.strong_count() > 0);
+        listeners.push(listener);
+        self.any.store(true, Ordering::Release);
+    }
+
+    /// Call every registered listener.
+    pub fn notify(&self) {
+        if !self.any.load(Ordering::Acquire) {
+            return;
+        }
+        // Don't hold the lock while calling listeners, so that they may
+        // themselves allocate or release slots.
+        let live = {
+            let mut listeners = self.listeners.lock().unwrap();
+            listeners.retain(|l| l.strong_count() > 0);
+            self.any.store(!listeners.is_empty(), Ordering::Release);
+            listeners
+                .iter()
+                .filter_map(Weak::upgrade)
+                .collect::<Vec<_>>()
+        };
+        for listener in live {
+            listener();
+        }
+    }
+}
+
+impl std::fmt::Debug for SlotReleaseListeners {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SlotReleaseListeners")
+            .field("len", &self.listeners.lock().unwrap().len())
+            .finish()
+    }
+}
@@ -1,14 +1,25 @@
-use super::index_allocator::{SimpleIndexAllocator, SlotId};
+use super::index_allocator::{SlotId, StripedIndexAllocator};
 use super::GcHeapAllocationIndex;
 use crate::prelude::*;
-use crate::runtime::vm::{GcHeap, GcRuntime, PoolingInstanceAllocatorConfig, Result};
+use crate::runtime::vm::mpk::ProtectionKey;
+use crate::runtime::vm::sys::vm::{decommit_behavior, decommit_pages};
+use crate::runtime::vm::sys::DecommitBehavior;
+use crate::runtime::vm::{GcHeap, GcRuntime, PoolingInstanceAllocatorConfig, Result, ScrubPolicy};
 use std::sync::Mutex;
 
 /// A pool of reusable GC heaps.
+///
+/// When the memory pool is striped with memory protection keys, GC heap slots
+/// are striped with the same keys: each heap's memory is colored with its
+/// slot's key when the heap is first created, and a store only receives heaps
+/// from its own stripe.
 pub struct GcHeapPool {
     max_gc_heaps: usize,
-    index_allocator: SimpleIndexAllocator,
+    index_allocator: StripedIndexAllocator,
+    pkeys: Vec<ProtectionKey>,
     heaps: Mutex<Vec<Option<Box<dyn GcHeap>>>>,
+    scrub_policy: ScrubPolicy,
+    verify_scrubbing: bool,
 }
 
 impl std::fmt::Debug for GcHeapPool {
@@ -16,17 +27,29 @@ impl std::fmt::Debug for GcHeapPool {
         f.debug_struct("GcHeapPool")
             .field("max_gc_heaps", &self.max_gc_heaps)
             .field("index_allocator", &self.index_allocator)
+            .field("pkeys", &self.pkeys)
             .field("heaps", &"..")
+            .field("scrub_policy", &self.scrub_policy)
+            .field("verify_scrubbing", &self.verify_scrubbing)
             .finish()
     }
 }
 
 impl GcHeapPool {
-    /// Create a new `GcHeapPool` with the given configuration.
-    pub fn new(config: &PoolingInstanceAllocatorConfig) -> Result<Self> {
-        let index_allocator = SimpleIndexAllocator::new(config.limits.total_gc_heaps);
+    /// Create a new `GcHeapPool` with the given configuration, striping its
+    /// slots with `pkeys` if there are at least two of them.
+    pub fn new(config: &PoolingInstanceAllocatorConfig, pkeys: &[ProtectionKey]) -> Result<Self> {
         let max_gc_heaps = usize::try_from(config.limits.total_gc_heaps).unwrap();
 
+        // As with tables, only stripe if every stripe gets at least one slot.
+        let pkeys = if pkeys.len() >= 2 && max_gc_heaps >= pkeys.len() {
+            pkeys.to_vec()
+        } else {
+            Vec::new()
+        };
+        let index_allocator =
+            StripedIndexAllocator::new(config.limits.total_gc_heaps, pkeys.len().max(1));
+
         // Each individual GC heap in the pool is lazily allocated. See the
         // `allocate` method.
         let heaps = Mutex::new((0..max_gc_heaps).map(|_| None).collect());
@@ -34,7 +57,10 @@ impl GcHeapPool {
         Ok(Self {
             max_gc_heaps,
             index_allocator,
+            pkeys,
             heaps,
+            scrub_policy: config.scrub_policy,
+            verify_scrubbing: config.verify_scrubbing,
         })
     }
 
@@ -44,20 +70,37 @@ impl GcHeapPool {
         self.index_allocator.is_empty()
     }
 
-    /// Allocate a single table for the given instance allocation request.
+    /// Allocate a single GC heap for a store using the given protection key.
     pub fn allocate(
         &self,
+        engine: &crate::Engine,
         gc_runtime: &dyn GcRuntime,
+        pkey: Option<ProtectionKey>,
     ) -> Result<(GcHeapAllocationIndex, Box<dyn GcHeap>)> {
+        let stripe_index = match pkey {
+            Some(pkey) if !self.pkeys.is_empty() => pkey.as_stripe(),
+            _ => {
+                debug_assert!(self.pkeys.is_empty());
+                0
+            }
+        };
         let allocation_index = self
             .index_allocator
-            .alloc()
+            .alloc(stripe_index)
             .map(|slot| GcHeapAllocationIndex(slot.0))
             .ok_or_else(|| {
-                anyhow!(
-                    "maximum concurrent GC heap limit of {} reached",
-                    self.max_gc_heaps
-                )
+                if self.pkeys.is_empty() {
+                    anyhow!(
+                        "maximum concurrent GC heap limit of {} reached",
+                        self.max_gc_heaps
+                    )
+                } else {
+                    anyhow!(
+                        "maximum concurrent GC heap limit of {} for GC heap stripe {} reached",
+                        self.index_allocator.stripe_len(stripe_index),
+                        stripe_index
+                    )
+                }
             })?;
         debug_assert_ne!(allocation_index, GcHeapAllocationIndex::default());
 
@@ -66,19 +109,69 @@ impl GcHeapPool {
             heaps[allocation_index.index()].take()
         } {
             // If we already have a heap at this slot, reuse it.
-            Some(heap) => heap,
+            Some(mut heap) => {
+                if self.verify_scrubbing && self.scrub_policy.zeroes_everything() {
+                    super::verify_scrubbed(
+                        "GC heap",
+                        allocation_index.index(),
+                        heap.heap_slice_mut(),
+                    );
+                }
+                heap
+            }
             // Otherwise, we haven't forced this slot's lazily allocated heap
-            // yet. So do that now.
-            None => gc_runtime.new_gc_heap()?,
+            // yet. So do that now, coloring it with the slot's stripe.
+            None => match self.new_gc_heap(engine, gc_runtime, stripe_index) {
+                Ok(heap) => heap,
+                Err(e) => {
+                    self.index_allocator.free(SlotId(allocation_index.0));
+                    return Err(e);
+                }
+            },
         };
 
         Ok((allocation_index, heap))
     }
 
+    fn new_gc_heap(
+        &self,
+        engine: &crate::Engine,
+        gc_runtime: &dyn GcRuntime,
+        stripe_index: usize,
+    ) -> Result<Box<dyn GcHeap>> {
+        let mut heap = gc_runtime.new_gc_heap(engine)?;
+        if let Some(pkey) = self.pkeys.get(stripe_index) {
+            // The heap's memory stays colored with this key for as long as it
+            // lives in this slot, including across `reset`s.
+            pkey.protect_read_write(heap.heap_slice_mut())?;
+        }
+        Ok(heap)
+    }
+
+    /// Zeroes the contents of `heap`, if configured to do so.
+    fn scrub(&self, heap: &mut dyn GcHeap) {
+        let contents = heap.heap_slice_mut();
+        match self.scrub_policy {
+            ScrubPolicy::Reset => {}
+            // Where decommitting restores the heap's original anonymous
+            // mapping it keeps its protection key, and pages read as zero
+            // again. Elsewhere decommitting replaces the mapping, so `memset`
+            // instead.
+            ScrubPolicy::Decommit
+                if decommit_behavior() == DecommitBehavior::RestoreOriginalMapping =>
+            unsafe {
+                decommit_pages(contents.as_mut_ptr(), contents.len())
+                    .expect("failed to decommit GC heap");
+            },
+            ScrubPolicy::Zero | ScrubPolicy::Decommit => contents.fill(0),
+        }
+    }
+
     /// Deallocate a previously-allocated GC heap.
     pub fn deallocate(&self, allocation_index: GcHeapAllocationIndex, mut heap: Box<dyn GcHeap>) {
         debug_assert_ne!(allocation_index, GcHeapAllocationIndex::default());
         heap.reset();
+        self.scrub(&mut *heap);
 
         // NB: Replace the heap before freeing the index. If we did it in the
         // opposite order, a concurrent allocation request could reallocate the
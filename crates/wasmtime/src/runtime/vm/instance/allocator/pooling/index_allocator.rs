@@ -51,6 +51,66 @@ impl SimpleIndexAllocator {
     }
 }
 
+/// A simple index allocator split into stripes.
+///
+/// Slot `i` belongs to stripe `i % num_stripes`, and each stripe is allocated
+/// from independently. This is used by pools whose slots are colored with
+/// memory protection keys so that a store only receives slots of its own
+/// color. With a single stripe this behaves like a `SimpleIndexAllocator`.
+#[derive(Debug)]
+pub struct StripedIndexAllocator {
+    stripes: Vec<SimpleIndexAllocator>,
+}
+
+impl StripedIndexAllocator {
+    pub fn new(capacity: u32, num_stripes: usize) -> Self {
+        assert!(num_stripes > 0);
+        let num_stripes_u32 = u32::try_from(num_stripes).unwrap();
+        let stripes = (0..num_stripes_u32)
+            .map(|i| {
+                SimpleIndexAllocator::new(
+                    capacity / num_stripes_u32 + u32::from(capacity % num_stripes_u32 > i),
+                )
+            })
+            .collect();
+        StripedIndexAllocator { stripes }
+    }
+
+    pub fn num_stripes(&self) -> usize {
+        self.stripes.len()
+    }
+
+    /// The number of slots in the given stripe.
+    pub fn stripe_len(&self, stripe: usize) -> usize {
+        self.stripes[stripe].0.len()
+    }
+
+    /// The stripe that the given slot belongs to.
+    pub fn stripe_of(&self, index: SlotId) -> usize {
+        index.index() % self.stripes.len()
+    }
+
+    #[allow(unused)] // some cfgs don't use this
+    pub fn is_empty(&self) -> bool {
+        self.stripes.iter().all(|s| s.is_empty())
+    }
+
+    /// Allocates a slot from `stripe`, returning its index within the whole
+    /// pool.
+    pub fn alloc(&self, stripe: usize) -> Option<SlotId> {
+        let num_stripes = u32::try_from(self.stripes.len()).unwrap();
+        let slot = self.stripes[stripe].alloc()?;
+        Some(SlotId(
+            slot.0 * num_stripes + u32::try_from(stripe).unwrap(),
+        ))
+    }
+
+    pub(crate) fn free(&self, index: SlotId) {
+        let num_stripes = u32::try_from(self.stripes.len()).unwrap();
+        self.stripes[self.stripe_of(index)].free(SlotId(index.0 / num_stripes));
+    }
+}
+
 /// A particular defined memory within a particular module.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct MemoryInModule(pub CompiledModuleId, pub DefinedMemoryIndex);
@@ -699,4 +759,32 @@ mod test {
         // for good measure make sure id3 is still affine
         assert_eq!(state.alloc(Some(id3)), Some(SlotId(0)));
     }
+
+    #[test]
+    fn test_striped_allocation() {
+        let state = StripedIndexAllocator::new(5, 2);
+        assert_eq!(state.num_stripes(), 2);
+        assert_eq!(state.stripe_len(0), 3);
+        assert_eq!(state.stripe_len(1), 2);
+
+        // Each stripe hands out its own slots, interleaved across the pool.
+        assert_eq!(state.alloc(1), Some(SlotId(1)));
+        assert_eq!(state.alloc(1), Some(SlotId(3)));
+        assert_eq!(state.alloc(1), None);
+        assert_eq!(state.alloc(0), Some(SlotId(0)));
+        assert_eq!(state.alloc(0), Some(SlotId(2)));
+        assert_eq!(state.alloc(0), Some(SlotId(4)));
+        assert_eq!(state.alloc(0), None);
+        assert_eq!(state.stripe_of(SlotId(3)), 1);
+
+        // Freed slots return to the stripe they came from.
+        state.free(SlotId(3));
+        assert_eq!(state.alloc(0), None);
+        assert_eq!(state.alloc(1), Some(SlotId(3)));
+
+        for i in 0..5 {
+            state.free(SlotId(i));
+        }
+        assert!(state.is_empty());
+    }
 }
@@ -67,6 +67,12 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use wasmtime_environ::{DefinedMemoryIndex, Module, Tunables};
 
+#[cfg(all(target_os = "linux", not(miri)))]
+use crate::runtime::vm::{
+    sys::uffd::{PageSource, Userfaultfd},
+    ModuleRuntimeInfo,
+};
+
 /// A set of allocator slots.
 ///
 /// The allocated slots can be split by striping them: e.g., with two stripe
@@ -133,9 +139,18 @@ pub struct MemoryPool {
     /// Only applicable on Linux.
     pub(super) keep_resident: HostAlignedByteCount,
 
+    /// Whether to check that slots were reset to zero, outside of their
+    /// image, when they are reused.
+    verify_scrubbing: bool,
+
     /// Keep track of protection keys handed out to initialized stores; this
     /// allows us to round-robin the assignment of stores to stripes.
     next_available_pkey: AtomicUsize,
+
+    /// If configured, the `userfaultfd` which lazily populates memories from
+    /// their module's data segments, one slot per memory.
+    #[cfg(all(target_os = "linux", not(miri)))]
+    uffd: Option<Userfaultfd>,
 }
 
 impl MemoryPool {
@@ -218,6 +233,35 @@ impl MemoryPool {
             );
         }
 
+        // When memories are populated lazily every page must be missing when
+        // a slot is reused so that it faults again, so nothing is kept
+        // resident.
+        let mut keep_resident = HostAlignedByteCount::new_rounded_up(
+            config
+                .scrub_policy
+                .keep_resident(config.linear_memory_keep_resident),
+        )?;
+        #[cfg(all(target_os = "linux", not(miri)))]
+        let uffd = if config.userfaultfd {
+            keep_resident = HostAlignedByteCount::ZERO;
+            let base = unsafe {
+                mapping
+                    .as_mut_ptr()
+                    .add(layout.pre_slab_guard_bytes.byte_count())
+            };
+            Some(Userfaultfd::new(
+                base,
+                layout.slot_bytes.byte_count(),
+                constraints.num_slots,
+            )?)
+        } else {
+            None
+        };
+        #[cfg(not(all(target_os = "linux", not(miri))))]
+        if config.userfaultfd {
+            bail!("userfaultfd is only supported on Linux");
+        }
+
         let image_slots: Vec<_> = std::iter::repeat_with(|| Mutex::new(None))
             .take(constraints.num_slots)
             .collect();
@@ -247,10 +291,11 @@ impl MemoryPool {
             image_slots,
             layout,
             memories_per_instance: usize::try_from(config.limits.max_memories_per_module).unwrap(),
-            keep_resident: HostAlignedByteCount::new_rounded_up(
-                config.linear_memory_keep_resident,
-            )?,
+            keep_resident,
+            verify_scrubbing: config.verify_scrubbing,
             next_available_pkey: AtomicUsize::new(0),
+            #[cfg(all(target_os = "linux", not(miri)))]
+            uffd,
         };
 
         Ok(pool)
@@ -266,6 +311,19 @@ impl MemoryPool {
         self.stripes[index].pkey
     }
 
+    /// Returns the protection keys that this pool's slots are striped with, in
+    /// stripe order, or nothing if the pool is not striped.
+    ///
+    /// The other pools use these same keys for their own slots so that a
+    /// store, restricted to its key while running Wasm, can reach only its own
+    /// tables and GC heap.
+    pub fn stripe_pkeys(&self) -> Vec<ProtectionKey> {
+        if self.stripes.len() < 2 {
+            return Vec::new();
+        }
+        self.stripes.iter().map(|s| s.pkey.unwrap()).collect()
+    }
+
     /// Validate whether this memory pool supports the given module.
     pub fn validate(&self, module: &Module) -> Result<()> {
         let memories = module.num_defined_memories();
@@ -360,7 +418,12 @@ impl MemoryPool {
             let base_capacity = self.layout.max_memory_bytes;
 
             let mut slot = self.take_memory_image_slot(allocation_index);
-            let image = request.runtime_info.memory_image(memory_index)?;
+            if self.verify_scrubbing {
+                for contents in slot.contents_outside_image() {
+                    super::verify_scrubbed("linear memory", allocation_index.index(), contents);
+                }
+            }
+            let image = request.memory_image(memory_index)?;
             let initial_size = ty
                 .minimum_byte_size()
                 .expect("min size checked in validation");
@@ -379,7 +442,19 @@ impl MemoryPool {
             // mmap that would leave an open space for someone
             // else to come in and map something.
             let initial_size = usize::try_from(initial_size).unwrap();
+            if initial_size > base_capacity.byte_count() {
+                // This can only happen when restoring a memory that grew beyond
+                // its module's minimum size.
+                bail!(
+                    "memory of {initial_size} bytes is larger than the pooling \
+                     allocator's maximum memory size of {} bytes",
+                    base_capacity.byte_count()
+                );
+            }
             slot.instantiate(initial_size, image, ty, tunables)?;
+            slot.set_lazy_init(false);
+            #[cfg(all(target_os = "linux", not(miri)))]
+            self.register_lazy_init(allocation_index, request, memory_index, &mut slot)?;
 
             Memory::new_static(
                 ty,
@@ -392,6 +467,8 @@ impl MemoryPool {
         })() {
             Ok(memory) => Ok((allocation_index, memory)),
             Err(e) => {
+                #[cfg(all(target_os = "linux", not(miri)))]
+                self.unregister_lazy_init(allocation_index);
                 self.stripes[stripe_index]
                     .allocator
                     .free(SlotId(striped_allocation_index.0));
@@ -415,6 +492,8 @@ impl MemoryPool {
         allocation_index: MemoryAllocationIndex,
         image: MemoryImageSlot,
     ) {
+        #[cfg(all(target_os = "linux", not(miri)))]
+        self.unregister_lazy_init(allocation_index);
         self.return_memory_image_slot(allocation_index, image);
 
         let (stripe_index, striped_allocation_index) =
@@ -470,6 +549,50 @@ impl MemoryPool {
         }
     }
 
+    /// If this pool populates memories lazily, registers the given slot with
+    /// the pool's `userfaultfd` so that it's populated from the memory's data
+    /// segments on demand.
+    ///
+    /// Memories whose data segments aren't laid out statically, or which are
+    /// being restored from a snapshot, are initialized eagerly as usual.
+    #[cfg(all(target_os = "linux", not(miri)))]
+    fn register_lazy_init(
+        &self,
+        allocation_index: MemoryAllocationIndex,
+        request: &InstanceAllocationRequest,
+        memory_index: DefinedMemoryIndex,
+        slot: &mut MemoryImageSlot,
+    ) -> Result<()> {
+        let Some(uffd) = &self.uffd else {
+            return Ok(());
+        };
+        if request.restore.is_some() {
+            return Ok(());
+        }
+        let Some(image) = LazyMemoryImage::new(request.runtime_info, memory_index) else {
+            return Ok(());
+        };
+        uffd.register(
+            allocation_index.index(),
+            self.layout.max_memory_bytes.byte_count(),
+            Arc::new(image),
+        )?;
+        slot.set_lazy_init(true);
+        Ok(())
+    }
+
+    /// Undoes `register_lazy_init` for the given slot, if it was registered.
+    #[cfg(all(target_os = "linux", not(miri)))]
+    fn unregister_lazy_init(&self, allocation_index: MemoryAllocationIndex) {
+        if let Some(uffd) = &self.uffd {
+            uffd.unregister(
+                allocation_index.index(),
+                self.layout.max_memory_bytes.byte_count(),
+            )
+            .expect("failed to unregister memory from userfaultfd");
+        }
+    }
+
     fn get_base(&self, allocation_index: MemoryAllocationIndex) -> MmapOffset {
         assert!(allocation_index.index() < self.layout.num_slots);
         let offset = self
@@ -523,6 +646,54 @@ impl Drop for MemoryPool {
     }
 }
 
+/// The initial contents of a linear memory, populated lazily by the pool's
+/// `userfaultfd` from its module's statically laid out data segments.
+#[cfg(all(target_os = "linux", not(miri)))]
+struct LazyMemoryImage {
+    module: ModuleRuntimeInfo,
+    /// The offset in linear memory of the start of the data.
+    offset: usize,
+    /// The range of the module's data section with the memory's contents.
+    data: std::ops::Range<usize>,
+}
+
+#[cfg(all(target_os = "linux", not(miri)))]
+impl LazyMemoryImage {
+    fn new(module: &ModuleRuntimeInfo, memory_index: DefinedMemoryIndex) -> Option<Self> {
+        let env_module = module.env_module();
+        let wasmtime_environ::MemoryInitialization::Static { map } =
+            &env_module.memory_initialization
+        else {
+            return None;
+        };
+        let init = map[env_module.memory_index(memory_index)].as_ref()?;
+        if init.data.is_empty() {
+            return None;
+        }
+        Some(LazyMemoryImage {
+            module: module.clone(),
+            offset: usize::try_from(init.offset).unwrap(),
+            data: usize::try_from(init.data.start).unwrap()
+                ..usize::try_from(init.data.end).unwrap(),
+        })
+    }
+}
+
+#[cfg(all(target_os = "linux", not(miri)))]
+impl PageSource for LazyMemoryImage {
+    fn fill(&self, offset: usize, page: &mut [u8]) -> bool {
+        let start = offset.max(self.offset);
+        let end = (offset + page.len()).min(self.offset + self.data.len());
+        if start >= end {
+            return false;
+        }
+        let data = &self.module.wasm_data()[self.data.clone()];
+        page[start - offset..end - offset]
+            .copy_from_slice(&data[start - self.offset..end - self.offset]);
+        true
+    }
+}
+
 /// The index of a memory allocation within an `InstanceAllocator`.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub struct StripedAllocationIndex(u32);
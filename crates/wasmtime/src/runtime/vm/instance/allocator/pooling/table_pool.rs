@@ -1,7 +1,8 @@
 use super::{
-    index_allocator::{SimpleIndexAllocator, SlotId},
+    index_allocator::{SlotId, StripedIndexAllocator},
     TableAllocationIndex,
 };
+use crate::runtime::vm::mpk::ProtectionKey;
 use crate::runtime::vm::sys::vm::commit_pages;
 use crate::runtime::vm::{
     mmap::AlignedLength, InstanceAllocationRequest, Mmap, PoolingInstanceAllocatorConfig,
@@ -16,20 +17,26 @@ use wasmtime_environ::{Module, Tunables};
 ///
 /// Each instance index into the pool returns an iterator over the base addresses
 /// of the instance's tables.
+///
+/// When the memory pool is striped with memory protection keys, table slots
+/// are striped with the same keys, so that a store's tables are only
+/// accessible to Wasm running in that store.
 #[derive(Debug)]
 pub struct TablePool {
-    index_allocator: SimpleIndexAllocator,
+    index_allocator: StripedIndexAllocator,
     mapping: Mmap<AlignedLength>,
     table_size: HostAlignedByteCount,
     max_total_tables: usize,
     tables_per_instance: usize,
     keep_resident: HostAlignedByteCount,
     table_elements: usize,
+    verify_scrubbing: bool,
 }
 
 impl TablePool {
-    /// Create a new `TablePool`.
-    pub fn new(config: &PoolingInstanceAllocatorConfig) -> Result<Self> {
+    /// Create a new `TablePool`, striping its slots with `pkeys` if there are
+    /// at least two of them.
+    pub fn new(config: &PoolingInstanceAllocatorConfig, pkeys: &[ProtectionKey]) -> Result<Self> {
         let table_size = HostAlignedByteCount::new_rounded_up(
             mem::size_of::<*mut u8>()
                 .checked_mul(config.limits.table_elements)
@@ -43,17 +50,40 @@ impl TablePool {
             .checked_mul(max_total_tables)
             .context("total size of tables exceeds addressable memory")?;
 
-        let mapping = Mmap::accessible_reserved(allocation_size, allocation_size)
+        let mut mapping = Mmap::accessible_reserved(allocation_size, allocation_size)
             .context("failed to create table pool mapping")?;
 
+        // Color each slot with its stripe's protection key. Tables are
+        // accessible as soon as they're allocated, so unlike linear memories
+        // the slots stay readable and writable. If there are too few slots to
+        // give every stripe one then fall back to a single, uncolored stripe
+        // rather than leaving some stores unable to allocate tables at all.
+        let num_stripes = if pkeys.len() >= 2 && max_total_tables >= pkeys.len() {
+            pkeys.len()
+        } else {
+            1
+        };
+        if num_stripes >= 2 && table_size.byte_count() > 0 {
+            for i in 0..max_total_tables {
+                let start = table_size.checked_mul(i)?.byte_count();
+                let region = unsafe { mapping.slice_mut(start..start + table_size.byte_count()) };
+                pkeys[i % num_stripes].protect_read_write(region)?;
+            }
+        }
+
         Ok(Self {
-            index_allocator: SimpleIndexAllocator::new(config.limits.total_tables),
+            index_allocator: StripedIndexAllocator::new(config.limits.total_tables, num_stripes),
             mapping,
             table_size,
             max_total_tables,
             tables_per_instance,
-            keep_resident: HostAlignedByteCount::new_rounded_up(config.table_keep_resident)?,
+            keep_resident: HostAlignedByteCount::new_rounded_up(
+                config
+                    .scrub_policy
+                    .keep_resident(config.table_keep_resident),
+            )?,
             table_elements: usize::try_from(config.limits.table_elements).unwrap(),
+            verify_scrubbing: config.verify_scrubbing,
         })
     }
 
@@ -122,12 +152,26 @@ impl TablePool {
         ty: &wasmtime_environ::Table,
         tunables: &Tunables,
     ) -> Result<(TableAllocationIndex, Table)> {
+        let stripe_index = match &request.pkey {
+            Some(pkey) if self.index_allocator.num_stripes() >= 2 => pkey.as_stripe(),
+            _ => {
+                debug_assert!(self.index_allocator.num_stripes() < 2);
+                0
+            }
+        };
         let allocation_index = self
             .index_allocator
-            .alloc()
+            .alloc(stripe_index)
             .map(|slot| TableAllocationIndex(slot.0))
             .ok_or_else(|| {
-                super::PoolConcurrencyLimitError::new(self.max_total_tables, "tables")
+                if self.index_allocator.num_stripes() < 2 {
+                    super::PoolConcurrencyLimitError::new(self.max_total_tables, "tables")
+                } else {
+                    super::PoolConcurrencyLimitError::new(
+                        self.index_allocator.stripe_len(stripe_index),
+                        format!("table stripe {stripe_index}"),
+                    )
+                }
             })?;
 
         match (|| {
@@ -136,6 +180,15 @@ impl TablePool {
             unsafe {
                 commit_pages(base, self.table_elements * mem::size_of::<*mut u8>())?;
             }
+            if self.verify_scrubbing {
+                let contents = unsafe {
+                    std::slice::from_raw_parts(
+                        base,
+                        self.table_elements * mem::size_of::<*mut u8>(),
+                    )
+                };
+                super::verify_scrubbed("table", allocation_index.index(), contents);
+            }
 
             let ptr = NonNull::new(std::ptr::slice_from_raw_parts_mut(
                 base.cast(),
@@ -222,16 +275,19 @@ mod tests {
 
     #[test]
     fn test_table_pool() -> Result<()> {
-        let pool = TablePool::new(&PoolingInstanceAllocatorConfig {
-            limits: InstanceLimits {
-                total_tables: 7,
-                table_elements: 100,
-                max_memory_size: 0,
-                max_memories_per_module: 0,
+        let pool = TablePool::new(
+            &PoolingInstanceAllocatorConfig {
+                limits: InstanceLimits {
+                    total_tables: 7,
+                    table_elements: 100,
+                    max_memory_size: 0,
+                    max_memories_per_module: 0,
+                    ..Default::default()
+                },
                 ..Default::default()
             },
-            ..Default::default()
-        })?;
+            &[],
+        )?;
 
         let host_page_size = HostAlignedByteCount::host_page_size();
 
@@ -26,6 +26,7 @@ pub struct StackPool {
     index_allocator: SimpleIndexAllocator,
     async_stack_zeroing: bool,
     async_stack_keep_resident: HostAlignedByteCount,
+    verify_scrubbing: bool,
 }
 
 impl StackPool {
@@ -76,10 +77,14 @@ impl StackPool {
             stack_size,
             max_stacks,
             page_size,
-            async_stack_zeroing: config.async_stack_zeroing,
+            async_stack_zeroing: config.async_stack_zeroing
+                || config.scrub_policy.zeroes_everything(),
             async_stack_keep_resident: HostAlignedByteCount::new_rounded_up(
-                config.async_stack_keep_resident,
+                config
+                    .scrub_policy
+                    .keep_resident(config.async_stack_keep_resident),
             )?,
+            verify_scrubbing: config.verify_scrubbing,
             index_allocator: SimpleIndexAllocator::new(config.limits.total_stacks),
         })
     }
@@ -119,6 +124,15 @@ impl StackPool {
 
             commit_pages(bottom_of_stack, size_without_guard.byte_count())?;
 
+            // Stacks are only scrubbed when they're zeroed after use.
+            if self.verify_scrubbing && self.async_stack_zeroing {
+                let contents = std::slice::from_raw_parts(
+                    bottom_of_stack.add(self.page_size.byte_count()),
+                    size_without_guard.byte_count(),
+                );
+                super::verify_scrubbed("async stack", index, contents);
+            }
+
             let stack = wasmtime_fiber::FiberStack::from_raw_parts(
                 bottom_of_stack,
                 self.page_size.byte_count(),
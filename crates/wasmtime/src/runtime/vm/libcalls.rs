@@ -476,7 +476,7 @@ unsafe fn gc(store: &mut dyn VMStore, _instance: &mut Instance, gc_ref: u32) ->
 /// Allocate a raw, unininitialized GC object for Wasm code.
 ///
 /// The Wasm code is responsible for initializing the object.
-#[cfg(feature = "gc-drc")]
+#[cfg(any(feature = "gc-drc", feature = "gc-copying"))]
 unsafe fn gc_alloc_raw(
     store: &mut dyn VMStore,
     instance: &mut Instance,
@@ -518,15 +518,24 @@ unsafe fn gc_alloc_raw(
             // If the allocation failed, do a GC to hopefully clean up space.
             store.maybe_async_gc(None)?;
 
-            // And then try again.
+            // And then try again, growing the heap if there still isn't room.
             store
-                .unwrap_gc_store_mut()
-                .alloc_raw(header, layout)?
+                .store_opaque_mut()
+                .retry_after_gc_heap_growth(size, |gc_store| gc_store.alloc_raw(header, layout))?
                 .ok_or_else(|| GcHeapOutOfMemory::new(()))?
         }
     };
 
-    Ok(gc_ref.as_raw_u32())
+    // The new object is about to be exposed to Wasm, which does not hold onto
+    // it in any way that the collector knows about, so transfer its initial
+    // ownership to the collector (e.g. into the DRC activations table) rather
+    // than leaking it.
+    let raw = gc_ref.as_raw_u32();
+    store
+        .store_opaque_mut()
+        .unwrap_gc_store_mut()
+        .expose_gc_ref_to_wasm(gc_ref);
+    Ok(raw)
 }
 
 // Intern a `funcref` into the GC heap, returning its `FuncRefTableId`.
@@ -645,8 +654,9 @@ unsafe fn array_new_data(
             store.maybe_async_gc(None)?;
             store
                 .store_opaque_mut()
-                .unwrap_gc_store_mut()
-                .alloc_uninit_array(shared_ty, u32::try_from(byte_len).unwrap(), &array_layout)?
+                .retry_after_gc_heap_growth(array_layout.layout(len).size(), |gc_store| {
+                    gc_store.alloc_uninit_array(shared_ty, len, &array_layout)
+                })?
                 .ok_or_else(|| GcHeapOutOfMemory::new(()))?
         }
     };
@@ -1087,6 +1097,60 @@ unsafe impl HostResultHasUnwindSentinel for NextEpoch {
     }
 }
 
+// Hook invoked before each instruction when guest debugging is enabled,
+// returning whether execution should stop there.
+fn debug_check(store: &mut dyn VMStore, instance: &mut Instance, offset: u32) -> u32 {
+    let Some(module) = instance.runtime_module() else {
+        return 0;
+    };
+    let store = store.store_opaque();
+    let fp = unsafe { *(*store.vmruntime_limits()).last_wasm_exit_fp.get() };
+    let offset = usize::try_from(offset).unwrap();
+    u32::from(store.debug_state().should_break(module.id(), offset, fp))
+}
+
+// Hook invoked when execution stops at an instruction while guest debugging.
+unsafe fn debug_break(
+    store: &mut dyn VMStore,
+    instance: &mut Instance,
+    offset: u32,
+    locals: *mut u8,
+    len: u32,
+) -> Result<()> {
+    let fp = *(*store.store_opaque().vmruntime_limits())
+        .last_wasm_exit_fp
+        .get();
+    let offset = usize::try_from(offset).unwrap();
+    let len = usize::try_from(len).unwrap();
+    let frame = crate::DebugFrame::new(instance, offset, locals, len);
+    let step = store.debug_break(&frame)?;
+    store.store_opaque_mut().debug_state_mut().resume(step, fp);
+    Ok(())
+}
+
+// Hook invoked before each linear memory access when memory access tracing is
+// enabled.
+fn trace_memory_access(
+    store: &mut dyn VMStore,
+    instance: &mut Instance,
+    memory: u32,
+    addr: u64,
+    size: u32,
+    kind: u32,
+    offset: u32,
+) -> Result<()> {
+    let kind = wasmtime_environ::MemoryAccessKind::from_u32(kind).expect("invalid access kind");
+    let access = crate::MemoryAccess::new(
+        instance,
+        memory,
+        addr,
+        u8::try_from(size).unwrap(),
+        kind,
+        usize::try_from(offset).unwrap(),
+    );
+    store.memory_access(&access)
+}
+
 // Hook for validating malloc using wmemcheck_state.
 #[cfg(feature = "wmemcheck")]
 unsafe fn check_malloc(
@@ -355,7 +355,7 @@ impl Memory {
 
     /// Returns whether or not this memory needs initialization. It
     /// may not if it already has initial content thanks to a CoW
-    /// mechanism.
+    /// mechanism, or if its initial content is populated lazily.
     pub(crate) fn needs_init(&self) -> bool {
         match self {
             Memory::Local(mem) => mem.needs_init(),
@@ -700,7 +700,7 @@ impl LocalMemory {
 
     pub fn needs_init(&self) -> bool {
         match &self.memory_image {
-            Some(image) => !image.has_image(),
+            Some(image) => image.needs_init(),
             None => true,
         }
     }
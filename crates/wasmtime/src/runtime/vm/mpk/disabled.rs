@@ -23,6 +23,9 @@ impl ProtectionKey {
     pub fn protect(&self, _: &mut [u8]) -> Result<()> {
         match *self {}
     }
+    pub fn protect_read_write(&self, _: &mut [u8]) -> Result<()> {
+        match *self {}
+    }
     pub fn as_stripe(&self) -> usize {
         match *self {}
     }
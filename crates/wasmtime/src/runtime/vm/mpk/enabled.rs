@@ -90,9 +90,26 @@ impl ProtectionKey {
     /// This will fail if the region is not page aligned or for some unknown
     /// kernel reason.
     pub fn protect(&self, region: &mut [u8]) -> Result<()> {
+        self.protect_with(region, sys::PROT_NONE)
+    }
+
+    /// Like [`ProtectionKey::protect`], but leaves the pages of `region`
+    /// readable and writable while this [`ProtectionKey`] is activated.
+    ///
+    /// This is used for regions which do not start out as `PROT_NONE`, such as
+    /// tables and GC heaps.
+    ///
+    /// # Errors
+    ///
+    /// This will fail if the region is not page aligned or for some unknown
+    /// kernel reason.
+    pub fn protect_read_write(&self, region: &mut [u8]) -> Result<()> {
+        self.protect_with(region, sys::PROT_READ_WRITE)
+    }
+
+    fn protect_with(&self, region: &mut [u8], prot: u32) -> Result<()> {
         let addr = region.as_mut_ptr() as usize;
         let len = region.len();
-        let prot = sys::PROT_NONE;
         sys::pkey_mprotect(addr, len, prot, self.id).with_context(|| {
             format!(
                 "failed to mark region with pkey (addr = {addr:#x}, len = {len}, prot = {prot:#b})"
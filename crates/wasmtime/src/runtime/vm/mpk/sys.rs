@@ -18,6 +18,11 @@ use std::io::Error;
 /// to start as `PROT_NONE`.
 pub const PROT_NONE: u32 = libc::PROT_NONE as u32; // == 0b0000;
 
+/// Protection mask allowing reads and writes of pkey-protected memory; used
+/// for regions, such as tables and GC heaps, which are accessible as soon as
+/// they are allocated.
+pub const PROT_READ_WRITE: u32 = (libc::PROT_READ | libc::PROT_WRITE) as u32; // == 0b0011;
+
 /// Allocate a new protection key in the Linux kernel ([docs]); returns the
 /// key ID.
 ///
@@ -38,3 +38,9 @@ pub fn tls_get() -> *mut u8 {
 pub fn tls_set(ptr: *mut u8) {
     unsafe { capi::wasmtime_tls_set(ptr) }
 }
+
+/// Custom platforms have no way to measure CPU time.
+#[cfg(feature = "std")]
+pub fn thread_cpu_time() -> Option<std::time::Duration> {
+    None
+}
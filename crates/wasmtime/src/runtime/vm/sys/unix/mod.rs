@@ -16,6 +16,8 @@ pub mod vm;
 pub mod machports;
 #[cfg(feature = "signals-based-traps")]
 pub mod signals;
+#[cfg(all(feature = "pooling-allocator", target_os = "linux"))]
+pub mod uffd;
 
 std::thread_local!(static TLS: Cell<*mut u8> = const { Cell::new(std::ptr::null_mut()) });
 
@@ -28,3 +30,15 @@ pub fn tls_get() -> *mut u8 {
 pub fn tls_set(ptr: *mut u8) {
     TLS.with(|p| p.set(ptr));
 }
+
+/// Returns the CPU time consumed by the current thread so far.
+pub fn thread_cpu_time() -> Option<std::time::Duration> {
+    let mut ts: libc::timespec = unsafe { std::mem::zeroed() };
+    if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) } != 0 {
+        return None;
+    }
+    Some(std::time::Duration::new(
+        u64::try_from(ts.tv_sec).ok()?,
+        u32::try_from(ts.tv_nsec).ok()?,
+    ))
+}
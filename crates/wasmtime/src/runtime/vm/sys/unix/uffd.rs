@@ -0,0 +1,447 @@
+//! Lazy population of memory with Linux's `userfaultfd`.
+//!
+//! A [`Userfaultfd`] covers a region of memory divided into equally-sized
+//! slots, such as the pooling allocator's memory pool. Slots are registered
+//! with [`Userfaultfd::register`] along with a [`PageSource`] describing their
+//! initial contents. The first access to a page which isn't resident in a
+//! registered slot suspends the faulting thread and delivers an event to a
+//! handler thread, which fills in the page from the slot's source, or with
+//! zeros, and wakes the faulting thread back up.
+//!
+//! Resetting a slot back to its initial contents is then just a matter of
+//! releasing its pages with `madvise(MADV_DONTNEED)`: the next access to each
+//! page faults again and is filled in from the source again.
+//!
+//! Note that only pages which are both missing and accessible are handled
+//! here. Accesses to `PROT_NONE` guard regions still raise a signal as usual.
+//!
+//! A thread which faults on a registered page stays suspended until the
+//! handler resolves the fault, so the handler must never give up on an event
+//! silently. If a fault can't be resolved because its page has been
+//! unregistered or unmapped in the meantime, the faulting thread is woken up
+//! to retry the access. Any other failure, including failing to poll or read
+//! the `userfaultfd` itself, aborts the process rather than leaving threads
+//! suspended forever.
+
+use crate::prelude::*;
+use crate::runtime::vm::host_page_size;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// Provides the initial contents of pages in a slot registered with a
+/// [`Userfaultfd`].
+pub trait PageSource: Send + Sync {
+    /// Fills in `page`, which is zeroed, with the contents of the slot
+    /// starting `offset` bytes into it.
+    ///
+    /// Returns `false` if the page is left entirely zero.
+    fn fill(&self, offset: usize, page: &mut [u8]) -> bool;
+}
+
+/// A `userfaultfd` file descriptor and the thread handling its faults for a
+/// region of memory divided into slots.
+pub struct Userfaultfd {
+    uffd: Arc<OwnedFd>,
+    shared: Arc<Shared>,
+    stop: OwnedFd,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    base: usize,
+    slot_stride: usize,
+    sources: Vec<Mutex<Option<Arc<dyn PageSource>>>>,
+}
+
+impl Userfaultfd {
+    /// Creates a new `userfaultfd` for `num_slots` slots of memory, the first
+    /// of which starts at `base`, each `slot_stride` bytes apart, and spawns
+    /// a thread to handle its faults.
+    pub fn new(base: *mut u8, slot_stride: usize, num_slots: usize) -> Result<Userfaultfd> {
+        let uffd = unsafe {
+            rustix::mm::userfaultfd(
+                rustix::mm::UserfaultfdFlags::CLOEXEC | rustix::mm::UserfaultfdFlags::NONBLOCK,
+            )
+        }
+        .map_err(io::Error::from)
+        .context(
+            "failed to create userfaultfd, which may require \
+             `vm.unprivileged_userfaultfd` to be enabled",
+        )?;
+
+        let mut api = sys::uffdio_api {
+            api: sys::UFFD_API,
+            features: 0,
+            ioctls: 0,
+        };
+        unsafe { sys::ioctl(uffd.as_raw_fd(), sys::UFFDIO_API, &mut api) }
+            .context("failed to negotiate the userfaultfd API")?;
+
+        let stop = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+        if stop < 0 {
+            return Err(io::Error::last_os_error()).context("failed to create eventfd");
+        }
+        let stop = unsafe { OwnedFd::from_raw_fd(stop) };
+
+        let uffd = Arc::new(uffd);
+        let shared = Arc::new(Shared {
+            base: base as usize,
+            slot_stride,
+            sources: core::iter::repeat_with(|| Mutex::new(None))
+                .take(num_slots)
+                .collect(),
+        });
+        let thread = std::thread::Builder::new()
+            .name("wasmtime-uffd".to_string())
+            .spawn({
+                let uffd = uffd.clone();
+                let shared = shared.clone();
+                let stop = stop.as_raw_fd();
+                move || handler_thread(&uffd, &shared, stop)
+            })
+            .context("failed to spawn userfaultfd handler thread")?;
+
+        Ok(Userfaultfd {
+            uffd,
+            shared,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Registers the first `len` bytes of slot `index` so that missing pages
+    /// within it are populated from `source`.
+    ///
+    /// The range must be backed by a private anonymous mapping.
+    pub fn register(&self, index: usize, len: usize, source: Arc<dyn PageSource>) -> Result<()> {
+        assert!(len <= self.shared.slot_stride);
+        *self.shared.sources[index].lock().unwrap() = Some(source);
+        let mut register = sys::uffdio_register {
+            range: self.shared.range(index, len),
+            mode: sys::UFFDIO_REGISTER_MODE_MISSING,
+            ioctls: 0,
+        };
+        let result =
+            unsafe { sys::ioctl(self.uffd.as_raw_fd(), sys::UFFDIO_REGISTER, &mut register) };
+        if result.is_err() {
+            *self.shared.sources[index].lock().unwrap() = None;
+        }
+        result.context("failed to register memory with userfaultfd")
+    }
+
+    /// Unregisters the first `len` bytes of slot `index`, after which missing
+    /// pages are populated with zeros by the kernel as usual.
+    pub fn unregister(&self, index: usize, len: usize) -> Result<()> {
+        let mut range = self.shared.range(index, len);
+        unsafe { sys::ioctl(self.uffd.as_raw_fd(), sys::UFFDIO_UNREGISTER, &mut range) }
+            .context("failed to unregister memory from userfaultfd")?;
+        *self.shared.sources[index].lock().unwrap() = None;
+        Ok(())
+    }
+}
+
+impl Shared {
+    fn range(&self, index: usize, len: usize) -> sys::uffdio_range {
+        assert!(index < self.sources.len());
+        sys::uffdio_range {
+            start: u64::try_from(self.base + index * self.slot_stride).unwrap(),
+            len: u64::try_from(len).unwrap(),
+        }
+    }
+}
+
+impl Drop for Userfaultfd {
+    fn drop(&mut self) {
+        let one = 1u64;
+        let n = unsafe {
+            libc::write(
+                self.stop.as_raw_fd(),
+                (&one as *const u64).cast(),
+                core::mem::size_of::<u64>(),
+            )
+        };
+        assert_eq!(n, 8, "failed to stop userfaultfd thread");
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+}
+
+impl core::fmt::Debug for Userfaultfd {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Userfaultfd")
+            .field("uffd", &self.uffd)
+            .field("base", &self.shared.base)
+            .field("slot_stride", &self.shared.slot_stride)
+            .field("num_slots", &self.shared.sources.len())
+            .finish()
+    }
+}
+
+fn handler_thread(uffd: &OwnedFd, shared: &Shared, stop: RawFd) {
+    let page_size = host_page_size();
+    let mut page = vec![0u8; page_size];
+    loop {
+        let mut fds = [
+            libc::pollfd {
+                fd: uffd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: stop,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        let n = unsafe { libc::poll(fds.as_mut_ptr(), 2, -1) };
+        if n < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            fatal(format_args!("failed to poll userfaultfd: {err}"));
+        }
+        if fds[1].revents != 0 {
+            return;
+        }
+        if fds[0].revents == 0 {
+            continue;
+        }
+        if fds[0].revents & (libc::POLLERR | libc::POLLHUP | libc::POLLNVAL) != 0 {
+            fatal(format_args!(
+                "userfaultfd is no longer usable: poll returned {:#x}",
+                fds[0].revents
+            ));
+        }
+
+        let mut msg = core::mem::MaybeUninit::<sys::uffd_msg>::uninit();
+        let n = unsafe {
+            libc::read(
+                uffd.as_raw_fd(),
+                msg.as_mut_ptr().cast(),
+                core::mem::size_of::<sys::uffd_msg>(),
+            )
+        };
+        if n < 0 {
+            let err = io::Error::last_os_error();
+            match err.kind() {
+                // Another wakeup may have raced with this read.
+                io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted => continue,
+                _ => fatal(format_args!("failed to read from userfaultfd: {err}")),
+            }
+        }
+        if usize::try_from(n).unwrap() != core::mem::size_of::<sys::uffd_msg>() {
+            fatal(format_args!("short read of {n} bytes from userfaultfd"));
+        }
+        let msg = unsafe { msg.assume_init() };
+        if msg.event != sys::UFFD_EVENT_PAGEFAULT {
+            continue;
+        }
+
+        let arg = msg.arg;
+        let addr = usize::try_from(arg[1]).unwrap() & !(page_size - 1);
+        let offset = addr - shared.base;
+        let index = offset / shared.slot_stride;
+        let offset = offset % shared.slot_stride;
+        let source = shared.sources[index].lock().unwrap().clone();
+
+        page.fill(0);
+        let filled = source.is_some_and(|source| source.fill(offset, &mut page));
+        let range = sys::uffdio_range {
+            start: u64::try_from(addr).unwrap(),
+            len: u64::try_from(page_size).unwrap(),
+        };
+        let result = if filled {
+            let mut copy = sys::uffdio_copy {
+                dst: range.start,
+                src: page.as_ptr() as u64,
+                len: range.len,
+                mode: 0,
+                copy: 0,
+            };
+            resolve(|| unsafe { sys::ioctl(uffd.as_raw_fd(), sys::UFFDIO_COPY, &mut copy) })
+        } else {
+            let mut zeropage = sys::uffdio_zeropage {
+                range,
+                mode: 0,
+                zeropage: 0,
+            };
+            resolve(|| unsafe { sys::ioctl(uffd.as_raw_fd(), sys::UFFDIO_ZEROPAGE, &mut zeropage) })
+        };
+        match result {
+            Ok(()) => {}
+
+            // The page is no longer registered, or no longer mapped at all,
+            // for example because its slot was unregistered while the fault
+            // was in flight, or the faulting process has exited. Wake up
+            // the faulting thread, if any, so that it retries the access
+            // without this `userfaultfd` being involved.
+            Err(e) if matches!(e.raw_os_error(), Some(libc::ENOENT | libc::ESRCH)) => {
+                log::debug!("waking fault at {addr:#x} which can no longer be resolved: {e}");
+                let mut range = range;
+                if let Err(e) =
+                    unsafe { sys::ioctl(uffd.as_raw_fd(), sys::UFFDIO_WAKE, &mut range) }
+                {
+                    fatal(format_args!(
+                        "failed to wake thread faulting at {addr:#x} with userfaultfd: {e}"
+                    ));
+                }
+            }
+
+            Err(e) => fatal(format_args!(
+                "failed to resolve page fault at {addr:#x} with userfaultfd: {e}"
+            )),
+        }
+    }
+}
+
+/// Aborts the process because a fault can't be handled.
+///
+/// The threads waiting on the `userfaultfd` would otherwise stay suspended
+/// forever, and unwinding out of the handler thread wouldn't wake them.
+#[cold]
+fn fatal(msg: core::fmt::Arguments<'_>) -> ! {
+    log::error!("{msg}");
+    eprintln!("wasmtime: {msg}");
+    std::process::abort();
+}
+
+/// Runs `ioctl`, which resolves a page fault, until it succeeds.
+///
+/// `EEXIST` means that the page was already populated, for example because
+/// several threads faulted on it at once, and `EAGAIN` that the mapping was
+/// changing, in which case the resolution is retried.
+fn resolve(mut ioctl: impl FnMut() -> io::Result<()>) -> io::Result<()> {
+    loop {
+        match ioctl() {
+            Err(e) if e.raw_os_error() == Some(libc::EAGAIN) => continue,
+            Err(e) if e.raw_os_error() == Some(libc::EEXIST) => return Ok(()),
+            result => return result,
+        }
+    }
+}
+
+/// Definitions from `linux/userfaultfd.h`.
+#[allow(non_camel_case_types)]
+mod sys {
+    use std::io;
+    use std::os::fd::RawFd;
+
+    pub const UFFD_API: u64 = 0xaa;
+    pub const UFFD_EVENT_PAGEFAULT: u8 = 0x12;
+    pub const UFFDIO_REGISTER_MODE_MISSING: u64 = 1 << 0;
+
+    pub const UFFDIO_API: u32 = iowr::<uffdio_api>(0x3f);
+    pub const UFFDIO_REGISTER: u32 = iowr::<uffdio_register>(0x00);
+    pub const UFFDIO_UNREGISTER: u32 = ior::<uffdio_range>(0x01);
+    pub const UFFDIO_WAKE: u32 = ior::<uffdio_range>(0x02);
+    pub const UFFDIO_COPY: u32 = iowr::<uffdio_copy>(0x03);
+    pub const UFFDIO_ZEROPAGE: u32 = iowr::<uffdio_zeropage>(0x04);
+
+    // See `asm-generic/ioctl.h` and its overrides for the encoding of ioctl
+    // request numbers.
+    cfg_if::cfg_if! {
+        if #[cfg(any(
+            target_arch = "mips",
+            target_arch = "mips64",
+            target_arch = "powerpc",
+            target_arch = "powerpc64",
+            target_arch = "sparc64"
+        ))] {
+            const IOC_READ: u32 = 2;
+            const IOC_WRITE: u32 = 4;
+            const IOC_DIRSHIFT: u32 = 29;
+        } else {
+            const IOC_READ: u32 = 2;
+            const IOC_WRITE: u32 = 1;
+            const IOC_DIRSHIFT: u32 = 30;
+        }
+    }
+
+    // The sizes of the argument structures all fit in the 13 or 14 bits
+    // available for them.
+    #[allow(clippy::cast_possible_truncation)]
+    const fn ioc<T>(dir: u32, nr: u32) -> u32 {
+        (dir << IOC_DIRSHIFT) | ((core::mem::size_of::<T>() as u32) << 16) | (0xaa << 8) | nr
+    }
+
+    const fn iowr<T>(nr: u32) -> u32 {
+        ioc::<T>(IOC_READ | IOC_WRITE, nr)
+    }
+
+    const fn ior<T>(nr: u32) -> u32 {
+        ioc::<T>(IOC_READ, nr)
+    }
+
+    #[repr(C)]
+    pub struct uffdio_api {
+        pub api: u64,
+        pub features: u64,
+        pub ioctls: u64,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct uffdio_range {
+        pub start: u64,
+        pub len: u64,
+    }
+
+    #[repr(C)]
+    pub struct uffdio_register {
+        pub range: uffdio_range,
+        pub mode: u64,
+        pub ioctls: u64,
+    }
+
+    #[repr(C)]
+    pub struct uffdio_copy {
+        pub dst: u64,
+        pub src: u64,
+        pub len: u64,
+        pub mode: u64,
+        pub copy: i64,
+    }
+
+    #[repr(C)]
+    pub struct uffdio_zeropage {
+        pub range: uffdio_range,
+        pub mode: u64,
+        pub zeropage: i64,
+    }
+
+    #[repr(C, packed)]
+    pub struct uffd_msg {
+        pub event: u8,
+        pub reserved1: u8,
+        pub reserved2: u16,
+        pub reserved3: u32,
+        pub arg: [u64; 3],
+    }
+
+    pub unsafe fn ioctl<T>(fd: RawFd, request: u32, arg: &mut T) -> io::Result<()> {
+        if libc::ioctl(fd, request as _, arg as *mut T) < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "aarch64",
+        target_arch = "riscv64"
+    ))]
+    const _: () = {
+        assert!(UFFDIO_API == 0xc018aa3f);
+        assert!(UFFDIO_REGISTER == 0xc020aa00);
+        assert!(UFFDIO_UNREGISTER == 0x8010aa01);
+        assert!(UFFDIO_WAKE == 0x8010aa02);
+        assert!(UFFDIO_COPY == 0xc028aa03);
+        assert!(UFFDIO_ZEROPAGE == 0xc020aa04);
+        assert!(core::mem::size_of::<uffd_msg>() == 32);
+    };
+}
@@ -930,6 +930,14 @@ pub struct VMRuntimeLimits {
     /// Used to find the end of a contiguous sequence of Wasm frames when
     /// walking the stack.
     pub last_wasm_entry_fp: UnsafeCell<usize>,
+
+    /// Pointer to the store's guest debugging flags, which are nonzero when
+    /// code compiled for guest debugging needs to ask the host whether to
+    /// stop before each instruction.
+    ///
+    /// The flags are shared with the store's `DebugInterruptHandle`s, which
+    /// may set them from other threads.
+    pub debug_flags: UnsafeCell<*const u32>,
 }
 
 // The `VMRuntimeLimits` type is a pod-type with no destructor, and we don't
@@ -948,6 +956,7 @@ impl Default for VMRuntimeLimits {
             last_wasm_exit_fp: UnsafeCell::new(0),
             last_wasm_exit_pc: UnsafeCell::new(0),
             last_wasm_entry_fp: UnsafeCell::new(0),
+            debug_flags: UnsafeCell::new(ptr::null()),
         }
     }
 }
@@ -986,6 +995,10 @@ mod test_vmruntime_limits {
             offset_of!(VMRuntimeLimits, last_wasm_entry_fp),
             usize::from(offsets.ptr.vmruntime_limits_last_wasm_entry_fp())
         );
+        assert_eq!(
+            offset_of!(VMRuntimeLimits, debug_flags),
+            usize::from(offsets.ptr.vmruntime_limits_debug_flags())
+        );
     }
 }
 
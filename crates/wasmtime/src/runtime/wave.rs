@@ -4,6 +4,14 @@
 mod component;
 mod core;
 
+#[cfg(feature = "component-model")]
+pub use self::component::WaveHandles;
+#[cfg(feature = "component-model")]
+pub(crate) use self::component::{
+    from_wave_args_with_handles, from_wave_with_handles, to_wave_with_handles,
+};
+pub(crate) use self::core::from_wave_args;
+
 macro_rules! unwrap_val {
     ($val:expr, $case:path, $name:expr) => {
         match $val {
@@ -23,6 +31,17 @@ macro_rules! unwrap_2val {
 pub(crate) use unwrap_2val;
 pub(crate) use unwrap_val;
 
+/// Returns whether `s`, a tuple of function arguments, is empty.
+///
+/// WAVE has no empty tuples, so `()` can't be parsed as one and is recognized
+/// here instead.
+pub(crate) fn is_empty_args(s: &str) -> bool {
+    s.trim()
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .is_some_and(|s| s.trim().is_empty())
+}
+
 #[inline]
 pub(crate) fn canonicalize_nan32(val: f32) -> f32 {
     if val.is_nan() {
@@ -1,8 +1,12 @@
 use crate::component;
 use crate::prelude::*;
+use alloc::collections::BTreeMap;
 use std::borrow::Cow;
 
-use super::{canonicalize_nan32, canonicalize_nan64, unwrap_2val, unwrap_val};
+use super::{canonicalize_nan32, canonicalize_nan64, is_empty_args, unwrap_2val, unwrap_val};
+use component::wasm_wave::ast::Node;
+use component::wasm_wave::lex::Keyword;
+use component::wasm_wave::untyped::UntypedValue;
 use component::wasm_wave::wasm::{
     ensure_type_kind, DisplayValue, WasmFunc, WasmType, WasmTypeKind, WasmValue, WasmValueError,
 };
@@ -404,6 +408,431 @@ impl WasmFunc for component::types::ComponentFunc {
     }
 }
 
+/// A table of resource handles for the `@handle(N)` extension to WAVE.
+///
+/// WAVE has no syntax for resources, so [`Val::to_wave_with_handles`] renders
+/// each resource as `@handle(N)` where `N` names the resource in this table,
+/// and [`Val::from_wave_with_handles`] resolves `@handle(N)` back to the
+/// resource. This allows resources returned from one call to be passed as
+/// arguments to later calls, for example in an interactive session.
+///
+/// Handles remain in the table until they're removed with
+/// [`WaveHandles::remove`] or passed as an `own` argument, which transfers
+/// ownership of the resource away from the table. Removed resources must still
+/// be dropped with [`ResourceAny::resource_drop`].
+///
+/// [`Val::to_wave_with_handles`]: component::Val::to_wave_with_handles
+/// [`Val::from_wave_with_handles`]: component::Val::from_wave_with_handles
+/// [`ResourceAny::resource_drop`]: component::ResourceAny::resource_drop
+#[derive(Debug, Default)]
+pub struct WaveHandles {
+    handles: BTreeMap<u32, component::ResourceAny>,
+    next: u32,
+}
+
+impl WaveHandles {
+    /// Creates a new, empty, table of handles.
+    pub fn new() -> WaveHandles {
+        WaveHandles::default()
+    }
+
+    /// Adds `resource` to this table, returning the number it's named by.
+    ///
+    /// If `resource` is already in this table then its existing number is
+    /// returned.
+    pub fn insert(&mut self, resource: component::ResourceAny) -> u32 {
+        if let Some((n, _)) = self.handles.iter().find(|(_, r)| **r == resource) {
+            return *n;
+        }
+        let n = self.next;
+        self.next += 1;
+        self.handles.insert(n, resource);
+        n
+    }
+
+    /// Returns the resource named `@handle(n)`, if any.
+    pub fn get(&self, n: u32) -> Option<component::ResourceAny> {
+        self.handles.get(&n).copied()
+    }
+
+    /// Removes the resource named `@handle(n)` from this table, returning it.
+    pub fn remove(&mut self, n: u32) -> Option<component::ResourceAny> {
+        self.handles.remove(&n)
+    }
+
+    /// Returns an iterator over the numbers and resources in this table, in
+    /// ascending order of their numbers.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (u32, component::ResourceAny)> + '_ {
+        self.handles.iter().map(|(n, r)| (*n, *r))
+    }
+
+    /// Returns the number of resources in this table.
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Returns whether this table is empty.
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+}
+
+/// Renders `val` as WAVE, with resources as `@handle(N)` in `handles`.
+pub(crate) fn to_wave_with_handles(
+    val: &component::Val,
+    handles: &mut WaveHandles,
+) -> Result<String> {
+    let mut out = String::new();
+    write_val(val, handles, &mut out)?;
+    Ok(out)
+}
+
+fn write_val(val: &component::Val, handles: &mut WaveHandles, out: &mut String) -> Result<()> {
+    use component::Val;
+
+    fn write_seq<'a>(
+        open: &str,
+        close: &str,
+        vals: impl IntoIterator<Item = &'a Val>,
+        handles: &mut WaveHandles,
+        out: &mut String,
+    ) -> Result<()> {
+        out.push_str(open);
+        for (i, val) in vals.into_iter().enumerate() {
+            if i != 0 {
+                out.push_str(", ");
+            }
+            write_val(val, handles, out)?;
+        }
+        out.push_str(close);
+        Ok(())
+    }
+
+    fn write_case(
+        name: &str,
+        payload: Option<&Val>,
+        handles: &mut WaveHandles,
+        out: &mut String,
+    ) -> Result<()> {
+        out.push_str(name);
+        if let Some(payload) = payload {
+            write_seq("(", ")", [payload], handles, out)?;
+        }
+        Ok(())
+    }
+
+    match val {
+        Val::Resource(resource) => {
+            let n = handles.insert(*resource);
+            out.push_str(&format!("@handle({n})"));
+        }
+        Val::List(vals) => write_seq("[", "]", vals, handles, out)?,
+        Val::Tuple(vals) => write_seq("(", ")", vals, handles, out)?,
+        Val::Record(fields) => {
+            // As with plain WAVE, `none` fields are omitted.
+            let mut fields = fields
+                .iter()
+                .filter(|(_, val)| !matches!(val, Val::Option(None)))
+                .peekable();
+            out.push('{');
+            if fields.peek().is_none() {
+                out.push(':');
+            }
+            let mut first = true;
+            for (name, val) in fields {
+                if !first {
+                    out.push_str(", ");
+                }
+                first = false;
+                out.push_str(name);
+                out.push_str(": ");
+                write_val(val, handles, out)?;
+            }
+            out.push('}');
+        }
+        Val::Variant(name, payload) => {
+            if Keyword::decode(name).is_some() {
+                out.push('%');
+            }
+            write_case(name, payload.as_deref(), handles, out)?
+        }
+        Val::Option(Some(val)) => write_case("some", Some(val), handles, out)?,
+        Val::Result(Ok(val)) => write_case("ok", val.as_deref(), handles, out)?,
+        Val::Result(Err(val)) => write_case("err", val.as_deref(), handles, out)?,
+        _ => out.push_str(&wasm_wave::to_string(val)?),
+    }
+    Ok(())
+}
+
+/// Parses WAVE-encoded `s` as a value of type `ty`, resolving `@handle(N)` in
+/// `handles`.
+pub(crate) fn from_wave_with_handles(
+    ty: &component::Type,
+    s: &str,
+    handles: &mut WaveHandles,
+) -> Result<component::Val> {
+    parse_with_handles(s, handles, |cx, node| cx.parse(node, ty))
+}
+
+/// Parses WAVE-encoded `s`, a tuple of arguments of types `params`, resolving
+/// `@handle(N)` in `handles`. Trailing `option` arguments may be omitted.
+pub(crate) fn from_wave_args_with_handles(
+    params: &[component::Type],
+    s: &str,
+    handles: &mut WaveHandles,
+) -> Result<Vec<component::Val>> {
+    let mut args = if is_empty_args(s) {
+        Vec::new()
+    } else {
+        parse_with_handles(s, handles, |cx, node| {
+            let nodes = node.as_tuple()?;
+            if nodes.len() > params.len() {
+                bail!("expected {} arguments, found {}", params.len(), nodes.len());
+            }
+            nodes
+                .zip(params)
+                .map(|(node, ty)| cx.parse(node, ty))
+                .collect::<Result<Vec<_>>>()
+        })?
+    };
+    for ty in &params[args.len()..] {
+        if !matches!(ty, component::Type::Option(_)) {
+            bail!("expected {} arguments, found {}", params.len(), args.len());
+        }
+        args.push(component::Val::Option(None));
+    }
+    Ok(args)
+}
+
+fn parse_with_handles<T>(
+    s: &str,
+    handles: &mut WaveHandles,
+    parse: impl FnOnce(&mut ParseHandles<'_>, &Node) -> Result<T>,
+) -> Result<T> {
+    let (src, handle_starts) = mask_handles(s);
+    let value = UntypedValue::parse(&src)?;
+    let mut cx = ParseHandles {
+        src: &src,
+        handle_starts,
+        handles,
+        consumed: Vec::new(),
+    };
+    let val = parse(&mut cx, value.node())?;
+    for n in cx.consumed {
+        cx.handles.remove(n);
+    }
+    Ok(val)
+}
+
+/// Replaces the `@` of each `@handle(` outside of literals and comments with a
+/// space so the rest of `s` can be parsed as plain WAVE, in which
+/// `handle(N)` is a variant case. The returned offsets are where each such
+/// `handle` now starts.
+///
+/// `wasm_wave` has no hook for extending its grammar, hence this rewrite.
+/// It can't be confused with a variant case that is really named `handle`:
+/// `@` isn't valid in plain WAVE outside of the literals and comments skipped
+/// here, so only text written as `@handle(` gets an entry in the returned
+/// offsets, and a parsed `handle(...)` node is only treated as a resource
+/// handle if it starts at one of them.
+fn mask_handles(s: &str) -> (String, Vec<usize>) {
+    let mut out = String::with_capacity(s.len());
+    let mut starts = Vec::new();
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        let skip = if let Some(string) = rest.strip_prefix("\"\"\"") {
+            string.find("\"\"\"").map_or(rest.len(), |end| end + 6)
+        } else if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if c == '"' || c == '\'' {
+            let mut escaped = false;
+            rest.char_indices()
+                .skip(1)
+                .find(|(_, d)| {
+                    let end = !escaped && *d == c;
+                    escaped = !escaped && *d == '\\';
+                    end
+                })
+                .map_or(rest.len(), |(end, _)| end + 1)
+        } else if rest.starts_with("@handle(") {
+            out.push(' ');
+            starts.push(out.len());
+            rest = &rest[1..];
+            continue;
+        } else {
+            c.len_utf8()
+        };
+        out.push_str(&rest[..skip]);
+        rest = &rest[skip..];
+    }
+    (out, starts)
+}
+
+struct ParseHandles<'a> {
+    src: &'a str,
+    handle_starts: Vec<usize>,
+    handles: &'a mut WaveHandles,
+    consumed: Vec<u32>,
+}
+
+impl ParseHandles<'_> {
+    fn parse(&mut self, node: &Node, ty: &component::Type) -> Result<component::Val> {
+        use component::{Type, Val};
+
+        if !has_resources(ty) {
+            if let Some(start) = self
+                .handle_starts
+                .iter()
+                .find(|start| node.span().contains(start))
+            {
+                bail!("unexpected `@handle` at offset {}", start - 1);
+            }
+            return Ok(node.to_wasm_value(ty, self.src)?);
+        }
+
+        Ok(match ty {
+            Type::Own(expected) | Type::Borrow(expected) => {
+                let n = self.handle(node)?;
+                let resource = self
+                    .handles
+                    .get(n)
+                    .ok_or_else(|| anyhow!("unknown handle `@handle({n})`"))?;
+                if resource.ty() != *expected {
+                    bail!("`@handle({n})` has the wrong resource type");
+                }
+                if let Type::Own(_) = ty {
+                    if !resource.owned() {
+                        bail!("`@handle({n})` is borrowed and cannot be passed as an `own`");
+                    }
+                    if self.consumed.contains(&n) {
+                        bail!("`@handle({n})` cannot be passed as an `own` more than once");
+                    }
+                    self.consumed.push(n);
+                }
+                Val::Resource(resource)
+            }
+            Type::List(list) => {
+                let ty = list.ty();
+                Val::List(
+                    node.as_list()?
+                        .map(|node| self.parse(node, &ty))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            Type::Tuple(tuple) => {
+                let nodes = node.as_tuple()?;
+                if nodes.len() != tuple.types().len() {
+                    bail!(
+                        "expected a tuple of {} values, found {}",
+                        tuple.types().len(),
+                        nodes.len()
+                    );
+                }
+                Val::Tuple(
+                    nodes
+                        .zip(tuple.types())
+                        .map(|(node, ty)| self.parse(node, &ty))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            Type::Record(record) => {
+                let nodes = node.as_record(self.src)?.collect::<Vec<_>>();
+                if let Some((name, _)) = nodes
+                    .iter()
+                    .find(|(name, _)| !record.fields().any(|f| f.name == *name))
+                {
+                    bail!("unknown record field `{name}`");
+                }
+                let mut fields = Vec::new();
+                for field in record.fields() {
+                    let val = match nodes.iter().find(|(name, _)| *name == field.name) {
+                        Some((_, node)) => self.parse(node, &field.ty)?,
+                        None if matches!(field.ty, Type::Option(_)) => Val::Option(None),
+                        None => bail!("missing record field `{}`", field.name),
+                    };
+                    fields.push((field.name.to_string(), val));
+                }
+                Val::Record(fields)
+            }
+            Type::Variant(variant) => {
+                if self.handle_starts.contains(&node.span().start) {
+                    bail!("unexpected `@handle` at offset {}", node.span().start - 1);
+                }
+                let (name, payload) = node.as_variant(self.src)?;
+                let case = variant
+                    .cases()
+                    .find(|case| case.name == name)
+                    .ok_or_else(|| anyhow!("unknown variant case `{name}`"))?;
+                let payload = self.parse_payload(name, payload, case.ty.as_ref())?;
+                Val::Variant(name.to_string(), payload)
+            }
+            Type::Option(option) => match node.as_option()? {
+                Some(node) => Val::Option(Some(Box::new(self.parse(node, &option.ty())?))),
+                None => Val::Option(None),
+            },
+            Type::Result(result) => match node.as_result()? {
+                Ok(payload) => Val::Result(Ok(self.parse_payload(
+                    "ok",
+                    payload,
+                    result.ok().as_ref(),
+                )?)),
+                Err(payload) => Val::Result(Err(self.parse_payload(
+                    "err",
+                    payload,
+                    result.err().as_ref(),
+                )?)),
+            },
+            _ => unreachable!("type without resources: {ty:?}"),
+        })
+    }
+
+    fn parse_payload(
+        &mut self,
+        case: &str,
+        node: Option<&Node>,
+        ty: Option<&component::Type>,
+    ) -> Result<Option<Box<component::Val>>> {
+        match (node, ty) {
+            (Some(node), Some(ty)) => Ok(Some(Box::new(self.parse(node, ty)?))),
+            (None, None) => Ok(None),
+            (Some(_), None) => bail!("unexpected payload for `{case}`"),
+            (None, Some(_)) => bail!("missing payload for `{case}`"),
+        }
+    }
+
+    /// Returns `N` from a `@handle(N)` node.
+    fn handle(&self, node: &Node) -> Result<u32> {
+        if self.handle_starts.contains(&node.span().start) {
+            if let Ok(("handle", Some(n))) = node.as_variant(self.src) {
+                return Ok(n.as_number(self.src)?);
+            }
+        }
+        bail!(
+            "expected a resource handle such as `@handle(0)` at offset {}",
+            node.span().start
+        )
+    }
+}
+
+fn has_resources(ty: &component::Type) -> bool {
+    use component::Type;
+    match ty {
+        Type::Own(_) | Type::Borrow(_) => true,
+        Type::List(list) => has_resources(&list.ty()),
+        Type::Record(record) => record.fields().any(|f| has_resources(&f.ty)),
+        Type::Tuple(tuple) => tuple.types().any(|ty| has_resources(&ty)),
+        Type::Variant(variant) => variant
+            .cases()
+            .any(|case| case.ty.as_ref().is_some_and(has_resources)),
+        Type::Option(option) => has_resources(&option.ty()),
+        Type::Result(result) => {
+            result.ok().as_ref().is_some_and(has_resources)
+                || result.err().as_ref().is_some_and(has_resources)
+        }
+        _ => false,
+    }
+}
+
 fn cow<T: Clone>(t: &T) -> Cow<T> {
     Cow::Borrowed(t)
 }
@@ -469,4 +898,128 @@ mod tests {
         round_trip(&Type::Float32, &Val::Float32(f32::EPSILON));
         round_trip(&Type::Float64, &Val::Float64(f64::EPSILON));
     }
+
+    #[test]
+    fn mask_handles() {
+        let (src, starts) =
+            super::mask_handles(r#"(@handle(0), "@handle(1)", '@', [@handle(2)]) // @handle(3)"#);
+        assert_eq!(
+            src,
+            r#"( handle(0), "@handle(1)", '@', [ handle(2)]) // @handle(3)"#
+        );
+        assert_eq!(starts, [2, 34]);
+    }
+
+    #[test]
+    fn resource_handles_round_trip() -> crate::Result<()> {
+        use crate::component::{Component, Linker, Val, WaveHandles};
+        use crate::prelude::*;
+        use crate::{Engine, Store};
+
+        let engine = Engine::default();
+        let component = Component::new(
+            &engine,
+            r#"
+                (component
+                    (type $t' (resource (rep i32)))
+                    (export $t "t" (type $t'))
+                    (type $v' (variant (case "handle" u32) (case "r" (own $t))))
+                    (export $v "v" (type $v'))
+
+                    (core func $new (canon resource.new $t))
+                    (core module $m
+                        (func (export "id") (param i32) (result i32) local.get 0)
+                        (func (export "pair") (param i32 i32)))
+                    (core instance $i (instantiate $m))
+                    (core func $drop (canon resource.drop $t))
+
+                    (func (export "new") (param "x" u32) (result (own $t))
+                        (canon lift (core func $new)))
+                    ;; Borrows of a component's own resources are lifted as their rep.
+                    (func (export "rep") (param "x" (borrow $t)) (result u32)
+                        (canon lift (core func $i "id")))
+                    (func (export "drop") (param "x" (own $t))
+                        (canon lift (core func $drop)))
+                    (func (export "variant") (param "x" $v)
+                        (canon lift (core func $i "pair")))
+                )
+            "#,
+        )?;
+        let mut store = Store::new(&engine, ());
+        let instance = Linker::new(&engine).instantiate(&mut store, &component)?;
+        let new = instance.get_func(&mut store, "new").unwrap();
+        let rep = instance.get_func(&mut store, "rep").unwrap();
+        let drop = instance.get_func(&mut store, "drop").unwrap();
+        let variant = instance.get_func(&mut store, "variant").unwrap();
+        let mut handles = WaveHandles::new();
+
+        let mut results = [Val::Bool(false)];
+        new.call(&mut store, &[Val::U32(42)], &mut results)?;
+        new.post_return(&mut store)?;
+        let Val::Resource(resource) = results[0] else {
+            panic!("expected a resource")
+        };
+        assert_eq!(resource.type_name(&store).as_deref(), Some("t"));
+        assert!(results[0].to_wave().is_err());
+        assert_eq!(results[0].to_wave_with_handles(&mut handles)?, "@handle(0)");
+        assert_eq!(results[0].to_wave_with_handles(&mut handles)?, "@handle(0)");
+        assert_eq!(handles.len(), 1);
+        let nested = Val::Tuple(vec![
+            Val::Option(Some(Box::new(Val::Resource(resource)))),
+            Val::Record(vec![
+                ("a".to_string(), Val::Resource(resource)),
+                ("b".to_string(), Val::Option(None)),
+            ]),
+        ]);
+        assert_eq!(
+            nested.to_wave_with_handles(&mut handles)?,
+            "(some(@handle(0)), {a: @handle(0)})"
+        );
+
+        // Passing a handle as a `borrow` leaves it in the table.
+        let ty = rep.params(&store)[0].1.clone();
+        let param = Val::from_wave_with_handles(&ty, "@handle(0)", &mut handles)?;
+        assert_eq!(param, Val::Resource(resource));
+        rep.call(&mut store, &[param], &mut results)?;
+        rep.post_return(&mut store)?;
+        assert_eq!(results[0], Val::U32(42));
+        assert_eq!(handles.len(), 1);
+        let args = Val::from_wave_args_with_handles(&[ty.clone()], "(@handle(0))", &mut handles)?;
+        assert_eq!(args, [Val::Resource(resource)]);
+        assert!(Val::from_wave_args_with_handles(&[ty.clone()], "()", &mut handles).is_err());
+        assert_eq!(
+            Val::from_wave_args_with_handles(&[], " ( ) ", &mut handles)?,
+            []
+        );
+
+        for (wave, err) in [
+            ("@handle(1)", "unknown handle `@handle(1)`"),
+            ("handle(0)", "expected a resource handle"),
+            ("0", "expected a resource handle"),
+        ] {
+            let result = Val::from_wave_with_handles(&ty, wave, &mut handles);
+            let msg = format!("{}", result.unwrap_err());
+            assert!(msg.contains(err), "{wave}: {msg}");
+        }
+        let result =
+            Val::from_wave_with_handles(&crate::component::Type::U32, "@handle(0)", &mut handles);
+        assert!(result.is_err());
+
+        // A variant case named `handle` is never mistaken for a handle.
+        let ty = variant.params(&store)[0].1.clone();
+        assert_eq!(
+            Val::from_wave_with_handles(&ty, "handle(7)", &mut handles)?,
+            Val::Variant("handle".to_string(), Some(Box::new(Val::U32(7))))
+        );
+        assert!(Val::from_wave_with_handles(&ty, "@handle(0)", &mut handles).is_err());
+        assert_eq!(handles.len(), 1);
+
+        // Passing a handle as an `own` moves it out of the table.
+        let ty = drop.params(&store)[0].1.clone();
+        let param = Val::from_wave_with_handles(&ty, "@handle(0)", &mut handles)?;
+        assert!(handles.is_empty());
+        drop.call(&mut store, &[param], &mut [])?;
+        drop.post_return(&mut store)?;
+        Ok(())
+    }
 }
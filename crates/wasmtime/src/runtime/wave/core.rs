@@ -1,7 +1,8 @@
 use crate::prelude::*;
 use std::borrow::Cow;
 
-use super::{canonicalize_nan32, canonicalize_nan64, unwrap_val};
+use super::{canonicalize_nan32, canonicalize_nan64, is_empty_args, unwrap_val};
+use wasm_wave::untyped::UntypedValue;
 use wasm_wave::wasm::{WasmFunc, WasmType, WasmTypeKind, WasmValue, WasmValueError};
 
 impl WasmType for crate::ValType {
@@ -100,7 +101,7 @@ impl WasmValue for crate::Val {
 
     fn unwrap_tuple(&self) -> Box<dyn Iterator<Item = Cow<Self>> + '_> {
         let v = unwrap_val!(self, Self::V128, "tuple").as_u128();
-        let low = v as i64;
+        let low = truncate_u128_to_i64(v);
         let high = (v >> 64) as i64;
         Box::new(
             [Self::I64(low), Self::I64(high)]
@@ -110,6 +111,11 @@ impl WasmValue for crate::Val {
     }
 }
 
+#[allow(clippy::cast_possible_truncation)]
+fn truncate_u128_to_i64(a: u128) -> i64 {
+    a as i64
+}
+
 impl WasmFunc for crate::FuncType {
     type Type = crate::ValType;
 
@@ -149,3 +155,17 @@ mod tests {
         }
     }
 }
+
+/// Parses WAVE-encoded `s`, a tuple of arguments of types `params`.
+pub(crate) fn from_wave_args(params: &[crate::ValType], s: &str) -> Result<Vec<crate::Val>> {
+    let args = if is_empty_args(s) {
+        Vec::new()
+    } else {
+        let value = UntypedValue::parse(s)?;
+        value.node().to_wasm_params(params, value.source())?
+    };
+    if args.len() != params.len() {
+        bail!("expected {} arguments, found {}", params.len(), args.len());
+    }
+    Ok(args)
+}
@@ -102,6 +102,7 @@ macro_rules! integer_primitives {
                 let atomic_value_ref: &$ty_atomic =
                     unsafe { &*(host_ptr.get().cast::<$ty_atomic>()) };
                 atomic_value_ref.store(val, Ordering::Relaxed);
+                mem.record_write::<Self>(offset, 1);
                 Ok(())
             }
         }
@@ -19,12 +19,14 @@ pub use witx;
 mod error;
 mod guest_type;
 mod region;
+mod write_log;
 
 pub use tracing;
 
 pub use error::GuestError;
 pub use guest_type::{GuestErrorType, GuestType, GuestTypeTransparent};
 pub use region::Region;
+pub use write_log::WriteLog;
 
 pub mod async_trait_crate {
     pub use async_trait::*;
@@ -156,7 +158,10 @@ impl<'a> GuestMemory<'a> {
     pub fn as_slice_mut(&mut self, ptr: GuestPtr<[u8]>) -> Result<Option<&mut [u8]>, GuestError> {
         let range = self.validate_range::<u8>(ptr.pointer.0, ptr.pointer.1)?;
         match self {
-            GuestMemory::Unshared(slice) => Ok(Some(&mut slice[range])),
+            GuestMemory::Unshared(slice) => {
+                write_log::record(slice.as_ptr(), range.clone());
+                Ok(Some(&mut slice[range]))
+            }
             GuestMemory::Shared(_) => Ok(None),
         }
     }
@@ -228,9 +233,27 @@ impl<'a> GuestMemory<'a> {
             let guest = guest.cast_mut().cast::<T>();
             std::ptr::copy(slice.as_ptr(), guest, slice.len());
         }
+        self.record_write::<T>(ptr.pointer.0, ptr.pointer.1);
         Ok(())
     }
 
+    /// Adds the `len` values of type `T` at `offset`, which have just been
+    /// written, to any [`WriteLog`] for this memory.
+    #[inline]
+    pub(crate) fn record_write<T>(&self, offset: u32, len: u32)
+    where
+        T: GuestTypeTransparent,
+    {
+        let base = match self {
+            GuestMemory::Unshared(s) => s.as_ptr(),
+            GuestMemory::Shared(s) => s.as_ptr().cast(),
+        };
+        // The range has already been validated so none of this can overflow.
+        let start = offset as usize;
+        let end = start + (len * T::guest_size()) as usize;
+        write_log::record(base, start..end);
+    }
+
     /// Validates a guest-relative pointer given various attributes, and returns
     /// the corresponding host pointer.
     ///
@@ -0,0 +1,96 @@
+use std::cell::{Cell, RefCell};
+use std::marker;
+use std::ops::Range;
+
+thread_local! {
+    static NEXT_ID: Cell<usize> = const { Cell::new(0) };
+
+    /// The ranges written so far for each [`WriteLog`] existing on this
+    /// thread.
+    static LOGS: RefCell<Vec<Entry>> = const { RefCell::new(Vec::new()) };
+}
+
+struct Entry {
+    id: usize,
+    base: usize,
+    ranges: Vec<Range<usize>>,
+}
+
+/// Logs the byte ranges of a linear memory which are written through a
+/// [`GuestMemory`](crate::GuestMemory) on the current thread for as long as
+/// this exists.
+///
+/// This is how an embedder can find out which parts of memory a
+/// `wiggle`-generated host function wrote to, without comparing the whole of
+/// memory before and after the call. It's meant to be created just before
+/// calling the host function and finished right after it returns, on the
+/// thread running the store, so only that call's writes are logged. A log is
+/// only ever visible to the thread which created it, so writes made by other
+/// threads, or to other memories, never contend with it.
+///
+/// The memory is identified by the address of its first byte, so writes made
+/// after it has moved, for example by growing it, aren't logged. Ranges
+/// obtained with [`GuestMemory::as_slice_mut`](crate::GuestMemory::as_slice_mut)
+/// are logged in full whether or not they're actually written to.
+pub struct WriteLog {
+    id: usize,
+    _not_send: marker::PhantomData<*const ()>,
+}
+
+impl WriteLog {
+    /// Starts logging writes to the memory whose first byte is at `base`.
+    pub fn new(base: *const u8) -> WriteLog {
+        let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+        LOGS.with_borrow_mut(|logs| {
+            logs.push(Entry {
+                id,
+                base: base as usize,
+                ranges: Vec::new(),
+            })
+        });
+        WriteLog {
+            id,
+            _not_send: marker::PhantomData,
+        }
+    }
+
+    /// Stops logging, returning the ranges which were written in ascending
+    /// order, with overlapping and adjacent ranges merged together.
+    pub fn finish(self) -> Vec<Range<usize>> {
+        let mut ranges = LOGS.with_borrow_mut(|logs| {
+            let entry = logs.iter_mut().find(|e| e.id == self.id).unwrap();
+            std::mem::take(&mut entry.ranges)
+        });
+        ranges.sort_by_key(|r| r.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(prev) if range.start <= prev.end => prev.end = prev.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+}
+
+impl Drop for WriteLog {
+    fn drop(&mut self) {
+        // The thread-local may already be gone if this is dropped while the
+        // thread is exiting, in which case there's nothing left to remove.
+        let _ = LOGS.try_with(|logs| logs.borrow_mut().retain(|e| e.id != self.id));
+    }
+}
+
+/// Adds `range` to every log on this thread for the memory whose first byte
+/// is at `base`.
+#[inline]
+pub(crate) fn record(base: *const u8, range: Range<usize>) {
+    if range.is_empty() {
+        return;
+    }
+    LOGS.with_borrow_mut(|logs| {
+        for entry in logs.iter_mut().filter(|e| e.base == base as usize) {
+            entry.ranges.push(range.clone());
+        }
+    })
+}
@@ -0,0 +1,39 @@
+use wiggle::{GuestMemory, GuestPtr, WriteLog};
+
+#[test]
+fn logs_writes_to_its_memory() {
+    let mut bytes = vec![0u8; 64];
+    let mut other = vec![0u8; 64];
+    let log = WriteLog::new(bytes.as_ptr());
+
+    let mut mem = GuestMemory::Unshared(&mut bytes);
+    mem.write(GuestPtr::<u32>::new(8), 1).unwrap();
+    mem.write(GuestPtr::<u32>::new(4), 2).unwrap();
+    mem.copy_from_slice(&[1, 2, 3], GuestPtr::<[u8]>::new((32, 3)))
+        .unwrap();
+
+    // Writes to other memories aren't logged.
+    let mut other_mem = GuestMemory::Unshared(&mut other);
+    other_mem.write(GuestPtr::<u32>::new(48), 3).unwrap();
+
+    assert_eq!(log.finish(), [4..12, 32..35]);
+}
+
+#[test]
+fn logs_are_per_thread() {
+    let mut bytes = vec![0u8; 64];
+    let base = bytes.as_ptr() as usize;
+    let log = WriteLog::new(bytes.as_ptr());
+
+    // A log on another thread for the same memory doesn't see this thread's
+    // writes, and vice versa.
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            let log = WriteLog::new(base as *const u8);
+            let mut mem = GuestMemory::Unshared(&mut bytes);
+            mem.write(GuestPtr::<u32>::new(0), 1).unwrap();
+            assert_eq!(log.finish(), [0..4]);
+        });
+    });
+    assert!(log.finish().is_empty());
+}
@@ -0,0 +1,82 @@
+//! Pre-initialization of components, by way of the core modules within them.
+
+use anyhow::{bail, Result};
+use wasm_encoder::{ComponentSectionId, RawSection};
+use wasmparser::{Chunk, Encoding, Parser, Payload};
+
+/// Rebuild the component `wasm`, replacing each core module within it,
+/// including those within nested components, with the result of `f`.
+///
+/// The index passed to `f` numbers modules in the order that they appear in
+/// the binary, which matches the numbering used by
+/// [`wasmtime::component::Instance::core_instances`].
+pub(crate) fn map_modules(
+    wasm: &[u8],
+    f: &mut dyn FnMut(usize, &[u8]) -> Result<Vec<u8>>,
+) -> Result<Vec<u8>> {
+    let mut next_index = 0;
+    map_modules_(wasm, &mut next_index, f)
+}
+
+fn map_modules_(
+    wasm: &[u8],
+    next_index: &mut usize,
+    f: &mut dyn FnMut(usize, &[u8]) -> Result<Vec<u8>>,
+) -> Result<Vec<u8>> {
+    let mut component = wasm_encoder::Component::new();
+    let mut parser = Parser::new(0);
+    let mut offset = 0;
+    loop {
+        let payload = match parser.parse(&wasm[offset..], true)? {
+            Chunk::Parsed { consumed, payload } => {
+                offset += consumed;
+                payload
+            }
+            // this state isn't possible with `eof = true`
+            Chunk::NeedMoreData(_) => unreachable!(),
+        };
+        match payload {
+            Payload::Version {
+                encoding: Encoding::Module,
+                ..
+            } => bail!("expected a component, found a core wasm module"),
+
+            // The parser skips over nested modules and components, so their
+            // contents need to be skipped here too.
+            Payload::ModuleSection {
+                unchecked_range, ..
+            } => {
+                let index = *next_index;
+                *next_index += 1;
+                let module = f(index, &wasm[unchecked_range.clone()])?;
+                component.section(&RawSection {
+                    id: ComponentSectionId::CoreModule.into(),
+                    data: &module,
+                });
+                offset += unchecked_range.len();
+            }
+            Payload::ComponentSection {
+                unchecked_range, ..
+            } => {
+                let nested = map_modules_(&wasm[unchecked_range.clone()], next_index, f)?;
+                component.section(&RawSection {
+                    id: ComponentSectionId::Component.into(),
+                    data: &nested,
+                });
+                offset += unchecked_range.len();
+            }
+
+            Payload::End(_) => break,
+
+            payload => {
+                if let Some((id, range)) = payload.as_section() {
+                    component.section(&RawSection {
+                        id,
+                        data: &wasm[range],
+                    });
+                }
+            }
+        }
+    }
+    Ok(component.finish())
+}
@@ -0,0 +1,276 @@
+//! Pre-initialization of WebAssembly modules and components with Wasmtime.
+//!
+//! This crate implements the transformation made popular by [Wizer]: a
+//! module is instantiated, an initialization function that it exports is run,
+//! and then a new module is emitted whose memories and globals are initialized
+//! with the state that the initialization function left behind. Instantiating
+//! the new module is equivalent to instantiating the original module and
+//! running its initialization function, without paying for that
+//! initialization at runtime.
+//!
+//! [Wizer]: https://github.com/bytecodealliance/wizer
+//!
+//! Pre-initialization is configured with a [`Wizer`] and performed with
+//! [`Wizer::run`], which uses a caller-provided [`Store`] and instantiation
+//! function so that the module sees the same engine configuration and host
+//! functions that it would at runtime:
+//!
+//! ```
+//! use wasmtime::{Engine, Linker, Store};
+//! use wasmtime_wizer::Wizer;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let wasm = wat::parse_str(
+//!     r#"
+//!         (module
+//!             (global $g (export "g") (mut i32) (i32.const 0))
+//!             (func (export "wizer.initialize")
+//!                 (global.set $g (i32.const 42)))
+//!         )
+//!     "#,
+//! )?;
+//!
+//! let engine = Engine::default();
+//! let linker = Linker::new(&engine);
+//! let mut store = Store::new(&engine, ());
+//! let initialized = Wizer::new().run(&mut store, &wasm, |store, module| {
+//!     linker.instantiate(store, module)
+//! })?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Embedders that need to instantiate asynchronously can instead drive each
+//! step themselves with [`Wizer::instrument`] and [`Wizer::snapshot`].
+//!
+//! # Limitations
+//!
+//! * Only the contents of memories and the values of globals are captured.
+//!   Tables are initialized from the module's element segments as usual, so
+//!   any changes that the initialization function makes to them are lost.
+//!
+//! * Which passive data and element segments have been dropped isn't
+//!   captured.
+//!
+//! * Modules which import memories can't be pre-initialized, since the
+//!   contents of those memories belong to whoever provides them. Within a
+//!   component this is allowed, since the module that defines the memory is
+//!   pre-initialized as well.
+//!
+//! * Shared memories, and globals holding non-null references, can't be
+//!   captured.
+//!
+//! * Within a component, each module may be instantiated at most once, and
+//!   component-level state such as resource handles isn't captured.
+
+#![deny(missing_docs)]
+
+use anyhow::{Context, Result};
+use wasmtime::{AsContextMut, Instance, Module, Store};
+
+#[cfg(feature = "component-model")]
+mod component;
+mod module;
+
+use module::{ModuleInfo, Snapshot};
+
+/// The default name of a module's initialization function.
+pub const DEFAULT_INIT_FUNC: &str = "wizer.initialize";
+
+/// The default name of a component's initialization function.
+///
+/// This differs from [`DEFAULT_INIT_FUNC`] since component export names must
+/// be in kebab case.
+pub const DEFAULT_COMPONENT_INIT_FUNC: &str = "wizer-initialize";
+
+/// Configuration for pre-initializing a module or component.
+///
+/// See the [crate documentation](crate) for more information.
+#[derive(Clone, Debug)]
+pub struct Wizer {
+    init_func: Option<String>,
+    keep_init_func: bool,
+}
+
+impl Default for Wizer {
+    fn default() -> Wizer {
+        Wizer::new()
+    }
+}
+
+impl Wizer {
+    /// Creates a new configuration with the default settings.
+    pub fn new() -> Wizer {
+        Wizer {
+            init_func: None,
+            keep_init_func: false,
+        }
+    }
+
+    /// Sets the name of the exported function which initializes the module.
+    ///
+    /// The function must take no parameters and return no results. Defaults
+    /// to [`DEFAULT_INIT_FUNC`] for modules and [`DEFAULT_COMPONENT_INIT_FUNC`]
+    /// for components.
+    pub fn init_func(&mut self, name: impl Into<String>) -> &mut Self {
+        self.init_func = Some(name.into());
+        self
+    }
+
+    /// Returns the name of the function which initializes a module.
+    pub fn get_init_func(&self) -> &str {
+        self.init_func.as_deref().unwrap_or(DEFAULT_INIT_FUNC)
+    }
+
+    /// Returns the name of the function which initializes a component.
+    pub fn get_component_init_func(&self) -> &str {
+        self.init_func
+            .as_deref()
+            .unwrap_or(DEFAULT_COMPONENT_INIT_FUNC)
+    }
+
+    /// Sets whether the initialization function remains exported from the
+    /// pre-initialized module.
+    ///
+    /// By default it's removed, since running it again would usually be a
+    /// mistake. This has no effect on components, whose exports are left
+    /// as-is.
+    pub fn keep_init_func(&mut self, keep: bool) -> &mut Self {
+        self.keep_init_func = keep;
+        self
+    }
+
+    /// Pre-initializes the core wasm module `wasm`, returning the new module.
+    ///
+    /// The `instantiate` function is used to instantiate an instrumented
+    /// version of `wasm` within `store`, after which the initialization
+    /// function is called and the resulting state is captured.
+    pub fn run<T>(
+        &self,
+        store: &mut Store<T>,
+        wasm: &[u8],
+        instantiate: impl FnOnce(&mut Store<T>, &Module) -> Result<Instance>,
+    ) -> Result<Vec<u8>> {
+        let instrumented = self.instrument(wasm)?;
+        let module = Module::new(store.engine(), &instrumented)
+            .context("failed to compile the instrumented module")?;
+        let instance = instantiate(store, &module)?;
+        let name = self.get_init_func();
+        instance
+            .get_typed_func::<(), ()>(&mut *store, name)
+            .with_context(|| format!("failed to find initialization function `{name}`"))?
+            .call(&mut *store, ())
+            .context("failed to run the initialization function")?;
+        self.snapshot(wasm, store, &instance)
+    }
+
+    /// Instruments the core wasm module `wasm` so that its state can be
+    /// captured with [`Wizer::snapshot`].
+    ///
+    /// The returned module should be instantiated and have its initialization
+    /// function called, and then that instance should be passed to
+    /// [`Wizer::snapshot`]. [`Wizer::run`] performs all of these steps.
+    pub fn instrument(&self, wasm: &[u8]) -> Result<Vec<u8>> {
+        let info = ModuleInfo::parse(wasm)?;
+        info.ensure_no_imported_memories()?;
+        module::instrument(wasm, &info)
+    }
+
+    /// Captures the state of `instance` and returns a version of the core
+    /// wasm module `wasm` initialized with that state.
+    ///
+    /// The `instance` must be an instance of the result of
+    /// [`Wizer::instrument`] on `wasm`.
+    pub fn snapshot(
+        &self,
+        wasm: &[u8],
+        store: impl AsContextMut,
+        instance: &Instance,
+    ) -> Result<Vec<u8>> {
+        let info = ModuleInfo::parse(wasm)?;
+        info.ensure_no_imported_memories()?;
+        let snapshot = Snapshot::new(store, &info, instance)?;
+        let remove_export = if self.keep_init_func {
+            None
+        } else {
+            Some(self.get_init_func())
+        };
+        module::rewrite(wasm, &info, &snapshot, remove_export)
+    }
+}
+
+#[cfg(feature = "component-model")]
+impl Wizer {
+    /// Pre-initializes the component `wasm`, returning the new component.
+    ///
+    /// This is like [`Wizer::run`], except that the initialization function is
+    /// exported from the component and the state of each of the component's
+    /// core wasm instances is captured.
+    pub fn run_component<T>(
+        &self,
+        store: &mut Store<T>,
+        wasm: &[u8],
+        instantiate: impl FnOnce(
+            &mut Store<T>,
+            &wasmtime::component::Component,
+        ) -> Result<wasmtime::component::Instance>,
+    ) -> Result<Vec<u8>> {
+        let instrumented = self.instrument_component(wasm)?;
+        let component = wasmtime::component::Component::new(store.engine(), &instrumented)
+            .context("failed to compile the instrumented component")?;
+        let instance = instantiate(store, &component)?;
+        let name = self.get_component_init_func();
+        let init = instance
+            .get_typed_func::<(), ()>(&mut *store, name)
+            .with_context(|| format!("failed to find initialization function `{name}`"))?;
+        init.call(&mut *store, ())
+            .context("failed to run the initialization function")?;
+        init.post_return(&mut *store)?;
+        self.snapshot_component(wasm, store, &instance)
+    }
+
+    /// Instruments the component `wasm` so that its state can be captured
+    /// with [`Wizer::snapshot_component`].
+    pub fn instrument_component(&self, wasm: &[u8]) -> Result<Vec<u8>> {
+        component::map_modules(wasm, &mut |_, module| {
+            let info = ModuleInfo::parse(module)?;
+            module::instrument(module, &info)
+        })
+    }
+
+    /// Captures the state of `instance` and returns a version of the
+    /// component `wasm` initialized with that state.
+    ///
+    /// The `instance` must be an instance of the result of
+    /// [`Wizer::instrument_component`] on `wasm`.
+    pub fn snapshot_component(
+        &self,
+        wasm: &[u8],
+        mut store: impl AsContextMut,
+        instance: &wasmtime::component::Instance,
+    ) -> Result<Vec<u8>> {
+        let mut store = store.as_context_mut();
+        let instances = instance.core_instances(&store);
+        component::map_modules(wasm, &mut |index, module| {
+            let mut instances = instances
+                .iter()
+                .filter(|(i, _)| *i == Some(index))
+                .map(|(_, instance)| instance);
+            let Some(instance) = instances.next() else {
+                // Modules which were never instantiated have no state to
+                // capture.
+                return Ok(module.to_vec());
+            };
+            if instances.next().is_some() {
+                anyhow::bail!(
+                    "cannot pre-initialize component: module {index} is \
+                     instantiated more than once"
+                );
+            }
+            let info = ModuleInfo::parse(module)?;
+            let snapshot = Snapshot::new(&mut store, &info, instance)
+                .with_context(|| format!("failed to snapshot module {index}"))?;
+            module::rewrite(module, &info, &snapshot, None)
+        })
+    }
+}
@@ -0,0 +1,400 @@
+//! Instrumenting, snapshotting, and rewriting core wasm modules.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use wasm_encoder::reencode::{Reencode, RoundtripReencoder};
+use wasm_encoder::{ConstExpr, DataSection, ExportKind, ExportSection, RawSection};
+use wasmparser::{DataKind, Parser, Payload, TypeRef, ValType};
+use wasmtime::{AsContextMut, Instance, Val};
+
+/// The maximum number of data segments emitted for a single memory.
+///
+/// Wasmtime, like other engines, limits the number of data segments a module
+/// may have, so memories with many small non-zero regions are emitted with
+/// some zeros included in their segments instead.
+const MAX_DATA_SEGMENTS: usize = 10_000;
+
+/// The prefix of exports added by instrumentation.
+const EXPORT_PREFIX: &str = "__wasmtime_wizer_";
+
+fn memory_export_name(index: u32) -> String {
+    format!("{EXPORT_PREFIX}memory_{index}")
+}
+
+fn global_export_name(index: u32) -> String {
+    format!("{EXPORT_PREFIX}global_{index}")
+}
+
+/// The parts of a module relevant to pre-initialization.
+pub(crate) struct ModuleInfo {
+    /// The number of imported memories.
+    imported_memories: u32,
+    /// The number of imported globals.
+    imported_globals: u32,
+    /// The types of the module's defined memories.
+    memories: Vec<wasmparser::MemoryType>,
+    /// The types of the module's defined globals.
+    globals: Vec<wasmparser::GlobalType>,
+    /// The names of the module's exports.
+    exports: HashSet<String>,
+}
+
+impl ModuleInfo {
+    pub(crate) fn parse(wasm: &[u8]) -> Result<ModuleInfo> {
+        let mut info = ModuleInfo {
+            imported_memories: 0,
+            imported_globals: 0,
+            memories: Vec::new(),
+            globals: Vec::new(),
+            exports: HashSet::new(),
+        };
+        for payload in Parser::new(0).parse_all(wasm) {
+            match payload? {
+                Payload::Version {
+                    encoding: wasmparser::Encoding::Component,
+                    ..
+                } => bail!("expected a core wasm module, found a component"),
+                Payload::ImportSection(imports) => {
+                    for import in imports {
+                        match import?.ty {
+                            TypeRef::Memory(_) => info.imported_memories += 1,
+                            TypeRef::Global(_) => info.imported_globals += 1,
+                            _ => {}
+                        }
+                    }
+                }
+                Payload::MemorySection(memories) => {
+                    for memory in memories {
+                        let memory = memory?;
+                        if memory.shared {
+                            bail!("pre-initializing modules with shared memories is not supported");
+                        }
+                        info.memories.push(memory);
+                    }
+                }
+                Payload::GlobalSection(globals) => {
+                    for global in globals {
+                        info.globals.push(global?.ty);
+                    }
+                }
+                Payload::ExportSection(exports) => {
+                    for export in exports {
+                        info.exports.insert(export?.name.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+
+    /// Returns an error if this module imports a memory.
+    ///
+    /// The contents of an imported memory are owned by whoever provides it, so
+    /// a standalone module that imports a memory can't have its contents
+    /// captured in its data segments.
+    pub(crate) fn ensure_no_imported_memories(&self) -> Result<()> {
+        if self.imported_memories > 0 {
+            bail!("pre-initializing modules which import memories is not supported");
+        }
+        Ok(())
+    }
+
+    fn defined_memory_indices(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.memories.len()).map(|i| self.imported_memories + u32::try_from(i).unwrap())
+    }
+
+    /// The indices of the module's defined, mutable globals, which are the
+    /// only globals whose values can change during initialization.
+    fn mutable_global_indices(&self) -> impl Iterator<Item = u32> + '_ {
+        self.globals
+            .iter()
+            .enumerate()
+            .filter(|(_, ty)| ty.mutable)
+            .map(|(i, _)| self.imported_globals + u32::try_from(i).unwrap())
+    }
+}
+
+/// Add exports for all of the defined memories and mutable globals of a
+/// module, so that their state can be read after initialization.
+pub(crate) fn instrument(wasm: &[u8], info: &ModuleInfo) -> Result<Vec<u8>> {
+    let mut added = Vec::new();
+    for index in info.defined_memory_indices() {
+        added.push((memory_export_name(index), ExportKind::Memory, index));
+    }
+    for index in info.mutable_global_indices() {
+        added.push((global_export_name(index), ExportKind::Global, index));
+    }
+    if let Some((name, ..)) = added.iter().find(|(name, ..)| info.exports.contains(name)) {
+        bail!("module already has an export named `{name}`");
+    }
+    let emit_exports = |module: &mut wasm_encoder::Module, existing: Option<_>| -> Result<()> {
+        let mut exports = ExportSection::new();
+        if let Some(existing) = existing {
+            RoundtripReencoder.parse_export_section(&mut exports, existing)?;
+        }
+        for (name, kind, index) in &added {
+            exports.export(name, *kind, *index);
+        }
+        module.section(&exports);
+        Ok(())
+    };
+
+    let mut module = wasm_encoder::Module::new();
+    let mut emitted_exports = false;
+    for payload in Parser::new(0).parse_all(wasm) {
+        let payload = payload?;
+        match &payload {
+            Payload::ExportSection(exports) => {
+                emit_exports(&mut module, Some(exports.clone()))?;
+                emitted_exports = true;
+                continue;
+            }
+
+            // The export section comes before all of these sections, so if
+            // we reach one of them without having seen an export section
+            // then one needs to be added.
+            Payload::StartSection { .. }
+            | Payload::ElementSection(_)
+            | Payload::DataCountSection { .. }
+            | Payload::CodeSectionStart { .. }
+            | Payload::DataSection(_)
+            | Payload::End(_)
+                if !emitted_exports =>
+            {
+                emit_exports(&mut module, None)?;
+                emitted_exports = true;
+            }
+            _ => {}
+        }
+        copy_section(&mut module, wasm, &payload);
+    }
+    Ok(module.finish())
+}
+
+/// The state of a module's instance after initialization.
+pub(crate) struct Snapshot {
+    /// The size in pages and contents of each defined memory.
+    memories: Vec<(u64, Vec<u8>)>,
+    /// The value of each defined, mutable global, as an initializer
+    /// expression.
+    globals: Vec<(u32, ConstExpr)>,
+}
+
+impl Snapshot {
+    /// Read the state of an instance of a module instrumented with
+    /// [`instrument`].
+    pub(crate) fn new(
+        mut store: impl AsContextMut,
+        info: &ModuleInfo,
+        instance: &Instance,
+    ) -> Result<Snapshot> {
+        let mut store = store.as_context_mut();
+
+        let mut memories = Vec::new();
+        for index in info.defined_memory_indices() {
+            let memory = instance
+                .get_memory(&mut store, &memory_export_name(index))
+                .context("instance is not an instance of an instrumented module")?;
+            memories.push((memory.size(&store), memory.data(&store).to_vec()));
+        }
+
+        let mut globals = Vec::new();
+        for index in info.mutable_global_indices() {
+            let global = instance
+                .get_global(&mut store, &global_export_name(index))
+                .context("instance is not an instance of an instrumented module")?;
+            let ty = info.globals[usize::try_from(index - info.imported_globals).unwrap()];
+            let init = match global.get(&mut store) {
+                Val::I32(x) => ConstExpr::i32_const(x),
+                Val::I64(x) => ConstExpr::i64_const(x),
+                Val::F32(x) => ConstExpr::f32_const(f32::from_bits(x)),
+                Val::F64(x) => ConstExpr::f64_const(f64::from_bits(x)),
+                Val::V128(x) => ConstExpr::v128_const(x.as_u128() as i128),
+                Val::FuncRef(None) | Val::ExternRef(None) | Val::AnyRef(None) => {
+                    let ValType::Ref(ty) = ty.content_type else {
+                        unreachable!()
+                    };
+                    ConstExpr::ref_null(RoundtripReencoder.heap_type(ty.heap_type())?)
+                }
+                Val::FuncRef(Some(_)) | Val::ExternRef(Some(_)) | Val::AnyRef(Some(_)) => {
+                    bail!("cannot snapshot global {index}, which holds a non-null reference")
+                }
+            };
+            globals.push((index, init));
+        }
+
+        Ok(Snapshot { memories, globals })
+    }
+}
+
+/// Rewrite a module so that instantiating it produces the state in
+/// `snapshot`.
+///
+/// The module's start function is removed, since its effects are already
+/// captured by the snapshot, and so is the export named `remove_export`, if
+/// any.
+pub(crate) fn rewrite(
+    wasm: &[u8],
+    info: &ModuleInfo,
+    snapshot: &Snapshot,
+    remove_export: Option<&str>,
+) -> Result<Vec<u8>> {
+    // Build the new active data segments first, since the data count section,
+    // if any, comes before the data section.
+    let mut new_segments = Vec::new();
+    for ((index, ty), (_, data)) in info
+        .defined_memory_indices()
+        .zip(&info.memories)
+        .zip(&snapshot.memories)
+    {
+        for (offset, bytes) in data_segments(data) {
+            let offset = if ty.memory64 {
+                ConstExpr::i64_const(offset as i64)
+            } else {
+                ConstExpr::i32_const(offset as i32)
+            };
+            new_segments.push((index, offset, bytes));
+        }
+    }
+
+    let mut module = wasm_encoder::Module::new();
+    let mut emitted_data = false;
+    for payload in Parser::new(0).parse_all(wasm) {
+        let payload = payload?;
+        match payload {
+            Payload::MemorySection(memories) => {
+                let mut section = wasm_encoder::MemorySection::new();
+                for (memory, (size, _)) in memories.into_iter().zip(&snapshot.memories) {
+                    let mut ty = RoundtripReencoder.memory_type(memory?);
+                    ty.minimum = *size;
+                    section.memory(ty);
+                }
+                module.section(&section);
+            }
+
+            Payload::GlobalSection(globals) => {
+                let mut section = wasm_encoder::GlobalSection::new();
+                let mut values = snapshot.globals.iter().peekable();
+                for (i, global) in globals.into_iter().enumerate() {
+                    let global = global?;
+                    let index = info.imported_globals + u32::try_from(i).unwrap();
+                    let init = match values.next_if(|(j, _)| *j == index) {
+                        Some((_, init)) => init.clone(),
+                        None => RoundtripReencoder.const_expr(global.init_expr)?,
+                    };
+                    section.global(RoundtripReencoder.global_type(global.ty)?, &init);
+                }
+                module.section(&section);
+            }
+
+            Payload::ExportSection(exports) => {
+                let mut section = ExportSection::new();
+                for export in exports {
+                    let export = export?;
+                    if Some(export.name) == remove_export {
+                        continue;
+                    }
+                    let kind = RoundtripReencoder.export_kind(export.kind);
+                    section.export(export.name, kind, export.index);
+                }
+                module.section(&section);
+            }
+
+            Payload::StartSection { .. } => {}
+
+            Payload::DataCountSection { count, .. } => {
+                let count = count + u32::try_from(new_segments.len()).unwrap();
+                module.section(&wasm_encoder::DataCountSection { count });
+            }
+
+            // Active segments have already been applied, and are replaced by
+            // the new segments capturing the snapshot. To keep the indices of
+            // passive segments intact, each is replaced by an empty passive
+            // segment, which behaves just like an active segment does after
+            // instantiation.
+            Payload::DataSection(data) => {
+                let mut section = DataSection::new();
+                for segment in data {
+                    let segment = segment?;
+                    match segment.kind {
+                        DataKind::Passive => section.passive(segment.data.iter().copied()),
+                        DataKind::Active { .. } => section.passive([]),
+                    };
+                }
+                add_segments(&mut section, &new_segments);
+                module.section(&section);
+                emitted_data = true;
+            }
+
+            Payload::End(_) => {
+                if !emitted_data && !new_segments.is_empty() {
+                    let mut section = DataSection::new();
+                    add_segments(&mut section, &new_segments);
+                    module.section(&section);
+                }
+            }
+
+            payload => copy_section(&mut module, wasm, &payload),
+        }
+    }
+    Ok(module.finish())
+}
+
+fn add_segments(section: &mut DataSection, segments: &[(u32, ConstExpr, &[u8])]) {
+    for (memory, offset, bytes) in segments {
+        section.active(*memory, offset, bytes.iter().copied());
+    }
+}
+
+/// Copy `payload` into `module` as-is, if it's a section.
+fn copy_section(module: &mut wasm_encoder::Module, wasm: &[u8], payload: &Payload<'_>) {
+    if let Some((id, range)) = payload.as_section() {
+        module.section(&RawSection {
+            id,
+            data: &wasm[range],
+        });
+    }
+}
+
+/// Split the contents of a memory into data segments covering its non-zero
+/// bytes, returning each segment's offset and contents.
+fn data_segments(memory: &[u8]) -> Vec<(u64, &[u8])> {
+    // Start with one segment per run of non-zero bytes.
+    let mut runs = Vec::new();
+    let mut i = 0;
+    while i < memory.len() {
+        if memory[i] == 0 {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < memory.len() && memory[i] != 0 {
+            i += 1;
+        }
+        runs.push(start..i);
+    }
+
+    // Merge runs separated by fewer zeros than it takes to encode a segment
+    // header, and then by ever-larger gaps as needed to stay under the limit
+    // on segments.
+    let mut min_gap = 8;
+    loop {
+        let mut merged: Vec<std::ops::Range<usize>> = Vec::new();
+        for run in &runs {
+            match merged.last_mut() {
+                Some(last) if run.start - last.end < min_gap => last.end = run.end,
+                _ => merged.push(run.clone()),
+            }
+        }
+        runs = merged;
+        if runs.len() <= MAX_DATA_SEGMENTS {
+            break;
+        }
+        min_gap *= 2;
+    }
+
+    runs.into_iter()
+        .map(|run| (u64::try_from(run.start).unwrap(), &memory[run]))
+        .collect()
+}
@@ -0,0 +1,235 @@
+use anyhow::Result;
+use wasmtime::{Engine, Instance, Linker, Module, Store};
+use wasmtime_wizer::Wizer;
+
+fn wizen(wizer: &Wizer, wat: &str) -> Result<Vec<u8>> {
+    let wasm = wat::parse_str(wat)?;
+    let engine = Engine::default();
+    let linker = Linker::new(&engine);
+    let mut store = Store::new(&engine, ());
+    let wasm = wizer.run(&mut store, &wasm, |store, module| {
+        linker.instantiate(store, module)
+    })?;
+    wasmparser::Validator::new().validate_all(&wasm)?;
+    Ok(wasm)
+}
+
+fn instantiate(wasm: &[u8]) -> Result<(Store<()>, Instance)> {
+    let engine = Engine::default();
+    let module = Module::new(&engine, wasm)?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    Ok((store, instance))
+}
+
+#[test]
+fn captures_memories_and_globals() -> Result<()> {
+    let wasm = wizen(
+        &Wizer::new(),
+        r#"
+            (module
+                (memory (export "memory") 1)
+                (global $g (export "g") (mut i32) (i32.const 0))
+                (global $f (export "f") (mut f64) (f64.const 0))
+                (global $c (export "c") i64 (i64.const 7))
+                (global $started (export "started") (mut i32) (i32.const 0))
+                (data (i32.const 0) "hello")
+
+                (func $start
+                    (global.set $started
+                        (i32.add (global.get $started) (i32.const 1))))
+                (start $start)
+
+                (func (export "wizer.initialize")
+                    (global.set $g (i32.const 42))
+                    (global.set $f (f64.const 1.5))
+                    (drop (memory.grow (i32.const 1)))
+                    (i32.store (i32.const 1000) (i32.const 0x01020304))
+                    (i32.store (i32.const 65540) (i32.const 0x05060708)))
+            )
+        "#,
+    )?;
+
+    let (mut store, instance) = instantiate(&wasm)?;
+    assert!(instance.get_func(&mut store, "wizer.initialize").is_none());
+
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    assert_eq!(memory.size(&store), 2);
+    let data = memory.data(&store);
+    assert_eq!(&data[0..5], b"hello");
+    assert_eq!(data[1000..1004], 0x01020304u32.to_le_bytes());
+    assert_eq!(data[65540..65544], 0x05060708u32.to_le_bytes());
+
+    let global = |store: &mut Store<()>, name| instance.get_global(&mut *store, name).unwrap();
+    assert_eq!(global(&mut store, "g").get(&mut store).unwrap_i32(), 42);
+    assert_eq!(global(&mut store, "f").get(&mut store).unwrap_f64(), 1.5);
+    assert_eq!(global(&mut store, "c").get(&mut store).unwrap_i64(), 7);
+
+    // The start function ran once before initialization, and isn't run again.
+    assert_eq!(
+        global(&mut store, "started").get(&mut store).unwrap_i32(),
+        1
+    );
+    Ok(())
+}
+
+#[test]
+fn keep_init_func() -> Result<()> {
+    let wasm = wizen(
+        Wizer::new().init_func("init").keep_init_func(true),
+        r#"
+            (module
+                (global $g (export "g") (mut i32) (i32.const 0))
+                (func (export "init")
+                    (global.set $g (i32.add (global.get $g) (i32.const 1))))
+            )
+        "#,
+    )?;
+
+    let (mut store, instance) = instantiate(&wasm)?;
+    let g = instance.get_global(&mut store, "g").unwrap();
+    assert_eq!(g.get(&mut store).unwrap_i32(), 1);
+    let init = instance.get_typed_func::<(), ()>(&mut store, "init")?;
+    init.call(&mut store, ())?;
+    assert_eq!(g.get(&mut store).unwrap_i32(), 2);
+    Ok(())
+}
+
+#[test]
+fn preserves_passive_segments() -> Result<()> {
+    let wasm = wizen(
+        &Wizer::new(),
+        r#"
+            (module
+                (memory (export "memory") 1)
+                (data (i32.const 0) "active")
+                (data $passive "passive")
+                (func (export "wizer.initialize")
+                    (i32.store8 (i32.const 0) (i32.const 0x41)))
+                (func (export "copy") (param i32)
+                    (memory.init $passive (local.get 0) (i32.const 0) (i32.const 7)))
+            )
+        "#,
+    )?;
+
+    let (mut store, instance) = instantiate(&wasm)?;
+    let copy = instance.get_typed_func::<i32, ()>(&mut store, "copy")?;
+    copy.call(&mut store, 100)?;
+    let memory = instance.get_memory(&mut store, "memory").unwrap();
+    assert_eq!(&memory.data(&store)[0..6], b"Active");
+    assert_eq!(&memory.data(&store)[100..107], b"passive");
+    Ok(())
+}
+
+#[test]
+fn rejects_imported_memories() -> Result<()> {
+    let wasm = wat::parse_str(
+        r#"
+            (module
+                (import "" "memory" (memory 1))
+                (func (export "wizer.initialize"))
+            )
+        "#,
+    )?;
+    let err = Wizer::new().instrument(&wasm).unwrap_err();
+    assert!(err.to_string().contains("import memories"), "{err}");
+    Ok(())
+}
+
+#[test]
+fn rejects_non_null_references() -> Result<()> {
+    let err = wizen(
+        &Wizer::new(),
+        r#"
+            (module
+                (global $g (mut funcref) (ref.null func))
+                (func $f)
+                (elem declare func $f)
+                (func (export "wizer.initialize")
+                    (global.set $g (ref.func $f)))
+            )
+        "#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("non-null reference"), "{err}");
+    Ok(())
+}
+
+#[test]
+fn missing_init_func() -> Result<()> {
+    let err = wizen(&Wizer::new(), "(module)").unwrap_err();
+    assert!(err.to_string().contains("wizer.initialize"), "{err}");
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "component-model")]
+fn component() -> Result<()> {
+    use wasmtime::component::{Component, Linker};
+
+    let wasm = wat::parse_str(
+        r#"
+            (component
+                (core module $m
+                    (memory (export "memory") 1)
+                    (global $g (mut i32) (i32.const 0))
+                    (func (export "init")
+                        (global.set $g (i32.const 10))
+                        (i32.store (i32.const 16) (i32.const 32)))
+                    (func (export "get") (result i32)
+                        (i32.add (global.get $g) (i32.load (i32.const 16))))
+                )
+                (core module $unused
+                    (func (export "f"))
+                )
+                (core instance $i (instantiate $m))
+                (func (export "wizer-initialize") (canon lift (core func $i "init")))
+                (func (export "get") (result u32) (canon lift (core func $i "get")))
+            )
+        "#,
+    )?;
+
+    let engine = Engine::default();
+    let linker = Linker::new(&engine);
+    let mut store = Store::new(&engine, ());
+    let wasm = Wizer::new().run_component(&mut store, &wasm, |store, component| {
+        linker.instantiate(store, component)
+    })?;
+
+    let component = Component::new(&engine, &wasm)?;
+    let mut store = Store::new(&engine, ());
+    let instance = linker.instantiate(&mut store, &component)?;
+    let get = instance.get_typed_func::<(), (u32,)>(&mut store, "get")?;
+    assert_eq!(get.call(&mut store, ())?, (42,));
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "component-model")]
+fn component_with_module_instantiated_twice() -> Result<()> {
+    use wasmtime::component::Linker;
+
+    let wasm = wat::parse_str(
+        r#"
+            (component
+                (core module $m
+                    (func (export "init"))
+                )
+                (core instance $a (instantiate $m))
+                (core instance $b (instantiate $m))
+                (func (export "wizer-initialize") (canon lift (core func $a "init")))
+            )
+        "#,
+    )?;
+
+    let engine = Engine::default();
+    let linker = Linker::new(&engine);
+    let mut store = Store::new(&engine, ());
+    let err = Wizer::new()
+        .run_component(&mut store, &wasm, |store, component| {
+            linker.instantiate(store, component)
+        })
+        .unwrap_err();
+    assert!(err.to_string().contains("more than once"), "{err}");
+    Ok(())
+}
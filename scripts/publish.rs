@@ -76,6 +76,7 @@ const CRATES_TO_PUBLISH: &[&str] = &[
     "wasmtime-c-api-impl",
     "wasmtime-cli-flags",
     "wasmtime-explorer",
+    "wasmtime-wizer",
     "wasmtime-cli",
 ];
 
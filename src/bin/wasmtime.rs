@@ -78,6 +78,14 @@ enum Subcommand {
     #[cfg(feature = "cranelift")]
     Settings(wasmtime_cli::commands::SettingsCommand),
 
+    /// Pre-initializes a WebAssembly module or component
+    #[cfg(feature = "wizer")]
+    Wizer(wasmtime_cli::commands::WizerCommand),
+
+    /// Interactively calls the exports of a WebAssembly module or component
+    #[cfg(feature = "repl")]
+    Repl(wasmtime_cli::commands::ReplCommand),
+
     /// Runs a WebAssembly test script file
     #[cfg(feature = "wast")]
     Wast(wasmtime_cli::commands::WastCommand),
@@ -114,6 +122,12 @@ impl Wasmtime {
             #[cfg(feature = "cranelift")]
             Subcommand::Settings(c) => c.execute(),
 
+            #[cfg(feature = "wizer")]
+            Subcommand::Wizer(c) => c.execute(),
+
+            #[cfg(feature = "repl")]
+            Subcommand::Repl(c) => c.execute(),
+
             #[cfg(feature = "wast")]
             Subcommand::Wast(c) => c.execute(),
 
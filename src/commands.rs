@@ -15,6 +15,16 @@ mod explore;
 #[cfg(feature = "explore")]
 pub use self::explore::*;
 
+#[cfg(feature = "wizer")]
+mod wizer;
+#[cfg(feature = "wizer")]
+pub use self::wizer::*;
+
+#[cfg(feature = "repl")]
+mod repl;
+#[cfg(feature = "repl")]
+pub use self::repl::*;
+
 #[cfg(feature = "wast")]
 mod wast;
 #[cfg(feature = "wast")]
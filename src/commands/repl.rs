@@ -0,0 +1,536 @@
+//! The module that implements the `wasmtime repl` command.
+
+use crate::commands::run::{CliLinker, Host};
+use crate::commands::RunCommand;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
+use std::io::{BufRead, IsTerminal, Write};
+use tokio::runtime::Runtime;
+use wasmtime::component::types::ComponentItem;
+use wasmtime::component::{self, Type, WaveHandles};
+use wasmtime::{Engine, Extern, Mutability, Store, Val};
+
+const HELP: &str = "\
+Calls are written as `NAME(ARGS)` where `NAME` is the name of an exported
+function and `ARGS` are its arguments in the WebAssembly Value Encoding (WAVE),
+for example `add(1, 2)` or `wasi:cli/run@0.2.0#run()`. Resources returned from
+calls are named `@handle(N)` and may be passed to later calls.
+
+Commands:
+  :exports                       list the exports and their WIT-like types
+  :handles                       list the resource handles returned so far
+  :drop N                        drop the resource `@handle(N)`
+  :memory NAME [OFFSET [LEN]]    dump the contents of an exported memory
+  :global NAME                   print the value of an exported global
+  :help                          print this message
+  :quit                          exit";
+
+/// Interactively calls the exports of a WebAssembly module or component.
+///
+/// The module or component is instantiated with the WASI and other options
+/// given, and then exports are called with arguments read from standard input,
+/// one call per line. All calls share the same store, so state such as
+/// memories, globals and resources carries over from one call to the next.
+/// Type `:help` for the list of commands.
+#[derive(Parser)]
+pub struct ReplCommand {
+    #[command(flatten)]
+    #[allow(missing_docs, reason = "the flattened options have their own docs")]
+    run: RunCommand,
+}
+
+impl ReplCommand {
+    /// Executes the command.
+    pub fn execute(mut self) -> Result<()> {
+        self.run.run.common.init_logging()?;
+
+        if self.run.invoke.is_some() {
+            bail!("`--invoke` is not supported in the REPL");
+        }
+        if !self.run.preloads.is_empty() {
+            bail!("`--preload` is not supported in the REPL");
+        }
+        #[cfg(feature = "debug-adapter")]
+        if self.run.debug_adapter.is_some() {
+            bail!("`--debug-adapter` is not supported in the REPL");
+        }
+
+        let mut config = self.run.run.common.config(None)?;
+        config.async_support(true);
+        let engine = Engine::new(&config)?;
+
+        let main = self
+            .run
+            .run
+            .load_module(&engine, self.run.module_and_args[0].as_ref())?;
+        let (mut store, mut linker) = self.run.new_store_and_linker(&engine, &main)?;
+        self.run.define_unknown_imports(&mut linker, &main)?;
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_time()
+            .enable_io()
+            .build()?;
+
+        let target = runtime
+            .block_on(async {
+                match &mut linker {
+                    CliLinker::Core(linker) => {
+                        if !self.run.virtualize.is_empty() {
+                            bail!("--virtualize can only be used with components");
+                        }
+                        let instance = linker
+                            .instantiate_async(&mut store, main.unwrap_core())
+                            .await?;
+                        if let Some(func) = instance.get_func(&mut store, "_initialize") {
+                            func.typed::<(), ()>(&store)?
+                                .call_async(&mut store, ())
+                                .await?;
+                        }
+                        Ok(Target::Core(instance))
+                    }
+                    CliLinker::Component(linker) => {
+                        let component = main.unwrap_component();
+                        self.run
+                            .link_virtualizers(&engine, &mut store, linker, component)
+                            .await?;
+                        let instance = linker.instantiate_async(&mut store, component).await?;
+                        Ok(Target::Component(instance, component.clone()))
+                    }
+                }
+            })
+            .with_context(|| {
+                format!(
+                    "failed to instantiate {:?}",
+                    self.run.module_and_args[0].to_string_lossy()
+                )
+            })?;
+
+        let mut type_names = Vec::new();
+        if let Target::Component(_, component) = &target {
+            let ty = component.component_type();
+            collect_type_names(&engine, ty.imports(&engine), &mut type_names);
+            collect_type_names(&engine, ty.exports(&engine), &mut type_names);
+        }
+        let mut repl = Repl {
+            engine,
+            runtime,
+            store,
+            target,
+            handles: WaveHandles::new(),
+            type_names,
+        };
+
+        let interactive = std::io::stdin().is_terminal();
+        if interactive {
+            repl.print_exports()?;
+            println!("Type `:help` for help.");
+        }
+        let mut lines = std::io::stdin().lock().lines();
+        loop {
+            if interactive {
+                print!("> ");
+                std::io::stdout().flush()?;
+            }
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match repl.eval(line) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => eprintln!("error: {e:#}"),
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Target {
+    Core(wasmtime::Instance),
+    Component(component::Instance, component::Component),
+}
+
+struct Repl {
+    engine: Engine,
+    runtime: Runtime,
+    store: Store<Host>,
+    target: Target,
+    handles: WaveHandles,
+    /// The names of the types imported and exported by a component, to print
+    /// them by name rather than by structure.
+    type_names: Vec<(Type, String)>,
+}
+
+fn collect_type_names<'a>(
+    engine: &Engine,
+    items: impl Iterator<Item = (&'a str, ComponentItem)>,
+    names: &mut Vec<(Type, String)>,
+) {
+    for (name, item) in items {
+        match item {
+            ComponentItem::Type(ty) => names.push((ty, name.to_string())),
+            ComponentItem::ComponentInstance(instance) => {
+                collect_type_names(engine, instance.exports(engine), names)
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Repl {
+    /// Evaluates one line of input, returning whether to keep going.
+    fn eval(&mut self, line: &str) -> Result<bool> {
+        let Some(command) = line.strip_prefix(':') else {
+            let (name, args) = line.split_at(line.find('(').ok_or_else(|| {
+                anyhow!("expected a call such as `name(args)` or a command; see `:help`")
+            })?);
+            self.call(name.trim(), args)?;
+            return Ok(true);
+        };
+        let mut words = command.split_whitespace();
+        let mut arg = |name: &str| {
+            words
+                .next()
+                .ok_or_else(|| anyhow!("missing argument `{name}`; see `:help`"))
+        };
+        match arg("command")? {
+            "help" | "h" => println!("{HELP}"),
+            "quit" | "q" => return Ok(false),
+            "exports" => self.print_exports()?,
+            "handles" => {
+                for (n, resource) in self.handles.iter() {
+                    let name = resource.type_name(&self.store);
+                    let name = name.as_deref().unwrap_or("resource");
+                    if resource.owned() {
+                        println!("@handle({n}): {name}");
+                    } else {
+                        println!("@handle({n}): borrow<{name}>");
+                    }
+                }
+            }
+            "drop" => {
+                let n = arg("N")?;
+                let resource = n
+                    .parse()
+                    .ok()
+                    .and_then(|n| self.handles.remove(n))
+                    .ok_or_else(|| anyhow!("unknown handle `@handle({n})`"))?;
+                self.runtime
+                    .block_on(resource.resource_drop_async(&mut self.store))?;
+            }
+            "memory" => {
+                let name = arg("NAME")?;
+                let offset = words.next().map(parse_u64).transpose()?.unwrap_or(0);
+                let len = words.next().map(parse_u64).transpose()?.unwrap_or(64);
+                self.print_memory(name, offset, len)?;
+            }
+            "global" => {
+                let name = arg("NAME")?;
+                let global = self
+                    .core_instance()?
+                    .get_global(&mut self.store, name)
+                    .ok_or_else(|| anyhow!("no global export named `{name}` found"))?;
+                println!("{}", core_val(&global.get(&mut self.store)));
+            }
+            other => bail!("unknown command `:{other}`; see `:help`"),
+        }
+        Ok(true)
+    }
+
+    /// Calls the export `name` with the WAVE-encoded tuple `args`, printing
+    /// its results.
+    fn call(&mut self, name: &str, args: &str) -> Result<()> {
+        match &self.target {
+            Target::Core(instance) => {
+                let func = instance
+                    .get_func(&mut self.store, name)
+                    .ok_or_else(|| anyhow!("no func export named `{name}` found"))?;
+                let ty = func.ty(&self.store);
+                let params = ty.params().collect::<Vec<_>>();
+                let args = Val::from_wave_args(&params, args)?;
+                let mut results = vec![Val::null_func_ref(); ty.results().len()];
+                self.runtime
+                    .block_on(func.call_async(&mut self.store, &args, &mut results))
+                    .with_context(|| format!("failed to invoke `{name}`"))?;
+                for result in results {
+                    println!("{}", core_val(&result));
+                }
+            }
+            Target::Component(instance, _) => {
+                let mut index = None;
+                for part in name.split('#') {
+                    index = Some(
+                        instance
+                            .get_export(&mut self.store, index.as_ref(), part)
+                            .ok_or_else(|| anyhow!("no export named `{name}` found"))?,
+                    );
+                }
+                let func = index
+                    .and_then(|index| instance.get_func(&mut self.store, index))
+                    .ok_or_else(|| anyhow!("no func export named `{name}` found"))?;
+                let params = func
+                    .params(&self.store)
+                    .iter()
+                    .map(|(_, ty)| ty.clone())
+                    .collect::<Vec<_>>();
+                let args =
+                    component::Val::from_wave_args_with_handles(&params, args, &mut self.handles)?;
+                let mut results =
+                    vec![component::Val::Bool(false); func.results(&self.store).len()];
+                self.runtime
+                    .block_on(async {
+                        func.call_async(&mut self.store, &args, &mut results)
+                            .await?;
+                        func.post_return_async(&mut self.store).await
+                    })
+                    .with_context(|| format!("failed to invoke `{name}`"))?;
+                for result in results {
+                    println!("{}", result.to_wave_with_handles(&mut self.handles)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn print_exports(&mut self) -> Result<()> {
+        match &self.target {
+            Target::Core(instance) => {
+                let exports = instance
+                    .exports(&mut self.store)
+                    .map(|e| (e.name().to_string(), e.into_extern()))
+                    .collect::<Vec<_>>();
+                for (name, export) in exports {
+                    let desc = match export {
+                        Extern::Func(func) => {
+                            let ty = func.ty(&self.store);
+                            let params = ty.params().map(|t| t.to_string()).collect::<Vec<_>>();
+                            let results = ty.results().map(|t| t.to_string()).collect::<Vec<_>>();
+                            let mut desc = format!("func({})", params.join(", "));
+                            match results.len() {
+                                0 => {}
+                                1 => desc.push_str(&format!(" -> {}", results[0])),
+                                _ => desc.push_str(&format!(" -> ({})", results.join(", "))),
+                            }
+                            desc
+                        }
+                        Extern::Global(global) => {
+                            let ty = global.ty(&self.store);
+                            let mutability = match ty.mutability() {
+                                Mutability::Var => "mut ",
+                                Mutability::Const => "",
+                            };
+                            let val = core_val(&global.get(&mut self.store));
+                            format!("global {mutability}{} = {val}", ty.content())
+                        }
+                        Extern::Table(table) => format!(
+                            "table {} ({} elements)",
+                            table.ty(&self.store).element(),
+                            table.size(&self.store)
+                        ),
+                        Extern::Memory(memory) => {
+                            format!("memory ({} pages)", memory.size(&self.store))
+                        }
+                        Extern::SharedMemory(memory) => {
+                            format!("shared memory ({} pages)", memory.size())
+                        }
+                    };
+                    println!("{name}: {desc}");
+                }
+            }
+            Target::Component(instance, component) => {
+                let instance = *instance;
+                let ty = component.component_type();
+                let mut exports = ty
+                    .exports(&self.engine)
+                    .map(|(name, item)| (name.to_string(), item, None))
+                    .collect::<Vec<_>>();
+                exports.reverse();
+                while let Some((name, item, parent)) = exports.pop() {
+                    let leaf = name.rsplit('#').next().unwrap_or(&name);
+                    let index = instance
+                        .get_export(&mut self.store, parent.as_ref(), leaf)
+                        .ok_or_else(|| anyhow!("no export named `{name}` found"))?;
+                    let desc = match item {
+                        ComponentItem::ComponentFunc(_) => {
+                            let func = instance
+                                .get_func(&mut self.store, &index)
+                                .ok_or_else(|| anyhow!("no func export named `{name}` found"))?;
+                            let params = func
+                                .params(&self.store)
+                                .iter()
+                                .map(|(name, ty)| format!("{name}: {}", self.wit(ty)))
+                                .collect::<Vec<_>>();
+                            let results = func
+                                .results(&self.store)
+                                .iter()
+                                .map(|ty| self.wit(ty))
+                                .collect::<Vec<_>>();
+                            let mut desc = format!("func({})", params.join(", "));
+                            match results.len() {
+                                0 => {}
+                                1 => desc.push_str(&format!(" -> {}", results[0])),
+                                _ => desc.push_str(&format!(" -> ({})", results.join(", "))),
+                            }
+                            desc
+                        }
+                        ComponentItem::ComponentInstance(ty) => {
+                            let mut nested = ty
+                                .exports(&self.engine)
+                                .map(|(n, item)| (format!("{name}#{n}"), item, Some(index)))
+                                .collect::<Vec<_>>();
+                            nested.reverse();
+                            exports.extend(nested);
+                            "instance".to_string()
+                        }
+                        ComponentItem::Resource(_) => "resource".to_string(),
+                        ComponentItem::Type(_) => "type".to_string(),
+                        ComponentItem::Module(_) => "module".to_string(),
+                        ComponentItem::Component(_) => "component".to_string(),
+                        ComponentItem::CoreFunc(_) => "core func".to_string(),
+                    };
+                    println!("{name}: {desc}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn print_memory(&mut self, name: &str, offset: u64, len: u64) -> Result<()> {
+        let export = self
+            .core_instance()?
+            .get_export(&mut self.store, name)
+            .ok_or_else(|| anyhow!("no memory export named `{name}` found"))?;
+        let data = match export {
+            Extern::Memory(memory) => memory.data(&self.store).to_vec(),
+            // SAFETY: the guest isn't running, so no other thread is accessing
+            // this memory right now.
+            Extern::SharedMemory(memory) => {
+                memory.data().iter().map(|b| unsafe { *b.get() }).collect()
+            }
+            _ => bail!("no memory export named `{name}` found"),
+        };
+        let start = usize::try_from(offset)
+            .unwrap_or(usize::MAX)
+            .min(data.len());
+        let end = usize::try_from(offset.saturating_add(len))
+            .unwrap_or(usize::MAX)
+            .min(data.len());
+        for (i, line) in data[start..end].chunks(16).enumerate() {
+            let hex = line
+                .iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii = line
+                .iter()
+                .map(|b| match *b {
+                    b' '..=b'~' => char::from(*b),
+                    _ => '.',
+                })
+                .collect::<String>();
+            println!("{:08x}: {hex:<47}  |{ascii}|", start + i * 16);
+        }
+        Ok(())
+    }
+
+    fn core_instance(&self) -> Result<wasmtime::Instance> {
+        match &self.target {
+            Target::Core(instance) => Ok(*instance),
+            Target::Component(..) => {
+                bail!("memories and globals can only be inspected in core modules")
+            }
+        }
+    }
+
+    /// Renders `ty` in WIT syntax.
+    ///
+    /// Records, variants, enums and flags are printed by the name the
+    /// component gives them, if any. Otherwise, as their structure can't be
+    /// written inline in WIT, they're printed in the syntax of their
+    /// definitions, such as `record { x: u32 }`.
+    fn wit(&self, ty: &Type) -> String {
+        if let Type::Record(_) | Type::Variant(_) | Type::Enum(_) | Type::Flags(_) = ty {
+            if let Some((_, name)) = self.type_names.iter().find(|(t, _)| t == ty) {
+                return name.clone();
+            }
+        }
+        let list = |tys: &mut dyn Iterator<Item = String>| tys.collect::<Vec<_>>().join(", ");
+        match ty {
+            Type::Bool => "bool".to_string(),
+            Type::S8 => "s8".to_string(),
+            Type::U8 => "u8".to_string(),
+            Type::S16 => "s16".to_string(),
+            Type::U16 => "u16".to_string(),
+            Type::S32 => "s32".to_string(),
+            Type::U32 => "u32".to_string(),
+            Type::S64 => "s64".to_string(),
+            Type::U64 => "u64".to_string(),
+            Type::Float32 => "f32".to_string(),
+            Type::Float64 => "f64".to_string(),
+            Type::Char => "char".to_string(),
+            Type::String => "string".to_string(),
+            Type::List(l) => format!("list<{}>", self.wit(&l.ty())),
+            Type::Record(r) => format!(
+                "record {{ {} }}",
+                list(
+                    &mut r
+                        .fields()
+                        .map(|f| format!("{}: {}", f.name, self.wit(&f.ty)))
+                )
+            ),
+            Type::Tuple(t) => format!("tuple<{}>", list(&mut t.types().map(|t| self.wit(&t)))),
+            Type::Variant(v) => format!(
+                "variant {{ {} }}",
+                list(&mut v.cases().map(|c| match &c.ty {
+                    Some(ty) => format!("{}({})", c.name, self.wit(ty)),
+                    None => c.name.to_string(),
+                }))
+            ),
+            Type::Enum(e) => format!("enum {{ {} }}", list(&mut e.names().map(String::from))),
+            Type::Option(o) => format!("option<{}>", self.wit(&o.ty())),
+            Type::Result(r) => match (r.ok(), r.err()) {
+                (None, None) => "result".to_string(),
+                (Some(ok), None) => format!("result<{}>", self.wit(&ok)),
+                (None, Some(err)) => format!("result<_, {}>", self.wit(&err)),
+                (Some(ok), Some(err)) => format!("result<{}, {}>", self.wit(&ok), self.wit(&err)),
+            },
+            Type::Flags(f) => format!("flags {{ {} }}", list(&mut f.names().map(String::from))),
+            Type::Own(r) => r
+                .name(&self.store)
+                .unwrap_or_else(|| "resource".to_string()),
+            Type::Borrow(r) => format!(
+                "borrow<{}>",
+                r.name(&self.store)
+                    .unwrap_or_else(|| "resource".to_string())
+            ),
+        }
+    }
+}
+
+fn parse_u64(s: &str) -> Result<u64> {
+    let n = match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    n.with_context(|| format!("invalid number `{s}`"))
+}
+
+fn core_val(val: &Val) -> String {
+    match val {
+        Val::I32(i) => i.to_string(),
+        Val::I64(i) => i.to_string(),
+        Val::F32(f) => f32::from_bits(*f).to_string(),
+        Val::F64(f) => f64::from_bits(*f).to_string(),
+        Val::V128(i) => i.as_u128().to_string(),
+        Val::ExternRef(None) => "<null externref>".to_string(),
+        Val::ExternRef(Some(_)) => "<externref>".to_string(),
+        Val::FuncRef(None) => "<null funcref>".to_string(),
+        Val::FuncRef(Some(_)) => "<funcref>".to_string(),
+        Val::AnyRef(None) => "<null anyref>".to_string(),
+        Val::AnyRef(Some(_)) => "<anyref>".to_string(),
+    }
+}
@@ -6,6 +6,8 @@
 )]
 
 use crate::common::{Profile, RunCommon, RunTarget};
+#[cfg(feature = "debug-adapter")]
+use crate::debug_adapter::DebugAdapter;
 
 use anyhow::{anyhow, bail, Context as _, Error, Result};
 use clap::Parser;
@@ -40,6 +42,16 @@ fn parse_preloads(s: &str) -> Result<(String, PathBuf)> {
     Ok((parts[0].into(), parts[1].into()))
 }
 
+/// Returns whether `export` of a `--virtualize` component is selected by
+/// `name`, which is either an interface or a package.
+#[cfg(feature = "component-model")]
+fn virtualizes(name: &str, export: &str) -> bool {
+    match export.strip_prefix(name) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/') || rest.starts_with('@'),
+        None => false,
+    }
+}
+
 /// Runs a WebAssembly module
 #[derive(Parser)]
 pub struct RunCommand {
@@ -60,6 +72,23 @@ pub struct RunCommand {
     )]
     pub preloads: Vec<(String, PathBuf)>,
 
+    /// Implement the given WASI interface or package with a WebAssembly
+    /// component instead of Wasmtime's host implementation.
+    ///
+    /// The `NAME` is either an interface such as `wasi:filesystem/types` or a
+    /// package such as `wasi:filesystem`, in which case every interface of
+    /// that package exported by the component is used. The virtualizing
+    /// component is itself instantiated with Wasmtime's host implementations
+    /// of WASI, and those of any previous `--virtualize` components. This can
+    /// only be used when running components.
+    #[arg(
+        long = "virtualize",
+        number_of_values = 1,
+        value_name = "NAME=COMPONENT_PATH",
+        value_parser = parse_preloads,
+    )]
+    pub virtualize: Vec<(String, PathBuf)>,
+
     /// Override the value of `argv[0]`, typically the name of the executable of
     /// the application being run.
     ///
@@ -69,6 +98,18 @@ pub struct RunCommand {
     #[arg(long)]
     pub argv0: Option<String>,
 
+    /// Wait for a debugger to connect to the given port or address, and let
+    /// it control execution of the program with the Debug Adapter Protocol.
+    ///
+    /// This supports breakpoints on source lines, if the program has DWARF
+    /// debug information, or on offsets of instructions in the WebAssembly
+    /// module, as well as stepping and inspecting locals, globals and
+    /// memories. Line and instruction breakpoints are only supported for
+    /// core modules.
+    #[cfg(feature = "debug-adapter")]
+    #[arg(long, value_name = "PORT|ADDR")]
+    pub debug_adapter: Option<String>,
+
     /// The WebAssembly module to run and arguments to pass to it.
     ///
     /// Arguments passed to the wasm module will be configured as WASI CLI
@@ -78,7 +119,7 @@ pub struct RunCommand {
     pub module_and_args: Vec<OsString>,
 }
 
-enum CliLinker {
+pub(crate) enum CliLinker {
     Core(wasmtime::Linker<Host>),
     #[cfg(feature = "component-model")]
     Component(wasmtime::component::Linker<Host>),
@@ -95,6 +136,11 @@ impl RunCommand {
         if self.run.common.wasm.timeout.is_some() {
             config.epoch_interruption(true);
         }
+        #[cfg(feature = "debug-adapter")]
+        if self.debug_adapter.is_some() {
+            config.guest_debug(true);
+            config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
+        }
         match self.run.profile {
             Some(Profile::Native(s)) => {
                 config.profiler(s);
@@ -120,49 +166,22 @@ impl RunCommand {
             }
         }
 
-        let mut linker = match &main {
-            RunTarget::Core(_) => CliLinker::Core(wasmtime::Linker::new(&engine)),
-            #[cfg(feature = "component-model")]
-            RunTarget::Component(_) => {
-                CliLinker::Component(wasmtime::component::Linker::new(&engine))
-            }
-        };
-        if let Some(enable) = self.run.common.wasm.unknown_exports_allow {
-            match &mut linker {
-                CliLinker::Core(l) => {
-                    l.allow_unknown_exports(enable);
-                }
-                #[cfg(feature = "component-model")]
-                CliLinker::Component(_) => {
-                    bail!("--allow-unknown-exports not supported with components");
-                }
-            }
-        }
+        let (mut store, mut linker) = self.new_store_and_linker(&engine, &main)?;
+        self.setup_host_call_trace(&mut store, &mut linker, &main)?;
 
-        let host = Host {
-            #[cfg(feature = "wasi-http")]
-            wasi_http_outgoing_body_buffer_chunks: self
-                .run
-                .common
-                .wasi
-                .http_outgoing_body_buffer_chunks,
-            #[cfg(feature = "wasi-http")]
-            wasi_http_outgoing_body_chunk_size: self.run.common.wasi.http_outgoing_body_chunk_size,
-            ..Default::default()
+        #[cfg(feature = "debug-adapter")]
+        let debug_adapter = match &self.debug_adapter {
+            Some(addr) => {
+                let module = match &main {
+                    RunTarget::Core(module) => Some(module),
+                    #[cfg(feature = "component-model")]
+                    RunTarget::Component(_) => None,
+                };
+                Some(DebugAdapter::listen(addr, &mut store, module)?)
+            }
+            None => None,
         };
 
-        let mut store = Store::new(&engine, host);
-        self.populate_with_wasi(&mut linker, &mut store, &main)?;
-
-        store.data_mut().limits = self.run.store_limits();
-        store.limiter(|t| &mut t.limits);
-
-        // If fuel has been configured, we want to add the configured
-        // fuel amount to this store.
-        if let Some(fuel) = self.run.common.wasm.fuel {
-            store.set_fuel(fuel)?;
-        }
-
         // Always run the module asynchronously to ensure that the module can be
         // interrupted, even if it is blocking on I/O or a timeout or something.
         let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -181,7 +200,8 @@ impl RunCommand {
                 // Load the preload wasm modules.
                 let mut modules = Vec::new();
                 if let RunTarget::Core(m) = &main {
-                    modules.push((String::new(), m.clone()));
+                    let name = self.module_and_args[0].to_string_lossy();
+                    modules.push((name.into_owned(), m.clone()));
                 }
                 for (name, path) in self.preloads.iter() {
                     // Read the wasm module binary either as `*.wat` or a raw binary
@@ -218,6 +238,24 @@ impl RunCommand {
                     }
                 }
 
+                match &mut linker {
+                    CliLinker::Core(_) => {
+                        if !self.virtualize.is_empty() {
+                            bail!("--virtualize can only be used with components");
+                        }
+                    }
+                    #[cfg(feature = "component-model")]
+                    CliLinker::Component(linker) => {
+                        self.link_virtualizers(
+                            &engine,
+                            &mut store,
+                            linker,
+                            main.unwrap_component(),
+                        )
+                        .await?;
+                    }
+                }
+
                 self.load_main_module(&mut store, &mut linker, &main, modules)
                     .await
                     .with_context(|| {
@@ -230,11 +268,30 @@ impl RunCommand {
             .await
         });
 
-        // Load the main wasm module.
-        match result.unwrap_or_else(|elapsed| {
+        let result = result.unwrap_or_else(|elapsed| {
             Err(anyhow::Error::from(wasmtime::Trap::Interrupt))
                 .with_context(|| format!("timed out after {elapsed}"))
-        }) {
+        });
+
+        #[cfg(feature = "debug-adapter")]
+        if let Some(debug_adapter) = debug_adapter {
+            let code = match &result {
+                Ok(()) => 0,
+                Err(e) => {
+                    if let Some(exit) = e.downcast_ref::<wasmtime_wasi::I32Exit>() {
+                        exit.0
+                    } else if let Some(exit) = e.downcast_ref::<wasi_common::I32Exit>() {
+                        exit.0
+                    } else {
+                        1
+                    }
+                }
+            };
+            debug_adapter.finish(code)?;
+        }
+
+        // Load the main wasm module.
+        match result {
             Ok(()) => (),
             Err(e) => {
                 // Exit the process if Wasmtime understands the error;
@@ -265,6 +322,135 @@ impl RunCommand {
         Ok(())
     }
 
+    /// Creates the store and linker used to run `main`, with WASI and the
+    /// other host APIs requested on the command line.
+    pub(crate) fn new_store_and_linker(
+        &self,
+        engine: &Engine,
+        main: &RunTarget,
+    ) -> Result<(Store<Host>, CliLinker)> {
+        let mut linker = match main {
+            RunTarget::Core(_) => CliLinker::Core(wasmtime::Linker::new(engine)),
+            #[cfg(feature = "component-model")]
+            RunTarget::Component(_) => {
+                CliLinker::Component(wasmtime::component::Linker::new(engine))
+            }
+        };
+        if let Some(enable) = self.run.common.wasm.unknown_exports_allow {
+            match &mut linker {
+                CliLinker::Core(l) => {
+                    l.allow_unknown_exports(enable);
+                }
+                #[cfg(feature = "component-model")]
+                CliLinker::Component(_) => {
+                    bail!("--allow-unknown-exports not supported with components");
+                }
+            }
+        }
+
+        let host = Host {
+            #[cfg(feature = "wasi-http")]
+            wasi_http_outgoing_body_buffer_chunks: self
+                .run
+                .common
+                .wasi
+                .http_outgoing_body_buffer_chunks,
+            #[cfg(feature = "wasi-http")]
+            wasi_http_outgoing_body_chunk_size: self.run.common.wasi.http_outgoing_body_chunk_size,
+            ..Default::default()
+        };
+
+        let mut store = Store::new(engine, host);
+        self.populate_with_wasi(&mut linker, &mut store, main)?;
+
+        store.data_mut().limits = self.run.store_limits();
+        store.limiter(|t| &mut t.limits);
+
+        // If fuel has been configured, we want to add the configured
+        // fuel amount to this store.
+        if let Some(fuel) = self.run.common.wasm.fuel {
+            store.set_fuel(fuel)?;
+        } else if self.run.common.wasm.deterministic == Some(true) {
+            store.set_fuel(u64::MAX)?;
+        }
+
+        Ok((store, linker))
+    }
+
+    /// Records or replays the calls made to the host functions in `linker`,
+    /// if requested on the command line.
+    fn setup_host_call_trace(
+        &self,
+        store: &mut Store<Host>,
+        linker: &mut CliLinker,
+        #[cfg_attr(not(feature = "component-model"), allow(unused_variables))] main: &RunTarget,
+    ) -> Result<()> {
+        let wasi = &self.run.common.wasi;
+        let (record, replay) = match (&wasi.record, &wasi.replay) {
+            (None, None) => return Ok(()),
+            (Some(_), Some(_)) => bail!("`-S record` and `-S replay` cannot be used together"),
+            (Some(path), None) => {
+                let file = std::fs::File::create(path)
+                    .with_context(|| format!("failed to create trace `{path}`"))?;
+                (Some(std::io::BufWriter::new(file)), None)
+            }
+            (None, Some(path)) => {
+                let file = std::fs::File::open(path)
+                    .with_context(|| format!("failed to open trace `{path}`"))?;
+                (None, Some(std::io::BufReader::new(file)))
+            }
+        };
+        match linker {
+            CliLinker::Core(linker) => match (record, replay) {
+                (Some(output), _) => wasmtime_wasi::trace::record(linker, store, output),
+                (_, Some(input)) => wasmtime_wasi::trace::replay(linker, store, input),
+                (None, None) => unreachable!(),
+            },
+            #[cfg(feature = "component-model")]
+            CliLinker::Component(linker) => match (record, replay) {
+                (Some(output), _) => wasmtime_wasi::trace::record_component(linker, output),
+                (_, Some(input)) => {
+                    wasmtime_wasi::trace::replay_component(linker, main.unwrap_component(), input)
+                }
+                (None, None) => unreachable!(),
+            },
+        }
+    }
+
+    /// Defines any imports of `module` which `linker` doesn't provide, if
+    /// requested on the command line.
+    pub(crate) fn define_unknown_imports(
+        &self,
+        linker: &mut CliLinker,
+        module: &RunTarget,
+    ) -> Result<()> {
+        // The main module might be allowed to have unknown imports, which
+        // should be defined as traps:
+        if self.run.common.wasm.unknown_imports_trap == Some(true) {
+            match linker {
+                CliLinker::Core(linker) => {
+                    linker.define_unknown_imports_as_traps(module.unwrap_core())?;
+                }
+                #[cfg(feature = "component-model")]
+                CliLinker::Component(linker) => {
+                    linker.define_unknown_imports_as_traps(module.unwrap_component())?;
+                }
+            }
+        }
+
+        // ...or as default values.
+        if self.run.common.wasm.unknown_imports_default == Some(true) {
+            match linker {
+                CliLinker::Core(linker) => {
+                    linker.define_unknown_imports_as_default_values(module.unwrap_core())?;
+                }
+                _ => bail!("cannot use `--default-values-unknown-imports` with components"),
+            }
+        }
+
+        Ok(())
+    }
+
     fn compute_argv(&self) -> Result<Vec<String>> {
         let mut result = Vec::new();
 
@@ -292,14 +478,15 @@ impl RunCommand {
     fn setup_epoch_handler(
         &self,
         store: &mut Store<Host>,
+        main: &RunTarget,
         modules: Vec<(String, Module)>,
     ) -> Result<Box<dyn FnOnce(&mut Store<Host>)>> {
         if let Some(Profile::Guest { path, interval }) = &self.run.profile {
             #[cfg(feature = "profiling")]
-            return Ok(self.setup_guest_profiler(store, modules, path, *interval));
+            return Ok(self.setup_guest_profiler(store, main, modules, path, *interval));
             #[cfg(not(feature = "profiling"))]
             {
-                let _ = (modules, path, interval);
+                let _ = (main, modules, path, interval);
                 bail!("support for profiling disabled at compile time");
             }
         }
@@ -320,6 +507,7 @@ impl RunCommand {
     fn setup_guest_profiler(
         &self,
         store: &mut Store<Host>,
+        main: &RunTarget,
         modules: Vec<(String, Module)>,
         path: &str,
         interval: std::time::Duration,
@@ -327,8 +515,14 @@ impl RunCommand {
         use wasmtime::{AsContext, GuestProfiler, StoreContext, StoreContextMut, UpdateDeadline};
 
         let module_name = self.module_and_args[0].to_str().unwrap_or("<main module>");
-        store.data_mut().guest_profiler =
-            Some(Arc::new(GuestProfiler::new(module_name, interval, modules)));
+        let profiler = match main {
+            RunTarget::Core(_) => GuestProfiler::new(module_name, interval, modules),
+            #[cfg(feature = "component-model")]
+            RunTarget::Component(component) => {
+                GuestProfiler::new_component(module_name, interval, component.clone(), modules)
+            }
+        };
+        store.data_mut().guest_profiler = Some(Arc::new(profiler));
 
         fn sample(
             mut store: StoreContextMut<Host>,
@@ -380,51 +574,159 @@ impl RunCommand {
         return Box::new(move |store| {
             let profiler = Arc::try_unwrap(store.data_mut().guest_profiler.take().unwrap())
                 .expect("profiling doesn't support threads yet");
-            if let Err(e) = std::fs::File::create(&path)
+            // The format of the profile is chosen by the extension of its
+            // path, defaulting to Firefox's format.
+            let extension = Path::new(&path).extension().and_then(|e| e.to_str());
+            let result = std::fs::File::create(&path)
                 .map_err(anyhow::Error::new)
-                .and_then(|output| profiler.finish(std::io::BufWriter::new(output)))
-            {
+                .and_then(|output| {
+                    let output = std::io::BufWriter::new(output);
+                    match extension {
+                        Some("pb" | "pprof") => profiler.finish_pprof(output),
+                        Some("folded" | "collapsed") => profiler.finish_collapsed(output),
+                        _ => profiler.finish(output),
+                    }
+                });
+            if let Err(e) = result {
                 eprintln!("failed writing profile at {path}: {e:#}");
             } else {
                 eprintln!();
                 eprintln!("Profile written to: {path}");
-                eprintln!("View this profile at https://profiler.firefox.com/.");
+                match extension {
+                    Some("pb" | "pprof") => eprintln!("View this profile with `pprof`."),
+                    Some("folded" | "collapsed") => {}
+                    _ => eprintln!("View this profile at https://profiler.firefox.com/."),
+                }
             }
         });
     }
 
-    async fn load_main_module(
+    /// Instantiates the components passed with `--virtualize` and replaces
+    /// the host's definitions of the interfaces they virtualize in `linker`
+    /// with their exports.
+    ///
+    /// Returns an error if `main` imports an interface which isn't
+    /// virtualized but which shares a resource type with one that is, such as
+    /// `wasi:filesystem/preopens` handing out `wasi:filesystem/types`
+    /// descriptors, as the host's implementation can't produce resources of
+    /// the virtualizer's types.
+    #[cfg(feature = "component-model")]
+    pub(crate) async fn link_virtualizers(
         &self,
+        engine: &Engine,
         store: &mut Store<Host>,
-        linker: &mut CliLinker,
-        module: &RunTarget,
-        modules: Vec<(String, Module)>,
+        linker: &mut wasmtime::component::Linker<Host>,
+        main: &wasmtime::component::Component,
     ) -> Result<()> {
-        // The main module might be allowed to have unknown imports, which
-        // should be defined as traps:
-        if self.run.common.wasm.unknown_imports_trap == Some(true) {
-            match linker {
-                CliLinker::Core(linker) => {
-                    linker.define_unknown_imports_as_traps(module.unwrap_core())?;
-                }
-                #[cfg(feature = "component-model")]
-                CliLinker::Component(linker) => {
-                    linker.define_unknown_imports_as_traps(module.unwrap_component())?;
+        use wasmtime::component::types::ComponentItem;
+
+        let mut virtualized = Vec::new();
+        for (name, path) in self.virtualize.iter() {
+            let component = match self.run.load_module(engine, path)? {
+                RunTarget::Component(c) => c,
+                RunTarget::Core(_) => bail!(
+                    "virtualizer `{}` is a core module, not a component",
+                    path.display()
+                ),
+            };
+            let instance = linker
+                .instantiate_async(&mut *store, &component)
+                .await
+                .with_context(|| {
+                    format!("failed to instantiate virtualizer `{}`", path.display())
+                })?;
+
+            let ty = component.component_type();
+            let mut found = false;
+            linker.allow_shadowing(true);
+            for (export, item) in ty.exports(engine) {
+                if !virtualizes(name, export)
+                    || !matches!(item, ComponentItem::ComponentInstance(_))
+                {
+                    continue;
                 }
+                let index = instance
+                    .get_export(&mut *store, None, export)
+                    .ok_or_else(|| {
+                        anyhow!(
+                            "virtualizer `{}` has no export named `{export}` at runtime",
+                            path.display()
+                        )
+                    })?;
+                linker
+                    .instance(export)?
+                    .instance_exports_from(&mut *store, &instance, Some(&index))
+                    .with_context(|| format!("failed to virtualize `{export}`"))?;
+                virtualized.push(export.to_string());
+                found = true;
+            }
+            linker.allow_shadowing(false);
+            if !found {
+                bail!(
+                    "virtualizer `{}` does not export any interface of `{name}`",
+                    path.display()
+                );
             }
         }
 
-        // ...or as default values.
-        if self.run.common.wasm.unknown_imports_default == Some(true) {
-            match linker {
-                CliLinker::Core(linker) => {
-                    linker.define_unknown_imports_as_default_values(module.unwrap_core())?;
+        // Resource types imported by `main` through virtualized interfaces
+        // are now defined by a virtualizer, so every other import using them
+        // must be virtualized too.
+        let main_ty = main.component_type();
+        let imports = main_ty
+            .imports(engine)
+            .filter_map(|(name, item)| match item {
+                ComponentItem::ComponentInstance(ty) => Some((name, ty)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let resources = |ty: &wasmtime::component::types::ComponentInstance| {
+            ty.exports(engine)
+                .filter_map(|(name, item)| match item {
+                    ComponentItem::Resource(ty) => Some((name.to_string(), ty)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let virtualized_resources = imports
+            .iter()
+            .filter(|(name, _)| virtualized.iter().any(|v| v == name))
+            .flat_map(|(name, ty)| {
+                resources(ty)
+                    .into_iter()
+                    .map(move |(resource, ty)| (*name, resource, ty))
+            })
+            .collect::<Vec<_>>();
+        for (name, ty) in imports.iter() {
+            if virtualized.iter().any(|v| v == name) {
+                continue;
+            }
+            for (_, resource) in resources(ty) {
+                if let Some((interface, resource, _)) = virtualized_resources
+                    .iter()
+                    .find(|(_, _, ty)| *ty == resource)
+                {
+                    bail!(
+                        "`{name}` uses the resource `{resource}` of the virtualized \
+                         interface `{interface}` but isn't virtualized itself; \
+                         pass a virtualizer which exports both"
+                    );
                 }
-                _ => bail!("cannot use `--default-values-unknown-imports` with components"),
             }
         }
+        Ok(())
+    }
 
-        let finish_epoch_handler = self.setup_epoch_handler(store, modules)?;
+    async fn load_main_module(
+        &self,
+        store: &mut Store<Host>,
+        linker: &mut CliLinker,
+        module: &RunTarget,
+        modules: Vec<(String, Module)>,
+    ) -> Result<()> {
+        self.define_unknown_imports(linker, module)?;
+
+        let finish_epoch_handler = self.setup_epoch_handler(store, module, modules)?;
 
         let result = match linker {
             CliLinker::Core(linker) => {
@@ -798,10 +1100,14 @@ impl RunCommand {
                 wasmtime_wasi_threads::add_to_linker(linker, store, &module, |host| {
                     host.wasi_threads.as_ref().unwrap()
                 })?;
-                store.data_mut().wasi_threads = Some(Arc::new(WasiThreadsCtx::new(
-                    module.clone(),
-                    Arc::new(linker.clone()),
-                )?));
+                let mut ctx = WasiThreadsCtx::new(module.clone(), Arc::new(linker.clone()))?;
+                if self.run.common.debug.race_detection == Some(true) {
+                    let detector =
+                        wasmtime::RaceDetector::with_handler(|race| eprintln!("warning: {race}"));
+                    detector.thread().attach(store);
+                    ctx = ctx.with_race_detector(detector);
+                }
+                store.data_mut().wasi_threads = Some(Arc::new(ctx));
             }
         }
 
@@ -901,7 +1207,7 @@ impl RunCommand {
 }
 
 #[derive(Default, Clone)]
-struct Host {
+pub(crate) struct Host {
     preview1_ctx: Option<wasi_common::WasiCtx>,
 
     // The Mutex is only needed to satisfy the Sync constraint but we never
@@ -0,0 +1,170 @@
+//! The module that implements the `wasmtime wizer` command.
+
+use crate::commands::run::CliLinker;
+use crate::commands::RunCommand;
+use crate::common::RunTarget;
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use std::borrow::Cow;
+use std::path::PathBuf;
+use wasmtime::{Engine, Module};
+use wasmtime_wizer::Wizer;
+
+/// Pre-initializes a WebAssembly module or component.
+///
+/// The module or component is instantiated with the WASI and other options
+/// given, its initialization function is run, and then a new module or
+/// component is written whose memories and globals start out in the state
+/// that the initialization function left them in.
+#[derive(Parser)]
+pub struct WizerCommand {
+    /// The exported function which initializes the module or component.
+    ///
+    /// Defaults to `wizer.initialize` for modules and `wizer-initialize` for
+    /// components.
+    #[arg(long, value_name = "FUNCTION")]
+    init_func: Option<String>,
+
+    /// Keep the initialization function exported from the pre-initialized
+    /// module.
+    #[arg(long)]
+    keep_init_func: bool,
+
+    /// The path to write the pre-initialized module or component to.
+    #[arg(short, long, value_name = "OUTPUT")]
+    output: PathBuf,
+
+    #[command(flatten)]
+    #[allow(missing_docs, reason = "the flattened options have their own docs")]
+    run: RunCommand,
+}
+
+impl WizerCommand {
+    /// Executes the command.
+    pub fn execute(mut self) -> Result<()> {
+        self.run.run.common.init_logging()?;
+
+        if self.run.invoke.is_some() {
+            bail!("`--invoke` is not supported when pre-initializing");
+        }
+        if !self.run.preloads.is_empty() {
+            bail!("`--preload` is not supported when pre-initializing");
+        }
+        if !self.run.virtualize.is_empty() {
+            bail!("`--virtualize` is not supported when pre-initializing");
+        }
+
+        let mut config = self.run.run.common.config(None)?;
+        config.async_support(true);
+        let engine = Engine::new(&config)?;
+
+        let path = PathBuf::from(&self.run.module_and_args[0]);
+        let wasm = Cow::Owned(
+            std::fs::read(&path)
+                .with_context(|| format!("failed to read Wasm module: {}", path.display()))?,
+        );
+        #[cfg(feature = "wat")]
+        let wasm = wat::parse_bytes(&wasm).map_err(|mut e| {
+            e.set_path(&path);
+            e
+        })?;
+
+        let mut wizer = Wizer::new();
+        if let Some(init_func) = &self.init_func {
+            wizer.init_func(init_func);
+        }
+        wizer.keep_init_func(self.keep_init_func);
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_time()
+            .enable_io()
+            .build()?;
+
+        let output = if wasmparser::Parser::is_component(&wasm) {
+            self.run_component(&engine, &wizer, &runtime, &wasm)?
+        } else {
+            self.run_module(&engine, &wizer, &runtime, &wasm)?
+        };
+
+        std::fs::write(&self.output, output)
+            .with_context(|| format!("failed to write `{}`", self.output.display()))?;
+        Ok(())
+    }
+
+    fn run_module(
+        &self,
+        engine: &Engine,
+        wizer: &Wizer,
+        runtime: &tokio::runtime::Runtime,
+        wasm: &[u8],
+    ) -> Result<Vec<u8>> {
+        let instrumented = wizer.instrument(wasm)?;
+        let main = RunTarget::Core(Module::new(engine, &instrumented)?);
+        let (mut store, mut linker) = self.run.new_store_and_linker(engine, &main)?;
+        self.run.define_unknown_imports(&mut linker, &main)?;
+        let linker = match &linker {
+            CliLinker::Core(linker) => linker,
+            #[cfg(feature = "component-model")]
+            CliLinker::Component(_) => unreachable!(),
+        };
+
+        runtime.block_on(async {
+            let instance = linker
+                .instantiate_async(&mut store, main.unwrap_core())
+                .await?;
+            let name = wizer.get_init_func();
+            instance
+                .get_typed_func::<(), ()>(&mut store, name)
+                .with_context(|| format!("failed to find initialization function `{name}`"))?
+                .call_async(&mut store, ())
+                .await
+                .context("failed to run the initialization function")?;
+            wizer.snapshot(wasm, &mut store, &instance)
+        })
+    }
+
+    #[cfg(feature = "component-model")]
+    fn run_component(
+        &self,
+        engine: &Engine,
+        wizer: &Wizer,
+        runtime: &tokio::runtime::Runtime,
+        wasm: &[u8],
+    ) -> Result<Vec<u8>> {
+        let instrumented = wizer.instrument_component(wasm)?;
+        let component = wasmtime::component::Component::new(engine, &instrumented)?;
+        let main = RunTarget::Component(component);
+        let (mut store, mut linker) = self.run.new_store_and_linker(engine, &main)?;
+        self.run.define_unknown_imports(&mut linker, &main)?;
+        let linker = match &linker {
+            CliLinker::Component(linker) => linker,
+            CliLinker::Core(_) => unreachable!(),
+        };
+
+        runtime.block_on(async {
+            let instance = linker
+                .instantiate_async(&mut store, main.unwrap_component())
+                .await?;
+            let name = wizer.get_component_init_func();
+            let init = instance
+                .get_typed_func::<(), ()>(&mut store, name)
+                .with_context(|| format!("failed to find initialization function `{name}`"))?;
+            init.call_async(&mut store, ())
+                .await
+                .context("failed to run the initialization function")?;
+            init.post_return_async(&mut store).await?;
+            wizer.snapshot_component(wasm, &mut store, &instance)
+        })
+    }
+
+    #[cfg(not(feature = "component-model"))]
+    fn run_component(
+        &self,
+        _engine: &Engine,
+        _wizer: &Wizer,
+        _runtime: &tokio::runtime::Runtime,
+        _wasm: &[u8],
+    ) -> Result<Vec<u8>> {
+        bail!("support for components was not enabled at compile time")
+    }
+}
@@ -67,6 +67,11 @@ pub struct RunCommon {
     /// where `path` is where to write the profile and `interval` is the
     /// duration between samples. When used with `--wasm-timeout` the timeout
     /// will be rounded up to the nearest multiple of this interval.
+    ///
+    /// The format of the profile is chosen by the extension of `path`: a
+    /// `.pb` or `.pprof` file is written in the pprof format, a `.folded` or
+    /// `.collapsed` file holds collapsed stacks for flame graph tools, and
+    /// anything else is written in the Firefox profiler's format.
     #[arg(
         long,
         value_name = "STRATEGY",
@@ -0,0 +1,692 @@
+//! A server for the [Debug Adapter Protocol] used by `wasmtime run
+//! --debug-adapter`.
+//!
+//! A single debugger connects over TCP before the program starts. Execution
+//! is controlled through [`Store::debug_handler`], which blocks the program
+//! on every stop until the debugger asks it to continue or step.
+//!
+//! [Debug Adapter Protocol]: https://microsoft.github.io/debug-adapter-protocol/
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use base64::Engine as _;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use wasmtime::{
+    AsContext, AsContextMut, DebugFrame, DebugInterruptHandle, DebugStep, Global, Memory, Module,
+    Store, StoreContextMut, Val, WasmBacktrace,
+};
+
+/// The reference of the "Locals" scope in `variables` requests.
+const LOCALS: u64 = 1;
+/// The reference of the "Globals" scope in `variables` requests.
+const GLOBALS: u64 = 2;
+/// The reference of the "Memories" scope in `variables` requests.
+const MEMORIES: u64 = 3;
+
+/// The only thread a program has, as far as the debugger is concerned.
+const THREAD_ID: u64 = 1;
+
+/// A connected debugger.
+pub(crate) struct DebugAdapter {
+    session: Arc<Mutex<Session>>,
+}
+
+/// State shared between the thread reading requests and the program.
+#[derive(Default)]
+struct Shared {
+    /// Whether the program is running, in which case it can't answer
+    /// requests until it's interrupted.
+    running: AtomicBool,
+    /// Whether the next stop was requested with a `pause` request.
+    paused: AtomicBool,
+    /// Whether the next stop was only to answer requests which arrived while
+    /// the program was running, and should resume without telling the
+    /// debugger.
+    service: AtomicBool,
+}
+
+struct Session {
+    requests: Receiver<Value>,
+    writer: Writer,
+    shared: Arc<Shared>,
+    interrupt: DebugInterruptHandle,
+    /// The module which line and instruction breakpoints are set in, if the
+    /// program is a core module.
+    module: Option<Module>,
+    /// Breakpoint offsets set by `setBreakpoints`, per source file.
+    line_breakpoints: HashMap<String, Vec<usize>>,
+    /// Breakpoint offsets set by `setInstructionBreakpoints`.
+    instruction_breakpoints: Vec<usize>,
+    /// The reason to report for the next stop, overriding the default.
+    stop_reason: Option<&'static str>,
+    /// The line-granularity step in progress, if any.
+    step: Option<Step>,
+    /// Whether the debugger has disconnected.
+    disconnected: bool,
+}
+
+/// A step through source lines, which continues stepping through wasm
+/// instructions until it reaches a different line.
+struct Step {
+    kind: DebugStep,
+    location: Location,
+}
+
+/// Where execution stopped, in terms of source lines.
+#[derive(PartialEq)]
+struct Location {
+    func_index: u32,
+    depth: usize,
+    line: Option<(String, u32)>,
+}
+
+impl DebugAdapter {
+    /// Waits for a debugger to connect to `addr`, which is either a socket
+    /// address or a port on the loopback interface.
+    ///
+    /// This configures `store` to stop whenever the debugger asks it to, and
+    /// returns once the debugger has finished configuring breakpoints in
+    /// `module`.
+    pub(crate) fn listen<T: 'static>(
+        addr: &str,
+        store: &mut Store<T>,
+        module: Option<&Module>,
+    ) -> Result<DebugAdapter> {
+        let addr = match addr.parse::<u16>() {
+            Ok(port) => format!("127.0.0.1:{port}"),
+            Err(_) => addr.to_string(),
+        };
+        let listener =
+            TcpListener::bind(&addr).with_context(|| format!("failed to listen on {addr}"))?;
+        eprintln!("Waiting for a debugger on {}...", listener.local_addr()?);
+        let (stream, _) = listener.accept()?;
+
+        let shared = Arc::new(Shared::default());
+        let interrupt = store.debug_interrupt_handle();
+        let writer = Writer::new(stream.try_clone()?);
+        let (tx, requests) = mpsc::channel();
+        {
+            let shared = shared.clone();
+            let interrupt = interrupt.clone();
+            let writer = writer.clone();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream);
+                while let Ok(Some(message)) = read_message(&mut reader) {
+                    if message["type"] != "request" {
+                        continue;
+                    }
+                    // Requests can only be answered while the program is
+                    // stopped, so interrupt it if it's running. A `pause`
+                    // is answered here since that's all it does.
+                    if message["command"] == "pause" {
+                        shared.paused.store(true, Ordering::SeqCst);
+                        interrupt.interrupt();
+                        let _ = writer.respond(&message, Ok(json!({})));
+                        continue;
+                    }
+                    if shared.running.load(Ordering::SeqCst) {
+                        shared.service.store(true, Ordering::SeqCst);
+                        interrupt.interrupt();
+                    }
+                    if tx.send(message).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let mut session = Session {
+            requests,
+            writer,
+            shared,
+            interrupt,
+            module: module.cloned(),
+            line_breakpoints: HashMap::new(),
+            instruction_breakpoints: Vec::new(),
+            stop_reason: None,
+            step: None,
+            disconnected: false,
+        };
+        session.configure(store.as_context_mut())?;
+        session.shared.running.store(true, Ordering::SeqCst);
+
+        let session = Arc::new(Mutex::new(session));
+        let handler_session = session.clone();
+        store.debug_handler(move |store, frame| {
+            let mut session = handler_session.lock().unwrap();
+            session.stopped(store, frame)
+        });
+        Ok(DebugAdapter { session })
+    }
+
+    /// Tells the debugger that the program exited with `code`, and waits for
+    /// it to disconnect.
+    pub(crate) fn finish(self, code: i32) -> Result<()> {
+        let session = self.session.lock().unwrap();
+        if session.disconnected {
+            return Ok(());
+        }
+        session
+            .writer
+            .event("exited", json!({ "exitCode": code }))?;
+        session.writer.event("terminated", json!({}))?;
+        loop {
+            let Ok(request) = session.requests.recv_timeout(Duration::from_secs(5)) else {
+                break;
+            };
+            if is_disconnect(&request) {
+                session.writer.respond(&request, Ok(json!({})))?;
+                break;
+            }
+            session
+                .writer
+                .respond(&request, Err(anyhow!("the program has exited")))?;
+        }
+        Ok(())
+    }
+}
+
+impl Session {
+    /// Answers requests until the debugger has configured the session and
+    /// asked for the program to be launched.
+    fn configure<T>(&mut self, mut store: StoreContextMut<'_, T>) -> Result<()> {
+        let mut launched = false;
+        let mut configured = false;
+        while !(launched && configured) {
+            let request = self.recv()?;
+            let result = match request["command"].as_str().unwrap_or("") {
+                "initialize" => {
+                    self.writer.respond(
+                        &request,
+                        Ok(json!({
+                            "supportsConfigurationDoneRequest": true,
+                            "supportsInstructionBreakpoints": true,
+                            "supportsReadMemoryRequest": true,
+                            "supportsTerminateRequest": true,
+                        })),
+                    )?;
+                    self.writer.event("initialized", json!({}))?;
+                    continue;
+                }
+                "launch" | "attach" => {
+                    launched = true;
+                    if request["arguments"]["stopOnEntry"] == true {
+                        self.stop_reason = Some("entry");
+                        self.interrupt.interrupt();
+                    }
+                    Ok(json!({}))
+                }
+                "configurationDone" => {
+                    configured = true;
+                    Ok(json!({}))
+                }
+                "threads" => Ok(self.threads()),
+                "setBreakpoints" => self.set_breakpoints(&mut store, &request["arguments"]),
+                "setInstructionBreakpoints" => {
+                    self.set_instruction_breakpoints(&mut store, &request["arguments"])
+                }
+                command if is_disconnect(&request) => {
+                    self.writer.respond(&request, Ok(json!({})))?;
+                    bail!("debugger sent `{command}` before the program started");
+                }
+                command => Err(anyhow!("`{command}` isn't supported before launching")),
+            };
+            self.writer.respond(&request, result)?;
+        }
+        Ok(())
+    }
+
+    /// Called when the program stops at `frame`, returning how it should
+    /// resume.
+    fn stopped<T>(
+        &mut self,
+        mut store: StoreContextMut<'_, T>,
+        frame: &DebugFrame,
+    ) -> Result<DebugStep> {
+        self.shared.running.store(false, Ordering::SeqCst);
+        let paused = self.shared.paused.swap(false, Ordering::SeqCst);
+        let service = self.shared.service.swap(false, Ordering::SeqCst);
+
+        let backtrace = WasmBacktrace::force_capture(&store);
+        let location = Location::new(frame, &backtrace);
+        let at_breakpoint = self.is_breakpoint(frame);
+
+        let reason = if let Some(reason) = self.stop_reason.take() {
+            reason
+        } else if paused {
+            "pause"
+        } else if at_breakpoint {
+            "breakpoint"
+        } else if let Some(step) = &self.step {
+            // Keep stepping until the step leaves the line that it started
+            // on, if there's line information.
+            if step.location.line.is_some() && step.location == location {
+                let kind = step.kind;
+                self.shared.running.store(true, Ordering::SeqCst);
+                return Ok(kind);
+            }
+            "step"
+        } else if service {
+            // Nothing stops here, so answer the requests which are waiting
+            // and carry on.
+            while let Ok(request) = self.requests.try_recv() {
+                if let Some(step) = self.handle(&mut store, frame, &backtrace, &request)? {
+                    self.shared.running.store(true, Ordering::SeqCst);
+                    return Ok(step);
+                }
+            }
+            self.shared.running.store(true, Ordering::SeqCst);
+            return Ok(DebugStep::Continue);
+        } else {
+            "step"
+        };
+        self.step = None;
+        self.writer.event(
+            "stopped",
+            json!({
+                "reason": reason,
+                "threadId": THREAD_ID,
+                "allThreadsStopped": true,
+            }),
+        )?;
+
+        loop {
+            let request = self.recv()?;
+            if let Some(step) = self.handle(&mut store, frame, &backtrace, &request)? {
+                if step != DebugStep::Continue {
+                    self.step = Some(Step {
+                        kind: step,
+                        location,
+                    });
+                }
+                self.shared.running.store(true, Ordering::SeqCst);
+                return Ok(step);
+            }
+        }
+    }
+
+    /// Answers `request` while the program is stopped at `frame`, returning
+    /// how to resume if the request resumes execution.
+    fn handle<T>(
+        &mut self,
+        store: &mut StoreContextMut<'_, T>,
+        frame: &DebugFrame,
+        backtrace: &WasmBacktrace,
+        request: &Value,
+    ) -> Result<Option<DebugStep>> {
+        let args = &request["arguments"];
+        let (result, step) = match request["command"].as_str().unwrap_or("") {
+            "threads" => (Ok(self.threads()), None),
+            "stackTrace" => (Ok(stack_trace(backtrace)), None),
+            "scopes" => (Ok(scopes(args)), None),
+            "variables" => (variables(store, frame, args), None),
+            "readMemory" => (read_memory(store, frame, args), None),
+            "setBreakpoints" => (self.set_breakpoints(store, args), None),
+            "setInstructionBreakpoints" => (self.set_instruction_breakpoints(store, args), None),
+            "continue" => (
+                Ok(json!({ "allThreadsContinued": true })),
+                Some(DebugStep::Continue),
+            ),
+            "next" => (Ok(json!({})), Some(DebugStep::Over)),
+            "stepIn" => (Ok(json!({})), Some(DebugStep::Into)),
+            "stepOut" => (Ok(json!({})), Some(DebugStep::Out)),
+            command if is_disconnect(request) => {
+                self.disconnected = true;
+                self.writer.respond(request, Ok(json!({})))?;
+                bail!("debugger sent `{command}`");
+            }
+            command => (Err(anyhow!("`{command}` isn't supported")), None),
+        };
+        self.writer.respond(request, result)?;
+        Ok(step)
+    }
+
+    fn recv(&mut self) -> Result<Value> {
+        match self.requests.recv() {
+            Ok(request) => Ok(request),
+            Err(_) => {
+                self.disconnected = true;
+                bail!("debugger disconnected")
+            }
+        }
+    }
+
+    fn threads(&self) -> Value {
+        json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] })
+    }
+
+    fn is_breakpoint(&self, frame: &DebugFrame) -> bool {
+        let Some(module) = &self.module else {
+            return false;
+        };
+        if !Module::same(module, frame.module()) {
+            return false;
+        }
+        let offset = frame.module_offset();
+        self.instruction_breakpoints.contains(&offset)
+            || self
+                .line_breakpoints
+                .values()
+                .any(|offsets| offsets.contains(&offset))
+    }
+
+    fn set_breakpoints<T>(
+        &mut self,
+        store: &mut StoreContextMut<'_, T>,
+        args: &Value,
+    ) -> Result<Value> {
+        let path = args["source"]["path"]
+            .as_str()
+            .ok_or_else(|| anyhow!("breakpoints require a source path"))?;
+        let module = self.module.as_ref();
+        for offset in self.line_breakpoints.remove(path).unwrap_or_default() {
+            if let Some(module) = module {
+                store.remove_breakpoint(module, offset);
+            }
+        }
+
+        let mut offsets = Vec::new();
+        let mut breakpoints = Vec::new();
+        for breakpoint in args["breakpoints"].as_array().into_iter().flatten() {
+            let line = breakpoint["line"]
+                .as_u64()
+                .and_then(|line| u32::try_from(line).ok())
+                .ok_or_else(|| anyhow!("invalid breakpoint line"))?;
+            let found = match module {
+                Some(module) => line_offsets(module, path, line)?,
+                None => Vec::new(),
+            };
+            breakpoints.push(json!({
+                "verified": !found.is_empty(),
+                "line": line,
+                "instructionReference": found.first().map(|o| format!("{o:#x}")),
+            }));
+            offsets.extend(found);
+        }
+        if let Some(module) = module {
+            for offset in offsets.iter() {
+                store.add_breakpoint(module, *offset);
+            }
+        }
+        self.line_breakpoints.insert(path.to_string(), offsets);
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+
+    fn set_instruction_breakpoints<T>(
+        &mut self,
+        store: &mut StoreContextMut<'_, T>,
+        args: &Value,
+    ) -> Result<Value> {
+        let module = self.module.as_ref();
+        for offset in self.instruction_breakpoints.drain(..) {
+            if let Some(module) = module {
+                store.remove_breakpoint(module, offset);
+            }
+        }
+        let mut breakpoints = Vec::new();
+        for breakpoint in args["breakpoints"].as_array().into_iter().flatten() {
+            let offset = breakpoint["instructionReference"]
+                .as_str()
+                .and_then(parse_offset)
+                .and_then(|offset| {
+                    let extra = breakpoint["offset"].as_i64().unwrap_or(0);
+                    offset.checked_add_signed(isize::try_from(extra).ok()?)
+                });
+            let verified = match (module, offset) {
+                (Some(module), Some(offset)) => {
+                    store.add_breakpoint(module, offset);
+                    self.instruction_breakpoints.push(offset);
+                    true
+                }
+                _ => false,
+            };
+            breakpoints.push(json!({ "verified": verified }));
+        }
+        Ok(json!({ "breakpoints": breakpoints }))
+    }
+}
+
+impl Location {
+    fn new(frame: &DebugFrame, backtrace: &WasmBacktrace) -> Location {
+        let line = backtrace
+            .frames()
+            .first()
+            .and_then(|f| f.symbols().first())
+            .and_then(|s| Some((s.file()?.to_string(), s.line()?)));
+        Location {
+            func_index: frame.func_index(),
+            depth: backtrace.frames().len(),
+            line,
+        }
+    }
+}
+
+/// Returns the offsets at which breakpoints for `line` of `path` go.
+fn line_offsets(module: &Module, path: &str, line: u32) -> Result<Vec<usize>> {
+    #[cfg(feature = "addr2line")]
+    return module.line_offsets(path, line);
+    #[cfg(not(feature = "addr2line"))]
+    {
+        let _ = (module, path, line);
+        Ok(Vec::new())
+    }
+}
+
+fn is_disconnect(request: &Value) -> bool {
+    request["command"] == "disconnect" || request["command"] == "terminate"
+}
+
+/// Parses an instruction reference, which is a module offset in hexadecimal.
+fn parse_offset(s: &str) -> Option<usize> {
+    usize::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16).ok()
+}
+
+fn stack_trace(backtrace: &WasmBacktrace) -> Value {
+    let frames = backtrace
+        .frames()
+        .iter()
+        .enumerate()
+        .map(|(id, frame)| {
+            let name = match frame.func_name() {
+                Some(name) => name.to_string(),
+                None => format!("wasm-function[{}]", frame.func_index()),
+            };
+            let mut json = json!({
+                "id": id,
+                "name": name,
+                "line": 0,
+                "column": 0,
+                "instructionPointerReference":
+                    frame.module_offset().map(|o| format!("{o:#x}")),
+            });
+            if let Some(symbol) = frame.symbols().first() {
+                if let Some(file) = symbol.file() {
+                    json["source"] = json!({ "path": file });
+                }
+                json["line"] = json!(symbol.line().unwrap_or(0));
+                json["column"] = json!(symbol.column().unwrap_or(0));
+            }
+            json
+        })
+        .collect::<Vec<_>>();
+    json!({ "stackFrames": frames, "totalFrames": frames.len() })
+}
+
+fn scopes(args: &Value) -> Value {
+    // Locals are only available for the frame which is executing.
+    let mut scopes = Vec::new();
+    if args["frameId"] == 0 {
+        scopes.push(json!({
+            "name": "Locals",
+            "presentationHint": "locals",
+            "variablesReference": LOCALS,
+            "expensive": false,
+        }));
+    }
+    scopes.push(json!({
+        "name": "Globals",
+        "variablesReference": GLOBALS,
+        "expensive": false,
+    }));
+    scopes.push(json!({
+        "name": "Memories",
+        "variablesReference": MEMORIES,
+        "expensive": false,
+    }));
+    json!({ "scopes": scopes })
+}
+
+fn variables<T>(
+    store: &mut StoreContextMut<'_, T>,
+    frame: &DebugFrame,
+    args: &Value,
+) -> Result<Value> {
+    let variable = |name: String, value: String| json!({ "name": name, "value": value, "variablesReference": 0 });
+    let variables = match args["variablesReference"].as_u64() {
+        Some(LOCALS) => frame
+            .locals()
+            .iter()
+            .enumerate()
+            .map(|(i, local)| variable(format!("local {i}"), display(local.as_ref())))
+            .collect(),
+        Some(GLOBALS) => frame
+            .globals(&mut *store)
+            .iter()
+            .enumerate()
+            .map(|(i, global): (usize, &Global)| {
+                let value = global.get(&mut *store);
+                variable(format!("global {i}"), display(Some(&value)))
+            })
+            .collect(),
+        Some(MEMORIES) => frame
+            .memories(&mut *store)
+            .iter()
+            .enumerate()
+            .map(|(i, memory): (usize, &Memory)| {
+                let mut json = variable(
+                    format!("memory {i}"),
+                    format!("{} bytes", memory.data_size(&*store)),
+                );
+                json["memoryReference"] = json!(format!("mem{i}"));
+                json
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Ok(json!({ "variables": variables }))
+}
+
+fn display(val: Option<&Val>) -> String {
+    match val {
+        Some(Val::I32(i)) => i.to_string(),
+        Some(Val::I64(i)) => i.to_string(),
+        Some(Val::F32(bits)) => f32::from_bits(*bits).to_string(),
+        Some(Val::F64(bits)) => f64::from_bits(*bits).to_string(),
+        Some(Val::V128(v)) => format!("{:#034x}", v.as_u128()),
+        Some(_) | None => "<ref>".to_string(),
+    }
+}
+
+fn read_memory<T>(
+    store: &mut StoreContextMut<'_, T>,
+    frame: &DebugFrame,
+    args: &Value,
+) -> Result<Value> {
+    let index = args["memoryReference"]
+        .as_str()
+        .and_then(|r| r.strip_prefix("mem"))
+        .and_then(|i| i.parse::<usize>().ok())
+        .ok_or_else(|| anyhow!("invalid memory reference"))?;
+    let memories = frame.memories(&mut *store);
+    let memory = memories
+        .get(index)
+        .ok_or_else(|| anyhow!("no memory {index}"))?;
+    let data = memory.data(store.as_context());
+    let start = args["offset"]
+        .as_i64()
+        .and_then(|o| usize::try_from(o).ok())
+        .unwrap_or(0)
+        .min(data.len());
+    let count = usize::try_from(args["count"].as_u64().unwrap_or(0))?;
+    let end = start.saturating_add(count).min(data.len());
+    Ok(json!({
+        "address": format!("{start:#x}"),
+        "data": base64::engine::general_purpose::STANDARD.encode(&data[start..end]),
+        "unreadableBytes": count - (end - start),
+    }))
+}
+
+/// Reads one message, or `None` at the end of the stream.
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            len = Some(value.trim().parse::<usize>()?);
+        }
+    }
+    let len = len.ok_or_else(|| anyhow!("message is missing `Content-Length`"))?;
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// The sending half of the connection, shared by the program and the thread
+/// reading requests.
+#[derive(Clone)]
+struct Writer(Arc<Mutex<(TcpStream, u64)>>);
+
+impl Writer {
+    fn new(stream: TcpStream) -> Writer {
+        Writer(Arc::new(Mutex::new((stream, 1))))
+    }
+
+    fn send(&self, mut message: Value) -> Result<()> {
+        let mut inner = self.0.lock().unwrap();
+        let (stream, seq) = &mut *inner;
+        message["seq"] = json!(*seq);
+        *seq += 1;
+        let body = serde_json::to_vec(&message)?;
+        write!(stream, "Content-Length: {}\r\n\r\n", body.len())?;
+        stream.write_all(&body)?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    fn respond(&self, request: &Value, result: Result<Value>) -> Result<()> {
+        let mut response = json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+        });
+        match result {
+            Ok(body) => {
+                response["success"] = json!(true);
+                response["body"] = body;
+            }
+            Err(e) => {
+                response["success"] = json!(false);
+                response["message"] = json!(format!("{e:#}"));
+            }
+        }
+        self.send(response)
+    }
+
+    fn event(&self, event: &str, body: Value) -> Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+}
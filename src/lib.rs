@@ -9,3 +9,6 @@ pub mod commands;
 
 #[cfg(feature = "run")]
 pub(crate) mod common;
+
+#[cfg(feature = "debug-adapter")]
+pub(crate) mod debug_adapter;
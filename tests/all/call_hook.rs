@@ -417,6 +417,50 @@ fn recursion() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn call_func_from_caller() -> Result<(), Error> {
+    // Calls made through `Caller::call_func` are transitions like any other.
+
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, State::default());
+    store.call_hook(sync_call_hook);
+    store.enable_stats(true);
+
+    let inner = Func::wrap(&mut store, |caller: Caller<State>| {
+        assert_eq!(caller.data().calls_into_host, 2);
+        assert_eq!(caller.data().calls_into_wasm, 2);
+    });
+    let ty = inner.ty(&store);
+    let outer = Func::new(&mut store, ty, move |mut caller, params, results| {
+        caller.call_func(&inner, params, results)?;
+        assert_eq!(caller.data().context.last(), Some(&Context::Host));
+        Ok(())
+    });
+
+    let wat = r#"
+        (module
+            (import "" "" (func $f))
+            (func (export "export") (call $f))
+        )
+    "#;
+    let module = Module::new(&engine, wat)?;
+    let inst = Instance::new(&mut store, &module, &[outer.into()])?;
+    inst.get_typed_func::<(), ()>(&mut store, "export")?
+        .call(&mut store, ())?;
+
+    assert_eq!(store.data().calls_into_host, 2);
+    assert_eq!(store.data().returns_from_host, 2);
+    assert_eq!(store.data().calls_into_wasm, 2);
+    assert_eq!(store.data().returns_from_wasm, 2);
+    assert!(store.data().context.is_empty());
+
+    let stats = store.stats();
+    assert_eq!(stats.host_calls, 2);
+    assert_eq!(stats.traps, 0);
+
+    Ok(())
+}
+
 #[test]
 fn trapping() -> Result<(), Error> {
     const TRAP_IN_F: i32 = 0;
@@ -847,6 +847,69 @@ fn run_basic_component() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg_attr(not(feature = "component-model"), ignore)]
+fn run_virtualized_component() -> Result<()> {
+    let guest = "tests/all/cli_tests/component-random-42.wat";
+    let virt = "tests/all/cli_tests/virtualize-random.wat";
+
+    // Without a virtualizer the host's random number generator is used.
+    let output = get_wasmtime_command()?
+        .args(&["run", "-Ccache=n", guest])
+        .output()?;
+    assert!(!output.status.success());
+
+    for name in ["wasi:random", "wasi:random/random"] {
+        run_wasmtime(&[
+            "run",
+            "-Ccache=n",
+            &format!("--virtualize={name}={virt}"),
+            guest,
+        ])?;
+    }
+
+    let output = get_wasmtime_command()?
+        .args(&["run", "-Ccache=n", "--virtualize=wasi:filesystem", guest])
+        .output()?;
+    assert!(!output.status.success());
+    let output = get_wasmtime_command()?
+        .args(&[
+            "run",
+            "-Ccache=n",
+            &format!("--virtualize=wasi:filesystem={virt}"),
+            guest,
+        ])
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("does not export any interface of `wasi:filesystem`"),
+        "bad stderr: {stderr}"
+    );
+
+    // Interfaces sharing resources with a virtualized interface can't be left
+    // to the host.
+    let output = get_wasmtime_command()?
+        .args(&[
+            "run",
+            "-Ccache=n",
+            "--virtualize=wasi:filesystem=tests/all/cli_tests/virtualize-filesystem-types.wat",
+            "tests/all/cli_tests/component-filesystem-preopens.wat",
+        ])
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(
+            "`wasi:filesystem/preopens@0.2.0` uses the resource `descriptor` of the \
+             virtualized interface `wasi:filesystem/types@0.2.0`"
+        ),
+        "bad stderr: {stderr}"
+    );
+
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(not(feature = "component-model"), ignore)]
 fn run_precompiled_component() -> Result<()> {
@@ -2108,6 +2171,54 @@ fn is_vtune_available() -> bool {
     Command::new("vtune").arg("-version").output().is_ok()
 }
 
+#[test]
+fn profile_guest_formats() -> Result<()> {
+    let td = TempDir::new()?;
+    let wasm = td.path().join("spin.wat");
+    std::fs::write(
+        &wasm,
+        r#"
+            (module
+                (func $spin (param i32) (result i32)
+                    (local i32)
+                    (loop
+                        (local.set 1 (i32.add (local.get 1) (i32.const 1)))
+                        (br_if 0 (i32.lt_u (local.get 1) (local.get 0))))
+                    local.get 1)
+                (func $main (export "_start")
+                    (drop (call $spin (i32.const 100000000)))))
+        "#,
+    )?;
+
+    let folded = td.path().join("profile.folded");
+    run_wasmtime(&[
+        "run",
+        "-Ccache=n",
+        &format!("--profile=guest,{},1ms", folded.display()),
+        wasm.to_str().unwrap(),
+    ])?;
+    let folded = std::fs::read_to_string(&folded)?;
+    let module = wasm.to_str().unwrap();
+    for line in folded.lines() {
+        let (stack, samples) = line.rsplit_once(' ').unwrap();
+        assert!(stack.starts_with(&format!("{module}`main")), "{line}");
+        assert!(samples.parse::<u64>()? > 0, "{line}");
+    }
+    assert!(folded.contains(&format!("{module}`main;{module}`spin ")));
+
+    // The pprof output is a protobuf whose first field is a `sample_type`.
+    let pprof = td.path().join("profile.pb");
+    run_wasmtime(&[
+        "run",
+        "-Ccache=n",
+        &format!("--profile=guest,{},1ms", pprof.display()),
+        wasm.to_str().unwrap(),
+    ])?;
+    let pprof = std::fs::read(&pprof)?;
+    assert_eq!(pprof[0], 0x0a);
+    Ok(())
+}
+
 #[test]
 fn unreachable_without_wasi() -> Result<()> {
     let output = run_wasmtime_for_output(
@@ -2124,3 +2235,323 @@ fn unreachable_without_wasi() -> Result<()> {
     assert_trap_code(&output.status);
     Ok(())
 }
+
+#[test]
+fn wizer() -> Result<()> {
+    let td = TempDir::new()?;
+    let input = td.path().join("input.wat");
+    let output = td.path().join("output.wasm");
+    std::fs::write(
+        &input,
+        r#"
+            (module
+                (memory (export "memory") 1)
+                (global $g (mut i32) (i32.const 0))
+                (func (export "wizer.initialize")
+                    (global.set $g (i32.const 40))
+                    (i32.store (i32.const 8) (i32.const 2)))
+                (func (export "get") (result i32)
+                    (i32.add (global.get $g) (i32.load (i32.const 8))))
+            )
+        "#,
+    )?;
+    let stdout = run_wasmtime(&[
+        "wizer",
+        "-Ccache=n",
+        "-o",
+        output.to_str().unwrap(),
+        input.to_str().unwrap(),
+    ])?;
+    assert_eq!(stdout, "");
+    let stdout = run_wasmtime(&[
+        "run",
+        "-Ccache=n",
+        "--invoke",
+        "get",
+        output.to_str().unwrap(),
+    ])?;
+    assert_eq!(stdout, "42\n");
+    Ok(())
+}
+
+#[test]
+fn debug_adapter() -> Result<()> {
+    use serde_json::{json, Value};
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpStream;
+
+    let wasm = r#"
+        (module
+            (func $add (param i32 i32) (result i32)
+                local.get 0
+                local.get 1
+                i32.add)
+            (func (export "_start")
+                (drop (call $add (i32.const 1) (i32.const 2))))
+        )
+    "#;
+    let wasm = wat::parse_str(wasm)?;
+    let td = TempDir::new()?;
+    let path = td.path().join("debug.wasm");
+    std::fs::write(&path, &wasm)?;
+    // The offset of the first `local.get` in `$add`.
+    let add_start = wasm
+        .windows(4)
+        .position(|w| w == [0x20, 0, 0x20, 1])
+        .unwrap();
+
+    let mut child = get_wasmtime_command()?
+        .args(&["run", "-Ccache=n", "--debug-adapter", "127.0.0.1:0"])
+        .arg(&path)
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stderr = BufReader::new(child.stderr.take().unwrap());
+    let mut line = String::new();
+    stderr.read_line(&mut line)?;
+    let addr = line
+        .strip_prefix("Waiting for a debugger on ")
+        .and_then(|s| s.trim().strip_suffix("..."))
+        .unwrap();
+
+    let mut stream = TcpStream::connect(addr)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut seq = 0;
+    let mut request = |command: &str, arguments: Value| -> Result<()> {
+        seq += 1;
+        let body = json!({
+            "seq": seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        })
+        .to_string();
+        write!(stream, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+        Ok(())
+    };
+    let mut next = |ty: &str, name: &str| -> Result<Value> {
+        loop {
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line)?;
+                match line.trim().strip_prefix("Content-Length:") {
+                    Some(n) => len = n.trim().parse()?,
+                    None if line.trim().is_empty() => break,
+                    None => {}
+                }
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body)?;
+            let message: Value = serde_json::from_slice(&body)?;
+            let key = if ty == "event" { "event" } else { "command" };
+            if message["type"] == ty && message[key] == name {
+                return Ok(message);
+            }
+        }
+    };
+
+    request("initialize", json!({}))?;
+    next("event", "initialized")?;
+    request("launch", json!({}))?;
+    request(
+        "setInstructionBreakpoints",
+        json!({ "breakpoints": [{ "instructionReference": format!("{add_start:#x}") }] }),
+    )?;
+    let response = next("response", "setInstructionBreakpoints")?;
+    assert_eq!(response["body"]["breakpoints"][0]["verified"], true);
+    request("configurationDone", json!({}))?;
+
+    let stopped = next("event", "stopped")?;
+    assert_eq!(stopped["body"]["reason"], "breakpoint");
+    request("stackTrace", json!({ "threadId": 1 }))?;
+    let frames = next("response", "stackTrace")?["body"]["stackFrames"].clone();
+    assert_eq!(frames.as_array().unwrap().len(), 2);
+    assert_eq!(frames[0]["name"], "add");
+    request("variables", json!({ "variablesReference": 1 }))?;
+    let locals = next("response", "variables")?["body"]["variables"].clone();
+    assert_eq!(locals[0]["value"], "1");
+    assert_eq!(locals[1]["value"], "2");
+
+    request("stepOut", json!({ "threadId": 1 }))?;
+    let stopped = next("event", "stopped")?;
+    assert_eq!(stopped["body"]["reason"], "step");
+    request("continue", json!({ "threadId": 1 }))?;
+    let exited = next("event", "exited")?;
+    assert_eq!(exited["body"]["exitCode"], 0);
+    request("disconnect", json!({}))?;
+    next("response", "disconnect")?;
+
+    assert!(child.wait()?.success());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "cranelift"), ignore)]
+fn record_and_replay() -> Result<()> {
+    let td = TempDir::new()?;
+    let wasm = td.path().join("random.wat");
+    std::fs::write(
+        &wasm,
+        r#"
+            (module
+                (import "wasi_snapshot_preview1" "random_get"
+                    (func $random_get (param i32 i32) (result i32)))
+                (import "wasi_snapshot_preview1" "proc_exit"
+                    (func $proc_exit (param i32)))
+                (memory (export "memory") 1)
+                (func (export "_start")
+                    (drop (call $random_get (i32.const 0) (i32.const 1)))
+                    (call $proc_exit (i32.and (i32.load (i32.const 0)) (i32.const 0x7f)))))
+        "#,
+    )?;
+    let trace = td.path().join("run.trace");
+    let run = |flag: &str| -> Result<Option<i32>> {
+        let output = get_wasmtime_command()?
+            .args(&[
+                "run",
+                "-Ccache=n",
+                "-Wdeterministic",
+                &format!("-S{flag}={}", trace.display()),
+                wasm.to_str().unwrap(),
+            ])
+            .output()?;
+        Ok(output.status.code())
+    };
+    let recorded = run("record")?;
+    assert!(recorded.is_some());
+    assert_eq!(run("replay")?, recorded);
+    assert_eq!(run("replay")?, recorded);
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "cranelift"), ignore)]
+fn record_and_replay_component() -> Result<()> {
+    let td = TempDir::new()?;
+    let wasm = td.path().join("random.wat");
+    std::fs::write(
+        &wasm,
+        r#"
+            (component
+                (import "wasi:random/random@0.2.0" (instance $random
+                    (export "get-random-u64" (func (result u64)))
+                ))
+                (import "wasi:cli/exit@0.2.0" (instance $exit
+                    (export "exit-with-code" (func (param "status-code" u8)))
+                ))
+                (core func $random (canon lower (func $random "get-random-u64")))
+                (core func $exit (canon lower (func $exit "exit-with-code")))
+                (core module $m
+                    (import "" "random" (func $random (result i64)))
+                    (import "" "exit" (func $exit (param i32)))
+                    (func (export "run") (result i32)
+                        (call $exit (i32.and (i32.wrap_i64 (call $random)) (i32.const 0x7f)))
+                        unreachable))
+                (core instance $i (instantiate $m
+                    (with "" (instance
+                        (export "random" (func $random))
+                        (export "exit" (func $exit))
+                    ))
+                ))
+                (func $run (result (result)) (canon lift (core func $i "run")))
+                (instance $run (export "run" (func $run)))
+                (export "wasi:cli/run@0.2.0" (instance $run))
+            )
+        "#,
+    )?;
+    let trace = td.path().join("run.trace");
+    let run = |flag: &str| -> Result<Option<i32>> {
+        let output = get_wasmtime_command()?
+            .args(&[
+                "run",
+                "-Ccache=n",
+                "-Wdeterministic",
+                "-Scli-exit-with-code",
+                &format!("-S{flag}={}", trace.display()),
+                wasm.to_str().unwrap(),
+            ])
+            .output()?;
+        Ok(output.status.code())
+    };
+    let recorded = run("record")?;
+    assert!(recorded.is_some());
+    assert_eq!(run("replay")?, recorded);
+    assert_eq!(run("replay")?, recorded);
+    Ok(())
+}
+
+fn run_repl(wasm: &str, input: &str) -> Result<(String, String)> {
+    let mut child = get_wasmtime_command()?
+        .args(&["repl", "-Ccache=n", wasm])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_string();
+    let t = std::thread::spawn(move || {
+        let _ = stdin.write_all(input.as_bytes());
+    });
+    let output = child.wait_with_output()?;
+    t.join().unwrap();
+    assert!(output.status.success(), "{output:?}");
+    Ok((
+        String::from_utf8(output.stdout)?,
+        String::from_utf8(output.stderr)?,
+    ))
+}
+
+#[test]
+fn repl_module() -> Result<()> {
+    let (stdout, stderr) = run_repl(
+        "tests/all/cli_tests/repl-core.wat",
+        "add(1, 2)\n\
+         bump()\n\
+         bump()\n\
+         :global counter\n\
+         :memory memory 0 16\n\
+         trap()\n\
+         bump()\n",
+    )?;
+    assert_eq!(
+        stdout,
+        "3\n\
+         1\n\
+         2\n\
+         2\n\
+         00000000: 68 65 6c 6c 6f 2c 20 77 6f 72 6c 64 00 00 00 00  |hello, world....|\n\
+         3\n"
+    );
+    assert!(stderr.contains("failed to invoke `trap`"), "{stderr}");
+    Ok(())
+}
+
+#[test]
+fn repl_component() -> Result<()> {
+    let (stdout, stderr) = run_repl(
+        "tests/all/cli_tests/repl-counter.wat",
+        ":exports\n\
+         [constructor]counter(7)\n\
+         [constructor]counter(9)\n\
+         [method]counter.get(@handle(1))\n\
+         example:math/ops#add(2, 3)\n\
+         :drop 0\n\
+         :handles\n\
+         [method]counter.get(@handle(0))\n",
+    )?;
+    assert_eq!(
+        stdout,
+        "counter: resource\n\
+         example:math/ops: instance\n\
+         example:math/ops#add: func(a: u32, b: u32) -> u32\n\
+         [constructor]counter: func(x: u32) -> counter\n\
+         [method]counter.get: func(self: borrow<counter>) -> u32\n\
+         @handle(0)\n\
+         @handle(1)\n\
+         9\n\
+         5\n\
+         @handle(1): counter\n"
+    );
+    assert_eq!(stderr, "error: unknown handle `@handle(0)`\n");
+    Ok(())
+}
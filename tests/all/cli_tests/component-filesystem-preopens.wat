@@ -0,0 +1,9 @@
+(component
+  (import (interface "wasi:filesystem/types@0.2.0") (instance $types
+    (export "descriptor" (type (sub resource)))
+  ))
+  (alias export $types "descriptor" (type $descriptor))
+  (import (interface "wasi:filesystem/preopens@0.2.0") (instance
+    (export "descriptor" (type (eq $descriptor)))
+  ))
+)
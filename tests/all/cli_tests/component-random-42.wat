@@ -0,0 +1,19 @@
+(component
+  (import (interface "wasi:random/random@0.2.0") (instance $random
+    (export "get-random-u64" (func (result u64)))
+  ))
+  (core func $get-random-u64 (canon lower (func $random "get-random-u64")))
+  (core module $m
+    (import "" "get-random-u64" (func $get-random-u64 (result i64)))
+    (func (export "run") (result i32)
+      (i64.ne (call $get-random-u64) (i64.const 42)))
+  )
+  (core instance $i (instantiate $m
+    (with "" (instance (export "get-random-u64" (func $get-random-u64))))
+  ))
+  (func $run (result (result))
+    (canon lift (core func $i "run")))
+
+  (instance (export (interface "wasi:cli/run@0.2.0"))
+    (export "run" (func $run)))
+)
@@ -0,0 +1,9 @@
+(module
+  (memory (export "memory") 1)
+  (global $g (export "counter") (mut i32) (i32.const 0))
+  (data (i32.const 0) "hello, world")
+  (func (export "add") (param i32 i32) (result i32)
+    local.get 0 local.get 1 i32.add)
+  (func (export "bump") (result i32)
+    global.get $g i32.const 1 i32.add global.set $g global.get $g)
+  (func (export "trap") unreachable))
@@ -0,0 +1,17 @@
+(component
+  (type $t' (resource (rep i32)))
+  (export $t "counter" (type $t'))
+  (core func $new (canon resource.new $t))
+  (core module $m
+    (func (export "id") (param i32) (result i32) local.get 0)
+    (func (export "add") (param i32 i32) (result i32) local.get 0 local.get 1 i32.add))
+  (core instance $i (instantiate $m))
+  (func (export "[constructor]counter") (param "x" u32) (result (own $t))
+    (canon lift (core func $new)))
+  (func (export "[method]counter.get") (param "self" (borrow $t)) (result u32)
+    (canon lift (core func $i "id")))
+  (func $add (param "a" u32) (param "b" u32) (result u32)
+    (canon lift (core func $i "add")))
+  (instance $math (export "add" (func $add)))
+  (export "example:math/ops" (instance $math))
+)
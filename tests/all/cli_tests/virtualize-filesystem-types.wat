@@ -0,0 +1,5 @@
+(component
+  (type $descriptor (resource (rep i32)))
+  (instance (export (interface "wasi:filesystem/types@0.2.0"))
+    (export "descriptor" (type $descriptor)))
+)
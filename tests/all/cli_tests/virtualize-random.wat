@@ -0,0 +1,12 @@
+(component
+  (core module $m
+    (func (export "get-random-u64") (result i64)
+      i64.const 42)
+  )
+  (core instance $i (instantiate $m))
+  (func $get-random-u64 (result u64)
+    (canon lift (core func $i "get-random-u64")))
+
+  (instance (export (interface "wasi:random/random@0.2.0"))
+    (export "get-random-u64" (func $get-random-u64)))
+)
@@ -0,0 +1,59 @@
+#![cfg(not(miri))]
+
+use anyhow::Result;
+use std::time::Duration;
+use wasmtime::component::*;
+use wasmtime::{Engine, GuestProfiler, Store, StoreContextMut};
+
+#[test]
+fn functions_are_attributed_to_the_component() -> Result<()> {
+    let engine = Engine::default();
+    let component = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "sample" (func $sample))
+                (core func $sample_lower (canon lower (func $sample)))
+                (core module $m
+                    (import "" "sample" (func $sample))
+                    (func $leaf
+                        call $sample)
+                    (func $run (export "run")
+                        call $leaf))
+                (core instance $i (instantiate $m
+                    (with "" (instance (export "sample" (func $sample_lower))))))
+                (func (export "run") (canon lift (core func $i "run")))
+            )
+        "#,
+    )?;
+
+    let profiler = GuestProfiler::new_component(
+        "guest",
+        Duration::from_millis(1),
+        component.clone(),
+        Vec::new(),
+    );
+    let mut store = Store::new(&engine, Some(profiler));
+    let mut linker = Linker::new(&engine);
+    linker.root().func_wrap(
+        "sample",
+        |mut store: StoreContextMut<'_, Option<GuestProfiler>>, (): ()| {
+            let mut profiler = store.data_mut().take().unwrap();
+            profiler.sample(&store, Duration::ZERO);
+            *store.data_mut() = Some(profiler);
+            Ok(())
+        },
+    )?;
+    let instance = linker.instantiate(&mut store, &component)?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    run.call(&mut store, ())?;
+
+    let mut collapsed = Vec::new();
+    let profiler = store.into_data().unwrap();
+    profiler.finish_collapsed(&mut collapsed)?;
+    assert_eq!(
+        String::from_utf8(collapsed)?,
+        "guest/m`run;guest/m`leaf 1\n"
+    );
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use std::sync::{Arc, Mutex};
 use wasmtime::component::types::ComponentItem;
 use wasmtime::component::{Component, Linker, ResourceType};
 use wasmtime::{Engine, Store};
@@ -181,3 +182,394 @@ fn linker_fails_to_define_unknown_core_module_imports_as_traps() -> Result<()> {
 
     Ok(())
 }
+
+const PROVIDER: &str = r#"
+    (component
+        (core module $m
+            (func (export "add") (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1)))
+            (func (export "fail") unreachable)
+        )
+        (core instance $i (instantiate $m))
+        (func $add (param "a" u32) (param "b" u32) (result u32)
+            (canon lift (core func $i "add")))
+        (func $fail (canon lift (core func $i "fail")))
+        (instance $api
+            (export "add" (func $add))
+            (export "fail" (func $fail))
+        )
+        (export "my:pkg/api" (instance $api))
+        (export "fail" (func $fail))
+    )
+"#;
+
+fn consumer(engine: &Engine, interface: &str) -> Result<Component> {
+    Component::new(
+        engine,
+        format!(
+            r#"
+                (component
+                    (import "{interface}" (instance $api
+                        (export "add" (func (param "a" u32) (param "b" u32) (result u32)))
+                    ))
+                    (import "fail" (func $fail))
+                    (core func $add (canon lower (func $api "add")))
+                    (core func $fail (canon lower (func $fail)))
+                    (core module $m
+                        (import "" "add" (func $add (param i32 i32) (result i32)))
+                        (import "" "fail" (func $fail))
+                        (func (export "run") (param i32) (result i32)
+                            (call $add (local.get 0) (i32.const 1)))
+                        (func (export "fail") call $fail)
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance
+                            (export "add" (func $add))
+                            (export "fail" (func $fail))
+                        ))
+                    ))
+                    (func (export "run") (param "x" u32) (result u32)
+                        (canon lift (core func $i "run")))
+                    (func (export "fail") (canon lift (core func $i "fail")))
+                )
+            "#
+        ),
+    )
+}
+
+#[test]
+fn link_component_forwards_calls() -> Result<()> {
+    let engine = Engine::default();
+    let mut linker = Linker::<()>::new(&engine);
+    let mut store = Store::new(&engine, ());
+
+    let provider = Component::new(&engine, PROVIDER)?;
+    let provider = linker.instantiate(&mut store, &provider)?;
+    linker.link_component(&mut store, &provider)?;
+
+    let consumer = linker.instantiate(&mut store, &consumer(&engine, "my:pkg/api")?)?;
+    let run = consumer.get_typed_func::<(u32,), (u32,)>(&mut store, "run")?;
+    for i in 0..3 {
+        assert_eq!(run.call(&mut store, (i,))?, (i + 1,));
+        run.post_return(&mut store)?;
+    }
+
+    let fail = consumer.get_typed_func::<(), ()>(&mut store, "fail")?;
+    let err = fail.call(&mut store, ()).unwrap_err();
+    assert_eq!(
+        err.downcast::<wasmtime::Trap>()?,
+        wasmtime::Trap::UnreachableCodeReached
+    );
+    Ok(())
+}
+
+#[test]
+fn link_component_is_bound_to_store() -> Result<()> {
+    let engine = Engine::default();
+    let mut linker = Linker::<()>::new(&engine);
+    let mut store = Store::new(&engine, ());
+
+    let provider = Component::new(&engine, PROVIDER)?;
+    let provider = linker.instantiate(&mut store, &provider)?;
+    linker.link_component(&mut store, &provider)?;
+
+    let consumer = consumer(&engine, "my:pkg/api")?;
+    let mut other = Store::new(&engine, ());
+    let err = match linker.instantiate(&mut other, &consumer) {
+        Ok(_) => panic!("instantiated in a different store"),
+        Err(e) => e,
+    };
+    assert!(
+        err.to_string().contains("different store"),
+        "unexpected error: {err:?}"
+    );
+    linker.instantiate(&mut store, &consumer)?;
+    Ok(())
+}
+
+#[test]
+fn instance_exports_from_renames_interface() -> Result<()> {
+    let engine = Engine::default();
+    let mut linker = Linker::<()>::new(&engine);
+    let mut store = Store::new(&engine, ());
+
+    let provider = Component::new(&engine, PROVIDER)?;
+    let api = provider.export_index(None, "my:pkg/api").unwrap().1;
+    let fail = provider.export_index(None, "fail").unwrap().1;
+    let provider = linker.instantiate(&mut store, &provider)?;
+    linker
+        .instance("other:pkg/api")?
+        .instance_exports_from(&mut store, &provider, Some(&api))?;
+    linker
+        .root()
+        .func_new("fail", |_, _, _| anyhow::bail!("host failure"))?;
+
+    let consumer = linker.instantiate(&mut store, &consumer(&engine, "other:pkg/api")?)?;
+    let run = consumer.get_typed_func::<(u32,), (u32,)>(&mut store, "run")?;
+    assert_eq!(run.call(&mut store, (41,))?, (42,));
+
+    // Only exported instances can be linked this way.
+    assert!(linker
+        .root()
+        .instance_exports_from(&mut store, &provider, Some(&fail))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn link_component_forwards_resources() -> Result<()> {
+    let engine = Engine::default();
+    let mut linker = Linker::<()>::new(&engine);
+    let mut store = Store::new(&engine, ());
+
+    let provider = Component::new(
+        &engine,
+        r#"
+            (component
+                (core module $d
+                    (global $drops (export "drops") (mut i32) (i32.const 0))
+                    (func (export "dtor") (param i32)
+                        (global.set $drops (i32.add (global.get $drops) (i32.const 1))))
+                )
+                (core instance $d (instantiate $d))
+                (type $r (resource (rep i32) (dtor (func $d "dtor"))))
+                (core func $new (canon resource.new $r))
+                (core module $m
+                    (import "" "new" (func $new (param i32) (result i32)))
+                    (import "" "drops" (global $drops (mut i32)))
+                    (func (export "make") (param i32) (result i32) (call $new (local.get 0)))
+                    (func (export "get") (param i32) (result i32) local.get 0)
+                    (func (export "drops") (result i32) global.get $drops)
+                )
+                (core instance $i (instantiate $m
+                    (with "" (instance
+                        (export "new" (func $new))
+                        (export "drops" (global $d "drops"))
+                    ))
+                ))
+                (export $r' "r" (type $r))
+                (func (export "make") (param "rep" u32) (result (own $r'))
+                    (canon lift (core func $i "make")))
+                (func (export "get") (param "r" (borrow $r')) (result u32)
+                    (canon lift (core func $i "get")))
+                (func (export "drops") (result u32)
+                    (canon lift (core func $i "drops")))
+            )
+        "#,
+    )?;
+    let provider = linker.instantiate(&mut store, &provider)?;
+    linker.link_component(&mut store, &provider)?;
+
+    let consumer = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "r" (type $r (sub resource)))
+                (import "make" (func $make (param "rep" u32) (result (own $r))))
+                (import "get" (func $get (param "r" (borrow $r)) (result u32)))
+                (import "drops" (func $drops (result u32)))
+                (core func $make (canon lower (func $make)))
+                (core func $get (canon lower (func $get)))
+                (core func $drops (canon lower (func $drops)))
+                (core func $drop (canon resource.drop $r))
+                (core module $m
+                    (import "" "make" (func $make (param i32) (result i32)))
+                    (import "" "get" (func $get (param i32) (result i32)))
+                    (import "" "drops" (func $drops (result i32)))
+                    (import "" "drop" (func $drop (param i32)))
+                    (func (export "run") (result i32)
+                        (local $h i32)
+                        (local.set $h (call $make (i32.const 42)))
+                        (if (i32.ne (call $get (local.get $h)) (i32.const 42))
+                            (then unreachable))
+                        (if (i32.ne (call $drops) (i32.const 0))
+                            (then unreachable))
+                        (call $drop (local.get $h))
+                        call $drops)
+                )
+                (core instance $i (instantiate $m
+                    (with "" (instance
+                        (export "make" (func $make))
+                        (export "get" (func $get))
+                        (export "drops" (func $drops))
+                        (export "drop" (func $drop))
+                    ))
+                ))
+                (func (export "run") (result u32) (canon lift (core func $i "run")))
+            )
+        "#,
+    )?;
+    let consumer = linker.instantiate(&mut store, &consumer)?;
+    let run = consumer.get_typed_func::<(), (u32,)>(&mut store, "run")?;
+    assert_eq!(run.call(&mut store, ())?, (1,));
+    Ok(())
+}
+
+/// A bump allocator used as `realloc` by the components below, which copies
+/// the old allocation when growing.
+const REALLOC: &str = r#"
+    (global $next (mut i32) (i32.const 1024))
+    (func (export "realloc") (param $old i32) (param $old_size i32)
+                             (param $align i32) (param $size i32) (result i32)
+        (local $ret i32)
+        (if (i32.le_u (local.get $size) (local.get $old_size))
+            (then (return (local.get $old))))
+        (local.set $ret
+            (i32.and
+                (i32.add (global.get $next) (i32.sub (local.get $align) (i32.const 1)))
+                (i32.sub (i32.const 0) (local.get $align))))
+        (global.set $next (i32.add (local.get $ret) (local.get $size)))
+        (memory.copy (local.get $ret) (local.get $old) (local.get $old_size))
+        local.get $ret)
+"#;
+
+#[test]
+fn link_component_transcodes_strings() -> Result<()> {
+    let engine = Engine::default();
+
+    for encoding in ["utf8", "utf16", "latin1+utf16"] {
+        let mut linker = Linker::<()>::new(&engine);
+        let mut store = Store::new(&engine, ());
+
+        let provider = Component::new(
+            &engine,
+            format!(
+                r#"
+                    (component
+                        (core module $m
+                            (memory (export "memory") 1)
+                            {REALLOC}
+                            (func (export "echo") (param i32 i32) (result i32)
+                                (i32.store (i32.const 16) (local.get 0))
+                                (i32.store (i32.const 20) (local.get 1))
+                                i32.const 16)
+                        )
+                        (core instance $i (instantiate $m))
+                        (func (export "echo") (param "s" string) (result string)
+                            (canon lift (core func $i "echo")
+                                (memory $i "memory")
+                                (realloc (func $i "realloc"))
+                                string-encoding={encoding}))
+                    )
+                "#
+            ),
+        )?;
+        let provider = linker.instantiate(&mut store, &provider)?;
+        linker.link_component(&mut store, &provider)?;
+
+        let consumer = Component::new(
+            &engine,
+            format!(
+                r#"
+                    (component
+                        (import "echo" (func $echo (param "s" string) (result string)))
+                        (core module $libc
+                            (memory (export "memory") 1)
+                            {REALLOC}
+                        )
+                        (core instance $libc (instantiate $libc))
+                        (core func $echo (canon lower (func $echo)
+                            (memory $libc "memory")
+                            (realloc (func $libc "realloc"))))
+                        (core module $m
+                            (import "" "echo" (func $echo (param i32 i32 i32)))
+                            (func (export "run") (param i32 i32) (result i32)
+                                (call $echo (local.get 0) (local.get 1) (i32.const 16))
+                                i32.const 16)
+                        )
+                        (core instance $i (instantiate $m
+                            (with "" (instance (export "echo" (func $echo))))
+                        ))
+                        (func (export "run") (param "s" string) (result string)
+                            (canon lift (core func $i "run")
+                                (memory $libc "memory")
+                                (realloc (func $libc "realloc"))))
+                    )
+                "#
+            ),
+        )?;
+        let consumer = linker.instantiate(&mut store, &consumer)?;
+        let run = consumer.get_typed_func::<(&str,), (String,)>(&mut store, "run")?;
+        for s in ["", "hello", "h\u{e9}llo", "h\u{e9}llo \u{2603} \u{1f980}"] {
+            let (result,) = run.call(&mut store, (s,))?;
+            assert_eq!(result, s, "with {encoding}");
+            run.post_return(&mut store)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn link_component_typechecks_imports() -> Result<()> {
+    let engine = Engine::default();
+    let mut linker = Linker::<()>::new(&engine);
+    let mut store = Store::new(&engine, ());
+
+    let provider = Component::new(&engine, PROVIDER)?;
+    let provider = linker.instantiate(&mut store, &provider)?;
+    linker.link_component(&mut store, &provider)?;
+
+    let consumer = Component::new(
+        &engine,
+        r#"
+            (component
+                (import "my:pkg/api" (instance
+                    (export "add" (func (param "a" u64) (param "b" u64) (result u64)))
+                ))
+            )
+        "#,
+    )?;
+    let err = match linker.instantiate(&mut store, &consumer) {
+        Ok(_) => panic!("instantiated with a mismatched type"),
+        Err(e) => e,
+    };
+    assert!(
+        format!("{err:?}").contains("type mismatch with parameters"),
+        "unexpected error: {err:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn observe_host_calls() -> Result<()> {
+    let engine = Engine::default();
+    let mut linker = Linker::<()>::new(&engine);
+    let mut store = Store::new(&engine, ());
+
+    linker
+        .instance("my:pkg/api")?
+        .func_wrap("add", |_, (a, b): (u32, u32)| Ok((a + b,)))?;
+    linker
+        .root()
+        .func_new("fail", |_, _, _| anyhow::bail!("host failure"))?;
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let observed = calls.clone();
+    linker.observe_host_calls(move |_, name, result| {
+        let result = match result {
+            Ok(results) => format!("{results:?}"),
+            Err(e) => e.to_string(),
+        };
+        observed.lock().unwrap().push(format!("{name}: {result}"));
+        Ok(())
+    });
+
+    let component = consumer(&engine, "my:pkg/api")?;
+    let instance = linker.instantiate(&mut store, &component)?;
+    let run = instance.get_typed_func::<(u32,), (u32,)>(&mut store, "run")?;
+    assert_eq!(run.call(&mut store, (41,))?, (42,));
+    run.post_return(&mut store)?;
+    let fail = instance.get_typed_func::<(), ()>(&mut store, "fail")?;
+    assert!(fail.call(&mut store, ()).is_err());
+    assert_eq!(
+        *calls.lock().unwrap(),
+        ["my:pkg/api#add: [U32(42)]", "fail: host failure"]
+    );
+
+    // An error from the observer fails the call.
+    linker.observe_host_calls(|_, _, _| anyhow::bail!("observer failure"));
+    let instance = linker.instantiate(&mut store, &component)?;
+    let run = instance.get_typed_func::<(u32,), (u32,)>(&mut store, "run")?;
+    let err = run.call(&mut store, (41,)).unwrap_err();
+    assert!(format!("{err:?}").contains("observer failure"), "{err:?}");
+    Ok(())
+}
@@ -258,3 +258,160 @@ fn multiple_globals_memories_and_instances() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn coredump_has_component_state() -> Result<()> {
+    use wasmtime::component::{Component, Linker, ResourceAny};
+
+    let mut config = Config::default();
+    config.coredump_on_trap(true);
+    let engine = Engine::new(&config).unwrap();
+    let mut store = Store::<()>::new(&engine, ());
+
+    let component = Component::new(
+        &engine,
+        r#"
+            (component
+                (type $r (resource (rep i32)))
+                (core func $new (canon resource.new $r))
+                (core module $m
+                    (import "" "new" (func $new (param i32) (result i32)))
+                    (func (export "make") (result i32) (call $new (i32.const 5)))
+                    (func (export "boom")
+                        (drop (call $new (i32.const 7)))
+                        (drop (call $new (i32.const 8)))
+                        unreachable)
+                )
+                (core instance $i (instantiate $m
+                    (with "" (instance (export "new" (func $new))))
+                ))
+                (export $r' "r" (type $r))
+                (func (export "make") (result (own $r'))
+                    (canon lift (core func $i "make")))
+                (func $boom (canon lift (core func $i "boom")))
+                (instance $api (export "boom" (func $boom)))
+                (export "api" (instance $api))
+            )
+        "#,
+    )?;
+    let instance = Linker::new(&engine).instantiate(&mut store, &component)?;
+    let make = instance.get_typed_func::<(), (ResourceAny,)>(&mut store, "make")?;
+    let (_resource,) = make.call(&mut store, ())?;
+    make.post_return(&mut store)?;
+
+    let api = instance.get_export(&mut store, None, "api").unwrap();
+    let boom = instance.get_export(&mut store, Some(&api), "boom").unwrap();
+    let boom = instance.get_typed_func::<(), ()>(&mut store, &boom)?;
+    let e = boom.call(&mut store, ()).unwrap_err();
+    let cd = e.downcast_ref::<WasmCoreDump>().unwrap();
+
+    assert_eq!(cd.component_instances().len(), 1);
+    let component = &cd.component_instances()[0];
+    assert!(!component.core_instances().is_empty());
+    for i in component.core_instances() {
+        assert!(*i < cd.instances().len());
+    }
+    assert_eq!(component.resource_tables().len(), 1);
+    let handles = component.resource_tables()[0].handles();
+    assert_eq!(handles.len(), 2);
+    assert_eq!(handles[0].rep(), 7);
+    assert_eq!(handles[1].rep(), 8);
+    assert!(handles.iter().all(|h| h.is_own() && h.lend_count() == 0));
+
+    assert_eq!(cd.component_frames().len(), 1);
+    assert_eq!(cd.component_frames()[0].instance(), 0);
+    assert_eq!(cd.component_frames()[0].export(), Some("api#boom"));
+    assert_eq!(cd.component_frames()[0].adapter(), None);
+
+    assert_eq!(cd.host_resource_handles().len(), 1);
+    assert_eq!(cd.host_resource_handles()[0].rep(), 5);
+
+    // The component state is serialized into a custom section which can be
+    // read back.
+    let bytes = cd.serialize(&mut store, "test");
+    let parsed = ComponentCoreDump::parse(&component_coredump_section(&bytes))?;
+    assert_eq!(&parsed, cd.component_core_dump());
+
+    Ok(())
+}
+
+fn component_coredump_section(coredump: &[u8]) -> Vec<u8> {
+    wasmparser::Parser::new(0)
+        .parse_all(coredump)
+        .find_map(|payload| match payload {
+            Ok(wasmparser::Payload::CustomSection(s))
+                if s.name() == ComponentCoreDump::SECTION_NAME =>
+            {
+                Some(s.data().to_vec())
+            }
+            _ => None,
+        })
+        .expect("should have a component-coredump section")
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn coredump_has_adapter_frames() -> Result<()> {
+    use wasmtime::component::{Component, Linker};
+
+    let mut config = Config::default();
+    config.coredump_on_trap(true);
+    let engine = Engine::new(&config).unwrap();
+    let mut store = Store::<()>::new(&engine, ());
+
+    let component = Component::new(
+        &engine,
+        r#"
+            (component
+                (component $callee
+                    (core module $m
+                        (func (export "boom") unreachable)
+                    )
+                    (core instance $i (instantiate $m))
+                    (func (export "boom") (canon lift (core func $i "boom")))
+                )
+                (component $caller
+                    (import "boom" (func $boom))
+                    (core func $boom (canon lower (func $boom)))
+                    (core module $m
+                        (import "" "boom" (func $boom))
+                        (func (export "run") call $boom)
+                    )
+                    (core instance $i (instantiate $m
+                        (with "" (instance (export "boom" (func $boom))))
+                    ))
+                    (func (export "run") (canon lift (core func $i "run")))
+                )
+                (instance $callee (instantiate $callee))
+                (instance $caller (instantiate $caller
+                    (with "boom" (func $callee "boom"))
+                ))
+                (export "run" (func $caller "run"))
+            )
+        "#,
+    )?;
+    let instance = Linker::new(&engine).instantiate(&mut store, &component)?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    let e = run.call(&mut store, ()).unwrap_err();
+    let cd = e.downcast_ref::<WasmCoreDump>().unwrap();
+
+    let frames = cd.component_frames();
+    assert_eq!(frames.len(), 2, "{frames:?}");
+    assert_eq!(frames[0].instance(), 0);
+    assert_eq!(frames[0].export(), None);
+    // The root component is nested instance 0, followed by `$callee` and
+    // `$caller` in the order they're instantiated.
+    assert_eq!(frames[0].adapter(), Some((2, 1)));
+    assert_eq!(frames[1].instance(), 0);
+    assert_eq!(frames[1].export(), Some("run"));
+
+    let bytes = cd.serialize(&mut store, "test");
+    let parsed = ComponentCoreDump::parse(&component_coredump_section(&bytes))?;
+    assert_eq!(&parsed, cd.component_core_dump());
+
+    // Other versions of the format and malformed sections are rejected.
+    assert!(ComponentCoreDump::parse(&[1, 0, 0, 0]).is_err());
+    assert!(ComponentCoreDump::parse(&[2, 0, 1]).is_err());
+    Ok(())
+}
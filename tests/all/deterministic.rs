@@ -0,0 +1,274 @@
+use std::fs::File;
+use wasmtime::*;
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::WasiCtxBuilder;
+
+fn deterministic_engine() -> Result<Engine> {
+    let mut config = Config::new();
+    config.deterministic(true);
+    Engine::new(&config)
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn canonicalizes_nans() -> Result<()> {
+    let engine = deterministic_engine()?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (func (export "nan32") (param f32) (result i32)
+                    (i32.reinterpret_f32 (f32.div (local.get 0) (local.get 0))))
+                (func (export "nan64") (param f64) (result i64)
+                    (i64.reinterpret_f64 (f64.sqrt (local.get 0)))))
+        "#,
+    )?;
+    let mut store = Store::new(&engine, ());
+    store.set_fuel(u64::MAX)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let nan32 = instance.get_typed_func::<f32, u32>(&mut store, "nan32")?;
+    let nan64 = instance.get_typed_func::<f64, u64>(&mut store, "nan64")?;
+    assert_eq!(nan32.call(&mut store, 0.0)?, 0x7fc0_0000);
+    assert_eq!(nan64.call(&mut store, -1.0)?, 0x7ff8_0000_0000_0000);
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn enables_fuel() -> Result<()> {
+    let engine = deterministic_engine()?;
+    let module = Module::new(&engine, r#"(module (func (export "run") (loop br 0)))"#)?;
+    let mut store = Store::new(&engine, ());
+    store.set_fuel(1000)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    let trap = run.call(&mut store, ()).unwrap_err().downcast::<Trap>()?;
+    assert_eq!(trap, Trap::OutOfFuel);
+    Ok(())
+}
+
+#[test]
+fn rejects_epoch_interruption() {
+    let mut config = Config::new();
+    config.deterministic(true).epoch_interruption(true);
+    let err = Engine::new(&config).err().unwrap();
+    assert!(err.to_string().contains("epoch interruption"), "{err}");
+}
+
+const WASI_MODULE: &str = r#"
+    (module
+        (import "wasi_snapshot_preview1" "random_get"
+            (func $random_get (param i32 i32) (result i32)))
+        (import "wasi_snapshot_preview1" "clock_time_get"
+            (func $clock_time_get (param i32 i64 i32) (result i32)))
+        (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+        (memory (export "memory") 1)
+        (func (export "run") (result i64 i64)
+            (drop (call $random_get (i32.const 0) (i32.const 8)))
+            (drop (call $clock_time_get (i32.const 0) (i64.const 1) (i32.const 8)))
+            (i64.load (i32.const 0))
+            (i64.load (i32.const 8)))
+        (func (export "exit")
+            (call $proc_exit (i32.const 7))))
+"#;
+
+fn wasi_store(engine: &Engine) -> Result<(Linker<WasiP1Ctx>, Store<WasiP1Ctx>)> {
+    let mut linker = Linker::new(engine);
+    preview1::add_to_linker_sync(&mut linker, |t| t)?;
+    let mut store = Store::new(engine, WasiCtxBuilder::new().build_p1());
+    store.set_fuel(u64::MAX)?;
+    Ok((linker, store))
+}
+
+fn run_wasi(
+    engine: &Engine,
+    trace: impl FnOnce(&mut Linker<WasiP1Ctx>, &mut Store<WasiP1Ctx>) -> Result<()>,
+) -> Result<((i64, i64), i32)> {
+    let module = Module::new(engine, WASI_MODULE)?;
+    let (mut linker, mut store) = wasi_store(engine)?;
+    trace(&mut linker, &mut store)?;
+    let instance = linker.instantiate(&mut store, &module)?;
+    let run = instance.get_typed_func::<(), (i64, i64)>(&mut store, "run")?;
+    let exit = instance.get_typed_func::<(), ()>(&mut store, "exit")?;
+    let results = run.call(&mut store, ())?;
+    let code = exit
+        .call(&mut store, ())
+        .unwrap_err()
+        .downcast::<wasmtime_wasi::I32Exit>()?
+        .0;
+    Ok((results, code))
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn record_and_replay_wasi() -> Result<()> {
+    let engine = deterministic_engine()?;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("run.trace");
+
+    let recorded = run_wasi(&engine, |linker, store| {
+        wasmtime_wasi::trace::record(linker, store, File::create(&path)?)?;
+        // Redefining the linker's functions doesn't leave shadowing enabled.
+        assert!(!linker.shadowing_allowed());
+        Ok(())
+    })?;
+    assert_eq!(recorded.1, 7);
+
+    for _ in 0..2 {
+        let replayed = run_wasi(&engine, |linker, store| {
+            wasmtime_wasi::trace::replay(linker, store, File::open(&path)?)
+        })?;
+        assert_eq!(replayed, recorded);
+    }
+
+    // Without the trace, the random number and clock differ.
+    let fresh = run_wasi(&engine, |_, _| Ok(()))?;
+    assert_ne!(fresh.0, recorded.0);
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn replay_detects_divergence() -> Result<()> {
+    let engine = deterministic_engine()?;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("run.trace");
+    run_wasi(&engine, |linker, store| {
+        wasmtime_wasi::trace::record(linker, store, File::create(&path)?)
+    })?;
+
+    let module = Module::new(&engine, WASI_MODULE)?;
+    let (mut linker, mut store) = wasi_store(&engine)?;
+    wasmtime_wasi::trace::replay(&mut linker, &mut store, File::open(&path)?)?;
+    let instance = linker.instantiate(&mut store, &module)?;
+    let exit = instance.get_typed_func::<(), ()>(&mut store, "exit")?;
+    let err = exit.call(&mut store, ()).unwrap_err();
+    assert!(
+        format!("{err:?}")
+            .contains("replay diverged at call to `wasi_snapshot_preview1::proc_exit`"),
+        "{err:?}"
+    );
+    Ok(())
+}
+
+const TRACED_COMPONENT: &str = r#"
+    (component
+        (import "my:host/api@0.1.0" (instance $api
+            (export $t "thing" (type (sub resource)))
+            (export "next" (func (result u64)))
+            (export "make" (func (result (own $t))))
+            (export "bytes" (func (param "n" u32) (result (list u8))))
+        ))
+        (alias export $api "thing" (type $thing))
+
+        (core module $libc
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+                (global.get $next)
+                (global.set $next (i32.add (global.get $next) (local.get 3)))))
+        (core instance $libc (instantiate $libc))
+
+        (core func $next (canon lower (func $api "next")))
+        (core func $make (canon lower (func $api "make")))
+        (core func $bytes (canon lower (func $api "bytes")
+            (memory $libc "memory") (realloc (func $libc "realloc"))))
+        (core func $drop (canon resource.drop $thing))
+        (core module $m
+            (import "" "next" (func $next (result i64)))
+            (import "" "make" (func $make (result i32)))
+            (import "" "bytes" (func $bytes (param i32 i32)))
+            (import "" "drop" (func $drop (param i32)))
+            (import "libc" "memory" (memory 1))
+            (func (export "run") (result i64)
+                (local $h i32)
+                (local.set $h (call $make))
+                (call $bytes (i32.const 4) (i32.const 0))
+                (call $drop (local.get $h))
+                (i64.add
+                    (call $next)
+                    (i64.add
+                        (i64.extend_i32_u (i32.load (i32.load (i32.const 0))))
+                        (i64.extend_i32_u (local.get $h))))))
+        (core instance $i (instantiate $m
+            (with "" (instance
+                (export "next" (func $next))
+                (export "make" (func $make))
+                (export "bytes" (func $bytes))
+                (export "drop" (func $drop))
+            ))
+            (with "libc" (instance $libc))
+        ))
+        (func (export "run") (result u64) (canon lift (core func $i "run")))
+    )
+"#;
+
+struct Thing;
+
+/// Runs `TRACED_COMPONENT` against host functions whose results depend on
+/// `seed`, defined at a newer patch version than the component imports.
+fn run_component(
+    engine: &Engine,
+    seed: u64,
+    trace: impl FnOnce(&mut component::Linker<u64>, &component::Component) -> Result<()>,
+) -> Result<u64> {
+    let component = component::Component::new(engine, TRACED_COMPONENT)?;
+    let mut linker = component::Linker::new(engine);
+    let mut api = linker.instance("my:host/api@0.1.1")?;
+    api.resource("thing", component::ResourceType::host::<Thing>(), |_, _| {
+        Ok(())
+    })?;
+    api.func_wrap("next", |store: StoreContextMut<'_, u64>, ()| {
+        Ok((*store.data(),))
+    })?;
+    api.func_wrap("make", |store: StoreContextMut<'_, u64>, ()| {
+        Ok((component::Resource::<Thing>::new_own(*store.data() as u32),))
+    })?;
+    api.func_wrap("bytes", |store: StoreContextMut<'_, u64>, (n,): (u32,)| {
+        Ok((vec![*store.data() as u8; n as usize],))
+    })?;
+    trace(&mut linker, &component)?;
+
+    let mut store = Store::new(engine, seed);
+    store.set_fuel(u64::MAX)?;
+    let instance = linker.instantiate(&mut store, &component)?;
+    let run = instance.get_typed_func::<(), (u64,)>(&mut store, "run")?;
+    Ok(run.call(&mut store, ())?.0)
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn record_and_replay_component() -> Result<()> {
+    let engine = deterministic_engine()?;
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("run.trace");
+
+    let recorded = run_component(&engine, 3, |linker, _| {
+        wasmtime_wasi::trace::record_component(linker, File::create(&path)?)
+    })?;
+    assert_eq!(recorded, 3 + 0x0303_0303 + 1);
+
+    for _ in 0..2 {
+        let replayed = run_component(&engine, 5, |linker, component| {
+            wasmtime_wasi::trace::replay_component(linker, component, File::open(&path)?)
+        })?;
+        assert_eq!(replayed, recorded);
+    }
+
+    // Without the trace, the results differ.
+    assert_ne!(run_component(&engine, 5, |_, _| Ok(()))?, recorded);
+
+    // Core module traces can't be used with components.
+    run_wasi(&engine, |linker, store| {
+        wasmtime_wasi::trace::record(linker, store, File::create(&path)?)
+    })?;
+    let err = run_component(&engine, 5, |linker, component| {
+        wasmtime_wasi::trace::replay_component(linker, component, File::open(&path)?)
+    })
+    .unwrap_err();
+    assert!(
+        err.to_string().contains("recorded from a core module"),
+        "{err:?}"
+    );
+    Ok(())
+}
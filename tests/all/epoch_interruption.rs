@@ -458,3 +458,114 @@ async fn drop_future_on_epoch_yield(config: &mut Config) {
 
     assert_eq!(true, alive_flag.load(Ordering::Acquire));
 }
+
+#[wasmtime_test]
+fn resumable_call_suspends_on_epoch(config: &mut Config) -> Result<()> {
+    let wasm = "
+    (module
+      (import \"\" \"bump_epoch\" (func $bump))
+      (func (export \"run\") (result i32)
+        (local i32)
+        (loop $l
+          (call $bump)
+          (local.set 0 (i32.add (local.get 0) (i32.const 1)))
+          (br_if $l (i32.lt_u (local.get 0) (i32.const 5))))
+        (local.get 0)))
+    ";
+
+    config.epoch_interruption(true);
+    let engine = Engine::new(config)?;
+    let linker = make_env(&engine);
+    let module = Module::new(&engine, wasm)?;
+    let mut store = Store::new(&engine, ());
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_async_yield_and_update(1);
+
+    let instance = linker.instantiate(&mut store, &module)?;
+    let f = instance.get_func(&mut store, "run").unwrap();
+    let mut results = [Val::I32(0)];
+    let mut suspensions = 0;
+    {
+        let mut call = store.call_resumable(&f, &[], &mut results)?;
+        while let ResumableCall::Suspended(suspended) = call {
+            suspensions += 1;
+            call = suspended.resume()?;
+        }
+    }
+    assert!(suspensions > 0);
+    assert_eq!(results[0].unwrap_i32(), 5);
+
+    // The store is usable for regular calls again afterwards.
+    store.epoch_deadline_callback(|_| Ok(UpdateDeadline::Continue(1)));
+    let mut results = [Val::I32(0)];
+    f.call(&mut store, &[], &mut results)?;
+    assert_eq!(results[0].unwrap_i32(), 5);
+    Ok(())
+}
+
+#[wasmtime_test]
+fn drop_suspended_resumable_call(config: &mut Config) -> Result<()> {
+    let wasm = "
+    (module
+      (import \"\" \"bump_epoch\" (func $bump))
+      (func (export \"run\")
+        (loop $l
+          (call $bump)
+          (br $l)))
+      (func (export \"answer\") (result i32)
+        (i32.const 42)))
+    ";
+
+    config.epoch_interruption(true);
+    let engine = Engine::new(config)?;
+    let linker = make_env(&engine);
+    let module = Module::new(&engine, wasm)?;
+    let mut store = Store::new(&engine, ());
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_async_yield_and_update(1);
+
+    let instance = linker.instantiate(&mut store, &module)?;
+    let run = instance.get_func(&mut store, "run").unwrap();
+    let answer = instance.get_typed_func::<(), i32>(&mut store, "answer")?;
+
+    match store.call_resumable(&run, &[], &mut [])? {
+        ResumableCall::Suspended(suspended) => drop(suspended),
+        ResumableCall::Finished => panic!("infinite loop should not finish"),
+    }
+
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_trap();
+    assert_eq!(answer.call(&mut store, ())?, 42);
+    Ok(())
+}
+
+#[wasmtime_test]
+fn yield_outside_resumable_call_fails(config: &mut Config) -> Result<()> {
+    let wasm = "
+    (module
+      (import \"\" \"bump_epoch\" (func $bump))
+      (func (export \"run\")
+        (local i32)
+        (loop $l
+          (call $bump)
+          (local.set 0 (i32.add (local.get 0) (i32.const 1)))
+          (br_if $l (i32.lt_u (local.get 0) (i32.const 5))))))
+    ";
+
+    config.epoch_interruption(true);
+    let engine = Engine::new(config)?;
+    let linker = make_env(&engine);
+    let module = Module::new(&engine, wasm)?;
+    let mut store = Store::new(&engine, ());
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_async_yield_and_update(1);
+
+    let instance = linker.instantiate(&mut store, &module)?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    let err = run.call(&mut store, ()).unwrap_err();
+    assert!(
+        format!("{err:?}").contains("outside of `Store::call_resumable`"),
+        "{err:?}"
+    );
+    Ok(())
+}
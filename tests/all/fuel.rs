@@ -278,3 +278,72 @@ fn ensure_stack_alignment(config: &mut Config) -> Result<()> {
     );
     Ok(())
 }
+
+#[wasmtime_test(strategies(not(Winch)))]
+#[cfg_attr(miri, ignore)]
+fn function_fuel_profile(config: &mut Config) -> Result<()> {
+    config.consume_fuel(true);
+    config.fuel_profiling(true);
+    let engine = Engine::new(config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+(module
+  (func $idle)
+  (func $spin (param $n i32)
+    (loop $l
+      (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+      (br_if $l (local.get $n))))
+  (func $run (export "run")
+    (call $spin (i32.const 100))
+    (call $spin (i32.const 100)))
+)
+        "#,
+    )?;
+    let mut store = Store::new(&engine, ());
+    store.set_fuel(10_000)?;
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    run.call(&mut store, ())?;
+
+    let profile = instance.function_fuel_profile(&store).unwrap();
+    let names = profile
+        .iter()
+        .map(|f| f.name.as_deref())
+        .collect::<Vec<_>>();
+    assert_eq!(names, [Some("idle"), Some("spin"), Some("run")]);
+    let [idle, spin, run] = [0, 1, 2].map(|i| profile[i].fuel);
+    assert_eq!(idle, 0);
+    assert!(spin > 200 * 4, "{profile:?}");
+    assert!(run > 0 && run < 10, "{profile:?}");
+    assert_eq!(idle + spin + run, 10_000 - store.get_fuel()?);
+
+    // Each instance has its own counters.
+    let other = Instance::new(&mut store, &module, &[])?;
+    assert!(other
+        .function_fuel_profile(&store)
+        .unwrap()
+        .iter()
+        .all(|f| f.fuel == 0));
+    Ok(())
+}
+
+#[test]
+fn fuel_profiling_requires_fuel() {
+    let mut config = Config::new();
+    config.fuel_profiling(true);
+    assert!(Engine::new(&config).is_err());
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn function_fuel_profile_disabled() -> Result<()> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(&engine, "(module (func))")?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    assert!(instance.function_fuel_profile(&store).is_none());
+    Ok(())
+}
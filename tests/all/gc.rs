@@ -882,3 +882,475 @@ fn table_copy_doesnt_leak() -> Result<()> {
     assert!(flag.load(SeqCst));
     Ok(())
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn copying_collector_moves_live_objects() -> Result<()> {
+    let _ = env_logger::try_init();
+
+    let mut config = Config::new();
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    config.collector(Collector::Copying);
+
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $node (struct (field i32) (field (ref null $node))))
+                (type $garbage (array i32))
+
+                (func (export "sum") (param $n i32) (result i32)
+                    (local $list (ref null $node))
+                    (local $i i32)
+                    (local $sum i32)
+
+                    ;; Build a linked list of `n` nodes, allocating plenty of
+                    ;; garbage along the way so that we trigger collections.
+                    (loop $build
+                        (local.set $list (struct.new $node (local.get $i) (local.get $list)))
+                        (drop (array.new_default $garbage (i32.const 64)))
+                        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                        (br_if $build (i32.lt_u (local.get $i) (local.get $n)))
+                    )
+
+                    ;; Then sum up the list's values.
+                    (block $done
+                        (loop $walk
+                            (br_if $done (ref.is_null (local.get $list)))
+                            (local.set $sum
+                                (i32.add (local.get $sum)
+                                         (struct.get $node 0 (local.get $list))))
+                            (local.set $list (struct.get $node 1 (local.get $list)))
+                            (br $walk)
+                        )
+                    )
+                    (local.get $sum)
+                )
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let sum = instance.get_typed_func::<i32, i32>(&mut store, "sum")?;
+
+    let n = 5000;
+    assert_eq!(sum.call(&mut store, n)?, n * (n - 1) / 2);
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn copying_collector_collects_cycles() -> Result<()> {
+    let _ = env_logger::try_init();
+
+    let mut config = Config::new();
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    config.collector(Collector::Copying);
+
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $pair (struct (field (mut (ref null $pair))) (field externref)))
+
+                (func (export "make_cycle") (param externref)
+                    (local $a (ref null $pair))
+                    (local $b (ref null $pair))
+                    (local.set $a (struct.new $pair (ref.null $pair) (local.get 0)))
+                    (local.set $b (struct.new $pair (local.get $a) (local.get 0)))
+                    (struct.set $pair 0 (local.get $a) (local.get $b))
+                )
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let make_cycle = instance.get_func(&mut store, "make_cycle").unwrap();
+
+    let flag = Arc::new(AtomicBool::new(false));
+    {
+        let mut scope = RootScope::new(&mut store);
+        let x = ExternRef::new(&mut scope, SetFlagOnDrop(flag.clone()))?;
+        make_cycle.call(&mut scope, &[x.into()], &mut [])?;
+    }
+
+    // The only references to the `externref` are from the now-unreachable
+    // cycle, so collecting garbage should drop it.
+    assert!(!flag.load(SeqCst));
+    store.gc();
+    assert!(flag.load(SeqCst));
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn drc_collector_releases_references_from_freed_objects() -> Result<()> {
+    let _ = env_logger::try_init();
+
+    let mut config = Config::new();
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    config.collector(Collector::DeferredReferenceCounting);
+
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $box (struct (field externref)))
+                (global $g (mut (ref null $box)) (ref.null $box))
+
+                (func (export "set") (param externref)
+                    (global.set $g (struct.new $box (local.get 0)))
+                )
+
+                (func (export "clear")
+                    (global.set $g (ref.null $box))
+                )
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let set = instance.get_typed_func::<Option<Rooted<ExternRef>>, ()>(&mut store, "set")?;
+    let clear = instance.get_typed_func::<(), ()>(&mut store, "clear")?;
+
+    let flag = Arc::new(AtomicBool::new(false));
+    {
+        let mut scope = RootScope::new(&mut store);
+        let x = ExternRef::new(&mut scope, SetFlagOnDrop(flag.clone()))?;
+        set.call(&mut scope, Some(x))?;
+    }
+    clear.call(&mut store, ())?;
+
+    // Collecting the now-unreachable struct should drop its reference to the
+    // `externref` as well.
+    assert!(!flag.load(SeqCst));
+    store.gc();
+    assert!(flag.load(SeqCst));
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn drc_collector_collects_cycles() -> Result<()> {
+    let _ = env_logger::try_init();
+
+    let mut config = Config::new();
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    config.collector(Collector::DeferredReferenceCounting);
+
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $pair (struct (field (mut (ref null $pair))) (field externref)))
+
+                (func (export "make_cycles") (param externref i32)
+                    (local $a (ref null $pair))
+                    (local $b (ref null $pair))
+                    (loop $loop
+                        (local.set $a (struct.new $pair (ref.null $pair) (local.get 0)))
+                        (local.set $b (struct.new $pair (local.get $a) (local.get 0)))
+                        (struct.set $pair 0 (local.get $a) (local.get $b))
+                        (br_if $loop (local.tee 1 (i32.sub (local.get 1) (i32.const 1))))
+                    )
+                )
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let make_cycles = instance
+        .get_typed_func::<(Option<Rooted<ExternRef>>, u32), ()>(&mut store, "make_cycles")?;
+
+    // Create many more garbage cycles than fit in the GC heap at once. This
+    // would fail with an out-of-memory error if cycles were never reclaimed.
+    let flag = Arc::new(AtomicBool::new(false));
+    {
+        let mut scope = RootScope::new(&mut store);
+        let x = ExternRef::new(&mut scope, SetFlagOnDrop(flag.clone()))?;
+        make_cycles.call(&mut scope, (Some(x), 30_000))?;
+    }
+
+    // Keep allocating cycles that don't reference the `externref` until the
+    // cycle collector runs again and reclaims the last of the cycles that do.
+    for _ in 0..10 {
+        if flag.load(SeqCst) {
+            break;
+        }
+        make_cycles.call(&mut store, (None, 30_000))?;
+    }
+    assert!(flag.load(SeqCst));
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn drc_collector_collects_cycles_unlinked_by_wasm_writes() -> Result<()> {
+    let _ = env_logger::try_init();
+
+    let mut config = Config::new();
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    config.collector(Collector::DeferredReferenceCounting);
+
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $pair (struct (field (mut (ref null $pair))) (field externref)))
+                (global $g (mut (ref null $pair)) (ref.null $pair))
+
+                (func (export "make_cycles") (param externref i32)
+                    (loop $loop
+                        (global.set $g (struct.new $pair (ref.null $pair) (local.get 0)))
+                        (struct.set $pair 0 (global.get $g) (global.get $g))
+                        (global.set $g (ref.null $pair))
+                        (br_if $loop (local.tee 1 (i32.sub (local.get 1) (i32.const 1))))
+                    )
+                )
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let make_cycles = instance
+        .get_typed_func::<(Option<Rooted<ExternRef>>, u32), ()>(&mut store, "make_cycles")?;
+
+    // Each self-cycle only becomes garbage when Wasm's write barrier drops the
+    // global's reference to it, which the cycle collector must still notice.
+    let flag = Arc::new(AtomicBool::new(false));
+    {
+        let mut scope = RootScope::new(&mut store);
+        let x = ExternRef::new(&mut scope, SetFlagOnDrop(flag.clone()))?;
+        make_cycles.call(&mut scope, (Some(x), 30_000))?;
+    }
+
+    for _ in 0..10 {
+        if flag.load(SeqCst) {
+            break;
+        }
+        make_cycles.call(&mut store, (None, 30_000))?;
+    }
+    assert!(flag.load(SeqCst));
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn drc_collector_collects_cycles_created_and_unlinked_by_struct_set() -> Result<()> {
+    let _ = env_logger::try_init();
+
+    let mut config = Config::new();
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    config.collector(Collector::DeferredReferenceCounting);
+
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $pair (struct (field (mut (ref null $pair))) (field externref)))
+                (type $holder (struct (field (mut (ref null $pair)))))
+                (global $h (ref $holder) (struct.new_default $holder))
+
+                (func (export "make_cycles") (param externref i32)
+                    (local $a (ref null $pair))
+                    (loop $loop
+                        (local.set $a (struct.new $pair (ref.null $pair) (local.get 0)))
+                        (struct.set $pair 0
+                            (local.get $a)
+                            (struct.new $pair (local.get $a) (local.get 0)))
+                        (struct.set $holder 0 (global.get $h) (local.get $a))
+                        (br_if $loop (local.tee 1 (i32.sub (local.get 1) (i32.const 1))))
+                    )
+                    (struct.set $holder 0 (global.get $h) (ref.null $pair))
+                )
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let make_cycles = instance
+        .get_typed_func::<(Option<Rooted<ExternRef>>, u32), ()>(&mut store, "make_cycles")?;
+
+    // Each cycle is created with `struct.set`, and becomes garbage when the
+    // next `struct.set` overwrites the holder's reference to it.
+    let flag = Arc::new(AtomicBool::new(false));
+    {
+        let mut scope = RootScope::new(&mut store);
+        let x = ExternRef::new(&mut scope, SetFlagOnDrop(flag.clone()))?;
+        make_cycles.call(&mut scope, (Some(x), 30_000))?;
+    }
+
+    for _ in 0..10 {
+        if flag.load(SeqCst) {
+            break;
+        }
+        make_cycles.call(&mut store, (None, 30_000))?;
+    }
+    assert!(flag.load(SeqCst));
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn drc_collector_collects_cycles_created_and_unlinked_by_array_set() -> Result<()> {
+    let _ = env_logger::try_init();
+
+    let mut config = Config::new();
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    config.collector(Collector::DeferredReferenceCounting);
+
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $box (struct (field externref)))
+                (type $node (array (mut anyref)))
+                (global $h (ref $node) (array.new_default $node (i32.const 1)))
+
+                (func (export "make_cycles") (param externref i32)
+                    (local $a (ref null $node))
+                    (loop $loop
+                        (local.set $a (array.new_default $node (i32.const 2)))
+                        (array.set $node (local.get $a) (i32.const 0) (local.get $a))
+                        (array.set $node
+                            (local.get $a)
+                            (i32.const 1)
+                            (struct.new $box (local.get 0)))
+                        (array.set $node (global.get $h) (i32.const 0) (local.get $a))
+                        (br_if $loop (local.tee 1 (i32.sub (local.get 1) (i32.const 1))))
+                    )
+                    (array.set $node (global.get $h) (i32.const 0) (ref.null any))
+                )
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let make_cycles = instance
+        .get_typed_func::<(Option<Rooted<ExternRef>>, u32), ()>(&mut store, "make_cycles")?;
+
+    // Each array refers to itself through `array.set`, and becomes garbage
+    // when the next `array.set` overwrites the holder's reference to it.
+    let flag = Arc::new(AtomicBool::new(false));
+    {
+        let mut scope = RootScope::new(&mut store);
+        let x = ExternRef::new(&mut scope, SetFlagOnDrop(flag.clone()))?;
+        make_cycles.call(&mut scope, (Some(x), 30_000))?;
+    }
+
+    for _ in 0..10 {
+        if flag.load(SeqCst) {
+            break;
+        }
+        make_cycles.call(&mut store, (None, 30_000))?;
+    }
+    assert!(flag.load(SeqCst));
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn drc_collector_rejects_objects_too_large_for_its_size_bits() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    config.collector(Collector::DeferredReferenceCounting);
+
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $bytes (array i8))
+                (func (export "alloc") (param i32) (result (ref $bytes))
+                    (array.new_default $bytes (local.get 0))
+                )
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let alloc = instance.get_typed_func::<u32, Rooted<ArrayRef>>(&mut store, "alloc")?;
+
+    // The DRC collector keeps one of the header's reserved bits for itself, so
+    // 64 MiB objects are too large even though other collectors accept them.
+    let err = alloc.call(&mut store, 1 << 26).unwrap_err();
+    assert_eq!(err.downcast::<Trap>()?, Trap::AllocationTooLarge);
+
+    alloc.call(&mut store, 1 << 10)?;
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn drc_read_barrier_skips_i31refs() -> Result<()> {
+    let _ = env_logger::try_init();
+
+    let mut config = Config::new();
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    config.collector(Collector::DeferredReferenceCounting);
+
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $bytes (array i8))
+                (global $g (mut (ref null $bytes)) (ref.null $bytes))
+                (table $t 1 anyref)
+
+                (func (export "init") (param i32)
+                    (global.set $g (array.new_fixed $bytes 3 (i32.const 7) (i32.const 8) (i32.const 9)))
+                    (table.set $t (i32.const 0) (ref.i31 (local.get 0)))
+                )
+
+                (func (export "get") (result i32)
+                    (i31.get_s (ref.cast (ref i31) (table.get $t (i32.const 0))))
+                )
+
+                (func (export "len") (result i32)
+                    (array.len (ref.as_non_null (global.get $g)))
+                )
+            )
+        "#,
+    )?;
+
+    // Reading an `i31ref` out of an `anyref` table must not treat it as a
+    // pointer to an object's header. Try a range of values so that, if it did,
+    // one of them would land on the array's header and corrupt it.
+    for i in 0..64 {
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let init = instance.get_typed_func::<i32, ()>(&mut store, "init")?;
+        let get = instance.get_typed_func::<(), i32>(&mut store, "get")?;
+        let len = instance.get_typed_func::<(), i32>(&mut store, "len")?;
+
+        init.call(&mut store, i)?;
+        assert_eq!(get.call(&mut store, ())?, i);
+        assert_eq!(len.call(&mut store, ())?, 3);
+    }
+    Ok(())
+}
@@ -0,0 +1,281 @@
+use std::sync::{Arc, Mutex};
+use wasmtime::*;
+use wasmtime_environ::TripleExt;
+
+const WAT: &str = r#"
+    (module
+        (func $add (param i32 i32) (result i32)
+            local.get 0
+            local.get 1
+            i32.add)
+        (func (export "run") (param i32) (result i32)
+            (local i32)
+            block
+                loop
+                    local.get 0
+                    i32.eqz
+                    br_if 1
+                    local.get 1
+                    local.get 0
+                    call $add
+                    local.set 1
+                    local.get 0
+                    i32.const 1
+                    i32.sub
+                    local.set 0
+                    br 0
+                end
+            end
+            local.get 1)
+    )
+"#;
+
+struct Stop {
+    func_index: u32,
+    offset: usize,
+    locals: Vec<Option<Val>>,
+}
+
+fn setup(config: &mut Config) -> Result<(Store<Vec<Stop>>, Instance)> {
+    config.guest_debug(true);
+    let engine = Engine::new(config)?;
+    let module = Module::new(&engine, WAT)?;
+    let mut store = Store::new(&engine, Vec::new());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    Ok((store, instance))
+}
+
+fn record(store: &mut StoreContextMut<'_, Vec<Stop>>, frame: &DebugFrame) {
+    store.data_mut().push(Stop {
+        func_index: frame.func_index(),
+        offset: frame.module_offset(),
+        locals: frame.locals().to_vec(),
+    });
+}
+
+fn i32s(locals: &[Option<Val>]) -> Vec<i32> {
+    locals
+        .iter()
+        .map(|l| l.as_ref().unwrap().unwrap_i32())
+        .collect()
+}
+
+fn run(store: &mut Store<Vec<Stop>>, instance: &Instance, n: i32) -> Result<i32> {
+    let run = instance.get_typed_func::<i32, i32>(&mut *store, "run")?;
+    run.call(&mut *store, n)
+}
+
+/// Returns the offset of the first instruction of `$add`, found by tracing.
+fn add_start(config: &mut Config) -> Result<usize> {
+    let (mut store, instance) = setup(config)?;
+    store.debug_handler(|mut store, frame| {
+        record(&mut store, frame);
+        Ok(DebugStep::Into)
+    });
+    store.debug_interrupt_handle().interrupt();
+    run(&mut store, &instance, 1)?;
+    let stop = store.data().iter().find(|s| s.func_index == 0).unwrap();
+    Ok(stop.offset)
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn no_handler_runs_normally() -> Result<()> {
+    let (mut store, instance) = setup(&mut Config::new())?;
+    store.debug_interrupt_handle().interrupt();
+    assert_eq!(run(&mut store, &instance, 3)?, 6);
+    assert!(store.data().is_empty());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn step_into_traces_every_instruction() -> Result<()> {
+    let (mut store, instance) = setup(&mut Config::new())?;
+    store.debug_handler(|mut store, frame| {
+        record(&mut store, frame);
+        Ok(DebugStep::Into)
+    });
+    store.debug_interrupt_handle().interrupt();
+    assert_eq!(run(&mut store, &instance, 2)?, 3);
+
+    let stops = store.data();
+    assert!(!stops.is_empty());
+    assert_eq!(stops[0].func_index, 1);
+    assert_eq!(i32s(&stops[0].locals), [2, 0]);
+
+    // Each call to `$add` is stepped into, and stops at each of its four
+    // instructions (including the implicit `end`).
+    let in_add = stops.iter().filter(|s| s.func_index == 0).count();
+    assert_eq!(in_add, 2 * 4);
+    let first_add = stops.iter().find(|s| s.func_index == 0).unwrap();
+    assert_eq!(i32s(&first_add.locals), [0, 2]);
+
+    let last = stops.last().unwrap();
+    assert_eq!(last.func_index, 1);
+    assert_eq!(i32s(&last.locals), [0, 3]);
+    Ok(())
+}
+
+fn pulley_config() -> Config {
+    let mut config = Config::new();
+    config
+        .target(&target_lexicon::Triple::pulley_host().to_string())
+        .unwrap();
+    config
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn breakpoints() -> Result<()> {
+    test_breakpoints(Config::new())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn pulley_breakpoints() -> Result<()> {
+    test_breakpoints(pulley_config())
+}
+
+fn test_breakpoints(mut config: Config) -> Result<()> {
+    let add_start = add_start(&mut config)?;
+    let (mut store, instance) = setup(&mut config)?;
+    store.debug_handler(|mut store, frame| {
+        record(&mut store, frame);
+        Ok(DebugStep::Continue)
+    });
+    let module = instance.module(&store).clone();
+    store.add_breakpoint(&module, add_start);
+    assert_eq!(run(&mut store, &instance, 4)?, 10);
+    let hits = store
+        .data()
+        .iter()
+        .map(|s| i32s(&s.locals))
+        .collect::<Vec<_>>();
+    assert_eq!(hits, [[0, 4], [4, 3], [7, 2], [9, 1]]);
+
+    store.data_mut().clear();
+    store.remove_breakpoint(&module, add_start);
+    assert_eq!(run(&mut store, &instance, 4)?, 10);
+    assert!(store.data().is_empty());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn step_over_and_out() -> Result<()> {
+    test_step_over_and_out(Config::new())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn pulley_step_over_and_out() -> Result<()> {
+    test_step_over_and_out(pulley_config())
+}
+
+fn test_step_over_and_out(mut config: Config) -> Result<()> {
+    // Stepping over never stops inside `$add`.
+    let (mut store, instance) = setup(&mut config)?;
+    store.debug_handler(|mut store, frame| {
+        record(&mut store, frame);
+        Ok(DebugStep::Over)
+    });
+    store.debug_interrupt_handle().interrupt();
+    assert_eq!(run(&mut store, &instance, 3)?, 6);
+    assert!(store.data().len() > 1);
+    assert!(store.data().iter().all(|s| s.func_index == 1));
+
+    // Stepping out of `$add` stops in its caller right after the call.
+    let (mut store, instance) = setup(&mut config)?;
+    store.debug_handler(|mut store, frame| {
+        record(&mut store, frame);
+        Ok(if frame.func_index() == 0 {
+            DebugStep::Out
+        } else {
+            DebugStep::Continue
+        })
+    });
+    let module = instance.module(&store).clone();
+    let add_start = add_start(&mut config)?;
+    store.add_breakpoint(&module, add_start);
+    assert_eq!(run(&mut store, &instance, 1)?, 1);
+    let stops = store.data();
+    assert_eq!(stops.len(), 2);
+    assert_eq!(stops[0].func_index, 0);
+    assert_eq!(stops[1].func_index, 1);
+    assert!(stops[1].offset > stops[0].offset);
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn handler_error_stops_execution() -> Result<()> {
+    let (mut store, instance) = setup(&mut Config::new())?;
+    store.debug_handler(|_, _| Err(anyhow::anyhow!("stop here")));
+    store.debug_interrupt_handle().interrupt();
+    let err = run(&mut store, &instance, 3).unwrap_err();
+    assert!(format!("{err:?}").contains("stop here"), "{err:?}");
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn interrupt_from_another_thread() -> Result<()> {
+    let mut config = Config::new();
+    config.guest_debug(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(&engine, r#"(module (func (export "run") (loop br 0)))"#)?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    store.debug_handler(|_, _| Err(anyhow::anyhow!("interrupted")));
+
+    // The loop runs without consulting the host until the interruption is
+    // requested.
+    let handle = store.debug_interrupt_handle();
+    let thread = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        handle.interrupt();
+    });
+    let run = instance.get_typed_func::<(), ()>(&mut store, "run")?;
+    let err = run.call(&mut store, ()).unwrap_err();
+    assert!(format!("{err:?}").contains("interrupted"), "{err:?}");
+    thread.join().unwrap();
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn inspect_globals_and_memory() -> Result<()> {
+    let mut config = Config::new();
+    config.guest_debug(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (global (mut i32) (i32.const 7))
+                (memory 1)
+                (data (i32.const 0) "hi")
+                (func (export "run") (param f64)
+                    nop))
+        "#,
+    )?;
+    let seen = Arc::new(Mutex::new(None));
+    let mut store = Store::new(&engine, ());
+    let seen2 = seen.clone();
+    store.debug_handler(move |mut store, frame| {
+        let globals = frame.globals(&mut store);
+        let memories = frame.memories(&mut store);
+        let global = globals[0].get(&mut store).unwrap_i32();
+        let data = memories[0].data(&store)[..2].to_vec();
+        let local = frame.locals()[0].as_ref().unwrap().unwrap_f64();
+        *seen2.lock().unwrap() = Some((global, data, local));
+        Ok(DebugStep::Continue)
+    });
+    store.debug_interrupt_handle().interrupt();
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let run = instance.get_typed_func::<f64, ()>(&mut store, "run")?;
+    run.call(&mut store, 1.5)?;
+    assert_eq!(*seen.lock().unwrap(), Some((7, b"hi".to_vec(), 1.5)));
+    Ok(())
+}
@@ -742,3 +742,48 @@ fn wasi_imports() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn call_func_from_caller() -> Result<()> {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+
+    // A host function which reads from its caller's memory.
+    let read = Func::wrap(&mut store, |mut caller: Caller<'_, ()>, ptr: u32| {
+        let memory = match caller.get_export("memory") {
+            Some(Extern::Memory(memory)) => memory,
+            _ => bail!("no caller memory"),
+        };
+        Ok(i32::from(memory.data(&caller)[usize::try_from(ptr)?]))
+    });
+
+    // Wrap it, calling it on behalf of the same caller.
+    let ty = read.ty(&store);
+    let wrapper = Func::new(&mut store, ty, move |mut caller, params, results| {
+        caller.call_func(&read, params, results)?;
+        results[0] = Val::I32(results[0].unwrap_i32() + 1);
+        Ok(())
+    });
+
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (import "" "read" (func $read (param i32) (result i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 10) "\2a")
+                (func (export "run") (result i32)
+                    (call $read (i32.const 10))))
+        "#,
+    )?;
+    let instance = Instance::new(&mut store, &module, &[wrapper.into()])?;
+    let run = instance.get_typed_func::<(), i32>(&mut store, "run")?;
+    assert_eq!(run.call(&mut store, ())?, 43);
+
+    // Called directly from the host there's no caller instance.
+    assert!(read
+        .call(&mut store, &[Val::I32(10)], &mut [Val::I32(0)])
+        .is_err());
+    Ok(())
+}
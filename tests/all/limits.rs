@@ -1175,3 +1175,205 @@ fn growth_trap() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn memory_budget_shared_between_stores() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::new(
+        &engine,
+        r#"(module
+            (memory (export "m") 0)
+            (table (export "t") 0 funcref)
+            (func (export "f"))
+           )"#,
+    )?;
+
+    let new_store = |budget: &MemoryBudget| {
+        let mut store = Store::new(
+            &engine,
+            StoreLimitsBuilder::new()
+                .memory_budget(budget.clone())
+                .build(),
+        );
+        store.limiter(|s| s as &mut dyn ResourceLimiter);
+        store
+    };
+
+    // Instantiation charges the module's compiled code to each store too, so
+    // measure how large that is first.
+    let probe = MemoryBudget::new(usize::MAX);
+    let mut store = new_store(&probe);
+    Instance::new(&mut store, &module, &[])?;
+    let code = probe.used_by(MemoryCategory::CompiledCode);
+    assert!(code > 0);
+    assert_eq!(probe.used(), code);
+    drop(store);
+    assert_eq!(probe.used(), 0);
+
+    let budget = MemoryBudget::new(2 * code + 3 * WASM_PAGE_SIZE);
+    let mut store1 = new_store(&budget);
+    let instance1 = Instance::new(&mut store1, &module, &[])?;
+    let memory1 = instance1.get_memory(&mut store1, "m").unwrap();
+    memory1.grow(&mut store1, 2)?;
+    assert_eq!(
+        budget.used_by(MemoryCategory::LinearMemory),
+        2 * WASM_PAGE_SIZE
+    );
+
+    let mut store2 = new_store(&budget);
+    let instance2 = Instance::new(&mut store2, &module, &[])?;
+    let memory2 = instance2.get_memory(&mut store2, "m").unwrap();
+    assert!(memory2.grow(&mut store2, 2).is_err());
+    memory2.grow(&mut store2, 1)?;
+
+    // Tables are charged against the same budget, which is now used up.
+    let table2 = instance2.get_table(&mut store2, "t").unwrap();
+    assert!(table2.grow(&mut store2, 1, Ref::Func(None)).is_err());
+    assert_eq!(budget.used(), budget.limit());
+
+    // Dropping a store releases everything it charged.
+    drop(store1);
+    assert_eq!(budget.used(), code + WASM_PAGE_SIZE);
+    table2.grow(&mut store2, 10, Ref::Func(None))?;
+    assert_eq!(
+        budget.used_by(MemoryCategory::Table),
+        10 * std::mem::size_of::<usize>()
+    );
+    drop(store2);
+    assert_eq!(budget.used(), 0);
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn gc_heap_limiter() -> Result<()> {
+    struct Limiter {
+        allow: bool,
+        requests: Vec<(usize, usize)>,
+    }
+
+    impl ResourceLimiter for Limiter {
+        fn memory_growing(&mut self, _: usize, _: usize, _: Option<usize>) -> Result<bool> {
+            Ok(true)
+        }
+        fn table_growing(&mut self, _: usize, _: usize, _: Option<usize>) -> Result<bool> {
+            Ok(true)
+        }
+        fn gc_heap_growing(
+            &mut self,
+            current: usize,
+            desired: usize,
+            maximum: Option<usize>,
+        ) -> Result<bool> {
+            assert!(current < desired);
+            assert!(desired <= maximum.unwrap());
+            self.requests.push((current, desired));
+            Ok(self.allow)
+        }
+    }
+
+    let engine = Engine::default();
+
+    // The GC heap starts out empty, so the first allocation has to grow it.
+    let mut store = Store::new(
+        &engine,
+        Limiter {
+            allow: false,
+            requests: Vec::new(),
+        },
+    );
+    store.limiter(|s| s as &mut dyn ResourceLimiter);
+    let err = ExternRef::new(&mut store, 42_u32).unwrap_err();
+    assert!(err.is::<GcHeapOutOfMemory<u32>>());
+    assert_eq!(store.data().requests.len(), 1);
+    assert_eq!(store.data().requests[0].0, 0);
+
+    // Keeping objects alive grows the heap step by step, each step starting
+    // from where the previous one left off.
+    let mut store = Store::new(
+        &engine,
+        Limiter {
+            allow: true,
+            requests: Vec::new(),
+        },
+    );
+    store.limiter(|s| s as &mut dyn ResourceLimiter);
+    let mut scope = RootScope::new(&mut store);
+    for i in 0..10_000_u32 {
+        ExternRef::new(&mut scope, i)?;
+    }
+    drop(scope);
+    let requests = &store.data().requests;
+    assert!(requests.len() > 1);
+    assert_eq!(requests[0].0, 0);
+    for pair in requests.windows(2) {
+        assert_eq!(pair[0].1, pair[1].0);
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn code_memory_limiter() -> Result<()> {
+    struct Limiter {
+        allow: bool,
+        requests: Vec<(usize, usize)>,
+    }
+
+    impl ResourceLimiter for Limiter {
+        fn memory_growing(&mut self, _: usize, _: usize, _: Option<usize>) -> Result<bool> {
+            Ok(true)
+        }
+        fn table_growing(&mut self, _: usize, _: usize, _: Option<usize>) -> Result<bool> {
+            Ok(true)
+        }
+        fn code_memory_growing(
+            &mut self,
+            current: usize,
+            desired: usize,
+            maximum: Option<usize>,
+        ) -> Result<bool> {
+            assert!(maximum.is_none());
+            self.requests.push((current, desired));
+            Ok(self.allow)
+        }
+    }
+
+    let engine = Engine::default();
+    let module1 = Module::new(&engine, r#"(module (func (export "f")))"#)?;
+    let module2 = Module::new(&engine, r#"(module (func (export "g")))"#)?;
+
+    let mut store = Store::new(
+        &engine,
+        Limiter {
+            allow: true,
+            requests: Vec::new(),
+        },
+    );
+    store.limiter(|s| s as &mut dyn ResourceLimiter);
+
+    // Each module's code is only reported the first time it's instantiated.
+    Instance::new(&mut store, &module1, &[])?;
+    Instance::new(&mut store, &module1, &[])?;
+    Instance::new(&mut store, &module2, &[])?;
+    let requests = &store.data().requests;
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].0, 0);
+    assert!(requests[0].1 > 0);
+    assert_eq!(requests[0].1, requests[1].0);
+    assert!(requests[1].1 > requests[1].0);
+
+    // Rejecting the growth fails instantiation.
+    let mut store = Store::new(
+        &engine,
+        Limiter {
+            allow: false,
+            requests: Vec::new(),
+        },
+    );
+    store.limiter(|s| s as &mut dyn ResourceLimiter);
+    assert!(Instance::new(&mut store, &module1, &[]).is_err());
+    assert_eq!(store.data().requests.len(), 1);
+    Ok(())
+}
@@ -11,6 +11,7 @@ mod coredump;
 mod custom_code_memory;
 mod debug;
 mod defaults;
+mod deterministic;
 mod epoch_interruption;
 mod externals;
 mod fuel;
@@ -18,6 +19,7 @@ mod func;
 mod funcref;
 mod gc;
 mod globals;
+mod guest_debug;
 mod host_funcs;
 mod i31ref;
 mod iloop;
@@ -29,6 +31,7 @@ mod limits;
 mod linker;
 mod memory;
 mod memory_creator;
+mod memory_trace;
 mod module;
 mod module_serialize;
 mod name;
@@ -36,7 +39,9 @@ mod noextern;
 mod piped_tests;
 mod pooling_allocator;
 mod pulley;
+mod race_detector;
 mod relocs;
+mod snapshot;
 mod stack_creator;
 mod stack_overflow;
 mod store;
@@ -0,0 +1,157 @@
+use anyhow::bail;
+use wasmtime::*;
+use wasmtime_environ::TripleExt;
+
+const WAT: &str = r#"
+    (module
+        (memory (export "memory") 1 1 shared)
+        (func (export "run") (param i32)
+            ;; plain store and load
+            (i32.store offset=4 (local.get 0) (i32.const 42))
+            (drop (i64.load8_u offset=5 (local.get 0)))
+            ;; atomics
+            (i32.atomic.store (i32.const 16) (i32.const 1))
+            (drop (i32.atomic.load16_u (i32.const 16)))
+            (drop (i32.atomic.rmw.add (i32.const 16) (i32.const 1)))
+            (drop (i64.atomic.rmw.cmpxchg (i32.const 24) (i64.const 0) (i64.const 1)))
+            ;; SIMD extending load
+            (drop (v128.load8x8_s (i32.const 32)))
+            ;; bulk memory isn't traced
+            (memory.fill (i32.const 0) (i32.const 0) (i32.const 8)))
+    )
+"#;
+
+#[derive(Debug, PartialEq)]
+struct Access {
+    memory: u32,
+    address: u64,
+    size: u8,
+    kind: MemoryAccessKind,
+}
+
+fn setup(mut config: Config, wat: &str) -> Result<(Store<Vec<(Access, usize)>>, Instance)> {
+    config.memory_access_tracing(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(&engine, wat)?;
+    let mut store = Store::new(&engine, Vec::new());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    Ok((store, instance))
+}
+
+fn pulley_config() -> Config {
+    let mut config = Config::new();
+    config
+        .target(&target_lexicon::Triple::pulley_host().to_string())
+        .unwrap();
+    config
+}
+
+fn record(store: &mut Store<Vec<(Access, usize)>>) {
+    store.memory_access_hook(|mut store, access| {
+        let recorded = Access {
+            memory: access.memory_index(),
+            address: access.address(),
+            size: access.size(),
+            kind: access.kind(),
+        };
+        store.data_mut().push((recorded, access.module_offset()));
+        Ok(())
+    });
+}
+
+fn access(address: u64, size: u8, kind: MemoryAccessKind) -> Access {
+    Access {
+        memory: 0,
+        address,
+        size,
+        kind,
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn traces_accesses() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_threads(true);
+    let (mut store, instance) = setup(config, WAT)?;
+    record(&mut store);
+    let run = instance.get_typed_func::<i32, ()>(&mut store, "run")?;
+    run.call(&mut store, 100)?;
+
+    let accesses = store.data();
+    assert_eq!(
+        accesses.iter().map(|(a, _)| a).collect::<Vec<_>>(),
+        [
+            &access(104, 4, MemoryAccessKind::Store),
+            &access(105, 1, MemoryAccessKind::Load),
+            &access(16, 4, MemoryAccessKind::AtomicStore),
+            &access(16, 2, MemoryAccessKind::AtomicLoad),
+            &access(16, 4, MemoryAccessKind::AtomicRmw),
+            &access(24, 8, MemoryAccessKind::AtomicRmw),
+            &access(32, 8, MemoryAccessKind::Load),
+        ]
+    );
+
+    // Each access is made by a different instruction, in order.
+    assert!(accesses.windows(2).all(|w| w[0].1 < w[1].1));
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn pulley_traces_accesses() -> Result<()> {
+    let (mut store, instance) = setup(
+        pulley_config(),
+        r#"
+            (module
+                (memory 1)
+                (func (export "run") (param i32)
+                    (i64.store16 (local.get 0) (i64.const 1))
+                    (drop (f64.load offset=8 (local.get 0)))))
+        "#,
+    )?;
+    record(&mut store);
+    let run = instance.get_typed_func::<i32, ()>(&mut store, "run")?;
+    run.call(&mut store, 200)?;
+    assert_eq!(
+        store.data().iter().map(|(a, _)| a).collect::<Vec<_>>(),
+        [
+            &access(200, 2, MemoryAccessKind::Store),
+            &access(208, 8, MemoryAccessKind::Load),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn no_hook_runs_normally() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_threads(true);
+    let (mut store, instance) = setup(config, WAT)?;
+    let run = instance.get_typed_func::<i32, ()>(&mut store, "run")?;
+    run.call(&mut store, 0)?;
+    assert!(store.data().is_empty());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn hook_error_stops_before_access() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_threads(true);
+    let (mut store, instance) = setup(config, WAT)?;
+    store.memory_access_hook(|_, access| {
+        if access.kind().is_write() && access.address() == 104 {
+            bail!("watchpoint hit at {:#x}", access.module_offset());
+        }
+        Ok(())
+    });
+    let run = instance.get_typed_func::<i32, ()>(&mut store, "run")?;
+    let err = run.call(&mut store, 100).unwrap_err();
+    assert!(format!("{err:?}").contains("watchpoint hit"), "{err:?}");
+
+    let memory = instance.get_shared_memory(&mut store, "memory").unwrap();
+    assert_eq!(unsafe { *memory.data()[104].get() }, 0);
+    Ok(())
+}
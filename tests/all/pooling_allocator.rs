@@ -895,7 +895,8 @@ fn total_tables_limit() -> Result<()> {
 
     let mut pool = crate::small_pool_config();
     pool.total_tables(TOTAL_TABLES)
-        .total_core_instances(TOTAL_TABLES + 1);
+        .total_core_instances(TOTAL_TABLES + 1)
+        .memory_protection_keys(MpkEnabled::Disable);
     let mut config = Config::new();
     config.allocation_strategy(pool);
 
@@ -1351,3 +1352,346 @@ fn instantiate_non_page_aligned_sizes() -> Result<()> {
     Instance::new(&mut store, &module, &[])?;
     Ok(())
 }
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn instance_pre_pool() -> Result<()> {
+    let mut pool = crate::small_pool_config();
+    pool.total_memories(4);
+    let mut config = Config::new();
+    config.allocation_strategy(pool);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (import "" "started" (func $started))
+                (memory (export "m") 1)
+                (data (i32.const 0) "\2a")
+                (func $start
+                    call $started
+                    (i32.store8 (i32.const 1) (i32.load8_u (i32.const 0))))
+                (start $start)
+                (func (export "get") (result i32)
+                    (i32.load8_u (i32.const 1)))
+            )
+        "#,
+    )?;
+
+    let started = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut linker = Linker::new(&engine);
+    let counter = started.clone();
+    linker.func_wrap("", "started", move || {
+        counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+    })?;
+    let pre = linker.instantiate_pre(&module)?;
+
+    let pool = InstancePrePool::new(pre, 3, |engine| Ok(Store::new(engine, ())))?;
+    assert_eq!(pool.capacity(), 3);
+    assert_eq!(pool.ready(), 0);
+    assert!(pool.try_take().is_none());
+
+    assert_eq!(pool.fill()?, 3);
+    assert_eq!(pool.ready(), 3);
+    assert_eq!(started.load(std::sync::atomic::Ordering::SeqCst), 3);
+    assert_eq!(pool.fill()?, 0);
+
+    // Taking an instance doesn't run any more initialization.
+    let mut pooled = pool.take()?;
+    assert_eq!(pool.ready(), 2);
+    assert_eq!(started.load(std::sync::atomic::Ordering::SeqCst), 3);
+    let get = pooled
+        .instance()
+        .get_typed_func::<(), i32>(pooled.store(), "get")?;
+    assert_eq!(get.call(pooled.store(), ())?, 42);
+    drop(pooled);
+
+    assert_eq!(pool.fill()?, 1);
+    assert_eq!(started.load(std::sync::atomic::Ordering::SeqCst), 4);
+
+    // An empty pool instantiates inline.
+    while pool.try_take().is_some() {}
+    let (mut store, instance) = pool.take()?.into_parts();
+    let get = instance.get_typed_func::<(), i32>(&mut store, "get")?;
+    assert_eq!(get.call(&mut store, ())?, 42);
+    assert_eq!(started.load(std::sync::atomic::Ordering::SeqCst), 5);
+
+    Ok(())
+}
+
+#[test]
+fn instance_pre_pool_rejects_store_owned_imports() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::new(&engine, r#"(module (import "" "f" (func)))"#)?;
+    let mut store = Store::new(&engine, ());
+    let mut linker = Linker::new(&engine);
+    let f = Func::wrap(&mut store, || {});
+    linker.define(&store, "", "f", f)?;
+    let pre = linker.instantiate_pre(&module)?;
+
+    let err = InstancePrePool::new(pre, 1, |engine| Ok(Store::new(engine, ())))
+        .err()
+        .unwrap();
+    assert!(
+        err.to_string().contains("imports owned by a store"),
+        "{err:?}"
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+#[cfg_attr(miri, ignore)]
+fn userfaultfd_lazy_init() -> Result<()> {
+    let mut pool = crate::small_pool_config();
+    pool.total_memories(2);
+    pool.max_memory_size(64 << 16);
+    pool.userfaultfd(true);
+    let mut config = Config::new();
+    config.allocation_strategy(pool);
+    let engine = match Engine::new(&config) {
+        Ok(engine) => engine,
+        // Creating a `userfaultfd` may not be permitted for unprivileged
+        // processes, but must work if the system allows it.
+        Err(e)
+            if format!("{e:?}").contains("failed to create userfaultfd")
+                && std::fs::read_to_string("/proc/sys/vm/unprivileged_userfaultfd")
+                    .is_ok_and(|s| s.trim() == "0") =>
+        {
+            println!("skipping `userfaultfd_lazy_init` test; userfaultfd is not permitted: {e:?}");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (memory (export "m") 64)
+                (data (i32.const 0) "\01")
+                (data (i32.const 70000) "\02\03")
+                (data (i32.const 4194000) "\04")
+                (func (export "load") (param i32) (result i32)
+                    (i32.load8_u (local.get 0)))
+                (func (export "store") (param i32 i32)
+                    (i32.store8 (local.get 0) (local.get 1)))
+            )
+        "#,
+    )?;
+    let zeros = Module::new(&engine, r#"(module (memory (export "m") 1))"#)?;
+
+    for _ in 0..3 {
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let load = instance.get_typed_func::<u32, u32>(&mut store, "load")?;
+        let store_fn = instance.get_typed_func::<(u32, u32), ()>(&mut store, "store")?;
+        let memory = instance.get_memory(&mut store, "m").unwrap();
+
+        assert_eq!(load.call(&mut store, 0)?, 1);
+        assert_eq!(load.call(&mut store, 1)?, 0);
+        assert_eq!(load.call(&mut store, 70000)?, 2);
+        assert_eq!(load.call(&mut store, 70001)?, 3);
+        assert_eq!(load.call(&mut store, 100000)?, 0);
+        assert_eq!(memory.data(&store)[4194000], 4);
+
+        // Writes are visible but don't persist into the next instance.
+        store_fn.call(&mut store, (0, 9))?;
+        store_fn.call(&mut store, (200000, 9))?;
+        assert_eq!(load.call(&mut store, 0)?, 9);
+        assert_eq!(memory.data(&store)[200000], 9);
+
+        // A memory without data segments reusing the slot is all zeros.
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &zeros, &[])?;
+        let memory = instance.get_memory(&mut store, "m").unwrap();
+        assert!(memory.data(&store).iter().all(|b| *b == 0));
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn mpk_stripes_tables_and_gc_heaps() -> Result<()> {
+    if !PoolingAllocationConfig::are_memory_protection_keys_available() {
+        println!("skipping `mpk_stripes_tables_and_gc_heaps` test; mpk is not supported");
+        return Ok(());
+    }
+
+    let mut pool = crate::small_pool_config();
+    pool.total_memories(4)
+        .total_tables(4)
+        .total_gc_heaps(4)
+        .total_core_instances(4)
+        .max_memory_size(10 << 16)
+        .memory_protection_keys(MpkEnabled::Enable)
+        .max_memory_protection_keys(2);
+    let mut config = Config::new();
+    config.memory_reservation(1 << 20);
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    config.allocation_strategy(pool);
+    let engine = Engine::new(&config)?;
+
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $s (struct (field i32)))
+                (memory 1)
+                (table $t 1 funcref)
+                (elem (table $t) (i32.const 0) func $get)
+                (func $get (param i32) (result i32)
+                    (struct.get $s 0 (struct.new $s (local.get 0))))
+                (func (export "run") (param i32) (result i32)
+                    (call_indirect $t (param i32) (result i32)
+                        (local.get 0) (i32.const 0)))
+            )
+        "#,
+    )?;
+
+    // Stores are assigned keys round-robin, so these use both stripes of each
+    // pool, and Wasm in each store can still reach its own table and GC heap.
+    let mut stores = Vec::new();
+    for i in 0..4 {
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let run = instance.get_typed_func::<i32, i32>(&mut store, "run")?;
+        assert_eq!(run.call(&mut store, i)?, i);
+        stores.push(store);
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn scrub_policies_are_verified() -> Result<()> {
+    for policy in [ScrubPolicy::Reset, ScrubPolicy::Zero, ScrubPolicy::Decommit] {
+        let mut pool = crate::small_pool_config();
+        pool.total_memories(1)
+            .total_tables(1)
+            .total_gc_heaps(1)
+            .total_core_instances(1)
+            .linear_memory_keep_resident(1 << 12)
+            .table_keep_resident(1 << 12)
+            .decommit_batch_size(2)
+            .scrub_policy(policy)
+            .verify_scrubbing(true)
+            .memory_protection_keys(MpkEnabled::Disable);
+        let mut config = Config::new();
+        config.wasm_function_references(true);
+        config.wasm_gc(true);
+        config.allocation_strategy(pool);
+        let engine = Engine::new(&config)?;
+
+        let module = Module::new(
+            &engine,
+            r#"
+                (module
+                    (type $s (struct (field (mut i64))))
+                    (memory 1)
+                    (data (i32.const 0) "image")
+                    (table $t 10 funcref)
+                    (global $g (mut (ref null $s)) (ref.null $s))
+                    (func $f)
+                    (elem declare func $f)
+                    (func (export "dirty")
+                        (memory.fill (i32.const 4096) (i32.const 0xfe) (i32.const 60000))
+                        (table.fill $t (i32.const 0) (ref.func $f) (i32.const 10))
+                        (global.set $g (struct.new $s (i64.const -1))))
+                )
+            "#,
+        )?;
+
+        // Every instantiation reuses the same slots, each of which must have
+        // been scrubbed of the previous instance's writes.
+        for _ in 0..5 {
+            let mut store = Store::new(&engine, ());
+            let instance = Instance::new(&mut store, &module, &[])?;
+            let dirty = instance.get_typed_func::<(), ()>(&mut store, "dirty")?;
+            dirty.call(&mut store, ())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg_attr(miri, ignore)]
+async fn scrub_policy_zeroes_async_stacks() -> Result<()> {
+    let mut pool = crate::small_pool_config();
+    pool.total_stacks(1)
+        .scrub_policy(ScrubPolicy::Zero)
+        .verify_scrubbing(true);
+    let mut config = Config::new();
+    config.async_support(true);
+    config.allocation_strategy(pool);
+    let engine = Engine::new(&config)?;
+
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (func $recurse (export "recurse") (param i32) (result i64)
+                    (local i64 i64 i64 i64)
+                    (if (result i64) (local.get 0)
+                        (then (call $recurse (i32.sub (local.get 0) (i32.const 1))))
+                        (else (i64.const -1))))
+            )
+        "#,
+    )?;
+
+    for _ in 0..5 {
+        let mut store = Store::new(&engine, ());
+        let instance = Instance::new_async(&mut store, &module, &[]).await?;
+        let recurse = instance.get_typed_func::<i32, i64>(&mut store, "recurse")?;
+        assert_eq!(recurse.call_async(&mut store, 100).await?, -1);
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn instance_pre_pool_refills_when_slots_are_released() -> Result<()> {
+    let mut pool = crate::small_pool_config();
+    pool.total_memories(2);
+    let mut config = Config::new();
+    config.allocation_strategy(pool);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(&engine, r#"(module (memory 1))"#)?;
+    let pre = Linker::new(&engine).instantiate_pre(&module)?;
+
+    let pool = std::sync::Arc::new(InstancePrePool::new(pre, 2, |engine| {
+        Ok(Store::new(engine, ()))
+    })?);
+    pool.spawn_refill()?;
+    assert!(pool.spawn_refill().is_err());
+
+    let wait_for_ready = |n: usize| {
+        let start = std::time::Instant::now();
+        while pool.ready() != n {
+            assert!(
+                start.elapsed() < std::time::Duration::from_secs(30),
+                "pool never had {n} instances ready"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    };
+    wait_for_ready(2);
+
+    // Taking both instances uses up every memory slot, so the refill can't
+    // create any more until one of them is dropped.
+    let a = pool.try_take().unwrap();
+    let b = pool.try_take().unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    assert_eq!(pool.ready(), 0);
+
+    drop(a);
+    wait_for_ready(1);
+    drop(b);
+    wait_for_ready(2);
+    Ok(())
+}
@@ -0,0 +1,239 @@
+use wasmtime::*;
+
+const WAT: &str = r#"
+    (module
+        (import "env" "memory" (memory 1 1 shared))
+        (func (export "write") (param i32)
+            (i32.store (local.get 0) (i32.const 1)))
+        (func (export "read") (param i32) (result i32)
+            (i32.load (local.get 0)))
+        (func (export "publish") (param i32)
+            (i32.store (local.get 0) (i32.const 1))
+            (i32.atomic.store (i32.const 0) (i32.const 1)))
+        (func (export "consume") (param i32) (result i32)
+            (if (i32.eqz (i32.atomic.load (i32.const 0)))
+                (then (return (i32.const -1))))
+            (i32.load (local.get 0)))
+        (func (export "increment")
+            (drop (i32.atomic.rmw.add (i32.const 8) (i32.const 1))))
+    )
+"#;
+
+struct Setup {
+    engine: Engine,
+    module: Module,
+    memory: SharedMemory,
+    detector: RaceDetector,
+}
+
+impl Setup {
+    fn new() -> Result<Setup> {
+        let mut config = Config::new();
+        config.wasm_threads(true).race_detection(true);
+        let engine = Engine::new(&config)?;
+        let module = Module::new(&engine, WAT)?;
+        let memory = SharedMemory::new(&engine, MemoryType::shared(1, 1))?;
+        Ok(Setup {
+            engine,
+            module,
+            memory,
+            detector: RaceDetector::new(),
+        })
+    }
+
+    fn thread(&self, thread: RaceThread) -> Result<(Store<()>, Instance)> {
+        let mut store = Store::new(&self.engine, ());
+        thread.attach(&mut store);
+        let instance = Instance::new(&mut store, &self.module, &[self.memory.clone().into()])?;
+        Ok((store, instance))
+    }
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn unsynchronized_accesses_race() -> Result<()> {
+    let setup = Setup::new()?;
+    let main = setup.detector.thread();
+    let main_id = main.id();
+    let (mut main_store, main_instance) = setup.thread(main)?;
+    let other = setup.detector.thread();
+    let other_id = other.id();
+    let (mut other_store, other_instance) = setup.thread(other)?;
+
+    main_instance
+        .get_typed_func::<i32, ()>(&mut main_store, "write")?
+        .call(&mut main_store, 100)?;
+    other_instance
+        .get_typed_func::<i32, i32>(&mut other_store, "read")?
+        .call(&mut other_store, 102)?;
+
+    let races = setup.detector.take_races();
+    assert_eq!(races.len(), 1);
+    let race = &races[0];
+    assert_eq!(race.address(), 102);
+    assert_eq!(race.memory_index(), 0);
+    assert_eq!(race.access().thread(), other_id);
+    assert_eq!(race.access().kind(), MemoryAccessKind::Load);
+    assert_eq!(race.access().size(), 4);
+    assert_eq!(race.previous().thread(), main_id);
+    assert_eq!(race.previous().kind(), MemoryAccessKind::Store);
+    assert_ne!(
+        race.access().module_offset(),
+        race.previous().module_offset()
+    );
+    assert_eq!(race.backtrace().frames().len(), 1);
+    assert_eq!(
+        race.backtrace().frames()[0].module_offset(),
+        Some(race.access().module_offset())
+    );
+    let message = race.to_string();
+    assert!(message.contains("data race on address 0x66"), "{message}");
+
+    // The same pair of instructions isn't reported twice.
+    other_instance
+        .get_typed_func::<i32, i32>(&mut other_store, "read")?
+        .call(&mut other_store, 100)?;
+    assert!(setup.detector.take_races().is_empty());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn fork_orders_parent_before_child() -> Result<()> {
+    let setup = Setup::new()?;
+    let (mut parent, parent_instance) = setup.thread(setup.detector.thread())?;
+    let write = parent_instance.get_typed_func::<i32, ()>(&mut parent, "write")?;
+    write.call(&mut parent, 100)?;
+
+    let (mut child, child_instance) = setup.thread(setup.detector.fork(&parent))?;
+    child_instance
+        .get_typed_func::<i32, ()>(&mut child, "write")?
+        .call(&mut child, 100)?;
+    assert!(setup.detector.take_races().is_empty());
+
+    // The parent's writes after the fork aren't ordered with the child's.
+    write.call(&mut parent, 100)?;
+    let races = setup.detector.take_races();
+    assert_eq!(races.len(), 1);
+    assert_eq!(races[0].access().kind(), MemoryAccessKind::Store);
+    assert_eq!(races[0].previous().kind(), MemoryAccessKind::Store);
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn atomics_synchronize() -> Result<()> {
+    let setup = Setup::new()?;
+    let (mut producer, producer_instance) = setup.thread(setup.detector.thread())?;
+    let (mut consumer, consumer_instance) = setup.thread(setup.detector.thread())?;
+
+    producer_instance
+        .get_typed_func::<i32, ()>(&mut producer, "publish")?
+        .call(&mut producer, 100)?;
+    let value = consumer_instance
+        .get_typed_func::<i32, i32>(&mut consumer, "consume")?
+        .call(&mut consumer, 100)?;
+    assert_eq!(value, 1);
+
+    // Concurrent atomic accesses never race with each other.
+    producer_instance
+        .get_typed_func::<(), ()>(&mut producer, "increment")?
+        .call(&mut producer, ())?;
+    consumer_instance
+        .get_typed_func::<(), ()>(&mut consumer, "increment")?
+        .call(&mut consumer, ())?;
+    assert!(setup.detector.take_races().is_empty());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn handler_sees_races() -> Result<()> {
+    let mut setup = Setup::new()?;
+    let (tx, rx) = std::sync::mpsc::channel();
+    let tx = std::sync::Mutex::new(tx);
+    setup.detector = RaceDetector::with_handler(move |race| {
+        tx.lock().unwrap().send(race.address()).unwrap();
+    });
+
+    let (mut a, a_instance) = setup.thread(setup.detector.thread())?;
+    let (mut b, b_instance) = setup.thread(setup.detector.thread())?;
+    a_instance
+        .get_typed_func::<i32, i32>(&mut a, "read")?
+        .call(&mut a, 200)?;
+    b_instance
+        .get_typed_func::<i32, ()>(&mut b, "write")?
+        .call(&mut b, 200)?;
+    assert_eq!(rx.try_recv().unwrap(), 200);
+    assert_eq!(setup.detector.take_races().len(), 1);
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn shadow_state_is_bounded() -> Result<()> {
+    let setup = Setup::new()?;
+    setup.detector.set_shadow_limit(4);
+    let (mut a, a_instance) = setup.thread(setup.detector.thread())?;
+    let (mut b, b_instance) = setup.thread(setup.detector.thread())?;
+    a_instance
+        .get_typed_func::<i32, ()>(&mut a, "write")?
+        .call(&mut a, 100)?;
+
+    // Remembering the write to 200 flushes the write to 100, so the read of
+    // 100 doesn't race with anything.
+    b_instance
+        .get_typed_func::<i32, ()>(&mut b, "write")?
+        .call(&mut b, 200)?;
+    b_instance
+        .get_typed_func::<i32, i32>(&mut b, "read")?
+        .call(&mut b, 100)?;
+    assert!(setup.detector.take_races().is_empty());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn dropping_all_stores_forgets_accesses() -> Result<()> {
+    let setup = Setup::new()?;
+    let (mut a, a_instance) = setup.thread(setup.detector.thread())?;
+    a_instance
+        .get_typed_func::<i32, ()>(&mut a, "write")?
+        .call(&mut a, 100)?;
+    drop(a);
+
+    let (mut b, b_instance) = setup.thread(setup.detector.thread())?;
+    b_instance
+        .get_typed_func::<i32, i32>(&mut b, "read")?
+        .call(&mut b, 100)?;
+    assert!(setup.detector.take_races().is_empty());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn only_shared_memories_are_instrumented() -> Result<()> {
+    let mut config = Config::new();
+    config.race_detection(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (memory 1)
+                (func (export "run")
+                    (i32.store (i32.const 0) (i32.const 1))))
+        "#,
+    )?;
+    let mut store = Store::new(&engine, 0);
+    store.memory_access_hook(|mut store, _| {
+        *store.data_mut() += 1;
+        Ok(())
+    });
+    let instance = Instance::new(&mut store, &module, &[])?;
+    instance
+        .get_typed_func::<(), ()>(&mut store, "run")?
+        .call(&mut store, ())?;
+    assert_eq!(*store.data(), 0);
+    Ok(())
+}
@@ -0,0 +1,330 @@
+use wasmtime::*;
+
+const WAT: &str = r#"
+    (module
+        (memory (export "memory") 1 10)
+        (global $counter (export "counter") (mut i32) (i32.const 0))
+        (global $constant (export "constant") i64 (i64.const 42))
+        (table $table (export "table") 1 funcref)
+        (type $ret_i32 (func (result i32)))
+
+        (func $one (result i32) i32.const 1)
+        (func $two (result i32) i32.const 2)
+        (elem declare func $one $two)
+
+        (func (export "init")
+            (i32.store (i32.const 100) (i32.const 0xdeadbeef))
+            (drop (memory.grow (i32.const 1)))
+            (i32.store (i32.const 65536) (i32.const 7))
+            (global.set $counter (i32.const 5))
+            (table.set $table (i32.const 0) (ref.func $two))
+            (drop (table.grow $table (ref.func $one) (i32.const 2))))
+
+        (func (export "call-table") (param i32) (result i32)
+            (call_indirect $table (type $ret_i32) (local.get 0)))
+
+        (func (export "bump") (result i32)
+            (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+            (global.get $counter))
+    )
+"#;
+
+fn check_restored(store: &mut Store<()>, instance: Instance) -> Result<()> {
+    let memory = instance.get_memory(&mut *store, "memory").unwrap();
+    assert_eq!(memory.size(&store), 2);
+    assert_eq!(memory.data(&store)[100..104], 0xdeadbeefu32.to_le_bytes());
+    assert_eq!(memory.data(&store)[65536..65540], 7u32.to_le_bytes());
+
+    let constant = instance.get_global(&mut *store, "constant").unwrap();
+    assert_eq!(constant.get(&mut *store).unwrap_i64(), 42);
+
+    let table = instance.get_table(&mut *store, "table").unwrap();
+    assert_eq!(table.size(&store), 3);
+    let call_table = instance.get_typed_func::<u32, i32>(&mut *store, "call-table")?;
+    assert_eq!(call_table.call(&mut *store, 0)?, 2);
+    assert_eq!(call_table.call(&mut *store, 1)?, 1);
+    assert_eq!(call_table.call(&mut *store, 2)?, 1);
+
+    let bump = instance.get_typed_func::<(), i32>(&mut *store, "bump")?;
+    assert_eq!(bump.call(&mut *store, ())?, 6);
+    Ok(())
+}
+
+fn snapshot_and_restore(config: &Config) -> Result<()> {
+    let engine = Engine::new(config)?;
+    let module = Module::new(&engine, WAT)?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let init = instance.get_typed_func::<(), ()>(&mut store, "init")?;
+    init.call(&mut store, ())?;
+    let snapshot = instance.snapshot(&mut store)?;
+
+    // Restore directly, twice, to check that the snapshot is reusable and
+    // that restored instances don't share state.
+    for _ in 0..2 {
+        let mut store = Store::new(&engine, ());
+        let instance = snapshot.instantiate(&mut store, &module, &[])?;
+        check_restored(&mut store, instance)?;
+    }
+
+    // Restore through serialized bytes.
+    let bytes = snapshot.serialize()?;
+    let snapshot = InstanceSnapshot::deserialize(&bytes)?;
+    let mut store = Store::new(&engine, ());
+    let instance = snapshot.instantiate(&mut store, &module, &[])?;
+    check_restored(&mut store, instance)?;
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn snapshot_restores_instance_state() -> Result<()> {
+    snapshot_and_restore(&Config::new())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn snapshot_restores_instance_state_without_cow() -> Result<()> {
+    let mut config = Config::new();
+    config.memory_init_cow(false);
+    snapshot_and_restore(&config)
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn snapshot_restores_instance_state_with_pooling() -> Result<()> {
+    let mut config = Config::new();
+    config.allocation_strategy(PoolingAllocationConfig::default());
+    snapshot_and_restore(&config)
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn snapshot_rejects_mismatched_module() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::new(&engine, WAT)?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let snapshot = instance.snapshot(&mut store)?;
+
+    let other = Module::new(&engine, r#"(module (memory 1))"#)?;
+    let mut store = Store::new(&engine, ());
+    assert!(snapshot.instantiate(&mut store, &other, &[]).is_err());
+
+    // A module with the same memories, tables, and globals, but different
+    // code, is a different module too.
+    let same_shape = Module::new(&engine, WAT.replace("i32.const 2", "i32.const 3"))?;
+    let mut store = Store::new(&engine, ());
+    assert!(snapshot.instantiate(&mut store, &same_shape, &[]).is_err());
+
+    assert!(InstanceSnapshot::deserialize(b"not a snapshot").is_err());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn snapshot_rejects_malformed_bytes() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::new(&engine, r#"(module (memory 0 1))"#)?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let bytes = instance.snapshot(&mut store)?.serialize()?;
+
+    // The serialized snapshot ends with its memories, tables, globals,
+    // objects, and externrefs: one empty memory, and nothing else.
+    let (head, tail) = bytes.split_at(bytes.len() - 6);
+    assert_eq!(tail, [1, 0, 0, 0, 0, 0]);
+
+    let restore = |tail: &[u8]| -> Result<Instance> {
+        let snapshot = InstanceSnapshot::deserialize(&[head, tail].concat())?;
+        let mut store = Store::new(&engine, ());
+        snapshot.instantiate(&mut store, &module, &[])
+    };
+    restore(tail)?;
+
+    let err = |tail: &[u8]| format!("{:?}", restore(tail).err().unwrap());
+
+    // No memories.
+    assert!(err(&[0, 0, 0, 0, 0]).contains("snapshot has 0 memories"));
+    // A memory that isn't a whole number of pages.
+    assert!(err(&[1, 1, 0, 0, 0, 0, 0]).contains("isn't a multiple of its page size"));
+    // A memory that's larger than the module's maximum.
+    let mut too_big = vec![1, 0x80, 0x80, 0x08];
+    too_big.extend(std::iter::repeat(0).take(2 << 16));
+    too_big.extend([0, 0, 0, 0]);
+    assert!(err(&too_big).contains("outside of its limits"));
+    // A table and a global that the module doesn't define.
+    assert!(err(&[1, 0, 1, 0, 0, 0, 0]).contains("snapshot has 1 tables"));
+    assert!(err(&[1, 0, 0, 1, 0, 0, 0]).contains("snapshot has 1 globals"));
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn snapshot_rejects_externrefs() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::new(
+        &engine,
+        r#"(module (global (export "g") (mut externref) (ref.null extern)))"#,
+    )?;
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+
+    // A null externref can be snapshotted.
+    instance.snapshot(&mut store)?;
+
+    let g = instance.get_global(&mut store, "g").unwrap();
+    let r = ExternRef::new(&mut store, 1234)?;
+    g.set(&mut store, r.into())?;
+    assert!(instance.snapshot(&mut store).is_err());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn snapshot_restores_gc_objects() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $node (struct (field $val i32) (field $next (mut (ref null $node)))))
+                (type $pair (struct (field (ref $node)) (field (ref $node))))
+                (type $nodes (array (mut (ref null $node))))
+                (type $bytes (array i8))
+
+                (global $cycle (mut (ref null $node)) (ref.null $node))
+                (global $pair (mut (ref null $pair)) (ref.null $pair))
+                (global $ext (mut externref) (ref.null extern))
+                (table $table 2 anyref)
+
+                (func (export "init")
+                    (local $a (ref $node))
+                    (local $b (ref $node))
+                    (local.set $a (struct.new $node (i32.const 1) (ref.null $node)))
+                    (local.set $b (struct.new $node (i32.const 2) (local.get $a)))
+                    (struct.set $node $next (local.get $a) (local.get $b))
+                    (global.set $cycle (local.get $a))
+                    (global.set $pair (struct.new $pair (local.get $a) (local.get $b)))
+                    (table.set $table (i32.const 0)
+                        (array.new_fixed $nodes 2 (local.get $b) (ref.null $node)))
+                    (table.set $table (i32.const 1) (ref.i31 (i32.const 42)))
+                    (global.set $ext
+                        (extern.convert_any
+                            (array.new_fixed $bytes 3 (i32.const 7) (i32.const 8) (i32.const 9)))))
+
+                (func $a (result (ref $node))
+                    (ref.as_non_null (global.get $cycle)))
+                (func $b (result (ref $node))
+                    (ref.as_non_null (struct.get $node $next (call $a))))
+
+                (func (export "cycle-sum") (result i32)
+                    (i32.add
+                        (struct.get $node $val (call $a))
+                        (struct.get $node $val (call $b))))
+                (func (export "cycle-closed") (result i32)
+                    (ref.eq (call $a) (struct.get $node $next (call $b))))
+                (func (export "pair-shared") (result i32)
+                    (i32.and
+                        (ref.eq (call $a) (struct.get $pair 0 (global.get $pair)))
+                        (ref.eq (call $b) (struct.get $pair 1 (global.get $pair)))))
+                (func (export "array-shared") (result i32)
+                    (local $nodes (ref $nodes))
+                    (local.set $nodes (ref.cast (ref $nodes) (table.get $table (i32.const 0))))
+                    (i32.and
+                        (ref.eq (call $b) (array.get $nodes (local.get $nodes) (i32.const 0)))
+                        (ref.is_null (array.get $nodes (local.get $nodes) (i32.const 1)))))
+                (func (export "i31") (result i32)
+                    (i31.get_s (ref.cast (ref i31) (table.get $table (i32.const 1)))))
+                (func (export "ext") (result i32)
+                    (array.get_u $bytes
+                        (ref.cast (ref $bytes) (any.convert_extern (global.get $ext)))
+                        (i32.const 2)))
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let init = instance.get_typed_func::<(), ()>(&mut store, "init")?;
+    init.call(&mut store, ())?;
+    let snapshot = instance.snapshot(&mut store)?;
+    let snapshot = InstanceSnapshot::deserialize(&snapshot.serialize()?)?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = snapshot.instantiate(&mut store, &module, &[])?;
+    for (name, expected) in [
+        ("cycle-sum", 3),
+        ("cycle-closed", 1),
+        ("pair-shared", 1),
+        ("array-shared", 1),
+        ("i31", 42),
+        ("ext", 9),
+    ] {
+        let func = instance.get_typed_func::<(), i32>(&mut store, name)?;
+        assert_eq!(func.call(&mut store, ())?, expected, "{name}");
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn snapshot_with_externrefs() -> Result<()> {
+    let mut config = Config::new();
+    config.wasm_function_references(true);
+    config.wasm_gc(true);
+    let engine = Engine::new(&config)?;
+    let module = Module::new(
+        &engine,
+        r#"
+            (module
+                (type $s (struct (field externref)))
+                (global $g (export "g") (mut externref) (ref.null extern))
+                (global $obj (mut (ref null $s)) (ref.null $s))
+                (func (export "init")
+                    (global.set $obj (struct.new $s (global.get $g))))
+                (func (export "get") (result externref)
+                    (struct.get $s 0 (global.get $obj)))
+            )
+        "#,
+    )?;
+
+    let mut store = Store::new(&engine, ());
+    let instance = Instance::new(&mut store, &module, &[])?;
+    let g = instance.get_global(&mut store, "g").unwrap();
+    let r = ExternRef::new(&mut store, 1234u32)?;
+    g.set(&mut store, r.into())?;
+    let init = instance.get_typed_func::<(), ()>(&mut store, "init")?;
+    init.call(&mut store, ())?;
+
+    let mut saved = 0;
+    let snapshot = instance.snapshot_with_externrefs(&mut store, |store, r| {
+        saved += 1;
+        let data = r.data(&store)?.unwrap().downcast_ref::<u32>().unwrap();
+        Ok(data.to_le_bytes().to_vec())
+    })?;
+    // The global and the struct field share one `externref`.
+    assert_eq!(saved, 1);
+    let snapshot = InstanceSnapshot::deserialize(&snapshot.serialize()?)?;
+
+    let mut store = Store::new(&engine, ());
+    assert!(snapshot.instantiate(&mut store, &module, &[]).is_err());
+    let instance =
+        snapshot.instantiate_with_externrefs(&mut store, &module, &[], |store, bytes| {
+            ExternRef::new(store, u32::from_le_bytes(bytes.try_into()?))
+        })?;
+
+    let get = instance.get_typed_func::<(), Option<Rooted<ExternRef>>>(&mut store, "get")?;
+    let r = get.call(&mut store, ())?.unwrap();
+    let data = r.data(&store)?.unwrap().downcast_ref::<u32>().copied();
+    assert_eq!(data, Some(1234));
+    let g = instance.get_global(&mut store, "g").unwrap();
+    let g = g.get(&mut store).unwrap_externref().cloned().unwrap();
+    assert!(Rooted::ref_eq(&store, &g, &r)?);
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
-use wasmtime::{Engine, Store};
+use std::time::Duration;
+use wasmtime::{CallHook, Caller, Engine, Instance, Linker, Module, Result, Store, StoreStats};
 
 #[test]
 fn into_inner() {
@@ -20,3 +21,105 @@ fn into_inner() {
     Store::new(&engine, A).into_data();
     assert_eq!(HITS.load(SeqCst), 2);
 }
+
+const STATS_WAT: &str = r#"
+    (module
+        (import "" "sleep" (func $sleep))
+        (import "" "fail" (func $fail))
+        (func (export "spin") (param i32)
+            (loop
+                (br_if 0 (local.tee 0 (i32.sub (local.get 0) (i32.const 1))))))
+        (func (export "sleep") (call $sleep) (call $sleep))
+        (func (export "fail") (call $fail))
+        (func (export "trap") unreachable))
+"#;
+
+fn stats_instance(store: &mut Store<()>) -> Result<Instance> {
+    let mut linker = Linker::new(store.engine());
+    linker.func_wrap("", "sleep", |_: Caller<'_, ()>| {
+        std::thread::sleep(Duration::from_millis(10));
+    })?;
+    linker.func_wrap("", "fail", |_: Caller<'_, ()>| -> Result<()> {
+        anyhow::bail!("fail")
+    })?;
+    let module = Module::new(store.engine(), STATS_WAT)?;
+    linker.instantiate(&mut *store, &module)
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn stats_disabled_by_default() -> Result<()> {
+    let mut store = Store::new(&Engine::default(), ());
+    let instance = stats_instance(&mut store)?;
+    let sleep = instance.get_typed_func::<(), ()>(&mut store, "sleep")?;
+    sleep.call(&mut store, ())?;
+    assert_eq!(store.stats(), StoreStats::default());
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn stats_attribute_time_and_count_calls() -> Result<()> {
+    let mut store = Store::new(&Engine::default(), ());
+    store.enable_stats(true);
+    let instance = stats_instance(&mut store)?;
+
+    let sleep = instance.get_typed_func::<(), ()>(&mut store, "sleep")?;
+    sleep.call(&mut store, ())?;
+    let stats = store.stats();
+    assert_eq!(stats.host_calls, 2);
+    assert_eq!(stats.traps, 0);
+    assert!(
+        stats.host_wall_time >= Duration::from_millis(20),
+        "{stats:?}"
+    );
+    assert!(stats.wasm_wall_time < stats.host_wall_time, "{stats:?}");
+    // Sleeping doesn't use the CPU.
+    assert!(stats.host_cpu_time < stats.host_wall_time, "{stats:?}");
+
+    // Time spent outside of any call isn't counted.
+    std::thread::sleep(Duration::from_millis(20));
+    assert_eq!(store.stats(), stats);
+
+    let spin = instance.get_typed_func::<i32, ()>(&mut store, "spin")?;
+    spin.call(&mut store, 10_000_000)?;
+    let after = store.stats();
+    assert!(after.wasm_wall_time > stats.wasm_wall_time, "{after:?}");
+    assert_eq!(after.host_wall_time, stats.host_wall_time);
+    assert_eq!(after.host_cpu_time, stats.host_cpu_time);
+    if cfg!(any(unix, windows)) {
+        assert!(after.wasm_cpu_time > stats.wasm_cpu_time, "{after:?}");
+    }
+    assert_eq!(after.host_calls, 2);
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn stats_count_traps() -> Result<()> {
+    let mut store = Store::new(&Engine::default(), ());
+    store.enable_stats(true);
+    let instance = stats_instance(&mut store)?;
+
+    let trap = instance.get_typed_func::<(), ()>(&mut store, "trap")?;
+    assert!(trap.call(&mut store, ()).is_err());
+    let fail = instance.get_typed_func::<(), ()>(&mut store, "fail")?;
+    assert!(fail.call(&mut store, ()).is_err());
+    let stats = store.stats();
+    assert_eq!(stats.traps, 2);
+    assert_eq!(stats.host_calls, 1);
+
+    store.enable_stats(false);
+    store.enable_stats(true);
+    assert_eq!(store.stats(), StoreStats::default());
+
+    // A call hook failing on the way out of WebAssembly fails the call too.
+    store.call_hook(|_, hook| match hook {
+        CallHook::ReturningFromWasm => anyhow::bail!("hook failed"),
+        _ => Ok(()),
+    });
+    let spin = instance.get_typed_func::<i32, ()>(&mut store, "spin")?;
+    assert!(spin.call(&mut store, 1).is_err());
+    assert_eq!(store.stats().traps, 1);
+    Ok(())
+}
@@ -19,7 +19,7 @@
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
 ;;     sig0 = (i64 vmctx, i32 uext) -> i64 tail
-;;     fn0 = colocated u1:26 sig0
+;;     fn0 = colocated u1:29 sig0
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64):
@@ -80,20 +80,25 @@
 ;; }
 ;;
 ;; function u0:1(i64 vmctx, i64, i32) tail {
+;;     ss0 = explicit_slot 4, align = 4
 ;;     gv0 = vmctx
 ;;     gv1 = load.i64 notrap aligned readonly gv0+8
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
 ;;     sig0 = (i64 vmctx, i32 uext) tail
-;;     fn0 = colocated u1:25 sig0
+;;     sig1 = (i64 vmctx, i32 uext) -> i64 tail
+;;     fn0 = colocated u1:28 sig0
+;;     fn1 = colocated u1:29 sig1
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: i32):
-;;                                     v58 = iconst.i64 96
-;; @003b                               v4 = iadd v0, v58  ; v58 = 96
+;;                                     v85 = iconst.i64 96
+;; @003b                               v4 = iadd v0, v85  ; v85 = 96
 ;; @003b                               v5 = load.i32 notrap aligned v4
-;;                                     v59 = iconst.i32 0
-;; @003b                               v6 = icmp eq v2, v59  ; v59 = 0
+;;                                     v86 = stack_addr.i64 ss0
+;;                                     store notrap v5, v86
+;;                                     v87 = iconst.i32 0
+;; @003b                               v6 = icmp eq v2, v87  ; v87 = 0
 ;; @003b                               brif v6, block3, block2
 ;;
 ;;                                 block2:
@@ -108,46 +113,78 @@
 ;; @003b                               v17 = iadd v33, v13
 ;; @003b                               v18 = load.i64 notrap aligned v17
 ;; @003b                               trapz v16, user1
-;;                                     v60 = iconst.i64 1
-;; @003b                               v19 = iadd v18, v60  ; v60 = 1
+;;                                     v92 = iconst.i64 1
+;; @003b                               v19 = iadd v18, v92  ; v92 = 1
 ;; @003b                               store notrap aligned v19, v17
 ;; @003b                               jump block3
 ;;
 ;;                                 block3:
-;;                                     v64 = iadd.i64 v0, v58  ; v58 = 96
-;; @003b                               store.i32 notrap aligned v2, v64
-;;                                     v65 = iconst.i32 0
-;;                                     v66 = icmp.i32 eq v5, v65  ; v65 = 0
-;; @003b                               brif v66, block7, block4
+;;                                     v100 = iadd.i64 v0, v85  ; v85 = 96
+;; @003b                               store.i32 notrap aligned v2, v100
+;;                                     v84 = load.i32 notrap v86
+;;                                     v101 = iconst.i32 0
+;;                                     v102 = icmp eq v84, v101  ; v101 = 0
+;; @003b                               brif v102, block9, block4
 ;;
 ;;                                 block4:
-;; @003b                               v36 = uextend.i64 v5
-;;                                     v67 = iconst.i64 8
-;; @003b                               v38 = uadd_overflow_trap v36, v67, user1  ; v67 = 8
-;; @003b                               v40 = uadd_overflow_trap v38, v67, user1  ; v67 = 8
-;;                                     v68 = load.i64 notrap aligned readonly v0+48
-;; @003b                               v41 = icmp ule v40, v68
+;; @003b                               v36 = uextend.i64 v84
+;;                                     v103 = iconst.i64 8
+;; @003b                               v38 = uadd_overflow_trap v36, v103, user1  ; v103 = 8
+;; @003b                               v40 = uadd_overflow_trap v38, v103, user1  ; v103 = 8
+;;                                     v104 = load.i64 notrap aligned readonly v0+48
+;; @003b                               v41 = icmp ule v40, v104
 ;; @003b                               trapz v41, user1
-;;                                     v69 = load.i64 notrap aligned readonly v0+40
-;; @003b                               v42 = iadd v69, v38
+;;                                     v105 = load.i64 notrap aligned readonly v0+40
+;; @003b                               v42 = iadd v105, v38
 ;; @003b                               v43 = load.i64 notrap aligned v42
-;;                                     v62 = iconst.i64 -1
-;; @003b                               v44 = iadd v43, v62  ; v62 = -1
-;;                                     v63 = iconst.i64 0
-;; @003b                               v45 = icmp eq v44, v63  ; v63 = 0
-;; @003b                               brif v45, block5, block6
+;;                                     v106 = iconst.i64 1
+;; @003b                               v44 = icmp eq v43, v106  ; v106 = 1
+;; @003b                               brif v44, block5, block6
 ;;
 ;;                                 block5 cold:
-;; @003b                               call fn0(v0, v5)
-;; @003b                               jump block7
+;;                                     v78 = load.i32 notrap v86
+;; @003b                               call fn0(v0, v78)
+;; @003b                               jump block9
 ;;
 ;;                                 block6:
-;; @003b                               trapz.i8 v41, user1
-;;                                     v70 = iadd.i64 v43, v62  ; v62 = -1
-;; @003b                               store notrap aligned v70, v42
-;; @003b                               jump block7
+;; @003b                               v47 = load.i64 notrap aligned readonly v0+56
+;; @003b                               v48 = load.i64 notrap aligned v47
+;; @003b                               v49 = load.i64 notrap aligned v47+8
+;; @003b                               v50 = icmp eq v48, v49
+;; @003b                               brif v50, block8, block7
 ;;
 ;;                                 block7:
+;;                                     v82 = load.i32 notrap v86
+;; @003b                               store notrap aligned v82, v48
+;;                                     v95 = iconst.i64 4
+;; @003b                               v51 = iadd.i64 v48, v95  ; v95 = 4
+;; @003b                               store notrap aligned v51, v47
+;; @003b                               jump block9
+;;
+;;                                 block8 cold:
+;;                                     v81 = load.i32 notrap v86
+;; @003b                               v53 = call fn1(v0, v81), stack_map=[i32 @ ss0+0]
+;;                                     v80 = load.i32 notrap v86
+;; @003b                               v58 = uextend.i64 v80
+;; @003b                               v60 = uadd_overflow_trap v58, v103, user1  ; v103 = 8
+;; @003b                               v62 = uadd_overflow_trap v60, v103, user1  ; v103 = 8
+;; @003b                               v63 = icmp ule v62, v104
+;; @003b                               trapz v63, user1
+;; @003b                               v64 = iadd.i64 v105, v60
+;; @003b                               v65 = load.i64 notrap aligned v64
+;;                                     v79 = load.i32 notrap v86
+;; @003b                               v71 = uextend.i64 v79
+;; @003b                               v73 = uadd_overflow_trap v71, v103, user1  ; v103 = 8
+;; @003b                               v75 = uadd_overflow_trap v73, v103, user1  ; v103 = 8
+;; @003b                               v76 = icmp ule v75, v104
+;; @003b                               trapz v76, user1
+;;                                     v98 = iconst.i64 -1
+;; @003b                               v66 = iadd v65, v98  ; v98 = -1
+;; @003b                               v77 = iadd.i64 v105, v73
+;; @003b                               store notrap aligned v66, v77
+;; @003b                               jump block9
+;;
+;;                                 block9:
 ;; @003d                               jump block1
 ;;
 ;;                                 block1:
@@ -110,7 +110,7 @@
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
 ;;     sig0 = (i64 vmctx, i32 uext) -> i64 tail
-;;     fn0 = colocated u1:26 sig0
+;;     fn0 = colocated u1:29 sig0
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: i32):
@@ -118,63 +118,65 @@
 ;; @004e                               v9 = uextend.i64 v2
 ;; @004e                               v10 = iconst.i64 24
 ;; @004e                               v11 = uadd_overflow_trap v9, v10, user1  ; v10 = 24
-;;                                     v68 = iconst.i64 32
-;; @004e                               v13 = uadd_overflow_trap v9, v68, user1  ; v68 = 32
+;;                                     v72 = iconst.i64 32
+;; @004e                               v13 = uadd_overflow_trap v9, v72, user1  ; v72 = 32
 ;; @004e                               v8 = load.i64 notrap aligned readonly v0+48
 ;; @004e                               v14 = icmp ule v13, v8
 ;; @004e                               trapz v14, user1
 ;; @004e                               v6 = load.i64 notrap aligned readonly v0+40
 ;; @004e                               v15 = iadd v6, v11
 ;; @004e                               v16 = load.i32 notrap aligned little v15
-;;                                     v58 = stack_addr.i64 ss0
-;;                                     store notrap v16, v58
-;; @004e                               v17 = iconst.i32 -2
-;; @004e                               v18 = band v16, v17  ; v17 = -2
-;;                                     v60 = iconst.i32 0
-;; @004e                               v19 = icmp eq v18, v60  ; v60 = 0
-;; @004e                               brif v19, block5, block2
+;;                                     v60 = stack_addr.i64 ss0
+;;                                     store notrap v16, v60
+;;                                     v62 = iconst.i32 1
+;; @004e                               v17 = band v16, v62  ; v62 = 1
+;;                                     v64 = iconst.i32 0
+;; @004e                               v18 = icmp eq v16, v64  ; v64 = 0
+;; @004e                               v19 = uextend.i32 v18
+;; @004e                               v20 = bor v17, v19
+;; @004e                               brif v20, block5, block2
 ;;
 ;;                                 block2:
-;; @004e                               v21 = load.i64 notrap aligned readonly v0+56
-;; @004e                               v22 = load.i64 notrap aligned v21
-;; @004e                               v23 = load.i64 notrap aligned v21+8
-;; @004e                               v24 = icmp eq v22, v23
-;; @004e                               brif v24, block3, block4
+;; @004e                               v22 = load.i64 notrap aligned readonly v0+56
+;; @004e                               v23 = load.i64 notrap aligned v22
+;; @004e                               v24 = load.i64 notrap aligned v22+8
+;; @004e                               v25 = icmp eq v23, v24
+;; @004e                               brif v25, block3, block4
 ;;
 ;;                                 block4:
-;; @004e                               v29 = uextend.i64 v16
-;; @004e                               v30 = iconst.i64 8
-;; @004e                               v31 = uadd_overflow_trap v29, v30, user1  ; v30 = 8
-;; @004e                               v33 = uadd_overflow_trap v31, v30, user1  ; v30 = 8
-;; @004e                               v34 = icmp ule v33, v8
-;; @004e                               trapz v34, user1
-;; @004e                               v35 = iadd.i64 v6, v31
-;; @004e                               v36 = load.i64 notrap aligned v35
-;;                                     v55 = load.i32 notrap v58
-;; @004e                               v42 = uextend.i64 v55
-;; @004e                               v44 = uadd_overflow_trap v42, v30, user1  ; v30 = 8
-;; @004e                               v46 = uadd_overflow_trap v44, v30, user1  ; v30 = 8
-;; @004e                               v47 = icmp ule v46, v8
-;; @004e                               trapz v47, user1
-;;                                     v62 = iconst.i64 1
-;; @004e                               v37 = iadd v36, v62  ; v62 = 1
-;; @004e                               v48 = iadd.i64 v6, v44
-;; @004e                               store notrap aligned v37, v48
-;;                                     v54 = load.i32 notrap v58
-;; @004e                               store notrap aligned v54, v22
-;;                                     v65 = iconst.i64 4
-;; @004e                               v49 = iadd.i64 v22, v65  ; v65 = 4
-;; @004e                               store notrap aligned v49, v21
+;; @004e                               v30 = uextend.i64 v16
+;; @004e                               v31 = iconst.i64 8
+;; @004e                               v32 = uadd_overflow_trap v30, v31, user1  ; v31 = 8
+;; @004e                               v34 = uadd_overflow_trap v32, v31, user1  ; v31 = 8
+;; @004e                               v35 = icmp ule v34, v8
+;; @004e                               trapz v35, user1
+;; @004e                               v36 = iadd.i64 v6, v32
+;; @004e                               v37 = load.i64 notrap aligned v36
+;;                                     v56 = load.i32 notrap v60
+;; @004e                               v43 = uextend.i64 v56
+;; @004e                               v45 = uadd_overflow_trap v43, v31, user1  ; v31 = 8
+;; @004e                               v47 = uadd_overflow_trap v45, v31, user1  ; v31 = 8
+;; @004e                               v48 = icmp ule v47, v8
+;; @004e                               trapz v48, user1
+;;                                     v66 = iconst.i64 1
+;; @004e                               v38 = iadd v37, v66  ; v66 = 1
+;; @004e                               v49 = iadd.i64 v6, v45
+;; @004e                               store notrap aligned v38, v49
+;;                                     v55 = load.i32 notrap v60
+;; @004e                               store notrap aligned v55, v23
+;;                                     v69 = iconst.i64 4
+;; @004e                               v50 = iadd.i64 v23, v69  ; v69 = 4
+;; @004e                               store notrap aligned v50, v22
 ;; @004e                               jump block5
 ;;
 ;;                                 block3 cold:
-;; @004e                               v51 = call fn0(v0, v16), stack_map=[i32 @ ss0+0]
+;; @004e                               v52 = call fn0(v0, v16), stack_map=[i32 @ ss0+0]
 ;; @004e                               jump block5
 ;;
 ;;                                 block5:
-;;                                     v52 = load.i32 notrap v58
+;;                                     v53 = load.i32 notrap v60
 ;; @0052                               jump block1
 ;;
 ;;                                 block1:
-;; @0052                               return v52
+;; @0052                               return v53
 ;; }
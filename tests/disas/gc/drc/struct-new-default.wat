@@ -17,7 +17,7 @@
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
 ;;     sig0 = (i64 vmctx, i32 uext, i32 uext, i32 uext, i32 uext) -> i64 tail
-;;     fn0 = colocated u1:27 sig0
+;;     fn0 = colocated u1:30 sig0
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64):
@@ -31,36 +31,36 @@
 ;; @0021                               v12 = ireduce.i32 v11
 ;; @0021                               v15 = uextend.i64 v12
 ;; @0021                               v16 = iadd v14, v15
-;;                                     v47 = iconst.i64 16
-;; @0021                               v17 = iadd v16, v47  ; v47 = 16
+;;                                     v48 = iconst.i64 16
+;; @0021                               v17 = iadd v16, v48  ; v48 = 16
 ;; @0021                               store notrap aligned little v3, v17  ; v3 = 0.0
-;;                                     v48 = iconst.i64 20
-;; @0021                               v18 = iadd v16, v48  ; v48 = 20
+;;                                     v49 = iconst.i64 20
+;; @0021                               v18 = iadd v16, v49  ; v49 = 20
 ;; @0021                               istore8 notrap aligned little v4, v18  ; v4 = 0
-;;                                     v58 = iconst.i8 1
-;; @0021                               brif v58, block3, block2  ; v58 = 1
+;;                                     v51 = iconst.i32 1
+;; @0021                               brif v51, block3, block2  ; v51 = 1
 ;;
 ;;                                 block2:
-;;                                     v65 = iconst.i64 0
-;; @0021                               v28 = iconst.i64 8
-;; @0021                               v29 = uadd_overflow_trap v65, v28, user1  ; v65 = 0, v28 = 8
-;; @0021                               v31 = uadd_overflow_trap v29, v28, user1  ; v28 = 8
-;; @0021                               v26 = load.i64 notrap aligned readonly v0+48
-;; @0021                               v32 = icmp ule v31, v26
-;; @0021                               trapz v32, user1
-;; @0021                               v33 = iadd.i64 v14, v29
-;; @0021                               v34 = load.i64 notrap aligned v33
-;; @0021                               trapz v32, user1
-;;                                     v51 = iconst.i64 1
-;; @0021                               v35 = iadd v34, v51  ; v51 = 1
-;; @0021                               store notrap aligned v35, v33
+;;                                     v73 = iconst.i64 0
+;; @0021                               v29 = iconst.i64 8
+;; @0021                               v30 = uadd_overflow_trap v73, v29, user1  ; v73 = 0, v29 = 8
+;; @0021                               v32 = uadd_overflow_trap v30, v29, user1  ; v29 = 8
+;; @0021                               v27 = load.i64 notrap aligned readonly v0+48
+;; @0021                               v33 = icmp ule v32, v27
+;; @0021                               trapz v33, user1
+;; @0021                               v34 = iadd.i64 v14, v30
+;; @0021                               v35 = load.i64 notrap aligned v34
+;; @0021                               trapz v33, user1
+;;                                     v53 = iconst.i64 1
+;; @0021                               v36 = iadd v35, v53  ; v53 = 1
+;; @0021                               store notrap aligned v36, v34
 ;; @0021                               jump block3
 ;;
 ;;                                 block3:
-;;                                     v66 = iconst.i32 0
-;;                                     v49 = iconst.i64 24
-;; @0021                               v19 = iadd.i64 v16, v49  ; v49 = 24
-;; @0021                               store notrap aligned little v66, v19  ; v66 = 0
+;;                                     v74 = iconst.i32 0
+;;                                     v50 = iconst.i64 24
+;; @0021                               v19 = iadd.i64 v16, v50  ; v50 = 24
+;; @0021                               store notrap aligned little v74, v19  ; v74 = 0
 ;; @0024                               jump block1
 ;;
 ;;                                 block1:
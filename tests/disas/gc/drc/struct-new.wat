@@ -18,12 +18,12 @@
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
 ;;     sig0 = (i64 vmctx, i32 uext, i32 uext, i32 uext, i32 uext) -> i64 tail
-;;     fn0 = colocated u1:27 sig0
+;;     fn0 = colocated u1:30 sig0
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: f32, v3: i32, v4: i32):
-;;                                     v51 = stack_addr.i64 ss0
-;;                                     store notrap v4, v51
+;;                                     v53 = stack_addr.i64 ss0
+;;                                     store notrap v4, v53
 ;; @002a                               v8 = iconst.i32 -1342177280
 ;; @002a                               v9 = iconst.i32 0
 ;; @002a                               v6 = iconst.i32 32
@@ -33,45 +33,47 @@
 ;; @002a                               v12 = ireduce.i32 v11
 ;; @002a                               v15 = uextend.i64 v12
 ;; @002a                               v16 = iadd v14, v15
-;;                                     v52 = iconst.i64 16
-;; @002a                               v17 = iadd v16, v52  ; v52 = 16
+;;                                     v54 = iconst.i64 16
+;; @002a                               v17 = iadd v16, v54  ; v54 = 16
 ;; @002a                               store notrap aligned little v2, v17
-;;                                     v53 = iconst.i64 20
-;; @002a                               v18 = iadd v16, v53  ; v53 = 20
+;;                                     v55 = iconst.i64 20
+;; @002a                               v18 = iadd v16, v55  ; v55 = 20
 ;; @002a                               istore8 notrap aligned little v3, v18
-;;                                     v50 = load.i32 notrap v51
-;; @002a                               v20 = iconst.i32 -2
-;; @002a                               v21 = band v50, v20  ; v20 = -2
-;; @002a                               v22 = icmp eq v21, v9  ; v9 = 0
-;; @002a                               brif v22, block3, block2
+;;                                     v52 = load.i32 notrap v53
+;;                                     v58 = iconst.i32 1
+;; @002a                               v20 = band v52, v58  ; v58 = 1
+;; @002a                               v21 = icmp eq v52, v9  ; v9 = 0
+;; @002a                               v22 = uextend.i32 v21
+;; @002a                               v23 = bor v20, v22
+;; @002a                               brif v23, block3, block2
 ;;
 ;;                                 block2:
-;; @002a                               v27 = uextend.i64 v50
-;; @002a                               v28 = iconst.i64 8
-;; @002a                               v29 = uadd_overflow_trap v27, v28, user1  ; v28 = 8
-;; @002a                               v31 = uadd_overflow_trap v29, v28, user1  ; v28 = 8
-;; @002a                               v26 = load.i64 notrap aligned readonly v0+48
-;; @002a                               v32 = icmp ule v31, v26
-;; @002a                               trapz v32, user1
-;; @002a                               v33 = iadd.i64 v14, v29
-;; @002a                               v34 = load.i64 notrap aligned v33
-;;                                     v48 = load.i32 notrap v51
-;; @002a                               v40 = uextend.i64 v48
-;; @002a                               v42 = uadd_overflow_trap v40, v28, user1  ; v28 = 8
-;; @002a                               v44 = uadd_overflow_trap v42, v28, user1  ; v28 = 8
-;; @002a                               v45 = icmp ule v44, v26
-;; @002a                               trapz v45, user1
-;;                                     v58 = iconst.i64 1
-;; @002a                               v35 = iadd v34, v58  ; v58 = 1
-;; @002a                               v46 = iadd.i64 v14, v42
-;; @002a                               store notrap aligned v35, v46
+;; @002a                               v28 = uextend.i64 v52
+;; @002a                               v29 = iconst.i64 8
+;; @002a                               v30 = uadd_overflow_trap v28, v29, user1  ; v29 = 8
+;; @002a                               v32 = uadd_overflow_trap v30, v29, user1  ; v29 = 8
+;; @002a                               v27 = load.i64 notrap aligned readonly v0+48
+;; @002a                               v33 = icmp ule v32, v27
+;; @002a                               trapz v33, user1
+;; @002a                               v34 = iadd.i64 v14, v30
+;; @002a                               v35 = load.i64 notrap aligned v34
+;;                                     v49 = load.i32 notrap v53
+;; @002a                               v41 = uextend.i64 v49
+;; @002a                               v43 = uadd_overflow_trap v41, v29, user1  ; v29 = 8
+;; @002a                               v45 = uadd_overflow_trap v43, v29, user1  ; v29 = 8
+;; @002a                               v46 = icmp ule v45, v27
+;; @002a                               trapz v46, user1
+;;                                     v62 = iconst.i64 1
+;; @002a                               v36 = iadd v35, v62  ; v62 = 1
+;; @002a                               v47 = iadd.i64 v14, v43
+;; @002a                               store notrap aligned v36, v47
 ;; @002a                               jump block3
 ;;
 ;;                                 block3:
-;;                                     v47 = load.i32 notrap v51
-;;                                     v54 = iconst.i64 24
-;; @002a                               v19 = iadd.i64 v16, v54  ; v54 = 24
-;; @002a                               store notrap aligned little v47, v19
+;;                                     v48 = load.i32 notrap v53
+;;                                     v56 = iconst.i64 24
+;; @002a                               v19 = iadd.i64 v16, v56  ; v56 = 24
+;; @002a                               store notrap aligned little v48, v19
 ;; @002d                               jump block1
 ;;
 ;;                                 block1:
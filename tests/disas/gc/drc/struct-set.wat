@@ -72,12 +72,15 @@
 ;; }
 ;;
 ;; function u0:2(i64 vmctx, i64, i32, i32) tail {
+;;     ss0 = explicit_slot 4, align = 4
 ;;     gv0 = vmctx
 ;;     gv1 = load.i64 notrap aligned readonly gv0+8
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
 ;;     sig0 = (i64 vmctx, i32 uext) tail
-;;     fn0 = colocated u1:25 sig0
+;;     sig1 = (i64 vmctx, i32 uext) -> i64 tail
+;;     fn0 = colocated u1:28 sig0
+;;     fn1 = colocated u1:29 sig1
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: i32, v3: i32):
@@ -85,69 +88,107 @@
 ;; @004a                               v9 = uextend.i64 v2
 ;; @004a                               v10 = iconst.i64 24
 ;; @004a                               v11 = uadd_overflow_trap v9, v10, user1  ; v10 = 24
-;;                                     v78 = iconst.i64 32
-;; @004a                               v13 = uadd_overflow_trap v9, v78, user1  ; v78 = 32
+;;                                     v120 = iconst.i64 32
+;; @004a                               v13 = uadd_overflow_trap v9, v120, user1  ; v120 = 32
 ;; @004a                               v8 = load.i64 notrap aligned readonly v0+48
 ;; @004a                               v14 = icmp ule v13, v8
 ;; @004a                               trapz v14, user1
 ;; @004a                               v6 = load.i64 notrap aligned readonly v0+40
 ;; @004a                               v15 = iadd v6, v11
 ;; @004a                               v16 = load.i32 notrap aligned little v15
-;; @004a                               v17 = iconst.i32 -2
-;; @004a                               v18 = band v3, v17  ; v17 = -2
-;;                                     v73 = iconst.i32 0
-;; @004a                               v19 = icmp eq v18, v73  ; v73 = 0
-;; @004a                               brif v19, block3, block2
+;;                                     v103 = stack_addr.i64 ss0
+;;                                     store notrap v16, v103
+;;                                     v104 = iconst.i32 1
+;; @004a                               v17 = band v3, v104  ; v104 = 1
+;;                                     v105 = iconst.i32 0
+;; @004a                               v18 = icmp eq v3, v105  ; v105 = 0
+;; @004a                               v19 = uextend.i32 v18
+;; @004a                               v20 = bor v17, v19
+;; @004a                               brif v20, block3, block2
 ;;
 ;;                                 block2:
-;; @004a                               v24 = uextend.i64 v3
-;; @004a                               v52 = iconst.i64 8
-;; @004a                               v26 = uadd_overflow_trap v24, v52, user1  ; v52 = 8
-;; @004a                               v28 = uadd_overflow_trap v26, v52, user1  ; v52 = 8
-;; @004a                               v29 = icmp ule v28, v8
-;; @004a                               trapz v29, user1
-;; @004a                               v30 = iadd.i64 v6, v26
-;; @004a                               v31 = load.i64 notrap aligned v30
-;; @004a                               trapz v29, user1
-;;                                     v74 = iconst.i64 1
-;; @004a                               v32 = iadd v31, v74  ; v74 = 1
-;; @004a                               store notrap aligned v32, v30
+;; @004a                               v25 = uextend.i64 v3
+;; @004a                               v54 = iconst.i64 8
+;; @004a                               v27 = uadd_overflow_trap v25, v54, user1  ; v54 = 8
+;; @004a                               v29 = uadd_overflow_trap v27, v54, user1  ; v54 = 8
+;; @004a                               v30 = icmp ule v29, v8
+;; @004a                               trapz v30, user1
+;; @004a                               v31 = iadd.i64 v6, v27
+;; @004a                               v32 = load.i64 notrap aligned v31
+;; @004a                               trapz v30, user1
+;;                                     v112 = iconst.i64 1
+;; @004a                               v33 = iadd v32, v112  ; v112 = 1
+;; @004a                               store notrap aligned v33, v31
 ;; @004a                               jump block3
 ;;
 ;;                                 block3:
 ;; @004a                               store.i32 notrap aligned little v3, v15
-;;                                     v79 = iconst.i32 -2
-;;                                     v80 = band.i32 v16, v79  ; v79 = -2
-;;                                     v81 = iconst.i32 0
-;;                                     v82 = icmp eq v80, v81  ; v81 = 0
-;; @004a                               brif v82, block7, block4
+;;                                     v102 = load.i32 notrap v103
+;;                                     v121 = iconst.i32 1
+;;                                     v122 = band v102, v121  ; v121 = 1
+;;                                     v123 = iconst.i32 0
+;;                                     v124 = icmp eq v102, v123  ; v123 = 0
+;; @004a                               v47 = uextend.i32 v124
+;; @004a                               v48 = bor v122, v47
+;; @004a                               brif v48, block9, block4
 ;;
 ;;                                 block4:
-;; @004a                               v51 = uextend.i64 v16
-;;                                     v83 = iconst.i64 8
-;; @004a                               v53 = uadd_overflow_trap v51, v83, user1  ; v83 = 8
-;; @004a                               v55 = uadd_overflow_trap v53, v83, user1  ; v83 = 8
-;; @004a                               v56 = icmp ule v55, v8
-;; @004a                               trapz v56, user1
-;; @004a                               v57 = iadd.i64 v6, v53
-;; @004a                               v58 = load.i64 notrap aligned v57
-;;                                     v76 = iconst.i64 -1
-;; @004a                               v59 = iadd v58, v76  ; v76 = -1
-;;                                     v77 = iconst.i64 0
-;; @004a                               v60 = icmp eq v59, v77  ; v77 = 0
-;; @004a                               brif v60, block5, block6
+;; @004a                               v53 = uextend.i64 v102
+;;                                     v125 = iconst.i64 8
+;; @004a                               v55 = uadd_overflow_trap v53, v125, user1  ; v125 = 8
+;; @004a                               v57 = uadd_overflow_trap v55, v125, user1  ; v125 = 8
+;; @004a                               v58 = icmp ule v57, v8
+;; @004a                               trapz v58, user1
+;; @004a                               v59 = iadd.i64 v6, v55
+;; @004a                               v60 = load.i64 notrap aligned v59
+;;                                     v126 = iconst.i64 1
+;; @004a                               v61 = icmp eq v60, v126  ; v126 = 1
+;; @004a                               brif v61, block5, block6
 ;;
 ;;                                 block5 cold:
-;; @004a                               call fn0(v0, v16)
-;; @004a                               jump block7
+;;                                     v95 = load.i32 notrap v103
+;; @004a                               call fn0(v0, v95)
+;; @004a                               jump block9
 ;;
 ;;                                 block6:
-;; @004a                               trapz.i8 v56, user1
-;;                                     v84 = iadd.i64 v58, v76  ; v76 = -1
-;; @004a                               store notrap aligned v84, v57
-;; @004a                               jump block7
+;; @004a                               v64 = load.i64 notrap aligned readonly v0+56
+;; @004a                               v65 = load.i64 notrap aligned v64
+;; @004a                               v66 = load.i64 notrap aligned v64+8
+;; @004a                               v67 = icmp eq v65, v66
+;; @004a                               brif v67, block8, block7
 ;;
 ;;                                 block7:
+;;                                     v99 = load.i32 notrap v103
+;; @004a                               store notrap aligned v99, v65
+;;                                     v115 = iconst.i64 4
+;; @004a                               v68 = iadd.i64 v65, v115  ; v115 = 4
+;; @004a                               store notrap aligned v68, v64
+;; @004a                               jump block9
+;;
+;;                                 block8 cold:
+;;                                     v98 = load.i32 notrap v103
+;; @004a                               v70 = call fn1(v0, v98), stack_map=[i32 @ ss0+0]
+;;                                     v97 = load.i32 notrap v103
+;; @004a                               v75 = uextend.i64 v97
+;; @004a                               v77 = uadd_overflow_trap v75, v125, user1  ; v125 = 8
+;; @004a                               v79 = uadd_overflow_trap v77, v125, user1  ; v125 = 8
+;; @004a                               v80 = icmp ule v79, v8
+;; @004a                               trapz v80, user1
+;; @004a                               v81 = iadd.i64 v6, v77
+;; @004a                               v82 = load.i64 notrap aligned v81
+;;                                     v96 = load.i32 notrap v103
+;; @004a                               v88 = uextend.i64 v96
+;; @004a                               v90 = uadd_overflow_trap v88, v125, user1  ; v125 = 8
+;; @004a                               v92 = uadd_overflow_trap v90, v125, user1  ; v125 = 8
+;; @004a                               v93 = icmp ule v92, v8
+;; @004a                               trapz v93, user1
+;;                                     v118 = iconst.i64 -1
+;; @004a                               v83 = iadd v82, v118  ; v118 = -1
+;; @004a                               v94 = iadd.i64 v6, v90
+;; @004a                               store notrap aligned v83, v94
+;; @004a                               jump block9
+;;
+;;                                 block9:
 ;; @004e                               jump block1
 ;;
 ;;                                 block1:
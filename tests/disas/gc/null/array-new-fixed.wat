@@ -14,54 +14,71 @@
 ;;     gv1 = load.i64 notrap aligned readonly gv0+8
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
+;;     sig0 = (i64 vmctx, i32 uext, i32 uext, i32 uext, i32 uext) -> i64 tail
+;;     fn0 = colocated u1:30 sig0
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: i64, v3: i64, v4: i64):
-;;                                     v58 = iconst.i64 0
-;; @0025                               trapnz v58, user18  ; v58 = 0
+;;                                     v63 = iconst.i64 0
+;; @0025                               trapnz v63, user18  ; v63 = 0
 ;; @0025                               v6 = iconst.i32 16
-;;                                     v59 = iconst.i32 24
-;; @0025                               v12 = uadd_overflow_trap v6, v59, user18  ; v6 = 16, v59 = 24
-;; @0025                               v14 = iconst.i32 -134217728
-;; @0025                               v15 = band v12, v14  ; v14 = -134217728
-;; @0025                               trapnz v15, user18
-;; @0025                               v17 = load.i64 notrap aligned readonly v0+56
-;; @0025                               v18 = load.i32 notrap aligned v17
-;;                                     v60 = iconst.i32 7
-;; @0025                               v21 = uadd_overflow_trap v18, v60, user18  ; v60 = 7
-;;                                     v67 = iconst.i32 -8
-;; @0025                               v23 = band v21, v67  ; v67 = -8
-;; @0025                               v24 = uadd_overflow_trap v23, v12, user18
-;; @0025                               v25 = uextend.i64 v24
-;; @0025                               v29 = load.i64 notrap aligned readonly v0+48
-;; @0025                               v30 = icmp ule v25, v29
-;; @0025                               trapz v30, user18
-;; @0025                               v33 = iconst.i32 -1476395008
-;;                                     v68 = bor v12, v33  ; v33 = -1476395008
-;; @0025                               v27 = load.i64 notrap aligned readonly v0+40
-;; @0025                               v31 = uextend.i64 v23
-;; @0025                               v32 = iadd v27, v31
-;; @0025                               store notrap aligned v68, v32
-;; @0025                               v36 = load.i64 notrap aligned readonly v0+80
-;; @0025                               v37 = load.i32 notrap aligned readonly v36
-;; @0025                               store notrap aligned v37, v32+4
-;; @0025                               store notrap aligned v24, v17
+;;                                     v64 = iconst.i32 24
+;; @0025                               v12 = uadd_overflow_trap v6, v64, user18  ; v6 = 16, v64 = 24
+;; @0025                               v13 = iconst.i32 -134217728
+;; @0025                               v14 = band v12, v13  ; v13 = -134217728
+;; @0025                               trapnz v14, user18
+;; @0025                               v16 = load.i64 notrap aligned readonly v0+56
+;; @0025                               v17 = load.i32 notrap aligned v16
+;; @0025                               v18 = iconst.i32 7
+;; @0025                               v19 = uadd_overflow_trap v17, v18, user18  ; v18 = 7
+;;                                     v65 = iconst.i32 -8
+;; @0025                               v21 = band v19, v65  ; v65 = -8
+;; @0025                               v22 = uadd_overflow_trap v21, v12, user18
+;; @0025                               v23 = load.i32 notrap aligned v16+4
+;; @0025                               v24 = icmp ule v22, v23
+;; @0025                               brif v24, block2, block3
+;;
+;;                                 block2:
+;;                                     v98 = iconst.i32 -1476395008
+;;                                     v96 = bor.i32 v12, v98  ; v98 = -1476395008
+;; @0025                               v26 = load.i64 notrap aligned readonly v0+40
+;;                                     v99 = band.i32 v19, v65  ; v65 = -8
+;;                                     v100 = uextend.i64 v99
+;; @0025                               v29 = iadd v26, v100
+;; @0025                               store notrap aligned v96, v29
+;; @0025                               v33 = load.i64 notrap aligned readonly v0+80
+;; @0025                               v34 = load.i32 notrap aligned readonly v33
+;; @0025                               store notrap aligned v34, v29+4
+;; @0025                               store.i32 notrap aligned v22, v16
+;; @0025                               jump block4(v99)
+;;
+;;                                 block3 cold:
+;; @0025                               v30 = iconst.i32 -1476395008
+;; @0025                               v37 = iconst.i32 0
+;; @0025                               v38 = iconst.i32 8
+;; @0025                               v39 = call fn0(v0, v30, v37, v12, v38)  ; v30 = -1476395008, v37 = 0, v38 = 8
+;; @0025                               v40 = ireduce.i32 v39
+;; @0025                               jump block4(v40)
+;;
+;;                                 block4(v27: i32):
 ;; @0025                               v7 = iconst.i32 3
-;;                                     v46 = iconst.i64 8
-;; @0025                               v38 = iadd v32, v46  ; v46 = 8
-;; @0025                               store notrap aligned v7, v38  ; v7 = 3
-;;                                     v71 = iconst.i64 16
-;;                                     v77 = iadd v32, v71  ; v71 = 16
-;; @0025                               store notrap aligned little v2, v77
-;;                                     v50 = iconst.i64 24
-;;                                     v84 = iadd v32, v50  ; v50 = 24
-;; @0025                               store notrap aligned little v3, v84
-;;                                     v47 = iconst.i64 32
-;;                                     v91 = iadd v32, v47  ; v47 = 32
-;; @0025                               store notrap aligned little v4, v91
-;; @0029                               jump block1
+;;                                     v101 = load.i64 notrap aligned readonly v0+40
+;; @0025                               v41 = uextend.i64 v27
+;; @0025                               v42 = iadd v101, v41
+;;                                     v51 = iconst.i64 8
+;; @0025                               v43 = iadd v42, v51  ; v51 = 8
+;; @0025                               store notrap aligned v7, v43  ; v7 = 3
+;;                                     v67 = iconst.i64 16
+;;                                     v73 = iadd v42, v67  ; v67 = 16
+;; @0025                               store.i64 notrap aligned little v2, v73
+;;                                     v55 = iconst.i64 24
+;;                                     v80 = iadd v42, v55  ; v55 = 24
+;; @0025                               store.i64 notrap aligned little v3, v80
+;;                                     v52 = iconst.i64 32
+;;                                     v87 = iadd v42, v52  ; v52 = 32
+;; @0025                               store.i64 notrap aligned little v4, v87
+;; @0029                               jump block1(v27)
 ;;
-;;                                 block1:
-;;                                     v100 = band.i32 v21, v67  ; v67 = -8
-;; @0029                               return v100
+;;                                 block1(v5: i32):
+;; @0029                               return v5
 ;; }
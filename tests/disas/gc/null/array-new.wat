@@ -14,66 +14,83 @@
 ;;     gv1 = load.i64 notrap aligned readonly gv0+8
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
+;;     sig0 = (i64 vmctx, i32 uext, i32 uext, i32 uext, i32 uext) -> i64 tail
+;;     fn0 = colocated u1:30 sig0
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: i64, v3: i32):
 ;; @0022                               v6 = uextend.i64 v3
-;;                                     v50 = iconst.i64 3
-;;                                     v51 = ishl v6, v50  ; v50 = 3
-;;                                     v48 = iconst.i64 32
-;; @0022                               v8 = ushr v51, v48  ; v48 = 32
+;;                                     v55 = iconst.i64 3
+;;                                     v56 = ishl v6, v55  ; v55 = 3
+;;                                     v53 = iconst.i64 32
+;; @0022                               v8 = ushr v56, v53  ; v53 = 32
 ;; @0022                               trapnz v8, user18
 ;; @0022                               v5 = iconst.i32 16
-;;                                     v57 = iconst.i32 3
-;;                                     v58 = ishl v3, v57  ; v57 = 3
-;; @0022                               v10 = uadd_overflow_trap v5, v58, user18  ; v5 = 16
-;; @0022                               v12 = iconst.i32 -134217728
-;; @0022                               v13 = band v10, v12  ; v12 = -134217728
-;; @0022                               trapnz v13, user18
-;; @0022                               v15 = load.i64 notrap aligned readonly v0+56
-;; @0022                               v16 = load.i32 notrap aligned v15
-;;                                     v61 = iconst.i32 7
-;; @0022                               v19 = uadd_overflow_trap v16, v61, user18  ; v61 = 7
-;;                                     v68 = iconst.i32 -8
-;; @0022                               v21 = band v19, v68  ; v68 = -8
-;; @0022                               v22 = uadd_overflow_trap v21, v10, user18
-;; @0022                               v23 = uextend.i64 v22
-;; @0022                               v27 = load.i64 notrap aligned readonly v0+48
-;; @0022                               v28 = icmp ule v23, v27
-;; @0022                               trapz v28, user18
-;; @0022                               v31 = iconst.i32 -1476395008
-;;                                     v69 = bor v10, v31  ; v31 = -1476395008
-;; @0022                               v25 = load.i64 notrap aligned readonly v0+40
-;; @0022                               v29 = uextend.i64 v21
-;; @0022                               v30 = iadd v25, v29
-;; @0022                               store notrap aligned v69, v30
-;; @0022                               v34 = load.i64 notrap aligned readonly v0+80
-;; @0022                               v35 = load.i32 notrap aligned readonly v34
-;; @0022                               store notrap aligned v35, v30+4
-;; @0022                               store notrap aligned v22, v15
-;;                                     v47 = iconst.i64 8
-;; @0022                               v36 = iadd v30, v47  ; v47 = 8
-;; @0022                               store notrap aligned v3, v36
-;;                                     v72 = iconst.i64 16
-;;                                     v78 = iadd v30, v72  ; v72 = 16
-;; @0022                               v42 = uextend.i64 v10
-;; @0022                               v43 = iadd v30, v42
-;; @0022                               jump block2(v78)
+;;                                     v62 = iconst.i32 3
+;;                                     v63 = ishl v3, v62  ; v62 = 3
+;; @0022                               v10 = uadd_overflow_trap v5, v63, user18  ; v5 = 16
+;; @0022                               v11 = iconst.i32 -134217728
+;; @0022                               v12 = band v10, v11  ; v11 = -134217728
+;; @0022                               trapnz v12, user18
+;; @0022                               v14 = load.i64 notrap aligned readonly v0+56
+;; @0022                               v15 = load.i32 notrap aligned v14
+;; @0022                               v16 = iconst.i32 7
+;; @0022                               v17 = uadd_overflow_trap v15, v16, user18  ; v16 = 7
+;;                                     v66 = iconst.i32 -8
+;; @0022                               v19 = band v17, v66  ; v66 = -8
+;; @0022                               v20 = uadd_overflow_trap v19, v10, user18
+;; @0022                               v21 = load.i32 notrap aligned v14+4
+;; @0022                               v22 = icmp ule v20, v21
+;; @0022                               brif v22, block2, block3
 ;;
-;;                                 block2(v44: i64):
-;; @0022                               v45 = icmp eq v44, v43
-;; @0022                               brif v45, block4, block3
+;;                                 block2:
+;;                                     v88 = iconst.i32 -1476395008
+;;                                     v86 = bor.i32 v10, v88  ; v88 = -1476395008
+;; @0022                               v24 = load.i64 notrap aligned readonly v0+40
+;;                                     v89 = band.i32 v17, v66  ; v66 = -8
+;;                                     v90 = uextend.i64 v89
+;; @0022                               v27 = iadd v24, v90
+;; @0022                               store notrap aligned v86, v27
+;; @0022                               v31 = load.i64 notrap aligned readonly v0+80
+;; @0022                               v32 = load.i32 notrap aligned readonly v31
+;; @0022                               store notrap aligned v32, v27+4
+;; @0022                               store.i32 notrap aligned v20, v14
+;; @0022                               jump block4(v89)
 ;;
-;;                                 block3:
-;; @0022                               store.i64 notrap aligned little v2, v44
-;;                                     v90 = iconst.i64 8
-;;                                     v91 = iadd.i64 v44, v90  ; v90 = 8
-;; @0022                               jump block2(v91)
+;;                                 block3 cold:
+;; @0022                               v28 = iconst.i32 -1476395008
+;; @0022                               v35 = iconst.i32 0
+;;                                     v60 = iconst.i32 8
+;; @0022                               v37 = call fn0(v0, v28, v35, v10, v60)  ; v28 = -1476395008, v35 = 0, v60 = 8
+;; @0022                               v38 = ireduce.i32 v37
+;; @0022                               jump block4(v38)
 ;;
-;;                                 block4:
-;; @0025                               jump block1
+;;                                 block4(v25: i32):
+;;                                     v91 = load.i64 notrap aligned readonly v0+40
+;; @0022                               v39 = uextend.i64 v25
+;; @0022                               v40 = iadd v91, v39
+;;                                     v52 = iconst.i64 8
+;; @0022                               v41 = iadd v40, v52  ; v52 = 8
+;; @0022                               store.i32 notrap aligned v3, v41
+;;                                     v68 = iconst.i64 16
+;;                                     v74 = iadd v40, v68  ; v68 = 16
+;; @0022                               v47 = uextend.i64 v10
+;; @0022                               v48 = iadd v40, v47
+;; @0022                               jump block5(v74)
 ;;
-;;                                 block1:
-;;                                     v92 = band.i32 v19, v68  ; v68 = -8
-;; @0025                               return v92
+;;                                 block5(v49: i64):
+;; @0022                               v50 = icmp eq v49, v48
+;; @0022                               brif v50, block7, block6
+;;
+;;                                 block6:
+;; @0022                               store.i64 notrap aligned little v2, v49
+;;                                     v92 = iconst.i64 8
+;;                                     v93 = iadd.i64 v49, v92  ; v92 = 8
+;; @0022                               jump block5(v93)
+;;
+;;                                 block7:
+;; @0025                               jump block1(v25)
+;;
+;;                                 block1(v4: i32):
+;; @0025                               return v4
 ;; }
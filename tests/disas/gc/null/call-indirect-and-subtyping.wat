@@ -26,7 +26,7 @@
 ;;     sig1 = (i64 vmctx, i32 uext, i64) -> i64 tail
 ;;     sig2 = (i64 vmctx, i32 uext, i32 uext) -> i32 uext tail
 ;;     fn0 = colocated u1:9 sig1
-;;     fn1 = colocated u1:35 sig2
+;;     fn1 = colocated u1:38 sig2
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: i32):
@@ -14,42 +14,60 @@
 ;;     gv1 = load.i64 notrap aligned readonly gv0+8
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
-;;     sig0 = (i64 vmctx, i64) -> i64 tail
-;;     fn0 = colocated u1:28 sig0
+;;     sig0 = (i64 vmctx, i32 uext, i32 uext, i32 uext, i32 uext) -> i64 tail
+;;     sig1 = (i64 vmctx, i64) -> i64 tail
+;;     fn0 = colocated u1:30 sig0
+;;     fn1 = colocated u1:31 sig1
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: i64):
-;;                                     v35 = iconst.i32 0
-;; @0020                               trapnz v35, user18  ; v35 = 0
-;; @0020                               v9 = load.i64 notrap aligned readonly v0+56
-;; @0020                               v10 = load.i32 notrap aligned v9
-;;                                     v42 = iconst.i32 7
-;; @0020                               v13 = uadd_overflow_trap v10, v42, user18  ; v42 = 7
-;;                                     v49 = iconst.i32 -8
-;; @0020                               v15 = band v13, v49  ; v49 = -8
+;;                                     v40 = iconst.i32 0
+;; @0020                               trapnz v40, user18  ; v40 = 0
+;; @0020                               v8 = load.i64 notrap aligned readonly v0+56
+;; @0020                               v9 = load.i32 notrap aligned v8
+;; @0020                               v10 = iconst.i32 7
+;; @0020                               v11 = uadd_overflow_trap v9, v10, user18  ; v10 = 7
+;;                                     v47 = iconst.i32 -8
+;; @0020                               v13 = band v11, v47  ; v47 = -8
 ;; @0020                               v4 = iconst.i32 16
-;; @0020                               v16 = uadd_overflow_trap v15, v4, user18  ; v4 = 16
-;; @0020                               v17 = uextend.i64 v16
-;; @0020                               v21 = load.i64 notrap aligned readonly v0+48
-;; @0020                               v22 = icmp ule v17, v21
-;; @0020                               trapz v22, user18
-;;                                     v50 = iconst.i32 -1342177264
-;; @0020                               v19 = load.i64 notrap aligned readonly v0+40
-;; @0020                               v23 = uextend.i64 v15
-;; @0020                               v24 = iadd v19, v23
-;; @0020                               store notrap aligned v50, v24  ; v50 = -1342177264
-;; @0020                               v28 = load.i64 notrap aligned readonly v0+80
-;; @0020                               v29 = load.i32 notrap aligned readonly v28
-;; @0020                               store notrap aligned v29, v24+4
-;; @0020                               store notrap aligned v16, v9
-;; @0020                               v32 = call fn0(v0, v2)
-;; @0020                               v33 = ireduce.i32 v32
-;;                                     v34 = iconst.i64 8
-;; @0020                               v30 = iadd v24, v34  ; v34 = 8
-;; @0020                               store notrap aligned little v33, v30
-;; @0023                               jump block1
+;; @0020                               v14 = uadd_overflow_trap v13, v4, user18  ; v4 = 16
+;; @0020                               v15 = load.i32 notrap aligned v8+4
+;; @0020                               v16 = icmp ule v14, v15
+;; @0020                               brif v16, block2, block3
 ;;
-;;                                 block1:
-;;                                     v57 = band.i32 v13, v49  ; v49 = -8
-;; @0023                               return v57
+;;                                 block2:
+;;                                     v48 = iconst.i32 -1342177264
+;; @0020                               v18 = load.i64 notrap aligned readonly v0+40
+;;                                     v57 = band.i32 v11, v47  ; v47 = -8
+;;                                     v58 = uextend.i64 v57
+;; @0020                               v21 = iadd v18, v58
+;; @0020                               store notrap aligned v48, v21  ; v48 = -1342177264
+;; @0020                               v25 = load.i64 notrap aligned readonly v0+80
+;; @0020                               v26 = load.i32 notrap aligned readonly v25
+;; @0020                               store notrap aligned v26, v21+4
+;; @0020                               store.i32 notrap aligned v14, v8
+;; @0020                               jump block4(v57)
+;;
+;;                                 block3 cold:
+;; @0020                               v22 = iconst.i32 -1342177280
+;;                                     v55 = iconst.i32 0
+;;                                     v56 = iconst.i32 16
+;; @0020                               v30 = iconst.i32 8
+;; @0020                               v31 = call fn0(v0, v22, v55, v56, v30)  ; v22 = -1342177280, v55 = 0, v56 = 16, v30 = 8
+;; @0020                               v32 = ireduce.i32 v31
+;; @0020                               jump block4(v32)
+;;
+;;                                 block4(v19: i32):
+;; @0020                               v37 = call fn1(v0, v2)
+;; @0020                               v38 = ireduce.i32 v37
+;;                                     v59 = load.i64 notrap aligned readonly v0+40
+;; @0020                               v33 = uextend.i64 v19
+;; @0020                               v34 = iadd v59, v33
+;;                                     v39 = iconst.i64 8
+;; @0020                               v35 = iadd v34, v39  ; v39 = 8
+;; @0020                               store notrap aligned little v38, v35
+;; @0023                               jump block1(v19)
+;;
+;;                                 block1(v3: i32):
+;; @0023                               return v3
 ;; }
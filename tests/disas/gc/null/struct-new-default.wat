@@ -16,45 +16,64 @@
 ;;     gv1 = load.i64 notrap aligned readonly gv0+8
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
+;;     sig0 = (i64 vmctx, i32 uext, i32 uext, i32 uext, i32 uext) -> i64 tail
+;;     fn0 = colocated u1:30 sig0
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64):
 ;; @0021                               v4 = iconst.i32 0
 ;; @0021                               trapnz v4, user18  ; v4 = 0
-;; @0021                               v11 = load.i64 notrap aligned readonly v0+56
-;; @0021                               v12 = load.i32 notrap aligned v11
-;;                                     v44 = iconst.i32 7
-;; @0021                               v15 = uadd_overflow_trap v12, v44, user18  ; v44 = 7
-;;                                     v51 = iconst.i32 -8
-;; @0021                               v17 = band v15, v51  ; v51 = -8
+;; @0021                               v10 = load.i64 notrap aligned readonly v0+56
+;; @0021                               v11 = load.i32 notrap aligned v10
+;; @0021                               v12 = iconst.i32 7
+;; @0021                               v13 = uadd_overflow_trap v11, v12, user18  ; v12 = 7
+;;                                     v49 = iconst.i32 -8
+;; @0021                               v15 = band v13, v49  ; v49 = -8
 ;; @0021                               v6 = iconst.i32 24
-;; @0021                               v18 = uadd_overflow_trap v17, v6, user18  ; v6 = 24
-;; @0021                               v19 = uextend.i64 v18
-;; @0021                               v23 = load.i64 notrap aligned readonly v0+48
-;; @0021                               v24 = icmp ule v19, v23
-;; @0021                               trapz v24, user18
-;;                                     v52 = iconst.i32 -1342177256
-;; @0021                               v21 = load.i64 notrap aligned readonly v0+40
-;; @0021                               v25 = uextend.i64 v17
-;; @0021                               v26 = iadd v21, v25
-;; @0021                               store notrap aligned v52, v26  ; v52 = -1342177256
-;; @0021                               v30 = load.i64 notrap aligned readonly v0+80
-;; @0021                               v31 = load.i32 notrap aligned readonly v30
-;; @0021                               store notrap aligned v31, v26+4
-;; @0021                               store notrap aligned v18, v11
+;; @0021                               v16 = uadd_overflow_trap v15, v6, user18  ; v6 = 24
+;; @0021                               v17 = load.i32 notrap aligned v10+4
+;; @0021                               v18 = icmp ule v16, v17
+;; @0021                               brif v18, block2, block3
+;;
+;;                                 block2:
+;;                                     v50 = iconst.i32 -1342177256
+;; @0021                               v20 = load.i64 notrap aligned readonly v0+40
+;;                                     v59 = band.i32 v13, v49  ; v49 = -8
+;;                                     v60 = uextend.i64 v59
+;; @0021                               v23 = iadd v20, v60
+;; @0021                               store notrap aligned v50, v23  ; v50 = -1342177256
+;; @0021                               v27 = load.i64 notrap aligned readonly v0+80
+;; @0021                               v28 = load.i32 notrap aligned readonly v27
+;; @0021                               store notrap aligned v28, v23+4
+;; @0021                               store.i32 notrap aligned v16, v10
+;; @0021                               jump block4(v59)
+;;
+;;                                 block3 cold:
+;; @0021                               v24 = iconst.i32 -1342177280
+;;                                     v57 = iconst.i32 0
+;;                                     v58 = iconst.i32 24
+;; @0021                               v32 = iconst.i32 8
+;; @0021                               v33 = call fn0(v0, v24, v57, v58, v32)  ; v24 = -1342177280, v57 = 0, v58 = 24, v32 = 8
+;; @0021                               v34 = ireduce.i32 v33
+;; @0021                               jump block4(v34)
+;;
+;;                                 block4(v21: i32):
 ;; @0021                               v3 = f32const 0.0
-;;                                     v35 = iconst.i64 8
-;; @0021                               v32 = iadd v26, v35  ; v35 = 8
-;; @0021                               store notrap aligned little v3, v32  ; v3 = 0.0
-;;                                     v36 = iconst.i64 12
-;; @0021                               v33 = iadd v26, v36  ; v36 = 12
-;; @0021                               istore8 notrap aligned little v4, v33  ; v4 = 0
-;;                                     v37 = iconst.i64 16
-;; @0021                               v34 = iadd v26, v37  ; v37 = 16
-;; @0021                               store notrap aligned little v4, v34  ; v4 = 0
-;; @0024                               jump block1
+;;                                     v61 = load.i64 notrap aligned readonly v0+40
+;; @0021                               v35 = uextend.i64 v21
+;; @0021                               v36 = iadd v61, v35
+;;                                     v40 = iconst.i64 8
+;; @0021                               v37 = iadd v36, v40  ; v40 = 8
+;; @0021                               store notrap aligned little v3, v37  ; v3 = 0.0
+;;                                     v62 = iconst.i32 0
+;;                                     v41 = iconst.i64 12
+;; @0021                               v38 = iadd v36, v41  ; v41 = 12
+;; @0021                               istore8 notrap aligned little v62, v38  ; v62 = 0
+;;                                     v42 = iconst.i64 16
+;; @0021                               v39 = iadd v36, v42  ; v42 = 16
+;; @0021                               store notrap aligned little v62, v39  ; v62 = 0
+;; @0024                               jump block1(v21)
 ;;
-;;                                 block1:
-;;                                     v59 = band.i32 v15, v51  ; v51 = -8
-;; @0024                               return v59
+;;                                 block1(v2: i32):
+;; @0024                               return v2
 ;; }
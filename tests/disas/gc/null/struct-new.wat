@@ -12,48 +12,70 @@
   )
 )
 ;; function u0:0(i64 vmctx, i64, f32, i32, i32) -> i32 tail {
+;;     ss0 = explicit_slot 4, align = 4
 ;;     gv0 = vmctx
 ;;     gv1 = load.i64 notrap aligned readonly gv0+8
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
+;;     sig0 = (i64 vmctx, i32 uext, i32 uext, i32 uext, i32 uext) -> i64 tail
+;;     fn0 = colocated u1:30 sig0
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: f32, v3: i32, v4: i32):
-;;                                     v38 = iconst.i32 0
-;; @002a                               trapnz v38, user18  ; v38 = 0
-;; @002a                               v11 = load.i64 notrap aligned readonly v0+56
-;; @002a                               v12 = load.i32 notrap aligned v11
-;;                                     v45 = iconst.i32 7
-;; @002a                               v15 = uadd_overflow_trap v12, v45, user18  ; v45 = 7
-;;                                     v52 = iconst.i32 -8
-;; @002a                               v17 = band v15, v52  ; v52 = -8
+;;                                     v41 = stack_addr.i64 ss0
+;;                                     store notrap v4, v41
+;;                                     v46 = iconst.i32 0
+;; @002a                               trapnz v46, user18  ; v46 = 0
+;; @002a                               v10 = load.i64 notrap aligned readonly v0+56
+;; @002a                               v11 = load.i32 notrap aligned v10
+;; @002a                               v12 = iconst.i32 7
+;; @002a                               v13 = uadd_overflow_trap v11, v12, user18  ; v12 = 7
+;;                                     v53 = iconst.i32 -8
+;; @002a                               v15 = band v13, v53  ; v53 = -8
 ;; @002a                               v6 = iconst.i32 24
-;; @002a                               v18 = uadd_overflow_trap v17, v6, user18  ; v6 = 24
-;; @002a                               v19 = uextend.i64 v18
-;; @002a                               v23 = load.i64 notrap aligned readonly v0+48
-;; @002a                               v24 = icmp ule v19, v23
-;; @002a                               trapz v24, user18
-;;                                     v53 = iconst.i32 -1342177256
-;; @002a                               v21 = load.i64 notrap aligned readonly v0+40
-;; @002a                               v25 = uextend.i64 v17
-;; @002a                               v26 = iadd v21, v25
-;; @002a                               store notrap aligned v53, v26  ; v53 = -1342177256
-;; @002a                               v30 = load.i64 notrap aligned readonly v0+80
-;; @002a                               v31 = load.i32 notrap aligned readonly v30
-;; @002a                               store notrap aligned v31, v26+4
-;; @002a                               store notrap aligned v18, v11
-;;                                     v35 = iconst.i64 8
-;; @002a                               v32 = iadd v26, v35  ; v35 = 8
-;; @002a                               store notrap aligned little v2, v32
-;;                                     v36 = iconst.i64 12
-;; @002a                               v33 = iadd v26, v36  ; v36 = 12
-;; @002a                               istore8 notrap aligned little v3, v33
-;;                                     v37 = iconst.i64 16
-;; @002a                               v34 = iadd v26, v37  ; v37 = 16
-;; @002a                               store notrap aligned little v4, v34
-;; @002d                               jump block1
+;; @002a                               v16 = uadd_overflow_trap v15, v6, user18  ; v6 = 24
+;; @002a                               v17 = load.i32 notrap aligned v10+4
+;; @002a                               v18 = icmp ule v16, v17
+;; @002a                               brif v18, block2, block3
 ;;
-;;                                 block1:
-;;                                     v60 = band.i32 v15, v52  ; v52 = -8
-;; @002d                               return v60
+;;                                 block2:
+;;                                     v54 = iconst.i32 -1342177256
+;; @002a                               v20 = load.i64 notrap aligned readonly v0+40
+;;                                     v63 = band.i32 v13, v53  ; v53 = -8
+;;                                     v64 = uextend.i64 v63
+;; @002a                               v23 = iadd v20, v64
+;; @002a                               store notrap aligned v54, v23  ; v54 = -1342177256
+;; @002a                               v27 = load.i64 notrap aligned readonly v0+80
+;; @002a                               v28 = load.i32 notrap aligned readonly v27
+;; @002a                               store notrap aligned v28, v23+4
+;; @002a                               store.i32 notrap aligned v16, v10
+;; @002a                               jump block4(v63)
+;;
+;;                                 block3 cold:
+;; @002a                               v24 = iconst.i32 -1342177280
+;;                                     v61 = iconst.i32 0
+;;                                     v62 = iconst.i32 24
+;; @002a                               v32 = iconst.i32 8
+;; @002a                               v33 = call fn0(v0, v24, v61, v62, v32), stack_map=[i32 @ ss0+0]  ; v24 = -1342177280, v61 = 0, v62 = 24, v32 = 8
+;; @002a                               v34 = ireduce.i32 v33
+;; @002a                               jump block4(v34)
+;;
+;;                                 block4(v21: i32):
+;;                                     v65 = load.i64 notrap aligned readonly v0+40
+;; @002a                               v35 = uextend.i64 v21
+;; @002a                               v36 = iadd v65, v35
+;;                                     v42 = iconst.i64 8
+;; @002a                               v37 = iadd v36, v42  ; v42 = 8
+;; @002a                               store.f32 notrap aligned little v2, v37
+;;                                     v43 = iconst.i64 12
+;; @002a                               v38 = iadd v36, v43  ; v43 = 12
+;; @002a                               istore8.i32 notrap aligned little v3, v38
+;;                                     v40 = load.i32 notrap v41
+;;                                     v44 = iconst.i64 16
+;; @002a                               v39 = iadd v36, v44  ; v44 = 16
+;; @002a                               store notrap aligned little v40, v39
+;; @002d                               jump block1(v21)
+;;
+;;                                 block1(v5: i32):
+;; @002d                               return v5
 ;; }
@@ -17,13 +17,16 @@
     local.get 1
     table.set 0))
 ;; function u0:0(i64 vmctx, i64, i32) tail {
+;;     ss0 = explicit_slot 4, align = 4
 ;;     gv0 = vmctx
 ;;     gv1 = load.i64 notrap aligned readonly gv0+8
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
 ;;     gv4 = load.i64 notrap aligned readonly gv3+88
 ;;     sig0 = (i64 vmctx, i32 uext) tail
-;;     fn0 = colocated u1:25 sig0
+;;     sig1 = (i64 vmctx, i32 uext) -> i64 tail
+;;     fn0 = colocated u1:28 sig0
+;;     fn1 = colocated u1:29 sig1
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: i32):
@@ -32,14 +35,16 @@
 ;; @0056                               v5 = icmp uge v3, v4  ; v3 = 0, v4 = 7
 ;; @0056                               v6 = uextend.i64 v3  ; v3 = 0
 ;; @0056                               v7 = load.i64 notrap aligned readonly v0+88
-;;                                     v66 = iconst.i64 2
-;; @0056                               v8 = ishl v6, v66  ; v66 = 2
+;;                                     v93 = iconst.i64 2
+;; @0056                               v8 = ishl v6, v93  ; v93 = 2
 ;; @0056                               v9 = iadd v7, v8
 ;; @0056                               v10 = iconst.i64 0
 ;; @0056                               v11 = select_spectre_guard v5, v10, v9  ; v10 = 0
 ;; @0056                               v12 = load.i32 user5 aligned table v11
-;;                                     v67 = iconst.i32 0
-;; @0056                               v13 = icmp eq v2, v67  ; v67 = 0
+;;                                     v94 = stack_addr.i64 ss0
+;;                                     store notrap v12, v94
+;;                                     v95 = iconst.i32 0
+;; @0056                               v13 = icmp eq v2, v95  ; v95 = 0
 ;; @0056                               brif v13, block3, block2
 ;;
 ;;                                 block2:
@@ -54,8 +59,8 @@
 ;; @0056                               trapz v23, user1
 ;; @0056                               v24 = iadd v15, v20
 ;; @0056                               v25 = load.i64 notrap aligned v24
-;;                                     v68 = iconst.i64 1
-;; @0056                               v26 = iadd v25, v68  ; v68 = 1
+;;                                     v96 = iconst.i64 1
+;; @0056                               v26 = iadd v25, v96  ; v96 = 1
 ;; @0056                               v28 = load.i64 notrap aligned readonly v0+40
 ;; @0056                               v30 = load.i64 notrap aligned readonly v0+48
 ;; @0056                               v31 = uextend.i64 v2
@@ -71,14 +76,18 @@
 ;;
 ;;                                 block3:
 ;; @0056                               store.i32 user5 aligned table v2, v11
-;;                                     v69 = iconst.i32 0
-;; @0056                               v38 = icmp.i32 eq v12, v69  ; v69 = 0
-;; @0056                               brif v38, block7, block4
+;;                                     v97 = stack_addr.i64 ss0
+;;                                     v91 = load.i32 notrap v97
+;;                                     v98 = iconst.i32 0
+;; @0056                               v38 = icmp eq v91, v98  ; v98 = 0
+;; @0056                               brif v38, block9, block4
 ;;
 ;;                                 block4:
 ;; @0056                               v40 = load.i64 notrap aligned readonly v0+40
 ;; @0056                               v42 = load.i64 notrap aligned readonly v0+48
-;; @0056                               v43 = uextend.i64 v12
+;;                                     v99 = stack_addr.i64 ss0
+;;                                     v90 = load.i32 notrap v99
+;; @0056                               v43 = uextend.i64 v90
 ;; @0056                               v44 = iconst.i64 8
 ;; @0056                               v45 = uadd_overflow_trap v43, v44, user1  ; v44 = 8
 ;; @0056                               v46 = iconst.i64 8
@@ -87,31 +96,67 @@
 ;; @0056                               trapz v48, user1
 ;; @0056                               v49 = iadd v40, v45
 ;; @0056                               v50 = load.i64 notrap aligned v49
-;;                                     v70 = iconst.i64 -1
-;; @0056                               v51 = iadd v50, v70  ; v70 = -1
-;;                                     v71 = iconst.i64 0
-;; @0056                               v52 = icmp eq v51, v71  ; v71 = 0
-;; @0056                               brif v52, block5, block6
+;;                                     v100 = iconst.i64 1
+;; @0056                               v51 = icmp eq v50, v100  ; v100 = 1
+;; @0056                               brif v51, block5, block6
 ;;
 ;;                                 block5 cold:
-;; @0056                               call fn0(v0, v12)
-;; @0056                               jump block7
+;;                                     v101 = stack_addr.i64 ss0
+;;                                     v85 = load.i32 notrap v101
+;; @0056                               call fn0(v0, v85)
+;; @0056                               jump block9
 ;;
 ;;                                 block6:
-;; @0056                               v55 = load.i64 notrap aligned readonly v0+40
-;; @0056                               v57 = load.i64 notrap aligned readonly v0+48
-;; @0056                               v58 = uextend.i64 v12
-;; @0056                               v59 = iconst.i64 8
-;; @0056                               v60 = uadd_overflow_trap v58, v59, user1  ; v59 = 8
-;; @0056                               v61 = iconst.i64 8
-;; @0056                               v62 = uadd_overflow_trap v60, v61, user1  ; v61 = 8
-;; @0056                               v63 = icmp ule v62, v57
-;; @0056                               trapz v63, user1
-;; @0056                               v64 = iadd v55, v60
-;; @0056                               store.i64 notrap aligned v51, v64
-;; @0056                               jump block7
+;; @0056                               v54 = load.i64 notrap aligned readonly v0+56
+;; @0056                               v55 = load.i64 notrap aligned v54
+;; @0056                               v56 = load.i64 notrap aligned v54+8
+;; @0056                               v57 = icmp eq v55, v56
+;; @0056                               brif v57, block8, block7
 ;;
 ;;                                 block7:
+;;                                     v102 = stack_addr.i64 ss0
+;;                                     v89 = load.i32 notrap v102
+;; @0056                               store notrap aligned v89, v55
+;;                                     v103 = iconst.i64 4
+;; @0056                               v58 = iadd.i64 v55, v103  ; v103 = 4
+;; @0056                               store notrap aligned v58, v54
+;; @0056                               jump block9
+;;
+;;                                 block8 cold:
+;;                                     v104 = stack_addr.i64 ss0
+;;                                     v88 = load.i32 notrap v104
+;; @0056                               v60 = call fn1(v0, v88), stack_map=[i32 @ ss0+0]
+;; @0056                               v62 = load.i64 notrap aligned readonly v0+40
+;; @0056                               v64 = load.i64 notrap aligned readonly v0+48
+;;                                     v105 = stack_addr.i64 ss0
+;;                                     v87 = load.i32 notrap v105
+;; @0056                               v65 = uextend.i64 v87
+;; @0056                               v66 = iconst.i64 8
+;; @0056                               v67 = uadd_overflow_trap v65, v66, user1  ; v66 = 8
+;; @0056                               v68 = iconst.i64 8
+;; @0056                               v69 = uadd_overflow_trap v67, v68, user1  ; v68 = 8
+;; @0056                               v70 = icmp ule v69, v64
+;; @0056                               trapz v70, user1
+;; @0056                               v71 = iadd v62, v67
+;; @0056                               v72 = load.i64 notrap aligned v71
+;;                                     v106 = iconst.i64 -1
+;; @0056                               v73 = iadd v72, v106  ; v106 = -1
+;; @0056                               v75 = load.i64 notrap aligned readonly v0+40
+;; @0056                               v77 = load.i64 notrap aligned readonly v0+48
+;;                                     v107 = stack_addr.i64 ss0
+;;                                     v86 = load.i32 notrap v107
+;; @0056                               v78 = uextend.i64 v86
+;; @0056                               v79 = iconst.i64 8
+;; @0056                               v80 = uadd_overflow_trap v78, v79, user1  ; v79 = 8
+;; @0056                               v81 = iconst.i64 8
+;; @0056                               v82 = uadd_overflow_trap v80, v81, user1  ; v81 = 8
+;; @0056                               v83 = icmp ule v82, v77
+;; @0056                               trapz v83, user1
+;; @0056                               v84 = iadd v75, v80
+;; @0056                               store notrap aligned v73, v84
+;; @0056                               jump block9
+;;
+;;                                 block9:
 ;; @0058                               jump block1
 ;;
 ;;                                 block1:
@@ -119,13 +164,16 @@
 ;; }
 ;;
 ;; function u0:1(i64 vmctx, i64, i32, i32) tail {
+;;     ss0 = explicit_slot 4, align = 4
 ;;     gv0 = vmctx
 ;;     gv1 = load.i64 notrap aligned readonly gv0+8
 ;;     gv2 = load.i64 notrap aligned gv1+16
 ;;     gv3 = vmctx
 ;;     gv4 = load.i64 notrap aligned readonly gv3+88
 ;;     sig0 = (i64 vmctx, i32 uext) tail
-;;     fn0 = colocated u1:25 sig0
+;;     sig1 = (i64 vmctx, i32 uext) -> i64 tail
+;;     fn0 = colocated u1:28 sig0
+;;     fn1 = colocated u1:29 sig1
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: i32, v3: i32):
@@ -133,14 +181,16 @@
 ;; @005f                               v5 = icmp uge v2, v4  ; v4 = 7
 ;; @005f                               v6 = uextend.i64 v2
 ;; @005f                               v7 = load.i64 notrap aligned readonly v0+88
-;;                                     v66 = iconst.i64 2
-;; @005f                               v8 = ishl v6, v66  ; v66 = 2
+;;                                     v93 = iconst.i64 2
+;; @005f                               v8 = ishl v6, v93  ; v93 = 2
 ;; @005f                               v9 = iadd v7, v8
 ;; @005f                               v10 = iconst.i64 0
 ;; @005f                               v11 = select_spectre_guard v5, v10, v9  ; v10 = 0
 ;; @005f                               v12 = load.i32 user5 aligned table v11
-;;                                     v67 = iconst.i32 0
-;; @005f                               v13 = icmp eq v3, v67  ; v67 = 0
+;;                                     v94 = stack_addr.i64 ss0
+;;                                     store notrap v12, v94
+;;                                     v95 = iconst.i32 0
+;; @005f                               v13 = icmp eq v3, v95  ; v95 = 0
 ;; @005f                               brif v13, block3, block2
 ;;
 ;;                                 block2:
@@ -155,8 +205,8 @@
 ;; @005f                               trapz v23, user1
 ;; @005f                               v24 = iadd v15, v20
 ;; @005f                               v25 = load.i64 notrap aligned v24
-;;                                     v68 = iconst.i64 1
-;; @005f                               v26 = iadd v25, v68  ; v68 = 1
+;;                                     v96 = iconst.i64 1
+;; @005f                               v26 = iadd v25, v96  ; v96 = 1
 ;; @005f                               v28 = load.i64 notrap aligned readonly v0+40
 ;; @005f                               v30 = load.i64 notrap aligned readonly v0+48
 ;; @005f                               v31 = uextend.i64 v3
@@ -172,14 +222,18 @@
 ;;
 ;;                                 block3:
 ;; @005f                               store.i32 user5 aligned table v3, v11
-;;                                     v69 = iconst.i32 0
-;; @005f                               v38 = icmp.i32 eq v12, v69  ; v69 = 0
-;; @005f                               brif v38, block7, block4
+;;                                     v97 = stack_addr.i64 ss0
+;;                                     v91 = load.i32 notrap v97
+;;                                     v98 = iconst.i32 0
+;; @005f                               v38 = icmp eq v91, v98  ; v98 = 0
+;; @005f                               brif v38, block9, block4
 ;;
 ;;                                 block4:
 ;; @005f                               v40 = load.i64 notrap aligned readonly v0+40
 ;; @005f                               v42 = load.i64 notrap aligned readonly v0+48
-;; @005f                               v43 = uextend.i64 v12
+;;                                     v99 = stack_addr.i64 ss0
+;;                                     v90 = load.i32 notrap v99
+;; @005f                               v43 = uextend.i64 v90
 ;; @005f                               v44 = iconst.i64 8
 ;; @005f                               v45 = uadd_overflow_trap v43, v44, user1  ; v44 = 8
 ;; @005f                               v46 = iconst.i64 8
@@ -188,31 +242,67 @@
 ;; @005f                               trapz v48, user1
 ;; @005f                               v49 = iadd v40, v45
 ;; @005f                               v50 = load.i64 notrap aligned v49
-;;                                     v70 = iconst.i64 -1
-;; @005f                               v51 = iadd v50, v70  ; v70 = -1
-;;                                     v71 = iconst.i64 0
-;; @005f                               v52 = icmp eq v51, v71  ; v71 = 0
-;; @005f                               brif v52, block5, block6
+;;                                     v100 = iconst.i64 1
+;; @005f                               v51 = icmp eq v50, v100  ; v100 = 1
+;; @005f                               brif v51, block5, block6
 ;;
 ;;                                 block5 cold:
-;; @005f                               call fn0(v0, v12)
-;; @005f                               jump block7
+;;                                     v101 = stack_addr.i64 ss0
+;;                                     v85 = load.i32 notrap v101
+;; @005f                               call fn0(v0, v85)
+;; @005f                               jump block9
 ;;
 ;;                                 block6:
-;; @005f                               v55 = load.i64 notrap aligned readonly v0+40
-;; @005f                               v57 = load.i64 notrap aligned readonly v0+48
-;; @005f                               v58 = uextend.i64 v12
-;; @005f                               v59 = iconst.i64 8
-;; @005f                               v60 = uadd_overflow_trap v58, v59, user1  ; v59 = 8
-;; @005f                               v61 = iconst.i64 8
-;; @005f                               v62 = uadd_overflow_trap v60, v61, user1  ; v61 = 8
-;; @005f                               v63 = icmp ule v62, v57
-;; @005f                               trapz v63, user1
-;; @005f                               v64 = iadd v55, v60
-;; @005f                               store.i64 notrap aligned v51, v64
-;; @005f                               jump block7
+;; @005f                               v54 = load.i64 notrap aligned readonly v0+56
+;; @005f                               v55 = load.i64 notrap aligned v54
+;; @005f                               v56 = load.i64 notrap aligned v54+8
+;; @005f                               v57 = icmp eq v55, v56
+;; @005f                               brif v57, block8, block7
 ;;
 ;;                                 block7:
+;;                                     v102 = stack_addr.i64 ss0
+;;                                     v89 = load.i32 notrap v102
+;; @005f                               store notrap aligned v89, v55
+;;                                     v103 = iconst.i64 4
+;; @005f                               v58 = iadd.i64 v55, v103  ; v103 = 4
+;; @005f                               store notrap aligned v58, v54
+;; @005f                               jump block9
+;;
+;;                                 block8 cold:
+;;                                     v104 = stack_addr.i64 ss0
+;;                                     v88 = load.i32 notrap v104
+;; @005f                               v60 = call fn1(v0, v88), stack_map=[i32 @ ss0+0]
+;; @005f                               v62 = load.i64 notrap aligned readonly v0+40
+;; @005f                               v64 = load.i64 notrap aligned readonly v0+48
+;;                                     v105 = stack_addr.i64 ss0
+;;                                     v87 = load.i32 notrap v105
+;; @005f                               v65 = uextend.i64 v87
+;; @005f                               v66 = iconst.i64 8
+;; @005f                               v67 = uadd_overflow_trap v65, v66, user1  ; v66 = 8
+;; @005f                               v68 = iconst.i64 8
+;; @005f                               v69 = uadd_overflow_trap v67, v68, user1  ; v68 = 8
+;; @005f                               v70 = icmp ule v69, v64
+;; @005f                               trapz v70, user1
+;; @005f                               v71 = iadd v62, v67
+;; @005f                               v72 = load.i64 notrap aligned v71
+;;                                     v106 = iconst.i64 -1
+;; @005f                               v73 = iadd v72, v106  ; v106 = -1
+;; @005f                               v75 = load.i64 notrap aligned readonly v0+40
+;; @005f                               v77 = load.i64 notrap aligned readonly v0+48
+;;                                     v107 = stack_addr.i64 ss0
+;;                                     v86 = load.i32 notrap v107
+;; @005f                               v78 = uextend.i64 v86
+;; @005f                               v79 = iconst.i64 8
+;; @005f                               v80 = uadd_overflow_trap v78, v79, user1  ; v79 = 8
+;; @005f                               v81 = iconst.i64 8
+;; @005f                               v82 = uadd_overflow_trap v80, v81, user1  ; v81 = 8
+;; @005f                               v83 = icmp ule v82, v77
+;; @005f                               trapz v83, user1
+;; @005f                               v84 = iadd v75, v80
+;; @005f                               store notrap aligned v73, v84
+;; @005f                               jump block9
+;;
+;;                                 block9:
 ;; @0061                               jump block1
 ;;
 ;;                                 block1:
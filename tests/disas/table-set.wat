@@ -17,6 +17,7 @@
     table.set 0))
 
 ;; function u0:0(i64 vmctx, i64, i32) tail {
+;;     ss0 = explicit_slot 4, align = 4
 ;;     gv0 = vmctx
 ;;     gv1 = load.i64 notrap aligned readonly gv0+8
 ;;     gv2 = load.i64 notrap aligned gv1+16
@@ -24,7 +25,9 @@
 ;;     gv4 = load.i64 notrap aligned gv3+88
 ;;     gv5 = load.i64 notrap aligned gv3+96
 ;;     sig0 = (i64 vmctx, i32 uext) tail
-;;     fn0 = colocated u1:25 sig0
+;;     sig1 = (i64 vmctx, i32 uext) -> i64 tail
+;;     fn0 = colocated u1:28 sig0
+;;     fn1 = colocated u1:29 sig1
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: i32):
@@ -34,14 +37,16 @@
 ;; @0055                               v6 = icmp uge v3, v5  ; v3 = 0
 ;; @0055                               v7 = uextend.i64 v3  ; v3 = 0
 ;; @0055                               v8 = load.i64 notrap aligned v0+88
-;;                                     v68 = iconst.i64 2
-;; @0055                               v9 = ishl v7, v68  ; v68 = 2
+;;                                     v95 = iconst.i64 2
+;; @0055                               v9 = ishl v7, v95  ; v95 = 2
 ;; @0055                               v10 = iadd v8, v9
 ;; @0055                               v11 = iconst.i64 0
 ;; @0055                               v12 = select_spectre_guard v6, v11, v10  ; v11 = 0
 ;; @0055                               v13 = load.i32 user5 aligned table v12
-;;                                     v69 = iconst.i32 0
-;; @0055                               v14 = icmp eq v2, v69  ; v69 = 0
+;;                                     v96 = stack_addr.i64 ss0
+;;                                     store notrap v13, v96
+;;                                     v97 = iconst.i32 0
+;; @0055                               v14 = icmp eq v2, v97  ; v97 = 0
 ;; @0055                               brif v14, block3, block2
 ;;
 ;;                                 block2:
@@ -56,8 +61,8 @@
 ;; @0055                               trapz v24, user1
 ;; @0055                               v25 = iadd v16, v21
 ;; @0055                               v26 = load.i64 notrap aligned v25
-;;                                     v70 = iconst.i64 1
-;; @0055                               v27 = iadd v26, v70  ; v70 = 1
+;;                                     v98 = iconst.i64 1
+;; @0055                               v27 = iadd v26, v98  ; v98 = 1
 ;; @0055                               v29 = load.i64 notrap aligned readonly v0+40
 ;; @0055                               v31 = load.i64 notrap aligned readonly v0+48
 ;; @0055                               v32 = uextend.i64 v2
@@ -73,14 +78,18 @@
 ;;
 ;;                                 block3:
 ;; @0055                               store.i32 user5 aligned table v2, v12
-;;                                     v71 = iconst.i32 0
-;; @0055                               v39 = icmp.i32 eq v13, v71  ; v71 = 0
-;; @0055                               brif v39, block7, block4
+;;                                     v99 = stack_addr.i64 ss0
+;;                                     v92 = load.i32 notrap v99
+;;                                     v100 = iconst.i32 0
+;; @0055                               v39 = icmp eq v92, v100  ; v100 = 0
+;; @0055                               brif v39, block9, block4
 ;;
 ;;                                 block4:
 ;; @0055                               v41 = load.i64 notrap aligned readonly v0+40
 ;; @0055                               v43 = load.i64 notrap aligned readonly v0+48
-;; @0055                               v44 = uextend.i64 v13
+;;                                     v101 = stack_addr.i64 ss0
+;;                                     v91 = load.i32 notrap v101
+;; @0055                               v44 = uextend.i64 v91
 ;; @0055                               v45 = iconst.i64 8
 ;; @0055                               v46 = uadd_overflow_trap v44, v45, user1  ; v45 = 8
 ;; @0055                               v47 = iconst.i64 8
@@ -89,31 +98,67 @@
 ;; @0055                               trapz v49, user1
 ;; @0055                               v50 = iadd v41, v46
 ;; @0055                               v51 = load.i64 notrap aligned v50
-;;                                     v72 = iconst.i64 -1
-;; @0055                               v52 = iadd v51, v72  ; v72 = -1
-;;                                     v73 = iconst.i64 0
-;; @0055                               v53 = icmp eq v52, v73  ; v73 = 0
-;; @0055                               brif v53, block5, block6
+;;                                     v102 = iconst.i64 1
+;; @0055                               v52 = icmp eq v51, v102  ; v102 = 1
+;; @0055                               brif v52, block5, block6
 ;;
 ;;                                 block5 cold:
-;; @0055                               call fn0(v0, v13)
-;; @0055                               jump block7
+;;                                     v103 = stack_addr.i64 ss0
+;;                                     v86 = load.i32 notrap v103
+;; @0055                               call fn0(v0, v86)
+;; @0055                               jump block9
 ;;
 ;;                                 block6:
-;; @0055                               v56 = load.i64 notrap aligned readonly v0+40
-;; @0055                               v58 = load.i64 notrap aligned readonly v0+48
-;; @0055                               v59 = uextend.i64 v13
-;; @0055                               v60 = iconst.i64 8
-;; @0055                               v61 = uadd_overflow_trap v59, v60, user1  ; v60 = 8
-;; @0055                               v62 = iconst.i64 8
-;; @0055                               v63 = uadd_overflow_trap v61, v62, user1  ; v62 = 8
-;; @0055                               v64 = icmp ule v63, v58
-;; @0055                               trapz v64, user1
-;; @0055                               v65 = iadd v56, v61
-;; @0055                               store.i64 notrap aligned v52, v65
-;; @0055                               jump block7
+;; @0055                               v55 = load.i64 notrap aligned readonly v0+56
+;; @0055                               v56 = load.i64 notrap aligned v55
+;; @0055                               v57 = load.i64 notrap aligned v55+8
+;; @0055                               v58 = icmp eq v56, v57
+;; @0055                               brif v58, block8, block7
 ;;
 ;;                                 block7:
+;;                                     v104 = stack_addr.i64 ss0
+;;                                     v90 = load.i32 notrap v104
+;; @0055                               store notrap aligned v90, v56
+;;                                     v105 = iconst.i64 4
+;; @0055                               v59 = iadd.i64 v56, v105  ; v105 = 4
+;; @0055                               store notrap aligned v59, v55
+;; @0055                               jump block9
+;;
+;;                                 block8 cold:
+;;                                     v106 = stack_addr.i64 ss0
+;;                                     v89 = load.i32 notrap v106
+;; @0055                               v61 = call fn1(v0, v89), stack_map=[i32 @ ss0+0]
+;; @0055                               v63 = load.i64 notrap aligned readonly v0+40
+;; @0055                               v65 = load.i64 notrap aligned readonly v0+48
+;;                                     v107 = stack_addr.i64 ss0
+;;                                     v88 = load.i32 notrap v107
+;; @0055                               v66 = uextend.i64 v88
+;; @0055                               v67 = iconst.i64 8
+;; @0055                               v68 = uadd_overflow_trap v66, v67, user1  ; v67 = 8
+;; @0055                               v69 = iconst.i64 8
+;; @0055                               v70 = uadd_overflow_trap v68, v69, user1  ; v69 = 8
+;; @0055                               v71 = icmp ule v70, v65
+;; @0055                               trapz v71, user1
+;; @0055                               v72 = iadd v63, v68
+;; @0055                               v73 = load.i64 notrap aligned v72
+;;                                     v108 = iconst.i64 -1
+;; @0055                               v74 = iadd v73, v108  ; v108 = -1
+;; @0055                               v76 = load.i64 notrap aligned readonly v0+40
+;; @0055                               v78 = load.i64 notrap aligned readonly v0+48
+;;                                     v109 = stack_addr.i64 ss0
+;;                                     v87 = load.i32 notrap v109
+;; @0055                               v79 = uextend.i64 v87
+;; @0055                               v80 = iconst.i64 8
+;; @0055                               v81 = uadd_overflow_trap v79, v80, user1  ; v80 = 8
+;; @0055                               v82 = iconst.i64 8
+;; @0055                               v83 = uadd_overflow_trap v81, v82, user1  ; v82 = 8
+;; @0055                               v84 = icmp ule v83, v78
+;; @0055                               trapz v84, user1
+;; @0055                               v85 = iadd v76, v81
+;; @0055                               store notrap aligned v74, v85
+;; @0055                               jump block9
+;;
+;;                                 block9:
 ;; @0057                               jump block1
 ;;
 ;;                                 block1:
@@ -121,6 +166,7 @@
 ;; }
 ;;
 ;; function u0:1(i64 vmctx, i64, i32, i32) tail {
+;;     ss0 = explicit_slot 4, align = 4
 ;;     gv0 = vmctx
 ;;     gv1 = load.i64 notrap aligned readonly gv0+8
 ;;     gv2 = load.i64 notrap aligned gv1+16
@@ -128,7 +174,9 @@
 ;;     gv4 = load.i64 notrap aligned gv3+88
 ;;     gv5 = load.i64 notrap aligned gv3+96
 ;;     sig0 = (i64 vmctx, i32 uext) tail
-;;     fn0 = colocated u1:25 sig0
+;;     sig1 = (i64 vmctx, i32 uext) -> i64 tail
+;;     fn0 = colocated u1:28 sig0
+;;     fn1 = colocated u1:29 sig1
 ;;     stack_limit = gv2
 ;;
 ;;                                 block0(v0: i64, v1: i64, v2: i32, v3: i32):
@@ -137,14 +185,16 @@
 ;; @005e                               v6 = icmp uge v2, v5
 ;; @005e                               v7 = uextend.i64 v2
 ;; @005e                               v8 = load.i64 notrap aligned v0+88
-;;                                     v68 = iconst.i64 2
-;; @005e                               v9 = ishl v7, v68  ; v68 = 2
+;;                                     v95 = iconst.i64 2
+;; @005e                               v9 = ishl v7, v95  ; v95 = 2
 ;; @005e                               v10 = iadd v8, v9
 ;; @005e                               v11 = iconst.i64 0
 ;; @005e                               v12 = select_spectre_guard v6, v11, v10  ; v11 = 0
 ;; @005e                               v13 = load.i32 user5 aligned table v12
-;;                                     v69 = iconst.i32 0
-;; @005e                               v14 = icmp eq v3, v69  ; v69 = 0
+;;                                     v96 = stack_addr.i64 ss0
+;;                                     store notrap v13, v96
+;;                                     v97 = iconst.i32 0
+;; @005e                               v14 = icmp eq v3, v97  ; v97 = 0
 ;; @005e                               brif v14, block3, block2
 ;;
 ;;                                 block2:
@@ -159,8 +209,8 @@
 ;; @005e                               trapz v24, user1
 ;; @005e                               v25 = iadd v16, v21
 ;; @005e                               v26 = load.i64 notrap aligned v25
-;;                                     v70 = iconst.i64 1
-;; @005e                               v27 = iadd v26, v70  ; v70 = 1
+;;                                     v98 = iconst.i64 1
+;; @005e                               v27 = iadd v26, v98  ; v98 = 1
 ;; @005e                               v29 = load.i64 notrap aligned readonly v0+40
 ;; @005e                               v31 = load.i64 notrap aligned readonly v0+48
 ;; @005e                               v32 = uextend.i64 v3
@@ -176,14 +226,18 @@
 ;;
 ;;                                 block3:
 ;; @005e                               store.i32 user5 aligned table v3, v12
-;;                                     v71 = iconst.i32 0
-;; @005e                               v39 = icmp.i32 eq v13, v71  ; v71 = 0
-;; @005e                               brif v39, block7, block4
+;;                                     v99 = stack_addr.i64 ss0
+;;                                     v92 = load.i32 notrap v99
+;;                                     v100 = iconst.i32 0
+;; @005e                               v39 = icmp eq v92, v100  ; v100 = 0
+;; @005e                               brif v39, block9, block4
 ;;
 ;;                                 block4:
 ;; @005e                               v41 = load.i64 notrap aligned readonly v0+40
 ;; @005e                               v43 = load.i64 notrap aligned readonly v0+48
-;; @005e                               v44 = uextend.i64 v13
+;;                                     v101 = stack_addr.i64 ss0
+;;                                     v91 = load.i32 notrap v101
+;; @005e                               v44 = uextend.i64 v91
 ;; @005e                               v45 = iconst.i64 8
 ;; @005e                               v46 = uadd_overflow_trap v44, v45, user1  ; v45 = 8
 ;; @005e                               v47 = iconst.i64 8
@@ -192,31 +246,67 @@
 ;; @005e                               trapz v49, user1
 ;; @005e                               v50 = iadd v41, v46
 ;; @005e                               v51 = load.i64 notrap aligned v50
-;;                                     v72 = iconst.i64 -1
-;; @005e                               v52 = iadd v51, v72  ; v72 = -1
-;;                                     v73 = iconst.i64 0
-;; @005e                               v53 = icmp eq v52, v73  ; v73 = 0
-;; @005e                               brif v53, block5, block6
+;;                                     v102 = iconst.i64 1
+;; @005e                               v52 = icmp eq v51, v102  ; v102 = 1
+;; @005e                               brif v52, block5, block6
 ;;
 ;;                                 block5 cold:
-;; @005e                               call fn0(v0, v13)
-;; @005e                               jump block7
+;;                                     v103 = stack_addr.i64 ss0
+;;                                     v86 = load.i32 notrap v103
+;; @005e                               call fn0(v0, v86)
+;; @005e                               jump block9
 ;;
 ;;                                 block6:
-;; @005e                               v56 = load.i64 notrap aligned readonly v0+40
-;; @005e                               v58 = load.i64 notrap aligned readonly v0+48
-;; @005e                               v59 = uextend.i64 v13
-;; @005e                               v60 = iconst.i64 8
-;; @005e                               v61 = uadd_overflow_trap v59, v60, user1  ; v60 = 8
-;; @005e                               v62 = iconst.i64 8
-;; @005e                               v63 = uadd_overflow_trap v61, v62, user1  ; v62 = 8
-;; @005e                               v64 = icmp ule v63, v58
-;; @005e                               trapz v64, user1
-;; @005e                               v65 = iadd v56, v61
-;; @005e                               store.i64 notrap aligned v52, v65
-;; @005e                               jump block7
+;; @005e                               v55 = load.i64 notrap aligned readonly v0+56
+;; @005e                               v56 = load.i64 notrap aligned v55
+;; @005e                               v57 = load.i64 notrap aligned v55+8
+;; @005e                               v58 = icmp eq v56, v57
+;; @005e                               brif v58, block8, block7
 ;;
 ;;                                 block7:
+;;                                     v104 = stack_addr.i64 ss0
+;;                                     v90 = load.i32 notrap v104
+;; @005e                               store notrap aligned v90, v56
+;;                                     v105 = iconst.i64 4
+;; @005e                               v59 = iadd.i64 v56, v105  ; v105 = 4
+;; @005e                               store notrap aligned v59, v55
+;; @005e                               jump block9
+;;
+;;                                 block8 cold:
+;;                                     v106 = stack_addr.i64 ss0
+;;                                     v89 = load.i32 notrap v106
+;; @005e                               v61 = call fn1(v0, v89), stack_map=[i32 @ ss0+0]
+;; @005e                               v63 = load.i64 notrap aligned readonly v0+40
+;; @005e                               v65 = load.i64 notrap aligned readonly v0+48
+;;                                     v107 = stack_addr.i64 ss0
+;;                                     v88 = load.i32 notrap v107
+;; @005e                               v66 = uextend.i64 v88
+;; @005e                               v67 = iconst.i64 8
+;; @005e                               v68 = uadd_overflow_trap v66, v67, user1  ; v67 = 8
+;; @005e                               v69 = iconst.i64 8
+;; @005e                               v70 = uadd_overflow_trap v68, v69, user1  ; v69 = 8
+;; @005e                               v71 = icmp ule v70, v65
+;; @005e                               trapz v71, user1
+;; @005e                               v72 = iadd v63, v68
+;; @005e                               v73 = load.i64 notrap aligned v72
+;;                                     v108 = iconst.i64 -1
+;; @005e                               v74 = iadd v73, v108  ; v108 = -1
+;; @005e                               v76 = load.i64 notrap aligned readonly v0+40
+;; @005e                               v78 = load.i64 notrap aligned readonly v0+48
+;;                                     v109 = stack_addr.i64 ss0
+;;                                     v87 = load.i32 notrap v109
+;; @005e                               v79 = uextend.i64 v87
+;; @005e                               v80 = iconst.i64 8
+;; @005e                               v81 = uadd_overflow_trap v79, v80, user1  ; v80 = 8
+;; @005e                               v82 = iconst.i64 8
+;; @005e                               v83 = uadd_overflow_trap v81, v82, user1  ; v82 = 8
+;; @005e                               v84 = icmp ule v83, v78
+;; @005e                               trapz v84, user1
+;; @005e                               v85 = iadd v76, v81
+;; @005e                               store notrap aligned v74, v85
+;; @005e                               jump block9
+;;
+;;                                 block9:
 ;; @0060                               jump block1
 ;;
 ;;                                 block1:
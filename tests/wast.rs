@@ -34,7 +34,11 @@ fn main() {
 
             for pooling in [true, false] {
                 let collectors: &[_] = if !pooling && test_uses_gc_types {
-                    &[Collector::DeferredReferenceCounting, Collector::Null]
+                    &[
+                        Collector::DeferredReferenceCounting,
+                        Collector::Null,
+                        Collector::Copying,
+                    ]
                 } else {
                     &[Collector::Auto]
                 };